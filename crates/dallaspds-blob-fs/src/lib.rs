@@ -93,6 +93,23 @@ impl BlobStore for FsBlobStore {
         Ok(Some((Bytes::from(data), mime_type)))
     }
 
+    async fn get_blob_file(&self, did: &str, cid: &str) -> PdsResult<Option<(String, String)>> {
+        // The path is handed to the web server, so only accept plain CIDs.
+        if !cid.chars().all(|c| c.is_ascii_alphanumeric()) || !self.has_blob(did, cid).await? {
+            return Ok(None);
+        }
+        let mime_type = match tokio::fs::read_to_string(self.meta_path(did, cid)).await {
+            Ok(mime) => mime,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(PdsError::Storage(format!(
+                    "failed to read blob metadata: {e}"
+                )));
+            }
+        };
+        Ok(Some((format!("{}/{cid}", Self::safe_did(did)), mime_type)))
+    }
+
     async fn has_blob(&self, did: &str, cid: &str) -> PdsResult<bool> {
         let blob_path = self.blob_path(did, cid);
         match tokio::fs::metadata(&blob_path).await {
@@ -104,6 +121,16 @@ impl BlobStore for FsBlobStore {
         }
     }
 
+    async fn set_blob_mime(&self, did: &str, cid: &str, mime_type: &str) -> PdsResult<bool> {
+        if !self.has_blob(did, cid).await? {
+            return Ok(false);
+        }
+        tokio::fs::write(self.meta_path(did, cid), mime_type.as_bytes())
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to write blob metadata: {e}")))?;
+        Ok(true)
+    }
+
     async fn delete_blob(&self, did: &str, cid: &str) -> PdsResult<()> {
         let blob_path = self.blob_path(did, cid);
         let meta_path = self.meta_path(did, cid);
@@ -32,6 +32,20 @@ async fn stores_mime_type() {
     assert_eq!(mime, "image/png");
 }
 
+#[tokio::test]
+async fn set_blob_mime() {
+    let (store, _dir) = setup();
+    store.put_blob("did:plc:test", "cid-img", Bytes::from_static(b"png data"), "text/plain").await.unwrap();
+
+    assert!(store.set_blob_mime("did:plc:test", "cid-img", "image/png").await.unwrap());
+    let (data, mime) = store.get_blob("did:plc:test", "cid-img").await.unwrap().unwrap();
+    assert_eq!(mime, "image/png");
+    assert_eq!(data, Bytes::from_static(b"png data"));
+
+    assert!(!store.set_blob_mime("did:plc:test", "missing", "image/png").await.unwrap());
+    assert!(store.get_blob("did:plc:test", "missing").await.unwrap().is_none());
+}
+
 #[tokio::test]
 async fn get_nonexistent() {
     let (store, _dir) = setup();
@@ -113,3 +127,14 @@ async fn did_directory_escaping() {
     let result = store.get_blob("did:plc:abc123", "cid1").await.unwrap();
     assert!(result.is_some());
 }
+
+#[tokio::test]
+async fn get_blob_file() {
+    let (store, _dir) = setup();
+    store.put_blob("did:plc:test", "bafkreiabc", Bytes::from_static(b"data"), "image/png").await.unwrap();
+
+    let file = store.get_blob_file("did:plc:test", "bafkreiabc").await.unwrap();
+    assert_eq!(file, Some(("did_plc_test/bafkreiabc".to_string(), "image/png".to_string())));
+    assert!(store.get_blob_file("did:plc:test", "missing").await.unwrap().is_none());
+    assert!(store.get_blob_file("did:plc:test", "../did_plc_test/bafkreiabc").await.unwrap().is_none());
+}
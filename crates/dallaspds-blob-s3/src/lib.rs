@@ -136,6 +136,28 @@ impl BlobStore for S3BlobStore {
         }
     }
 
+    async fn set_blob_mime(&self, did: &str, cid: &str, mime_type: &str) -> PdsResult<bool> {
+        if !self.has_blob(did, cid).await? {
+            return Ok(false);
+        }
+        let key = Self::object_key(did, cid);
+
+        // Object metadata is immutable; copy the object onto itself with
+        // the new content type.
+        self.client
+            .copy_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .copy_source(format!("{}/{}", self.bucket, key))
+            .content_type(mime_type)
+            .metadata_directive(aws_sdk_s3::types::MetadataDirective::Replace)
+            .send()
+            .await
+            .map_err(|e| PdsError::Storage(format!("S3 copy_object failed: {e}")))?;
+
+        Ok(true)
+    }
+
     async fn delete_blob(&self, did: &str, cid: &str) -> PdsResult<()> {
         let key = Self::object_key(did, cid);
 
@@ -1,16 +1,54 @@
+use std::collections::HashMap;
+
 use figment::{
     Figment,
     providers::{Env, Format, Toml},
 };
 use serde::Deserialize;
 
+use crate::error::PdsResult;
+use crate::secrets::{self, SECRET_SCHEME};
+pub use crate::secrets::SecretsConfig;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SmtpConfig {
     pub host: String,
     pub port: u16,
-    pub username: String,
-    pub password: String,
+    /// How the connection is encrypted (default: implicit).
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    /// Login for servers that require authentication; set together with
+    /// `password`.
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Sender address, which need not be the SMTP login.
     pub from_address: String,
+    /// Display name shown with `from_address` (default: none).
+    #[serde(default)]
+    pub from_name: Option<String>,
+    /// Seconds to wait on the SMTP server before giving up (default: 30).
+    #[serde(default = "default_smtp_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_smtp_timeout_secs() -> u64 {
+    30
+}
+
+/// Transport security for the SMTP connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// TLS from the first byte, usually on port 465.
+    #[default]
+    Implicit,
+    /// Plain connection upgraded with STARTTLS, usually on port 587. The
+    /// upgrade is required; the server must offer it.
+    Starttls,
+    /// No encryption. Only for local relays; credentials are refused.
+    None,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -26,21 +64,73 @@ pub struct PdsConfig {
     pub public_url: String,
     pub plc_url: String,
     pub available_user_domains: Vec<String>,
+    /// Handles no account may take, compared case-insensitively. An entry
+    /// is either a whole handle (`admin.pds.example.com`) or just the name
+    /// before the user domain (`admin`).
+    #[serde(default)]
+    pub reserved_handles: Vec<String>,
+    /// Names before the user domain that no handle may start with (e.g.
+    /// `admin` also blocks `administrator`), compared case-insensitively.
+    #[serde(default)]
+    pub reserved_handle_prefixes: Vec<String>,
     pub invite_required: bool,
+    /// Refuse record writes and blob uploads from accounts whose email is
+    /// not confirmed (default: false).
+    #[serde(default)]
+    pub require_email_confirmed_for_writes: bool,
+    /// Seconds between deleteAccount and the account actually being purged,
+    /// during which it is deactivated and the deletion can be cancelled
+    /// (default: 0, delete immediately).
+    #[serde(default)]
+    pub account_deletion_grace_secs: u64,
+    /// What createAccount does when the PLC directory doesn't accept a new
+    /// DID's genesis operation (default: `best_effort`). `best_effort`
+    /// creates the account anyway, leaving it with a DID nothing can
+    /// resolve; production servers should use `required`.
+    #[serde(default)]
+    pub plc_registration: PlcRegistration,
+    /// Start in maintenance mode, refusing account, repo and blob writes
+    /// while reads and sync keep working (default: false). Admins can
+    /// toggle it at runtime, on one instance at a time.
+    #[serde(default)]
+    pub read_only: bool,
     pub jwt: JwtConfig,
     pub database: DatabaseConfig,
     pub blobs: BlobsConfig,
     #[serde(default = "default_mode")]
     pub mode: PdsMode,
+    /// In single-user mode, give the account the `did:web` of `hostname`
+    /// instead of registering a did:plc (default: false). The DID document
+    /// is then served from `/.well-known/did.json`.
+    #[serde(default)]
+    pub did_web: bool,
     /// URL of the AppView service for proxying unknown XRPC methods.
     #[serde(default)]
     pub appview_url: Option<String>,
     /// DID of the AppView service (used as JWT audience in service auth).
+    /// Defaults to the `did:web` of `appview_url`'s host.
     #[serde(default)]
     pub appview_did: Option<String>,
-    /// URL of the relay/BGS to notify via requestCrawl after writes.
+    /// Service (e.g. an AppView) whose `com.atproto.identity.resolveHandle`
+    /// resolves handles not hosted here, instead of looking them up over DNS
+    /// and HTTPS directly.
+    #[serde(default)]
+    pub handle_resolver_url: Option<String>,
+    /// Let PDS endpoints from other accounts' DID documents point at
+    /// loopback, private or link-local addresses when proxying their records
+    /// and blobs (default: false). Only for local development: otherwise
+    /// any DID document could aim this server's requests at internal
+    /// services.
     #[serde(default)]
-    pub relay_url: Option<String>,
+    pub allow_private_endpoints: bool,
+    /// URLs of the relays/BGSes to notify via requestCrawl after writes.
+    /// Accepts a single URL or a list.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub relay_url: Vec<String>,
+    /// Minimum seconds between write-triggered requestCrawl calls for the
+    /// same repo (default: 60).
+    #[serde(default = "default_relay_debounce_secs")]
+    pub relay_debounce_secs: u64,
     /// DIDs that have admin privileges on this PDS.
     #[serde(default)]
     pub admin_dids: Vec<String>,
@@ -50,17 +140,585 @@ pub struct PdsConfig {
     /// Optional SMTP configuration for email sending.
     #[serde(default)]
     pub smtp: Option<SmtpConfig>,
+    /// Length and lifetime of the tokens sent by email.
+    #[serde(default)]
+    pub email: EmailConfig,
+    /// Optional endpoint notified of account lifecycle events.
+    #[serde(default)]
+    pub webhooks: Option<WebhooksConfig>,
+    /// Argon2 parameters used when hashing account passwords.
+    #[serde(default)]
+    pub password: PasswordConfig,
+    /// Validate written records against lexicon schemas (default: false).
+    #[serde(default)]
+    pub validate_records: bool,
+    /// Directory of lexicon JSON files used when `validate_records` is set.
+    #[serde(default)]
+    pub lexicon_dir: Option<String>,
+    /// Repair legacy record values (missing `$type`, whole-number floats in
+    /// fields `lexicon_dir` declares as integers) in getRecord and
+    /// listRecords responses (default: false). Stored records are not
+    /// rewritten.
+    #[serde(default)]
+    pub normalize_on_read: bool,
+    /// Firehose (subscribeRepos) settings.
+    #[serde(default)]
+    pub firehose: FirehoseConfig,
+    /// Server-wide resource limits.
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    /// HTTP listener tuning.
+    #[serde(default)]
+    pub server: ServerConfig,
+    /// Per-client request rate limits.
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// Caching of proxied AppView GET responses.
+    #[serde(default)]
+    pub appview_cache: AppViewCacheConfig,
+    /// Retries of idempotent requests to the PLC directory, DID hosts and
+    /// the AppView.
+    #[serde(default)]
+    pub http_retry: HttpRetryConfig,
+    /// Browser origins allowed to call the admin and OAuth endpoints.
+    #[serde(default)]
+    pub cors: CorsConfig,
+    /// Provider for `secret://` references in sensitive fields.
+    #[serde(default)]
+    pub secrets: SecretsConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct JwtConfig {
     pub access_secret: String,
     pub refresh_secret: String,
+    /// Algorithm used to sign access tokens (default: HS256 with `access_secret`).
+    /// With ES256, any process holding the public key can verify tokens
+    /// without sharing a secret. Refresh tokens always use `refresh_secret`.
+    #[serde(default)]
+    pub algorithm: JwtAlgorithm,
+    /// PEM file with the P-256 private key (PKCS#8) that signs ES256 access tokens.
+    #[serde(default)]
+    pub es256_private_key_path: Option<String>,
+    /// PEM file with the matching P-256 public key, used to verify ES256 access tokens.
+    #[serde(default)]
+    pub es256_public_key_path: Option<String>,
+    /// Absolute lifetime of a session in days. Once a refresh-token chain is
+    /// older than this, refreshing fails and the user must log in again.
+    /// Unset means sessions can be refreshed indefinitely.
+    #[serde(default)]
+    pub session_max_age_days: Option<u32>,
+}
+
+/// Signing algorithm for session access tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum JwtAlgorithm {
+    /// HMAC-SHA256 with a shared secret.
+    #[default]
+    Hs256,
+    /// ECDSA over P-256 with a configured keypair.
+    Es256,
+}
+
+/// Email token and template settings.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EmailConfig {
+    /// Where emails go (default: smtp).
+    #[serde(default)]
+    pub backend: EmailBackend,
+    /// Directory of operator email templates. Missing files fall back to the
+    /// built-in templates (default: none).
+    #[serde(default)]
+    pub template_dir: Option<String>,
+    /// Tokens confirming an account's email address.
+    #[serde(default)]
+    pub confirm: EmailTokenConfig,
+    /// Password reset tokens.
+    #[serde(default)]
+    pub reset: EmailTokenConfig,
+    /// Tokens authorizing a change of email address.
+    #[serde(default)]
+    pub update: EmailTokenConfig,
+}
+
+/// How emails are delivered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailBackend {
+    /// Send through the `[smtp]` server; without one, no email is sent.
+    #[default]
+    Smtp,
+    /// Log each email at info level instead of sending it. For development.
+    Log,
+}
+
+/// How strictly createAccount requires registering the new DID with the PLC
+/// directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlcRegistration {
+    /// Log a warning and create the account anyway. For development, where
+    /// the PLC directory may be unreachable.
+    #[default]
+    BestEffort,
+    /// Fail account creation with `502 PlcRegistrationFailed`.
+    Required,
+}
+
+/// Purposes email tokens are issued for.
+pub const EMAIL_TOKEN_PURPOSES: [&str; 3] = ["confirm_email", "reset_password", "update_email"];
+
+impl EmailConfig {
+    /// Settings for an email token purpose (one of [`EMAIL_TOKEN_PURPOSES`]).
+    pub fn token(&self, purpose: &str) -> &EmailTokenConfig {
+        match purpose {
+            "reset_password" => &self.reset,
+            "update_email" => &self.update,
+            _ => &self.confirm,
+        }
+    }
+
+    /// How long a `purpose` token stays valid after it is requested.
+    pub fn token_lifetime(&self, purpose: &str) -> chrono::Duration {
+        chrono::Duration::seconds(self.token(purpose).expiry_secs as i64)
+    }
+
+    /// When a `purpose` token requested at `requested_at` stops being valid.
+    pub fn token_expires_at(
+        &self,
+        purpose: &str,
+        requested_at: chrono::DateTime<chrono::Utc>,
+    ) -> chrono::DateTime<chrono::Utc> {
+        requested_at + self.token_lifetime(purpose)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailTokenConfig {
+    /// Random bytes in each token, sent hex-encoded (default: 16; at least
+    /// [`MIN_EMAIL_TOKEN_BYTES`]).
+    #[serde(
+        default = "default_email_token_bytes",
+        deserialize_with = "deserialize_email_token_bytes"
+    )]
+    pub token_bytes: usize,
+    /// Seconds a token stays valid after it is requested (default: 3600).
+    #[serde(default = "default_email_token_expiry_secs")]
+    pub expiry_secs: u64,
+}
+
+/// Fewest random bytes an email token may have: shorter tokens could be
+/// guessed within their lifetime.
+pub const MIN_EMAIL_TOKEN_BYTES: usize = 16;
+
+fn default_email_token_bytes() -> usize {
+    MIN_EMAIL_TOKEN_BYTES
+}
+
+fn deserialize_email_token_bytes<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let bytes = usize::deserialize(deserializer)?;
+    if bytes < MIN_EMAIL_TOKEN_BYTES {
+        return Err(serde::de::Error::custom(format!(
+            "token_bytes must be at least {MIN_EMAIL_TOKEN_BYTES}, got {bytes}"
+        )));
+    }
+    Ok(bytes)
+}
+
+fn default_email_token_expiry_secs() -> u64 {
+    3600
+}
+
+impl Default for EmailTokenConfig {
+    fn default() -> Self {
+        Self {
+            token_bytes: default_email_token_bytes(),
+            expiry_secs: default_email_token_expiry_secs(),
+        }
+    }
+}
+
+/// Argon2id cost parameters for password hashing.
+///
+/// Raising these upgrades existing hashes transparently: a successful login
+/// against a hash made with weaker parameters rehashes the password.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PasswordConfig {
+    /// Memory cost in KiB (default: 19456, the argon2 crate default)
+    #[serde(default = "default_password_memory_kib")]
+    pub memory_kib: u32,
+    /// Number of iterations (default: 2)
+    #[serde(default = "default_password_iterations")]
+    pub iterations: u32,
+    /// Degree of parallelism (default: 1)
+    #[serde(default = "default_password_parallelism")]
+    pub parallelism: u32,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: default_password_memory_kib(),
+            iterations: default_password_iterations(),
+            parallelism: default_password_parallelism(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirehoseConfig {
+    /// Run the sequencer, event store, and relay notifications (default: true).
+    /// Personal, non-federated instances can turn this off.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Redis URL (`redis://host:6379`, or `rediss://` for TLS) used to share
+    /// sequence numbers and live events between PDS processes. Unset keeps
+    /// both in-process.
+    #[serde(default)]
+    pub redis_url: Option<String>,
+    /// Live events buffered per subscriber (default: 1024). A subscriber that
+    /// falls further behind is disconnected with `ConsumerTooSlow` and must
+    /// reconnect with a cursor to backfill.
+    #[serde(default = "default_firehose_buffer_size")]
+    pub buffer_size: usize,
+    /// Persisted events read per query when replaying from a cursor
+    /// (default: 100). Bounds the memory a backfilling subscriber holds.
+    #[serde(default = "default_firehose_backfill_batch_size")]
+    pub backfill_batch_size: usize,
+    /// Prune persisted events older than this many days. Unset keeps them
+    /// regardless of age.
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Keep at most this many of the newest persisted events. Unset keeps
+    /// them regardless of count.
+    #[serde(default)]
+    pub retention_events: Option<u64>,
+    /// How often the retention job runs, in seconds (default: 3600).
+    #[serde(default = "default_firehose_prune_interval_secs")]
+    pub prune_interval_secs: u64,
+    /// Turn away new subscribers while the server is under maintenance
+    /// (default: false). Connected subscribers are not affected.
+    #[serde(default)]
+    pub maintenance: bool,
+    /// Maximum subscribers replaying from a cursor at once (default: 0,
+    /// unlimited). Further backfill requests are asked to reconnect later.
+    #[serde(default)]
+    pub max_concurrent_backfills: usize,
+    /// Seconds a deferred subscriber is asked to wait before reconnecting
+    /// (default: 30).
+    #[serde(default = "default_firehose_retry_after_secs")]
+    pub retry_after_secs: u64,
+    /// Largest diff CAR, in bytes, carried inline in a `#commit` event
+    /// (default: 1000000). Bigger commits are sent with `tooBig` set and no
+    /// blocks, and consumers fetch the repo instead.
+    #[serde(default = "default_firehose_max_commit_blocks_bytes")]
+    pub max_commit_blocks_bytes: usize,
+}
+
+fn default_firehose_buffer_size() -> usize {
+    1024
+}
+
+fn default_firehose_backfill_batch_size() -> usize {
+    100
+}
+
+fn default_firehose_prune_interval_secs() -> u64 {
+    60 * 60
+}
+
+fn default_firehose_retry_after_secs() -> u64 {
+    30
+}
+
+fn default_firehose_max_commit_blocks_bytes() -> usize {
+    1_000_000
+}
+
+impl Default for FirehoseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            redis_url: None,
+            buffer_size: default_firehose_buffer_size(),
+            backfill_batch_size: default_firehose_backfill_batch_size(),
+            retention_days: None,
+            retention_events: None,
+            prune_interval_secs: default_firehose_prune_interval_secs(),
+            maintenance: false,
+            max_concurrent_backfills: 0,
+            retry_after_secs: default_firehose_retry_after_secs(),
+            max_commit_blocks_bytes: default_firehose_max_commit_blocks_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimitsConfig {
+    /// Maximum number of repo writes processed at once across all accounts
+    /// (default: 0, unlimited).
+    #[serde(default)]
+    pub max_concurrent_writes: usize,
+    /// How long a write waits for a free slot before it is rejected with
+    /// 503 (default: 5000 ms).
+    #[serde(default = "default_write_queue_timeout_ms")]
+    pub write_queue_timeout_ms: u64,
+    /// Maximum number of operations in one `applyWrites` call (default: 200).
+    #[serde(default = "default_max_writes_per_apply")]
+    pub max_writes_per_apply: usize,
+    /// Maximum combined JSON size, in bytes, of the records in one
+    /// `applyWrites` call (default: 2000000).
+    #[serde(default = "default_max_apply_writes_bytes")]
+    pub max_apply_writes_bytes: usize,
+    /// Maximum bytes of stored repo blocks per account, counting earlier
+    /// versions of the repo until `com.dallaspds.admin.compactRepo` prunes
+    /// them (default: 0, unlimited). Blobs are not included.
+    #[serde(default)]
+    pub max_repo_bytes: u64,
+}
+
+fn default_write_queue_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_max_writes_per_apply() -> usize {
+    200
+}
+
+fn default_max_apply_writes_bytes() -> usize {
+    2_000_000
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_writes: 0,
+            write_queue_timeout_ms: default_write_queue_timeout_ms(),
+            max_writes_per_apply: default_max_writes_per_apply(),
+            max_apply_writes_bytes: default_max_apply_writes_bytes(),
+            max_repo_bytes: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    /// Maximum open client connections (default: 0, unlimited). Connections
+    /// beyond this are closed as soon as they are accepted.
+    #[serde(default)]
+    pub max_connections: usize,
+    /// Maximum concurrent HTTP/2 streams on one connection (default: 100).
+    #[serde(default = "default_http2_max_concurrent_streams")]
+    pub http2_max_concurrent_streams: u32,
+    /// Seconds in-flight requests get to finish after SIGTERM/SIGINT before
+    /// the process exits (default: 30).
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+}
+
+fn default_http2_max_concurrent_streams() -> u32 {
+    100
+}
+
+fn default_shutdown_grace_secs() -> u64 {
+    30
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 0,
+            http2_max_concurrent_streams: default_http2_max_concurrent_streams(),
+            shutdown_grace_secs: default_shutdown_grace_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    /// Requests per minute each client may make to the credential endpoints
+    /// (`createSession`, `createAccount`, ...) (default: 30; 0 disables).
+    #[serde(default = "default_auth_per_minute")]
+    pub auth_per_minute: u32,
+    /// Requests per minute each client may make to other XRPC endpoints
+    /// (default: 3000; 0 disables).
+    #[serde(default = "default_xrpc_per_minute")]
+    pub xrpc_per_minute: u32,
+    /// Identify unauthenticated clients by their `X-Forwarded-For` address
+    /// instead of the peer address (default: false). Only enable behind a
+    /// reverse proxy that sets the header.
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    /// Reverse proxies in front of the server, each appending the address it
+    /// received the request from to `X-Forwarded-For` (default: 1). The
+    /// client is the address this many entries from the end; anything to its
+    /// left was supplied by the client.
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: u32,
+}
+
+fn default_trusted_proxies() -> u32 {
+    1
+}
+
+fn default_auth_per_minute() -> u32 {
+    30
+}
+
+fn default_xrpc_per_minute() -> u32 {
+    3000
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            auth_per_minute: default_auth_per_minute(),
+            xrpc_per_minute: default_xrpc_per_minute(),
+            trust_forwarded_for: false,
+            trusted_proxies: default_trusted_proxies(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppViewCacheConfig {
+    /// Seconds a successful proxied GET response is reused for the same
+    /// method, query and requesting account (default: 5; 0 disables).
+    #[serde(default = "default_appview_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Per-method overrides of `ttl_secs`, keyed by NSID
+    /// (e.g. `app.bsky.actor.getProfile = 30`).
+    #[serde(default)]
+    pub method_ttl_secs: HashMap<String, u64>,
+    /// Most responses kept at once (default: 10000). Expired entries are
+    /// swept when it is reached, and the cache is cleared if that isn't
+    /// enough.
+    #[serde(default = "default_appview_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_appview_cache_ttl_secs() -> u64 {
+    5
+}
+
+fn default_appview_cache_max_entries() -> usize {
+    10_000
+}
+
+impl Default for AppViewCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_appview_cache_ttl_secs(),
+            method_ttl_secs: HashMap::new(),
+            max_entries: default_appview_cache_max_entries(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpRetryConfig {
+    /// Attempts per upstream request, including the first (default: 3;
+    /// 1 disables retries). Only timeouts, connection failures, 429 and
+    /// 5xx responses are retried.
+    #[serde(default = "default_http_retry_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after each further failure
+    /// and randomly shortened by up to half (default: 200).
+    #[serde(default = "default_http_retry_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Upper bound on the delay between attempts (default: 5000).
+    #[serde(default = "default_http_retry_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+fn default_http_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_http_retry_initial_backoff_ms() -> u64 {
+    200
+}
+
+fn default_http_retry_max_backoff_ms() -> u64 {
+    5000
+}
+
+impl Default for HttpRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_http_retry_max_attempts(),
+            initial_backoff_ms: default_http_retry_initial_backoff_ms(),
+            max_backoff_ms: default_http_retry_max_backoff_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CorsConfig {
+    /// Origins (e.g. `https://admin.example.com`) allowed to make CORS
+    /// requests to the admin and OAuth endpoints (default: empty, any
+    /// origin). Other XRPC endpoints always allow any origin, as atproto
+    /// clients expect.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct DatabaseConfig {
     pub url: String,
+    /// Maximum open connections in each store's pool (default: 10).
+    #[serde(default = "default_db_max_connections")]
+    pub max_connections: u32,
+    /// Seconds to wait for a free pooled connection before failing the
+    /// query (default: 30).
+    #[serde(default = "default_db_acquire_timeout_secs")]
+    pub acquire_timeout_secs: u64,
+    /// Seconds an idle connection is kept open (default: 600; 0 keeps them
+    /// indefinitely).
+    #[serde(default = "default_db_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// SQLite only: use write-ahead logging, so readers don't block the
+    /// writer (default: true).
+    #[serde(default = "default_true")]
+    pub sqlite_wal: bool,
+    /// SQLite only: milliseconds a connection waits on a locked database
+    /// before returning `database is locked` (default: 5000).
+    #[serde(default = "default_sqlite_busy_timeout_ms")]
+    pub sqlite_busy_timeout_ms: u64,
+}
+
+fn default_db_max_connections() -> u32 {
+    10
+}
+
+fn default_db_acquire_timeout_secs() -> u64 {
+    30
+}
+
+fn default_db_idle_timeout_secs() -> u64 {
+    600
+}
+
+fn default_sqlite_busy_timeout_ms() -> u64 {
+    5000
+}
+
+impl DatabaseConfig {
+    /// Settings for `url` with every pool option at its default.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            max_connections: default_db_max_connections(),
+            acquire_timeout_secs: default_db_acquire_timeout_secs(),
+            idle_timeout_secs: default_db_idle_timeout_secs(),
+            sqlite_wal: true,
+            sqlite_busy_timeout_ms: default_sqlite_busy_timeout_ms(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -70,6 +728,28 @@ pub struct BlobsConfig {
     pub region: Option<String>,
     #[serde(default)]
     pub endpoint: Option<String>,
+    /// With filesystem storage, answer `getBlob` with an `X-Accel-Redirect`
+    /// to this prefix plus the blob's path under `path`, so a fronting nginx
+    /// serves the file from an `internal` location (default: unset).
+    #[serde(default)]
+    pub x_accel_redirect_prefix: Option<String>,
+    /// Answer `getBlob` for DIDs hosted elsewhere by fetching the blob from
+    /// their PDS (default: false). Off by default so the PDS isn't an open
+    /// blob proxy.
+    #[serde(default)]
+    pub allow_blob_proxy: bool,
+    /// Keep up to this many bytes of proxied blobs in memory (default: 0,
+    /// no cache).
+    #[serde(default)]
+    pub proxy_cache_bytes: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhooksConfig {
+    /// URL each event is POSTed to.
+    pub url: String,
+    /// Key for the HMAC-SHA256 body signature sent in `x-signature`.
+    pub secret: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -90,15 +770,171 @@ fn default_cert_cache() -> String {
     "data/certs".to_string()
 }
 
+fn default_password_memory_kib() -> u32 {
+    19 * 1024
+}
+
+fn default_password_iterations() -> u32 {
+    2
+}
+
+fn default_password_parallelism() -> u32 {
+    1
+}
+
+fn default_relay_debounce_secs() -> u64 {
+    60
+}
+
+/// Helper: accept either a single string or a list of strings.
+fn deserialize_one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+fn default_true() -> bool {
+    true
+}
+
 fn default_mode() -> PdsMode {
     PdsMode::Single
 }
 
 impl PdsConfig {
-    pub fn load(path: &str) -> Result<Self, figment::Error> {
-        Figment::new()
+    pub async fn load(path: &str) -> Result<Self, figment::Error> {
+        let mut config: Self = Figment::new()
             .merge(Toml::file(path))
             .merge(Env::prefixed("DALLAS_PDS_").split("__"))
-            .extract()
+            .extract()?;
+        config
+            .resolve_secrets()
+            .await
+            .map_err(|e| figment::Error::from(format!("failed to resolve secrets: {e}")))?;
+        Ok(config)
+    }
+
+    /// Replace `secret://` references in the sensitive fields with the values
+    /// fetched from the configured secret provider.
+    pub async fn resolve_secrets(&mut self) -> PdsResult<()> {
+        let mut fields = vec![&mut self.jwt.access_secret, &mut self.jwt.refresh_secret];
+        if let Some(password) = self.smtp.as_mut().and_then(|smtp| smtp.password.as_mut()) {
+            fields.push(password);
+        }
+        if let Some(webhooks) = &mut self.webhooks {
+            fields.push(&mut webhooks.secret);
+        }
+        if !fields.iter().any(|field| field.starts_with(SECRET_SCHEME)) {
+            return Ok(());
+        }
+        let provider = self.secrets.provider().await?;
+        for field in fields {
+            secrets::resolve(field, provider.as_ref()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Relays {
+        #[serde(default, deserialize_with = "deserialize_one_or_many")]
+        relay_url: Vec<String>,
+    }
+
+    fn relays(toml: &str) -> Vec<String> {
+        Figment::from(Toml::string(toml)).extract::<Relays>().unwrap().relay_url
+    }
+
+    #[test]
+    fn relay_url_accepts_a_string_or_a_list() {
+        assert_eq!(relays(r#"relay_url = "https://a""#), vec!["https://a"]);
+        assert_eq!(
+            relays(r#"relay_url = ["https://a", "https://b"]"#),
+            vec!["https://a", "https://b"]
+        );
+        assert!(relays("").is_empty());
+    }
+
+    #[test]
+    fn email_token_bytes_below_the_minimum_are_rejected() {
+        let token_bytes = |toml: &str| {
+            let token = Figment::from(Toml::string(toml)).extract::<EmailTokenConfig>();
+            token.ok().map(|token| token.token_bytes)
+        };
+        assert_eq!(token_bytes(""), Some(16));
+        assert_eq!(token_bytes("token_bytes = 32"), Some(32));
+        assert_eq!(token_bytes("token_bytes = 4"), None);
+    }
+
+    #[tokio::test]
+    async fn load_resolves_secret_references_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("jwt_access"), "from-a-file\n").unwrap();
+        let config_path = dir.path().join("pds.toml");
+        let write_config = |access_secret: &str| {
+            std::fs::write(
+                &config_path,
+                format!(
+                    r#"
+hostname = "localhost"
+port = 3000
+public_url = "http://localhost:3000"
+plc_url = "https://plc.directory"
+available_user_domains = [".test"]
+invite_required = false
+
+[jwt]
+access_secret = "{access_secret}"
+refresh_secret = "inline-refresh-secret"
+
+[database]
+url = "sqlite::memory:"
+
+[blobs]
+path = "blobs"
+
+[secrets]
+provider = "file"
+dir = "{}"
+"#,
+                    dir.path().display()
+                ),
+            )
+            .unwrap();
+        };
+
+        write_config("secret://jwt_access");
+        let config = PdsConfig::load(config_path.to_str().unwrap()).await.unwrap();
+        assert_eq!(config.jwt.access_secret, "from-a-file");
+        assert_eq!(config.jwt.refresh_secret, "inline-refresh-secret");
+
+        std::fs::remove_file(dir.path().join("jwt_access")).unwrap();
+        assert!(PdsConfig::load(config_path.to_str().unwrap()).await.is_err());
+
+        // Names can't reach files outside the secrets directory.
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("leak"), "outside\n").unwrap();
+        let absolute = outside.path().join("leak");
+        let relative = format!("../{}/leak", outside.path().file_name().unwrap().to_string_lossy());
+        for name in [absolute.to_string_lossy().into_owned(), relative] {
+            write_config(&format!("secret://{name}"));
+            let err = PdsConfig::load(config_path.to_str().unwrap()).await.unwrap_err();
+            assert!(err.to_string().contains("invalid secret name"), "{name}: {err}");
+        }
     }
 }
@@ -17,9 +17,32 @@ pub enum PdsError {
     #[error("invalid request: {0}")]
     InvalidRequest(String),
 
+    /// An upstream service (PLC, AppView, a handle or DID host) failed in a
+    /// way not covered below, e.g. it was unreachable or sent a malformed
+    /// response.
     #[error("upstream error: {0}")]
     Upstream(String),
 
+    /// An upstream service didn't answer in time. Retryable.
+    #[error("upstream timed out: {0}")]
+    UpstreamTimeout(String),
+
+    /// An upstream service rejected the request with a 4xx. Not retryable.
+    #[error("upstream rejected the request: {0}")]
+    UpstreamClientError(String),
+
+    /// An upstream service rate limited the request with a 429. Retryable.
+    #[error("upstream rate limited the request: {0}")]
+    UpstreamRateLimited(String),
+
+    /// An upstream service failed with a 5xx. Retryable.
+    #[error("upstream server error: {0}")]
+    UpstreamServerError(String),
+
+    /// A repo commit was built on a root that has since moved.
+    #[error("repo root changed during the write")]
+    RepoRootChanged,
+
     #[error("account not found")]
     AccountNotFound,
 
@@ -54,4 +77,38 @@ pub enum PdsError {
     InternalError(String),
 }
 
+impl PdsError {
+    /// Classify a failed request to an upstream service.
+    pub fn from_upstream_request(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            PdsError::UpstreamTimeout(err.to_string())
+        } else {
+            PdsError::Upstream(err.to_string())
+        }
+    }
+
+    /// Classify an upstream non-success HTTP `status`.
+    pub fn from_upstream_status(status: u16, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match status {
+            408 | 504 => PdsError::UpstreamTimeout(message),
+            429 => PdsError::UpstreamRateLimited(message),
+            400..=499 => PdsError::UpstreamClientError(message),
+            500..=599 => PdsError::UpstreamServerError(message),
+            _ => PdsError::Upstream(message),
+        }
+    }
+
+    /// `true` for upstream failures that a retry might get past.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            PdsError::Upstream(_)
+                | PdsError::UpstreamTimeout(_)
+                | PdsError::UpstreamRateLimited(_)
+                | PdsError::UpstreamServerError(_)
+        )
+    }
+}
+
 pub type PdsResult<T> = Result<T, PdsError>;
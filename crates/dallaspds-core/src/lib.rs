@@ -1,5 +1,6 @@
 pub mod config;
 pub mod error;
+pub mod secrets;
 pub mod traits;
 pub mod types;
 
@@ -8,6 +9,6 @@ pub use error::{PdsError, PdsResult};
 pub use traits::{AccountStore, BlobStore, EventStore, RepoStore};
 pub use traits::event_store::PersistedEvent;
 pub use types::{
-    AccountStatus, ActorAccount, BlobMeta, CreateAccountInput, InviteCode, InviteCodeUse,
-    RefreshTokenRecord, RepoRoot,
+    AccountSearchFilter, AccountStatus, ActorAccount, BlobMeta, CreateAccountInput, InviteCode, InviteCodeUse,
+    OAuthRequest, PlcOpLogEntry, RefreshTokenRecord, RepoListEntry, RepoRoot,
 };
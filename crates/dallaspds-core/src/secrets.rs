@@ -0,0 +1,247 @@
+//! Resolution of `secret://` references in the config.
+//!
+//! Sensitive config values (JWT secrets, the SMTP password) may be written as
+//! `secret://<name>` instead of inline. [`PdsConfig::load`] replaces each
+//! reference with the value fetched from the provider selected in the
+//! `[secrets]` section. Vault and AWS Secrets Manager references may select
+//! one field of a structured secret with `secret://<name>#<field>`.
+//!
+//! [`PdsConfig::load`]: crate::config::PdsConfig::load
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::{PdsError, PdsResult};
+
+/// Scheme marking a config value as a reference to a secret.
+pub const SECRET_SCHEME: &str = "secret://";
+
+/// Timeout for each request to a remote secret store.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A source of secret values, looked up by name.
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// Fetch the secret named by a reference (the part after `secret://`).
+    async fn fetch(&self, name: &str) -> PdsResult<String>;
+}
+
+/// Which provider resolves `secret://` references.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretProviderKind {
+    /// Environment variables, named by the reference.
+    #[default]
+    Env,
+    /// One file per secret under `dir`.
+    File,
+    /// HashiCorp Vault's KV v2 engine.
+    Vault,
+    /// AWS Secrets Manager.
+    AwsSm,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SecretsConfig {
+    /// Provider for `secret://` references (default: env).
+    #[serde(default)]
+    pub provider: SecretProviderKind,
+    /// Directory holding the secret files (`file` provider), e.g. `/run/secrets`.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Vault server address (default: `$VAULT_ADDR`).
+    #[serde(default)]
+    pub vault_addr: Option<String>,
+    /// Vault token (default: `$VAULT_TOKEN`).
+    #[serde(default)]
+    pub vault_token: Option<String>,
+    /// Mount path of the KV v2 engine (default: `secret`).
+    #[serde(default)]
+    pub vault_mount: Option<String>,
+    /// AWS region of the secrets (default: the AWS SDK's, e.g. `$AWS_REGION`).
+    /// Credentials come from the SDK's default chain: the environment, the
+    /// shared config files, or the instance or task role.
+    #[serde(default)]
+    pub aws_region: Option<String>,
+}
+
+impl SecretsConfig {
+    /// Build the configured provider.
+    pub async fn provider(&self) -> PdsResult<Box<dyn SecretProvider>> {
+        Ok(match self.provider {
+            SecretProviderKind::Env => Box::new(EnvSecretProvider),
+            SecretProviderKind::File => {
+                let dir = self.dir.clone().ok_or_else(|| {
+                    PdsError::InvalidRequest("secrets.dir is required for the file provider".into())
+                })?;
+                Box::new(FileSecretProvider { dir })
+            }
+            SecretProviderKind::Vault => Box::new(VaultSecretProvider {
+                addr: setting(&self.vault_addr, "VAULT_ADDR", "secrets.vault_addr")?,
+                token: setting(&self.vault_token, "VAULT_TOKEN", "secrets.vault_token")?,
+                mount: self.vault_mount.clone().unwrap_or_else(|| "secret".to_string()),
+            }),
+            SecretProviderKind::AwsSm => {
+                Box::new(AwsSecretsManagerProvider::new(self.aws_region.clone()).await)
+            }
+        })
+    }
+}
+
+/// A configured value, falling back to an environment variable.
+fn setting(value: &Option<String>, env: &str, key: &str) -> PdsResult<String> {
+    value
+        .clone()
+        .or_else(|| std::env::var(env).ok())
+        .ok_or_else(|| PdsError::InvalidRequest(format!("{key} or ${env} must be set")))
+}
+
+/// If `value` is a `secret://` reference, replace it with the secret.
+pub async fn resolve(value: &mut String, provider: &dyn SecretProvider) -> PdsResult<()> {
+    if let Some(name) = value.strip_prefix(SECRET_SCHEME) {
+        *value = provider.fetch(name).await?;
+    }
+    Ok(())
+}
+
+/// Split `name#field` into the secret name and the optional field.
+fn split_field(name: &str) -> (&str, Option<&str>) {
+    match name.split_once('#') {
+        Some((name, field)) => (name, Some(field)),
+        None => (name, None),
+    }
+}
+
+/// Reads secrets from environment variables.
+pub struct EnvSecretProvider;
+
+#[async_trait]
+impl SecretProvider for EnvSecretProvider {
+    async fn fetch(&self, name: &str) -> PdsResult<String> {
+        std::env::var(name)
+            .map_err(|_| PdsError::NotFound(format!("environment variable {name} is not set")))
+    }
+}
+
+/// Reads each secret from a file named after it, as mounted by Docker and
+/// Kubernetes secrets. Trailing whitespace is stripped.
+pub struct FileSecretProvider {
+    pub dir: String,
+}
+
+#[async_trait]
+impl SecretProvider for FileSecretProvider {
+    async fn fetch(&self, name: &str) -> PdsResult<String> {
+        // Joining an absolute name would replace `dir`, so only plain
+        // relative names are allowed.
+        let name_path = std::path::Path::new(name);
+        if name.is_empty()
+            || !name_path
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            return Err(PdsError::InvalidRequest(format!("invalid secret name: {name}")));
+        }
+        let path = std::path::Path::new(&self.dir).join(name_path);
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| PdsError::NotFound(format!("{}: {e}", path.display())))?;
+        Ok(contents.trim_end().to_string())
+    }
+}
+
+/// Reads secrets from Vault's KV v2 engine. The field defaults to `value`.
+pub struct VaultSecretProvider {
+    pub addr: String,
+    pub token: String,
+    pub mount: String,
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecretProvider {
+    async fn fetch(&self, name: &str) -> PdsResult<String> {
+        let (path, field) = split_field(name);
+        let url = format!("{}/v1/{}/data/{path}", self.addr.trim_end_matches('/'), self.mount);
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| PdsError::Upstream(e.to_string()))?;
+        let response = client
+            .get(&url)
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .map_err(|e| PdsError::Upstream(e.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PdsError::Upstream(format!("vault returned {status}")));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| PdsError::Upstream(e.to_string()))?;
+        let field = field.unwrap_or("value");
+        body["data"]["data"][field]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| PdsError::NotFound(format!("vault secret {path} has no field {field}")))
+    }
+}
+
+/// Reads secrets from AWS Secrets Manager. With a field, the secret string
+/// is parsed as JSON and that key is returned.
+pub struct AwsSecretsManagerProvider {
+    client: aws_sdk_secretsmanager::Client,
+}
+
+impl AwsSecretsManagerProvider {
+    /// Load the AWS SDK config, overriding its region with `region` if set.
+    pub async fn new(region: Option<String>) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .timeout_config(
+                aws_config::timeout::TimeoutConfig::builder()
+                    .operation_timeout(FETCH_TIMEOUT)
+                    .build(),
+            );
+        if let Some(region) = region {
+            loader = loader.region(aws_config::Region::new(region));
+        }
+        let sdk_config = loader.load().await;
+        Self {
+            client: aws_sdk_secretsmanager::Client::new(&sdk_config),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for AwsSecretsManagerProvider {
+    async fn fetch(&self, name: &str) -> PdsResult<String> {
+        let (secret_id, field) = split_field(name);
+        let output = self
+            .client
+            .get_secret_value()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .map_err(|e| {
+                PdsError::Upstream(format!(
+                    "AWS secret {secret_id}: {}",
+                    aws_sdk_secretsmanager::error::DisplayErrorContext(e)
+                ))
+            })?;
+        let secret = output.secret_string().ok_or_else(|| {
+            PdsError::NotFound(format!("AWS secret {secret_id} has no SecretString"))
+        })?;
+        match field {
+            None => Ok(secret.to_string()),
+            Some(field) => serde_json::from_str::<Value>(secret)
+                .ok()
+                .and_then(|v| v[field].as_str().map(str::to_string))
+                .ok_or_else(|| {
+                    PdsError::NotFound(format!("AWS secret {secret_id} has no field {field}"))
+                }),
+        }
+    }
+}
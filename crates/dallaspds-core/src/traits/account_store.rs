@@ -1,12 +1,17 @@
 use async_trait::async_trait;
 
+use crate::config::EmailConfig;
 use crate::error::PdsResult;
-use crate::types::{ActorAccount, CreateAccountInput, InviteCode, RefreshTokenRecord, RepoRoot};
+use crate::types::{
+    AccountSearchFilter, ActorAccount, CreateAccountInput, InviteCode, OAuthRequest, PlcOpLogEntry,
+    RefreshTokenRecord, RepoListEntry, RepoRoot,
+};
 
 #[async_trait]
 pub trait AccountStore: Send + Sync + 'static {
     async fn create_account(&self, input: &CreateAccountInput) -> PdsResult<ActorAccount>;
     async fn get_account_by_did(&self, did: &str) -> PdsResult<Option<ActorAccount>>;
+    /// Handles are matched case-insensitively.
     async fn get_account_by_handle(&self, handle: &str) -> PdsResult<Option<ActorAccount>>;
     async fn get_account_by_email(&self, email: &str) -> PdsResult<Option<ActorAccount>>;
     async fn update_handle(&self, did: &str, handle: &str) -> PdsResult<()>;
@@ -14,17 +19,80 @@ pub trait AccountStore: Send + Sync + 'static {
     async fn deactivate_account(&self, did: &str) -> PdsResult<()>;
     async fn activate_account(&self, did: &str) -> PdsResult<()>;
     async fn delete_account(&self, did: &str) -> PdsResult<()>;
+    /// Schedule the account to be deleted after `delete_after`, or cancel a
+    /// scheduled deletion (`None`).
+    async fn set_delete_after(
+        &self,
+        did: &str,
+        delete_after: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> PdsResult<()>;
+    /// DIDs of accounts whose scheduled deletion time is at or before `now`.
+    async fn list_accounts_due_for_deletion(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> PdsResult<Vec<String>>;
+    /// Claim `did`'s scheduled deletion if it is due at `now` by pushing it
+    /// back to `lease_until`, returning whether it was due. Only one caller
+    /// can claim a given deletion until the lease runs out; if the account
+    /// hasn't been purged by then, it comes due again.
+    async fn claim_scheduled_deletion(
+        &self,
+        did: &str,
+        now: chrono::DateTime<chrono::Utc>,
+        lease_until: chrono::DateTime<chrono::Utc>,
+    ) -> PdsResult<bool>;
     async fn get_repo_root(&self, did: &str) -> PdsResult<Option<RepoRoot>>;
     async fn update_repo_root(&self, did: &str, cid: &[u8], rev: &str) -> PdsResult<()>;
     async fn create_refresh_token(&self, token: &RefreshTokenRecord) -> PdsResult<()>;
     async fn get_refresh_token(&self, id: &str) -> PdsResult<Option<RefreshTokenRecord>>;
     async fn delete_refresh_token(&self, id: &str) -> PdsResult<()>;
     async fn delete_refresh_tokens_for_did(&self, did: &str) -> PdsResult<u64>;
+    /// Delete a refresh token together with every token rotated into or out
+    /// of it, i.e. one login session, leaving the account's other sessions.
+    async fn delete_refresh_token_chain(&self, id: &str) -> PdsResult<u64>;
+    /// Mark a refresh token as rotated by recording its successor.
+    ///
+    /// Returns `false` if the token was already rotated (or does not exist),
+    /// which callers treat as a replayed token.
+    async fn set_refresh_token_next_id(&self, id: &str, next_id: &str) -> PdsResult<bool>;
+    async fn delete_expired_refresh_tokens(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> PdsResult<u64>;
     async fn list_accounts(
         &self,
         cursor: Option<&str>,
         limit: usize,
     ) -> PdsResult<Vec<ActorAccount>>;
+    /// List repos with a committed head, ordered by DID, in a single query.
+    ///
+    /// `cursor` is the last DID of the previous page (keyset pagination).
+    async fn list_repos(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<RepoListEntry>>;
+
+    // OAuth authorization requests
+    async fn create_oauth_request(&self, request: &OAuthRequest) -> PdsResult<()>;
+    async fn get_oauth_request(&self, request_uri: &str) -> PdsResult<Option<OAuthRequest>>;
+    /// Record the user's approval of a pending request and its code.
+    ///
+    /// Returns `false` if the request does not exist or already has a code.
+    async fn authorize_oauth_request(
+        &self,
+        request_uri: &str,
+        did: &str,
+        code: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> PdsResult<bool>;
+    /// Delete and return the request holding `code`, so each code is
+    /// exchanged at most once.
+    async fn consume_oauth_code(&self, code: &str) -> PdsResult<Option<OAuthRequest>>;
+    async fn delete_expired_oauth_requests(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> PdsResult<u64>;
 
     // Invite code management
     async fn create_invite_code(
@@ -45,19 +113,51 @@ pub trait AccountStore: Send + Sync + 'static {
     async fn disable_invite_code(&self, code: &str) -> PdsResult<()>;
 
     // Account search and moderation
+    /// Accounts matching `filter`. With a `query`, matches are ranked by
+    /// relevance, best first; otherwise they are ordered by DID. Either way
+    /// `cursor` is the last DID of the previous page.
     async fn search_accounts(
         &self,
-        query: Option<&str>,
+        filter: &AccountSearchFilter,
         cursor: Option<&str>,
         limit: usize,
     ) -> PdsResult<Vec<ActorAccount>>;
     async fn set_takedown(&self, did: &str, takedown_ref: Option<&str>) -> PdsResult<()>;
+    /// Take down one of the account's blobs (`takedown_ref` set) or restore
+    /// it (`None`), independently of the account's own status.
+    async fn set_blob_takedown(
+        &self,
+        did: &str,
+        cid: &str,
+        takedown_ref: Option<&str>,
+    ) -> PdsResult<()>;
+    /// The takedown ref of a taken-down blob, or `None` if it may be served.
+    async fn get_blob_takedown(&self, did: &str, cid: &str) -> PdsResult<Option<String>>;
+
+    // PLC operation log
+    /// Record a signed PLC operation for `did` after those already logged.
+    /// The log isn't tied to the account: the genesis operation is logged
+    /// before the account exists, and the log is kept after it is deleted.
+    async fn append_plc_op(
+        &self,
+        did: &str,
+        cid: &str,
+        operation: &serde_json::Value,
+    ) -> PdsResult<()>;
+    /// The PLC operations logged for `did`, oldest first.
+    async fn list_plc_ops(&self, did: &str) -> PdsResult<Vec<PlcOpLogEntry>>;
 
     // Email token management
     async fn create_email_token(&self, purpose: &str, did: &str, token: &str) -> PdsResult<()>;
     async fn get_email_token(&self, purpose: &str, did: &str) -> PdsResult<Option<(String, chrono::DateTime<chrono::Utc>)>>;
     async fn get_email_token_by_token(&self, purpose: &str, token: &str) -> PdsResult<Option<(String, chrono::DateTime<chrono::Utc>)>>;
     async fn delete_email_token(&self, purpose: &str, did: &str) -> PdsResult<()>;
+    /// Delete email tokens whose lifetime under `config` ended before `now`.
+    async fn delete_expired_email_tokens(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        config: &EmailConfig,
+    ) -> PdsResult<u64>;
     async fn confirm_email(&self, did: &str) -> PdsResult<()>;
     async fn update_email(&self, did: &str, email: &str) -> PdsResult<()>;
 }
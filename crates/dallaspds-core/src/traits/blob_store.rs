@@ -14,6 +14,15 @@ pub trait BlobStore: Send + Sync + 'static {
     ) -> PdsResult<()>;
     async fn get_blob(&self, did: &str, cid: &str) -> PdsResult<Option<(Bytes, String)>>;
     async fn has_blob(&self, did: &str, cid: &str) -> PdsResult<bool>;
+    /// For stores that keep each blob in a local file: the file's path
+    /// relative to the store root and the blob's MIME type, found without
+    /// reading the data. Other stores return `None`.
+    async fn get_blob_file(&self, _did: &str, _cid: &str) -> PdsResult<Option<(String, String)>> {
+        Ok(None)
+    }
+    /// Replace the MIME type a stored blob is served with. Returns false if
+    /// the blob does not exist.
+    async fn set_blob_mime(&self, did: &str, cid: &str, mime_type: &str) -> PdsResult<bool>;
     async fn delete_blob(&self, did: &str, cid: &str) -> PdsResult<()>;
     async fn list_blobs(
         &self,
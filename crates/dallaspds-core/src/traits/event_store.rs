@@ -22,4 +22,21 @@ pub trait EventStore: Send + Sync + 'static {
 
     /// Get the maximum sequence number in the store (0 if empty).
     async fn get_max_seq(&self) -> PdsResult<i64>;
+
+    /// Get the most recent event of `event_type` for `did`, if any is still
+    /// retained.
+    async fn get_last_event_for_did(
+        &self,
+        did: &str,
+        event_type: &str,
+    ) -> PdsResult<Option<PersistedEvent>>;
+
+    /// Get the lowest seq among events persisted at or after `since`.
+    async fn get_first_seq_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> PdsResult<Option<i64>>;
+
+    /// Delete events with seq < `before_seq` and return how many were removed.
+    async fn prune_events_before(&self, before_seq: i64) -> PdsResult<u64>;
 }
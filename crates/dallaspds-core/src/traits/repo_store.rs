@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 
 use crate::error::PdsResult;
@@ -6,7 +8,51 @@ use crate::error::PdsResult;
 pub trait RepoStore: Send + Sync + 'static {
     async fn get_block(&self, did: &str, cid: &[u8]) -> PdsResult<Option<Vec<u8>>>;
     async fn put_block(&self, did: &str, cid: &[u8], block: &[u8]) -> PdsResult<()>;
+    /// Store a batch of `(cid, block)` pairs in one transaction, so a failed
+    /// batch leaves none of its blocks behind.
+    async fn put_blocks(&self, did: &str, blocks: &[(Vec<u8>, Vec<u8>)]) -> PdsResult<()>;
+    /// Store a commit's new `blocks` and move `did`'s repo root from
+    /// `expected_root` to `root` at `rev` in one transaction: either both
+    /// land or neither does, so the root never points at a commit whose
+    /// blocks are missing.
+    ///
+    /// `expected_root` is the root the commit was built on, empty for a repo
+    /// with no commit yet. If the root has moved since, nothing is written
+    /// and [`PdsError::RepoRootChanged`](crate::PdsError::RepoRootChanged)
+    /// is returned, so a writer that lost a race can't replace the winner's
+    /// commit.
+    async fn commit_blocks(
+        &self,
+        did: &str,
+        blocks: &[(Vec<u8>, Vec<u8>)],
+        expected_root: &[u8],
+        root: &[u8],
+        rev: &str,
+    ) -> PdsResult<()>;
     async fn has_block(&self, did: &str, cid: &[u8]) -> PdsResult<bool>;
     async fn get_all_blocks(&self, did: &str) -> PdsResult<Vec<(Vec<u8>, Vec<u8>)>>;
+    /// `(block_count, total_bytes)` over `did`'s stored blocks. The default
+    /// loads every block; stores that can aggregate in place override it.
+    async fn get_storage_usage(&self, did: &str) -> PdsResult<(u64, u64)> {
+        let blocks = self.get_all_blocks(did).await?;
+        let bytes = blocks.iter().map(|(_, block)| block.len() as u64).sum();
+        Ok((blocks.len() as u64, bytes))
+    }
+    /// Delete every block in `did`'s repo, along with its blob references.
     async fn delete_blocks_for_did(&self, did: &str) -> PdsResult<u64>;
+    /// Delete `did`'s blocks whose CIDs are not in `keep`, returning how many
+    /// were removed. Blob references are left alone.
+    async fn retain_blocks(&self, did: &str, keep: &HashSet<Vec<u8>>) -> PdsResult<u64>;
+    /// Record that the commit at `rev` references each blob in `cids`.
+    /// Blobs referenced by an earlier commit keep their original rev.
+    async fn add_blob_refs(&self, did: &str, cids: &[String], rev: &str) -> PdsResult<()>;
+    /// CIDs of blobs first referenced after the repo rev `since`, ordered by
+    /// CID and starting after `cursor`.
+    async fn list_blobs_since(
+        &self,
+        did: &str,
+        since: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<String>>;
 }
@@ -17,6 +17,8 @@ pub struct ActorAccount {
     pub email_confirmed_at: Option<chrono::DateTime<chrono::Utc>>,
     pub password_hash: String,
     pub signing_key: Vec<u8>,
+    /// Curve of `signing_key`: `"p256"` or `"k256"`.
+    pub key_type: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub status: AccountStatus,
     pub deactivated_at: Option<chrono::DateTime<chrono::Utc>>,
@@ -24,6 +26,15 @@ pub struct ActorAccount {
     pub delete_after: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Filters for admin account search. Unset fields match every account.
+#[derive(Debug, Clone, Default)]
+pub struct AccountSearchFilter {
+    /// Substring of the handle or email.
+    pub query: Option<String>,
+    pub status: Option<AccountStatus>,
+    pub email_confirmed: Option<bool>,
+}
+
 #[derive(Debug, Clone)]
 pub struct CreateAccountInput {
     pub did: String,
@@ -31,6 +42,8 @@ pub struct CreateAccountInput {
     pub email: Option<String>,
     pub password_hash: String,
     pub signing_key: Vec<u8>,
+    /// Curve of `signing_key`: `"p256"` or `"k256"`.
+    pub key_type: String,
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +54,15 @@ pub struct RepoRoot {
     pub indexed_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// A repo with a non-empty head, as listed by `com.atproto.sync.listRepos`.
+#[derive(Debug, Clone)]
+pub struct RepoListEntry {
+    pub did: String,
+    pub cid: Vec<u8>,
+    pub rev: String,
+    pub status: AccountStatus,
+}
+
 #[derive(Debug, Clone)]
 pub struct RefreshTokenRecord {
     pub id: String,
@@ -48,6 +70,32 @@ pub struct RefreshTokenRecord {
     pub expires_at: chrono::DateTime<chrono::Utc>,
     pub next_id: Option<String>,
     pub app_password_name: Option<String>,
+    /// When the session began, i.e. when the first token in this rotation
+    /// chain was issued. Carried over unchanged on every refresh.
+    pub session_created_at: chrono::DateTime<chrono::Utc>,
+    /// JWK thumbprint of the DPoP key an OAuth session is bound to. Such a
+    /// token can only be refreshed with a proof from the same key; `None`
+    /// for legacy `createSession` tokens.
+    pub dpop_jkt: Option<String>,
+}
+
+/// A pushed OAuth authorization request, and the authorization code issued
+/// for it once the user approves.
+#[derive(Debug, Clone)]
+pub struct OAuthRequest {
+    /// `urn:ietf:params:oauth:request_uri:...` handle returned by PAR.
+    pub request_uri: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub state: Option<String>,
+    /// PKCE S256 challenge: base64url(sha256(code_verifier)).
+    pub code_challenge: String,
+    /// The approving account; set together with `code`.
+    pub did: Option<String>,
+    pub code: Option<String>,
+    /// When the request (or, once approved, its code) stops being usable.
+    pub expires_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug, Clone)]
@@ -75,3 +123,13 @@ pub struct InviteCodeUse {
     pub used_by: String,
     pub used_at: chrono::DateTime<chrono::Utc>,
 }
+
+/// A PLC operation this PDS signed and submitted for a DID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlcOpLogEntry {
+    pub did: String,
+    /// CID of the signed operation, which the next operation's `prev` names.
+    pub cid: String,
+    pub operation: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
@@ -40,32 +40,90 @@ pub fn create_did_plc_operation(
         "prev": null
     });
 
-    // Step 2: Serialize unsigned op to DAG-CBOR
-    let unsigned_cbor = dag_cbor_encode(&unsigned_op)?;
+    // Steps 2-5: Sign the DAG-CBOR encoding and add the "sig" field
+    let signed_op_value = sign_plc_operation(signing_key, unsigned_op)?;
+
+    // Step 6: Compute the DID
+    let did = did_for_genesis_operation(&signed_op_value)?;
+
+    Ok((did, signed_op_value))
+}
+
+/// Create a signed did:plc update operation.
+///
+/// `prev` is the CID of the DID's latest operation (see
+/// [`plc_operation_cid`]) and the other fields are the complete new state of
+/// the document, as in [`create_did_plc_operation`]. `signing_key` must be
+/// one of the rotation keys of the current document, not of the new one.
+pub fn create_did_plc_update_operation(
+    signing_key: &SigningKey,
+    prev: &str,
+    rotation_keys: Vec<String>,
+    atproto_key: &str,
+    handle: &str,
+    pds_endpoint: &str,
+) -> PdsResult<serde_json::Value> {
+    let unsigned_op = json!({
+        "type": "plc_operation",
+        "rotationKeys": rotation_keys,
+        "verificationMethods": {
+            "atproto": atproto_key
+        },
+        "alsoKnownAs": [format!("at://{handle}")],
+        "services": {
+            "atproto_pds": {
+                "type": "AtprotoPersonalDataServer",
+                "endpoint": pds_endpoint
+            }
+        },
+        "prev": prev
+    });
+    sign_plc_operation(signing_key, unsigned_op)
+}
 
-    // Step 3: Sign the DAG-CBOR bytes
+/// Sign an unsigned PLC operation: sign its DAG-CBOR encoding and add the
+/// base64url signature (no padding) as `"sig"`.
+fn sign_plc_operation(
+    signing_key: &SigningKey,
+    unsigned_op: serde_json::Value,
+) -> PdsResult<serde_json::Value> {
     // atrium-crypto's sign() internally hashes with SHA-256 then signs
+    let unsigned_cbor = dag_cbor_encode(&unsigned_op)?;
     let signature = signing_key.sign(&unsigned_cbor)?;
 
-    // Step 4: Base64url-encode the signature (no padding)
-    let sig_b64 = base64url_encode(&signature);
-
-    // Step 5: Build signed operation (add "sig" field)
     let mut signed_op = match unsigned_op {
         Value::Object(map) => map,
         _ => unreachable!(),
     };
-    signed_op.insert("sig".to_string(), Value::String(sig_b64));
-    let signed_op_value = Value::Object(signed_op.clone());
+    signed_op.insert("sig".to_string(), Value::String(base64url_encode(&signature)));
+    Ok(Value::Object(signed_op))
+}
 
-    // Step 6: Compute the DID
-    // Hash the DAG-CBOR of the *signed* operation
-    let signed_cbor = dag_cbor_encode(&signed_op_value)?;
+/// Derive the DID a signed genesis operation registers:
+/// `did:plc:` + first 24 chars of base32-lower(sha256(dag-cbor(signed_op))).
+pub fn did_for_genesis_operation(signed_op: &serde_json::Value) -> PdsResult<String> {
+    let signed_cbor = dag_cbor_encode(signed_op)?;
     let hash = Sha256::digest(&signed_cbor);
     let hash_b32 = base32::encode(base32::Alphabet::Rfc4648Lower { padding: false }, &hash);
-    let did = format!("did:plc:{}", &hash_b32[..24]);
+    Ok(format!("did:plc:{}", &hash_b32[..24]))
+}
 
-    Ok((did, signed_op_value))
+/// CID of a signed PLC operation (CIDv1, dag-cbor, sha2-256), as the PLC
+/// directory reports it and as later operations reference it in `prev`.
+pub fn plc_operation_cid(signed_op: &serde_json::Value) -> PdsResult<String> {
+    let signed_cbor = dag_cbor_encode(signed_op)?;
+    let hash = Sha256::digest(&signed_cbor);
+    let mh = ipld_core::cid::multihash::Multihash::<64>::wrap(0x12, &hash)
+        .map_err(|e| PdsError::Crypto(format!("multihash failed: {e}")))?;
+    Ok(ipld_core::cid::Cid::new_v1(0x71, mh).to_string())
+}
+
+/// Check that `did_key` is a well-formed `did:key` for a P-256 or secp256k1
+/// public key, the curves PLC accepts as rotation keys.
+pub fn validate_did_key(did_key: &str) -> PdsResult<()> {
+    atrium_crypto::did::parse_did_key(did_key)
+        .map(|_| ())
+        .map_err(|e| PdsError::InvalidRequest(format!("invalid did:key {did_key}: {e}")))
 }
 
 /// Encode a serde_json::Value to DAG-CBOR bytes.
@@ -119,6 +177,14 @@ mod tests {
     use super::*;
     use crate::signing::SigningKey;
 
+    #[test]
+    fn validate_did_key_accepts_generated_keys_only() {
+        let key = SigningKey::generate_k256().unwrap();
+        assert!(validate_did_key(&key.did_key()).is_ok());
+        assert!(validate_did_key("did:key:zNotAKey").is_err());
+        assert!(validate_did_key("did:plc:abc").is_err());
+    }
+
     #[test]
     fn create_did_plc_produces_valid_did() {
         let key = SigningKey::generate_p256().unwrap();
@@ -152,6 +218,36 @@ mod tests {
         assert!(op["prev"].is_null());
     }
 
+    #[test]
+    fn update_op_links_to_prev_and_is_signed() {
+        let key = SigningKey::generate_p256().unwrap();
+        let (_did, genesis) =
+            create_did_plc_operation(&key, vec![key.did_key()], "alice.test", "https://pds.test").unwrap();
+        let prev = plc_operation_cid(&genesis).unwrap();
+        let recovery = SigningKey::generate_k256().unwrap().did_key();
+
+        let op = create_did_plc_update_operation(
+            &key,
+            &prev,
+            vec![recovery.clone(), key.did_key()],
+            &key.did_key(),
+            "alice.example.com",
+            "https://new-pds.test",
+        )
+        .unwrap();
+        assert_eq!(op["prev"], prev);
+        assert_eq!(op["rotationKeys"], serde_json::json!([recovery, key.did_key()]));
+        assert_eq!(op["alsoKnownAs"], serde_json::json!(["at://alice.example.com"]));
+        assert_eq!(op["services"]["atproto_pds"]["endpoint"], "https://new-pds.test");
+
+        // ECDSA signing is deterministic, so re-signing the unsigned op must
+        // reproduce the signature.
+        let mut unsigned = op.clone();
+        let sig = unsigned.as_object_mut().unwrap().remove("sig").unwrap();
+        let expected = base64url_encode(&key.sign(&dag_cbor_encode(&unsigned).unwrap()).unwrap());
+        assert_eq!(sig, expected);
+    }
+
     #[test]
     fn deterministic_dag_cbor_encoding() {
         // DAG-CBOR must produce the same output for the same input
@@ -165,6 +261,73 @@ mod tests {
         let enc3 = dag_cbor_encode(&value_reordered).unwrap();
         assert_eq!(enc1, enc3, "key order in JSON should not affect DAG-CBOR output");
     }
+
+    /// Known-answer vectors for genesis operations.
+    ///
+    /// The expected values were computed outside this crate, with an ECDSA
+    /// implementation using RFC 6979 nonces and low-S signatures (as the
+    /// reference PLC tooling produces) and a separate DAG-CBOR encoder that
+    /// orders map keys length-first. ECDSA signing here is deterministic too,
+    /// so a fixed key yields a fixed signature, DID, and operation CID.
+    mod vectors {
+        use super::*;
+
+        struct Vector {
+            key_type: &'static str,
+            private_key: [u8; 32],
+            did_key: &'static str,
+            sig: &'static str,
+            did: &'static str,
+            cid: &'static str,
+        }
+
+        fn scalar(start: u8) -> [u8; 32] {
+            std::array::from_fn(|i| start + i as u8)
+        }
+
+        fn check(v: &Vector) {
+            let key = SigningKey::from_bytes(v.key_type, &v.private_key).unwrap();
+            assert_eq!(key.did_key(), v.did_key);
+
+            let (did, op) = create_did_plc_operation(
+                &key,
+                vec![key.did_key()],
+                "alice.test",
+                "https://pds.test",
+            )
+            .unwrap();
+            assert_eq!(op["sig"], v.sig);
+            assert_eq!(did, v.did);
+            assert_eq!(plc_operation_cid(&op).unwrap(), v.cid);
+
+            // The DID depends only on the signed op, however it was signed.
+            assert_eq!(did_for_genesis_operation(&op).unwrap(), v.did);
+        }
+
+        #[test]
+        fn p256_genesis_operation() {
+            check(&Vector {
+                key_type: "p256",
+                private_key: scalar(1),
+                did_key: "did:key:zDnaeVuZeVRqvscGkiEoR9PFFra2xZUMp97ZPuGFK1VLU7iYN",
+                sig: "SG4ULqm6vVnIYQMNs2NzPa1V3wxR3XonC-i-1LstxywozseJFN1T6Qk6nTb09GHQpcOPn1jKu-fpVickpTDV5w",
+                did: "did:plc:vmnwhzseudumfxoeexjow2oa",
+                cid: "bafyreifldnr6mrfa5dbn3rbf2lvwtqdrefmyzj57t7gx73fpopqn5w4j4e",
+            });
+        }
+
+        #[test]
+        fn secp256k1_genesis_operation() {
+            check(&Vector {
+                key_type: "k256",
+                private_key: scalar(33),
+                did_key: "did:key:zQ3shPbbEENPXcD2eU7v2Z6B5vQwKjfRdXbYYhhNU3tbJYoU5",
+                sig: "9CTV8v3OyfsjHIUBa2S9pKmWH-qG4ldK0IO2YuwF2rtIMWLkfnyn1_7DuqBjEW_Kro6MaIYcM98IOzNLU2_4Lg",
+                did: "did:plc:6tifmuyxhema4nzx5khuqt2l",
+                cid: "bafyreihu2blfgfzzdahdon7kr5ee6s4wtf4jw33csnzjj2573spzaopoj4",
+            });
+        }
+    }
 }
 
 /// Minimal base64url encoder (no padding) to avoid an extra dependency.
@@ -4,11 +4,14 @@ pub mod password;
 pub mod signing;
 pub mod tid;
 
-pub use did::create_did_plc_operation;
+pub use did::{
+    create_did_plc_operation, create_did_plc_update_operation, did_for_genesis_operation,
+    plc_operation_cid, validate_did_key,
+};
 pub use jwt::{
-    AccessTokenClaims, RefreshTokenClaims, create_access_token, create_refresh_token,
-    validate_access_token, validate_refresh_token,
+    AccessTokenClaims, AccessTokenKeys, Confirmation, RefreshTokenClaims, create_access_token,
+    create_dpop_access_token, create_refresh_token, validate_access_token, validate_refresh_token,
 };
-pub use password::{hash_password, verify_password};
-pub use signing::SigningKey;
+pub use password::{PasswordVerification, hash_password, verify_password};
+pub use signing::{SigningKey, verify_with_did_key};
 pub use tid::TidGenerator;
@@ -1,58 +1,167 @@
 use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::SaltString;
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use dallaspds_core::config::PasswordConfig;
 use dallaspds_core::{PdsError, PdsResult};
 
-/// Hash a password using Argon2id with a random salt.
-pub fn hash_password(password: &str) -> PdsResult<String> {
+/// Outcome of checking a password against a stored hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordVerification {
+    /// The password does not match.
+    Invalid,
+    /// The password matches and the hash uses the current parameters.
+    Valid,
+    /// The password matches but the hash was made with weaker parameters
+    /// than the current configuration and should be rehashed.
+    ValidNeedsRehash,
+}
+
+impl PasswordVerification {
+    /// Returns `true` if the password matched, regardless of rehash state.
+    pub fn is_valid(self) -> bool {
+        !matches!(self, PasswordVerification::Invalid)
+    }
+}
+
+/// Build an Argon2id hasher from the configured cost parameters.
+fn argon2_from_config(config: &PasswordConfig) -> PdsResult<Argon2<'static>> {
+    let params = Params::new(config.memory_kib, config.iterations, config.parallelism, None)
+        .map_err(|e| PdsError::Crypto(format!("invalid argon2 parameters: {e}")))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// Hash a password using Argon2id with a random salt and the configured parameters.
+pub fn hash_password(password: &str, config: &PasswordConfig) -> PdsResult<String> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default(); // Argon2id by default
+    let argon2 = argon2_from_config(config)?;
     let hash = argon2
         .hash_password(password.as_bytes(), &salt)
         .map_err(|e| PdsError::Crypto(format!("password hashing failed: {e}")))?;
     Ok(hash.to_string())
 }
 
-/// Verify a password against an Argon2id hash string.
+/// Verify a password against an Argon2 hash string.
 ///
-/// Returns `Ok(true)` if the password matches, `Ok(false)` otherwise.
-pub fn verify_password(password: &str, hash: &str) -> PdsResult<bool> {
+/// The parameters embedded in the hash are used for verification, so hashes
+/// made with older settings keep working. If the password matches but the
+/// hash is weaker than `config` (or not Argon2id v0x13), the result asks the
+/// caller to rehash.
+pub fn verify_password(
+    password: &str,
+    hash: &str,
+    config: &PasswordConfig,
+) -> PdsResult<PasswordVerification> {
     let parsed_hash = PasswordHash::new(hash)
         .map_err(|e| PdsError::Crypto(format!("invalid password hash: {e}")))?;
     let argon2 = Argon2::default();
     match argon2.verify_password(password.as_bytes(), &parsed_hash) {
-        Ok(()) => Ok(true),
-        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Ok(()) => {
+            if needs_rehash(&parsed_hash, config) {
+                Ok(PasswordVerification::ValidNeedsRehash)
+            } else {
+                Ok(PasswordVerification::Valid)
+            }
+        }
+        Err(argon2::password_hash::Error::Password) => Ok(PasswordVerification::Invalid),
         Err(e) => Err(PdsError::Crypto(format!("password verification failed: {e}"))),
     }
 }
 
+/// Returns `true` if the hash was produced with weaker settings than `config`.
+fn needs_rehash(hash: &PasswordHash<'_>, config: &PasswordConfig) -> bool {
+    if hash.algorithm != Algorithm::Argon2id.ident() || hash.version != Some(Version::V0x13.into())
+    {
+        return true;
+    }
+    match Params::try_from(hash) {
+        Ok(params) => {
+            params.m_cost() < config.memory_kib
+                || params.t_cost() < config.iterations
+                || params.p_cost() < config.parallelism
+        }
+        Err(_) => true,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Cheap parameters so the test suite stays fast.
+    fn weak_config() -> PasswordConfig {
+        PasswordConfig {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
     #[test]
     fn hash_verify_correct_password() {
-        let hash = hash_password("correct-horse").unwrap();
-        assert!(verify_password("correct-horse", &hash).unwrap());
+        let config = PasswordConfig::default();
+        let hash = hash_password("correct-horse", &config).unwrap();
+        assert_eq!(
+            verify_password("correct-horse", &hash, &config).unwrap(),
+            PasswordVerification::Valid
+        );
     }
 
     #[test]
     fn hash_verify_wrong_password() {
-        let hash = hash_password("correct-horse").unwrap();
-        assert!(!verify_password("wrong-horse", &hash).unwrap());
+        let config = PasswordConfig::default();
+        let hash = hash_password("correct-horse", &config).unwrap();
+        assert!(!verify_password("wrong-horse", &hash, &config).unwrap().is_valid());
     }
 
     #[test]
     fn hash_produces_argon2_format() {
-        let hash = hash_password("test").unwrap();
+        let hash = hash_password("test", &PasswordConfig::default()).unwrap();
         assert!(hash.starts_with("$argon2"), "hash should start with $argon2, got: {hash}");
     }
 
     #[test]
     fn different_hashes_for_same_password() {
-        let hash1 = hash_password("same-password").unwrap();
-        let hash2 = hash_password("same-password").unwrap();
+        let config = PasswordConfig::default();
+        let hash1 = hash_password("same-password", &config).unwrap();
+        let hash2 = hash_password("same-password", &config).unwrap();
         assert_ne!(hash1, hash2, "different salts should produce different hashes");
     }
+
+    #[test]
+    fn hash_embeds_configured_params() {
+        let hash = hash_password("test", &weak_config()).unwrap();
+        assert!(hash.contains("m=1024,t=1,p=1"), "unexpected params in {hash}");
+    }
+
+    #[test]
+    fn weaker_hash_needs_rehash() {
+        let hash = hash_password("correct-horse", &weak_config()).unwrap();
+        let stronger = PasswordConfig {
+            memory_kib: 2048,
+            ..weak_config()
+        };
+        assert_eq!(
+            verify_password("correct-horse", &hash, &stronger).unwrap(),
+            PasswordVerification::ValidNeedsRehash
+        );
+        // A wrong password never asks for a rehash.
+        assert_eq!(
+            verify_password("wrong-horse", &hash, &stronger).unwrap(),
+            PasswordVerification::Invalid
+        );
+    }
+
+    #[test]
+    fn legacy_default_hash_still_verifies() {
+        // Hashes created before parameters were configurable used Argon2::default().
+        let salt = SaltString::generate(&mut OsRng);
+        let legacy = Argon2::default()
+            .hash_password(b"correct-horse", &salt)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            verify_password("correct-horse", &legacy, &PasswordConfig::default()).unwrap(),
+            PasswordVerification::Valid
+        );
+    }
 }
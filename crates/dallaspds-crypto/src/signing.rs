@@ -38,6 +38,11 @@ impl SigningKey {
         }
     }
 
+    /// Verify a signature produced by [`SigningKey::sign`] over `msg`.
+    pub fn verify(&self, msg: &[u8], sig: &[u8]) -> bool {
+        verify_with_did_key(&self.did_key(), msg, sig)
+    }
+
     /// Returns the compressed public key bytes.
     pub fn public_key_bytes(&self) -> Vec<u8> {
         // The did_key() string contains the multibase-encoded compressed public key.
@@ -80,6 +85,15 @@ impl SigningKey {
         }
     }
 
+    /// Returns the curve name stored alongside the key bytes, as accepted by
+    /// [`SigningKey::from_bytes`].
+    pub fn key_type(&self) -> &'static str {
+        match self {
+            SigningKey::P256(_) => "p256",
+            SigningKey::K256(_) => "k256",
+        }
+    }
+
     /// Returns the JWT algorithm name for this key type.
     ///
     /// - P-256 => `"ES256"`
@@ -92,6 +106,12 @@ impl SigningKey {
     }
 }
 
+/// Verify `sig` over `msg` against the public key in `did_key`, e.g. a key
+/// taken from a DID document.
+pub fn verify_with_did_key(did_key: &str, msg: &[u8], sig: &[u8]) -> bool {
+    atrium_crypto::verify::verify_signature(did_key, msg, sig).is_ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +133,16 @@ mod tests {
         assert!(sig.len() >= 60 && sig.len() <= 72, "unexpected sig length: {}", sig.len());
     }
 
+    #[test]
+    fn verify_accepts_own_signature_only() {
+        let key = SigningKey::generate_p256().unwrap();
+        let other = SigningKey::generate_k256().unwrap();
+        let sig = key.sign(b"commit bytes").unwrap();
+        assert!(key.verify(b"commit bytes", &sig));
+        assert!(!key.verify(b"other bytes", &sig));
+        assert!(!other.verify(b"commit bytes", &sig));
+    }
+
     #[test]
     fn from_bytes_roundtrip_p256() {
         let key = SigningKey::generate_p256().unwrap();
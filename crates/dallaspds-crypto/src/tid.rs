@@ -35,10 +35,15 @@ impl TidGenerator {
 
         let candidate = (now_micros << 10) | (self.clock_id as u64);
 
-        // Ensure monotonically increasing: if candidate <= last, use last + 1
+        // Ensure monotonically increasing: if candidate <= last, advance the
+        // timestamp part of last by one microsecond, keeping our clock ID.
         let value = loop {
             let last = self.last.load(Ordering::Acquire);
-            let next = if candidate > last { candidate } else { last + 1 };
+            let next = if candidate > last {
+                candidate
+            } else {
+                (((last >> 10) + 1) << 10) | (self.clock_id as u64)
+            };
             match self.last.compare_exchange_weak(last, next, Ordering::AcqRel, Ordering::Acquire) {
                 Ok(_) => break next,
                 Err(_) => continue, // retry on contention
@@ -110,6 +115,43 @@ mod tests {
         assert_eq!(tid2.len(), 13);
     }
 
+    #[test]
+    fn tid_keeps_clock_id_when_clock_stalls() {
+        let tidgen = TidGenerator::new();
+        // Pretend a TID far in the future was already issued.
+        let future = u64::MAX >> 2;
+        tidgen.last.store(future, Ordering::Release);
+        let tid = tidgen.next_tid();
+        assert!(tid > encode_base32_sortkey(future));
+        // The low 10 bits of the last issued value are our clock ID.
+        assert_eq!(tidgen.last.load(Ordering::Acquire) & 0x03FF, tidgen.clock_id as u64);
+    }
+
+    #[test]
+    fn tid_shared_generator_concurrent() {
+        let tidgen = std::sync::Arc::new(TidGenerator::new());
+        let per_thread: Vec<Vec<String>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..16)
+                .map(|_| {
+                    let tidgen = tidgen.clone();
+                    scope.spawn(move || (0..500).map(|_| tidgen.next_tid()).collect::<Vec<_>>())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        // Each caller observes strictly increasing TIDs...
+        for tids in &per_thread {
+            assert!(tids.windows(2).all(|w| w[0] < w[1]), "TIDs not sorted");
+        }
+        // ...and no TID is ever handed out twice.
+        let mut all: Vec<String> = per_thread.into_iter().flatten().collect();
+        let total = all.len();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), total, "all TIDs must be unique");
+    }
+
     #[test]
     fn tid_all_unique_across_batch() {
         let tidgen = TidGenerator::new();
@@ -0,0 +1,85 @@
+//! Retries of idempotent requests to upstream services.
+//!
+//! Requests that time out, fail to connect or get a 429 or 5xx (anything
+//! [`PdsError::is_retryable`]) are sent again after an exponentially growing,
+//! jittered delay, up to `HttpRetryConfig::max_attempts` in total. A
+//! `Retry-After` (in seconds) on the response replaces that delay, unless it
+//! is longer than `HttpRetryConfig::max_backoff_ms`, in which case the
+//! response is returned without waiting. Other responses, including other
+//! 4xx, are returned straight away.
+
+use std::time::Duration;
+
+use dallaspds_core::config::HttpRetryConfig;
+use dallaspds_core::{PdsError, PdsResult};
+use rand::Rng;
+
+/// Send `request`, retrying transient failures as configured.
+///
+/// Only use this for requests that are safe to repeat. The final response is
+/// returned whatever its status, so callers handle errors as they would for
+/// a single attempt. A request whose body can't be cloned is sent once.
+pub async fn send_with_retry(
+    config: &HttpRetryConfig,
+    mut request: reqwest::RequestBuilder,
+) -> PdsResult<reqwest::Response> {
+    let mut delay = Duration::from_millis(config.initial_backoff_ms);
+    let max_delay = Duration::from_millis(config.max_backoff_ms);
+    let mut attempt = 1;
+    loop {
+        let retry = if attempt < config.max_attempts {
+            request.try_clone()
+        } else {
+            None
+        };
+        let result = request.send().await;
+        let Some(next) = retry else {
+            return result.map_err(PdsError::from_upstream_request);
+        };
+
+        let wait = match result {
+            Ok(resp) => {
+                let status = resp.status();
+                let error = PdsError::from_upstream_status(status.as_u16(), "");
+                if status.is_success() || !error.is_retryable() {
+                    return Ok(resp);
+                }
+                let wait = match retry_after(&resp) {
+                    Some(retry_after) if retry_after > max_delay => return Ok(resp),
+                    Some(retry_after) => retry_after,
+                    None => jittered(delay.min(max_delay)),
+                };
+                tracing::debug!("{} returned {status}, retrying (attempt {attempt})", resp.url());
+                wait
+            }
+            Err(e) => {
+                let error = PdsError::from_upstream_request(e);
+                if !error.is_retryable() {
+                    return Err(error);
+                }
+                tracing::debug!("upstream request failed, retrying (attempt {attempt}): {error}");
+                jittered(delay.min(max_delay))
+            }
+        };
+
+        tokio::time::sleep(wait).await;
+        delay = delay.saturating_mul(2);
+        request = next;
+        attempt += 1;
+    }
+}
+
+/// The delay asked for by a `Retry-After` header in seconds. HTTP dates are
+/// ignored.
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let value = resp.headers().get(reqwest::header::RETRY_AFTER)?;
+    let secs: u64 = value.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// A random delay between half of `delay` and all of it, so clients that
+/// failed together don't retry in lockstep.
+fn jittered(delay: Duration) -> Duration {
+    let millis = delay.as_millis() as u64;
+    Duration::from_millis(rand::thread_rng().gen_range(millis / 2..=millis))
+}
@@ -1,70 +1,166 @@
+use dallaspds_core::config::HttpRetryConfig;
 use dallaspds_core::{PdsError, PdsResult};
 
+pub mod http_retry;
+pub mod public_http;
+
 /// Resolve a handle to a DID using DNS TXT and HTTPS fallback.
 ///
 /// 1. Try DNS TXT record at `_atproto.{handle}` looking for `did=did:...`
 /// 2. Fallback to HTTPS: `https://{handle}/.well-known/atproto-did`
+///
+/// Returns `Ok(None)` only when both lookups got a definitive answer without
+/// a DID. If neither found one and either failed transiently (a DNS server
+/// failure, an HTTPS timeout or 5xx), returns that failure as one of the
+/// upstream [`PdsError`] variants.
 pub async fn resolve_handle(handle: &str) -> PdsResult<Option<String>> {
     // Try DNS first.
-    match resolve_handle_dns(handle).await {
+    let dns_err = match resolve_handle_dns(handle).await {
         Ok(Some(did)) => return Ok(Some(did)),
-        Ok(None) => {}
+        Ok(None) => None,
         Err(e) => {
             tracing::debug!("DNS handle resolution failed for {handle}: {e}");
+            Some(e)
         }
-    }
+    };
 
     // Fallback to HTTPS.
     match resolve_handle_https(handle).await {
         Ok(Some(did)) => Ok(Some(did)),
-        Ok(None) => Ok(None),
+        Ok(None) => dns_err.map_or(Ok(None), Err),
         Err(e) => {
             tracing::debug!("HTTPS handle resolution failed for {handle}: {e}");
-            Ok(None)
+            Err(e)
         }
     }
 }
 
+/// Resolve a handle by calling `com.atproto.identity.resolveHandle` on the
+/// service at `service_url`.
+///
+/// A `HandleNotFound` or `InvalidHandle` error from the service is a
+/// definitive `Ok(None)`; any other failure is an upstream [`PdsError`]
+/// classified by its cause.
+pub async fn resolve_handle_via(service_url: &str, handle: &str) -> PdsResult<Option<String>> {
+    let url = format!(
+        "{}/xrpc/com.atproto.identity.resolveHandle",
+        service_url.trim_end_matches('/')
+    );
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| PdsError::InternalError(e.to_string()))?;
+
+    let resp = client
+        .get(&url)
+        .query(&[("handle", handle)])
+        .send()
+        .await
+        .map_err(PdsError::from_upstream_request)?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+
+    if status.is_success() {
+        return match body.get("did").and_then(|did| did.as_str()) {
+            Some(did) if did.starts_with("did:") => Ok(Some(did.to_string())),
+            _ => Err(PdsError::Upstream(format!("{url} returned no DID"))),
+        };
+    }
+    match body.get("error").and_then(|error| error.as_str()) {
+        Some("HandleNotFound" | "InvalidHandle") if status.is_client_error() => Ok(None),
+        _ => Err(PdsError::from_upstream_status(
+            status.as_u16(),
+            format!("{url} returned {status}"),
+        )),
+    }
+}
+
 /// Resolve a DID document.
 ///
 /// - `did:plc:*` -> fetch from PLC directory (`https://plc.directory/{did}`)
 /// - `did:web:*` -> fetch `https://{domain}/.well-known/did.json`
 pub async fn resolve_did(did: &str) -> PdsResult<Option<serde_json::Value>> {
-    if let Some(plc_id) = did.strip_prefix("did:plc:") {
+    resolve_did_with_plc(did, "https://plc.directory", &HttpRetryConfig::default()).await
+}
+
+/// Resolve a DID document, fetching `did:plc` documents from `plc_url`.
+///
+/// A 4xx from the host is a definitive `Ok(None)`; timeouts and 5xx
+/// responses are retried per `retry`, then returned as upstream errors.
+/// `did:web` hosts must be public addresses (see [`public_http`]).
+pub async fn resolve_did_with_plc(
+    did: &str,
+    plc_url: &str,
+    retry: &HttpRetryConfig,
+) -> PdsResult<Option<serde_json::Value>> {
+    let (url, builder) = if let Some(plc_id) = did.strip_prefix("did:plc:") {
         if plc_id.is_empty() {
             return Ok(None);
         }
-        let url = format!("https://plc.directory/{did}");
-        let resp = reqwest::get(&url)
-            .await
-            .map_err(|e| PdsError::Upstream(e.to_string()))?;
-        if !resp.status().is_success() {
-            return Ok(None);
-        }
-        let doc: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| PdsError::Upstream(e.to_string()))?;
-        Ok(Some(doc))
+        let url = format!("{}/{did}", plc_url.trim_end_matches('/'));
+        (url, reqwest::Client::builder())
     } else if let Some(domain) = did.strip_prefix("did:web:") {
         if domain.is_empty() {
             return Ok(None);
         }
         let url = format!("https://{}/.well-known/did.json", domain);
-        let resp = reqwest::get(&url)
-            .await
-            .map_err(|e| PdsError::Upstream(e.to_string()))?;
-        if !resp.status().is_success() {
+        let Ok(parsed) = reqwest::Url::parse(&url) else {
             return Ok(None);
-        }
-        let doc: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| PdsError::Upstream(e.to_string()))?;
-        Ok(Some(doc))
+        };
+        public_http::ensure_public_url(&parsed)?;
+        (url, public_http::client_builder())
     } else {
-        Ok(None)
+        return Ok(None);
+    };
+
+    let client = builder
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| PdsError::InternalError(e.to_string()))?;
+    let resp = http_retry::send_with_retry(retry, client.get(&url)).await?;
+    let status = resp.status();
+    if status.is_client_error() {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(PdsError::from_upstream_status(
+            status.as_u16(),
+            format!("{url} returned {status}"),
+        ));
     }
+    let doc: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(PdsError::from_upstream_request)?;
+    Ok(Some(doc))
+}
+
+/// Extract the `#atproto_pds` service endpoint from a DID document.
+pub fn pds_endpoint(doc: &serde_json::Value) -> Option<String> {
+    doc.get("service")?
+        .as_array()?
+        .iter()
+        .find(|service| {
+            service
+                .get("id")
+                .and_then(|id| id.as_str())
+                .is_some_and(|id| id == "#atproto_pds" || id.ends_with("#atproto_pds"))
+        })?
+        .get("serviceEndpoint")?
+        .as_str()
+        .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+}
+
+/// Extract the `#atproto` signing key from a DID document, as a `did:key`.
+pub fn atproto_signing_key(doc: &serde_json::Value) -> Option<String> {
+    let method = doc.get("verificationMethod")?.as_array()?.iter().find(|method| {
+        method
+            .get("id")
+            .and_then(|id| id.as_str())
+            .is_some_and(|id| id == "#atproto" || id.ends_with("#atproto"))
+    })?;
+    let multibase = method.get("publicKeyMultibase")?.as_str()?;
+    Some(format!("did:key:{multibase}"))
 }
 
 /// Try resolving a handle via DNS TXT record at `_atproto.{handle}`.
@@ -76,10 +172,12 @@ async fn resolve_handle_dns(handle: &str) -> PdsResult<Option<String>> {
         .build();
 
     let lookup_name = format!("_atproto.{handle}.");
-    let txt_lookup = resolver
-        .txt_lookup(&lookup_name)
-        .await
-        .map_err(|e| PdsError::Upstream(format!("DNS TXT lookup failed: {e}")))?;
+    let txt_lookup = match resolver.txt_lookup(&lookup_name).await {
+        Ok(lookup) => lookup,
+        // The name or its TXT records don't exist: a definitive miss.
+        Err(e) if e.is_nx_domain() || e.is_no_records_found() => return Ok(None),
+        Err(e) => return Err(PdsError::Upstream(format!("DNS TXT lookup failed: {e}"))),
+    };
 
     for record in txt_lookup {
         let txt = record.to_string();
@@ -95,19 +193,28 @@ async fn resolve_handle_dns(handle: &str) -> PdsResult<Option<String>> {
 }
 
 /// Try resolving a handle via HTTPS well-known endpoint.
+///
+/// A host that can't be reached or answers with a non-5xx response without a
+/// DID doesn't serve the handle; timeouts and 5xx responses are transient.
 async fn resolve_handle_https(handle: &str) -> PdsResult<Option<String>> {
     let url = format!("https://{handle}/.well-known/atproto-did");
-    let client = reqwest::Client::builder()
+    let client = public_http::client_builder()
         .timeout(std::time::Duration::from_secs(10))
         .build()
         .map_err(|e| PdsError::InternalError(e.to_string()))?;
 
-    let resp = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| PdsError::Upstream(e.to_string()))?;
+    let resp = match client.get(&url).send().await {
+        Ok(resp) => resp,
+        Err(e) if e.is_connect() && !e.is_timeout() => return Ok(None),
+        Err(e) => return Err(PdsError::from_upstream_request(e)),
+    };
 
+    if resp.status().is_server_error() {
+        return Err(PdsError::from_upstream_status(
+            resp.status().as_u16(),
+            format!("{url} returned {}", resp.status()),
+        ));
+    }
     if !resp.status().is_success() {
         return Ok(None);
     }
@@ -115,7 +222,7 @@ async fn resolve_handle_https(handle: &str) -> PdsResult<Option<String>> {
     let body = resp
         .text()
         .await
-        .map_err(|e| PdsError::Upstream(e.to_string()))?;
+        .map_err(PdsError::from_upstream_request)?;
 
     let did = body.trim();
     if did.starts_with("did:") {
@@ -0,0 +1,146 @@
+//! HTTP clients for URLs that come from untrusted input: `did:web` domains,
+//! handle domains, DID document service endpoints and OAuth client IDs.
+//!
+//! Host names are resolved through [`PublicResolver`], which drops loopback,
+//! private, link-local and other non-public addresses, so the check applies
+//! to the address actually connected to rather than an earlier lookup. Hosts
+//! given as IP literals never reach the resolver; [`ensure_public_url`]
+//! checks those, including on every redirect.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use dallaspds_core::{PdsError, PdsResult};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+/// Redirects followed before a request fails.
+const MAX_REDIRECTS: usize = 5;
+
+/// Whether `ip` is a globally routable unicast address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space (100.64.0.0/10) and benchmarking (198.18.0.0/15).
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (18..20).contains(&b))
+        // Reserved (240.0.0.0/4).
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local (fc00::/7) and link-local (fe80::/10).
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80)
+}
+
+/// Fail unless `url` is http(s) and its host, if an IP literal, is public.
+/// Named hosts are checked when they are resolved.
+pub fn ensure_public_url(url: &reqwest::Url) -> PdsResult<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(PdsError::Upstream(format!("{url} is not an http(s) URL")));
+    }
+    let Some(host) = url.host_str() else {
+        return Err(PdsError::Upstream(format!("{url} has no host")));
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.parse::<IpAddr>().is_ok_and(|ip| !is_public_ip(ip)) {
+        return Err(PdsError::Upstream(format!("{url} is not a public address")));
+    }
+    Ok(())
+}
+
+/// DNS resolver that only returns public addresses.
+#[derive(Debug, Default)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} does not resolve to a public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A client builder that can only reach public addresses. Callers still
+/// check the initial URL with [`ensure_public_url`].
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let redirects = reqwest::redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if let Err(e) = ensure_public_url(attempt.url()) {
+            attempt.error(e.to_string())
+        } else {
+            attempt.follow()
+        }
+    });
+    reqwest::Client::builder()
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(redirects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_public_addresses_pass() {
+        for ip in ["8.8.8.8", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn ip_literal_urls_are_checked() {
+        let url = |s: &str| reqwest::Url::parse(s).unwrap();
+        assert!(ensure_public_url(&url("https://example.com/")).is_ok());
+        assert!(ensure_public_url(&url("https://8.8.8.8/")).is_ok());
+        assert!(ensure_public_url(&url("http://169.254.169.254/latest/meta-data")).is_err());
+        assert!(ensure_public_url(&url("http://[::1]:8080/")).is_err());
+        assert!(ensure_public_url(&url("file:///etc/passwd")).is_err());
+    }
+}
@@ -1,11 +1,437 @@
 use std::sync::Arc;
 
-use atrium_repo::blockstore::{AsyncBlockStoreRead, AsyncBlockStoreWrite, CarStore, SHA2_256};
+use atrium_repo::blockstore::{
+    AsyncBlockStoreRead, AsyncBlockStoreWrite, DAG_CBOR, MemoryBlockStore, SHA2_256,
+};
 use atrium_repo::{Cid, Repository};
 use dallaspds_core::error::{PdsError, PdsResult};
 use dallaspds_core::traits::RepoStore;
+use dallaspds_crypto::SigningKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::blockstore_adapter::{RepoStoreAdapter, cid_from_bytes};
+use crate::blockstore_adapter::{RepoStoreAdapter, cid_from_bytes, cid_to_bytes};
+use crate::commit::{Commit, SignedCommit, UnsignedCommit};
+
+/// A block in a CAR file: its CID and raw bytes.
+pub type CarBlock = (Cid, Vec<u8>);
+
+/// CARv1 header: a DAG-CBOR map of `{ roots, version }`.
+///
+/// Fields are declared in canonical DAG-CBOR key order.
+#[derive(Debug, Serialize, Deserialize)]
+struct CarHeader {
+    roots: Vec<Cid>,
+    version: u64,
+}
+
+/// Encode a CARv1 file with a single root.
+///
+/// Each block is written as a varint-length-prefixed section of
+/// `CID bytes || block bytes`, in the order given.
+pub fn write_car(root: Cid, blocks: &[CarBlock]) -> PdsResult<Vec<u8>> {
+    encode_car(vec![root], blocks.iter())
+}
+
+/// Encode a CARv1 file of loose blocks, as served by `sync.getBlocks`.
+///
+/// `blocks[i]` is the block for `cids[i]`, or `None` if it wasn't found;
+/// missing blocks are left out. The blocks don't form a DAG, so like the
+/// reference PDS the header lists no roots.
+pub fn blocks_to_car(cids: &[Cid], blocks: &[Option<Vec<u8>>]) -> PdsResult<Vec<u8>> {
+    let found: Vec<CarBlock> = cids
+        .iter()
+        .zip(blocks)
+        .filter_map(|(cid, block)| Some((*cid, block.clone()?)))
+        .collect();
+    encode_car(Vec::new(), found.iter())
+}
+
+fn encode_car<'a>(roots: Vec<Cid>, blocks: impl Iterator<Item = &'a CarBlock>) -> PdsResult<Vec<u8>> {
+    let header = serde_ipld_dagcbor::to_vec(&CarHeader { roots, version: 1 })
+        .map_err(|e| PdsError::Storage(format!("failed to encode CAR header: {e}")))?;
+
+    let mut buf = Vec::new();
+    write_varint(&mut buf, header.len() as u64);
+    buf.extend_from_slice(&header);
+
+    for (cid, data) in blocks {
+        let cid_bytes = cid.to_bytes();
+        write_varint(&mut buf, (cid_bytes.len() + data.len()) as u64);
+        buf.extend_from_slice(&cid_bytes);
+        buf.extend_from_slice(data);
+    }
+
+    Ok(buf)
+}
+
+/// Decode a CARv1 file into its roots and blocks.
+///
+/// Every block's CID is checked against a SHA-256 hash of its bytes, so a
+/// successfully decoded CAR contains only content-addressed data.
+pub fn read_car(bytes: &[u8]) -> PdsResult<(Vec<Cid>, Vec<CarBlock>)> {
+    let mut pos = 0;
+
+    let header_len = read_varint(bytes, &mut pos)?;
+    let header_bytes = take(bytes, &mut pos, header_len)?;
+    let header: CarHeader = serde_ipld_dagcbor::from_slice(header_bytes)
+        .map_err(|e| PdsError::InvalidRequest(format!("invalid CAR header: {e}")))?;
+    if header.version != 1 {
+        return Err(PdsError::InvalidRequest(format!(
+            "unsupported CAR version: {}",
+            header.version
+        )));
+    }
+
+    let mut blocks = Vec::new();
+    while pos < bytes.len() {
+        let section_len = read_varint(bytes, &mut pos)?;
+        let section = take(bytes, &mut pos, section_len)?;
+
+        let mut cursor = std::io::Cursor::new(section);
+        let cid = Cid::read_bytes(&mut cursor)
+            .map_err(|e| PdsError::InvalidRequest(format!("invalid CID in CAR: {e}")))?;
+        let data = &section[cursor.position() as usize..];
+
+        if cid.hash().code() != SHA2_256 {
+            return Err(PdsError::InvalidRequest(format!(
+                "unsupported hash in CAR block {cid}"
+            )));
+        }
+        if Sha256::digest(data).as_slice() != cid.hash().digest() {
+            return Err(PdsError::InvalidRequest(format!(
+                "CAR block {cid} does not match its hash"
+            )));
+        }
+
+        blocks.push((cid, data.to_vec()));
+    }
+
+    Ok((header.roots, blocks))
+}
+
+/// Returns `true` if `ancestor` is `head` or reachable from it through
+/// commit `prev` links.
+///
+/// A missing or undecodable commit ends the walk, so history that was
+/// replaced by an import reports `false` rather than an error.
+async fn is_ancestor_commit<S: AsyncBlockStoreRead>(
+    blocks: &mut S,
+    head: Cid,
+    ancestor: Cid,
+) -> bool {
+    let mut cursor = Some(head);
+    while let Some(cid) = cursor {
+        if cid == ancestor {
+            return true;
+        }
+        let Ok(block) = blocks.read_block(cid).await else {
+            return false;
+        };
+        cursor = match Commit::decode(&block) {
+            Ok(commit) => commit.prev,
+            Err(_) => return false,
+        };
+    }
+    false
+}
+
+/// A repo read from a CAR and checked, but not yet written to the store.
+struct StagedRepo {
+    root: Cid,
+    /// Root of the MST the commit points at.
+    data: Cid,
+    rev: String,
+    /// Blocks reachable from the root commit.
+    blocks: Vec<CarBlock>,
+}
+
+/// Decode a repo CAR and check it belongs to `did` and is complete.
+///
+/// If `signing_key` is given, the root commit's signature must verify
+/// against it.
+async fn stage_repo_car(
+    did: &str,
+    car_bytes: &[u8],
+    signing_key: Option<&SigningKey>,
+) -> PdsResult<StagedRepo> {
+    let (roots, blocks) = read_car(car_bytes)?;
+    let root_cid = match roots.as_slice() {
+        [root] => *root,
+        _ => {
+            return Err(PdsError::InvalidRequest(format!(
+                "CAR must have exactly one root, found {}",
+                roots.len()
+            )));
+        }
+    };
+
+    // Stage the blocks in memory so the repo can be checked before import.
+    let mut staged = MemoryBlockStore::new();
+    for (cid, data) in &blocks {
+        staged
+            .write_block(cid.codec(), SHA2_256, data)
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to stage block {cid}: {e}")))?;
+    }
+
+    let root_block = blocks
+        .iter()
+        .find(|(cid, _)| *cid == root_cid)
+        .map(|(_, data)| data)
+        .ok_or_else(|| PdsError::InvalidRequest("CAR is missing its root block".to_string()))?;
+    let owner = Commit::decode(root_block)
+        .map_err(|e| PdsError::InvalidRequest(format!("invalid root commit: {e}")))?;
+    if owner.did != did {
+        return Err(PdsError::InvalidRequest(format!(
+            "CAR commit belongs to {}, not {did}",
+            owner.did
+        )));
+    }
+
+    let mut repo = Repository::open(&mut staged, root_cid)
+        .await
+        .map_err(|e| PdsError::InvalidRequest(format!("invalid root commit: {e}")))?;
+
+    let commit = repo.commit();
+    if let Some(signing_key) = signing_key
+        && !signing_key.verify(&commit.bytes(), commit.sig())
+    {
+        return Err(PdsError::InvalidRequest(
+            "root commit signature does not match the account signing key".to_string(),
+        ));
+    }
+
+    // Walking the whole tree fails if any reachable block is missing.
+    let reachable = repo
+        .export()
+        .await
+        .map_err(|e| PdsError::InvalidRequest(format!("incomplete repo in CAR: {e}")))?
+        .collect::<std::collections::HashSet<_>>();
+
+    Ok(StagedRepo {
+        root: root_cid,
+        data: commit.data(),
+        rev: commit.rev().to_string(),
+        blocks: blocks
+            .into_iter()
+            .filter(|(cid, _)| reachable.contains(cid))
+            .collect(),
+    })
+}
+
+/// Blocks written per transaction when importing a repo.
+const IMPORT_BATCH_SIZE: usize = 500;
+
+/// How much of a repo import has been written, and the commit it was
+/// verified against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// CID bytes of the commit the repo root should point at.
+    pub commit: Vec<u8>,
+    pub rev: String,
+    /// Blocks written so far.
+    pub blocks: usize,
+    /// Total size of those blocks.
+    pub bytes: u64,
+}
+
+/// Write staged blocks in batches, counting them into `progress`.
+///
+/// A failed batch is reported with how far the import got, so the caller
+/// can tell the client which verified commit was being written.
+async fn write_staged_blocks<R: RepoStore>(
+    store: &R,
+    did: &str,
+    blocks: Vec<CarBlock>,
+    progress: &mut ImportProgress,
+) -> PdsResult<()> {
+    let total = blocks.len();
+    let mut blocks = blocks.into_iter().peekable();
+    while blocks.peek().is_some() {
+        let batch: Vec<(Vec<u8>, Vec<u8>)> = blocks
+            .by_ref()
+            .take(IMPORT_BATCH_SIZE)
+            .map(|(cid, data)| (cid.to_bytes(), data))
+            .collect();
+        if let Err(e) = store.put_blocks(did, &batch).await {
+            let commit = cid_from_bytes(&progress.commit)
+                .map(|cid| cid.to_string())
+                .unwrap_or_default();
+            return Err(PdsError::Storage(format!(
+                "import stopped after {} of {total} blocks ({} bytes) of verified commit \
+                 {commit} (rev {}); the repo root was not changed: {e}",
+                progress.blocks, progress.bytes, progress.rev
+            )));
+        }
+        progress.blocks += batch.len();
+        progress.bytes += batch.iter().map(|(_, data)| data.len() as u64).sum::<u64>();
+    }
+    Ok(())
+}
+
+/// Write the contents of a CAR file into a repository.
+///
+/// The CAR must have a single root pointing at a commit for `did`, signed
+/// by `signing_key`, and must contain every block reachable from that
+/// commit. Nothing is written until these checks pass; the blocks are then
+/// written in batches alongside the DID's existing ones, so the current
+/// root stays readable until the caller moves it with `update_repo_root`.
+/// Blocks left unreachable are removed afterwards by [`prune_unreachable`].
+pub async fn import_car<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    car_bytes: &[u8],
+    signing_key: &SigningKey,
+) -> PdsResult<ImportProgress> {
+    let staged = stage_repo_car(did, car_bytes, Some(signing_key)).await?;
+
+    let mut progress = ImportProgress {
+        commit: cid_to_bytes(&staged.root),
+        rev: staged.rev,
+        blocks: 0,
+        bytes: 0,
+    };
+    write_staged_blocks(store.as_ref(), did, staged.blocks, &mut progress).await?;
+    Ok(progress)
+}
+
+/// Install a repo exported from another PDS, re-signing it for this one.
+///
+/// Used for account migration, where the CAR's commit is signed by the old
+/// PDS's key. The CAR is checked as in [`import_car`] apart from the
+/// signature, then its tree is kept as-is under a fresh commit with no
+/// `prev`, signed by `signing_key` at `rev`.
+pub async fn import_migrated_car<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    car_bytes: &[u8],
+    signing_key: &SigningKey,
+    rev: &str,
+) -> PdsResult<ImportProgress> {
+    let staged = stage_repo_car(did, car_bytes, None).await?;
+
+    let unsigned = UnsignedCommit {
+        did,
+        rev,
+        data: staged.data,
+        prev: None,
+        version: 3,
+    };
+    let unsigned_bytes = serde_ipld_dagcbor::to_vec(&unsigned)
+        .map_err(|e| PdsError::Storage(format!("failed to encode commit: {e}")))?;
+    let sig = signing_key.sign(&unsigned_bytes)?;
+    let commit_bytes = serde_ipld_dagcbor::to_vec(&SignedCommit {
+        did,
+        rev,
+        sig: &sig,
+        data: staged.data,
+        prev: None,
+        version: 3,
+    })
+    .map_err(|e| PdsError::Storage(format!("failed to encode commit: {e}")))?;
+
+    // The old commit is not reachable from the new one, so it is dropped.
+    let mut progress = ImportProgress {
+        commit: cid_to_bytes(&staged.root),
+        rev: staged.rev,
+        blocks: 0,
+        bytes: 0,
+    };
+    let blocks = staged
+        .blocks
+        .into_iter()
+        .filter(|(cid, _)| *cid != staged.root)
+        .collect();
+    write_staged_blocks(store.as_ref(), did, blocks, &mut progress).await?;
+    let commit_cid = RepoStoreAdapter::new(store, did.to_string())
+        .write_block(DAG_CBOR, SHA2_256, &commit_bytes)
+        .await
+        .map_err(|e| PdsError::Storage(format!("failed to write commit: {e}")))?;
+
+    Ok(ImportProgress {
+        commit: cid_to_bytes(&commit_cid),
+        rev: rev.to_string(),
+        blocks: progress.blocks + 1,
+        bytes: progress.bytes + commit_bytes.len() as u64,
+    })
+}
+
+/// Delete the DID's blocks that are not reachable from `root`, such as
+/// those of a repo replaced by an import. Returns how many were removed.
+pub async fn prune_unreachable<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    root: &[u8],
+) -> PdsResult<u64> {
+    let mut adapter = RepoStoreAdapter::new(store.clone(), did.to_string());
+    let root_cid =
+        cid_from_bytes(root).map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+    let keep = {
+        let mut repo = Repository::open(&mut adapter, root_cid)
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to open repo: {e}")))?;
+        repo.export()
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to export repo CIDs: {e}")))?
+            .map(|cid| cid.to_bytes())
+            .collect()
+    };
+    store.retain_blocks(did, &keep).await
+}
+
+/// Append an unsigned LEB128 varint.
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+/// Read an unsigned LEB128 varint, advancing `pos`.
+fn read_varint(bytes: &[u8], pos: &mut usize) -> PdsResult<usize> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or_else(|| PdsError::InvalidRequest("truncated CAR varint".to_string()))?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return usize::try_from(value)
+                .map_err(|_| PdsError::InvalidRequest("CAR varint too large".to_string()));
+        }
+    }
+    Err(PdsError::InvalidRequest("CAR varint too long".to_string()))
+}
+
+/// Take `len` bytes starting at `pos`, advancing `pos`.
+fn take<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> PdsResult<&'a [u8]> {
+    let end = pos
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| PdsError::InvalidRequest("truncated CAR section".to_string()))?;
+    let slice = &bytes[*pos..end];
+    *pos = end;
+    Ok(slice)
+}
+
+/// Read the given blocks from the repo store, in order.
+async fn read_blocks<R: RepoStore>(
+    adapter: &mut RepoStoreAdapter<R>,
+    cids: impl IntoIterator<Item = Cid>,
+) -> PdsResult<Vec<CarBlock>> {
+    let mut blocks = Vec::new();
+    for cid in cids {
+        let block = adapter
+            .read_block(cid)
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to read block {cid}: {e}")))?;
+        blocks.push((cid, block));
+    }
+    Ok(blocks)
+}
 
 /// Export the full repository as a CAR file (v1).
 ///
@@ -35,30 +461,8 @@ pub async fn export_full_car<R: RepoStore>(
     };
     // repo is dropped, adapter is available again
 
-    // Create a CAR file in memory with the root CID
-    let mut car_buf = Vec::new();
-    let mut car_store =
-        CarStore::create_with_roots(std::io::Cursor::new(&mut car_buf), [root_cid])
-            .await
-            .map_err(|e| PdsError::Storage(format!("failed to create CAR: {e}")))?;
-
-    // Write each block into the CAR
-    for cid in cids {
-        let block = adapter
-            .read_block(cid)
-            .await
-            .map_err(|e| PdsError::Storage(format!("failed to read block {cid}: {e}")))?;
-
-        car_store
-            .write_block(cid.codec(), SHA2_256, &block)
-            .await
-            .map_err(|e| PdsError::Storage(format!("failed to write block to CAR: {e}")))?;
-    }
-
-    // The car_store borrows car_buf via Cursor; drop it to release the borrow
-    drop(car_store);
-
-    Ok(car_buf)
+    let blocks = read_blocks(&mut adapter, cids).await?;
+    write_car(root_cid, &blocks)
 }
 
 /// Generate a diff CAR containing only blocks changed since a given revision.
@@ -66,7 +470,9 @@ pub async fn export_full_car<R: RepoStore>(
 /// This compares the current repo state with a previous commit CID and returns
 /// a CAR file containing only the new/changed blocks.
 ///
-/// If `since_root` is `None`, this behaves identically to `export_full_car`.
+/// If `since_root` is `None`, or is not an ancestor of `current_root` in the
+/// commit chain, this behaves identically to `export_full_car`, so a bogus
+/// `since` never produces a partial repo.
 pub async fn generate_diff_car<R: RepoStore>(
     store: Arc<R>,
     did: &str,
@@ -83,7 +489,11 @@ pub async fn generate_diff_car<R: RepoStore>(
     let current_cid = cid_from_bytes(current_root)
         .map_err(|e| PdsError::Storage(format!("invalid current root CID: {e}")))?;
 
-    let mut adapter = RepoStoreAdapter::new(store, did.to_string());
+    let mut adapter = RepoStoreAdapter::new(store.clone(), did.to_string());
+
+    if !is_ancestor_commit(&mut adapter, current_cid, since_cid).await {
+        return export_full_car(store, did, current_root).await;
+    }
 
     // Get current repo CIDs
     let current_cids = {
@@ -116,25 +526,404 @@ pub async fn generate_diff_car<R: RepoStore>(
         .collect();
 
     // Create a CAR with just the diff blocks
-    let mut car_buf = Vec::new();
-    let mut car_store =
-        CarStore::create_with_roots(std::io::Cursor::new(&mut car_buf), [current_cid])
+    let blocks = read_blocks(&mut adapter, diff_cids).await?;
+    write_car(current_cid, &blocks)
+}
+
+/// Generate a CAR proving a record's presence (or absence) in the repo.
+///
+/// The CAR's root is the current commit, followed by the commit block, the
+/// MST nodes on the path from the tree root to `collection/rkey`, and the
+/// record block itself if it exists. Without the record, the nodes show
+/// where it would have been.
+pub async fn generate_record_proof_car<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    current_root: &[u8],
+    collection: &str,
+    rkey: &str,
+) -> PdsResult<Vec<u8>> {
+    let mut adapter = RepoStoreAdapter::new(store, did.to_string());
+
+    let root_cid = cid_from_bytes(current_root)
+        .map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+
+    let cids = {
+        let mut repo = Repository::open(&mut adapter, root_cid)
             .await
-            .map_err(|e| PdsError::Storage(format!("failed to create diff CAR: {e}")))?;
+            .map_err(|e| PdsError::Storage(format!("failed to open repo: {e}")))?;
 
-    for cid in diff_cids {
-        let block = adapter
-            .read_block(cid)
+        // The commit, then each node traversed, then the record if found.
+        repo.extract_raw(&format!("{collection}/{rkey}"))
             .await
-            .map_err(|e| PdsError::Storage(format!("failed to read block {cid}: {e}")))?;
+            .map_err(|e| PdsError::Storage(format!("failed to collect record proof: {e}")))?
+            .collect::<Vec<_>>()
+    };
+
+    let blocks = read_blocks(&mut adapter, cids).await?;
+    write_car(root_cid, &blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound::Unbounded;
+
+    use atrium_repo::Multihash;
+    use atrium_repo::blockstore::CarStore;
+    use dallaspds_crypto::{SigningKey, TidGenerator};
+
+    use super::*;
+    use crate::test_store::MemRepoStore;
+
+    fn block(data: &[u8]) -> CarBlock {
+        let hash = Multihash::wrap(SHA2_256, &Sha256::digest(data)).unwrap();
+        (Cid::new_v1(DAG_CBOR, hash), data.to_vec())
+    }
+
+    /// Create a repo with `count` posts, returning the store and root bytes.
+    async fn build_repo(did: &str, key: &SigningKey, count: usize) -> (Arc<MemRepoStore>, Vec<u8>) {
+        let store = Arc::new(MemRepoStore::default());
+        let tid_gen = TidGenerator::new();
+
+        let (mut root, _) = crate::create_repo(store.clone(), did, key).await.unwrap();
+        for i in 0..count {
+            let record = serde_json::json!({ "$type": "app.bsky.feed.post", "text": format!("post {i}") });
+            let output = crate::create_record(
+                store.clone(),
+                did,
+                key,
+                "app.bsky.feed.post",
+                None,
+                &record,
+                &tid_gen,
+                &root,
+            )
+            .await
+            .unwrap();
+            root = output.new_root;
+        }
+        (store, root)
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        for n in [0u64, 1, 127, 128, 300, 16_384, u32::MAX as u64] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, n);
+            let mut pos = 0;
+            assert_eq!(read_varint(&buf, &mut pos).unwrap() as u64, n);
+            assert_eq!(pos, buf.len());
+        }
+    }
+
+    #[test]
+    fn car_round_trip() {
+        let blocks = vec![block(b"\xa1aa\x01"), block(b"\xa1ab\x02"), block(&[0x80; 300])];
+        let root = blocks[0].0;
+
+        let car = write_car(root, &blocks).unwrap();
+        let (roots, decoded) = read_car(&car).unwrap();
+
+        assert_eq!(roots, vec![root]);
+        assert_eq!(decoded, blocks);
+    }
+
+    #[test]
+    fn car_with_no_blocks() {
+        let (root, _) = block(b"\xa0");
+        let (roots, blocks) = read_car(&write_car(root, &[]).unwrap()).unwrap();
+        assert_eq!(roots, vec![root]);
+        assert!(blocks.is_empty());
+    }
 
-        car_store
-            .write_block(cid.codec(), SHA2_256, &block)
+    #[test]
+    fn blocks_to_car_skips_missing_blocks() {
+        let (a, b, c) = (block(b"\xa1aa\x01"), block(b"\xa1ab\x02"), block(b"\xa1ac\x03"));
+        let cids = [a.0, b.0, c.0];
+        let car = blocks_to_car(&cids, &[Some(a.1.clone()), None, Some(c.1.clone())]).unwrap();
+
+        let (roots, decoded) = read_car(&car).unwrap();
+        assert!(roots.is_empty());
+        assert_eq!(decoded, vec![a, c]);
+    }
+
+    #[test]
+    fn read_car_rejects_truncated_input() {
+        let blocks = vec![block(b"\xa1aa\x01")];
+        let car = write_car(blocks[0].0, &blocks).unwrap();
+        assert!(read_car(&car[..car.len() - 1]).is_err());
+        assert!(read_car(&[]).is_err());
+    }
+
+    #[test]
+    fn read_car_rejects_hash_mismatch() {
+        let (cid, _) = block(b"\xa1aa\x01");
+        let car = write_car(cid, &[(cid, b"\xa1aa\x02".to_vec())]).unwrap();
+        assert!(read_car(&car).is_err());
+    }
+
+    #[tokio::test]
+    async fn export_full_car_decodes_with_independent_parser() {
+        let did = "did:plc:carexporttest000000000000";
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root) = build_repo(did, &key, 5).await;
+
+        let car = export_full_car(store.clone(), did, &root).await.unwrap();
+        let root_cid = cid_from_bytes(&root).unwrap();
+
+        let (roots, blocks) = read_car(&car).unwrap();
+        assert_eq!(roots, vec![root_cid]);
+
+        // atrium's CarStore parses the same bytes independently.
+        let mut car_store = CarStore::open(std::io::Cursor::new(car.clone())).await.unwrap();
+        assert_eq!(car_store.roots().collect::<Vec<_>>(), vec![root_cid]);
+        for (cid, data) in &blocks {
+            assert_eq!(&car_store.read_block(*cid).await.unwrap(), data);
+        }
+
+        // The exported blocks are enough to reopen the repo at the same root.
+        let restored = Arc::new(MemRepoStore::default());
+        for (cid, data) in &blocks {
+            restored.put_block(did, &cid.to_bytes(), data).await.unwrap();
+        }
+        let records =
+            crate::list_records(restored, did, "app.bsky.feed.post", 100, (Unbounded, Unbounded), false, &root)
+                .await
+                .unwrap();
+        assert_eq!(records.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn import_car_round_trips_export() {
+        let did = "did:plc:carimporttest0000000000000";
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root) = build_repo(did, &key, 3).await;
+        let car = export_full_car(store, did, &root).await.unwrap();
+
+        let target = Arc::new(MemRepoStore::default());
+        // Stale blocks from a previous repo are replaced.
+        target.put_block(did, b"stale", b"old").await.unwrap();
+
+        let progress = import_car(target.clone(), did, &car, &key).await.unwrap();
+        assert_eq!(progress.commit, root);
+        assert!(!progress.rev.is_empty());
+        let (_, blocks) = read_car(&car).unwrap();
+        assert_eq!(progress.blocks, blocks.len());
+        let bytes: usize = blocks.iter().map(|(_, data)| data.len()).sum();
+        assert_eq!(progress.bytes, bytes as u64);
+
+        // The previous repo's blocks stay until the root has moved.
+        assert!(target.has_block(did, b"stale").await.unwrap());
+        assert_eq!(prune_unreachable(target.clone(), did, &root).await.unwrap(), 1);
+        assert!(!target.has_block(did, b"stale").await.unwrap());
+        let new_root = progress.commit;
+
+        let records = crate::list_records(
+            target,
+            did,
+            "app.bsky.feed.post",
+            100,
+            (Unbounded, Unbounded),
+            false,
+            &new_root,
+        )
+        .await
+        .unwrap();
+        assert_eq!(records.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn import_car_rejects_wrong_signing_key() {
+        let did = "did:plc:carimportbadkey00000000000";
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root) = build_repo(did, &key, 1).await;
+        let car = export_full_car(store, did, &root).await.unwrap();
+
+        let target = Arc::new(MemRepoStore::default());
+        let other_key = SigningKey::generate_p256().unwrap();
+        assert!(import_car(target.clone(), did, &car, &other_key).await.is_err());
+        assert!(target.get_all_blocks(did).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn import_car_rejects_other_did() {
+        let did = "did:plc:carimportowner000000000000";
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root) = build_repo(did, &key, 1).await;
+        let car = export_full_car(store, did, &root).await.unwrap();
+
+        let target = Arc::new(MemRepoStore::default());
+        let other_did = "did:plc:carimportother000000000000";
+        assert!(import_car(target, other_did, &car, &key).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn import_migrated_car_resigns_with_new_key() {
+        let did = "did:plc:carimportmigrate0000000000";
+        let old_key = SigningKey::generate_p256().unwrap();
+        let (store, root) = build_repo(did, &old_key, 3).await;
+        let car = export_full_car(store.clone(), did, &root).await.unwrap();
+
+        let target = Arc::new(MemRepoStore::default());
+        let new_key = SigningKey::generate_p256().unwrap();
+        let rev = TidGenerator::new().next_tid();
+        let progress = import_migrated_car(target.clone(), did, &car, &new_key, &rev)
             .await
-            .map_err(|e| PdsError::Storage(format!("failed to write block to CAR: {e}")))?;
+            .unwrap();
+        let new_root = progress.commit;
+        assert_ne!(new_root, root);
+        assert_eq!(progress.rev, rev);
+
+        // Same tree, new commit with no history, signed by the new key.
+        let mut old_adapter = RepoStoreAdapter::new(store, did.to_string());
+        let old_repo = Repository::open(&mut old_adapter, cid_from_bytes(&root).unwrap())
+            .await
+            .unwrap();
+        let mut new_adapter = RepoStoreAdapter::new(target.clone(), did.to_string());
+        let new_repo = Repository::open(&mut new_adapter, cid_from_bytes(&new_root).unwrap())
+            .await
+            .unwrap();
+        let commit = new_repo.commit();
+        assert_eq!(commit.data(), old_repo.commit().data());
+        assert_eq!(commit.rev().to_string(), rev);
+        assert!(new_key.verify(&commit.bytes(), commit.sig()));
+        assert!(!target.has_block(did, &root).await.unwrap());
+
+        // The result is an ordinary repo that round-trips under the new key.
+        let exported = export_full_car(target.clone(), did, &new_root).await.unwrap();
+        let again = Arc::new(MemRepoStore::default());
+        import_car(again, did, &exported, &new_key).await.unwrap();
+
+        let records = crate::list_records(
+            target,
+            did,
+            "app.bsky.feed.post",
+            100,
+            (Unbounded, Unbounded),
+            false,
+            &new_root,
+        )
+        .await
+        .unwrap();
+        assert_eq!(records.len(), 3);
+    }
+
+    /// The set of block CIDs in a CAR.
+    fn car_cids(car: &[u8]) -> std::collections::HashSet<Cid> {
+        read_car(car).unwrap().1.into_iter().map(|(cid, _)| cid).collect()
+    }
+
+    #[tokio::test]
+    async fn diff_car_covers_changes_since_ancestor() {
+        let did = "did:plc:cardifftest00000000000000000";
+        let key = SigningKey::generate_p256().unwrap();
+        let tid_gen = TidGenerator::new();
+        let (store, since) = build_repo(did, &key, 2).await;
+
+        let mut root = since.clone();
+        for i in 0..3 {
+            let record =
+                serde_json::json!({ "$type": "app.bsky.feed.post", "text": format!("later {i}") });
+            root = crate::create_record(
+                store.clone(),
+                did,
+                &key,
+                "app.bsky.feed.post",
+                None,
+                &record,
+                &tid_gen,
+                &root,
+            )
+            .await
+            .unwrap()
+            .new_root;
+        }
+
+        let full = car_cids(&export_full_car(store.clone(), did, &root).await.unwrap());
+        let before = car_cids(&export_full_car(store.clone(), did, &since).await.unwrap());
+        let diff = generate_diff_car(store.clone(), did, &root, Some(since.as_slice()))
+            .await
+            .unwrap();
+        let (roots, _) = read_car(&diff).unwrap();
+        assert_eq!(roots, vec![cid_from_bytes(&root).unwrap()]);
+
+        // Exactly the blocks added since `since`, all part of the full export.
+        let diff = car_cids(&diff);
+        let expected: std::collections::HashSet<Cid> = full.difference(&before).copied().collect();
+        assert_eq!(diff, expected);
+        assert!(diff.len() < full.len());
+    }
+
+    #[tokio::test]
+    async fn diff_car_falls_back_to_full_export() {
+        let did = "did:plc:cardifffallback00000000000000";
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root) = build_repo(did, &key, 3).await;
+        let full = car_cids(&export_full_car(store.clone(), did, &root).await.unwrap());
+
+        // A CID that was never a commit in this repo.
+        let bogus = block(b"not a commit").0.to_bytes();
+        let diff = generate_diff_car(store.clone(), did, &root, Some(bogus.as_slice()))
+            .await
+            .unwrap();
+        assert_eq!(car_cids(&diff), full);
+
+        // A commit from an unrelated repo in the same store.
+        let (other_root, _) = crate::create_repo(store.clone(), did, &key).await.unwrap();
+        let diff = generate_diff_car(store.clone(), did, &root, Some(other_root.as_slice()))
+            .await
+            .unwrap();
+        assert_eq!(car_cids(&diff), full);
     }
 
-    drop(car_store);
+    #[tokio::test]
+    async fn record_proof_car_is_enough_to_read_the_record() {
+        let did = "did:plc:carrecordproof000000000000";
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root) = build_repo(did, &key, 40).await;
+        let records = crate::list_records(
+            store.clone(),
+            did,
+            "app.bsky.feed.post",
+            1,
+            (Unbounded, Unbounded),
+            false,
+            &root,
+        )
+        .await
+        .unwrap();
+        let rkey = records[0].uri.rsplit('/').next().unwrap();
 
-    Ok(car_buf)
+        let car = generate_record_proof_car(store.clone(), did, &root, "app.bsky.feed.post", rkey)
+            .await
+            .unwrap();
+        let (roots, blocks) = read_car(&car).unwrap();
+        assert_eq!(roots, vec![cid_from_bytes(&root).unwrap()]);
+        let full = car_cids(&export_full_car(store.clone(), did, &root).await.unwrap());
+        assert!(blocks.len() < full.len());
+
+        // The proof alone is enough to resolve the record.
+        let proof = Arc::new(MemRepoStore::default());
+        for (cid, data) in &blocks {
+            proof.put_block(did, &cid.to_bytes(), data).await.unwrap();
+        }
+        let found = crate::get_record(proof.clone(), did, "app.bsky.feed.post", rkey, &root)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.cid, records[0].cid);
+        assert_eq!(found.value, records[0].value);
+
+        // A missing record gets a proof of absence: the commit and tree path only.
+        let missing = generate_record_proof_car(store, did, &root, "app.bsky.feed.post", "zzzz")
+            .await
+            .unwrap();
+        let (_, absence) = read_car(&missing).unwrap();
+        let absent = Arc::new(MemRepoStore::default());
+        for (cid, data) in &absence {
+            absent.put_block(did, &cid.to_bytes(), data).await.unwrap();
+        }
+        let lookup = crate::get_record(absent, did, "app.bsky.feed.post", "zzzz", &root).await;
+        assert!(lookup.unwrap().is_none());
+    }
 }
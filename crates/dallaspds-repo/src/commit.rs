@@ -0,0 +1,67 @@
+//! Signed v3 repo commits: the encodings written for new commits and the one
+//! decoder used for every stored or imported commit block.
+
+use std::convert::Infallible;
+
+use atrium_repo::Cid;
+use dallaspds_core::error::{PdsError, PdsResult};
+use dallaspds_crypto::SigningKey;
+use serde::{Deserialize, Serialize};
+
+/// An unsigned v3 commit. Fields are declared in canonical DAG-CBOR key order.
+#[derive(Debug, Serialize)]
+pub(crate) struct UnsignedCommit<'a> {
+    pub(crate) did: &'a str,
+    pub(crate) rev: &'a str,
+    pub(crate) data: Cid,
+    pub(crate) prev: Option<Cid>,
+    pub(crate) version: i64,
+}
+
+/// A signed v3 commit. Fields are declared in canonical DAG-CBOR key order.
+#[derive(Debug, Serialize)]
+pub(crate) struct SignedCommit<'a> {
+    pub(crate) did: &'a str,
+    pub(crate) rev: &'a str,
+    #[serde(with = "serde_bytes")]
+    pub(crate) sig: &'a [u8],
+    pub(crate) data: Cid,
+    pub(crate) prev: Option<Cid>,
+    pub(crate) version: i64,
+}
+
+/// A signed commit decoded from a block.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Commit {
+    pub(crate) did: String,
+    pub(crate) rev: String,
+    #[serde(with = "serde_bytes")]
+    pub(crate) sig: Vec<u8>,
+    pub(crate) data: Cid,
+    pub(crate) prev: Option<Cid>,
+    pub(crate) version: i64,
+}
+
+impl Commit {
+    /// Decode a commit block. MST nodes and records fail to decode.
+    pub(crate) fn decode(block: &[u8]) -> Result<Self, serde_ipld_dagcbor::DecodeError<Infallible>> {
+        serde_ipld_dagcbor::from_slice(block)
+    }
+
+    /// `true` if this is a commit for `did` whose signature verifies against
+    /// `signing_key`.
+    pub(crate) fn is_signed_by(&self, did: &str, signing_key: &SigningKey) -> PdsResult<bool> {
+        if self.did != did {
+            return Ok(false);
+        }
+        let unsigned = serde_ipld_dagcbor::to_vec(&UnsignedCommit {
+            did: &self.did,
+            rev: &self.rev,
+            data: self.data,
+            prev: self.prev,
+            version: self.version,
+        })
+        .map_err(|e| PdsError::Storage(format!("failed to encode commit: {e}")))?;
+        Ok(signing_key.verify(&unsigned, &self.sig))
+    }
+}
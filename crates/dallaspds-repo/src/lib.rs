@@ -1,11 +1,26 @@
 pub mod blockstore_adapter;
 pub mod car;
+mod commit;
 pub mod operations;
+pub mod staged;
+pub mod verify;
+
+#[cfg(test)]
+mod mst_tests;
+#[cfg(test)]
+mod test_store;
 
 // Re-export key types for external consumers
 pub use blockstore_adapter::{RepoStoreAdapter, cid_from_bytes, cid_to_bytes};
-pub use car::{export_full_car, generate_diff_car};
+pub use car::{
+    CarBlock, ImportProgress, blocks_to_car, export_full_car, generate_diff_car,
+    generate_record_proof_car, import_car, import_migrated_car, prune_unreachable, read_car,
+    write_car,
+};
 pub use operations::{
-    RecordOutput, RecordWriteOutput, create_record, create_repo, delete_record, get_record,
-    list_records, put_record,
+    RecordDeleteOutput, RecordOutput, RecordWriteOutput, count_records, create_record, create_repo,
+    delete_record, get_commit_block, get_record, get_record_by_cid, is_valid_rkey,
+    list_all_records, list_blob_refs, list_record_cids, list_records, put_record,
 };
+pub use staged::StagedRepoStore;
+pub use verify::{RepoVerification, SignatureStatus, find_latest_commit, verify_repo};
@@ -0,0 +1,218 @@
+//! Large-tree tests for the repo write path.
+//!
+//! A few thousand keys give the MST several layers, so inserts and deletes
+//! split and merge nodes. The MST shape depends only on its keys, which lets
+//! these tests compare data roots of repos built in different ways.
+
+use std::ops::Bound::{Excluded, Unbounded};
+use std::sync::Arc;
+
+use atrium_repo::{Cid, Repository};
+use dallaspds_crypto::{SigningKey, TidGenerator};
+
+use crate::blockstore_adapter::{RepoStoreAdapter, cid_from_bytes};
+use crate::test_store::MemRepoStore;
+use crate::{
+    delete_record, export_full_car, get_record, import_car, list_records, put_record, read_car,
+};
+
+const COLLECTION: &str = "app.bsky.feed.post";
+const RECORD_COUNT: usize = 2000;
+
+/// Visit `0..RECORD_COUNT` in a scrambled but deterministic order.
+fn scrambled(i: usize) -> usize {
+    // 7919 is prime and coprime with RECORD_COUNT, so this is a permutation.
+    (i * 7919) % RECORD_COUNT
+}
+
+fn rkey(i: usize) -> String {
+    format!("rec{i:06}")
+}
+
+fn record(i: usize) -> serde_json::Value {
+    serde_json::json!({ "$type": COLLECTION, "text": format!("post {i}") })
+}
+
+struct TestRepo {
+    did: &'static str,
+    key: Arc<SigningKey>,
+    tid_gen: TidGenerator,
+    store: Arc<MemRepoStore>,
+    root: Vec<u8>,
+}
+
+impl TestRepo {
+    async fn new(did: &'static str) -> Self {
+        let key = Arc::new(SigningKey::generate_p256().unwrap());
+        let store = Arc::new(MemRepoStore::default());
+        let (root, _) = crate::create_repo(store.clone(), did, &key).await.unwrap();
+        Self {
+            did,
+            key,
+            tid_gen: TidGenerator::new(),
+            store,
+            root,
+        }
+    }
+
+    async fn put(&mut self, i: usize) {
+        let output = put_record(
+            self.store.clone(),
+            self.did,
+            &self.key,
+            COLLECTION,
+            &rkey(i),
+            &record(i),
+            &self.tid_gen,
+            &self.root,
+        )
+        .await
+        .unwrap();
+        self.root = output.new_root;
+    }
+
+    async fn delete(&mut self, i: usize) {
+        let output = delete_record(
+            self.store.clone(),
+            self.did,
+            &self.key,
+            COLLECTION,
+            &rkey(i),
+            &self.tid_gen,
+            &self.root,
+        )
+        .await
+        .unwrap();
+        self.root = output.new_root;
+    }
+
+    /// CID of the MST root referenced by the current commit.
+    async fn data_root(&self) -> Cid {
+        let mut adapter = RepoStoreAdapter::new(self.store.clone(), self.did.to_string());
+        let repo = Repository::open(&mut adapter, cid_from_bytes(&self.root).unwrap())
+            .await
+            .unwrap();
+        repo.commit().data()
+    }
+
+    /// Assert exactly `expected` (ascending) are present, both by direct
+    /// lookup and through paginated listing.
+    async fn assert_contents(&self, expected: &[usize]) {
+        for &i in expected {
+            let found = get_record(self.store.clone(), self.did, COLLECTION, &rkey(i), &self.root)
+                .await
+                .unwrap()
+                .unwrap_or_else(|| panic!("record {i} missing"));
+            assert_eq!(found.value, record(i));
+        }
+
+        let mut listed = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = list_records(
+                self.store.clone(),
+                self.did,
+                COLLECTION,
+                250,
+                (cursor.as_deref().map_or(Unbounded, Excluded), Unbounded),
+                false,
+                &self.root,
+            )
+            .await
+            .unwrap();
+            let Some(last) = page.last() else { break };
+            cursor = Some(last.uri.rsplit('/').next().unwrap().to_string());
+            listed.extend(page.into_iter().map(|r| r.uri));
+        }
+        let want: Vec<String> = expected
+            .iter()
+            .map(|&i| format!("at://{}/{COLLECTION}/{}", self.did, rkey(i)))
+            .collect();
+        assert_eq!(listed, want);
+    }
+
+    /// Export a CAR, decode it, and import it into a fresh store.
+    async fn assert_car_round_trip(&self, expected: &[usize]) {
+        let car = export_full_car(self.store.clone(), self.did, &self.root)
+            .await
+            .unwrap();
+        let (roots, blocks) = read_car(&car).unwrap();
+        assert_eq!(roots, vec![cid_from_bytes(&self.root).unwrap()]);
+        // One block per record plus the commit and MST nodes.
+        assert!(blocks.len() > expected.len() + 1);
+
+        let restored = Self {
+            did: self.did,
+            key: self.key.clone(),
+            tid_gen: TidGenerator::new(),
+            store: Arc::new(MemRepoStore::default()),
+            root: Vec::new(),
+        };
+        let root = import_car(restored.store.clone(), self.did, &car, &self.key)
+            .await
+            .unwrap()
+            .commit;
+        assert_eq!(root, self.root);
+        let restored = Self { root, ..restored };
+        assert_eq!(restored.data_root().await, self.data_root().await);
+        restored.assert_contents(expected).await;
+    }
+}
+
+#[tokio::test]
+async fn large_tree_insert_and_delete() {
+    let mut repo = TestRepo::new("did:plc:msttestlargetree00000000").await;
+    for i in 0..RECORD_COUNT {
+        repo.put(scrambled(i)).await;
+    }
+
+    let all: Vec<usize> = (0..RECORD_COUNT).collect();
+    repo.assert_contents(&all).await;
+    repo.assert_car_round_trip(&all).await;
+
+    // Rewriting a record with identical contents makes a new commit but
+    // leaves the tree untouched.
+    let data_root = repo.data_root().await;
+    let commit_root = repo.root.clone();
+    repo.put(RECORD_COUNT / 2).await;
+    assert_ne!(repo.root, commit_root);
+    assert_eq!(repo.data_root().await, data_root);
+
+    // Remove every even record, again in scrambled order, forcing merges.
+    for i in 0..RECORD_COUNT {
+        let n = scrambled(i);
+        if n.is_multiple_of(2) {
+            repo.delete(n).await;
+        }
+    }
+
+    let odd: Vec<usize> = (1..RECORD_COUNT).step_by(2).collect();
+    repo.assert_contents(&odd).await;
+    for i in (0..RECORD_COUNT).step_by(2) {
+        let gone = get_record(repo.store.clone(), repo.did, COLLECTION, &rkey(i), &repo.root)
+            .await
+            .unwrap();
+        assert!(gone.is_none(), "record {i} should be deleted");
+    }
+    repo.assert_car_round_trip(&odd).await;
+
+    // The shrunken tree matches one built directly from the survivors.
+    let mut fresh = TestRepo::new("did:plc:msttestlargetree00000000").await;
+    for &i in &odd {
+        fresh.put(i).await;
+    }
+    assert_eq!(repo.data_root().await, fresh.data_root().await);
+}
+
+#[tokio::test]
+async fn tree_shape_is_independent_of_insertion_order() {
+    let mut ascending = TestRepo::new("did:plc:msttestascending000000000").await;
+    let mut scrambled_repo = TestRepo::new("did:plc:msttestscrambled000000000").await;
+    for i in 0..RECORD_COUNT {
+        ascending.put(i).await;
+        scrambled_repo.put(scrambled(i)).await;
+    }
+
+    assert_eq!(ascending.data_root().await, scrambled_repo.data_root().await);
+}
+
@@ -1,14 +1,19 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
 use std::sync::Arc;
 
 use atrium_api::types::string::{Did, Tid};
-use atrium_repo::blockstore::AsyncBlockStoreRead;
+use atrium_repo::blockstore::{AsyncBlockStoreRead, SHA2_256};
 use atrium_repo::{Cid, Repository};
 use dallaspds_core::error::{PdsError, PdsResult};
 use dallaspds_core::traits::RepoStore;
 use dallaspds_crypto::{SigningKey, TidGenerator};
 use futures::TryStreamExt;
+use ipld_core::ipld::Ipld;
+use sha2::{Digest, Sha256};
 
 use crate::blockstore_adapter::{RepoStoreAdapter, cid_from_bytes, cid_to_bytes};
+use crate::commit::Commit;
 
 /// Output returned when a record is created, updated, or put.
 #[derive(Debug, Clone)]
@@ -21,6 +26,29 @@ pub struct RecordWriteOutput {
     pub new_rev: String,
 }
 
+impl RecordWriteOutput {
+    /// The MST path (`collection/rkey`) the record was written to, including
+    /// a server-generated rkey.
+    pub fn path(&self) -> &str {
+        // URI format: at://did/collection/rkey
+        self.uri
+            .strip_prefix("at://")
+            .and_then(|rest| rest.split_once('/'))
+            .map_or("", |(_, path)| path)
+    }
+}
+
+/// Output returned when a record is deleted.
+#[derive(Debug, Clone)]
+pub struct RecordDeleteOutput {
+    /// New repo root CID bytes after the delete.
+    pub new_root: Vec<u8>,
+    /// New rev string after the delete.
+    pub new_rev: String,
+    /// CID bytes of the record that was removed.
+    pub deleted_cid: Vec<u8>,
+}
+
 /// Output returned when reading a record.
 #[derive(Debug, Clone)]
 pub struct RecordOutput {
@@ -183,15 +211,132 @@ pub async fn get_record<R: RepoStore>(
     }
 }
 
+/// Read a specific version of a record by its CID.
+///
+/// The version must have been at `collection/rkey` in the repo's history:
+/// the `prev` chain is followed back from `current_root`, looking the path
+/// up in each commit's MST, until one maps it to `record_cid`. The record
+/// block is re-hashed and must match. Returns `None` if no commit in the
+/// chain has that version at the path, or the chain ends (or a block is
+/// missing) first.
+pub async fn get_record_by_cid<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    collection: &str,
+    rkey: &str,
+    record_cid: &Cid,
+    current_root: &[u8],
+) -> PdsResult<Option<RecordOutput>> {
+    let mut adapter = RepoStoreAdapter::new(store.clone(), did.to_string());
+    let mut commit_cid = cid_from_bytes(current_root)
+        .map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+    let mst_key = format!("{collection}/{rkey}");
+
+    loop {
+        let Some(commit_block) = store.get_block(did, &cid_to_bytes(&commit_cid)).await? else {
+            return Ok(None);
+        };
+        let found = {
+            let mut repo = Repository::open(&mut adapter, commit_cid)
+                .await
+                .map_err(|e| PdsError::Storage(format!("failed to open repo: {e}")))?;
+            let mut tree = repo.tree();
+            tree.get(&mst_key)
+                .await
+                .map_err(|e| PdsError::Storage(format!("failed to get record from MST: {e}")))?
+        };
+        if found.as_ref() == Some(record_cid) {
+            break;
+        }
+
+        let commit = Commit::decode(&commit_block)
+            .map_err(|e| PdsError::Storage(format!("failed to decode commit {commit_cid}: {e}")))?;
+        match commit.prev {
+            Some(prev) => commit_cid = prev,
+            None => return Ok(None),
+        }
+    }
+
+    let cid_bytes = cid_to_bytes(record_cid);
+    let Some(block_data) = store.get_block(did, &cid_bytes).await? else {
+        return Ok(None);
+    };
+    let hash = record_cid.hash();
+    if hash.code() != SHA2_256 || Sha256::digest(&block_data).as_slice() != hash.digest() {
+        return Err(PdsError::Storage(format!(
+            "stored block does not match CID {record_cid}"
+        )));
+    }
+
+    let value: serde_json::Value = serde_ipld_dagcbor::from_reader(&block_data[..])
+        .map_err(|e| PdsError::Storage(format!("failed to decode record: {e}")))?;
+
+    Ok(Some(RecordOutput {
+        uri: format!("at://{did}/{collection}/{rkey}"),
+        cid: cid_bytes,
+        value,
+    }))
+}
+
+/// Return the raw signed commit block for `commit_cid`, or for the current
+/// commit if `commit_cid` is `None`.
+///
+/// The commit must be in the repo's history: the `prev` chain is followed
+/// back from `current_root` until the CID is found. Returns `None` if the
+/// chain ends (or a block is missing) first.
+pub async fn get_commit_block<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    current_root: &[u8],
+    commit_cid: Option<&Cid>,
+) -> PdsResult<Option<(Cid, Vec<u8>)>> {
+    let mut cid = cid_from_bytes(current_root)
+        .map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+
+    loop {
+        let Some(block) = store.get_block(did, &cid_to_bytes(&cid)).await? else {
+            return Ok(None);
+        };
+        if commit_cid.is_none_or(|wanted| *wanted == cid) {
+            return Ok(Some((cid, block)));
+        }
+
+        let commit = Commit::decode(&block)
+            .map_err(|e| PdsError::Storage(format!("failed to decode commit {cid}: {e}")))?;
+        match commit.prev {
+            Some(prev) => cid = prev,
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Returns `true` if `rkey` is a syntactically valid record key.
+///
+/// Record keys are 1-512 characters from `A-Za-z0-9.-_:~`, excluding the
+/// special names `.` and `..`.
+pub fn is_valid_rkey(rkey: &str) -> bool {
+    !rkey.is_empty()
+        && rkey.len() <= 512
+        && rkey != "."
+        && rkey != ".."
+        && rkey
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b".-_:~".contains(&b))
+}
+
 /// List records in a given collection.
 ///
-/// Returns up to `limit` records, optionally starting after `cursor` (an rkey).
+/// Returns up to `limit` records whose rkeys fall within `rkeys`, in
+/// ascending rkey order, or descending with `reverse`. To page, exclude
+/// the last rkey returned: as the start bound going forwards, or as the end
+/// bound in reverse.
 pub async fn list_records<R: RepoStore>(
     store: Arc<R>,
     did: &str,
     collection: &str,
     limit: usize,
-    cursor: Option<&str>,
+    rkeys: (Bound<&str>, Bound<&str>),
+    reverse: bool,
     current_root: &[u8],
 ) -> PdsResult<Vec<RecordOutput>> {
     let mut adapter = RepoStoreAdapter::new(store, did.to_string());
@@ -217,16 +362,83 @@ pub async fn list_records<R: RepoStore>(
             .await
             .map_err(|e| PdsError::Storage(format!("failed to iterate MST: {e}")))?
         {
-            // Extract the rkey from the full MST key
-            let rkey = key.strip_prefix(&prefix).unwrap_or(&key);
+            // Extract the rkey from the full MST key. Only keys whose collection
+            // segment is exactly `collection` belong to this listing.
+            let Some(rkey) = key.strip_prefix(&prefix) else {
+                continue;
+            };
+            if rkey.is_empty() || rkey.contains('/') {
+                continue;
+            }
+            let before_start = match rkeys.0 {
+                Bound::Included(start) => rkey < start,
+                Bound::Excluded(start) => rkey <= start,
+                Bound::Unbounded => false,
+            };
+            if before_start {
+                continue;
+            }
+            let past_end = match rkeys.1 {
+                Bound::Included(end) => rkey > end,
+                Bound::Excluded(end) => rkey >= end,
+                Bound::Unbounded => false,
+            };
+            if past_end {
+                break;
+            }
 
-            // Apply cursor: skip entries until we pass the cursor rkey
-            if let Some(cursor_rkey) = cursor {
-                if rkey <= cursor_rkey {
-                    continue;
-                }
+            collected.push((key, cid));
+            // The MST only iterates forwards, so in reverse collect the whole
+            // range and take the tail.
+            if !reverse && collected.len() >= limit {
+                break;
             }
+        }
+        if reverse {
+            collected.reverse();
+            collected.truncate(limit);
+        }
+        collected
+    };
+    // repo and tree are now dropped, adapter is available again
 
+    read_record_entries(&mut adapter, did, entries).await
+}
+
+/// List records across every collection, in MST key order.
+///
+/// Returns up to `limit` records, starting after `cursor`, which is a full
+/// MST key (`collection/rkey`) so pagination is stable across collections.
+pub async fn list_all_records<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    limit: usize,
+    cursor: Option<&str>,
+    current_root: &[u8],
+) -> PdsResult<Vec<RecordOutput>> {
+    let mut adapter = RepoStoreAdapter::new(store, did.to_string());
+
+    let root_cid = cid_from_bytes(current_root)
+        .map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+
+    let entries: Vec<(String, Cid)> = {
+        let mut repo = Repository::open(&mut adapter, root_cid)
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to open repo: {e}")))?;
+
+        let mut tree = repo.tree();
+        let entries_stream = tree.entries();
+        futures::pin_mut!(entries_stream);
+
+        let mut collected = Vec::new();
+        while let Some((key, cid)) = entries_stream
+            .try_next()
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to iterate MST: {e}")))?
+        {
+            if cursor.is_some_and(|cursor_key| key.as_str() <= cursor_key) {
+                continue;
+            }
             collected.push((key, cid));
             if collected.len() >= limit {
                 break;
@@ -234,12 +446,127 @@ pub async fn list_records<R: RepoStore>(
         }
         collected
     };
-    // repo and tree are now dropped, adapter is available again
 
-    // Read each record block
+    read_record_entries(&mut adapter, did, entries).await
+}
+
+/// Count the records in a repository by walking every MST key.
+pub async fn count_records<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    current_root: &[u8],
+) -> PdsResult<usize> {
+    let mut adapter = RepoStoreAdapter::new(store, did.to_string());
+
+    let root_cid = cid_from_bytes(current_root)
+        .map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+    let mut repo = Repository::open(&mut adapter, root_cid)
+        .await
+        .map_err(|e| PdsError::Storage(format!("failed to open repo: {e}")))?;
+
+    let mut tree = repo.tree();
+    let keys = tree.keys();
+    futures::pin_mut!(keys);
+
+    let mut count = 0;
+    while keys
+        .try_next()
+        .await
+        .map_err(|e| PdsError::Storage(format!("failed to iterate MST: {e}")))?
+        .is_some()
+    {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Map every MST key (`collection/rkey`) in a repository to its record CID
+/// bytes, without reading the records themselves.
+pub async fn list_record_cids<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    current_root: &[u8],
+) -> PdsResult<BTreeMap<String, Vec<u8>>> {
+    let mut adapter = RepoStoreAdapter::new(store, did.to_string());
+
+    let root_cid = cid_from_bytes(current_root)
+        .map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+    let mut repo = Repository::open(&mut adapter, root_cid)
+        .await
+        .map_err(|e| PdsError::Storage(format!("failed to open repo: {e}")))?;
+
+    let mut tree = repo.tree();
+    let entries = tree.entries();
+    futures::pin_mut!(entries);
+
+    let mut cids = BTreeMap::new();
+    while let Some((key, cid)) = entries
+        .try_next()
+        .await
+        .map_err(|e| PdsError::Storage(format!("failed to iterate MST: {e}")))?
+    {
+        cids.insert(key, cid_to_bytes(&cid));
+    }
+    Ok(cids)
+}
+
+/// Collect the CIDs of every blob referenced by a record in a repository,
+/// sorted and without duplicates.
+///
+/// Accepts blob refs both as DAG-CBOR links, as written by other PDS
+/// implementations, and as `{"$link": ...}` maps.
+pub async fn list_blob_refs<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    current_root: &[u8],
+) -> PdsResult<Vec<String>> {
+    fn collect(value: &Ipld, cids: &mut Vec<String>) {
+        match value {
+            Ipld::Map(map) => {
+                if matches!(map.get("$type"), Some(Ipld::String(t)) if t == "blob") {
+                    match map.get("ref") {
+                        Some(Ipld::Link(cid)) => cids.push(cid.to_string()),
+                        Some(Ipld::Map(link)) => {
+                            if let Some(Ipld::String(cid)) = link.get("$link") {
+                                cids.push(cid.clone());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                map.values().for_each(|v| collect(v, cids));
+            }
+            Ipld::List(items) => items.iter().for_each(|v| collect(v, cids)),
+            _ => {}
+        }
+    }
+
+    let record_cids = list_record_cids(store.clone(), did, current_root).await?;
+    let mut adapter = RepoStoreAdapter::new(store, did.to_string());
+    let mut cids = Vec::new();
+    for cid in record_cids.values() {
+        let cid = cid_from_bytes(cid).map_err(|e| PdsError::Storage(format!("invalid CID: {e}")))?;
+        let block_data = adapter
+            .read_block(cid)
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to read record block: {e}")))?;
+        let value: Ipld = serde_ipld_dagcbor::from_slice(&block_data)
+            .map_err(|e| PdsError::Storage(format!("failed to decode record: {e}")))?;
+        collect(&value, &mut cids);
+    }
+    cids.sort();
+    cids.dedup();
+    Ok(cids)
+}
+
+/// Read and decode the record blocks for a set of MST entries.
+async fn read_record_entries<R: RepoStore>(
+    adapter: &mut RepoStoreAdapter<R>,
+    did: &str,
+    entries: Vec<(String, Cid)>,
+) -> PdsResult<Vec<RecordOutput>> {
     let mut results = Vec::with_capacity(entries.len());
     for (key, record_cid) in entries {
-        let rkey = key.strip_prefix(&prefix).unwrap_or(&key);
         let block_data = adapter
             .read_block(record_cid)
             .await
@@ -249,7 +576,7 @@ pub async fn list_records<R: RepoStore>(
             .map_err(|e| PdsError::Storage(format!("failed to decode record: {e}")))?;
 
         results.push(RecordOutput {
-            uri: format!("at://{did}/{collection}/{rkey}"),
+            uri: format!("at://{did}/{key}"),
             cid: cid_to_bytes(&record_cid),
             value,
         });
@@ -260,7 +587,8 @@ pub async fn list_records<R: RepoStore>(
 
 /// Delete a record from a repository.
 ///
-/// Returns the new root CID bytes and rev string for updating the repo root.
+/// Returns the new root and rev for updating the repo root, along with the
+/// CID of the deleted record.
 pub async fn delete_record<R: RepoStore>(
     store: Arc<R>,
     did: &str,
@@ -269,7 +597,7 @@ pub async fn delete_record<R: RepoStore>(
     rkey: &str,
     tid_gen: &TidGenerator,
     current_root: &[u8],
-) -> PdsResult<(Vec<u8>, String)> {
+) -> PdsResult<RecordDeleteOutput> {
     let mut adapter = RepoStoreAdapter::new(store, did.to_string());
 
     let root_cid = cid_from_bytes(current_root)
@@ -281,6 +609,15 @@ pub async fn delete_record<R: RepoStore>(
 
     let mst_key = format!("{collection}/{rkey}");
 
+    // Look up the record first so the caller can report what was deleted.
+    let deleted_cid = {
+        let mut tree = repo.tree();
+        tree.get(&mst_key)
+            .await
+            .map_err(|e| PdsError::Storage(format!("failed to look up record: {e}")))?
+            .ok_or_else(|| PdsError::NotFound(format!("record not found: {mst_key}")))?
+    };
+
     // Delete from MST
     let mut commit_builder = repo
         .delete_raw(&mst_key)
@@ -302,7 +639,11 @@ pub async fn delete_record<R: RepoStore>(
         .await
         .map_err(|e| PdsError::Storage(format!("failed to finalize commit: {e}")))?;
 
-    Ok((cid_to_bytes(&new_root_cid), rev_str))
+    Ok(RecordDeleteOutput {
+        new_root: cid_to_bytes(&new_root_cid),
+        new_rev: rev_str,
+        deleted_cid: cid_to_bytes(&deleted_cid),
+    })
 }
 
 /// Create or update a record at a specific rkey.
@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use dallaspds_core::error::PdsResult;
+use dallaspds_core::traits::RepoStore;
+
+/// A [`RepoStore`] that holds the blocks written to one repo in memory until
+/// [`commit`](Self::commit) stores them together with the new repo root.
+///
+/// Repo operations write blocks as they build the new MST and commit. Running
+/// them against a staged store means a write that fails part way, or whose
+/// root update fails, leaves nothing behind. Reads see staged blocks first,
+/// so several operations can be chained before a single commit.
+pub struct StagedRepoStore<R: RepoStore> {
+    store: Arc<R>,
+    did: String,
+    /// CID bytes -> block, for blocks of `did` not yet committed.
+    staged: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl<R: RepoStore> StagedRepoStore<R> {
+    pub fn new(store: Arc<R>, did: &str) -> Self {
+        Self {
+            store,
+            did: did.to_string(),
+            staged: Mutex::default(),
+        }
+    }
+
+    /// Store every staged block and move the repo root from `expected_root`
+    /// to `root` at `rev` in one transaction, as
+    /// [`RepoStore::commit_blocks`].
+    pub async fn commit(&self, expected_root: &[u8], root: &[u8], rev: &str) -> PdsResult<()> {
+        let blocks: Vec<(Vec<u8>, Vec<u8>)> = self.staged.lock().unwrap().drain().collect();
+        self.store
+            .commit_blocks(&self.did, &blocks, expected_root, root, rev)
+            .await
+    }
+
+    /// Total size of the blocks staged so far.
+    pub fn staged_bytes(&self) -> u64 {
+        let staged = self.staged.lock().unwrap();
+        staged.values().map(|block| block.len() as u64).sum()
+    }
+
+    fn staged_block(&self, did: &str, cid: &[u8]) -> Option<Vec<u8>> {
+        if did != self.did {
+            return None;
+        }
+        self.staged.lock().unwrap().get(cid).cloned()
+    }
+}
+
+#[async_trait]
+impl<R: RepoStore> RepoStore for StagedRepoStore<R> {
+    async fn get_block(&self, did: &str, cid: &[u8]) -> PdsResult<Option<Vec<u8>>> {
+        match self.staged_block(did, cid) {
+            Some(block) => Ok(Some(block)),
+            None => self.store.get_block(did, cid).await,
+        }
+    }
+
+    async fn put_block(&self, did: &str, cid: &[u8], block: &[u8]) -> PdsResult<()> {
+        if did != self.did {
+            return self.store.put_block(did, cid, block).await;
+        }
+        let mut staged = self.staged.lock().unwrap();
+        staged.entry(cid.to_vec()).or_insert_with(|| block.to_vec());
+        Ok(())
+    }
+
+    async fn put_blocks(&self, did: &str, blocks: &[(Vec<u8>, Vec<u8>)]) -> PdsResult<()> {
+        if did != self.did {
+            return self.store.put_blocks(did, blocks).await;
+        }
+        let mut staged = self.staged.lock().unwrap();
+        for (cid, block) in blocks {
+            staged.entry(cid.clone()).or_insert_with(|| block.clone());
+        }
+        Ok(())
+    }
+
+    async fn commit_blocks(
+        &self,
+        did: &str,
+        blocks: &[(Vec<u8>, Vec<u8>)],
+        expected_root: &[u8],
+        root: &[u8],
+        rev: &str,
+    ) -> PdsResult<()> {
+        if did != self.did {
+            return self
+                .store
+                .commit_blocks(did, blocks, expected_root, root, rev)
+                .await;
+        }
+        self.put_blocks(did, blocks).await?;
+        self.commit(expected_root, root, rev).await
+    }
+
+    async fn has_block(&self, did: &str, cid: &[u8]) -> PdsResult<bool> {
+        if self.staged_block(did, cid).is_some() {
+            return Ok(true);
+        }
+        self.store.has_block(did, cid).await
+    }
+
+    async fn get_all_blocks(&self, did: &str) -> PdsResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut blocks = self.store.get_all_blocks(did).await?;
+        if did == self.did {
+            let staged = self.staged.lock().unwrap();
+            blocks.extend(staged.iter().map(|(cid, block)| (cid.clone(), block.clone())));
+        }
+        Ok(blocks)
+    }
+
+    async fn get_storage_usage(&self, did: &str) -> PdsResult<(u64, u64)> {
+        let (mut count, mut bytes) = self.store.get_storage_usage(did).await?;
+        if did == self.did {
+            let staged = self.staged.lock().unwrap();
+            count += staged.len() as u64;
+            bytes += staged.values().map(|block| block.len() as u64).sum::<u64>();
+        }
+        Ok((count, bytes))
+    }
+
+    async fn delete_blocks_for_did(&self, did: &str) -> PdsResult<u64> {
+        if did == self.did {
+            self.staged.lock().unwrap().clear();
+        }
+        self.store.delete_blocks_for_did(did).await
+    }
+
+    async fn retain_blocks(&self, did: &str, keep: &HashSet<Vec<u8>>) -> PdsResult<u64> {
+        self.store.retain_blocks(did, keep).await
+    }
+
+    async fn add_blob_refs(&self, did: &str, cids: &[String], rev: &str) -> PdsResult<()> {
+        self.store.add_blob_refs(did, cids, rev).await
+    }
+
+    async fn list_blobs_since(
+        &self,
+        did: &str,
+        since: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<String>> {
+        self.store.list_blobs_since(did, since, cursor, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_store::MemRepoStore;
+
+    #[tokio::test]
+    async fn staged_blocks_are_readable_but_only_stored_on_commit() {
+        let store = Arc::new(MemRepoStore::default());
+        let staged = StagedRepoStore::new(store.clone(), "did:plc:alice");
+
+        staged.put_block("did:plc:alice", b"cid", b"block").await.unwrap();
+        assert_eq!(
+            staged.get_block("did:plc:alice", b"cid").await.unwrap(),
+            Some(b"block".to_vec())
+        );
+        assert!(!store.has_block("did:plc:alice", b"cid").await.unwrap());
+
+        staged.commit(b"", b"root", "rev1").await.unwrap();
+        assert!(store.has_block("did:plc:alice", b"cid").await.unwrap());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use dallaspds_core::error::PdsResult;
+use dallaspds_core::traits::RepoStore;
+
+/// (did, CID bytes)
+type BlockKey = (String, Vec<u8>);
+
+/// Minimal in-memory `RepoStore` for exercising repo operations in tests.
+#[derive(Default)]
+pub(crate) struct MemRepoStore {
+    blocks: Mutex<HashMap<BlockKey, Vec<u8>>>,
+    /// (did, blob CID) -> rev that first referenced it
+    blob_refs: Mutex<HashMap<(String, String), String>>,
+}
+
+#[async_trait]
+impl RepoStore for MemRepoStore {
+    async fn get_block(&self, did: &str, cid: &[u8]) -> PdsResult<Option<Vec<u8>>> {
+        let blocks = self.blocks.lock().unwrap();
+        Ok(blocks.get(&(did.to_string(), cid.to_vec())).cloned())
+    }
+
+    async fn put_block(&self, did: &str, cid: &[u8], block: &[u8]) -> PdsResult<()> {
+        let mut blocks = self.blocks.lock().unwrap();
+        blocks.insert((did.to_string(), cid.to_vec()), block.to_vec());
+        Ok(())
+    }
+
+    async fn put_blocks(&self, did: &str, batch: &[(Vec<u8>, Vec<u8>)]) -> PdsResult<()> {
+        let mut blocks = self.blocks.lock().unwrap();
+        for (cid, block) in batch {
+            blocks.insert((did.to_string(), cid.clone()), block.clone());
+        }
+        Ok(())
+    }
+
+    async fn commit_blocks(
+        &self,
+        did: &str,
+        batch: &[(Vec<u8>, Vec<u8>)],
+        _expected_root: &[u8],
+        _root: &[u8],
+        _rev: &str,
+    ) -> PdsResult<()> {
+        // Repo roots live with the accounts, which this store doesn't model.
+        self.put_blocks(did, batch).await
+    }
+
+    async fn has_block(&self, did: &str, cid: &[u8]) -> PdsResult<bool> {
+        let blocks = self.blocks.lock().unwrap();
+        Ok(blocks.contains_key(&(did.to_string(), cid.to_vec())))
+    }
+
+    async fn get_all_blocks(&self, did: &str) -> PdsResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let blocks = self.blocks.lock().unwrap();
+        Ok(blocks
+            .iter()
+            .filter(|((d, _), _)| d == did)
+            .map(|((_, cid), data)| (cid.clone(), data.clone()))
+            .collect())
+    }
+
+    async fn delete_blocks_for_did(&self, did: &str) -> PdsResult<u64> {
+        let mut blocks = self.blocks.lock().unwrap();
+        let before = blocks.len();
+        blocks.retain(|(d, _), _| d != did);
+        self.blob_refs.lock().unwrap().retain(|(d, _), _| d != did);
+        Ok((before - blocks.len()) as u64)
+    }
+
+    async fn retain_blocks(&self, did: &str, keep: &HashSet<Vec<u8>>) -> PdsResult<u64> {
+        let mut blocks = self.blocks.lock().unwrap();
+        let before = blocks.len();
+        blocks.retain(|(d, cid), _| d != did || keep.contains(cid));
+        Ok((before - blocks.len()) as u64)
+    }
+
+    async fn add_blob_refs(&self, did: &str, cids: &[String], rev: &str) -> PdsResult<()> {
+        let mut blob_refs = self.blob_refs.lock().unwrap();
+        for cid in cids {
+            blob_refs
+                .entry((did.to_string(), cid.clone()))
+                .or_insert_with(|| rev.to_string());
+        }
+        Ok(())
+    }
+
+    async fn list_blobs_since(
+        &self,
+        did: &str,
+        since: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<String>> {
+        let blob_refs = self.blob_refs.lock().unwrap();
+        let mut cids: Vec<String> = blob_refs
+            .iter()
+            .filter(|((d, cid), rev)| {
+                d == did && rev.as_str() > since && cursor.is_none_or(|c| cid.as_str() > c)
+            })
+            .map(|((_, cid), _)| cid.clone())
+            .collect();
+        cids.sort();
+        cids.truncate(limit);
+        Ok(cids)
+    }
+}
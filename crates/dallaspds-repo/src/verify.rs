@@ -0,0 +1,280 @@
+//! Integrity check of a stored repo, for operators recovering from bugs or
+//! partial writes.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use atrium_repo::Cid;
+use dallaspds_core::error::{PdsError, PdsResult};
+use dallaspds_core::traits::RepoStore;
+use dallaspds_crypto::SigningKey;
+use ipld_core::ipld::Ipld;
+use serde::{Deserialize, Serialize};
+
+use crate::blockstore_adapter::cid_from_bytes;
+use crate::commit::Commit;
+
+/// Outcome of checking the root commit's signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    Valid,
+    /// The signature does not verify against the key, or the commit names
+    /// another DID.
+    Invalid,
+    /// The commit block is missing or does not decode.
+    Unverifiable,
+}
+
+/// What [`verify_repo`] found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoVerification {
+    /// Distinct blocks read from the store.
+    pub blocks_checked: usize,
+    /// CIDs referenced from the repo but absent from the store.
+    pub missing: Vec<String>,
+    /// CIDs present in the store whose block failed to decode.
+    pub undecodable: Vec<String>,
+    pub signature: SignatureStatus,
+}
+
+impl RepoVerification {
+    /// `true` if every block is present and decodes and the commit is
+    /// validly signed.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.undecodable.is_empty()
+            && self.signature == SignatureStatus::Valid
+    }
+}
+
+/// An MST node, reduced to the links it holds.
+#[derive(Debug, Deserialize)]
+struct MstNode {
+    l: Option<Cid>,
+    e: Vec<MstEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MstEntry {
+    v: Cid,
+    t: Option<Cid>,
+}
+
+/// A block still to be checked, and what it should decode as.
+enum Pending {
+    Node(Cid),
+    Record(Cid),
+}
+
+/// Check the repo at `root`: every block reachable from the commit must be
+/// in the store and decode, and the commit must be signed by `signing_key`.
+///
+/// Unlike opening the repo, the walk doesn't stop at the first problem; it
+/// reports every dangling or corrupt CID it can reach. Only store errors
+/// abort it.
+pub async fn verify_repo<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    root: &[u8],
+    signing_key: &SigningKey,
+) -> PdsResult<RepoVerification> {
+    let root_cid =
+        cid_from_bytes(root).map_err(|e| PdsError::Storage(format!("invalid root CID: {e}")))?;
+    let mut report = RepoVerification {
+        blocks_checked: 0,
+        missing: Vec::new(),
+        undecodable: Vec::new(),
+        signature: SignatureStatus::Unverifiable,
+    };
+
+    let Some(block) = store.get_block(did, root).await? else {
+        report.missing.push(root_cid.to_string());
+        return Ok(report);
+    };
+    report.blocks_checked += 1;
+    let commit = match Commit::decode(&block) {
+        Ok(commit) => commit,
+        Err(_) => {
+            report.undecodable.push(root_cid.to_string());
+            return Ok(report);
+        }
+    };
+
+    report.signature = if commit.is_signed_by(did, signing_key)? {
+        SignatureStatus::Valid
+    } else {
+        SignatureStatus::Invalid
+    };
+
+    // Breadth-first over the MST; records are leaves.
+    let mut seen = HashSet::from([root_cid]);
+    let mut queue = VecDeque::from([Pending::Node(commit.data)]);
+    seen.insert(commit.data);
+    while let Some(pending) = queue.pop_front() {
+        let cid = match pending {
+            Pending::Node(cid) | Pending::Record(cid) => cid,
+        };
+        let Some(block) = store.get_block(did, &cid.to_bytes()).await? else {
+            report.missing.push(cid.to_string());
+            continue;
+        };
+        report.blocks_checked += 1;
+
+        match pending {
+            Pending::Node(_) => match serde_ipld_dagcbor::from_slice::<MstNode>(&block) {
+                Ok(node) => {
+                    let subtrees = node.l.into_iter().chain(node.e.iter().filter_map(|e| e.t));
+                    for subtree in subtrees {
+                        if seen.insert(subtree) {
+                            queue.push_back(Pending::Node(subtree));
+                        }
+                    }
+                    for entry in &node.e {
+                        if seen.insert(entry.v) {
+                            queue.push_back(Pending::Record(entry.v));
+                        }
+                    }
+                }
+                Err(_) => report.undecodable.push(cid.to_string()),
+            },
+            Pending::Record(_) => {
+                if serde_ipld_dagcbor::from_slice::<Ipld>(&block).is_err() {
+                    report.undecodable.push(cid.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Find the newest commit for `did` in the store: the one with the highest
+/// rev among blocks that decode as commits for `did`, are signed by
+/// `signing_key`, and whose MST root block is present.
+///
+/// This recovers the repo root when it was lost but the blocks survive.
+/// Returns the commit's CID bytes and rev, or `None` if no commit qualifies.
+pub async fn find_latest_commit<R: RepoStore>(
+    store: Arc<R>,
+    did: &str,
+    signing_key: &SigningKey,
+) -> PdsResult<Option<(Vec<u8>, String)>> {
+    let mut latest: Option<(Vec<u8>, String)> = None;
+    for (cid, block) in store.get_all_blocks(did).await? {
+        // Most blocks are MST nodes or records, which don't decode as commits.
+        let Ok(commit) = Commit::decode(&block) else {
+            continue;
+        };
+        if latest.as_ref().is_some_and(|(_, rev)| *rev >= commit.rev) {
+            continue;
+        }
+        if !commit.is_signed_by(did, signing_key)? {
+            tracing::warn!(did, rev = %commit.rev, "skipping commit with an invalid signature");
+            continue;
+        }
+        if !store.has_block(did, &commit.data.to_bytes()).await? {
+            tracing::warn!(did, rev = %commit.rev, "skipping commit whose MST root is missing");
+            continue;
+        }
+        latest = Some((cid, commit.rev));
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use dallaspds_crypto::TidGenerator;
+
+    use super::*;
+    use crate::test_store::MemRepoStore;
+
+    const DID: &str = "did:plc:verifytest00000000000000";
+
+    async fn build_repo(key: &SigningKey) -> (Arc<MemRepoStore>, Vec<u8>, Vec<u8>) {
+        let store = Arc::new(MemRepoStore::default());
+        let tid_gen = TidGenerator::new();
+        let (mut root, _) = crate::create_repo(store.clone(), DID, key).await.unwrap();
+        let mut record_cid = Vec::new();
+        for i in 0..20 {
+            let record = serde_json::json!({ "$type": "app.bsky.feed.post", "text": format!("post {i}") });
+            let output = crate::create_record(
+                store.clone(),
+                DID,
+                key,
+                "app.bsky.feed.post",
+                None,
+                &record,
+                &tid_gen,
+                &root,
+            )
+            .await
+            .unwrap();
+            root = output.new_root;
+            record_cid = output.cid;
+        }
+        (store, root, record_cid)
+    }
+
+    #[tokio::test]
+    async fn intact_repo_verifies() {
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root, _) = build_repo(&key).await;
+        crate::prune_unreachable(store.clone(), DID, &root).await.unwrap();
+
+        let report = verify_repo(store.clone(), DID, &root, &key).await.unwrap();
+        assert!(report.is_ok(), "{report:?}");
+        assert_eq!(report.blocks_checked, store.get_all_blocks(DID).await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn reports_missing_blocks_and_bad_signature() {
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root, record_cid) = build_repo(&key).await;
+
+        let keep: HashSet<Vec<u8>> = store
+            .get_all_blocks(DID)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(cid, _)| cid)
+            .filter(|cid| *cid != record_cid)
+            .collect();
+        store.retain_blocks(DID, &keep).await.unwrap();
+
+        let other_key = SigningKey::generate_p256().unwrap();
+        let report = verify_repo(store, DID, &root, &other_key).await.unwrap();
+        let missing = cid_from_bytes(&record_cid).unwrap().to_string();
+        assert_eq!(report.missing, [missing]);
+        assert!(report.undecodable.is_empty());
+        assert_eq!(report.signature, SignatureStatus::Invalid);
+        assert!(!report.is_ok());
+    }
+
+    #[tokio::test]
+    async fn missing_commit_is_unverifiable() {
+        let key = SigningKey::generate_p256().unwrap();
+        let store = Arc::new(MemRepoStore::default());
+        let (root, _) = crate::create_repo(store.clone(), DID, &key).await.unwrap();
+        store.delete_blocks_for_did(DID).await.unwrap();
+
+        let report = verify_repo(store, DID, &root, &key).await.unwrap();
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.signature, SignatureStatus::Unverifiable);
+    }
+
+    #[tokio::test]
+    async fn finds_latest_signed_commit() {
+        let key = SigningKey::generate_p256().unwrap();
+        let (store, root, _) = build_repo(&key).await;
+
+        let (cid, rev) = find_latest_commit(store.clone(), DID, &key).await.unwrap().unwrap();
+        assert_eq!(cid, root);
+        assert!(!rev.is_empty());
+
+        // Commits signed by another key don't count.
+        let other_key = SigningKey::generate_p256().unwrap();
+        assert!(find_latest_commit(store, DID, &other_key).await.unwrap().is_none());
+    }
+}
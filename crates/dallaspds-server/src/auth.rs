@@ -1,13 +1,23 @@
 use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
 use axum::Extension;
+use dallaspds_core::{PdsError, PdsResult};
+use dallaspds_crypto::AccessTokenKeys;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use crate::error::XrpcError;
+use crate::proxy::service_auth::base64url_encode;
+use crate::state::AppState;
+use dallaspds_core::traits::{AccountStore, BlobStore, RepoStore};
 
-/// A newtype wrapper around the JWT access secret, added as an Axum Extension.
+/// A newtype wrapper around the access token keys, added as an Axum Extension.
 #[derive(Clone)]
-pub struct JwtSecret(pub String);
+pub struct JwtAccessKeys(pub Arc<AccessTokenKeys>);
 
 /// A newtype wrapper around the JWT refresh secret, added as an Axum Extension.
 #[derive(Clone)]
@@ -31,7 +41,7 @@ where
     type Rejection = XrpcError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let Extension(jwt_secret) = Extension::<JwtSecret>::from_request_parts(parts, state)
+        let Extension(access_keys) = Extension::<JwtAccessKeys>::from_request_parts(parts, state)
             .await
             .map_err(|_| {
                 XrpcError::new(
@@ -40,43 +50,83 @@ where
                     "JWT secret not configured",
                 )
             })?;
+        let dpop = parts.extensions.get::<Dpop>().cloned();
 
-        let auth_header = parts
-            .headers
-            .get("authorization")
-            .and_then(|v| v.to_str().ok())
-            .ok_or_else(|| {
-                XrpcError::new(
-                    StatusCode::UNAUTHORIZED,
-                    "AuthenticationRequired",
-                    "Missing authorization header",
-                )
-            })?;
+        authenticate(
+            &parts.headers,
+            &parts.method,
+            &parts.uri,
+            &access_keys.0,
+            dpop.as_ref(),
+        )
+    }
+}
 
-        let token = auth_header.strip_prefix("Bearer ").ok_or_else(|| {
+/// Authenticate a request from its `Authorization` header.
+///
+/// Plain access tokens are presented as `Bearer`. DPoP-bound tokens (issued
+/// by the OAuth token endpoint) must be presented as `DPoP` together with a
+/// proof, signed by the bound key, in the `DPoP` header.
+pub(crate) fn authenticate(
+    headers: &HeaderMap,
+    method: &Method,
+    uri: &Uri,
+    access_keys: &AccessTokenKeys,
+    dpop: Option<&Dpop>,
+) -> Result<AuthenticatedUser, XrpcError> {
+    let auth_header = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| {
             XrpcError::new(
                 StatusCode::UNAUTHORIZED,
                 "AuthenticationRequired",
-                "Invalid authorization format",
+                "Missing authorization header",
             )
         })?;
 
-        let claims = dallaspds_crypto::jwt::validate_access_token(token, &jwt_secret.0)
-            .map_err(|e| {
-                let err_msg = e.to_string();
-                if err_msg.contains("ExpiredSignature") {
-                    XrpcError::new(
-                        StatusCode::UNAUTHORIZED,
-                        "ExpiredToken",
-                        "Token has expired",
-                    )
-                } else {
-                    XrpcError::new(StatusCode::UNAUTHORIZED, "InvalidToken", "Invalid token")
-                }
-            })?;
+    let (token, dpop_scheme) = if let Some(token) = auth_header.strip_prefix("Bearer ") {
+        (token, false)
+    } else if let Some(token) = auth_header.strip_prefix("DPoP ") {
+        (token, true)
+    } else {
+        return Err(XrpcError::new(
+            StatusCode::UNAUTHORIZED,
+            "AuthenticationRequired",
+            "Invalid authorization format",
+        ));
+    };
+
+    let claims = dallaspds_crypto::jwt::validate_access_token(token, access_keys).map_err(|e| {
+        let err_msg = e.to_string();
+        if err_msg.contains("ExpiredSignature") {
+            XrpcError::new(StatusCode::UNAUTHORIZED, "ExpiredToken", "Token has expired")
+        } else {
+            XrpcError::new(StatusCode::UNAUTHORIZED, "InvalidToken", "Invalid token")
+        }
+    })?;
 
-        Ok(AuthenticatedUser { did: claims.sub })
+    let invalid = |message: &str| XrpcError::new(StatusCode::UNAUTHORIZED, "InvalidToken", message);
+    match (claims.cnf, dpop_scheme) {
+        (None, false) => {}
+        (None, true) => return Err(invalid("Token is not DPoP-bound")),
+        (Some(_), false) => return Err(invalid("DPoP-bound token must use the DPoP scheme")),
+        (Some(cnf), true) => {
+            let dpop = dpop.ok_or_else(|| invalid("DPoP is not configured"))?;
+            let proof = headers
+                .get("dpop")
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| invalid("Missing DPoP proof"))?;
+            let url = format!("{}{}", dpop.public_url.trim_end_matches('/'), uri.path());
+            let jkt = validate_dpop(proof, method.as_str(), &url, Some(token), &dpop.replay)
+                .map_err(|e| invalid(&e.to_string()))?;
+            if jkt != cnf.jkt {
+                return Err(invalid("DPoP proof is not signed by the token's key"));
+            }
+        }
     }
+
+    Ok(AuthenticatedUser { did: claims.sub })
 }
 
 impl<S> FromRequestParts<S> for OptionalAuth
@@ -148,3 +198,165 @@ where
         Ok(AdminAuth { did: user.did })
     }
 }
+
+/// Authenticated user allowed to change their repo. With
+/// `require_email_confirmed_for_writes` set, the account's email must be
+/// confirmed.
+#[derive(Debug, Clone)]
+pub struct WriterAuth {
+    pub did: String,
+}
+
+impl<A, R, B> FromRequestParts<AppState<A, R, B>> for WriterAuth
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    type Rejection = XrpcError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<A, R, B>,
+    ) -> Result<Self, Self::Rejection> {
+        let user = AuthenticatedUser::from_request_parts(parts, state).await?;
+
+        if state.config.require_email_confirmed_for_writes {
+            let account = state
+                .account_store
+                .get_account_by_did(&user.did)
+                .await?
+                .ok_or(PdsError::AccountNotFound)?;
+            if account.email_confirmed_at.is_none() {
+                return Err(XrpcError::new(
+                    StatusCode::FORBIDDEN,
+                    "EmailNotConfirmed",
+                    "Confirm your email address before writing to your repo",
+                ));
+            }
+        }
+
+        Ok(WriterAuth { did: user.did })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DPoP (RFC 9449)
+// ---------------------------------------------------------------------------
+
+/// How far a DPoP proof's `iat` may be from the server's clock.
+const DPOP_IAT_WINDOW_SECS: i64 = 60;
+
+/// Used proof `jti`s are swept once the cache grows past this many entries.
+const DPOP_SWEEP_THRESHOLD: usize = 10_000;
+
+/// DPoP settings, added as an Axum Extension.
+#[derive(Clone, Default)]
+pub struct Dpop {
+    /// Public URL of this server, which proofs' `htu` is checked against.
+    pub public_url: String,
+    pub replay: DpopReplayCache,
+}
+
+/// The `jti`s of recently accepted DPoP proofs, so that a captured proof
+/// can't be replayed within its validity window.
+#[derive(Clone, Default)]
+pub struct DpopReplayCache(Arc<Mutex<HashMap<String, i64>>>);
+
+impl DpopReplayCache {
+    /// Record `jti`, returning false if it was already used. Entries are
+    /// kept until the proof they came from can no longer be accepted.
+    fn insert(&self, jti: &str, iat: i64) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        let mut seen = self.0.lock().unwrap();
+        if seen.len() >= DPOP_SWEEP_THRESHOLD {
+            seen.retain(|_, expires| *expires > now);
+        }
+        match seen.get(jti) {
+            Some(expires) if *expires > now => false,
+            _ => {
+                seen.insert(jti.to_string(), iat + DPOP_IAT_WINDOW_SECS);
+                true
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DpopClaims {
+    jti: String,
+    htm: String,
+    htu: String,
+    iat: i64,
+    #[serde(default)]
+    ath: Option<String>,
+}
+
+/// Validate a DPoP proof JWT and return the JWK thumbprint of its key.
+///
+/// The proof must be an ES256 `dpop+jwt` signed by the JWK in its header,
+/// issued for `method` and `url` (ignoring any query or fragment) within
+/// the last minute, and not seen before. When `access_token` is given, the
+/// proof's `ath` must be the hash of that token.
+pub fn validate_dpop(
+    proof: &str,
+    method: &str,
+    url: &str,
+    access_token: Option<&str>,
+    replay: &DpopReplayCache,
+) -> PdsResult<String> {
+    let invalid = |message: &str| PdsError::Auth(format!("invalid DPoP proof: {message}"));
+
+    let header = jsonwebtoken::decode_header(proof).map_err(|e| invalid(&e.to_string()))?;
+    if header.typ.as_deref() != Some("dpop+jwt") {
+        return Err(invalid("typ must be dpop+jwt"));
+    }
+    if header.alg != jsonwebtoken::Algorithm::ES256 {
+        return Err(invalid("only ES256 is supported"));
+    }
+    let jwk = header.jwk.ok_or_else(|| invalid("missing jwk header"))?;
+    let AlgorithmParameters::EllipticCurve(ref params) = jwk.algorithm else {
+        return Err(invalid("jwk must be an EC key"));
+    };
+    if params.curve != EllipticCurve::P256 {
+        return Err(invalid("jwk must be a P-256 key"));
+    }
+
+    let key = jsonwebtoken::DecodingKey::from_jwk(&jwk).map_err(|e| invalid(&e.to_string()))?;
+    let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::ES256);
+    validation.validate_exp = false;
+    validation.required_spec_claims.clear();
+    let claims = jsonwebtoken::decode::<DpopClaims>(proof, &key, &validation)
+        .map_err(|e| invalid(&e.to_string()))?
+        .claims;
+
+    if claims.htm != method {
+        return Err(invalid("htm does not match the request method"));
+    }
+    if strip_query(&claims.htu) != strip_query(url) {
+        return Err(invalid("htu does not match the request URL"));
+    }
+    if (chrono::Utc::now().timestamp() - claims.iat).abs() > DPOP_IAT_WINDOW_SECS {
+        return Err(invalid("iat is too far from the current time"));
+    }
+    if let Some(token) = access_token {
+        let ath = base64url_encode(&Sha256::digest(token.as_bytes()));
+        if claims.ath.as_deref() != Some(ath.as_str()) {
+            return Err(invalid("ath does not match the access token"));
+        }
+    }
+    if !replay.insert(&claims.jti, claims.iat) {
+        return Err(invalid("jti has already been used"));
+    }
+
+    // JWK thumbprint (RFC 7638): the required members in lexical order.
+    let thumbprint_input = format!(
+        r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+        params.x, params.y
+    );
+    Ok(base64url_encode(&Sha256::digest(thumbprint_input.as_bytes())))
+}
+
+fn strip_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use dallaspds_core::PdsResult;
+use dallaspds_core::config::{EmailConfig, FirehoseConfig};
+use dallaspds_core::traits::{AccountStore, BlobStore, EventStore, RepoStore};
+
+use crate::firehose::retention::{SubscriberCursors, prune_events};
+use crate::state::AppState;
+
+/// How often expired refresh tokens are swept.
+pub const REFRESH_TOKEN_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often expired email tokens are swept.
+pub const EMAIL_TOKEN_CLEANUP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How often accounts past their deletion grace period are purged.
+pub const ACCOUNT_DELETION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Spawn a background task that periodically deletes expired refresh tokens
+/// and OAuth authorization requests.
+///
+/// Rotated tokens are kept (with `next_id` set) so that replays can be
+/// detected; once they pass their expiry they can no longer be presented and
+/// are safe to remove.
+pub fn spawn_refresh_token_cleanup<A: AccountStore>(
+    account_store: Arc<A>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match account_store
+                .delete_expired_refresh_tokens(chrono::Utc::now())
+                .await
+            {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Deleted {n} expired refresh tokens"),
+                Err(e) => tracing::warn!("Failed to delete expired refresh tokens: {e}"),
+            }
+            match account_store
+                .delete_expired_oauth_requests(chrono::Utc::now())
+                .await
+            {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Deleted {n} expired OAuth requests"),
+                Err(e) => tracing::warn!("Failed to delete expired OAuth requests: {e}"),
+            }
+        }
+    })
+}
+
+/// Spawn a background task that periodically deletes email tokens past
+/// the lifetime configured for their purpose.
+///
+/// Handlers already refuse expired tokens; sweeping them keeps the table
+/// small and means a stale token no longer exists to be presented at all.
+pub fn spawn_email_token_cleanup<A: AccountStore>(
+    account_store: Arc<A>,
+    config: EmailConfig,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match account_store
+                .delete_expired_email_tokens(chrono::Utc::now(), &config)
+                .await
+            {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Deleted {n} expired email tokens"),
+                Err(e) => tracing::warn!("Failed to delete expired email tokens: {e}"),
+            }
+        }
+    })
+}
+
+/// Spawn a background task that prunes persisted firehose events outside the
+/// retention window every `config.prune_interval_secs`.
+///
+/// Returns `None` without spawning when no retention limit is configured.
+pub fn spawn_event_pruning(
+    event_store: Arc<dyn EventStore>,
+    config: FirehoseConfig,
+    cursors: Arc<SubscriberCursors>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if config.retention_days.is_none() && config.retention_events.is_none() {
+        return None;
+    }
+    Some(tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(Duration::from_secs(config.prune_interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            match prune_events(event_store.as_ref(), &config, &cursors).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Pruned {n} firehose events"),
+                Err(e) => tracing::warn!("Failed to prune firehose events: {e}"),
+            }
+        }
+    }))
+}
+
+/// Spawn a background task that purges accounts whose deletion grace period
+/// has ended, every `interval`. Runs are skipped while read-only mode is on.
+pub fn spawn_scheduled_account_deletion<A, R, B>(
+    state: AppState<A, R, B>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if state.read_only.is_enabled() {
+                continue;
+            }
+            match purge_scheduled_deletions(&state).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Purged {n} accounts scheduled for deletion"),
+                Err(e) => tracing::warn!("Failed to purge accounts scheduled for deletion: {e}"),
+            }
+        }
+    })
+}
+
+/// How long a claimed account deletion is held before another run may
+/// retry it, in case the purge fails or the process stops partway.
+const DELETION_CLAIM_LEASE: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Purge every account whose `delete_after` has passed, returning how many
+/// were deleted. An account that fails to claim or purge is logged and
+/// skipped.
+///
+/// Each deletion is claimed before purging, so an account whose deletion was
+/// cancelled in the meantime is left alone, and instances sharing a database
+/// don't purge the same account at once. The claim only pushes `delete_after`
+/// back by [`DELETION_CLAIM_LEASE`], so a purge that doesn't finish is
+/// retried once the lease runs out.
+pub async fn purge_scheduled_deletions<A, R, B>(state: &AppState<A, R, B>) -> PdsResult<usize>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let now = chrono::Utc::now();
+    let due = state.account_store.list_accounts_due_for_deletion(now).await?;
+    let mut purged = 0;
+    for did in due {
+        match state
+            .account_store
+            .claim_scheduled_deletion(&did, now, now + DELETION_CLAIM_LEASE)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::warn!(did, "Failed to claim scheduled account deletion: {e}");
+                continue;
+            }
+        }
+        match crate::routes::admin::purge_account(state, &did).await {
+            Ok(()) => purged += 1,
+            Err(e) => tracing::warn!(did, "Failed to purge account, will retry: {e}"),
+        }
+    }
+    Ok(purged)
+}
@@ -0,0 +1,45 @@
+use dallaspds_core::types::ActorAccount;
+use serde_json::{Value, json};
+
+/// The `did:web` identity of this PDS's hostname, used by the account of a
+/// single-user PDS with `did_web` set.
+pub fn hostname_did_web(hostname: &str) -> String {
+    // A port separator has to be percent-encoded in a did:web.
+    format!("did:web:{}", hostname.replace(':', "%3A"))
+}
+
+/// The DID document for an account hosted here, built from its stored handle
+/// and signing key, with this PDS as its `#atproto_pds` service.
+///
+/// The `verificationMethod` is left out if the account has no usable
+/// signing key.
+pub fn did_document(account: &ActorAccount, public_url: &str) -> Value {
+    let did = &account.did;
+    let mut doc = json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/multikey/v1",
+            "https://w3id.org/security/suites/secp256k1-2019/v1"
+        ],
+        "id": did,
+        "alsoKnownAs": account.handle.iter().map(|handle| format!("at://{handle}")).collect::<Vec<_>>(),
+        "service": [{
+            "id": "#atproto_pds",
+            "type": "AtprotoPersonalDataServer",
+            "serviceEndpoint": public_url,
+        }]
+    });
+
+    let key = dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key);
+    if let Ok(key) = key
+        && let Some(multibase) = key.did_key().strip_prefix("did:key:")
+    {
+        doc["verificationMethod"] = json!([{
+            "id": format!("{did}#atproto"),
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": multibase,
+        }]);
+    }
+    doc
+}
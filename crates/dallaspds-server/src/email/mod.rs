@@ -0,0 +1,277 @@
+pub mod templates;
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use dallaspds_core::config::{EmailBackend, EmailConfig, PdsConfig, SmtpConfig, SmtpSecurity};
+use dallaspds_core::{PdsError, PdsResult};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+};
+
+use self::templates::{EmailTemplates, RenderedEmail, TemplateVars};
+
+/// How many emails the log backend keeps for [`EmailSender::outbox`].
+const OUTBOX_LEN: usize = 64;
+
+pub struct EmailSender {
+    backend: Backend,
+    templates: EmailTemplates,
+}
+
+enum Backend {
+    Smtp {
+        transport: AsyncSmtpTransport<Tokio1Executor>,
+        from: Mailbox,
+    },
+    /// Emails are logged rather than sent; the latest are kept in memory.
+    Log(Mutex<VecDeque<SentEmail>>),
+}
+
+/// An email handled by the log backend.
+#[derive(Debug, Clone)]
+pub struct SentEmail {
+    pub to: String,
+    pub email: RenderedEmail,
+}
+
+fn smtp_error(message: impl std::fmt::Display) -> PdsError {
+    PdsError::InternalError(format!("SMTP configuration error: {message}"))
+}
+
+impl EmailSender {
+    /// The sender selected by `email.backend`, or `None` if it is SMTP and
+    /// no `[smtp]` server is configured.
+    pub fn from_config(config: &PdsConfig) -> PdsResult<Option<Self>> {
+        match config.email.backend {
+            EmailBackend::Log => Self::log(&config.email).map(Some),
+            EmailBackend::Smtp => match &config.smtp {
+                Some(smtp) => Self::new(smtp, &config.email).map(Some),
+                None => {
+                    tracing::warn!(
+                        "no [smtp] server configured; verification and reset emails will not be sent"
+                    );
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    /// A sender that logs emails instead of sending them.
+    pub fn log(email: &EmailConfig) -> PdsResult<Self> {
+        Ok(Self {
+            backend: Backend::Log(Mutex::default()),
+            templates: EmailTemplates::load(email.template_dir.as_deref())?,
+        })
+    }
+
+    /// Build the SMTP transport and load templates. Nothing is sent or
+    /// connected here, but an inconsistent `[smtp]` section is rejected.
+    pub fn new(config: &SmtpConfig, email: &EmailConfig) -> PdsResult<Self> {
+        let address = config
+            .from_address
+            .parse()
+            .map_err(|e| smtp_error(format!("invalid from_address {:?}: {e}", config.from_address)))?;
+        let from = Mailbox::new(config.from_name.clone(), address);
+
+        let credentials = match (&config.username, &config.password) {
+            (Some(username), Some(password)) => Some(Credentials::new(username.clone(), password.clone())),
+            (None, None) => None,
+            _ => return Err(smtp_error("username and password must be set together")),
+        };
+        if credentials.is_some() && config.security == SmtpSecurity::None {
+            return Err(smtp_error("refusing to send credentials without TLS; set security"));
+        }
+
+        let builder = match config.security {
+            SmtpSecurity::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+                .map_err(|e| smtp_error(format!("TLS setup for {}: {e}", config.host)))?,
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(|e| smtp_error(format!("TLS setup for {}: {e}", config.host)))?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        };
+        let mut builder = builder
+            .port(config.port)
+            .timeout(Some(Duration::from_secs(config.timeout_secs)));
+        if let Some(credentials) = credentials {
+            builder = builder.credentials(credentials);
+        }
+
+        Ok(Self {
+            backend: Backend::Smtp {
+                transport: builder.build(),
+                from,
+            },
+            templates: EmailTemplates::load(email.template_dir.as_deref())?,
+        })
+    }
+
+    pub async fn send_verification_email(
+        &self,
+        to: &str,
+        handle: &str,
+        token: &str,
+        pds_url: &str,
+    ) -> PdsResult<()> {
+        self.send_templated(to, "confirm_email", handle, token, pds_url).await
+    }
+
+    pub async fn send_password_reset_email(
+        &self,
+        to: &str,
+        handle: &str,
+        token: &str,
+        pds_url: &str,
+    ) -> PdsResult<()> {
+        self.send_templated(to, "reset_password", handle, token, pds_url).await
+    }
+
+    pub async fn send_email_update_email(
+        &self,
+        to: &str,
+        handle: &str,
+        token: &str,
+        pds_url: &str,
+    ) -> PdsResult<()> {
+        self.send_templated(to, "update_email", handle, token, pds_url).await
+    }
+
+    async fn send_templated(
+        &self,
+        to: &str,
+        purpose: &str,
+        handle: &str,
+        token: &str,
+        pds_url: &str,
+    ) -> PdsResult<()> {
+        let vars = TemplateVars {
+            token,
+            public_url: pds_url,
+            handle,
+        };
+        self.send_email(to, self.templates.render(purpose, &vars)).await
+    }
+
+    /// Emails handled by the log backend, oldest first. Always empty for
+    /// SMTP.
+    pub fn outbox(&self) -> Vec<SentEmail> {
+        match &self.backend {
+            Backend::Log(outbox) => outbox.lock().unwrap().iter().cloned().collect(),
+            Backend::Smtp { .. } => Vec::new(),
+        }
+    }
+
+    async fn send_email(&self, to: &str, rendered: RenderedEmail) -> PdsResult<()> {
+        let (transport, from) = match &self.backend {
+            Backend::Smtp { transport, from } => (transport, from),
+            Backend::Log(outbox) => {
+                tracing::info!(
+                    to,
+                    subject = %rendered.subject,
+                    "email not sent (log backend):\n{}",
+                    rendered.text
+                );
+                let mut outbox = outbox.lock().unwrap();
+                if outbox.len() == OUTBOX_LEN {
+                    outbox.pop_front();
+                }
+                outbox.push_back(SentEmail {
+                    to: to.to_string(),
+                    email: rendered,
+                });
+                return Ok(());
+            }
+        };
+
+        let email = Message::builder()
+            .from(from.clone())
+            .to(to
+                .parse()
+                .map_err(|e| PdsError::InternalError(format!("Invalid to address: {e}")))?)
+            .subject(rendered.subject)
+            .multipart(MultiPart::alternative_plain_html(rendered.text, rendered.html))
+            .map_err(|e| PdsError::InternalError(format!("Failed to build email: {e}")))?;
+
+        transport
+            .send(email)
+            .await
+            .map_err(|e| PdsError::InternalError(format!("Failed to send email: {e}")))?;
+        Ok(())
+    }
+}
+
+/// Generate a hex-encoded token for `purpose`, as long as configured.
+pub fn generate_email_token(config: &EmailConfig, purpose: &str) -> String {
+    let bytes: Vec<u8> = (0..config.token(purpose).token_bytes).map(|_| rand::random()).collect();
+    hex::encode(bytes)
+}
+
+/// Whether a `purpose` token requested at `requested_at` is still within its
+/// configured lifetime.
+pub fn is_email_token_valid(
+    config: &EmailConfig,
+    requested_at: chrono::DateTime<chrono::Utc>,
+    purpose: &str,
+) -> bool {
+    config.token_expires_at(purpose, requested_at) >= chrono::Utc::now()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smtp_config() -> SmtpConfig {
+        SmtpConfig {
+            host: "smtp.example.com".to_string(),
+            port: 465,
+            security: SmtpSecurity::Implicit,
+            username: Some("pds@example.com".to_string()),
+            password: Some("secret".to_string()),
+            from_address: "noreply@example.com".to_string(),
+            from_name: Some("Example PDS".to_string()),
+            timeout_secs: 30,
+        }
+    }
+
+    fn new_sender(config: &SmtpConfig) -> PdsResult<EmailSender> {
+        EmailSender::new(config, &EmailConfig::default())
+    }
+
+    #[tokio::test]
+    async fn builds_each_security_mode() {
+        let mut config = smtp_config();
+        let sender = new_sender(&config).unwrap();
+        let Backend::Smtp { from, .. } = &sender.backend else {
+            panic!("expected the SMTP backend");
+        };
+        assert_eq!(from.to_string(), "Example PDS <noreply@example.com>");
+
+        config.security = SmtpSecurity::Starttls;
+        config.port = 587;
+        new_sender(&config).unwrap();
+
+        config.security = SmtpSecurity::None;
+        config.port = 25;
+        config.username = None;
+        config.password = None;
+        new_sender(&config).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_misconfiguration() {
+        let mut config = smtp_config();
+        config.password = None;
+        assert!(new_sender(&config).is_err());
+
+        let mut config = smtp_config();
+        config.security = SmtpSecurity::None;
+        assert!(new_sender(&config).is_err());
+
+        let mut config = smtp_config();
+        config.from_address = "not an address".to_string();
+        assert!(new_sender(&config).is_err());
+    }
+}
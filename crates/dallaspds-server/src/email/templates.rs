@@ -0,0 +1,209 @@
+//! Email subjects and bodies, overridable from `email.template_dir`.
+//!
+//! For each purpose in [`EMAIL_TOKEN_PURPOSES`] the directory may hold
+//! `<purpose>.subject`, `<purpose>.txt` and `<purpose>.html`; any file that
+//! is missing falls back to the built-in template. Templates may use the
+//! `{{token}}`, `{{public_url}}` and `{{handle}}` placeholders.
+
+use std::path::Path;
+
+use dallaspds_core::config::EMAIL_TOKEN_PURPOSES;
+use dallaspds_core::{PdsError, PdsResult};
+
+use crate::routes::oauth::html_escape;
+
+/// Subject and bodies for one kind of email, before substitution.
+#[derive(Debug, Clone)]
+pub struct EmailTemplate {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// Values substituted into a template.
+pub struct TemplateVars<'a> {
+    pub token: &'a str,
+    pub public_url: &'a str,
+    pub handle: &'a str,
+}
+
+/// An email ready to send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// The templates for every email purpose.
+#[derive(Debug, Clone)]
+pub struct EmailTemplates {
+    confirm: EmailTemplate,
+    reset: EmailTemplate,
+    update: EmailTemplate,
+}
+
+impl Default for EmailTemplates {
+    fn default() -> Self {
+        Self {
+            confirm: builtin("confirm_email"),
+            reset: builtin("reset_password"),
+            update: builtin("update_email"),
+        }
+    }
+}
+
+impl EmailTemplates {
+    /// Load templates from `dir`, or use the built-in ones if it is `None`.
+    pub fn load(dir: Option<&str>) -> PdsResult<Self> {
+        let mut templates = Self::default();
+        let Some(dir) = dir else {
+            return Ok(templates);
+        };
+        for purpose in EMAIL_TOKEN_PURPOSES {
+            let template = templates.template_mut(purpose);
+            if let Some(subject) = read_part(dir, purpose, "subject")? {
+                template.subject = subject.trim().to_string();
+            }
+            if let Some(text) = read_part(dir, purpose, "txt")? {
+                template.text = text;
+            }
+            if let Some(html) = read_part(dir, purpose, "html")? {
+                template.html = html;
+            }
+        }
+        Ok(templates)
+    }
+
+    /// Template for `purpose` (one of [`EMAIL_TOKEN_PURPOSES`]).
+    pub fn template(&self, purpose: &str) -> &EmailTemplate {
+        match purpose {
+            "reset_password" => &self.reset,
+            "update_email" => &self.update,
+            _ => &self.confirm,
+        }
+    }
+
+    fn template_mut(&mut self, purpose: &str) -> &mut EmailTemplate {
+        match purpose {
+            "reset_password" => &mut self.reset,
+            "update_email" => &mut self.update,
+            _ => &mut self.confirm,
+        }
+    }
+
+    /// Render the `purpose` email. Values are HTML-escaped in the HTML body.
+    pub fn render(&self, purpose: &str, vars: &TemplateVars<'_>) -> RenderedEmail {
+        let template = self.template(purpose);
+        RenderedEmail {
+            subject: substitute(&template.subject, vars, |v| v.to_string()),
+            text: substitute(&template.text, vars, |v| v.to_string()),
+            html: substitute(&template.html, vars, html_escape),
+        }
+    }
+}
+
+fn read_part(dir: &str, purpose: &str, extension: &str) -> PdsResult<Option<String>> {
+    let path = Path::new(dir).join(format!("{purpose}.{extension}"));
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(PdsError::InternalError(format!(
+            "failed to read email template {}: {e}",
+            path.display()
+        ))),
+    }
+}
+
+fn substitute(template: &str, vars: &TemplateVars<'_>, escape: impl Fn(&str) -> String) -> String {
+    template
+        .replace("{{token}}", &escape(vars.token))
+        .replace("{{public_url}}", &escape(vars.public_url))
+        .replace("{{handle}}", &escape(vars.handle))
+}
+
+fn builtin(purpose: &str) -> EmailTemplate {
+    let (subject, text, html) = match purpose {
+        "reset_password" => (
+            "Password Reset Request",
+            "Your password reset token is: {{token}}\n\n\
+             Or visit: {{public_url}}/reset-password?token={{token}}",
+            "<p>Your password reset token is: <strong>{{token}}</strong></p>\n\
+             <p>Or visit: <a href=\"{{public_url}}/reset-password?token={{token}}\">\
+             {{public_url}}/reset-password</a></p>",
+        ),
+        "update_email" => (
+            "Email Update Confirmation",
+            "Your email update confirmation token is: {{token}}\n\n\
+             Or visit: {{public_url}}/update-email?token={{token}}",
+            "<p>Your email update confirmation token is: <strong>{{token}}</strong></p>\n\
+             <p>Or visit: <a href=\"{{public_url}}/update-email?token={{token}}\">\
+             {{public_url}}/update-email</a></p>",
+        ),
+        _ => (
+            "Verify your email address",
+            "Your verification code is: {{token}}\n\n\
+             Or visit: {{public_url}}/xrpc/com.atproto.server.confirmEmail?token={{token}}",
+            "<p>Your verification code is: <strong>{{token}}</strong></p>\n\
+             <p>Or visit: <a href=\"{{public_url}}/xrpc/com.atproto.server.confirmEmail?token={{token}}\">\
+             {{public_url}}/xrpc/com.atproto.server.confirmEmail</a></p>",
+        ),
+    };
+    EmailTemplate {
+        subject: subject.to_string(),
+        text: text.to_string(),
+        html: html.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: TemplateVars<'static> = TemplateVars {
+        token: "abc123",
+        public_url: "https://pds.example.com",
+        handle: "alice.example.com",
+    };
+
+    #[test]
+    fn renders_overrides_and_falls_back_to_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("confirm_email.subject"), "Welcome, {{handle}}!\n").unwrap();
+        std::fs::write(
+            dir.path().join("confirm_email.html"),
+            "<a href=\"{{public_url}}/confirm?t={{token}}\">Confirm {{handle}} & go</a>",
+        )
+        .unwrap();
+
+        let templates = EmailTemplates::load(dir.path().to_str()).unwrap();
+        let email = templates.render("confirm_email", &VARS);
+        assert_eq!(email.subject, "Welcome, alice.example.com!");
+        assert_eq!(
+            email.html,
+            "<a href=\"https://pds.example.com/confirm?t=abc123\">Confirm alice.example.com & go</a>"
+        );
+        // No confirm_email.txt, so the text part is the built-in one.
+        assert_eq!(
+            email.text,
+            "Your verification code is: abc123\n\n\
+             Or visit: https://pds.example.com/xrpc/com.atproto.server.confirmEmail?token=abc123"
+        );
+
+        let reset = templates.render("reset_password", &VARS);
+        assert_eq!(reset.subject, "Password Reset Request");
+        assert!(reset.html.contains("<strong>abc123</strong>"));
+    }
+
+    #[test]
+    fn escapes_values_in_html_only() {
+        let vars = TemplateVars { handle: "<b>x</b>", ..VARS };
+        let mut templates = EmailTemplates::default();
+        templates.confirm.text = "hi {{handle}}".to_string();
+        templates.confirm.html = "<p>hi {{handle}}</p>".to_string();
+
+        let email = templates.render("confirm_email", &vars);
+        assert_eq!(email.text, "hi <b>x</b>");
+        assert_eq!(email.html, "<p>hi &lt;b&gt;x&lt;/b&gt;</p>");
+    }
+}
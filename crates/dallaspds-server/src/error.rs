@@ -1,4 +1,4 @@
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use dallaspds_core::PdsError;
 use serde_json::json;
@@ -8,6 +8,8 @@ pub struct XrpcError {
     pub status: StatusCode,
     pub error_name: String,
     pub message: String,
+    /// Seconds to send in a `Retry-After` header, if any.
+    pub retry_after: Option<u64>,
 }
 
 impl XrpcError {
@@ -16,17 +18,36 @@ impl XrpcError {
             status,
             error_name: error_name.into(),
             message: message.into(),
+            retry_after: None,
         }
     }
+
+    /// Ask the client to retry after `seconds`.
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
 }
 
 impl IntoResponse for XrpcError {
     fn into_response(self) -> Response {
+        // Logged inside the request's span, which carries its request id.
+        if self.status.is_server_error() {
+            tracing::error!(error = %self.error_name, "{}", self.message);
+        } else {
+            tracing::debug!(error = %self.error_name, "{}", self.message);
+        }
         let body = json!({
             "error": self.error_name,
             "message": self.message,
         });
-        (self.status, axum::Json(body)).into_response()
+        let mut response = (self.status, axum::Json(body)).into_response();
+        if let Some(seconds) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
     }
 }
 
@@ -58,11 +79,28 @@ impl From<PdsError> for XrpcError {
                 "InvalidRequest",
                 err.to_string(),
             ),
-            PdsError::Upstream(_) => XrpcError::new(
+            // Relayed requests (pipethrough, remote records and blobs) pass
+            // the upstream's own status through instead; here a 4xx means
+            // this server failed to talk to the upstream, not that the
+            // client's request was bad.
+            PdsError::Upstream(_)
+            | PdsError::UpstreamClientError(_)
+            | PdsError::UpstreamRateLimited(_)
+            | PdsError::UpstreamServerError(_) => XrpcError::new(
                 StatusCode::BAD_GATEWAY,
                 "UpstreamFailure",
                 err.to_string(),
             ),
+            PdsError::UpstreamTimeout(_) => XrpcError::new(
+                StatusCode::GATEWAY_TIMEOUT,
+                "UpstreamTimeout",
+                err.to_string(),
+            ),
+            PdsError::RepoRootChanged => XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidSwap",
+                err.to_string(),
+            ),
             PdsError::AccountNotFound => XrpcError::new(
                 StatusCode::BAD_REQUEST,
                 "AccountNotFound",
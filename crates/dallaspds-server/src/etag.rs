@@ -0,0 +1,64 @@
+use axum::body::Body;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::Response;
+
+/// `Cache-Control` for content that can never change for a given CID.
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Format a CID as a strong `ETag` value.
+pub fn etag_for_cid(cid: &str) -> String {
+    format!("\"{cid}\"")
+}
+
+/// Returns `true` if the request's `If-None-Match` header matches `etag`.
+///
+/// Handles lists of tags, weak tags (`W/"..."`), and the `*` wildcard.
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+/// Build a `304 Not Modified` response carrying the validator headers.
+pub fn not_modified(etag: &str, cache_control: Option<&str>) -> Response {
+    let mut builder = Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag);
+    if let Some(cache_control) = cache_control {
+        builder = builder.header(header::CACHE_CONTROL, cache_control);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn matches_exact_weak_list_and_wildcard() {
+        let etag = etag_for_cid("bafyabc");
+        assert!(if_none_match(&headers("\"bafyabc\""), &etag));
+        assert!(if_none_match(&headers("W/\"bafyabc\""), &etag));
+        assert!(if_none_match(&headers("\"other\", \"bafyabc\""), &etag));
+        assert!(if_none_match(&headers("*"), &etag));
+    }
+
+    #[test]
+    fn rejects_other_or_missing_tags() {
+        let etag = etag_for_cid("bafyabc");
+        assert!(!if_none_match(&headers("\"bafyxyz\""), &etag));
+        assert!(!if_none_match(&headers("bafyabc"), &etag));
+        assert!(!if_none_match(&HeaderMap::new(), &etag));
+    }
+}
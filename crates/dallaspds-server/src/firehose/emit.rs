@@ -6,16 +6,27 @@ use super::wire;
 
 /// Persist a firehose event to the event store (if configured), then broadcast
 /// it via the sequencer. The event must already have its `seq` assigned.
-pub async fn emit_and_persist<A, R, B>(state: &AppState<A, R, B>, event: FirehoseEvent)
+///
+/// Commits whose diff CAR exceeds `firehose.max_commit_blocks_bytes` are
+/// marked `tooBig` and sent without blocks.
+pub async fn emit_and_persist<A, R, B>(state: &AppState<A, R, B>, mut event: FirehoseEvent)
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
+    if let FirehoseEvent::Commit(ref mut commit) = event
+        && commit.blocks.len() > state.config.firehose.max_commit_blocks_bytes
+    {
+        commit.too_big = true;
+        commit.blocks = Vec::new();
+    }
+
     let (event_type, did) = match &event {
         FirehoseEvent::Commit(e) => ("commit", e.repo.as_str()),
         FirehoseEvent::Identity(e) => ("identity", e.did.as_str()),
         FirehoseEvent::Account(e) => ("account", e.did.as_str()),
+        FirehoseEvent::Sync(e) => ("sync", e.did.as_str()),
     };
 
     // Persist the wire-encoded event payload.
@@ -34,6 +45,6 @@ where
 
     // Broadcast to live subscribers.
     if let Some(ref sequencer) = state.sequencer {
-        sequencer.emit(event);
+        sequencer.emit(event).await;
     }
 }
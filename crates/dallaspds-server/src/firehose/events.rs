@@ -10,6 +10,9 @@ pub struct RepoOp {
     /// CID of the record (None for deletes).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cid: Option<CidLink>,
+    /// CID of the record before this op, set for deletes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<CidLink>,
 }
 
 /// A CID link for DAG-CBOR serialization.
@@ -24,7 +27,8 @@ pub struct CidLink {
 pub struct CommitEvent {
     /// Sequence number assigned by the sequencer.
     pub seq: i64,
-    /// Whether this event should update the subscriber's cursor.
+    /// Set when the diff was too large to include; `blocks` is then empty
+    /// and consumers should fetch the repo via `getRepo`.
     #[serde(rename = "tooBig")]
     pub too_big: bool,
     /// The DID of the repo that was modified.
@@ -67,6 +71,20 @@ pub struct AccountEvent {
     pub status: Option<String>,
 }
 
+/// A `#sync` firehose event body.
+///
+/// Declares the current head of a repo without listing operations. `blocks`
+/// is a CAR containing only the signed commit block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncEvent {
+    pub seq: i64,
+    pub did: String,
+    #[serde(with = "serde_bytes")]
+    pub blocks: Vec<u8>,
+    pub rev: String,
+    pub time: String,
+}
+
 /// A `#info` firehose frame (sent at connection start or on error).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InfoFrame {
@@ -89,6 +107,7 @@ pub enum FirehoseEvent {
     Commit(CommitEvent),
     Identity(IdentityEvent),
     Account(AccountEvent),
+    Sync(SyncEvent),
 }
 
 impl FirehoseEvent {
@@ -97,6 +116,7 @@ impl FirehoseEvent {
             FirehoseEvent::Commit(e) => e.seq,
             FirehoseEvent::Identity(e) => e.seq,
             FirehoseEvent::Account(e) => e.seq,
+            FirehoseEvent::Sync(e) => e.seq,
         }
     }
 }
@@ -1,6 +1,9 @@
 pub mod emit;
 pub mod events;
+pub mod reconcile;
+pub mod redis;
 pub mod relay;
+pub mod retention;
 pub mod sequencer;
 pub mod stream;
 pub mod wire;
@@ -0,0 +1,167 @@
+//! Startup reconciliation of repo roots with the firehose.
+//!
+//! A write updates `repo_root` before its commit event is persisted, so a
+//! crash in between leaves a repo ahead of what relays have been told. On
+//! startup each repo's rev is compared with its last persisted `#commit`
+//! event; a repo that is ahead gets one catch-up commit event covering
+//! everything since, with ops derived from diffing the two MSTs.
+
+use std::collections::BTreeSet;
+
+use dallaspds_core::traits::*;
+use dallaspds_core::types::RepoListEntry;
+use dallaspds_core::{PdsError, PdsResult};
+use ipld_core::cid::Cid;
+
+use super::events::{CidLink, CommitEvent, FirehoseEvent, RepoOp};
+use super::wire;
+use crate::state::AppState;
+
+/// Repos checked per page of `list_repos`.
+const PAGE_SIZE: usize = 500;
+
+/// Spawn [`reconcile_repo_roots`] in the background, logging the outcome.
+pub fn spawn_reconcile<A, R, B>(state: AppState<A, R, B>) -> tokio::task::JoinHandle<()>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    tokio::spawn(async move {
+        match reconcile_repo_roots(&state).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Re-emitted missing commit events for {n} repos"),
+            Err(e) => tracing::warn!("Failed to reconcile repo roots with the firehose: {e}"),
+        }
+    })
+}
+
+/// Re-emit a commit event for every repo whose root is ahead of its last
+/// persisted commit event, returning how many were caught up.
+///
+/// Repos with no retained commit event are skipped: with nothing to compare
+/// against, there is no way to tell what relays have seen.
+pub async fn reconcile_repo_roots<A, R, B>(state: &AppState<A, R, B>) -> PdsResult<usize>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    if state.event_store.is_none() || state.sequencer.is_none() {
+        return Ok(0);
+    }
+
+    let mut caught_up = 0;
+    let mut cursor: Option<String> = None;
+    loop {
+        let repos = state
+            .account_store
+            .list_repos(cursor.as_deref(), PAGE_SIZE)
+            .await?;
+        for repo in &repos {
+            match reconcile_repo(state, repo).await {
+                Ok(true) => caught_up += 1,
+                Ok(false) => {}
+                Err(e) => tracing::warn!(did = %repo.did, error = %e, "failed to reconcile repo"),
+            }
+        }
+        if repos.len() < PAGE_SIZE {
+            return Ok(caught_up);
+        }
+        cursor = repos.last().map(|repo| repo.did.clone());
+    }
+}
+
+async fn reconcile_repo<A, R, B>(
+    state: &AppState<A, R, B>,
+    repo: &RepoListEntry,
+) -> PdsResult<bool>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let (Some(event_store), Some(sequencer)) = (&state.event_store, &state.sequencer) else {
+        return Ok(false);
+    };
+    if repo.cid.is_empty() {
+        return Ok(false);
+    }
+    // Hold the repo's write lock so no write moves the root or emits its
+    // own commit event while the catch-up is built, and re-read the root
+    // under it: the listed one may already be stale.
+    let _write_permit = state
+        .write_limiter
+        .acquire(&repo.did)
+        .await
+        .map_err(|e| PdsError::InternalError(e.message))?;
+    let Some(repo) = state.account_store.get_repo_root(&repo.did).await? else {
+        return Ok(false);
+    };
+    let Some(persisted) = event_store.get_last_event_for_did(&repo.did, "commit").await? else {
+        return Ok(false);
+    };
+    let FirehoseEvent::Commit(last) = wire::decode_event_frame(&persisted.payload)
+        .map_err(|e| PdsError::Storage(format!("undecodable commit event: {e}")))?
+    else {
+        return Ok(false);
+    };
+    if last.rev.as_str() >= repo.rev.as_str() {
+        return Ok(false);
+    }
+
+    let last_root = Cid::try_from(last.commit.link.as_str())
+        .map_err(|e| PdsError::Storage(format!("invalid commit CID in event: {e}")))?
+        .to_bytes();
+    let before =
+        dallaspds_repo::list_record_cids(state.repo_store.clone(), &repo.did, &last_root).await?;
+    let after =
+        dallaspds_repo::list_record_cids(state.repo_store.clone(), &repo.did, &repo.cid).await?;
+    let blocks = dallaspds_repo::generate_diff_car(
+        state.repo_store.clone(),
+        &repo.did,
+        &repo.cid,
+        Some(&last_root),
+    )
+    .await?;
+
+    let link = |cid: &[u8]| {
+        Cid::try_from(cid).map(|cid| CidLink {
+            link: cid.to_string(),
+        })
+    };
+    let paths: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut ops = Vec::new();
+    for path in paths {
+        let (action, cid, prev) = match (before.get(path), after.get(path)) {
+            (None, Some(new)) => ("create", Some(new), None),
+            (Some(old), Some(new)) if old != new => ("update", Some(new), None),
+            (Some(old), None) => ("delete", None, Some(old)),
+            _ => continue,
+        };
+        ops.push(RepoOp {
+            action: action.to_string(),
+            path: path.clone(),
+            cid: cid.and_then(|cid| link(cid).ok()),
+            prev: prev.and_then(|cid| link(cid).ok()),
+        });
+    }
+
+    let event = FirehoseEvent::Commit(CommitEvent {
+        seq: sequencer.next_seq().await?,
+        too_big: false,
+        repo: repo.did.clone(),
+        commit: link(&repo.cid)
+            .map_err(|e| PdsError::Storage(format!("invalid repo root CID: {e}")))?,
+        prev: Some(last.commit),
+        rev: repo.rev.clone(),
+        time: chrono::Utc::now().to_rfc3339(),
+        ops,
+        blocks,
+    });
+    super::emit::emit_and_persist(state, event).await;
+    if let Some(ref notifier) = state.relay_notifier {
+        notifier.notify(&repo.did);
+    }
+    Ok(true)
+}
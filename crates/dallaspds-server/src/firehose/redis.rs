@@ -0,0 +1,153 @@
+//! Redis-backed [`Sequencer`] for running several PDS processes side by side.
+//!
+//! Sequence numbers come from `INCR` on a shared key and events fan out over
+//! a pub/sub channel, so every process sees every event in its local
+//! broadcast channel. Accepts `redis://` and, for TLS, `rediss://` URLs.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dallaspds_core::{PdsError, PdsResult};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use redis::aio::{ConnectionManager, PubSub};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use super::events::FirehoseEvent;
+use super::sequencer::Sequencer;
+use super::wire;
+
+/// Key holding the last assigned sequence number.
+const SEQ_KEY: &str = "dallaspds:firehose:seq";
+
+/// Pub/sub channel carrying wire-encoded events.
+const EVENTS_CHANNEL: &str = "dallaspds:firehose:events";
+
+/// Raise the counter to ARGV[1] but never lower it, so a process starting up
+/// can't rewind sequence numbers another process already handed out.
+const RAISE_COUNTER_SCRIPT: &str = "local cur = tonumber(redis.call('GET', KEYS[1]) or '0') \
+    if cur < tonumber(ARGV[1]) then redis.call('SET', KEYS[1], ARGV[1]) end \
+    return 0";
+
+/// Wait between attempts to re-establish a dropped subscription.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Sequencer that shares its sequence space and live events through Redis.
+pub struct RedisSequencer {
+    /// Reconnects on its own when the connection drops.
+    conn: ConnectionManager,
+    sender: broadcast::Sender<Arc<FirehoseEvent>>,
+    subscriber: JoinHandle<()>,
+}
+
+impl RedisSequencer {
+    /// Connect to Redis and start relaying events published by any process.
+    ///
+    /// `start_seq` is the first sequence number this deployment may assign
+    /// (typically last_persisted + 1). The shared counter is raised to it but
+    /// never lowered. `channel_capacity` sizes the local broadcast buffer.
+    pub async fn connect(url: &str, start_seq: i64, channel_capacity: usize) -> PdsResult<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| PdsError::InternalError(format!("invalid redis url: {e}")))?;
+        let mut conn = client.get_connection_manager().await.map_err(redis_error)?;
+        redis::Script::new(RAISE_COUNTER_SCRIPT)
+            .key(SEQ_KEY)
+            .arg(start_seq - 1)
+            .invoke_async::<()>(&mut conn)
+            .await
+            .map_err(redis_error)?;
+
+        // Subscribe before returning so no event emitted after startup is missed.
+        let (sender, _) = broadcast::channel(channel_capacity);
+        let subscription = subscribe(&client).await?;
+        let subscriber = tokio::spawn(relay_events(client, subscription, sender.clone()));
+
+        Ok(Self {
+            conn,
+            sender,
+            subscriber,
+        })
+    }
+}
+
+impl Drop for RedisSequencer {
+    fn drop(&mut self) {
+        self.subscriber.abort();
+    }
+}
+
+#[async_trait]
+impl Sequencer for RedisSequencer {
+    async fn next_seq(&self) -> PdsResult<i64> {
+        self.conn.clone().incr(SEQ_KEY, 1).await.map_err(redis_error)
+    }
+
+    async fn emit(&self, event: FirehoseEvent) {
+        // Local subscribers receive the event when it comes back over pub/sub.
+        let payload = match wire::encode_event_frame(&event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Failed to encode firehose event for redis: {e}");
+                return;
+            }
+        };
+        if let Err(e) = self
+            .conn
+            .clone()
+            .publish::<_, _, ()>(EVENTS_CHANNEL, payload)
+            .await
+        {
+            tracing::warn!("Failed to publish firehose event to redis: {e}");
+        }
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<Arc<FirehoseEvent>> {
+        self.sender.subscribe()
+    }
+
+    async fn current_seq(&self) -> PdsResult<i64> {
+        let last: Option<i64> = self.conn.clone().get(SEQ_KEY).await.map_err(redis_error)?;
+        Ok(last.map_or(1, |last| last + 1))
+    }
+}
+
+/// Open a connection subscribed to the events channel.
+async fn subscribe(client: &redis::Client) -> PdsResult<PubSub> {
+    let mut pubsub = client.get_async_pubsub().await.map_err(redis_error)?;
+    pubsub.subscribe(EVENTS_CHANNEL).await.map_err(redis_error)?;
+    Ok(pubsub)
+}
+
+/// Forward pub/sub messages into the local broadcast channel, resubscribing
+/// whenever the connection drops.
+async fn relay_events(
+    client: redis::Client,
+    mut subscription: PubSub,
+    sender: broadcast::Sender<Arc<FirehoseEvent>>,
+) {
+    loop {
+        let mut messages = subscription.into_on_message();
+        while let Some(message) = messages.next().await {
+            match wire::decode_event_frame(message.get_payload_bytes()) {
+                Ok(event) => {
+                    let _ = sender.send(Arc::new(event));
+                }
+                Err(e) => tracing::warn!("Ignoring undecodable firehose event from redis: {e}"),
+            }
+        }
+        tracing::warn!("Redis firehose subscription lost");
+        subscription = loop {
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            match subscribe(&client).await {
+                Ok(subscription) => break subscription,
+                Err(e) => tracing::warn!("Failed to resubscribe to redis: {e}"),
+            }
+        };
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> PdsError {
+    PdsError::Storage(format!("redis error: {e}"))
+}
@@ -1,77 +1,176 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 
-/// Notifies a configured relay (e.g., the Bluesky BGS) to crawl this PDS
-/// by sending `com.atproto.sync.requestCrawl` after each repo write.
+/// Delay before the first retry of a failed requestCrawl; doubled on each
+/// further failure up to [`MAX_RETRY_DELAY`].
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Notifies the configured relays (e.g., the Bluesky BGS) to crawl this PDS
+/// by sending `com.atproto.sync.requestCrawl` once at startup and again after
+/// repo writes, at most once per `debounce` for any given DID.
 #[derive(Clone)]
 pub struct RelayNotifier {
     sender: mpsc::UnboundedSender<String>,
 }
 
 impl RelayNotifier {
-    /// Create a new relay notifier that will POST requestCrawl to the given URL.
+    /// Create a new relay notifier that will POST requestCrawl to each of the
+    /// given URLs.
     ///
-    /// `relay_url` is the base URL of the relay (e.g. `https://bsky.network`).
+    /// `relay_urls` are the base URLs of the relays (e.g. `https://bsky.network`).
     /// `pds_hostname` is the hostname of this PDS (used in the requestCrawl body).
+    /// Writes to a DID within `debounce` of its last crawl request are not
+    /// forwarded.
     ///
     /// Returns the notifier handle and a future that should be spawned to run
     /// the background notification loop.
-    pub fn new(relay_url: String, pds_hostname: String) -> (Self, RelayNotifierWorker) {
+    pub fn new(
+        relay_urls: Vec<String>,
+        pds_hostname: String,
+        debounce: Duration,
+    ) -> (Self, RelayNotifierWorker) {
         let (sender, receiver) = mpsc::unbounded_channel();
         let notifier = RelayNotifier { sender };
         let worker = RelayNotifierWorker {
-            relay_url,
+            relay_urls,
             pds_hostname,
+            debounce,
             receiver,
             client: Arc::new(reqwest::Client::new()),
+            last_notified: HashMap::new(),
         };
         (notifier, worker)
     }
 
-    /// Notify the relay that a repo has been updated.
+    /// Notify the relays that a repo has been updated.
     /// This is a non-blocking fire-and-forget call.
-    pub fn notify(&self, _did: &str) {
-        // We just need to poke the relay; the DID doesn't matter for requestCrawl.
-        let _ = self.sender.send("crawl".to_string());
+    pub fn notify(&self, did: &str) {
+        let _ = self.sender.send(did.to_string());
     }
 }
 
 pub struct RelayNotifierWorker {
-    relay_url: String,
+    relay_urls: Vec<String>,
     pds_hostname: String,
+    debounce: Duration,
     receiver: mpsc::UnboundedReceiver<String>,
     client: Arc<reqwest::Client>,
+    /// When each DID last triggered a crawl request.
+    last_notified: HashMap<String, Instant>,
 }
 
 impl RelayNotifierWorker {
     /// Run the notification worker loop. Should be spawned as a tokio task.
+    ///
+    /// Each relay is served by its own task, so a relay that is down or slow
+    /// only delays its own requests. Every relay is announced to immediately;
+    /// write notifications are then fanned out until every [`RelayNotifier`]
+    /// is dropped.
     pub async fn run(mut self) {
-        while let Some(_) = self.receiver.recv().await {
-            let url = format!(
-                "{}/xrpc/com.atproto.sync.requestCrawl",
-                self.relay_url.trim_end_matches('/')
-            );
-            let body = serde_json::json!({
-                "hostname": self.pds_hostname,
-            });
+        let relays: Vec<mpsc::UnboundedSender<()>> = self
+            .relay_urls
+            .iter()
+            .map(|relay_url| {
+                let (pending, receiver) = mpsc::unbounded_channel();
+                let relay = RelayTarget {
+                    url: format!(
+                        "{}/xrpc/com.atproto.sync.requestCrawl",
+                        relay_url.trim_end_matches('/')
+                    ),
+                    body: serde_json::json!({ "hostname": self.pds_hostname }),
+                    client: self.client.clone(),
+                };
+                tokio::spawn(relay.run(receiver));
+                pending
+            })
+            .collect();
 
-            match self.client.post(&url).json(&body).send().await {
-                Ok(resp) => {
-                    if !resp.status().is_success() {
-                        let status = resp.status();
-                        let text = resp.text().await.unwrap_or_default();
-                        tracing::warn!(
-                            "Relay requestCrawl returned {}: {}",
-                            status,
-                            text
-                        );
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to notify relay at {}: {e}", url);
+        while let Some(did) = self.receiver.recv().await {
+            let now = Instant::now();
+            if self
+                .last_notified
+                .get(&did)
+                .is_some_and(|last| now.duration_since(*last) < self.debounce)
+            {
+                continue;
+            }
+            // Forget DIDs whose window has passed so the map stays bounded by
+            // the number of recently active repos.
+            let debounce = self.debounce;
+            self.last_notified
+                .retain(|_, last| now.duration_since(*last) < debounce);
+            self.last_notified.insert(did, now);
+
+            for relay in &relays {
+                let _ = relay.send(());
+            }
+        }
+    }
+}
+
+/// One relay's share of the notification work.
+struct RelayTarget {
+    url: String,
+    body: serde_json::Value,
+    client: Arc<reqwest::Client>,
+}
+
+impl RelayTarget {
+    /// Request a crawl now and again for each pending notification, until
+    /// the worker shuts down.
+    async fn run(self, mut pending: mpsc::UnboundedReceiver<()>) {
+        loop {
+            if self.request_crawl_with_retry().await {
+                // The crawl that finally got through covers every write that
+                // queued up while the relay was failing.
+                while pending.try_recv().is_ok() {}
+            }
+            if pending.recv().await.is_none() {
+                return;
+            }
+        }
+    }
+
+    /// Send requestCrawl until the relay accepts it, backing off between
+    /// attempts. Returns whether any attempt failed.
+    async fn request_crawl_with_retry(&self) -> bool {
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut retried = false;
+        while !self.request_crawl().await {
+            tracing::info!("Retrying relay requestCrawl at {} in {delay:?}", self.url);
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+            retried = true;
+        }
+        retried
+    }
+
+    /// Send one requestCrawl and report whether the relay accepted it.
+    async fn request_crawl(&self) -> bool {
+        match self.client.post(&self.url).json(&self.body).send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                if status.is_success() {
+                    tracing::info!("Relay requestCrawl at {} returned {status}", self.url);
+                    true
+                } else {
+                    let text = resp.text().await.unwrap_or_default();
+                    tracing::warn!(
+                        "Relay requestCrawl at {} returned {status}: {text}",
+                        self.url
+                    );
+                    false
                 }
             }
+            Err(e) => {
+                tracing::warn!("Failed to notify relay at {}: {e}", self.url);
+                false
+            }
         }
     }
 }
@@ -0,0 +1,130 @@
+//! Retention for persisted firehose events.
+//!
+//! Events older than the configured window are deleted, except that nothing
+//! a connected subscriber has yet to receive is ever pruned: each stream
+//! registers its position in [`SubscriberCursors`] and the lowest one acts
+//! as a low-water mark. The registry also records which subscribers are
+//! still replaying history, which `subscribeRepos` uses to shed load.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dallaspds_core::config::FirehoseConfig;
+use dallaspds_core::{EventStore, PdsResult};
+
+/// Positions of the currently connected firehose subscribers.
+#[derive(Default)]
+pub struct SubscriberCursors {
+    next_id: AtomicU64,
+    cursors: Mutex<HashMap<u64, Position>>,
+}
+
+struct Position {
+    seq: i64,
+    backfilling: bool,
+}
+
+impl SubscriberCursors {
+    /// Register a live subscriber that has been sent everything up to `seq`.
+    /// It stays registered until the returned guard is dropped.
+    pub fn track(self: &Arc<Self>, seq: i64) -> CursorGuard {
+        self.insert(seq, false)
+    }
+
+    /// Like [`SubscriberCursors::track`], for a subscriber replaying
+    /// persisted events. Call [`CursorGuard::caught_up`] once it goes live.
+    pub fn track_backfill(self: &Arc<Self>, seq: i64) -> CursorGuard {
+        self.insert(seq, true)
+    }
+
+    fn insert(self: &Arc<Self>, seq: i64, backfilling: bool) -> CursorGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.cursors
+            .lock()
+            .unwrap()
+            .insert(id, Position { seq, backfilling });
+        CursorGuard {
+            cursors: self.clone(),
+            id,
+        }
+    }
+
+    /// The lowest seq any connected subscriber has been sent, if any are
+    /// connected. Events after it must be kept.
+    pub fn low_water_mark(&self) -> Option<i64> {
+        self.cursors.lock().unwrap().values().map(|p| p.seq).min()
+    }
+
+    /// How many connected subscribers are still replaying persisted events.
+    pub fn backfilling(&self) -> usize {
+        self.cursors
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|p| p.backfilling)
+            .count()
+    }
+}
+
+/// A subscriber's entry in [`SubscriberCursors`].
+pub struct CursorGuard {
+    cursors: Arc<SubscriberCursors>,
+    id: u64,
+}
+
+impl CursorGuard {
+    /// Record that the subscriber has now been sent everything up to `seq`.
+    pub fn advance(&self, seq: i64) {
+        if let Some(position) = self.cursors.cursors.lock().unwrap().get_mut(&self.id) {
+            position.seq = seq;
+        }
+    }
+
+    /// Record that the subscriber has finished replaying persisted events.
+    pub fn caught_up(&self) {
+        if let Some(position) = self.cursors.cursors.lock().unwrap().get_mut(&self.id) {
+            position.backfilling = false;
+        }
+    }
+}
+
+impl Drop for CursorGuard {
+    fn drop(&mut self) {
+        self.cursors.cursors.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Delete events outside the retention window and return how many were
+/// removed.
+///
+/// An event is pruned once it is older than `retention_days` or further back
+/// than `retention_events` from the head, whichever cuts deeper. The newest
+/// event is always kept so the sequencer can resume from it after a restart,
+/// and nothing after the subscribers' low-water mark is touched.
+pub async fn prune_events(
+    event_store: &dyn EventStore,
+    config: &FirehoseConfig,
+    cursors: &SubscriberCursors,
+) -> PdsResult<u64> {
+    let max_seq = event_store.get_max_seq().await?;
+    let mut before_seq = 0;
+
+    if let Some(events) = config.retention_events {
+        before_seq = before_seq.max(max_seq - events as i64 + 1);
+    }
+    if let Some(days) = config.retention_days {
+        let since = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+        let first_kept = event_store.get_first_seq_since(since).await?;
+        before_seq = before_seq.max(first_kept.unwrap_or(max_seq + 1));
+    }
+
+    before_seq = before_seq.min(max_seq);
+    if let Some(low_water_mark) = cursors.low_water_mark() {
+        before_seq = before_seq.min(low_water_mark + 1);
+    }
+    if before_seq <= 1 {
+        return Ok(0);
+    }
+    event_store.prune_events_before(before_seq).await
+}
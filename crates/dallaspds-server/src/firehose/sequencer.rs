@@ -1,66 +1,96 @@
 use std::sync::Arc;
 use std::sync::atomic::{AtomicI64, Ordering};
 
+use async_trait::async_trait;
+use dallaspds_core::PdsResult;
+use dallaspds_core::config::FirehoseConfig;
 use tokio::sync::broadcast;
 
 use super::events::FirehoseEvent;
+use super::redis::RedisSequencer;
 
-/// The sequencer assigns monotonically increasing sequence numbers to firehose
-/// events and broadcasts them to connected subscribers.
+/// Assigns monotonically increasing sequence numbers to firehose events and
+/// broadcasts them to connected subscribers.
 ///
-/// Sequence numbers are atomic and in-memory. For persistence across restarts,
-/// the caller should persist the last-used seq and pass it when constructing.
-#[derive(Clone)]
-pub struct Sequencer {
-    inner: Arc<SequencerInner>,
+/// [`MemorySequencer`] serves a single process. [`RedisSequencer`] shares the
+/// sequence space and live events between processes behind a load balancer.
+///
+/// [`RedisSequencer`]: super::redis::RedisSequencer
+#[async_trait]
+pub trait Sequencer: Send + Sync {
+    /// Allocate the next sequence number.
+    async fn next_seq(&self) -> PdsResult<i64>;
+
+    /// Broadcast an event, whose `seq` is already assigned, to all subscribers.
+    async fn emit(&self, event: FirehoseEvent);
+
+    /// Subscribe to the live event stream.
+    ///
+    /// If the subscriber falls behind by more than the channel capacity, it
+    /// will receive a `Lagged` error.
+    fn subscribe(&self) -> broadcast::Receiver<Arc<FirehoseEvent>>;
+
+    /// Returns the current (next-to-be-assigned) sequence number.
+    /// Useful for knowing the "head" of the stream.
+    async fn current_seq(&self) -> PdsResult<i64>;
 }
 
-struct SequencerInner {
+/// Build the sequencer the config asks for: Redis-backed when
+/// `firehose.redis_url` is set, otherwise in-process. Either way the live
+/// broadcast buffer holds `firehose.buffer_size` events.
+pub async fn connect_sequencer(
+    config: &FirehoseConfig,
+    start_seq: i64,
+) -> PdsResult<Arc<dyn Sequencer>> {
+    let capacity = config.buffer_size.max(1);
+    Ok(match &config.redis_url {
+        Some(url) => Arc::new(RedisSequencer::connect(url, start_seq, capacity).await?),
+        None => Arc::new(MemorySequencer::new(start_seq, capacity)),
+    })
+}
+
+/// In-process sequencer backed by an atomic counter and a broadcast channel.
+///
+/// Sequence numbers are in-memory. For persistence across restarts, the
+/// caller should persist the last-used seq and pass it when constructing.
+pub struct MemorySequencer {
     next_seq: AtomicI64,
     /// Broadcast channel for live event streaming.
     /// Subscribers receive cloned events.
     sender: broadcast::Sender<Arc<FirehoseEvent>>,
 }
 
-impl Sequencer {
+impl MemorySequencer {
     /// Create a new sequencer.
     ///
     /// `start_seq` is the first sequence number to assign (typically last_persisted + 1).
     /// `channel_capacity` controls the broadcast buffer size (events before slow subscribers lag).
     pub fn new(start_seq: i64, channel_capacity: usize) -> Self {
         let (sender, _) = broadcast::channel(channel_capacity);
-        Sequencer {
-            inner: Arc::new(SequencerInner {
-                next_seq: AtomicI64::new(start_seq),
-                sender,
-            }),
+        MemorySequencer {
+            next_seq: AtomicI64::new(start_seq),
+            sender,
         }
     }
+}
 
-    /// Allocate the next sequence number.
-    pub fn next_seq(&self) -> i64 {
-        self.inner.next_seq.fetch_add(1, Ordering::Relaxed)
+#[async_trait]
+impl Sequencer for MemorySequencer {
+    async fn next_seq(&self) -> PdsResult<i64> {
+        Ok(self.next_seq.fetch_add(1, Ordering::Relaxed))
     }
 
-    /// Emit (broadcast) a firehose event to all connected subscribers.
-    pub fn emit(&self, event: FirehoseEvent) {
+    async fn emit(&self, event: FirehoseEvent) {
         // Ignore send errors — they just mean no subscribers are connected.
-        let _ = self.inner.sender.send(Arc::new(event));
+        let _ = self.sender.send(Arc::new(event));
     }
 
-    /// Subscribe to the live event stream.
-    ///
-    /// Returns a receiver that yields events as they are emitted.
-    /// If the subscriber falls behind by more than `channel_capacity` events,
-    /// it will receive a `Lagged` error.
-    pub fn subscribe(&self) -> broadcast::Receiver<Arc<FirehoseEvent>> {
-        self.inner.sender.subscribe()
+    fn subscribe(&self) -> broadcast::Receiver<Arc<FirehoseEvent>> {
+        self.sender.subscribe()
     }
 
-    /// Returns the current (next-to-be-assigned) sequence number.
-    /// Useful for knowing the "head" of the stream.
-    pub fn current_seq(&self) -> i64 {
-        self.inner.next_seq.load(Ordering::Relaxed)
+    async fn current_seq(&self) -> PdsResult<i64> {
+        Ok(self.next_seq.load(Ordering::Relaxed))
     }
 }
 
@@ -78,42 +108,42 @@ mod tests {
         })
     }
 
-    #[test]
-    fn assigns_sequential_numbers() {
-        let seq = Sequencer::new(1, 16);
-        assert_eq!(seq.next_seq(), 1);
-        assert_eq!(seq.next_seq(), 2);
-        assert_eq!(seq.next_seq(), 3);
+    #[tokio::test]
+    async fn assigns_sequential_numbers() {
+        let seq = MemorySequencer::new(1, 16);
+        assert_eq!(seq.next_seq().await.unwrap(), 1);
+        assert_eq!(seq.next_seq().await.unwrap(), 2);
+        assert_eq!(seq.next_seq().await.unwrap(), 3);
     }
 
-    #[test]
-    fn starts_at_given_seq() {
-        let seq = Sequencer::new(100, 16);
-        assert_eq!(seq.current_seq(), 100);
-        assert_eq!(seq.next_seq(), 100);
-        assert_eq!(seq.current_seq(), 101);
+    #[tokio::test]
+    async fn starts_at_given_seq() {
+        let seq = MemorySequencer::new(100, 16);
+        assert_eq!(seq.current_seq().await.unwrap(), 100);
+        assert_eq!(seq.next_seq().await.unwrap(), 100);
+        assert_eq!(seq.current_seq().await.unwrap(), 101);
     }
 
-    #[test]
-    fn subscribe_receives_events() {
-        let seq = Sequencer::new(1, 16);
+    #[tokio::test]
+    async fn subscribe_receives_events() {
+        let seq = MemorySequencer::new(1, 16);
         let mut rx = seq.subscribe();
 
         let event = make_identity_event(1);
-        seq.emit(event);
+        seq.emit(event).await;
 
         let received = rx.try_recv().unwrap();
         assert_eq!(received.seq(), 1);
     }
 
-    #[test]
-    fn current_seq_reflects_allocations() {
-        let seq = Sequencer::new(1, 16);
-        assert_eq!(seq.current_seq(), 1);
-        seq.next_seq();
-        assert_eq!(seq.current_seq(), 2);
-        seq.next_seq();
-        seq.next_seq();
-        assert_eq!(seq.current_seq(), 4);
+    #[tokio::test]
+    async fn current_seq_reflects_allocations() {
+        let seq = MemorySequencer::new(1, 16);
+        assert_eq!(seq.current_seq().await.unwrap(), 1);
+        seq.next_seq().await.unwrap();
+        assert_eq!(seq.current_seq().await.unwrap(), 2);
+        seq.next_seq().await.unwrap();
+        seq.next_seq().await.unwrap();
+        assert_eq!(seq.current_seq().await.unwrap(), 4);
     }
 }
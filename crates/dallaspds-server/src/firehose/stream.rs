@@ -45,10 +45,10 @@ async fn handle_subscribe<A, R, B>(
     let sequencer = match &state.sequencer {
         Some(seq) => seq.clone(),
         None => {
-            // No sequencer configured — send error and close
+            // Firehose disabled — send error and close
             let err = wire::encode_error_frame(&ErrorFrame {
-                error: "FutureCursor".to_string(),
-                message: Some("Firehose not available".to_string()),
+                error: "FirehoseDisabled".to_string(),
+                message: Some("The firehose is disabled on this PDS".to_string()),
             });
             if let Ok(frame) = err {
                 let _ = sender.send(Message::Binary(frame.into())).await;
@@ -57,21 +57,60 @@ async fn handle_subscribe<A, R, B>(
         }
     };
 
-    // Validate cursor: if provided and in the future, reject.
-    if let Some(cursor_val) = cursor {
-        let current = sequencer.current_seq();
-        if cursor_val > current {
-            let err = wire::encode_error_frame(&ErrorFrame {
-                error: "FutureCursor".to_string(),
-                message: Some(format!(
-                    "Cursor {cursor_val} is ahead of current seq {current}"
-                )),
-            });
-            if let Ok(frame) = err {
-                let _ = sender.send(Message::Binary(frame.into())).await;
-            }
+    let current = match sequencer.current_seq().await {
+        Ok(current) => current,
+        Err(e) => {
+            tracing::warn!("Failed to read current firehose seq: {e}");
             return;
         }
+    };
+
+    // Validate cursor: if provided and in the future, reject.
+    if let Some(cursor_val) = cursor
+        && cursor_val > current
+    {
+        let err = wire::encode_error_frame(&ErrorFrame {
+            error: "FutureCursor".to_string(),
+            message: Some(format!("Cursor {cursor_val} is ahead of current seq {current}")),
+        });
+        if let Ok(frame) = err {
+            let _ = sender.send(Message::Binary(frame.into())).await;
+        }
+        return;
+    }
+
+    let backfill = match (cursor, &state.event_store) {
+        (Some(cursor_val), Some(event_store)) if cursor_val < current => {
+            Some((cursor_val, event_store))
+        }
+        _ => None,
+    };
+
+    // Shed load during maintenance or when too many clients are replaying.
+    let firehose_config = &state.config.firehose;
+    let max_backfills = firehose_config.max_concurrent_backfills;
+    let defer_reason = if state.shutdown.is_triggered() {
+        Some("The server is shutting down")
+    } else if firehose_config.maintenance {
+        Some("The firehose is under maintenance")
+    } else if backfill.is_some()
+        && max_backfills > 0
+        && state.subscriber_cursors.backfilling() >= max_backfills
+    {
+        Some("Too many subscribers are replaying history")
+    } else {
+        None
+    };
+    if let Some(reason) = defer_reason {
+        let retry_after = firehose_config.retry_after_secs;
+        if let Ok(frame) = wire::encode_info_frame(&InfoFrame {
+            name: "ReconnectLater".to_string(),
+            message: Some(format!("{reason}; reconnect in {retry_after} seconds")),
+        }) {
+            let _ = sender.send(Message::Binary(frame.into())).await;
+        }
+        let _ = sender.send(Message::Close(None)).await;
+        return;
     }
 
     // Subscribe to live events FIRST (before backfill) to avoid gaps.
@@ -79,54 +118,63 @@ async fn handle_subscribe<A, R, B>(
 
     // Backfill from event store if cursor is provided and behind current seq.
     let mut last_sent_seq: i64 = cursor.unwrap_or(0);
+    // Hold back event pruning until this subscriber has caught up.
+    let position = match backfill {
+        Some((cursor_val, _)) => state.subscriber_cursors.track_backfill(cursor_val),
+        None => state.subscriber_cursors.track(cursor.unwrap_or(current - 1)),
+    };
 
-    if let (Some(cursor_val), Some(event_store)) = (cursor, &state.event_store) {
-        let current_seq = sequencer.current_seq();
-        if cursor_val < current_seq {
-            // Send an info frame indicating backfill.
-            if let Ok(info_frame) = wire::encode_info_frame(&InfoFrame {
-                name: "OutdatedCursor".to_string(),
-                message: Some("Replaying historical events".to_string()),
-            }) {
-                if sender
-                    .send(Message::Binary(info_frame.into()))
-                    .await
-                    .is_err()
-                {
-                    return;
-                }
+    if let Some((cursor_val, event_store)) = backfill {
+        // Send an info frame indicating backfill.
+        if let Ok(info_frame) = wire::encode_info_frame(&InfoFrame {
+            name: "OutdatedCursor".to_string(),
+            message: Some("Replaying historical events".to_string()),
+        }) {
+            if sender
+                .send(Message::Binary(info_frame.into()))
+                .await
+                .is_err()
+            {
+                return;
             }
+        }
 
-            // Replay persisted events in batches.
-            let mut replay_cursor = cursor_val;
-            loop {
-                let events = match event_store.get_events_after(replay_cursor, 100).await {
-                    Ok(events) => events,
-                    Err(e) => {
-                        tracing::warn!("Failed to read events from store: {e}");
-                        break;
-                    }
-                };
-
-                if events.is_empty() {
+        // Replay persisted events in batches, up to the last seq assigned
+        // before subscribing. Anything newer arrives on `rx`, so backfill
+        // has a fixed end even while writes keep coming in.
+        let batch_size = state.config.firehose.backfill_batch_size.max(1);
+        let backfill_end = current - 1;
+        let mut replay_cursor = cursor_val;
+        while replay_cursor < backfill_end {
+            let events = match event_store.get_events_after(replay_cursor, batch_size).await {
+                Ok(events) => events,
+                Err(e) => {
+                    tracing::warn!("Failed to read events from store: {e}");
                     break;
                 }
+            };
 
-                for event in &events {
-                    // The payload is already a wire-encoded frame.
-                    if sender
-                        .send(Message::Binary(event.payload.clone().into()))
-                        .await
-                        .is_err()
-                    {
-                        return; // Client disconnected
-                    }
-                    last_sent_seq = event.seq;
-                }
+            let Some(last) = events.last() else {
+                break;
+            };
+            let next_cursor = last.seq;
 
-                replay_cursor = last_sent_seq;
+            for event in events.iter().filter(|e| e.seq <= backfill_end) {
+                // The payload is already a wire-encoded frame.
+                if sender
+                    .send(Message::Binary(event.payload.clone().into()))
+                    .await
+                    .is_err()
+                {
+                    return; // Client disconnected
+                }
+                last_sent_seq = event.seq;
+                position.advance(last_sent_seq);
             }
+
+            replay_cursor = next_cursor;
         }
+        position.caught_up();
     }
 
     // Spawn a task to drain incoming messages (pings/pongs/close).
@@ -134,9 +182,27 @@ async fn handle_subscribe<A, R, B>(
         while let Some(Ok(_)) = receiver.next().await {}
     });
 
-    // Stream live events to the client, skipping any already sent during backfill.
+    // Stream live events to the client. Events buffered while backfilling may
+    // overlap what was replayed, so only forward seqs past the last one sent.
     loop {
-        match rx.recv().await {
+        let received = tokio::select! {
+            received = rx.recv() => received,
+            () = state.shutdown.wait() => {
+                // Tell the client to resume elsewhere (or after restart)
+                // rather than leaving it to notice a dropped socket.
+                if let Ok(frame) = wire::encode_info_frame(&InfoFrame {
+                    name: "ServerShuttingDown".to_string(),
+                    message: Some(format!(
+                        "The server is shutting down; reconnect with cursor {last_sent_seq}"
+                    )),
+                }) {
+                    let _ = sender.send(Message::Binary(frame.into())).await;
+                }
+                let _ = sender.send(Message::Close(None)).await;
+                break;
+            }
+        };
+        match received {
             Ok(event) => {
                 // Skip events already sent during backfill.
                 if event.seq() <= last_sent_seq {
@@ -149,6 +215,7 @@ async fn handle_subscribe<A, R, B>(
                             break; // Client disconnected
                         }
                         last_sent_seq = event.seq();
+                        position.advance(last_sent_seq);
                     }
                     Err(e) => {
                         tracing::warn!("Failed to encode firehose event: {e}");
@@ -157,20 +224,21 @@ async fn handle_subscribe<A, R, B>(
                 }
             }
             Err(RecvError::Lagged(n)) => {
-                tracing::warn!("Firehose subscriber lagged by {n} events");
-                // Send an info frame and continue — the subscriber missed some events.
-                if let Ok(info_frame) = wire::encode_info_frame(&InfoFrame {
-                    name: "OutdatedCursor".to_string(),
-                    message: Some(format!("Skipped {n} events due to slow consumption")),
+                tracing::warn!("Firehose subscriber lagged by {n} events, disconnecting");
+                // Events were dropped from the buffer. Rather than leave a gap,
+                // close the stream so the client reconnects with its last seq
+                // as the cursor and backfills from the event store.
+                if let Ok(frame) = wire::encode_error_frame(&ErrorFrame {
+                    error: "ConsumerTooSlow".to_string(),
+                    message: Some(format!(
+                        "Fell {n} events behind after seq {last_sent_seq}; \
+                         reconnect with a cursor to backfill"
+                    )),
                 }) {
-                    if sender
-                        .send(Message::Binary(info_frame.into()))
-                        .await
-                        .is_err()
-                    {
-                        break;
-                    }
+                    let _ = sender.send(Message::Binary(frame.into())).await;
                 }
+                let _ = sender.send(Message::Close(None)).await;
+                break;
             }
             Err(RecvError::Closed) => {
                 // Sequencer was dropped — server shutting down.
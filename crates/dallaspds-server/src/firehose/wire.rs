@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use super::events::{ErrorFrame, FirehoseEvent, InfoFrame};
 
@@ -9,12 +9,12 @@ use super::events::{ErrorFrame, FirehoseEvent, InfoFrame};
 ///   2. A DAG-CBOR body (the event payload).
 ///
 /// Both are concatenated into a single WebSocket binary frame.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct FrameHeader {
     /// 1 = message frame, -1 = error frame
     op: i32,
-    /// Event type tag (e.g. "#commit", "#identity", "#account", "#info")
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Event type tag (e.g. "#commit", "#identity", "#account", "#sync", "#info")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     t: Option<String>,
 }
 
@@ -33,6 +33,10 @@ pub fn encode_event_frame(event: &FirehoseEvent) -> Result<Vec<u8>, String> {
             "#account",
             dagcbor_encode(e)?,
         ),
+        FirehoseEvent::Sync(e) => (
+            "#sync",
+            dagcbor_encode(e)?,
+        ),
     };
 
     let header = FrameHeader {
@@ -47,6 +51,23 @@ pub fn encode_event_frame(event: &FirehoseEvent) -> Result<Vec<u8>, String> {
     Ok(frame)
 }
 
+/// Decode a frame produced by [`encode_event_frame`] back into an event.
+pub fn decode_event_frame(frame: &[u8]) -> Result<FirehoseEvent, String> {
+    let mut body = frame;
+    let header: FrameHeader = serde_ipld_dagcbor::de::from_reader_once(&mut body)
+        .map_err(|e| format!("DAG-CBOR decode error: {e}"))?;
+    if header.op != 1 {
+        return Err(format!("not a message frame (op {})", header.op));
+    }
+    match header.t.as_deref() {
+        Some("#commit") => dagcbor_decode(body).map(FirehoseEvent::Commit),
+        Some("#identity") => dagcbor_decode(body).map(FirehoseEvent::Identity),
+        Some("#account") => dagcbor_decode(body).map(FirehoseEvent::Account),
+        Some("#sync") => dagcbor_decode(body).map(FirehoseEvent::Sync),
+        other => Err(format!("unknown event type {other:?}")),
+    }
+}
+
 /// Encode an info frame for the firehose.
 pub fn encode_info_frame(info: &InfoFrame) -> Result<Vec<u8>, String> {
     let header = FrameHeader {
@@ -82,6 +103,11 @@ fn dagcbor_encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
     serde_ipld_dagcbor::to_vec(value).map_err(|e| format!("DAG-CBOR encode error: {e}"))
 }
 
+/// Helper: decode a value from DAG-CBOR bytes.
+fn dagcbor_decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    serde_ipld_dagcbor::from_slice(bytes).map_err(|e| format!("DAG-CBOR decode error: {e}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,6 +130,7 @@ mod tests {
                 cid: Some(CidLink {
                     link: "bafyrecidef456".to_string(),
                 }),
+                prev: None,
             }],
             blocks: vec![1, 2, 3],
         })
@@ -203,4 +230,21 @@ mod tests {
         assert_eq!(decoded.ops.len(), 1);
         assert_eq!(decoded.ops[0].action, "create");
     }
+
+    #[test]
+    fn decode_event_frame_round_trips() {
+        let frame = encode_event_frame(&make_commit_event()).unwrap();
+        let FirehoseEvent::Commit(decoded) = decode_event_frame(&frame).unwrap() else {
+            panic!("expected a commit event");
+        };
+        assert_eq!(decoded.repo, "did:plc:test");
+        assert_eq!(decoded.ops.len(), 1);
+
+        let error = encode_error_frame(&ErrorFrame {
+            error: "FutureCursor".to_string(),
+            message: None,
+        })
+        .unwrap();
+        assert!(decode_event_frame(&error).is_err());
+    }
 }
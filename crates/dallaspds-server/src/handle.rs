@@ -0,0 +1,78 @@
+use axum::http::StatusCode;
+use dallaspds_core::config::PdsConfig;
+
+use crate::error::XrpcError;
+
+/// Check that `handle` may be registered on this PDS: it must be a single
+/// DNS label under one of `available_user_domains` and not reserved.
+///
+/// Whether another account already holds it is up to the caller.
+pub fn check_local_handle(config: &PdsConfig, handle: &str) -> Result<(), XrpcError> {
+    let invalid = || {
+        XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidHandle",
+            format!(
+                "Handle must be a single name under one of: {}",
+                config.available_user_domains.join(", ")
+            ),
+        )
+    };
+
+    let label = config
+        .available_user_domains
+        .iter()
+        .find_map(|domain| handle.strip_suffix(domain.as_str()))
+        .ok_or_else(invalid)?;
+    if !is_dns_label(label) {
+        return Err(invalid());
+    }
+
+    if is_reserved(config, handle, label) {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "HandleNotAvailable",
+            format!("Handle {handle} is reserved"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// `true` if `handle`, whose name under the user domain is `label`, matches
+/// `reserved_handles` (as a whole handle or just the name) or starts with one
+/// of `reserved_handle_prefixes`. Case-insensitive.
+fn is_reserved(config: &PdsConfig, handle: &str, label: &str) -> bool {
+    let label = label.to_ascii_lowercase();
+    config.reserved_handles.iter().any(|reserved| {
+        reserved.eq_ignore_ascii_case(handle) || reserved.eq_ignore_ascii_case(&label)
+    }) || config
+        .reserved_handle_prefixes
+        .iter()
+        .any(|prefix| label.starts_with(&prefix.to_ascii_lowercase()))
+}
+
+/// `true` if `label` is a valid DNS label: 1-63 letters, digits and hyphens,
+/// not starting or ending with a hyphen.
+fn is_dns_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dns_labels() {
+        for label in ["alice", "a", "a-b", "x1"] {
+            assert!(is_dns_label(label), "{label}");
+        }
+        for label in ["", "-a", "a-", "a.b", "a_b", &"a".repeat(64)] {
+            assert!(!is_dns_label(label), "{label}");
+        }
+    }
+}
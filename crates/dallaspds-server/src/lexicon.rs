@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use axum::http::StatusCode;
+use dallaspds_core::config::PdsConfig;
+use dallaspds_core::{PdsError, PdsResult};
+use serde_json::Value;
+
+use crate::error::XrpcError;
+
+/// Record schemas loaded from lexicon JSON files, keyed by NSID.
+///
+/// Only the parts needed for write-time validation and read-time
+/// normalization are kept: a lexicon whose `main` definition is a `record`
+/// contributes its list of required fields and where it declares integers.
+#[derive(Debug, Default)]
+pub struct LexiconSet {
+    required: HashMap<String, Vec<String>>,
+    integers: HashMap<String, IntegerFields>,
+}
+
+/// The properties of an object schema that hold integers, directly or
+/// nested in inline objects and arrays. Referenced definitions are not
+/// followed.
+#[derive(Debug, Default)]
+pub struct IntegerFields(HashMap<String, IntegerField>);
+
+/// How a property in [`IntegerFields`] holds integers. Arrays are
+/// transparent: an array property is described by its items.
+#[derive(Debug)]
+pub enum IntegerField {
+    Integer,
+    Object(IntegerFields),
+}
+
+impl IntegerFields {
+    fn from_properties(properties: &Value) -> Self {
+        let fields = properties
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, schema)| Some((name.clone(), IntegerField::from_schema(schema)?)))
+            .collect();
+        Self(fields)
+    }
+
+    /// How the property `name` holds integers, if it does.
+    pub fn get(&self, name: &str) -> Option<&IntegerField> {
+        self.0.get(name)
+    }
+}
+
+impl IntegerField {
+    fn from_schema(schema: &Value) -> Option<Self> {
+        match schema["type"].as_str()? {
+            "integer" => Some(Self::Integer),
+            "array" => Self::from_schema(&schema["items"]),
+            "object" => {
+                let fields = IntegerFields::from_properties(&schema["properties"]);
+                (!fields.0.is_empty()).then_some(Self::Object(fields))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl LexiconSet {
+    /// Build the lexicon set described by the config.
+    ///
+    /// Returns `None` when neither `validate_records` nor `normalize_on_read`
+    /// is set. Without a `lexicon_dir` the set is empty: validation only
+    /// checks `$type` and normalization coerces no integers.
+    pub fn from_config(config: &PdsConfig) -> PdsResult<Option<Self>> {
+        if !config.validate_records && !config.normalize_on_read {
+            return Ok(None);
+        }
+        match &config.lexicon_dir {
+            Some(dir) => Self::load_dir(dir).map(Some),
+            None => Ok(Some(Self::default())),
+        }
+    }
+
+    /// Load every `*.json` lexicon under `dir`, recursing into subdirectories.
+    pub fn load_dir(dir: impl AsRef<Path>) -> PdsResult<Self> {
+        let mut set = Self::default();
+        set.load_dir_into(dir.as_ref())?;
+        Ok(set)
+    }
+
+    fn load_dir_into(&mut self, dir: &Path) -> PdsResult<()> {
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            PdsError::InternalError(format!("failed to read lexicon dir {}: {e}", dir.display()))
+        })?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| PdsError::InternalError(e.to_string()))?
+                .path();
+            if path.is_dir() {
+                self.load_dir_into(&path)?;
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                let data = std::fs::read(&path).map_err(|e| {
+                    PdsError::InternalError(format!("failed to read {}: {e}", path.display()))
+                })?;
+                let doc: Value = serde_json::from_slice(&data).map_err(|e| {
+                    PdsError::InternalError(format!("invalid lexicon {}: {e}", path.display()))
+                })?;
+                self.add_lexicon(&doc);
+            }
+        }
+        Ok(())
+    }
+
+    /// Register a single lexicon document. Non-record lexicons are ignored.
+    pub fn add_lexicon(&mut self, doc: &Value) {
+        let Some(id) = doc["id"].as_str() else {
+            return;
+        };
+        let main = &doc["defs"]["main"];
+        if main["type"] != "record" {
+            return;
+        }
+        let required = main["record"]["required"]
+            .as_array()
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter_map(|f| f.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.required.insert(id.to_string(), required);
+        self.integers.insert(
+            id.to_string(),
+            IntegerFields::from_properties(&main["record"]["properties"]),
+        );
+    }
+
+    /// Where records of `collection` hold integers, if a lexicon is loaded
+    /// for it.
+    pub fn integer_fields(&self, collection: &str) -> Option<&IntegerFields> {
+        self.integers.get(collection)
+    }
+
+    /// Check a record about to be written to `collection`.
+    ///
+    /// The record's `$type` must equal the collection, and if a lexicon is
+    /// loaded for the collection, all of its required fields must be present.
+    pub fn validate(&self, collection: &str, record: &Value) -> Result<(), XrpcError> {
+        let Some(record_type) = record.get("$type").and_then(|t| t.as_str()) else {
+            return Err(invalid_record(format!("record is missing $type for {collection}")));
+        };
+        if record_type != collection {
+            return Err(invalid_record(format!(
+                "record $type {record_type} does not match collection {collection}"
+            )));
+        }
+
+        if let Some(required) = self.required.get(collection) {
+            for field in required {
+                if record.get(field).is_none_or(Value::is_null) {
+                    return Err(invalid_record(format!(
+                        "record is missing required field {field}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid_record(message: String) -> XrpcError {
+    XrpcError::new(StatusCode::BAD_REQUEST, "InvalidRecord", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn post_lexicon() -> Value {
+        json!({
+            "lexicon": 1,
+            "id": "app.bsky.feed.post",
+            "defs": {
+                "main": {
+                    "type": "record",
+                    "key": "tid",
+                    "record": {
+                        "type": "object",
+                        "required": ["text", "createdAt"],
+                        "properties": {
+                            "text": { "type": "string" },
+                            "createdAt": { "type": "string", "format": "datetime" }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn accepts_valid_record() {
+        let mut set = LexiconSet::default();
+        set.add_lexicon(&post_lexicon());
+        let record = json!({
+            "$type": "app.bsky.feed.post",
+            "text": "hi",
+            "createdAt": "2025-01-01T00:00:00Z"
+        });
+        assert!(set.validate("app.bsky.feed.post", &record).is_ok());
+    }
+
+    #[test]
+    fn rejects_type_mismatch() {
+        let set = LexiconSet::default();
+        let record = json!({ "$type": "app.bsky.feed.like" });
+        let err = set.validate("app.bsky.feed.post", &record).unwrap_err();
+        assert_eq!(err.error_name, "InvalidRecord");
+        let err = set.validate("app.bsky.feed.post", &json!({})).unwrap_err();
+        assert_eq!(err.error_name, "InvalidRecord");
+    }
+
+    #[test]
+    fn rejects_missing_required_field() {
+        let mut set = LexiconSet::default();
+        set.add_lexicon(&post_lexicon());
+        let record = json!({ "$type": "app.bsky.feed.post", "text": "hi" });
+        let err = set.validate("app.bsky.feed.post", &record).unwrap_err();
+        assert_eq!(err.error_name, "InvalidRecord");
+        assert!(err.message.contains("createdAt"));
+    }
+
+    #[test]
+    fn unknown_collection_only_checks_type() {
+        let mut set = LexiconSet::default();
+        set.add_lexicon(&post_lexicon());
+        let record = json!({ "$type": "com.example.thing" });
+        assert!(set.validate("com.example.thing", &record).is_ok());
+    }
+
+    #[test]
+    fn load_dir_reads_nested_lexicons() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("app/bsky/feed");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(nested.join("post.json"), post_lexicon().to_string()).unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a lexicon").unwrap();
+
+        let set = LexiconSet::load_dir(dir.path()).unwrap();
+        let record = json!({ "$type": "app.bsky.feed.post", "text": "hi" });
+        assert!(set.validate("app.bsky.feed.post", &record).is_err());
+    }
+}
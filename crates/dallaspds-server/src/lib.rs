@@ -1,14 +1,30 @@
 pub mod admin_ui;
 pub mod auth;
+pub mod cleanup;
+pub mod did_doc;
 pub mod email;
 pub mod error;
+pub mod etag;
 pub mod firehose;
+pub mod handle;
+pub mod lexicon;
+pub mod limits;
+pub mod normalize;
+pub mod oauth_client;
 pub mod proxy;
+pub mod rate_limit;
+pub mod read_only;
+pub mod request_log;
 pub mod routes;
+pub mod shutdown;
 pub mod state;
+pub mod webhooks;
 
-pub use auth::{AdminAuth, AdminDids, AuthenticatedUser, JwtRefreshSecret, JwtSecret, OptionalAuth};
+pub use auth::{
+    AdminAuth, AdminDids, AuthenticatedUser, JwtAccessKeys, JwtRefreshSecret, OptionalAuth, WriterAuth,
+};
 pub use firehose::relay::{RelayNotifier, RelayNotifierWorker};
-pub use firehose::sequencer::Sequencer;
+pub use firehose::sequencer::{MemorySequencer, Sequencer, connect_sequencer};
 pub use routes::build_router;
 pub use state::AppState;
+pub use webhooks::{WebhookNotifier, WebhookWorker};
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum_server::accept::Accept;
+use dallaspds_core::config::{LimitsConfig, ServerConfig};
+use dallaspds_core::traits::RepoStore;
+use futures::future::{BoxFuture, FutureExt};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+
+use crate::error::XrpcError;
+
+/// Seconds a throttled client is asked to wait before retrying a write.
+const WRITE_RETRY_AFTER_SECS: u64 = 1;
+
+/// Serializes writes to each repo and bounds concurrent repo writes overall.
+///
+/// Writes to one repo run one at a time, so each reads the root the
+/// previous one committed; without this, two concurrent writes would build
+/// on the same root and the later commit would drop the earlier's record.
+/// Writes beyond `max_concurrent_writes` wait up to the configured queue
+/// timeout for a slot, then fail with `503 ServiceUnavailable`.
+pub struct WriteLimiter {
+    semaphore: Option<Arc<Semaphore>>,
+    queue_timeout: Duration,
+    /// DID -> lock held by the repo's current writer.
+    repo_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Held for the duration of a repo write; releases the repo and its slot
+/// on drop.
+pub struct WritePermit {
+    _repo: OwnedMutexGuard<()>,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl WriteLimiter {
+    pub fn new(config: &LimitsConfig) -> Self {
+        Self {
+            semaphore: (config.max_concurrent_writes > 0)
+                .then(|| Arc::new(Semaphore::new(config.max_concurrent_writes))),
+            queue_timeout: Duration::from_millis(config.write_queue_timeout_ms),
+            repo_locks: Mutex::default(),
+        }
+    }
+
+    /// Wait for exclusive access to `did`'s repo and a write slot, or return
+    /// a 503 with `Retry-After` if they don't free up within the queue
+    /// timeout.
+    ///
+    /// Take the permit before reading the repo root, and hold it until the
+    /// new root is committed.
+    pub async fn acquire(&self, did: &str) -> Result<WritePermit, XrpcError> {
+        let repo_lock = {
+            let mut locks = self.repo_locks.lock().unwrap();
+            // Drop locks nobody holds or waits on.
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(did.to_string()).or_default().clone()
+        };
+        // The repo comes first, so writers queued on one busy repo don't sit
+        // on slots that other repos could use.
+        let wait = async {
+            let repo = repo_lock.lock_owned().await;
+            let permit = match &self.semaphore {
+                Some(semaphore) => Some(semaphore.clone().acquire_owned().await.ok()?),
+                None => None,
+            };
+            Some(WritePermit {
+                _repo: repo,
+                _permit: permit,
+            })
+        };
+        match tokio::time::timeout(self.queue_timeout, wait).await {
+            Ok(Some(permit)) => Ok(permit),
+            _ => Err(XrpcError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "ServiceUnavailable",
+                "Too many concurrent writes, try again shortly",
+            )
+            .with_retry_after(WRITE_RETRY_AFTER_SECS)),
+        }
+    }
+}
+
+impl Default for WriteLimiter {
+    fn default() -> Self {
+        Self::new(&LimitsConfig::default())
+    }
+}
+
+/// Per-account cap on the bytes of a repo's stored blocks
+/// (`limits.max_repo_bytes`), separate from any blob limits.
+///
+/// An account's usage is read from the repo store once, then kept as a
+/// running total: commits add the bytes they stage, and a write that would
+/// exceed the quota recounts from the store before being refused. Writes
+/// don't each run an aggregate query.
+pub struct RepoQuota {
+    max_bytes: u64,
+    /// DID -> bytes of blocks stored, as far as we know.
+    usage: Mutex<HashMap<String, u64>>,
+}
+
+impl RepoQuota {
+    pub fn new(config: &LimitsConfig) -> Self {
+        Self {
+            max_bytes: config.max_repo_bytes,
+            usage: Mutex::default(),
+        }
+    }
+
+    /// Account for a commit storing `bytes` more of `did`'s blocks, or fail
+    /// with `400 RepoQuotaExceeded`.
+    ///
+    /// Stored blocks include every earlier version of the repo, so deleting
+    /// records only frees headroom once the repo is compacted
+    /// (`com.dallaspds.admin.compactRepo`), which calls [`Self::forget`].
+    pub async fn reserve<R: RepoStore>(
+        &self,
+        store: &Arc<R>,
+        did: &str,
+        bytes: u64,
+    ) -> Result<(), XrpcError> {
+        if self.max_bytes == 0 {
+            return Ok(());
+        }
+        let cached = self.usage.lock().unwrap().get(did).copied();
+        let used = match cached {
+            Some(used) => used,
+            None => store.get_storage_usage(did).await?.1,
+        };
+        if used + bytes <= self.max_bytes {
+            self.usage.lock().unwrap().insert(did.to_string(), used + bytes);
+            return Ok(());
+        }
+
+        // The running total may be stale; check again against the real figure.
+        let used = store.get_storage_usage(did).await?.1;
+        if used + bytes > self.max_bytes {
+            self.usage.lock().unwrap().insert(did.to_string(), used);
+            return Err(XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoQuotaExceeded",
+                format!(
+                    "Repo storage quota of {} bytes exceeded ({used} bytes used, {bytes} more requested)",
+                    self.max_bytes
+                ),
+            ));
+        }
+        self.usage.lock().unwrap().insert(did.to_string(), used + bytes);
+        Ok(())
+    }
+
+    /// Count `bytes` stored for `did` without checking the quota, for writes
+    /// such as deletes that must go through even when the repo is full.
+    pub fn add(&self, did: &str, bytes: u64) {
+        if let Some(used) = self.usage.lock().unwrap().get_mut(did) {
+            *used += bytes;
+        }
+    }
+
+    /// Drop `did`'s running total, e.g. after its blocks were replaced
+    /// wholesale, so the next write recounts from the store.
+    pub fn forget(&self, did: &str) {
+        self.usage.lock().unwrap().remove(did);
+    }
+}
+
+impl Default for RepoQuota {
+    fn default() -> Self {
+        Self::new(&LimitsConfig::default())
+    }
+}
+
+/// Apply the `[server]` settings to an HTTP server: cap HTTP/2 streams per
+/// connection and, if `max_connections` is set, the number of open
+/// connections.
+pub fn configure_server<A, Acc>(
+    server: axum_server::Server<A, Acc>,
+    config: &ServerConfig,
+) -> axum_server::Server<A, ConnectionLimitAcceptor<Acc>>
+where
+    A: axum_server::Address,
+{
+    let max_connections = config.max_connections;
+    let mut server = server.map(|inner| ConnectionLimitAcceptor::new(inner, max_connections));
+    server
+        .http_builder()
+        .http2()
+        .max_concurrent_streams(config.http2_max_concurrent_streams);
+    server
+}
+
+/// Acceptor that closes connections arriving while `max_connections` are
+/// already open, instead of queueing them.
+#[derive(Clone)]
+pub struct ConnectionLimitAcceptor<A> {
+    inner: A,
+    slots: Option<Arc<Semaphore>>,
+}
+
+impl<A> ConnectionLimitAcceptor<A> {
+    /// Wrap `inner`, allowing at most `max_connections` open connections
+    /// (0 means unlimited).
+    pub fn new(inner: A, max_connections: usize) -> Self {
+        Self {
+            inner,
+            slots: (max_connections > 0).then(|| Arc::new(Semaphore::new(max_connections))),
+        }
+    }
+}
+
+impl<A, I, S> Accept<I, S> for ConnectionLimitAcceptor<A>
+where
+    A: Accept<I, S>,
+    A::Stream: Send + 'static,
+    A::Service: Send + 'static,
+    A::Future: Send + 'static,
+{
+    type Stream = LimitedStream<A::Stream>;
+    type Service = A::Service;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let permit = match &self.slots {
+            Some(slots) => match slots.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    tracing::debug!("Connection limit reached; closing new connection");
+                    let error = io::Error::other("connection limit reached");
+                    return std::future::ready(Err(error)).boxed();
+                }
+            },
+            None => None,
+        };
+        self.inner
+            .accept(stream, service)
+            .map(|accepted| {
+                accepted.map(|(inner, service)| (LimitedStream { inner, _permit: permit }, service))
+            })
+            .boxed()
+    }
+}
+
+/// A connection's stream, holding its slot in the connection limit until
+/// it is dropped.
+pub struct LimitedStream<S> {
+    inner: S,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for LimitedStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for LimitedStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
@@ -0,0 +1,134 @@
+//! Read-time repair of legacy record values.
+//!
+//! Records written before validation was enabled may lack `$type` or carry
+//! whole numbers encoded as floats in integer fields, which the atproto data
+//! model does not allow. With `normalize_on_read` set, getRecord and
+//! listRecords return the repaired value; the stored block, and so the
+//! record's CID, is unchanged.
+
+use serde_json::{Map, Value};
+
+use crate::lexicon::{IntegerField, IntegerFields, LexiconSet};
+
+/// Largest magnitude at which every integer is exactly representable as an
+/// `f64` (2^53).
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Repair a record value read from `collection`: set its `$type` to the
+/// collection and turn floats with no fractional part into integers, in the
+/// fields the collection's lexicon declares as integers. Other numbers are
+/// left alone, so a collection with no loaded lexicon keeps its floats.
+pub fn normalize_record(lexicons: Option<&LexiconSet>, collection: &str, value: &mut Value) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    if map.get("$type").and_then(Value::as_str) != Some(collection) {
+        map.insert("$type".to_string(), Value::String(collection.to_string()));
+    }
+    if let Some(fields) = lexicons.and_then(|lexicons| lexicons.integer_fields(collection)) {
+        coerce_integers(fields, map);
+    }
+}
+
+fn coerce_integers(fields: &IntegerFields, map: &mut Map<String, Value>) {
+    for (name, value) in map.iter_mut() {
+        if let Some(field) = fields.get(name) {
+            coerce_field(field, value);
+        }
+    }
+}
+
+fn coerce_field(field: &IntegerField, value: &mut Value) {
+    match (field, value) {
+        (_, Value::Array(items)) => items.iter_mut().for_each(|item| coerce_field(field, item)),
+        (IntegerField::Object(fields), Value::Object(map)) => coerce_integers(fields, map),
+        (IntegerField::Integer, value) => {
+            if let Value::Number(n) = value
+                && n.is_f64()
+                && let Some(f) = n.as_f64()
+                && f.fract() == 0.0
+                && f.abs() <= MAX_SAFE_INTEGER
+            {
+                *value = Value::from(f as i64);
+            }
+        }
+        (IntegerField::Object(_), _) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lexicons() -> LexiconSet {
+        let mut lexicons = LexiconSet::default();
+        lexicons.add_lexicon(&json!({
+            "lexicon": 1,
+            "id": "com.example.score",
+            "defs": {
+                "main": {
+                    "type": "record",
+                    "record": {
+                        "type": "object",
+                        "properties": {
+                            "count": { "type": "integer" },
+                            "ratio": { "type": "number" },
+                            "sizes": { "type": "array", "items": { "type": "integer" } },
+                            "stats": {
+                                "type": "object",
+                                "properties": { "width": { "type": "integer" } }
+                            }
+                        }
+                    }
+                }
+            }
+        }));
+        lexicons
+    }
+
+    #[test]
+    fn adds_missing_type_and_coerces_declared_integers() {
+        let mut value = json!({
+            "count": 3.0,
+            "ratio": 2.0,
+            "sizes": [1.0, 2.5],
+            "stats": { "width": 640.0, "height": 480.0 },
+            "extra": 7.0,
+        });
+        normalize_record(Some(&lexicons()), "com.example.score", &mut value);
+        assert_eq!(
+            value,
+            json!({
+                "$type": "com.example.score",
+                "count": 3,
+                "ratio": 2.0,
+                "sizes": [1, 2.5],
+                "stats": { "width": 640, "height": 480.0 },
+                "extra": 7.0,
+            })
+        );
+        assert!(value["count"].is_i64());
+        assert!(value["ratio"].is_f64());
+        assert!(value["extra"].is_f64());
+    }
+
+    #[test]
+    fn leaves_numbers_of_unknown_collections_alone() {
+        let mut value = json!({ "count": 3.0 });
+        normalize_record(Some(&lexicons()), "app.bsky.feed.post", &mut value);
+        assert_eq!(value["$type"], "app.bsky.feed.post");
+        assert!(value["count"].is_f64());
+
+        let mut value = json!({ "count": 3.0 });
+        normalize_record(None, "com.example.score", &mut value);
+        assert!(value["count"].is_f64());
+    }
+
+    #[test]
+    fn replaces_a_mismatched_type() {
+        let mut value = json!({ "$type": "app.bsky.feed.like" });
+        normalize_record(None, "app.bsky.feed.post", &mut value);
+        assert_eq!(value["$type"], "app.bsky.feed.post");
+    }
+}
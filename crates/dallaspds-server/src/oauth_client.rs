@@ -0,0 +1,180 @@
+//! OAuth client metadata documents.
+//!
+//! An atproto OAuth client's `client_id` is the HTTPS URL of its metadata
+//! document, which lists the `redirect_uris` authorization codes may be sent
+//! to. The one exception is the development client `http://localhost`, which
+//! may only redirect to loopback addresses.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+/// How long a fetched metadata document is reused.
+pub const CLIENT_METADATA_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Most documents kept; the cache is cleared when it grows past this.
+const MAX_CACHED_CLIENTS: usize = 1_000;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The parts of a client metadata document this server checks.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientMetadata {
+    pub client_id: String,
+    pub redirect_uris: Vec<String>,
+}
+
+impl ClientMetadata {
+    /// Whether codes for this client may be sent to `redirect_uri`.
+    pub fn allows_redirect(&self, redirect_uri: &str) -> bool {
+        if !is_localhost_client(&self.client_id) {
+            return self.redirect_uris.iter().any(|uri| uri == redirect_uri);
+        }
+        // Loopback redirects may use any port (RFC 8252 section 7.3).
+        let Ok(uri) = reqwest::Url::parse(redirect_uri) else {
+            return false;
+        };
+        self.redirect_uris.iter().any(|allowed| {
+            reqwest::Url::parse(allowed).is_ok_and(|allowed| {
+                allowed.scheme() == uri.scheme()
+                    && allowed.host() == uri.host()
+                    && allowed.path() == uri.path()
+            })
+        })
+    }
+}
+
+/// Cache of `client_id` -> metadata document.
+pub struct ClientMetadataCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (ClientMetadata, Instant)>>,
+}
+
+impl ClientMetadataCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the cached document for `client_id` if it has not expired.
+    pub fn get(&self, client_id: &str) -> Option<ClientMetadata> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(client_id)
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < self.ttl)
+            .map(|(metadata, _)| metadata.clone())
+    }
+
+    pub fn insert(&self, metadata: ClientMetadata) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_CACHED_CLIENTS {
+            entries.clear();
+        }
+        entries.insert(metadata.client_id.clone(), (metadata, Instant::now()));
+    }
+}
+
+impl Default for ClientMetadataCache {
+    fn default() -> Self {
+        Self::new(CLIENT_METADATA_TTL)
+    }
+}
+
+/// Whether `client_id` is the loopback development client.
+fn is_localhost_client(client_id: &str) -> bool {
+    reqwest::Url::parse(client_id).is_ok_and(|url| {
+        url.scheme() == "http" && url.host_str() == Some("localhost") && url.port().is_none()
+    })
+}
+
+/// Look up the metadata for `client_id`, fetching it if it isn't cached.
+///
+/// Errors describe why the client can't be used, for the `invalid_client`
+/// response.
+pub async fn resolve_client_metadata(
+    cache: &ClientMetadataCache,
+    client_id: &str,
+) -> Result<ClientMetadata, String> {
+    if let Some(metadata) = cache.get(client_id) {
+        return Ok(metadata);
+    }
+
+    let url = reqwest::Url::parse(client_id).map_err(|_| "client_id is not a URL".to_string())?;
+    if is_localhost_client(client_id) {
+        // The development client declares its redirect URIs in client_id.
+        let mut redirect_uris: Vec<String> = url
+            .query_pairs()
+            .filter(|(key, _)| key == "redirect_uri")
+            .map(|(_, value)| value.into_owned())
+            .collect();
+        if redirect_uris.is_empty() {
+            redirect_uris = vec!["http://127.0.0.1/".to_string(), "http://[::1]/".to_string()];
+        }
+        if redirect_uris.iter().any(|uri| {
+            reqwest::Url::parse(uri)
+                .map(|uri| uri.scheme() != "http" || !matches!(uri.host_str(), Some("127.0.0.1" | "[::1]")))
+                .unwrap_or(true)
+        }) {
+            return Err("the localhost client may only redirect to loopback addresses".to_string());
+        }
+        return Ok(ClientMetadata {
+            client_id: client_id.to_string(),
+            redirect_uris,
+        });
+    }
+    if url.scheme() != "https" || url.host_str().is_none() {
+        return Err("client_id must be an https URL".to_string());
+    }
+
+    dallaspds_identity::public_http::ensure_public_url(&url).map_err(|e| e.to_string())?;
+    let client = dallaspds_identity::public_http::client_builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("failed to fetch client metadata: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("client metadata request returned {}", resp.status()));
+    }
+    let metadata: ClientMetadata = resp
+        .json()
+        .await
+        .map_err(|e| format!("invalid client metadata: {e}"))?;
+    if metadata.client_id != client_id {
+        return Err("client metadata is for a different client_id".to_string());
+    }
+
+    cache.insert(metadata.clone());
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn localhost_client_redirects_to_loopback_on_any_port() {
+        let cache = ClientMetadataCache::default();
+        let metadata = resolve_client_metadata(&cache, "http://localhost").await.unwrap();
+        assert!(metadata.allows_redirect("http://127.0.0.1:8080/"));
+        assert!(!metadata.allows_redirect("http://evil.example/"));
+
+        let client_id = "http://localhost?redirect_uri=http%3A%2F%2F127.0.0.1%2Fcallback";
+        let metadata = resolve_client_metadata(&cache, client_id).await.unwrap();
+        assert!(metadata.allows_redirect("http://127.0.0.1:1234/callback"));
+        assert!(!metadata.allows_redirect("http://127.0.0.1:1234/"));
+
+        let client_id = "http://localhost?redirect_uri=https%3A%2F%2Fevil.example%2F";
+        assert!(resolve_client_metadata(&cache, client_id).await.is_err());
+        assert!(resolve_client_metadata(&cache, "http://app.example/client.json").await.is_err());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod pipethrough;
 pub mod read_after_write;
+pub mod remote_blob;
+pub mod remote_record;
+pub mod response_cache;
 pub mod service_auth;
@@ -1,14 +1,18 @@
 
+use std::time::Duration;
+
 use axum::body::Body;
 use axum::extract::{Request, State};
-use axum::http::{StatusCode, header};
+use axum::http::{HeaderValue, Method, StatusCode, header};
 use axum::response::Response;
 
 use crate::auth::AuthenticatedUser;
 use crate::error::XrpcError;
 use crate::state::AppState;
+use dallaspds_core::PdsError;
 use dallaspds_core::traits::*;
 
+use super::response_cache::{AppViewCache, CachedResponse};
 use super::service_auth::create_service_auth_token;
 
 /// Proxy an XRPC request through to a configured AppView service.
@@ -36,7 +40,7 @@ where
         }
     };
 
-    let appview_did = state.config.appview_did.as_deref().unwrap_or("");
+    let appview_did = appview_audience(state.config.appview_did.as_deref(), &appview_url);
 
     // Extract the XRPC method from the path.
     let path = request.uri().path().to_string();
@@ -54,6 +58,23 @@ where
     );
 
     let http_method = request.method().clone();
+
+    // Serve idempotent queries from the cache when a fresh copy exists.
+    let cache_ttl = (http_method == Method::GET)
+        .then(|| state.appview_cache.ttl_for(method_name))
+        .flatten();
+    let cache_key = AppViewCache::key(
+        method_name,
+        request.uri().query().unwrap_or_default(),
+        user.as_ref().map(|u| u.did.as_str()),
+        request.headers(),
+    );
+    if let Some(ttl) = cache_ttl
+        && let Some((cached, age)) = state.appview_cache.get(&cache_key)
+    {
+        return cached_response(cached, age, ttl);
+    }
+
     let client = reqwest::Client::new();
     let mut builder = client.request(http_method.clone(), &upstream_url);
 
@@ -76,10 +97,10 @@ where
             .map_err(|e| XrpcError::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalServerError", e.to_string()))?;
 
         if let Some(account) = account {
-            let signing_key = dallaspds_crypto::SigningKey::from_bytes("p256", &account.signing_key)
+            let signing_key = dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key)
                 .map_err(|e| XrpcError::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalServerError", e.to_string()))?;
 
-            match create_service_auth_token(&signing_key, &user.did, appview_did, method_name) {
+            match create_service_auth_token(&signing_key, &user.did, &appview_did, method_name) {
                 Ok(token) => {
                     builder = builder.header("authorization", format!("Bearer {token}"));
                 }
@@ -98,11 +119,15 @@ where
         builder = builder.body(body_bytes.to_vec());
     }
 
-    // Send upstream request.
-    let upstream_resp = builder
-        .send()
-        .await
-        .map_err(|e| XrpcError::new(StatusCode::BAD_GATEWAY, "UpstreamFailure", e.to_string()))?;
+    // Send upstream request. Only GETs are safe to send again on failure.
+    let upstream_resp = if http_method == Method::GET {
+        dallaspds_identity::http_retry::send_with_retry(&state.config.http_retry, builder).await?
+    } else {
+        builder
+            .send()
+            .await
+            .map_err(|e| XrpcError::from(PdsError::from_upstream_request(e)))?
+    };
 
     // Convert upstream response back to axum response.
     let status = StatusCode::from_u16(upstream_resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
@@ -118,13 +143,56 @@ where
     let resp_body = upstream_resp
         .bytes()
         .await
-        .map_err(|e| XrpcError::new(StatusCode::BAD_GATEWAY, "UpstreamFailure", e.to_string()))?;
+        .map_err(|e| XrpcError::from(PdsError::from_upstream_request(e)))?;
+
+    if let Some(ttl) = cache_ttl
+        && status.is_success()
+        && let Some(headers) = response_builder.headers_ref()
+    {
+        let cached = CachedResponse {
+            status,
+            headers: headers.clone(),
+            body: resp_body.clone(),
+        };
+        state.appview_cache.insert(cache_key, cached, ttl);
+    }
 
     response_builder
         .body(Body::from(resp_body))
         .map_err(|e| XrpcError::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalServerError", e.to_string()))
 }
 
+/// Build the response for a cache hit, marking how old it is.
+fn cached_response(
+    cached: CachedResponse,
+    age: Duration,
+    ttl: Duration,
+) -> Result<Response, XrpcError> {
+    let mut response = Response::new(Body::from(cached.body));
+    *response.status_mut() = cached.status;
+    *response.headers_mut() = cached.headers;
+    let headers = response.headers_mut();
+    headers.insert(header::AGE, HeaderValue::from(age.as_secs()));
+    let cache_control = format!("private, max-age={}", ttl.as_secs());
+    if let Ok(value) = HeaderValue::from_str(&cache_control) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    Ok(response)
+}
+
+/// The audience for service auth tokens: the configured AppView DID, or the
+/// `did:web` of the AppView's host when none is configured.
+fn appview_audience(appview_did: Option<&str>, appview_url: &str) -> String {
+    if let Some(did) = appview_did {
+        return did.to_string();
+    }
+    let host = reqwest::Url::parse(appview_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    format!("did:web:{host}")
+}
+
 /// Fallback handler for the router. Extracts optional auth from the request
 /// headers and delegates to `pipethrough`.
 pub async fn pipethrough_fallback<A, R, B>(
@@ -152,11 +220,12 @@ where
     R: RepoStore,
     B: BlobStore,
 {
-    let auth_header = request
-        .headers()
-        .get("authorization")
-        .and_then(|v| v.to_str().ok())?;
-    let token = auth_header.strip_prefix("Bearer ")?;
-    let claims = dallaspds_crypto::jwt::validate_access_token(token, &state.config.jwt.access_secret).ok()?;
-    Some(AuthenticatedUser { did: claims.sub })
+    crate::auth::authenticate(
+        request.headers(),
+        request.method(),
+        request.uri(),
+        &state.access_token_keys,
+        request.extensions().get::<crate::auth::Dpop>(),
+    )
+    .ok()
 }
@@ -0,0 +1,180 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use bytes::Bytes;
+
+use dallaspds_core::PdsError;
+
+use crate::error::XrpcError;
+
+/// Proxied blobs larger than this are refused rather than buffered.
+pub const MAX_REMOTE_BLOB_BYTES: usize = 100 * 1024 * 1024;
+
+/// Served with every proxied blob so that content from another PDS can't run
+/// script on this origin, even if a browser renders it.
+pub const REMOTE_BLOB_CSP: &str = "default-src 'none'; sandbox";
+
+/// A blob fetched from another PDS, with its MIME type.
+#[derive(Clone)]
+pub struct RemoteBlob {
+    pub data: Bytes,
+    pub mime_type: String,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<(String, String), RemoteBlob>,
+    /// Keys in insertion order; the oldest is evicted first.
+    order: VecDeque<(String, String)>,
+    bytes: u64,
+}
+
+/// In-memory cache of proxied blobs, bounded by total size.
+///
+/// Blobs are content-addressed, so entries never go stale; they are only
+/// evicted, oldest first, to make room.
+#[derive(Default)]
+pub struct RemoteBlobCache {
+    capacity: u64,
+    inner: Mutex<CacheInner>,
+}
+
+impl RemoteBlobCache {
+    /// A cache holding up to `capacity` bytes; 0 disables it.
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    pub fn get(&self, did: &str, cid: &str) -> Option<RemoteBlob> {
+        let inner = self.inner.lock().unwrap();
+        inner.entries.get(&(did.to_string(), cid.to_string())).cloned()
+    }
+
+    pub fn insert(&self, did: &str, cid: &str, blob: RemoteBlob) {
+        let size = blob.data.len() as u64;
+        if self.capacity == 0 || size > self.capacity {
+            return;
+        }
+        let key = (did.to_string(), cid.to_string());
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.contains_key(&key) {
+            return;
+        }
+        while inner.bytes + size > self.capacity {
+            let Some(oldest) = inner.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = inner.entries.remove(&oldest) {
+                inner.bytes -= evicted.data.len() as u64;
+            }
+        }
+        inner.bytes += size;
+        inner.order.push_back(key.clone());
+        inner.entries.insert(key, blob);
+    }
+}
+
+/// Whether a proxied blob of `mime_type` may be displayed inline. Anything
+/// else is served as a download.
+pub fn is_inline_media(mime_type: &str) -> bool {
+    let Some((kind, subtype)) = mime_type.split_once('/') else {
+        return false;
+    };
+    match kind {
+        "image" => subtype != "svg+xml",
+        "video" | "audio" => true,
+        _ => false,
+    }
+}
+
+/// The MIME type to serve a remote blob as. The remote PDS's `Content-Type`
+/// is only kept for plain media; everything else, HTML included, becomes
+/// `application/octet-stream`.
+fn remote_mime_type(content_type: Option<&str>) -> String {
+    let essence = content_type
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let valid = essence
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"/+-.".contains(&b));
+    if valid && is_inline_media(&essence) {
+        essence
+    } else {
+        "application/octet-stream".to_string()
+    }
+}
+
+fn upstream_failure(message: impl Into<String>) -> XrpcError {
+    XrpcError::new(StatusCode::BAD_GATEWAY, "UpstreamFailure", message)
+}
+
+/// Fetch a blob from a remote PDS via `com.atproto.sync.getBlob`.
+///
+/// The bytes must hash to `cid`, so a misbehaving PDS can't serve (or get
+/// cached) content under a CID it doesn't match, and its `Content-Type` is
+/// only trusted for media. Upstream XRPC errors are passed through with their
+/// original status. `allow_private` is as for
+/// [`remote_pds_client`](super::remote_record::remote_pds_client).
+pub async fn fetch_remote_blob(
+    endpoint: &str,
+    allow_private: bool,
+    did: &str,
+    cid: &str,
+) -> Result<RemoteBlob, XrpcError> {
+    let expected = ipld_core::cid::Cid::try_from(cid).map_err(|e| {
+        XrpcError::new(StatusCode::BAD_REQUEST, "InvalidRequest", format!("invalid cid: {e}"))
+    })?;
+
+    let url = format!("{endpoint}/xrpc/com.atproto.sync.getBlob");
+    let mut resp = super::remote_record::remote_pds_client(endpoint, allow_private)?
+        .get(&url)
+        .query(&[("did", did), ("cid", cid)])
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| XrpcError::from(PdsError::from_upstream_request(e)))?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        let error = body["error"].as_str().unwrap_or("UpstreamFailure");
+        let message = body["message"].as_str().unwrap_or("remote getBlob failed");
+        return Err(XrpcError::new(status, error, message));
+    }
+
+    let mime_type = remote_mime_type(
+        resp.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok()),
+    );
+
+    let mut data = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| XrpcError::from(PdsError::from_upstream_request(e)))?
+    {
+        if data.len() + chunk.len() > MAX_REMOTE_BLOB_BYTES {
+            return Err(upstream_failure("remote blob is too large to proxy"));
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    let digest = <sha2::Sha256 as sha2::Digest>::digest(&data);
+    let matches = expected.hash().code() == 0x12 && expected.hash().digest() == digest.as_slice();
+    if !matches {
+        return Err(upstream_failure("remote blob does not match its cid"));
+    }
+
+    Ok(RemoteBlob {
+        data: Bytes::from(data),
+        mime_type,
+    })
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+use dallaspds_core::PdsError;
+use dallaspds_core::config::HttpRetryConfig;
+use serde_json::Value;
+
+use crate::error::XrpcError;
+
+/// How long a resolved PDS endpoint is reused before the DID is resolved again.
+pub const PDS_ENDPOINT_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Most endpoints kept; the cache is cleared when it grows past this.
+const MAX_CACHED_ENDPOINTS: usize = 10_000;
+
+/// Cache of DID -> PDS service endpoint, used when proxying reads for repos
+/// that are not hosted on this PDS.
+pub struct PdsEndpointCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl PdsEndpointCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the cached endpoint for `did` if it has not expired.
+    pub fn get(&self, did: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(did) {
+            Some((endpoint, cached_at)) if cached_at.elapsed() < self.ttl => {
+                Some(endpoint.clone())
+            }
+            Some(_) => {
+                entries.remove(did);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, did: &str, endpoint: String) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_CACHED_ENDPOINTS {
+            entries.clear();
+        }
+        entries.insert(did.to_string(), (endpoint, Instant::now()));
+    }
+}
+
+impl Default for PdsEndpointCache {
+    fn default() -> Self {
+        Self::new(PDS_ENDPOINT_CACHE_TTL)
+    }
+}
+
+/// Resolve the PDS endpoint for a DID, consulting the cache first.
+///
+/// Returns `None` if the DID document cannot be found or has no
+/// `#atproto_pds` service.
+pub async fn resolve_pds_endpoint(
+    cache: &PdsEndpointCache,
+    plc_url: &str,
+    retry: &HttpRetryConfig,
+    did: &str,
+) -> Result<Option<String>, XrpcError> {
+    if let Some(endpoint) = cache.get(did) {
+        return Ok(Some(endpoint));
+    }
+
+    let Some(doc) = dallaspds_identity::resolve_did_with_plc(did, plc_url, retry).await? else {
+        return Ok(None);
+    };
+    let Some(endpoint) = dallaspds_identity::pds_endpoint(&doc) else {
+        return Ok(None);
+    };
+
+    cache.insert(did, endpoint.clone());
+    Ok(Some(endpoint))
+}
+
+/// HTTP client for a PDS endpoint taken from a DID document. Unless
+/// `allow_private` (the `allow_private_endpoints` option) is set, the
+/// endpoint and anything it redirects to must be public addresses.
+pub fn remote_pds_client(endpoint: &str, allow_private: bool) -> Result<reqwest::Client, XrpcError> {
+    let builder = if allow_private {
+        reqwest::Client::builder()
+    } else {
+        let url = reqwest::Url::parse(endpoint).map_err(|e| {
+            XrpcError::from(PdsError::Upstream(format!("invalid PDS endpoint {endpoint}: {e}")))
+        })?;
+        dallaspds_identity::public_http::ensure_public_url(&url)?;
+        dallaspds_identity::public_http::client_builder()
+    };
+    builder
+        .build()
+        .map_err(|e| XrpcError::from(PdsError::InternalError(e.to_string())))
+}
+
+/// Fetch a record from a remote PDS via `com.atproto.repo.getRecord`.
+///
+/// Upstream XRPC errors are passed through with their original status.
+pub async fn fetch_remote_record(
+    endpoint: &str,
+    allow_private: bool,
+    repo: &str,
+    collection: &str,
+    rkey: &str,
+) -> Result<Value, XrpcError> {
+    let url = format!("{endpoint}/xrpc/com.atproto.repo.getRecord");
+    let query = [("repo", repo), ("collection", collection), ("rkey", rkey)];
+
+    let resp = remote_pds_client(endpoint, allow_private)?
+        .get(&url)
+        .query(&query)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| XrpcError::from(PdsError::from_upstream_request(e)))?;
+
+    let status = resp.status();
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| XrpcError::from(PdsError::from_upstream_request(e)))?;
+
+    if !status.is_success() {
+        let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        let error = body["error"].as_str().unwrap_or("UpstreamFailure");
+        let message = body["message"].as_str().unwrap_or("remote getRecord failed");
+        return Err(XrpcError::new(status, error, message));
+    }
+
+    Ok(body)
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::{HeaderMap, StatusCode};
+use bytes::Bytes;
+use dallaspds_core::config::AppViewCacheConfig;
+
+/// Request headers the AppView varies its responses on.
+const VARY_HEADERS: [&str; 2] = ["atproto-accept-labelers", "accept-language"];
+
+/// A proxied AppView response, as served to the client.
+#[derive(Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+struct Entry {
+    response: CachedResponse,
+    cached_at: Instant,
+    ttl: Duration,
+}
+
+/// Short-lived cache of successful AppView GET responses, keyed by method,
+/// query string, requesting DID and the headers in [`VARY_HEADERS`], so one
+/// account never sees another's view and labeler or language preferences
+/// aren't mixed up.
+pub struct AppViewCache {
+    default_ttl: Duration,
+    method_ttls: HashMap<String, Duration>,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl AppViewCache {
+    pub fn new(config: &AppViewCacheConfig) -> Self {
+        Self {
+            default_ttl: Duration::from_secs(config.ttl_secs),
+            method_ttls: config
+                .method_ttl_secs
+                .iter()
+                .map(|(method, secs)| (method.clone(), Duration::from_secs(*secs)))
+                .collect(),
+            max_entries: config.max_entries,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long responses for `method` are cached, or `None` if they aren't.
+    pub fn ttl_for(&self, method: &str) -> Option<Duration> {
+        let ttl = self.method_ttls.get(method).copied().unwrap_or(self.default_ttl);
+        (!ttl.is_zero()).then_some(ttl)
+    }
+
+    /// The cache key for a request.
+    pub fn key(method: &str, query: &str, did: Option<&str>, headers: &HeaderMap) -> String {
+        let mut key = format!("{method}?{query}#{}", did.unwrap_or_default());
+        for name in VARY_HEADERS {
+            key.push('\n');
+            let values = headers.get_all(name).iter().filter_map(|v| v.to_str().ok());
+            key.push_str(&values.collect::<Vec<_>>().join(","));
+        }
+        key
+    }
+
+    /// Return the cached response for `key` and its age, if it has not
+    /// expired.
+    pub fn get(&self, key: &str) -> Option<(CachedResponse, Duration)> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if entry.cached_at.elapsed() < entry.ttl => {
+                Some((entry.response.clone(), entry.cached_at.elapsed()))
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: String, response: CachedResponse, ttl: Duration) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries {
+            entries.retain(|_, entry| entry.cached_at.elapsed() < entry.ttl);
+            if entries.len() >= self.max_entries {
+                entries.clear();
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                response,
+                cached_at: Instant::now(),
+                ttl,
+            },
+        );
+    }
+}
+
+impl Default for AppViewCache {
+    fn default() -> Self {
+        Self::new(&AppViewCacheConfig::default())
+    }
+}
@@ -56,13 +56,65 @@ pub fn create_service_auth_token(
     Ok(format!("{signing_input}.{sig_b64}"))
 }
 
+/// Verify a service auth JWT presented to this server.
+///
+/// The token must be issued by `iss` (a DID, optionally with a `#fragment`),
+/// addressed to `aud`, scoped to `lxm`, unexpired, and signed by
+/// `signing_key`, the issuer's `#atproto` key as a `did:key`.
+pub fn verify_service_auth_token(
+    token: &str,
+    iss: &str,
+    signing_key: &str,
+    aud: &str,
+    lxm: &str,
+) -> PdsResult<ServiceAuthClaims> {
+    let invalid = |message: &str| PdsError::Auth(format!("invalid service auth token: {message}"));
+
+    let mut parts = token.split('.');
+    let (Some(header_b64), Some(claims_b64), Some(sig_b64), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("malformed JWT"));
+    };
+    let decode_json = |part: &str| -> PdsResult<serde_json::Value> {
+        let bytes = base64url_no_pad::decode(part).ok_or_else(|| invalid("bad base64"))?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid("bad JSON"))
+    };
+
+    let header = decode_json(header_b64)?;
+    if !matches!(header["alg"].as_str(), Some("ES256" | "ES256K")) {
+        return Err(invalid("unsupported alg"));
+    }
+    let claims: ServiceAuthClaims =
+        serde_json::from_value(decode_json(claims_b64)?).map_err(|_| invalid("missing claims"))?;
+    if claims.iss.split('#').next() != Some(iss) {
+        return Err(invalid("wrong issuer"));
+    }
+    if claims.aud != aud {
+        return Err(invalid("wrong audience"));
+    }
+    if claims.lxm != lxm {
+        return Err(invalid("wrong lxm"));
+    }
+    if claims.exp <= chrono::Utc::now().timestamp() {
+        return Err(invalid("expired"));
+    }
+
+    let signature = base64url_no_pad::decode(sig_b64).ok_or_else(|| invalid("bad signature"))?;
+    let signing_input = format!("{header_b64}.{claims_b64}");
+    if !dallaspds_crypto::verify_with_did_key(signing_key, signing_input.as_bytes(), &signature) {
+        return Err(invalid("bad signature"));
+    }
+    Ok(claims)
+}
+
 /// Base64url encode without padding (JWT standard).
-fn base64url_encode(data: &[u8]) -> String {
+pub(crate) fn base64url_encode(data: &[u8]) -> String {
     use base64url_no_pad::encode;
     encode(data)
 }
 
-/// Minimal base64url (no pad) encoder.
+/// Minimal base64url (no pad) encoder and decoder.
 mod base64url_no_pad {
     const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
 
@@ -85,4 +137,26 @@ mod base64url_no_pad {
         }
         result
     }
+
+    /// Decode unpadded base64url, or `None` if `data` isn't valid.
+    pub fn decode(data: &str) -> Option<Vec<u8>> {
+        let mut result = Vec::with_capacity(data.len() * 3 / 4);
+        let mut bits = 0u32;
+        let mut len = 0;
+        for byte in data.bytes() {
+            let value = ALPHABET.iter().position(|&c| c == byte)? as u32;
+            bits = (bits << 6) | value;
+            len += 6;
+            if len >= 8 {
+                len -= 8;
+                result.push((bits >> len) as u8);
+                bits &= (1 << len) - 1;
+            }
+        }
+        // Leftover bits must be zero padding, and a lone character is never valid.
+        if len >= 6 || bits & ((1 << len) - 1) != 0 {
+            return None;
+        }
+        Some(result)
+    }
 }
@@ -0,0 +1,236 @@
+//! Token-bucket rate limiting for XRPC requests.
+//!
+//! Each client gets one bucket per class of endpoint: a small one for the
+//! credential endpoints (`createSession`, `createAccount`, ...) and a larger
+//! one for everything else. Requests carrying a valid access token are
+//! counted against the account's DID, others against the client IP.
+//! Requests whose client can't be identified are not limited.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use dallaspds_core::config::RateLimitConfig;
+use dallaspds_core::traits::*;
+
+use crate::error::XrpcError;
+use crate::state::AppState;
+
+/// Endpoints that accept credentials, limited by `auth_per_minute`.
+const AUTH_ENDPOINTS: &[&str] = &[
+    "/xrpc/com.atproto.server.createAccount",
+    "/xrpc/com.atproto.server.createSession",
+    "/xrpc/com.atproto.server.refreshSession",
+    "/xrpc/com.atproto.server.confirmEmail",
+    "/xrpc/com.atproto.server.requestPasswordReset",
+    "/xrpc/com.atproto.server.resetPassword",
+    "/xrpc/com.atproto.server.deleteAccount",
+    "/oauth/par",
+    "/oauth/authorize",
+    "/oauth/token",
+];
+
+/// Buckets idle long enough to have refilled are dropped once the memory
+/// store holds more than this many.
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// Every bucket refills completely within a minute, so one untouched for
+/// this long is full and can be dropped. Also the least time between sweeps.
+const BUCKET_REFILL: Duration = Duration::from_secs(60);
+
+/// Storage for token buckets. The in-memory [`MemoryRateLimitStore`] limits
+/// each process separately; a shared store would limit across processes.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Take a token from `key`'s bucket, which holds `per_minute` tokens and
+    /// refills at that rate. If it is empty, return how long until the next
+    /// token is available.
+    async fn take(&self, key: &str, per_minute: u32) -> Result<(), Duration>;
+}
+
+/// Per-process [`RateLimitStore`].
+#[derive(Default)]
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Default)]
+struct Buckets {
+    by_key: HashMap<String, Bucket>,
+    last_sweep: Option<Instant>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn take(&self, key: &str, per_minute: u32) -> Result<(), Duration> {
+        let capacity = f64::from(per_minute);
+        let per_second = capacity / 60.0;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Sweeping is linear in the number of buckets, so it runs at most
+        // once per refill period however busy the server is.
+        if buckets.by_key.len() > MAX_IDLE_BUCKETS
+            && buckets
+                .last_sweep
+                .is_none_or(|swept| now.duration_since(swept) >= BUCKET_REFILL)
+        {
+            buckets
+                .by_key
+                .retain(|_, bucket| now.duration_since(bucket.updated) < BUCKET_REFILL);
+            buckets.last_sweep = Some(now);
+        }
+
+        let bucket = buckets.by_key.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Applies the `[rate_limit]` settings to incoming requests.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    /// A limiter keeping its buckets in memory.
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self::with_store(config, Arc::new(MemoryRateLimitStore::default()))
+    }
+
+    pub fn with_store(config: &RateLimitConfig, store: Arc<dyn RateLimitStore>) -> Self {
+        Self {
+            config: config.clone(),
+            store,
+        }
+    }
+
+    /// Charge one request to `client` for the endpoint at `path`, or return a
+    /// `429 RateLimitExceeded` error.
+    pub async fn check(&self, path: &str, client: &str) -> Result<(), XrpcError> {
+        let (class, per_minute) = if AUTH_ENDPOINTS.contains(&path) {
+            ("auth", self.config.auth_per_minute)
+        } else {
+            ("xrpc", self.config.xrpc_per_minute)
+        };
+        if per_minute == 0 {
+            return Ok(());
+        }
+
+        self.store
+            .take(&format!("{class}:{client}"), per_minute)
+            .await
+            .map_err(|wait| {
+                XrpcError::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "RateLimitExceeded",
+                    "Rate limit exceeded, try again later",
+                )
+                .with_retry_after(wait.as_secs_f64().ceil().max(1.0) as u64)
+            })
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(&RateLimitConfig::default())
+    }
+}
+
+/// Middleware enforcing [`AppState::rate_limiter`] on XRPC and OAuth
+/// requests.
+pub async fn rate_limit<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    request: Request,
+    next: Next,
+) -> Response
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let path = request.uri().path();
+    // The liveness probe touches nothing, so orchestrators may poll it
+    // freely; `/xrpc/_health` probes every store and is limited.
+    let limited = (path.starts_with("/xrpc/") || path.starts_with("/oauth/"))
+        && path != "/xrpc/_health/live";
+    if limited
+        && let Some(client) = client_key(&state, &request)
+        && let Err(e) = state.rate_limiter.check(path, &client).await
+    {
+        return e.into_response();
+    }
+    next.run(request).await
+}
+
+/// Identify the client: the DID of a valid bearer token, else its IP.
+fn client_key<A, R, B>(state: &AppState<A, R, B>, request: &Request) -> Option<String>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let headers = request.headers();
+    let did = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer ").or_else(|| v.strip_prefix("DPoP ")))
+        .and_then(|token| {
+            dallaspds_crypto::jwt::validate_access_token(token, &state.access_token_keys).ok()
+        })
+        .map(|claims| claims.sub);
+    if let Some(did) = did {
+        return Some(did);
+    }
+
+    let config = &state.config.rate_limit;
+    let forwarded = config
+        .trust_forwarded_for
+        .then(|| {
+            headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| forwarded_client(v, config.trusted_proxies))
+        })
+        .flatten();
+    forwarded
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|info| info.0.ip())
+        })
+        .map(|ip| ip.to_string())
+}
+
+/// The client address in an `X-Forwarded-For` value: the hop appended by the
+/// outermost of `trusted_proxies` proxies. Entries further left were sent by
+/// the client and can't be trusted.
+fn forwarded_client(header: &str, trusted_proxies: u32) -> Option<IpAddr> {
+    let hops: Vec<&str> = header.split(',').map(str::trim).collect();
+    let index = hops.len().checked_sub(trusted_proxies.max(1) as usize)?;
+    hops[index].parse().ok()
+}
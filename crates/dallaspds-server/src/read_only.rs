@@ -0,0 +1,51 @@
+//! Maintenance (read-only) mode.
+//!
+//! While it is on, handlers that change accounts, repos or blobs refuse with
+//! `503 ServiceReadOnly`; reads, sync and the firehose are unaffected. It
+//! starts from the `read_only` config option and can be flipped at runtime
+//! with `com.dallaspds.admin.setReadOnly`.
+//!
+//! The flag lives in process memory and is not shared through the database:
+//! when several instances serve the same stores, set `read_only` in every
+//! instance's config (or call `setReadOnly` on each) before maintenance.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use axum::http::StatusCode;
+
+use crate::error::XrpcError;
+
+/// Process-wide read-only flag, shared by every clone of the app state.
+#[derive(Clone, Default)]
+pub struct ReadOnly {
+    enabled: Arc<AtomicBool>,
+}
+
+impl ReadOnly {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Guard for mutating handlers: fails while read-only mode is on.
+    pub fn ensure_writable(&self) -> Result<(), XrpcError> {
+        if self.is_enabled() {
+            return Err(XrpcError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "ServiceReadOnly",
+                "This server is in read-only mode for maintenance; try again later",
+            ));
+        }
+        Ok(())
+    }
+}
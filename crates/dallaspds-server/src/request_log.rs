@@ -0,0 +1,39 @@
+use std::time::Instant;
+
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+
+/// Response header carrying the id assigned to each request.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Middleware that gives every request a fresh UUID, runs it inside a
+/// `request` span carrying that id (so everything logged while handling it,
+/// including [`XrpcError`](crate::error::XrpcError)s, can be correlated),
+/// logs the outcome, and echoes the id back in `x-request-id`.
+pub async fn log_requests(request: Request, next: Next) -> Response {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let started = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.in_scope(|| {
+        tracing::info!(
+            status = response.status().as_u16(),
+            latency_ms = started.elapsed().as_millis() as u64,
+            "request completed"
+        );
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
@@ -6,8 +6,9 @@ use serde::Deserialize;
 use crate::auth::{AdminAuth, AuthenticatedUser};
 use crate::error::XrpcError;
 use crate::state::AppState;
+use crate::webhooks::AccountEventKind;
 use dallaspds_core::traits::*;
-use dallaspds_core::PdsError;
+use dallaspds_core::{AccountSearchFilter, AccountStatus, PdsError, PdsResult};
 
 // ---------------------------------------------------------------------------
 // 1. deleteAccount
@@ -31,6 +32,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     // Verify the DID matches the authenticated user.
     if body.did != user.did {
         return Err(XrpcError::new(
@@ -47,32 +50,121 @@ where
         .await?
         .ok_or(PdsError::AccountNotFound)?;
 
-    let valid = dallaspds_crypto::verify_password(&body.password, &account.password_hash)
-        .map_err(|e| XrpcError::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalServerError", e.to_string()))?;
-    if !valid {
+    let verification = dallaspds_crypto::verify_password(
+        &body.password,
+        &account.password_hash,
+        &state.config.password,
+    )
+    .map_err(|e| XrpcError::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalServerError", e.to_string()))?;
+    if !verification.is_valid() {
         return Err(PdsError::InvalidPassword.into());
     }
 
-    // Delete associated data.
-    state.repo_store.delete_blocks_for_did(&user.did).await?;
-    state.account_store.delete_refresh_tokens_for_did(&user.did).await?;
-    state.account_store.delete_account(&user.did).await?;
+    let grace = state.config.account_deletion_grace_secs;
+    if grace == 0 {
+        purge_account(&state, &user.did).await?;
+        return Ok(StatusCode::OK);
+    }
 
-    // Emit account event.
+    // Schedule the deletion; the account stays deactivated until it is
+    // purged or the deletion is cancelled.
+    let delete_after = chrono::Utc::now() + chrono::Duration::seconds(grace as i64);
+    state
+        .account_store
+        .set_delete_after(&user.did, Some(delete_after))
+        .await?;
+    state.account_store.deactivate_account(&user.did).await?;
+    if let Some(ref webhooks) = state.webhooks {
+        webhooks.notify(AccountEventKind::Deactivate, &user.did);
+    }
+    emit_account_status(&state, &user.did, Some("deactivated")).await?;
+    tracing::info!(did = %user.did, %delete_after, "Scheduled account deletion");
+
+    Ok(StatusCode::OK)
+}
+
+/// Delete an account and its repo now, announcing the final rev and the
+/// tombstone on the firehose.
+pub async fn purge_account<A, R, B>(state: &AppState<A, R, B>, did: &str) -> PdsResult<()>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    // Keep writes out, so none commits on top of a repo being deleted.
+    let _write_permit = state
+        .write_limiter
+        .acquire(did)
+        .await
+        .map_err(|e| PdsError::InternalError(e.message))?;
+
+    // Capture the final commit before anything is removed so the firehose
+    // can advertise the last rev.
+    let final_commit = match state.account_store.get_repo_root(did).await? {
+        Some(root) if !root.cid.is_empty() => state
+            .repo_store
+            .get_block(did, &root.cid)
+            .await?
+            .map(|block| (root, block)),
+        _ => None,
+    };
+
+    // Delete the account first: this drops the repo root, so getRepo reports
+    // RepoNotFound instead of serving a partially deleted repo.
+    state.account_store.delete_refresh_tokens_for_did(did).await?;
+    state.account_store.delete_account(did).await?;
+    state.repo_store.delete_blocks_for_did(did).await?;
+    state.repo_quota.forget(did);
+
+    if let Some(ref webhooks) = state.webhooks {
+        webhooks.notify(AccountEventKind::Delete, did);
+    }
+
+    // Emit #sync with the final rev, then the account tombstone.
+    if let Some(ref sequencer) = state.sequencer {
+        use crate::firehose::events::{FirehoseEvent, SyncEvent};
+
+        if let Some((root, block)) = final_commit {
+            let commit_cid = ipld_core::cid::Cid::try_from(root.cid.as_slice())
+                .map_err(|e| PdsError::Storage(format!("invalid repo root CID: {e}")))?;
+            let blocks = dallaspds_repo::write_car(commit_cid, &[(commit_cid, block)])?;
+            let event = FirehoseEvent::Sync(SyncEvent {
+                seq: sequencer.next_seq().await?,
+                did: did.to_string(),
+                blocks,
+                rev: root.rev,
+                time: chrono::Utc::now().to_rfc3339(),
+            });
+            crate::firehose::emit::emit_and_persist(state, event).await;
+        }
+    }
+    emit_account_status(state, did, Some("deleted")).await
+}
+
+/// Emit an #account event: active if `status` is `None`, otherwise inactive
+/// with that status.
+async fn emit_account_status<A, R, B>(
+    state: &AppState<A, R, B>,
+    did: &str,
+    status: Option<&str>,
+) -> PdsResult<()>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::{AccountEvent, FirehoseEvent};
-        let seq = sequencer.next_seq();
         let event = FirehoseEvent::Account(AccountEvent {
-            seq,
-            did: user.did.clone(),
+            seq: sequencer.next_seq().await?,
+            did: did.to_string(),
             time: chrono::Utc::now().to_rfc3339(),
-            active: false,
-            status: Some("deleted".to_string()),
+            active: status.is_none(),
+            status: status.map(str::to_string),
         });
-        crate::firehose::emit::emit_and_persist(&state, event).await;
+        crate::firehose::emit::emit_and_persist(state, event).await;
     }
-
-    Ok(StatusCode::OK)
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
@@ -88,15 +180,21 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     state
         .account_store
         .deactivate_account(&user.did)
         .await?;
 
+    if let Some(ref webhooks) = state.webhooks {
+        webhooks.notify(AccountEventKind::Deactivate, &user.did);
+    }
+
     // Emit account event.
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::{AccountEvent, FirehoseEvent};
-        let seq = sequencer.next_seq();
+        let seq = sequencer.next_seq().await?;
         let event = FirehoseEvent::Account(AccountEvent {
             seq,
             did: user.did.clone(),
@@ -123,15 +221,52 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
+    // Reactivating must not leave the account queued for purging.
+    let account = state
+        .account_store
+        .get_account_by_did(&user.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+    if account.delete_after.is_some() {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            "account deletion is scheduled; cancel it with com.dallaspds.server.cancelAccountDeletion",
+        ));
+    }
+
+    // A migrated account has no repo until importRepo runs; going live
+    // without one would advertise an empty repo.
+    let repo_root = state.account_store.get_repo_root(&user.did).await?;
+    if repo_root.is_none_or(|root| root.cid.is_empty()) {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            "import a repo before activating the account",
+        ));
+    }
+
+    // Relays would reject (and users couldn't reach) a repo whose DID
+    // document points elsewhere. A single-user did:web document is ours.
+    if user.did != crate::did_doc::hostname_did_web(&state.config.hostname) {
+        crate::routes::server::check_did_document_points_here(&account, &state.config).await?;
+    }
+
     state
         .account_store
         .activate_account(&user.did)
         .await?;
 
+    if let Some(ref webhooks) = state.webhooks {
+        webhooks.notify(AccountEventKind::Activate, &user.did);
+    }
+
     // Emit account event.
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::{AccountEvent, FirehoseEvent};
-        let seq = sequencer.next_seq();
+        let seq = sequencer.next_seq().await?;
         let event = FirehoseEvent::Account(AccountEvent {
             seq,
             did: user.did.clone(),
@@ -196,6 +331,8 @@ where
 #[derive(Debug, Deserialize)]
 pub struct GetSubjectStatusQuery {
     pub did: String,
+    /// CID of one of the account's blobs, to ask about that blob instead.
+    pub blob: Option<String>,
 }
 
 pub async fn get_subject_status<A, R, B>(
@@ -214,6 +351,10 @@ where
         .await?
         .ok_or(PdsError::AccountNotFound)?;
 
+    if let Some(cid) = &params.blob {
+        return Ok(Json(blob_subject_status(&state, &account.did, cid).await?));
+    }
+
     let takedown = if account.takedown_ref.is_some() {
         serde_json::json!({
             "applied": true,
@@ -246,9 +387,12 @@ pub struct UpdateSubjectStatusRequest {
     pub takedown: Option<TakedownStatus>,
 }
 
+/// A `repoRef`, or a `repoBlobRef` when `cid` is set.
 #[derive(Debug, Deserialize)]
 pub struct SubjectRef {
     pub did: String,
+    #[serde(default)]
+    pub cid: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -278,18 +422,45 @@ where
         .await?
         .ok_or(PdsError::AccountNotFound)?;
 
+    if let Some(cid) = &body.subject.cid {
+        if !state.blob_store.has_blob(did, cid).await? {
+            return Err(XrpcError::new(
+                StatusCode::NOT_FOUND,
+                "BlobNotFound",
+                format!("blob not found: {cid}"),
+            ));
+        }
+        if let Some(takedown) = &body.takedown {
+            // The row is the takedown; a missing ref is stored as empty.
+            let takedown_ref = takedown
+                .applied
+                .then(|| takedown.r#ref.as_deref().unwrap_or_default());
+            state
+                .account_store
+                .set_blob_takedown(did, cid, takedown_ref)
+                .await?;
+            tracing::info!("Admin set takedown of blob {cid} ({did}) to {}", takedown.applied);
+        }
+        return Ok(Json(blob_subject_status(&state, did, cid).await?));
+    }
+
     // Update takedown status if provided
     if let Some(takedown) = body.takedown {
-        if takedown.applied {
+        let event = if takedown.applied {
             state
                 .account_store
                 .set_takedown(did, takedown.r#ref.as_deref())
                 .await?;
+            AccountEventKind::Takedown
         } else {
             state
                 .account_store
                 .set_takedown(did, None)
                 .await?;
+            AccountEventKind::ReverseTakedown
+        };
+        if let Some(ref webhooks) = state.webhooks {
+            webhooks.notify(event, did);
         }
     }
 
@@ -320,6 +491,32 @@ where
     })))
 }
 
+/// Subject status body for a single blob.
+async fn blob_subject_status<A, R, B>(
+    state: &AppState<A, R, B>,
+    did: &str,
+    cid: &str,
+) -> Result<serde_json::Value, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let takedown = match state.account_store.get_blob_takedown(did, cid).await? {
+        Some(takedown_ref) if takedown_ref.is_empty() => serde_json::json!({ "applied": true }),
+        Some(takedown_ref) => serde_json::json!({ "applied": true, "ref": takedown_ref }),
+        None => serde_json::json!({ "applied": false }),
+    };
+    Ok(serde_json::json!({
+        "subject": {
+            "$type": "com.atproto.admin.defs#repoBlobRef",
+            "did": did,
+            "cid": cid,
+        },
+        "takedown": takedown,
+    }))
+}
+
 // ---------------------------------------------------------------------------
 // 7. create_invite_code_endpoint
 // ---------------------------------------------------------------------------
@@ -450,6 +647,11 @@ pub struct ListAccountsQuery {
     pub cursor: Option<String>,
     #[serde(default)]
     pub limit: Option<usize>,
+    /// `active`, `deactivated`, or `takendown`.
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default, rename = "emailConfirmed")]
+    pub email_confirmed: Option<bool>,
 }
 
 pub async fn list_accounts_admin<A, R, B>(
@@ -463,14 +665,28 @@ where
     B: BlobStore,
 {
     let limit = params.limit.unwrap_or(50).min(100);
-    
+    let status = match params.status.as_deref() {
+        None => None,
+        Some("active") => Some(AccountStatus::Active),
+        Some("deactivated") => Some(AccountStatus::Deactivated),
+        Some("takendown") => Some(AccountStatus::Takendown),
+        Some(other) => {
+            return Err(XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidRequest",
+                format!("unknown status filter: {other}"),
+            ));
+        }
+    };
+    let filter = AccountSearchFilter {
+        query: params.query,
+        status,
+        email_confirmed: params.email_confirmed,
+    };
+
     let accounts = state
         .account_store
-        .search_accounts(
-            params.query.as_deref(),
-            params.cursor.as_deref(),
-            limit,
-        )
+        .search_accounts(&filter, params.cursor.as_deref(), limit)
         .await?;
 
     let cursor = if accounts.len() >= limit {
@@ -638,7 +854,486 @@ where
         "inviteRequired": config.invite_required,
         "appviewUrl": config.appview_url,
         "appviewDid": config.appview_did,
-        "relayUrl": config.relay_url,
+        "relayUrls": config.relay_url,
         "adminDids": config.admin_dids,
+        "readOnly": state.read_only.is_enabled(),
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 14. revoke_all_sessions
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeAllSessionsQuery {
+    pub did: String,
+}
+
+/// Force-logout an account by deleting every refresh token issued to it.
+///
+/// Access tokens already handed out stay valid until they expire.
+pub async fn revoke_all_sessions<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    _admin: AdminAuth,
+    Query(params): Query<RevokeAllSessionsQuery>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state
+        .account_store
+        .get_account_by_did(&params.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+
+    let revoked = state
+        .account_store
+        .delete_refresh_tokens_for_did(&params.did)
+        .await?;
+    tracing::info!("Admin revoked {revoked} sessions for {}", params.did);
+
+    Ok(Json(serde_json::json!({
+        "did": params.did,
+        "revoked": revoked,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 15. set_blob_mime
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBlobMimeRequest {
+    pub did: String,
+    pub cid: String,
+    pub mime_type: String,
+}
+
+/// Correct the MIME type a blob is served with, e.g. when the uploader's
+/// `Content-Type` disagrees with the `mimeType` its record declares. The
+/// blob bytes, and so its CID, are unchanged.
+pub async fn set_blob_mime<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    _admin: AdminAuth,
+    Json(body): Json<SetBlobMimeRequest>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    let mime_type = body.mime_type.trim().to_ascii_lowercase();
+    let valid = mime_type
+        .split_once('/')
+        .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty())
+        && mime_type.bytes().all(|b| b.is_ascii_graphic() || b == b' ');
+    if !valid {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            format!("invalid MIME type: {}", body.mime_type),
+        ));
+    }
+
+    let updated = state
+        .blob_store
+        .set_blob_mime(&body.did, &body.cid, &mime_type)
+        .await?;
+    if !updated {
+        return Err(XrpcError::new(
+            StatusCode::NOT_FOUND,
+            "BlobNotFound",
+            format!("blob not found: {}", body.cid),
+        ));
+    }
+    tracing::info!("Admin set MIME type of blob {} ({}) to {mime_type}", body.cid, body.did);
+
+    Ok(Json(serde_json::json!({
+        "did": body.did,
+        "cid": body.cid,
+        "mimeType": mime_type,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 16. verify_repo
+// ---------------------------------------------------------------------------
+
+fn account_signing_key(
+    account: &dallaspds_core::types::ActorAccount,
+) -> Result<dallaspds_crypto::SigningKey, PdsError> {
+    dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key)
+        .map_err(|e| PdsError::Storage(format!("failed to load signing key: {e}")))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyRepoQuery {
+    pub did: String,
+}
+
+/// Walk an account's repo from its current root, reporting blocks that are
+/// missing or fail to decode and whether the commit signature verifies
+/// against the account's signing key. Read-only; nothing is repaired.
+pub async fn verify_repo<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    _admin: AdminAuth,
+    Query(params): Query<VerifyRepoQuery>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let account = state
+        .account_store
+        .get_account_by_did(&params.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+    let root = state
+        .account_store
+        .get_repo_root(&params.did)
+        .await?
+        .filter(|root| !root.cid.is_empty())
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("repository not initialized for {}", params.did),
+            )
+        })?;
+    let signing_key = account_signing_key(&account)?;
+
+    let report =
+        dallaspds_repo::verify_repo(state.repo_store.clone(), &params.did, &root.cid, &signing_key)
+            .await?;
+    if !report.is_ok() {
+        tracing::warn!(
+            did = %params.did,
+            missing = report.missing.len(),
+            undecodable = report.undecodable.len(),
+            signature = ?report.signature,
+            "repo verification found problems"
+        );
+    }
+
+    let root_cid = dallaspds_repo::cid_from_bytes(&root.cid)
+        .map(|cid| cid.to_string())
+        .unwrap_or_default();
+    Ok(Json(serde_json::json!({
+        "did": params.did,
+        "root": root_cid,
+        "rev": root.rev,
+        "ok": report.is_ok(),
+        "blocksChecked": report.blocks_checked,
+        "missing": report.missing,
+        "undecodable": report.undecodable,
+        "signature": report.signature,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 17. rebuild_repo_root
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct RebuildRepoRootRequest {
+    pub did: String,
+}
+
+/// Disaster recovery for a lost or wrong `repo_root` row: scan the account's
+/// blocks for the newest commit signed by its key and point the repo root
+/// back at it.
+pub async fn rebuild_repo_root<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    _admin: AdminAuth,
+    Json(body): Json<RebuildRepoRootRequest>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    let account = state
+        .account_store
+        .get_account_by_did(&body.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+    let signing_key = account_signing_key(&account)?;
+
+    // Keep writes out while the root is being replaced.
+    let _write_permit = state.write_limiter.acquire(&body.did).await?;
+    let (cid, rev) =
+        dallaspds_repo::find_latest_commit(state.repo_store.clone(), &body.did, &signing_key)
+            .await?
+            .ok_or_else(|| {
+                tracing::error!("Admin rebuild of repo root for {} found no valid commit", body.did);
+                XrpcError::new(
+                    StatusCode::NOT_FOUND,
+                    "CommitNotFound",
+                    format!("no commit signed by the account key found for {}", body.did),
+                )
+            })?;
+
+    let previous = state.account_store.get_repo_root(&body.did).await?;
+    let previous_cid = previous.as_ref().map(|root| root.cid.as_slice()).unwrap_or_default();
+    state
+        .repo_store
+        .commit_blocks(&body.did, &[], previous_cid, &cid, &rev)
+        .await?;
+    state.repo_quota.forget(&body.did);
+
+    let cid_str = dallaspds_repo::cid_from_bytes(&cid)
+        .map(|cid| cid.to_string())
+        .unwrap_or_default();
+    let previous_rev = previous.map(|root| root.rev);
+    tracing::warn!(
+        "Admin rebuilt repo root for {}: now {cid_str} (rev {rev}), was rev {previous_rev:?}",
+        body.did
+    );
+
+    Ok(Json(serde_json::json!({
+        "did": body.did,
+        "cid": cid_str,
+        "rev": rev,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 18. reprovision_repo
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct ReprovisionRepoRequest {
+    pub did: String,
+}
+
+/// Initialize the repo of an account that never got one (an empty
+/// `repo_root`), as createAccount would have. Refuses to touch an account
+/// that already has a repo.
+pub async fn reprovision_repo<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    _admin: AdminAuth,
+    Json(body): Json<ReprovisionRepoRequest>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    let account = state
+        .account_store
+        .get_account_by_did(&body.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+    let signing_key = account_signing_key(&account)?;
+
+    let _write_permit = state.write_limiter.acquire(&body.did).await?;
+    let root = state.account_store.get_repo_root(&body.did).await?;
+    if root.is_some_and(|root| !root.cid.is_empty()) {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "RepoAlreadyExists",
+            format!("{} already has a repo", body.did),
+        ));
+    }
+
+    let staged = std::sync::Arc::new(dallaspds_repo::StagedRepoStore::new(
+        state.repo_store.clone(),
+        &body.did,
+    ));
+    let (cid, rev) = dallaspds_repo::create_repo(staged.clone(), &body.did, &signing_key).await?;
+    staged.commit(&[], &cid, &rev).await?;
+
+    let cid_str = dallaspds_repo::cid_from_bytes(&cid)
+        .map(|cid| cid.to_string())
+        .unwrap_or_default();
+    tracing::warn!("Admin provisioned a repo for {}: {cid_str} (rev {rev})", body.did);
+
+    Ok(Json(serde_json::json!({
+        "did": body.did,
+        "cid": cid_str,
+        "rev": rev,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 19. cancel_account_deletion
+// ---------------------------------------------------------------------------
+
+/// Cancel the caller's scheduled account deletion and reactivate the
+/// account.
+pub async fn cancel_account_deletion<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    let account = state
+        .account_store
+        .get_account_by_did(&user.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+    if account.delete_after.is_none() {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            "no account deletion is scheduled",
+        ));
+    }
+
+    state.account_store.set_delete_after(&user.did, None).await?;
+    state.account_store.activate_account(&user.did).await?;
+    if let Some(ref webhooks) = state.webhooks {
+        webhooks.notify(AccountEventKind::Activate, &user.did);
+    }
+    emit_account_status(&state, &user.did, None).await?;
+    tracing::info!(did = %user.did, "Cancelled scheduled account deletion");
+
+    Ok(StatusCode::OK)
+}
+
+// ---------------------------------------------------------------------------
+// 20. set_read_only
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetReadOnlyRequest {
+    pub read_only: bool,
+}
+
+/// Turn maintenance mode on or off. Lasts until the next restart, which goes
+/// back to the `read_only` config option. Only this instance is affected:
+/// instances sharing a database each need the call.
+pub async fn set_read_only<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    admin: AdminAuth,
+    Json(body): Json<SetReadOnlyRequest>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.set(body.read_only);
+    let action = if body.read_only { "enabled" } else { "disabled" };
+    tracing::warn!("Admin {} {action} read-only mode", admin.did);
+
+    Ok(Json(serde_json::json!({ "readOnly": body.read_only })))
+}
+
+// ---------------------------------------------------------------------------
+// 21. reindex_blob_refs
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct ReindexBlobRefsRequest {
+    pub did: String,
+}
+
+/// Record every blob referenced by the account's current records in the
+/// blob reference index, under the current rev. Backfills repos imported
+/// before importRepo indexed their blobs.
+pub async fn reindex_blob_refs<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    _admin: AdminAuth,
+    Json(body): Json<ReindexBlobRefsRequest>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    let root = state
+        .account_store
+        .get_repo_root(&body.did)
+        .await?
+        .filter(|root| !root.cid.is_empty())
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("no repo for {}", body.did),
+            )
+        })?;
+    let blob_refs =
+        dallaspds_repo::list_blob_refs(state.repo_store.clone(), &body.did, &root.cid).await?;
+    state
+        .repo_store
+        .add_blob_refs(&body.did, &blob_refs, &root.rev)
+        .await?;
+    tracing::info!("Admin reindexed {} blob refs for {}", blob_refs.len(), body.did);
+
+    Ok(Json(serde_json::json!({
+        "did": body.did,
+        "blobs": blob_refs.len(),
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 22. compact_repo
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct CompactRepoRequest {
+    pub did: String,
+}
+
+/// Delete the account's blocks that are not reachable from its current
+/// root, such as superseded MST nodes, deleted records and earlier commits.
+///
+/// This drops the repo's history, so older record versions and commits can
+/// no longer be fetched, and frees the space they held against
+/// `limits.max_repo_bytes`.
+pub async fn compact_repo<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    _admin: AdminAuth,
+    Json(body): Json<CompactRepoRequest>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    // Keep writes out so no block of a commit in progress is pruned.
+    let _write_permit = state.write_limiter.acquire(&body.did).await?;
+    let root = state
+        .account_store
+        .get_repo_root(&body.did)
+        .await?
+        .filter(|root| !root.cid.is_empty())
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("no repo for {}", body.did),
+            )
+        })?;
+    let removed =
+        dallaspds_repo::prune_unreachable(state.repo_store.clone(), &body.did, &root.cid).await?;
+    state.repo_quota.forget(&body.did);
+    tracing::info!("Admin compacted repo of {}: removed {removed} blocks", body.did);
+
+    Ok(Json(serde_json::json!({
+        "did": body.did,
+        "blocksRemoved": removed,
     })))
 }
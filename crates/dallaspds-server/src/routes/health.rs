@@ -1,3 +1,71 @@
-pub async fn health_check() -> axum::Json<serde_json::Value> {
-    axum::Json(serde_json::json!({"version": "0.1.0"}))
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::state::AppState;
+use dallaspds_core::traits::*;
+use dallaspds_core::PdsResult;
+
+const VERSION: &str = "0.1.0";
+
+/// How long a single store probe may take before it counts as unreachable.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Liveness: answers as long as the process is serving requests, without
+/// touching any store.
+pub async fn liveness_check() -> Json<Value> {
+    Json(json!({ "version": VERSION }))
+}
+
+/// Readiness: probes the account, repo and blob stores with cheap reads and
+/// returns 503 unless all of them answer.
+pub async fn health_check<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+) -> (StatusCode, Json<Value>)
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let (account, repo, blob) = tokio::join!(
+        probe("account", state.account_store.list_accounts(None, 1)),
+        probe("repo", state.repo_store.has_block("did:health:probe", &[])),
+        probe("blob", state.blob_store.has_blob("did:health:probe", "probe")),
+    );
+
+    let healthy = account && repo && blob;
+    let status = |ok: bool| if ok { "ok" } else { "unavailable" };
+    let body = json!({
+        "version": VERSION,
+        "checks": {
+            "accountStore": status(account),
+            "repoStore": status(repo),
+            "blobStore": status(blob),
+        },
+    });
+    let code = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(body))
+}
+
+/// Run one store probe, logging why it failed. The reason stays out of the
+/// response, which anyone can fetch.
+async fn probe<T>(store: &str, check: impl Future<Output = PdsResult<T>>) -> bool {
+    match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            tracing::warn!("health check: {store} store failed: {e}");
+            false
+        }
+        Err(_) => {
+            tracing::warn!("health check: {store} store timed out");
+            false
+        }
+    }
 }
@@ -8,7 +8,6 @@ use crate::auth::AuthenticatedUser;
 use crate::error::XrpcError;
 use crate::state::AppState;
 use dallaspds_core::traits::*;
-use dallaspds_core::PdsError;
 
 // ---------------------------------------------------------------------------
 // 1. resolveHandle
@@ -21,13 +20,15 @@ pub struct ResolveHandleQuery {
 
 pub async fn resolve_handle<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    Query(params): Query<ResolveHandleQuery>,
+    Query(mut params): Query<ResolveHandleQuery>,
 ) -> Result<Json<Value>, XrpcError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
+    params.handle = params.handle.to_ascii_lowercase();
+
     // Look up the handle in our account store first (for locally-hosted handles).
     let account = state
         .account_store
@@ -38,14 +39,39 @@ where
         return Ok(Json(json!({ "did": acct.did })));
     }
 
-    // Fallback to external resolution (DNS TXT / HTTPS).
-    match dallaspds_identity::resolve_handle(&params.handle).await {
-        Ok(Some(did)) => Ok(Json(json!({ "did": did }))),
-        _ => Err(XrpcError::new(
-            StatusCode::NOT_FOUND,
+    let not_found = || {
+        XrpcError::new(
+            StatusCode::BAD_REQUEST,
             "HandleNotFound",
             format!("handle not found: {}", params.handle),
-        )),
+        )
+    };
+
+    // We're authoritative for our own domains, so there's nothing to look up.
+    let is_local_domain = state
+        .config
+        .available_user_domains
+        .iter()
+        .any(|domain| params.handle.ends_with(domain));
+    if is_local_domain {
+        return Err(not_found());
+    }
+
+    // Fallback to external resolution (DNS TXT / HTTPS, or the configured
+    // resolver service). An error means we couldn't tell, not that the handle
+    // doesn't exist.
+    let resolved = match state.config.handle_resolver_url.as_deref() {
+        Some(url) => dallaspds_identity::resolve_handle_via(url, &params.handle).await,
+        None => dallaspds_identity::resolve_handle(&params.handle).await,
+    };
+    match resolved {
+        Ok(Some(did)) => Ok(Json(json!({ "did": did }))),
+        Ok(None) => Err(not_found()),
+        Err(e) => {
+            let mut err = XrpcError::from(e);
+            err.message = format!("could not resolve handle {}: {}", params.handle, err.message);
+            Err(err)
+        }
     }
 }
 
@@ -61,37 +87,28 @@ pub struct UpdateHandleRequest {
 pub async fn update_handle<A, R, B>(
     State(state): State<AppState<A, R, B>>,
     user: AuthenticatedUser,
-    Json(body): Json<UpdateHandleRequest>,
+    Json(mut body): Json<UpdateHandleRequest>,
 ) -> Result<StatusCode, XrpcError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
-    // Validate handle format — must end with one of the available user domains,
-    // or we'd need to verify DNS/HTTPS for external handles.
-    let handle_valid = state
-        .config
-        .available_user_domains
-        .iter()
-        .any(|domain| body.handle.ends_with(domain));
+    state.read_only.ensure_writable()?;
 
-    if !handle_valid {
-        return Err(XrpcError::new(
-            StatusCode::BAD_REQUEST,
-            "InvalidHandle",
-            format!(
-                "Handle must end with one of: {}",
-                state.config.available_user_domains.join(", ")
-            ),
-        ));
-    }
+    body.handle = body.handle.to_ascii_lowercase();
+
+    crate::handle::check_local_handle(&state.config, &body.handle)?;
 
     // Check handle isn't already taken by someone else.
-    if let Some(existing) = state.account_store.get_account_by_handle(&body.handle).await? {
-        if existing.did != user.did {
-            return Err(PdsError::HandleAlreadyTaken.into());
-        }
+    if let Some(existing) = state.account_store.get_account_by_handle(&body.handle).await?
+        && existing.did != user.did
+    {
+        return Err(XrpcError::new(
+            StatusCode::CONFLICT,
+            "HandleNotAvailable",
+            format!("Handle {} is already taken", body.handle),
+        ));
     }
 
     // Update the handle.
@@ -103,7 +120,7 @@ where
     // If we have a sequencer, emit an identity event.
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::{FirehoseEvent, IdentityEvent};
-        let seq = sequencer.next_seq();
+        let seq = sequencer.next_seq().await?;
         let event = FirehoseEvent::Identity(IdentityEvent {
             seq,
             did: user.did.clone(),
@@ -115,3 +132,225 @@ where
 
     Ok(StatusCode::OK)
 }
+
+// ---------------------------------------------------------------------------
+// 3. com.dallaspds.identity.getPlcOpLog — PLC operations this PDS signed
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct GetPlcOpLogQuery {
+    pub did: String,
+}
+
+/// Return the PLC operations this PDS signed for `did`, oldest first. Only
+/// the account itself and admins may read the log.
+pub async fn get_plc_op_log<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    user: AuthenticatedUser,
+    Query(params): Query<GetPlcOpLogQuery>,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    if user.did != params.did && !state.config.admin_dids.contains(&user.did) {
+        return Err(XrpcError::new(
+            StatusCode::FORBIDDEN,
+            "Forbidden",
+            "only the account or an admin may read its PLC operation log",
+        ));
+    }
+
+    let ops = state.account_store.list_plc_ops(&params.did).await?;
+    let operations: Vec<Value> = ops
+        .into_iter()
+        .map(|op| {
+            json!({
+                "cid": op.cid,
+                "operation": op.operation,
+                "createdAt": op.created_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({ "did": params.did, "operations": operations })))
+}
+
+// ---------------------------------------------------------------------------
+// 4. com.dallaspds.identity.updatePlc — sign and submit a PLC update
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePlcRequest {
+    /// The account password, re-checked because the update can hand the
+    /// identity to someone else.
+    pub password: String,
+    /// Replacement rotation keys, highest priority first.
+    pub rotation_keys: Option<Vec<String>>,
+    /// New `#atproto` verification key, e.g. a destination PDS's key.
+    pub verification_key: Option<String>,
+    /// New `#atproto_pds` service endpoint, e.g. when migrating.
+    pub pds_endpoint: Option<String>,
+    /// Confirm that `rotationKeys` leaves out this PDS's key, after which
+    /// this PDS can no longer update the DID.
+    #[serde(default)]
+    pub remove_pds_rotation_key: bool,
+}
+
+/// Update the account's PLC document, signed with the account key.
+///
+/// The password must be given again. The operation builds on the DID's
+/// latest operation as the PLC directory reports it, so operations signed
+/// elsewhere (e.g. with a recovery key) are respected. Fields left out keep
+/// their current value, and `alsoKnownAs` is always set to the account's
+/// current handle. The operation is logged once the directory accepts it.
+pub async fn update_plc<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    user: AuthenticatedUser,
+    Json(body): Json<UpdatePlcRequest>,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    let invalid = |message: String| XrpcError::new(StatusCode::BAD_REQUEST, "InvalidRequest", message);
+
+    if !user.did.starts_with("did:plc:") {
+        return Err(invalid(format!("{} is not a did:plc", user.did)));
+    }
+    let account = state
+        .account_store
+        .get_account_by_did(&user.did)
+        .await?
+        .ok_or(dallaspds_core::PdsError::AccountNotFound)?;
+    let verification = dallaspds_crypto::verify_password(
+        &body.password,
+        &account.password_hash,
+        &state.config.password,
+    )?;
+    if !verification.is_valid() {
+        return Err(dallaspds_core::PdsError::InvalidPassword.into());
+    }
+    let signing_key = dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key)?;
+    let pds_key = signing_key.did_key();
+
+    let plc_url = format!("{}/{}", state.config.plc_url.trim_end_matches('/'), user.did);
+    let current = fetch_last_plc_op(&state, &plc_url).await?;
+    if current["type"] != "plc_operation" {
+        return Err(invalid(format!(
+            "latest PLC operation of {} is a {}, which can't be updated",
+            user.did, current["type"]
+        )));
+    }
+    let prev = dallaspds_crypto::plc_operation_cid(&current)?;
+    let current_rotation_keys: Vec<String> = serde_json::from_value(current["rotationKeys"].clone())
+        .map_err(|e| invalid(format!("latest PLC operation has no rotation keys: {e}")))?;
+    if !current_rotation_keys.contains(&pds_key) {
+        return Err(invalid(format!(
+            "this PDS's key is no longer a rotation key of {}",
+            user.did
+        )));
+    }
+
+    let rotation_keys = body.rotation_keys.unwrap_or(current_rotation_keys);
+    if rotation_keys.is_empty() {
+        return Err(invalid("at least one rotation key is required".to_string()));
+    }
+    for key in &rotation_keys {
+        dallaspds_crypto::validate_did_key(key)?;
+    }
+    if !rotation_keys.contains(&pds_key) && !body.remove_pds_rotation_key {
+        return Err(invalid(
+            "rotationKeys leaves out this PDS's key; set removePdsRotationKey to confirm".to_string(),
+        ));
+    }
+    let verification_key = match body.verification_key {
+        Some(key) => {
+            dallaspds_crypto::validate_did_key(&key)?;
+            key
+        }
+        None => current["verificationMethods"]["atproto"]
+            .as_str()
+            .ok_or_else(|| invalid("latest PLC operation has no atproto key".to_string()))?
+            .to_string(),
+    };
+    let pds_endpoint = match body.pds_endpoint {
+        Some(endpoint) if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") => {
+            return Err(invalid(format!("invalid PDS endpoint: {endpoint}")));
+        }
+        Some(endpoint) => endpoint,
+        None => current["services"]["atproto_pds"]["endpoint"]
+            .as_str()
+            .ok_or_else(|| invalid("latest PLC operation has no PDS endpoint".to_string()))?
+            .to_string(),
+    };
+    let handle = account.handle.as_deref().unwrap_or(&account.did);
+
+    let op = dallaspds_crypto::create_did_plc_update_operation(
+        &signing_key,
+        &prev,
+        rotation_keys,
+        &verification_key,
+        handle,
+        &pds_endpoint,
+    )?;
+    let op_cid = dallaspds_crypto::plc_operation_cid(&op)?;
+
+    let request = reqwest::Client::new().post(&plc_url).json(&op);
+    let resp = dallaspds_identity::http_retry::send_with_retry(&state.config.http_retry, request).await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(XrpcError::new(
+            StatusCode::BAD_GATEWAY,
+            "PlcOperationFailed",
+            format!("PLC directory returned {status}: {text}"),
+        ));
+    }
+    state.account_store.append_plc_op(&user.did, &op_cid, &op).await?;
+
+    // Let relays and AppViews know the DID document changed.
+    if let Some(ref sequencer) = state.sequencer {
+        use crate::firehose::events::{FirehoseEvent, IdentityEvent};
+        let seq = sequencer.next_seq().await?;
+        let event = FirehoseEvent::Identity(IdentityEvent {
+            seq,
+            did: user.did.clone(),
+            time: chrono::Utc::now().to_rfc3339(),
+            handle: account.handle.clone(),
+        });
+        crate::firehose::emit::emit_and_persist(&state, event).await;
+    }
+
+    Ok(Json(json!({ "cid": op_cid, "operation": op })))
+}
+
+/// The latest operation of the DID at `plc_url`, from the PLC directory.
+async fn fetch_last_plc_op<A, R, B>(state: &AppState<A, R, B>, plc_url: &str) -> Result<Value, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let request = reqwest::Client::new().get(format!("{plc_url}/log/last"));
+    let resp = dallaspds_identity::http_retry::send_with_retry(&state.config.http_retry, request).await?;
+    if !resp.status().is_success() {
+        return Err(XrpcError::new(
+            StatusCode::BAD_GATEWAY,
+            "PlcOperationFailed",
+            format!("PLC directory returned {} for the latest operation", resp.status()),
+        ));
+    }
+    resp.json().await.map_err(|e| {
+        XrpcError::new(
+            StatusCode::BAD_GATEWAY,
+            "PlcOperationFailed",
+            format!("invalid latest PLC operation: {e}"),
+        )
+    })
+}
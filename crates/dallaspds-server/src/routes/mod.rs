@@ -9,23 +9,142 @@ pub mod well_known;
 
 use axum::Extension;
 
-use crate::auth::{AdminDids, JwtRefreshSecret, JwtSecret};
+use crate::auth::{AdminDids, Dpop, JwtAccessKeys, JwtRefreshSecret};
 use crate::state::AppState;
 use dallaspds_core::traits::*;
 
+/// Largest request body accepted by any route.
+pub const MAX_REQUEST_BODY_BYTES: usize = 10 * 1024 * 1024;
+
 pub fn build_router<A, R, B>(state: AppState<A, R, B>) -> axum::Router
 where
     A: AccountStore + Clone,
     R: RepoStore + Clone,
     B: BlobStore + Clone,
 {
-    let jwt_secret = JwtSecret(state.config.jwt.access_secret.clone());
+    let jwt_access_keys = JwtAccessKeys(state.access_token_keys.clone());
     let jwt_refresh_secret = JwtRefreshSecret(state.config.jwt.refresh_secret.clone());
     let admin_dids = AdminDids(state.config.admin_dids.clone());
+    let dpop = Dpop {
+        public_url: state.config.public_url.clone(),
+        replay: Default::default(),
+    };
+
+    // Admin and OAuth endpoints, which operators may restrict to their own
+    // origins.
+    let restricted = axum::Router::new()
+        // Admin endpoints
+        .route(
+            "/xrpc/com.atproto.admin.getAccountInfo",
+            axum::routing::get(admin::get_account_info::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.atproto.admin.getSubjectStatus",
+            axum::routing::get(admin::get_subject_status::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.atproto.admin.updateSubjectStatus",
+            axum::routing::post(admin::update_subject_status::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.atproto.server.createInviteCode",
+            axum::routing::post(admin::create_invite_code_endpoint::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.atproto.server.createInviteCodes",
+            axum::routing::post(admin::create_invite_codes_endpoint::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.listAccounts",
+            axum::routing::get(admin::list_accounts_admin::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.checkAdminStatus",
+            axum::routing::get(admin::check_admin_status::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.listInviteCodes",
+            axum::routing::get(admin::list_invite_codes_admin::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.getConfig",
+            axum::routing::get(admin::get_config::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.revokeAllSessions",
+            axum::routing::post(admin::revoke_all_sessions::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.setBlobMime",
+            axum::routing::post(admin::set_blob_mime::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.verifyRepo",
+            axum::routing::get(admin::verify_repo::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.rebuildRepoRoot",
+            axum::routing::post(admin::rebuild_repo_root::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.reprovisionRepo",
+            axum::routing::post(admin::reprovision_repo::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.setReadOnly",
+            axum::routing::post(admin::set_read_only::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.reindexBlobRefs",
+            axum::routing::post(admin::reindex_blob_refs::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.admin.compactRepo",
+            axum::routing::post(admin::compact_repo::<A, R, B>),
+        )
+        // OAuth operational endpoints
+        .route(
+            "/oauth/par",
+            axum::routing::post(oauth::oauth_par::<A, R, B>),
+        )
+        .route(
+            "/oauth/authorize",
+            axum::routing::get(oauth::oauth_authorize::<A, R, B>)
+                .post(oauth::oauth_authorize_submit::<A, R, B>),
+        )
+        .route(
+            "/oauth/token",
+            axum::routing::post(oauth::oauth_token::<A, R, B>),
+        )
+        .route(
+            "/oauth/revoke",
+            axum::routing::post(oauth::oauth_revoke::<A, R, B>),
+        )
+        .route(
+            "/oauth/jwks",
+            axum::routing::get(oauth::oauth_jwks::<A, R, B>),
+        )
+        // Admin UI (embedded SPA)
+        .route(
+            "/admin",
+            axum::routing::get(crate::admin_ui::admin_ui_handler),
+        )
+        .route(
+            "/admin/{*path}",
+            axum::routing::get(crate::admin_ui::admin_ui_handler),
+        )
+        .layer(cors_layer(&state.config.cors.allowed_origins));
 
     axum::Router::new()
         // Health
-        .route("/xrpc/_health", axum::routing::get(health::health_check))
+        .route(
+            "/xrpc/_health",
+            axum::routing::get(health::health_check::<A, R, B>),
+        )
+        .route(
+            "/xrpc/_health/live",
+            axum::routing::get(health::liveness_check),
+        )
         // Server endpoints
         .route(
             "/xrpc/com.atproto.server.describeServer",
@@ -51,6 +170,10 @@ where
             "/xrpc/com.atproto.server.deleteSession",
             axum::routing::post(server::delete_session::<A, R, B>),
         )
+        .route(
+            "/xrpc/com.dallaspds.server.deleteAllSessions",
+            axum::routing::post(server::delete_all_sessions::<A, R, B>),
+        )
         .route(
             "/xrpc/com.atproto.server.requestEmailConfirmation",
             axum::routing::post(server::request_email_confirmation::<A, R, B>),
@@ -75,59 +198,34 @@ where
             "/xrpc/com.atproto.server.updateEmail",
             axum::routing::post(server::update_email::<A, R, B>),
         )
-        // Account lifecycle
         .route(
-            "/xrpc/com.atproto.server.deleteAccount",
-            axum::routing::post(admin::delete_account::<A, R, B>),
-        )
-        .route(
-            "/xrpc/com.atproto.server.deactivateAccount",
-            axum::routing::post(admin::deactivate_account::<A, R, B>),
-        )
-        .route(
-            "/xrpc/com.atproto.server.activateAccount",
-            axum::routing::post(admin::activate_account::<A, R, B>),
+            "/xrpc/com.atproto.server.checkAccountStatus",
+            axum::routing::get(server::check_account_status::<A, R, B>),
         )
-        // Admin endpoints
         .route(
-            "/xrpc/com.atproto.admin.getAccountInfo",
-            axum::routing::get(admin::get_account_info::<A, R, B>),
-        )
-        .route(
-            "/xrpc/com.atproto.admin.getSubjectStatus",
-            axum::routing::get(admin::get_subject_status::<A, R, B>),
-        )
-        .route(
-            "/xrpc/com.atproto.admin.updateSubjectStatus",
-            axum::routing::post(admin::update_subject_status::<A, R, B>),
-        )
-        .route(
-            "/xrpc/com.atproto.server.createInviteCode",
-            axum::routing::post(admin::create_invite_code_endpoint::<A, R, B>),
-        )
-        .route(
-            "/xrpc/com.atproto.server.createInviteCodes",
-            axum::routing::post(admin::create_invite_codes_endpoint::<A, R, B>),
+            "/xrpc/com.dallaspds.server.getConfigPublic",
+            axum::routing::get(server::get_config_public::<A, R, B>),
         )
         .route(
             "/xrpc/com.atproto.server.getAccountInviteCodes",
             axum::routing::get(admin::get_account_invite_codes::<A, R, B>),
         )
+        // Account lifecycle
         .route(
-            "/xrpc/com.dallaspds.admin.listAccounts",
-            axum::routing::get(admin::list_accounts_admin::<A, R, B>),
+            "/xrpc/com.atproto.server.deleteAccount",
+            axum::routing::post(admin::delete_account::<A, R, B>),
         )
         .route(
-            "/xrpc/com.dallaspds.admin.checkAdminStatus",
-            axum::routing::get(admin::check_admin_status::<A, R, B>),
+            "/xrpc/com.atproto.server.deactivateAccount",
+            axum::routing::post(admin::deactivate_account::<A, R, B>),
         )
         .route(
-            "/xrpc/com.dallaspds.admin.listInviteCodes",
-            axum::routing::get(admin::list_invite_codes_admin::<A, R, B>),
+            "/xrpc/com.atproto.server.activateAccount",
+            axum::routing::post(admin::activate_account::<A, R, B>),
         )
         .route(
-            "/xrpc/com.dallaspds.admin.getConfig",
-            axum::routing::get(admin::get_config::<A, R, B>),
+            "/xrpc/com.dallaspds.server.cancelAccountDeletion",
+            axum::routing::post(admin::cancel_account_deletion::<A, R, B>),
         )
         // Repo endpoints
         .route(
@@ -162,11 +260,27 @@ where
             "/xrpc/com.atproto.repo.applyWrites",
             axum::routing::post(repo::apply_writes::<A, R, B>),
         )
+        .route(
+            "/xrpc/com.atproto.repo.importRepo",
+            axum::routing::post(repo::import_repo::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.repo.listAllRecords",
+            axum::routing::get(repo::list_all_records::<A, R, B>),
+        )
         // Sync endpoints
         .route(
             "/xrpc/com.atproto.sync.getRepo",
             axum::routing::get(sync::get_repo::<A, R, B>),
         )
+        .route(
+            "/xrpc/com.atproto.sync.getBlocks",
+            axum::routing::get(sync::get_blocks::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.atproto.sync.getRecord",
+            axum::routing::get(sync::get_record::<A, R, B>),
+        )
         .route(
             "/xrpc/com.atproto.sync.getLatestCommit",
             axum::routing::get(sync::get_latest_commit::<A, R, B>),
@@ -183,6 +297,14 @@ where
             "/xrpc/com.atproto.sync.listRepos",
             axum::routing::get(sync::list_repos::<A, R, B>),
         )
+        .route(
+            "/xrpc/com.atproto.sync.getRepoStatus",
+            axum::routing::get(sync::get_repo_status::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.sync.getCommit",
+            axum::routing::get(sync::get_commit::<A, R, B>),
+        )
         // Firehose WebSocket
         .route(
             "/xrpc/com.atproto.sync.subscribeRepos",
@@ -197,6 +319,14 @@ where
             "/xrpc/com.atproto.identity.updateHandle",
             axum::routing::post(identity::update_handle::<A, R, B>),
         )
+        .route(
+            "/xrpc/com.dallaspds.identity.getPlcOpLog",
+            axum::routing::get(identity::get_plc_op_log::<A, R, B>),
+        )
+        .route(
+            "/xrpc/com.dallaspds.identity.updatePlc",
+            axum::routing::post(identity::update_plc::<A, R, B>),
+        )
         // OAuth metadata endpoints
         .route(
             "/.well-known/oauth-authorization-server",
@@ -206,57 +336,60 @@ where
             "/.well-known/oauth-protected-resource",
             axum::routing::get(oauth::protected_resource_metadata::<A, R, B>),
         )
-        // OAuth operational endpoints (stubs)
-        .route(
-            "/oauth/par",
-            axum::routing::post(oauth::oauth_par::<A, R, B>),
-        )
-        .route(
-            "/oauth/authorize",
-            axum::routing::get(oauth::oauth_authorize::<A, R, B>),
-        )
-        .route(
-            "/oauth/token",
-            axum::routing::post(oauth::oauth_token::<A, R, B>),
-        )
-        .route(
-            "/oauth/revoke",
-            axum::routing::post(oauth::oauth_revoke::<A, R, B>),
-        )
-        .route(
-            "/oauth/jwks",
-            axum::routing::get(oauth::oauth_jwks::<A, R, B>),
-        )
         // Well-known
         .route(
             "/.well-known/atproto-did",
             axum::routing::get(well_known::atproto_did::<A, R, B>),
         )
-        // Admin UI (embedded SPA)
         .route(
-            "/admin",
-            axum::routing::get(crate::admin_ui::admin_ui_handler),
+            "/.well-known/did.json",
+            axum::routing::get(well_known::did_json::<A, R, B>),
         )
         .route(
-            "/admin/{*path}",
-            axum::routing::get(crate::admin_ui::admin_ui_handler),
+            "/user/{id}/did.json",
+            axum::routing::get(well_known::user_did_json::<A, R, B>),
         )
         // Fallback: proxy unknown XRPC methods to the configured AppView.
         .fallback(crate::proxy::pipethrough::pipethrough_fallback::<A, R, B>)
-        .layer(Extension(jwt_secret))
+        // CORS: allow any origin for XRPC (AT Protocol expects this).
+        .layer(cors_layer(&[]))
+        .merge(restricted)
+        .layer(Extension(jwt_access_keys))
         .layer(Extension(jwt_refresh_secret))
         .layer(Extension(admin_dids))
-        // CORS: allow any origin for XRPC (AT Protocol expects this).
-        .layer(
-            tower_http::cors::CorsLayer::new()
-                .allow_origin(tower_http::cors::Any)
-                .allow_methods(tower_http::cors::Any)
-                .allow_headers(tower_http::cors::Any)
-                .expose_headers(tower_http::cors::Any),
-        )
+        .layer(Extension(dpop))
         // Request body size limit: 10 MiB default.
         .layer(tower_http::limit::RequestBodyLimitLayer::new(
-            10 * 1024 * 1024,
+            MAX_REQUEST_BODY_BYTES,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::rate_limit::rate_limit::<A, R, B>,
         ))
+        // Outermost: tag each request with an id and log its outcome.
+        .layer(axum::middleware::from_fn(crate::request_log::log_requests))
         .with_state(state)
 }
+
+/// CORS for browser clients: any origin if `allowed_origins` is empty,
+/// otherwise only those listed.
+fn cors_layer(allowed_origins: &[String]) -> tower_http::cors::CorsLayer {
+    use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+    let allow_origin = if allowed_origins.is_empty() {
+        AllowOrigin::from(Any)
+    } else {
+        let origins = allowed_origins.iter().filter_map(|origin| {
+            origin
+                .parse()
+                .inspect_err(|_| tracing::warn!(origin, "ignoring invalid CORS origin"))
+                .ok()
+        });
+        AllowOrigin::list(origins)
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers(Any)
+}
@@ -1,11 +1,19 @@
-use axum::extract::State;
-use axum::http::StatusCode;
-use axum::Json;
+use axum::extract::{Form, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::{Extension, Json};
+use serde::Deserialize;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 
+use crate::auth::{Dpop, validate_dpop};
 use crate::error::XrpcError;
+use crate::oauth_client::resolve_client_metadata;
+use crate::proxy::service_auth::base64url_encode;
+use crate::routes::server::{issue_session, rotate_refresh_token, verify_login};
 use crate::state::AppState;
 use dallaspds_core::traits::*;
+use dallaspds_core::{OAuthRequest, PdsError};
 
 // ---------------------------------------------------------------------------
 // OAuth Authorization Server Metadata (RFC 8414)
@@ -39,7 +47,7 @@ where
         "subject_types_supported": ["public"],
         "token_endpoint_auth_methods_supported": ["none", "private_key_jwt"],
         "token_endpoint_auth_signing_alg_values_supported": ["ES256", "ES256K"],
-        "dpop_signing_alg_values_supported": ["ES256", "ES256K"],
+        "dpop_signing_alg_values_supported": ["ES256"],
         "code_challenge_methods_supported": ["S256"],
         "require_pushed_authorization_requests": true,
         "require_request_uri_registration": true,
@@ -69,62 +77,429 @@ where
         "authorization_servers": [resource],
         "scopes_supported": ["atproto", "transition:generic", "transition:chat.bsky"],
         "bearer_methods_supported": ["header"],
+        "dpop_signing_alg_values_supported": ["ES256"],
         "resource_documentation": "https://atproto.com",
     })))
 }
 
 // ---------------------------------------------------------------------------
-// Placeholder OAuth endpoints
+// Authorization code flow with PKCE
 //
-// Full OAuth DPoP + PAR implementation is complex. These stubs return
-// well-formed error responses so clients know the endpoints exist.
+// `oauth_par` stores the client's request, `oauth_authorize` logs the user in
+// and issues a single-use code, and `oauth_token` exchanges that code (after
+// checking the PKCE verifier) for a session. Every token request carries a
+// DPoP proof, and the access token is bound to the proof's key.
 // ---------------------------------------------------------------------------
 
+/// Prefix of the `request_uri` handles returned by PAR.
+const REQUEST_URI_PREFIX: &str = "urn:ietf:params:oauth:request_uri:req-";
+
+/// How long a pushed authorization request can be used.
+const PAR_TTL_SECS: i64 = 5 * 60;
+
+/// How long an authorization code can be exchanged.
+const CODE_TTL_SECS: i64 = 60;
+
+/// Lifetime of issued access tokens, matching `create_access_token`.
+const ACCESS_TOKEN_TTL_SECS: i64 = 2 * 60 * 60;
+
+/// An OAuth error response (RFC 6749 section 5.2).
+#[derive(Debug)]
+pub struct OAuthError {
+    status: StatusCode,
+    error: &'static str,
+    description: String,
+}
+
+impl OAuthError {
+    fn new(status: StatusCode, error: &'static str, description: impl Into<String>) -> Self {
+        Self {
+            status,
+            error,
+            description: description.into(),
+        }
+    }
+
+    fn invalid_request(description: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_request", description)
+    }
+
+    fn invalid_grant(description: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_grant", description)
+    }
+
+    fn invalid_dpop_proof(description: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_dpop_proof", description)
+    }
+}
+
+impl From<PdsError> for OAuthError {
+    fn from(err: PdsError) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", err.to_string())
+    }
+}
+
+impl From<XrpcError> for OAuthError {
+    fn from(err: XrpcError) -> Self {
+        Self::new(err.status, "server_error", err.message)
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let body = json!({ "error": self.error, "error_description": self.description });
+        (self.status, [(header::CACHE_CONTROL, "no-store")], Json(body)).into_response()
+    }
+}
+
+/// base64url(sha256(verifier)), the PKCE S256 transform.
+fn pkce_s256(verifier: &str) -> String {
+    base64url_encode(&Sha256::digest(verifier.as_bytes()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParRequest {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub response_type: String,
+    #[serde(default)]
+    pub scope: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+}
+
+/// POST /oauth/par
+///
+/// Store a pushed authorization request and return the `request_uri` the
+/// client then sends the user to `/oauth/authorize` with. `redirect_uri`
+/// must be listed in the client's metadata document.
 pub async fn oauth_par<A, R, B>(
-    State(_state): State<AppState<A, R, B>>,
-) -> Result<Json<Value>, XrpcError>
+    State(state): State<AppState<A, R, B>>,
+    Form(body): Form<ParRequest>,
+) -> Result<(StatusCode, Json<Value>), OAuthError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
-    Err(XrpcError::new(
-        StatusCode::NOT_IMPLEMENTED,
-        "NotImplemented",
-        "OAuth PAR endpoint not yet implemented",
+    if body.response_type != "code" {
+        return Err(OAuthError::new(
+            StatusCode::BAD_REQUEST,
+            "unsupported_response_type",
+            "Only the code response type is supported",
+        ));
+    }
+    if body.code_challenge_method != "S256" {
+        return Err(OAuthError::invalid_request("code_challenge_method must be S256"));
+    }
+    if body.code_challenge.is_empty() {
+        return Err(OAuthError::invalid_request("code_challenge is required"));
+    }
+    let client = resolve_client_metadata(&state.oauth_clients, &body.client_id)
+        .await
+        .map_err(|e| OAuthError::new(StatusCode::BAD_REQUEST, "invalid_client", e))?;
+    if !client.allows_redirect(&body.redirect_uri) {
+        return Err(OAuthError::invalid_request(
+            "redirect_uri is not one of the client's redirect_uris",
+        ));
+    }
+    let scope = body.scope.unwrap_or_else(|| "atproto".to_string());
+    if !scope.split_whitespace().any(|s| s == "atproto") {
+        return Err(OAuthError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_scope",
+            "scope must include atproto",
+        ));
+    }
+
+    let request_uri = format!("{REQUEST_URI_PREFIX}{}", hex::encode(rand::random::<[u8; 16]>()));
+    let request = OAuthRequest {
+        request_uri: request_uri.clone(),
+        client_id: body.client_id,
+        redirect_uri: body.redirect_uri,
+        scope,
+        state: body.state,
+        code_challenge: body.code_challenge,
+        did: None,
+        code: None,
+        expires_at: chrono::Utc::now() + chrono::Duration::seconds(PAR_TTL_SECS),
+    };
+    state.account_store.create_oauth_request(&request).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "request_uri": request_uri, "expires_in": PAR_TTL_SECS })),
     ))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AuthorizeQuery {
+    pub client_id: String,
+    pub request_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthorizeForm {
+    pub request_uri: String,
+    pub identifier: String,
+    pub password: String,
+}
+
+/// Load a pending (not yet approved, unexpired) request.
+async fn pending_request<A, R, B>(
+    state: &AppState<A, R, B>,
+    request_uri: &str,
+) -> Result<OAuthRequest, Response>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let request = state
+        .account_store
+        .get_oauth_request(request_uri)
+        .await
+        .map_err(|e| error_page(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()))?;
+    match request {
+        Some(request) if request.code.is_none() && request.expires_at > chrono::Utc::now() => {
+            Ok(request)
+        }
+        _ => Err(error_page(
+            StatusCode::BAD_REQUEST,
+            "This authorization request has expired. Return to the app and try again.",
+        )),
+    }
+}
+
+/// GET /oauth/authorize
+///
+/// Render the sign-in page for a pushed authorization request.
 pub async fn oauth_authorize<A, R, B>(
-    State(_state): State<AppState<A, R, B>>,
-) -> Result<Json<Value>, XrpcError>
+    State(state): State<AppState<A, R, B>>,
+    Query(params): Query<AuthorizeQuery>,
+) -> Response
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
-    Err(XrpcError::new(
-        StatusCode::NOT_IMPLEMENTED,
-        "NotImplemented",
-        "OAuth authorization endpoint not yet implemented",
-    ))
+    let request = match pending_request(&state, &params.request_uri).await {
+        Ok(request) => request,
+        Err(page) => return page,
+    };
+    if request.client_id != params.client_id {
+        return error_page(StatusCode::BAD_REQUEST, "client_id does not match the request");
+    }
+    login_page(StatusCode::OK, &request, None)
+}
+
+/// POST /oauth/authorize
+///
+/// Check the submitted credentials and, if they are valid, redirect back to
+/// the client with an authorization code.
+pub async fn oauth_authorize_submit<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    Form(form): Form<AuthorizeForm>,
+) -> Response
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let request = match pending_request(&state, &form.request_uri).await {
+        Ok(request) => request,
+        Err(page) => return page,
+    };
+    let account = match verify_login(&state, &form.identifier, &form.password).await {
+        Ok(account) => account,
+        Err(_) => {
+            return login_page(
+                StatusCode::UNAUTHORIZED,
+                &request,
+                Some("Invalid handle, email or password."),
+            );
+        }
+    };
+
+    let code = hex::encode(rand::random::<[u8; 32]>());
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(CODE_TTL_SECS);
+    match state
+        .account_store
+        .authorize_oauth_request(&request.request_uri, &account.did, &code, expires_at)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return error_page(StatusCode::BAD_REQUEST, "This request was already approved.");
+        }
+        Err(e) => return error_page(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+
+    let Ok(mut redirect) = reqwest::Url::parse(&request.redirect_uri) else {
+        return error_page(StatusCode::BAD_REQUEST, "Invalid redirect_uri");
+    };
+    {
+        let mut query = redirect.query_pairs_mut();
+        query.append_pair("code", &code);
+        if let Some(client_state) = &request.state {
+            query.append_pair("state", client_state);
+        }
+        query.append_pair("iss", &state.config.public_url);
+    }
+    Redirect::to(redirect.as_str()).into_response()
+}
+
+pub(crate) fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
 }
 
+fn error_page(status: StatusCode, message: &str) -> Response {
+    let body = format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><title>Sign in</title></head>\
+         <body><p>{}</p></body></html>",
+        html_escape(message)
+    );
+    (status, Html(body)).into_response()
+}
+
+fn login_page(status: StatusCode, request: &OAuthRequest, error: Option<&str>) -> Response {
+    let error = error
+        .map(|e| format!("<p role=\"alert\">{}</p>", html_escape(e)))
+        .unwrap_or_default();
+    let body = format!(
+        r#"<!doctype html>
+<html>
+<head><meta charset="utf-8"><title>Sign in</title></head>
+<body>
+<h1>Sign in</h1>
+<p><strong>{client}</strong> is asking for access to your account ({scope}).</p>
+{error}
+<form method="post" action="/oauth/authorize">
+<input type="hidden" name="request_uri" value="{request_uri}">
+<label>Handle or email <input name="identifier" autocomplete="username" required></label>
+<label>Password <input name="password" type="password" autocomplete="current-password" required></label>
+<button type="submit">Authorize</button>
+</form>
+</body>
+</html>
+"#,
+        client = html_escape(&request.client_id),
+        scope = html_escape(&request.scope),
+        request_uri = html_escape(&request.request_uri),
+    );
+    (status, Html(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub code_verifier: Option<String>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+/// POST /oauth/token
+///
+/// Exchange an authorization code (with its PKCE verifier) or a refresh
+/// token for a new access token and refresh token. The request must carry a
+/// DPoP proof; the access token is bound to its key.
 pub async fn oauth_token<A, R, B>(
-    State(_state): State<AppState<A, R, B>>,
-) -> Result<Json<Value>, XrpcError>
+    State(state): State<AppState<A, R, B>>,
+    Extension(dpop): Extension<Dpop>,
+    headers: HeaderMap,
+    Form(body): Form<TokenRequest>,
+) -> Result<Response, OAuthError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
-    Err(XrpcError::new(
-        StatusCode::NOT_IMPLEMENTED,
-        "NotImplemented",
-        "OAuth token endpoint not yet implemented",
-    ))
+    let proof = headers
+        .get("dpop")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| OAuthError::invalid_dpop_proof("DPoP proof is required"))?;
+    let token_url = format!("{}/oauth/token", state.config.public_url.trim_end_matches('/'));
+    let jkt = validate_dpop(proof, "POST", &token_url, None, &dpop.replay)
+        .map_err(|e| OAuthError::invalid_dpop_proof(e.to_string()))?;
+
+    let (did, scope, access_token, refresh_token) = match body.grant_type.as_str() {
+        "authorization_code" => {
+            let code = body.code.as_deref().ok_or_else(|| {
+                OAuthError::invalid_request("code is required")
+            })?;
+            let verifier = body.code_verifier.as_deref().ok_or_else(|| {
+                OAuthError::invalid_request("code_verifier is required")
+            })?;
+            let request = state
+                .account_store
+                .consume_oauth_code(code)
+                .await?
+                .ok_or_else(|| OAuthError::invalid_grant("Unknown or already used code"))?;
+            if request.expires_at <= chrono::Utc::now() {
+                return Err(OAuthError::invalid_grant("Code has expired"));
+            }
+            if body.client_id.as_deref() != Some(request.client_id.as_str()) {
+                return Err(OAuthError::invalid_grant("client_id does not match the code"));
+            }
+            if body.redirect_uri.as_deref() != Some(request.redirect_uri.as_str()) {
+                return Err(OAuthError::invalid_grant("redirect_uri does not match the code"));
+            }
+            if pkce_s256(verifier) != request.code_challenge {
+                return Err(OAuthError::invalid_grant("code_verifier does not match"));
+            }
+            let did = request
+                .did
+                .ok_or_else(|| OAuthError::invalid_grant("Code was never approved"))?;
+            let (access_token, refresh_token) = issue_session(&state, &did, Some(&jkt)).await?;
+            (did, request.scope, access_token, refresh_token)
+        }
+        "refresh_token" => {
+            let token = body.refresh_token.as_deref().ok_or_else(|| {
+                OAuthError::invalid_request("refresh_token is required")
+            })?;
+            let (account, access_token, refresh_token) =
+                rotate_refresh_token(&state, token, &state.config.jwt.refresh_secret, Some(&jkt))
+                    .await
+                    .map_err(|e| OAuthError::invalid_grant(e.message))?;
+            (account.did, "atproto".to_string(), access_token, refresh_token)
+        }
+        _ => {
+            return Err(OAuthError::new(
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+                "grant_type must be authorization_code or refresh_token",
+            ));
+        }
+    };
+
+    let body = json!({
+        "access_token": access_token,
+        "token_type": "DPoP",
+        "expires_in": ACCESS_TOKEN_TTL_SECS,
+        "refresh_token": refresh_token,
+        "scope": scope,
+        "sub": did,
+    });
+    Ok(([(header::CACHE_CONTROL, "no-store")], Json(body)).into_response())
 }
 
+// ---------------------------------------------------------------------------
+// Placeholder OAuth endpoints
+// ---------------------------------------------------------------------------
+
 pub async fn oauth_revoke<A, R, B>(
     State(_state): State<AppState<A, R, B>>,
 ) -> Result<Json<Value>, XrpcError>
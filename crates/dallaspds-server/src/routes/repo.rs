@@ -1,17 +1,21 @@
+use std::ops::Bound;
+
 use axum::body::Bytes;
 use axum::extract::{Query, State};
-use axum::http::{HeaderMap, StatusCode};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, WriterAuth};
 use crate::error::XrpcError;
+use crate::etag::{etag_for_cid, if_none_match, not_modified};
+use crate::lexicon::LexiconSet;
 use crate::state::AppState;
 use dallaspds_core::traits::*;
 use dallaspds_core::PdsError;
-use dallaspds_crypto::TidGenerator;
-use dallaspds_repo::cid_from_bytes;
+use dallaspds_repo::{StagedRepoStore, cid_from_bytes};
 
 /// Helper: convert raw CID bytes to a display string (base32lower CIDv1).
 fn cid_bytes_to_string(cid_bytes: &[u8]) -> Result<String, XrpcError> {
@@ -24,7 +28,7 @@ fn cid_bytes_to_string(cid_bytes: &[u8]) -> Result<String, XrpcError> {
 fn signing_key_from_account(
     account: &dallaspds_core::types::ActorAccount,
 ) -> Result<dallaspds_crypto::SigningKey, XrpcError> {
-    dallaspds_crypto::SigningKey::from_bytes("p256", &account.signing_key).map_err(|e| {
+    dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key).map_err(|e| {
         XrpcError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
             "InternalServerError",
@@ -51,21 +55,163 @@ async fn get_repo_root_bytes<A: AccountStore>(
     Ok(repo_root.cid)
 }
 
+/// Helper: validate a record against the loaded lexicons, if validation is enabled.
+///
+/// A request's `validate` flag overrides the server setting: `false` skips
+/// validation, and `true` checks at least the record's `$type` even when no
+/// lexicons are loaded.
+fn validate_record<A, R, B>(
+    state: &AppState<A, R, B>,
+    collection: &str,
+    record: &Value,
+    requested: Option<bool>,
+) -> Result<(), XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    if !requested.unwrap_or(state.config.validate_records) {
+        return Ok(());
+    }
+    match &state.lexicons {
+        Some(lexicons) => lexicons.validate(collection, record),
+        None => LexiconSet::default().validate(collection, record),
+    }
+}
+
+/// Helper: deserialize an optional field so that an explicit `null`
+/// (`Some(None)`) can be told apart from an absent field (`None`).
+fn deserialize_nullable<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(Some)
+}
+
+/// Helper: CIDs of every blob referenced anywhere in `record`.
+fn blob_cids(record: &Value) -> Vec<String> {
+    fn collect(value: &Value, cids: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if map.get("$type").and_then(Value::as_str) == Some("blob")
+                    && let Some(link) = map
+                        .get("ref")
+                        .and_then(|r| r.get("$link"))
+                        .and_then(Value::as_str)
+                {
+                    cids.push(link.to_string());
+                }
+                map.values().for_each(|v| collect(v, cids));
+            }
+            Value::Array(items) => items.iter().for_each(|v| collect(v, cids)),
+            _ => {}
+        }
+    }
+
+    let mut cids = Vec::new();
+    collect(record, &mut cids);
+    cids
+}
+
+/// Helper: enforce a `swapCommit` precondition against the current repo root.
+fn check_swap_commit(swap_commit: Option<&str>, current_root: &[u8]) -> Result<(), XrpcError> {
+    let Some(swap_cid) = swap_commit else {
+        return Ok(());
+    };
+    let current_cid_str = cid_bytes_to_string(current_root)?;
+    if swap_cid != current_cid_str {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidSwap",
+            format!("swap_commit mismatch: expected {swap_cid}, got {current_cid_str}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Helper: the CID (as a string) of the record currently at `collection/rkey`.
+async fn current_record_cid<R: RepoStore>(
+    repo_store: &std::sync::Arc<R>,
+    did: &str,
+    collection: &str,
+    rkey: &str,
+    current_root: &[u8],
+) -> Result<Option<String>, XrpcError> {
+    let existing = dallaspds_repo::get_record(
+        repo_store.clone(),
+        did,
+        collection,
+        rkey,
+        current_root,
+    )
+    .await?;
+    existing
+        .map(|record| cid_bytes_to_string(&record.cid))
+        .transpose()
+}
+
+/// Helper: compare a `swapRecord` precondition with the record's current CID.
+///
+/// `Some(None)` (an explicit `null`) requires that the record does not exist.
+fn ensure_swap_record(
+    swap_record: Option<Option<&str>>,
+    existing_cid: Option<&str>,
+) -> Result<(), XrpcError> {
+    let Some(expected) = swap_record else {
+        return Ok(());
+    };
+    if existing_cid != expected {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidSwap",
+            format!(
+                "swap_record mismatch: expected {}, got {}",
+                expected.unwrap_or("null"),
+                existing_cid.unwrap_or("null"),
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Helper: enforce a `swapRecord` precondition, looking up the record only
+/// when a precondition was given.
+async fn check_swap_record<R: RepoStore>(
+    repo_store: &std::sync::Arc<R>,
+    did: &str,
+    collection: &str,
+    rkey: &str,
+    swap_record: Option<Option<&str>>,
+    current_root: &[u8],
+) -> Result<(), XrpcError> {
+    if swap_record.is_none() {
+        return Ok(());
+    }
+    let existing_cid =
+        current_record_cid(repo_store, did, collection, rkey, current_root).await?;
+    ensure_swap_record(swap_record, existing_cid.as_deref())
+}
+
 // ---------------------------------------------------------------------------
 // 1. createRecord
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateRecordRequest {
     pub repo: String,
     pub collection: String,
     pub rkey: Option<String>,
     pub record: Value,
+    pub swap_commit: Option<String>,
+    /// Override lexicon validation for this write.
+    pub validate: Option<bool>,
 }
 
 pub async fn create_record<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    user: AuthenticatedUser,
+    user: WriterAuth,
     Json(body): Json<CreateRecordRequest>,
 ) -> Result<Json<Value>, XrpcError>
 where
@@ -73,6 +219,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     // Verify repo DID matches authenticated user.
     if body.repo != user.did {
         return Err(XrpcError::new(
@@ -82,6 +230,11 @@ where
         ));
     }
 
+    validate_record(&state, &body.collection, &body.record, body.validate)?;
+
+    // Take the repo's write lock (and a write slot) before reading its root.
+    let _write_permit = state.write_limiter.acquire(&user.did).await?;
+
     let account = state
         .account_store
         .get_account_by_did(&user.did)
@@ -90,31 +243,37 @@ where
 
     let signing_key = signing_key_from_account(&account)?;
     let current_root = get_repo_root_bytes(&*state.account_store, &user.did).await?;
-    let tid_gen = TidGenerator::new();
+    check_swap_commit(body.swap_commit.as_deref(), &current_root)?;
 
+    let staged = std::sync::Arc::new(StagedRepoStore::new(state.repo_store.clone(), &user.did));
     let output = dallaspds_repo::create_record(
-        state.repo_store.clone(),
+        staged.clone(),
         &user.did,
         &signing_key,
         &body.collection,
         body.rkey.as_deref(),
         &body.record,
-        &tid_gen,
+        &state.tid_gen,
         &current_root,
     )
     .await?;
 
-    // Update repo root after successful write.
+    // Store the new blocks and move the repo root in one transaction.
     let prev_root = current_root.clone();
     state
-        .account_store
-        .update_repo_root(&user.did, &output.new_root, &output.new_rev)
+        .repo_quota
+        .reserve(&state.repo_store, &user.did, staged.staged_bytes())
+        .await?;
+    staged.commit(&prev_root, &output.new_root, &output.new_rev).await?;
+    state
+        .repo_store
+        .add_blob_refs(&user.did, &blob_cids(&body.record), &output.new_rev)
         .await?;
 
     // Emit firehose event.
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::*;
-        let seq = sequencer.next_seq();
+        let seq = sequencer.next_seq().await?;
         let commit_cid_str = cid_bytes_to_string(&output.new_root).unwrap_or_default();
         let record_cid_str = cid_bytes_to_string(&output.cid).unwrap_or_default();
         let diff_car = dallaspds_repo::generate_diff_car(
@@ -138,8 +297,9 @@ where
             time: chrono::Utc::now().to_rfc3339(),
             ops: vec![RepoOp {
                 action: "create".to_string(),
-                path: format!("{}/{}", body.collection, body.rkey.as_deref().unwrap_or("")),
+                path: output.path().to_string(),
                 cid: Some(CidLink { link: record_cid_str }),
+                prev: None,
             }],
             blocks: diff_car,
         });
@@ -167,27 +327,60 @@ pub struct GetRecordQuery {
     pub repo: String,
     pub collection: String,
     pub rkey: String,
+    /// Fetch this exact version of the record instead of the current one.
+    pub cid: Option<String>,
 }
 
 pub async fn get_record<A, R, B>(
     State(state): State<AppState<A, R, B>>,
     Query(params): Query<GetRecordQuery>,
-) -> Result<Json<Value>, XrpcError>
+    headers: HeaderMap,
+) -> Result<Response, XrpcError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
-    let current_root = get_repo_root_bytes(&*state.account_store, &params.repo).await?;
+    // Repos hosted elsewhere are fetched from their own PDS.
+    if params.repo.starts_with("did:")
+        && state
+            .account_store
+            .get_account_by_did(&params.repo)
+            .await?
+            .is_none()
+    {
+        return get_remote_record(&state, &params)
+            .await
+            .map(|record| Json(record).into_response());
+    }
 
-    let record = dallaspds_repo::get_record(
-        state.repo_store.clone(),
-        &params.repo,
-        &params.collection,
-        &params.rkey,
-        &current_root,
-    )
-    .await?
+    let current_root = get_repo_root_bytes(&*state.account_store, &params.repo).await?;
+    let mut record = match &params.cid {
+        Some(cid) => {
+            let cid = ipld_core::cid::Cid::try_from(cid.as_str()).map_err(|e| {
+                XrpcError::new(StatusCode::BAD_REQUEST, "InvalidRequest", format!("invalid cid: {e}"))
+            })?;
+            dallaspds_repo::get_record_by_cid(
+                state.repo_store.clone(),
+                &params.repo,
+                &params.collection,
+                &params.rkey,
+                &cid,
+                &current_root,
+            )
+            .await?
+        }
+        None => {
+            dallaspds_repo::get_record(
+                state.repo_store.clone(),
+                &params.repo,
+                &params.collection,
+                &params.rkey,
+                &current_root,
+            )
+            .await?
+        }
+    }
     .ok_or_else(|| {
         XrpcError::new(
             StatusCode::BAD_REQUEST,
@@ -201,11 +394,65 @@ where
 
     let cid_string = cid_bytes_to_string(&record.cid)?;
 
-    Ok(Json(json!({
-        "uri": record.uri,
-        "cid": cid_string,
-        "value": record.value,
-    })))
+    // The record's CID identifies this exact version; a client that already
+    // has it needs nothing more.
+    let etag = etag_for_cid(&cid_string);
+    if if_none_match(&headers, &etag) {
+        return Ok(not_modified(&etag, None));
+    }
+    if state.config.normalize_on_read {
+        crate::normalize::normalize_record(
+            state.lexicons.as_deref(),
+            &params.collection,
+            &mut record.value,
+        );
+    }
+
+    Ok((
+        [(header::ETAG, etag)],
+        Json(json!({
+            "uri": record.uri,
+            "cid": cid_string,
+            "value": record.value,
+        })),
+    )
+        .into_response())
+}
+
+/// Proxy a getRecord for a repo that is not hosted on this PDS to the PDS
+/// listed in the repo's DID document.
+async fn get_remote_record<A, R, B>(
+    state: &AppState<A, R, B>,
+    params: &GetRecordQuery,
+) -> Result<Value, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let endpoint = crate::proxy::remote_record::resolve_pds_endpoint(
+        &state.pds_endpoint_cache,
+        &state.config.plc_url,
+        &state.config.http_retry,
+        &params.repo,
+    )
+    .await?
+    .ok_or_else(|| {
+        XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "RepoNotFound",
+            format!("could not resolve PDS for {}", params.repo),
+        )
+    })?;
+
+    crate::proxy::remote_record::fetch_remote_record(
+        &endpoint,
+        state.config.allow_private_endpoints,
+        &params.repo,
+        &params.collection,
+        &params.rkey,
+    )
+    .await
 }
 
 // ---------------------------------------------------------------------------
@@ -213,11 +460,18 @@ where
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ListRecordsQuery {
     pub repo: String,
     pub collection: String,
     pub limit: Option<usize>,
     pub cursor: Option<String>,
+    /// List in descending rkey order.
+    pub reverse: Option<bool>,
+    /// Lowest rkey to list, inclusive.
+    pub rkey_start: Option<String>,
+    /// Highest rkey to list, inclusive.
+    pub rkey_end: Option<String>,
 }
 
 pub async fn list_records<A, R, B>(
@@ -230,6 +484,33 @@ where
     B: BlobStore,
 {
     let limit = params.limit.unwrap_or(50).min(100);
+    let reverse = params.reverse.unwrap_or(false);
+
+    // A cursor is the rkey of the last record on the previous page. It need
+    // not still exist (it may have been deleted since), but it must be a valid
+    // rkey; anything else would silently restart the listing from the top.
+    if let Some(cursor) = params.cursor.as_deref()
+        && !dallaspds_repo::is_valid_rkey(cursor)
+    {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidCursor",
+            format!("malformed cursor: {cursor}"),
+        ));
+    }
+
+    // The cursor narrows whichever bound the listing runs from: the start
+    // going forwards, the end in reverse.
+    let mut start = params.rkey_start.as_deref().map_or(Bound::Unbounded, Bound::Included);
+    let mut end = params.rkey_end.as_deref().map_or(Bound::Unbounded, Bound::Included);
+    if let Some(cursor) = params.cursor.as_deref() {
+        if !reverse && params.rkey_start.as_deref().is_none_or(|s| s <= cursor) {
+            start = Bound::Excluded(cursor);
+        } else if reverse && params.rkey_end.as_deref().is_none_or(|e| e >= cursor) {
+            end = Bound::Excluded(cursor);
+        }
+    }
+
     let current_root = get_repo_root_bytes(&*state.account_store, &params.repo).await?;
 
     let records = dallaspds_repo::list_records(
@@ -237,7 +518,8 @@ where
         &params.repo,
         &params.collection,
         limit,
-        params.cursor.as_deref(),
+        (start, end),
+        reverse,
         &current_root,
     )
     .await?;
@@ -253,8 +535,15 @@ where
     };
 
     let record_values: Vec<Value> = records
-        .iter()
-        .map(|r| {
+        .into_iter()
+        .map(|mut r| {
+            if state.config.normalize_on_read {
+                crate::normalize::normalize_record(
+                    state.lexicons.as_deref(),
+                    &params.collection,
+                    &mut r.value,
+                );
+            }
             let cid_str = cid_bytes_to_string(&r.cid).unwrap_or_default();
             json!({
                 "uri": r.uri,
@@ -277,10 +566,14 @@ where
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DeleteRecordRequest {
     pub repo: String,
     pub collection: String,
     pub rkey: String,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub swap_record: Option<Option<String>>,
+    pub swap_commit: Option<String>,
 }
 
 pub async fn delete_record<A, R, B>(
@@ -293,6 +586,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     // Verify repo DID matches authenticated user.
     if body.repo != user.did {
         return Err(XrpcError::new(
@@ -302,6 +597,8 @@ where
         ));
     }
 
+    let _write_permit = state.write_limiter.acquire(&user.did).await?;
+
     let account = state
         .account_store
         .get_account_by_did(&user.did)
@@ -310,30 +607,43 @@ where
 
     let signing_key = signing_key_from_account(&account)?;
     let current_root = get_repo_root_bytes(&*state.account_store, &user.did).await?;
-    let tid_gen = TidGenerator::new();
+    check_swap_commit(body.swap_commit.as_deref(), &current_root)?;
+    check_swap_record(
+        &state.repo_store,
+        &user.did,
+        &body.collection,
+        &body.rkey,
+        body.swap_record.as_ref().map(Option::as_deref),
+        &current_root,
+    )
+    .await?;
 
     let prev_root = current_root.clone();
-    let (new_root, new_rev) = dallaspds_repo::delete_record(
-        state.repo_store.clone(),
+    let staged = std::sync::Arc::new(StagedRepoStore::new(state.repo_store.clone(), &user.did));
+    let dallaspds_repo::RecordDeleteOutput {
+        new_root,
+        new_rev,
+        deleted_cid,
+    } = dallaspds_repo::delete_record(
+        staged.clone(),
         &user.did,
         &signing_key,
         &body.collection,
         &body.rkey,
-        &tid_gen,
+        &state.tid_gen,
         &current_root,
     )
     .await?;
 
-    // Update repo root after successful write.
-    state
-        .account_store
-        .update_repo_root(&user.did, &new_root, &new_rev)
-        .await?;
+    // Store the new blocks and move the repo root in one transaction.
+    // Deletes are never held back by the quota.
+    state.repo_quota.add(&user.did, staged.staged_bytes());
+    staged.commit(&prev_root, &new_root, &new_rev).await?;
 
     // Emit firehose event.
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::*;
-        let seq = sequencer.next_seq();
+        let seq = sequencer.next_seq().await?;
         let commit_cid_str = cid_bytes_to_string(&new_root).unwrap_or_default();
         let diff_car = dallaspds_repo::generate_diff_car(
             state.repo_store.clone(),
@@ -358,6 +668,9 @@ where
                 action: "delete".to_string(),
                 path: format!("{}/{}", body.collection, body.rkey),
                 cid: None,
+                prev: Some(CidLink {
+                    link: cid_bytes_to_string(&deleted_cid).unwrap_or_default(),
+                }),
             }],
             blocks: diff_car,
         });
@@ -376,16 +689,22 @@ where
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PutRecordRequest {
     pub repo: String,
     pub collection: String,
     pub rkey: String,
     pub record: Value,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub swap_record: Option<Option<String>>,
+    pub swap_commit: Option<String>,
+    /// Override lexicon validation for this write.
+    pub validate: Option<bool>,
 }
 
 pub async fn put_record<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    user: AuthenticatedUser,
+    user: WriterAuth,
     Json(body): Json<PutRecordRequest>,
 ) -> Result<Json<Value>, XrpcError>
 where
@@ -393,6 +712,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     // Verify repo DID matches authenticated user.
     if body.repo != user.did {
         return Err(XrpcError::new(
@@ -402,6 +723,10 @@ where
         ));
     }
 
+    validate_record(&state, &body.collection, &body.record, body.validate)?;
+
+    let _write_permit = state.write_limiter.acquire(&user.did).await?;
+
     let account = state
         .account_store
         .get_account_by_did(&user.did)
@@ -410,31 +735,49 @@ where
 
     let signing_key = signing_key_from_account(&account)?;
     let current_root = get_repo_root_bytes(&*state.account_store, &user.did).await?;
-    let tid_gen = TidGenerator::new();
+    check_swap_commit(body.swap_commit.as_deref(), &current_root)?;
+    let existing_cid = current_record_cid(
+        &state.repo_store,
+        &user.did,
+        &body.collection,
+        &body.rkey,
+        &current_root,
+    )
+    .await?;
+    ensure_swap_record(
+        body.swap_record.as_ref().map(Option::as_deref),
+        existing_cid.as_deref(),
+    )?;
 
     let prev_root = current_root.clone();
+    let staged = std::sync::Arc::new(StagedRepoStore::new(state.repo_store.clone(), &user.did));
     let output = dallaspds_repo::put_record(
-        state.repo_store.clone(),
+        staged.clone(),
         &user.did,
         &signing_key,
         &body.collection,
         &body.rkey,
         &body.record,
-        &tid_gen,
+        &state.tid_gen,
         &current_root,
     )
     .await?;
 
-    // Update repo root after successful write.
+    // Store the new blocks and move the repo root in one transaction.
     state
-        .account_store
-        .update_repo_root(&user.did, &output.new_root, &output.new_rev)
+        .repo_quota
+        .reserve(&state.repo_store, &user.did, staged.staged_bytes())
+        .await?;
+    staged.commit(&prev_root, &output.new_root, &output.new_rev).await?;
+    state
+        .repo_store
+        .add_blob_refs(&user.did, &blob_cids(&body.record), &output.new_rev)
         .await?;
 
     // Emit firehose event.
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::*;
-        let seq = sequencer.next_seq();
+        let seq = sequencer.next_seq().await?;
         let commit_cid_str = cid_bytes_to_string(&output.new_root).unwrap_or_default();
         let record_cid_str = cid_bytes_to_string(&output.cid).unwrap_or_default();
         let diff_car = dallaspds_repo::generate_diff_car(
@@ -457,9 +800,10 @@ where
             rev: output.new_rev.clone(),
             time: chrono::Utc::now().to_rfc3339(),
             ops: vec![RepoOp {
-                action: "update".to_string(),
+                action: if existing_cid.is_some() { "update" } else { "create" }.to_string(),
                 path: format!("{}/{}", body.collection, body.rkey),
                 cid: Some(CidLink { link: record_cid_str }),
+                prev: None,
             }],
             blocks: diff_car,
         });
@@ -514,21 +858,7 @@ where
     let handle = account.handle.clone().unwrap_or_default();
     let did = account.did.clone();
 
-    // Build a minimal DID document.
-    let did_doc = json!({
-        "@context": [
-            "https://www.w3.org/ns/did/v1",
-            "https://w3id.org/security/multikey/v1",
-            "https://w3id.org/security/suites/secp256k1-2019/v1"
-        ],
-        "id": did,
-        "alsoKnownAs": [format!("at://{handle}")],
-        "service": [{
-            "id": "#atproto_pds",
-            "type": "AtprotoPersonalDataServer",
-            "serviceEndpoint": state.config.public_url,
-        }]
-    });
+    let did_doc = crate::did_doc::did_document(&account, &state.config.public_url);
 
     Ok(Json(json!({
         "handle": handle,
@@ -545,7 +875,7 @@ where
 
 pub async fn upload_blob<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    user: AuthenticatedUser,
+    user: WriterAuth,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Json<Value>, XrpcError>
@@ -554,6 +884,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     let content_type = headers
         .get("content-type")
         .and_then(|v| v.to_str().ok())
@@ -603,6 +935,8 @@ pub struct ApplyWritesRequest {
     pub repo: String,
     pub writes: Vec<ApplyWriteOp>,
     pub swap_commit: Option<String>,
+    /// Override lexicon validation for every write in the batch.
+    pub validate: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -629,7 +963,7 @@ pub enum ApplyWriteOp {
 
 pub async fn apply_writes<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    user: AuthenticatedUser,
+    user: WriterAuth,
     Json(body): Json<ApplyWritesRequest>,
 ) -> Result<Json<Value>, XrpcError>
 where
@@ -637,6 +971,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     // Verify repo DID matches authenticated user.
     if body.repo != user.did {
         return Err(XrpcError::new(
@@ -646,6 +982,64 @@ where
         ));
     }
 
+    let limits = &state.config.limits;
+    if body.writes.len() > limits.max_writes_per_apply {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            format!(
+                "Too many writes: {} exceeds the limit of {}",
+                body.writes.len(),
+                limits.max_writes_per_apply
+            ),
+        ));
+    }
+
+    // Validate every record up front so a bad write can't leave the batch
+    // half applied. Each path may be written at most once per batch;
+    // creates without an rkey get a fresh TID and can't collide.
+    let mut batch_bytes = 0;
+    let mut paths = std::collections::HashSet::new();
+    for write_op in &body.writes {
+        let path = match write_op {
+            ApplyWriteOp::Create { collection, rkey, .. } => {
+                rkey.as_ref().map(|rkey| format!("{collection}/{rkey}"))
+            }
+            ApplyWriteOp::Update { collection, rkey, .. }
+            | ApplyWriteOp::Delete { collection, rkey } => Some(format!("{collection}/{rkey}")),
+        };
+        if let Some(path) = path
+            && !paths.insert(path.clone())
+        {
+            return Err(XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidRequest",
+                format!("Conflicting writes to {path} in one batch"),
+            ));
+        }
+
+        match write_op {
+            ApplyWriteOp::Create { collection, value, .. }
+            | ApplyWriteOp::Update { collection, value, .. } => {
+                validate_record(&state, collection, value, body.validate)?;
+                batch_bytes += serde_json::to_vec(value).map_or(0, |bytes| bytes.len());
+            }
+            ApplyWriteOp::Delete { .. } => {}
+        }
+    }
+    if batch_bytes > limits.max_apply_writes_bytes {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            format!(
+                "Writes total {batch_bytes} bytes, over the limit of {}",
+                limits.max_apply_writes_bytes
+            ),
+        ));
+    }
+
+    let _write_permit = state.write_limiter.acquire(&user.did).await?;
+
     let account = state
         .account_store
         .get_account_by_did(&user.did)
@@ -656,22 +1050,13 @@ where
     let current_root = get_repo_root_bytes(&*state.account_store, &user.did).await?;
 
     // Validate swap_commit if provided.
-    if let Some(ref swap_cid) = body.swap_commit {
-        let current_cid_str = cid_bytes_to_string(&current_root)?;
-        if *swap_cid != current_cid_str {
-            return Err(XrpcError::new(
-                StatusCode::BAD_REQUEST,
-                "InvalidSwap",
-                format!(
-                    "swap_commit mismatch: expected {swap_cid}, got {current_cid_str}"
-                ),
-            ));
-        }
-    }
+    check_swap_commit(body.swap_commit.as_deref(), &current_root)?;
 
-    let tid_gen = TidGenerator::new();
+    // Every op's blocks stay staged until the batch commits as a whole.
     let prev_root = current_root.clone();
+    let staged = std::sync::Arc::new(StagedRepoStore::new(state.repo_store.clone(), &user.did));
     let mut running_root = current_root;
+    let mut running_rev = None;
     let mut ops = Vec::new();
     let mut results = Vec::new();
 
@@ -683,37 +1068,33 @@ where
                 value,
             } => {
                 let output = dallaspds_repo::create_record(
-                    state.repo_store.clone(),
+                    staged.clone(),
                     &user.did,
                     &signing_key,
                     collection,
                     rkey.as_deref(),
                     value,
-                    &tid_gen,
+                    &state.tid_gen,
                     &running_root,
                 )
                 .await?;
 
                 let record_cid_str = cid_bytes_to_string(&output.cid)?;
-                let rkey_actual = output
-                    .uri
-                    .rsplit('/')
-                    .next()
-                    .unwrap_or("")
-                    .to_string();
 
                 ops.push(crate::firehose::events::RepoOp {
                     action: "create".to_string(),
-                    path: format!("{collection}/{rkey_actual}"),
+                    path: output.path().to_string(),
                     cid: Some(crate::firehose::events::CidLink {
                         link: record_cid_str,
                     }),
+                    prev: None,
                 });
                 results.push(json!({
                     "uri": output.uri,
                     "cid": cid_bytes_to_string(&output.cid)?,
                 }));
                 running_root = output.new_root;
+                running_rev = Some(output.new_rev);
             }
             ApplyWriteOp::Update {
                 collection,
@@ -721,13 +1102,13 @@ where
                 value,
             } => {
                 let output = dallaspds_repo::put_record(
-                    state.repo_store.clone(),
+                    staged.clone(),
                     &user.did,
                     &signing_key,
                     collection,
                     rkey,
                     value,
-                    &tid_gen,
+                    &state.tid_gen,
                     &running_root,
                 )
                 .await?;
@@ -740,21 +1121,23 @@ where
                     cid: Some(crate::firehose::events::CidLink {
                         link: record_cid_str,
                     }),
+                    prev: None,
                 });
                 results.push(json!({
                     "uri": output.uri,
                     "cid": cid_bytes_to_string(&output.cid)?,
                 }));
                 running_root = output.new_root;
+                running_rev = Some(output.new_rev);
             }
             ApplyWriteOp::Delete { collection, rkey } => {
-                let (new_root, _new_rev) = dallaspds_repo::delete_record(
-                    state.repo_store.clone(),
+                let output = dallaspds_repo::delete_record(
+                    staged.clone(),
                     &user.did,
                     &signing_key,
                     collection,
                     rkey,
-                    &tid_gen,
+                    &state.tid_gen,
                     &running_root,
                 )
                 .await?;
@@ -763,27 +1146,62 @@ where
                     action: "delete".to_string(),
                     path: format!("{collection}/{rkey}"),
                     cid: None,
+                    prev: Some(crate::firehose::events::CidLink {
+                        link: cid_bytes_to_string(&output.deleted_cid)?,
+                    }),
                 });
-                running_root = new_root;
+                running_root = output.new_root;
+                running_rev = Some(output.new_rev);
             }
         }
     }
 
-    // Get the final rev from the last commit.
-    // We need to extract it from the repo root. The TID gen was used for each operation,
-    // so the rev from the last op is the final rev. We'll regenerate one for the root update.
-    let final_rev = tid_gen.next_tid();
+    // The final rev is the rev of the last commit in the batch, so the stored
+    // repo root matches the commit it points at.
+    let final_rev = match running_rev {
+        Some(rev) => rev,
+        None => state
+            .account_store
+            .get_repo_root(&user.did)
+            .await?
+            .map(|root| root.rev)
+            .unwrap_or_default(),
+    };
 
-    // Update repo root once with the final state.
+    // Store the batch's blocks and move the repo root once, in one
+    // transaction. A batch of only deletes is never held back by the quota.
+    let only_deletes = body
+        .writes
+        .iter()
+        .all(|write| matches!(write, ApplyWriteOp::Delete { .. }));
+    if only_deletes {
+        state.repo_quota.add(&user.did, staged.staged_bytes());
+    } else {
+        state
+            .repo_quota
+            .reserve(&state.repo_store, &user.did, staged.staged_bytes())
+            .await?;
+    }
+    staged.commit(&prev_root, &running_root, &final_rev).await?;
+    let referenced_blobs: Vec<String> = body
+        .writes
+        .iter()
+        .flat_map(|write_op| match write_op {
+            ApplyWriteOp::Create { value, .. } | ApplyWriteOp::Update { value, .. } => {
+                blob_cids(value)
+            }
+            ApplyWriteOp::Delete { .. } => Vec::new(),
+        })
+        .collect();
     state
-        .account_store
-        .update_repo_root(&user.did, &running_root, &final_rev)
+        .repo_store
+        .add_blob_refs(&user.did, &referenced_blobs, &final_rev)
         .await?;
 
     // Emit a single firehose commit event with all operations.
     if let Some(ref sequencer) = state.sequencer {
         use crate::firehose::events::*;
-        let seq = sequencer.next_seq();
+        let seq = sequencer.next_seq().await?;
         let commit_cid_str = cid_bytes_to_string(&running_root).unwrap_or_default();
         let diff_car = dallaspds_repo::generate_diff_car(
             state.repo_store.clone(),
@@ -820,3 +1238,228 @@ where
         "results": results,
     })))
 }
+
+// ---------------------------------------------------------------------------
+// 9. importRepo
+// ---------------------------------------------------------------------------
+
+/// Replace the caller's repository with the contents of a CAR file.
+///
+/// The CAR's root commit must be signed by the account's signing key, except
+/// for an account created by migration that has no repo yet. There the CAR
+/// comes from the old PDS, so its tree is installed under a new commit signed
+/// with this PDS's key.
+///
+/// Responds with the commit now at the root and how many blocks and bytes
+/// were written. If storage fails part-way, the error says how far the
+/// import got and the root is left where it was.
+pub async fn import_repo<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    user: WriterAuth,
+    body: Bytes,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    state.read_only.ensure_writable()?;
+
+    let _write_permit = state.write_limiter.acquire(&user.did).await?;
+
+    let account = state
+        .account_store
+        .get_account_by_did(&user.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+
+    let signing_key = signing_key_from_account(&account)?;
+    // A migrating account's root row exists but holds no commit yet.
+    let prev_root = state
+        .account_store
+        .get_repo_root(&user.did)
+        .await?
+        .map(|root| root.cid)
+        .filter(|cid| !cid.is_empty());
+
+    // The imported blocks are stored alongside the current ones until the
+    // root moves, so the whole CAR must fit.
+    state
+        .repo_quota
+        .reserve(&state.repo_store, &user.did, body.len() as u64)
+        .await?;
+
+    let (progress, blocks) = match prev_root {
+        Some(_) => {
+            let progress = dallaspds_repo::import_car(
+                state.repo_store.clone(),
+                &user.did,
+                &body,
+                &signing_key,
+            )
+            .await?;
+            (progress, body.to_vec())
+        }
+        None => {
+            let progress = dallaspds_repo::import_migrated_car(
+                state.repo_store.clone(),
+                &user.did,
+                &body,
+                &signing_key,
+                &state.tid_gen.next_tid(),
+            )
+            .await?;
+            // The uploaded CAR is rooted at the old PDS's commit; send the
+            // re-signed one instead.
+            let car = dallaspds_repo::export_full_car(
+                state.repo_store.clone(),
+                &user.did,
+                &progress.commit,
+            )
+            .await?;
+            (progress, car)
+        }
+    };
+    let new_root = progress.commit.clone();
+    let new_rev = progress.rev.clone();
+
+    // Index the imported records' blobs under the import's rev, as a write
+    // would, so listBlobs and the missing-blob checks see them.
+    let blob_refs =
+        dallaspds_repo::list_blob_refs(state.repo_store.clone(), &user.did, &new_root).await?;
+    state
+        .repo_store
+        .add_blob_refs(&user.did, &blob_refs, &new_rev)
+        .await?;
+
+    // Only move the root once every block of the new repo is stored, then
+    // drop what the old root referenced.
+    state
+        .repo_store
+        .commit_blocks(
+            &user.did,
+            &[],
+            prev_root.as_deref().unwrap_or_default(),
+            &new_root,
+            &new_rev,
+        )
+        .await?;
+    if let Err(e) =
+        dallaspds_repo::prune_unreachable(state.repo_store.clone(), &user.did, &new_root).await
+    {
+        tracing::warn!(did = %user.did, error = %e, "failed to prune blocks after import");
+    }
+    state.repo_quota.forget(&user.did);
+
+    // Emit firehose event carrying the full imported repo.
+    if let Some(ref sequencer) = state.sequencer {
+        use crate::firehose::events::*;
+        let seq = sequencer.next_seq().await?;
+
+        let event = FirehoseEvent::Commit(CommitEvent {
+            seq,
+            too_big: false,
+            repo: user.did.clone(),
+            commit: CidLink {
+                link: cid_bytes_to_string(&new_root).unwrap_or_default(),
+            },
+            prev: prev_root.map(|prev| CidLink {
+                link: cid_bytes_to_string(&prev).unwrap_or_default(),
+            }),
+            rev: new_rev,
+            time: chrono::Utc::now().to_rfc3339(),
+            ops: vec![],
+            blocks,
+        });
+        crate::firehose::emit::emit_and_persist(&state, event).await;
+
+        if let Some(ref notifier) = state.relay_notifier {
+            notifier.notify(&user.did);
+        }
+    }
+
+    Ok(Json(json!({
+        "commit": cid_bytes_to_string(&progress.commit).unwrap_or_default(),
+        "rev": progress.rev,
+        "blocks": progress.blocks,
+        "bytes": progress.bytes,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 10. com.dallaspds.repo.listAllRecords
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct ListAllRecordsQuery {
+    pub repo: String,
+    pub limit: Option<usize>,
+    /// Full MST key (`collection/rkey`) of the last record on the previous page.
+    pub cursor: Option<String>,
+}
+
+/// List every record in a repo regardless of collection, in MST key order.
+pub async fn list_all_records<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    Query(params): Query<ListAllRecordsQuery>,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let limit = params.limit.unwrap_or(50).min(100);
+
+    if let Some(cursor) = params.cursor.as_deref() {
+        let valid = cursor.split_once('/').is_some_and(|(collection, rkey)| {
+            !collection.is_empty() && dallaspds_repo::is_valid_rkey(rkey)
+        });
+        if !valid {
+            return Err(XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidCursor",
+                format!("malformed cursor: {cursor}"),
+            ));
+        }
+    }
+
+    let current_root = get_repo_root_bytes(&*state.account_store, &params.repo).await?;
+
+    let records = dallaspds_repo::list_all_records(
+        state.repo_store.clone(),
+        &params.repo,
+        limit,
+        params.cursor.as_deref(),
+        &current_root,
+    )
+    .await?;
+
+    // The cursor is the last record's MST key, i.e. its URI minus the repo.
+    let uri_prefix = format!("at://{}/", params.repo);
+    let cursor = if records.len() >= limit {
+        records
+            .last()
+            .and_then(|r| r.uri.strip_prefix(&uri_prefix).map(|s| s.to_string()))
+    } else {
+        None
+    };
+
+    let record_values: Vec<Value> = records
+        .iter()
+        .map(|r| {
+            let cid_str = cid_bytes_to_string(&r.cid).unwrap_or_default();
+            json!({
+                "uri": r.uri,
+                "cid": cid_str,
+                "value": r.value,
+            })
+        })
+        .collect();
+
+    let mut response = json!({ "records": record_values });
+    if let Some(c) = cursor {
+        response["cursor"] = json!(c);
+    }
+
+    Ok(Json(response))
+}
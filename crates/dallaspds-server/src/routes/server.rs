@@ -5,11 +5,15 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 
 use crate::auth::{AuthenticatedUser, JwtRefreshSecret};
+use crate::email::{generate_email_token, is_email_token_valid};
 use crate::error::XrpcError;
 use crate::state::AppState;
+use crate::webhooks::AccountEventKind;
 use dallaspds_core::traits::*;
-use dallaspds_core::types::{CreateAccountInput, RefreshTokenRecord};
+use dallaspds_core::types::{AccountStatus, ActorAccount, CreateAccountInput, RefreshTokenRecord};
 use dallaspds_core::PdsError;
+use dallaspds_core::config::{PdsConfig, PlcRegistration};
+use dallaspds_crypto::PasswordVerification;
 
 // ---------------------------------------------------------------------------
 // 1. describeServer
@@ -42,17 +46,47 @@ pub struct CreateAccountRequest {
     pub email: Option<String>,
     pub password: String,
     pub invite_code: Option<String>,
+    /// Existing DID to migrate onto this PDS. See [`create_account`].
+    pub did: Option<String>,
+    /// A `did:key` the user holds, listed ahead of the PDS's key in the new
+    /// DID's rotation keys so the user can recover the identity without us.
+    pub recovery_key: Option<String>,
 }
 
+/// Create an account, or the landing spot for one migrating from another PDS.
+///
+/// Without `did`, a new did:plc identity is registered (or, on a single-user
+/// PDS with `did_web` set, the account takes `did:web:{hostname}`) and the
+/// account is active immediately with an empty repo.
+///
+/// With `did`, the account is created deactivated and with no repo, and the
+/// PLC directory is not touched. Migration then proceeds as:
+///
+/// 1. `createAccount` with `did` (this handler), authorized by a service
+///    auth token for `com.atproto.server.createAccount` that the DID's
+///    current signing key issued to this PDS.
+/// 2. `importRepo` with a CAR exported from the old PDS. With no repo yet, the
+///    import is re-signed with this PDS's key instead of being verified.
+/// 3. `uploadBlob` for each blob the repo references.
+/// 4. Update the DID document (via the old PDS's PLC signing flow) to point
+///    at this PDS and its signing key.
+/// 5. `activateAccount`, which checks the DID document and emits the
+///    `#account` active event so relays start following the repo here.
 pub async fn create_account<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    Json(body): Json<CreateAccountRequest>,
+    headers: HeaderMap,
+    Json(mut body): Json<CreateAccountRequest>,
 ) -> Result<Json<Value>, XrpcError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
+    // Handles are case-insensitive; store them lowercased.
+    body.handle = body.handle.to_ascii_lowercase();
+
     // Check single-user mode: reject if an account already exists.
     if matches!(state.config.mode, dallaspds_core::config::PdsMode::Single) {
         let existing = state.account_store.list_accounts(None, 1).await?;
@@ -65,21 +99,10 @@ where
         }
     }
 
-    // (a) Validate handle — must end with one of the available user domains.
-    let handle_valid = state
-        .config
-        .available_user_domains
-        .iter()
-        .any(|domain| body.handle.ends_with(domain));
-    if !handle_valid {
-        return Err(XrpcError::new(
-            StatusCode::BAD_REQUEST,
-            "InvalidHandle",
-            format!(
-                "Handle must end with one of: {}",
-                state.config.available_user_domains.join(", ")
-            ),
-        ));
+    // (a) Validate handle — a name under one of the available user domains.
+    crate::handle::check_local_handle(&state.config, &body.handle)?;
+    if state.account_store.get_account_by_handle(&body.handle).await?.is_some() {
+        return Err(PdsError::HandleAlreadyTaken.into());
     }
 
     // Enforce invite code requirement
@@ -118,46 +141,97 @@ where
         )
     })?;
 
-    // (c) Create did:plc genesis operation.
-    let rotation_keys = vec![signing_key.did_key()];
-    let pds_endpoint = state.config.public_url.clone();
-    let (did, signed_genesis_op) = dallaspds_crypto::create_did_plc_operation(
-        &signing_key,
-        rotation_keys,
-        &body.handle,
-        &pds_endpoint,
-    )
-    .map_err(|e| {
-        XrpcError::new(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "InternalServerError",
-            e.to_string(),
+    let did_web = state.config.did_web
+        && matches!(state.config.mode, dallaspds_core::config::PdsMode::Single);
+    if let Some(recovery_key) = &body.recovery_key {
+        if body.did.is_some() || did_web {
+            return Err(XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidRequest",
+                "recoveryKey only applies when registering a new did:plc",
+            ));
+        }
+        dallaspds_crypto::validate_did_key(recovery_key)?;
+    }
+
+    let migrating = body.did.is_some();
+    let (did, genesis_op) = if let Some(did) = &body.did {
+        // A migrating account keeps its DID; the user points it here later.
+        if !(did.starts_with("did:plc:") || did.starts_with("did:web:")) {
+            return Err(XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidRequest",
+                format!("unsupported DID: {did}"),
+            ));
+        }
+        if state.account_store.get_account_by_did(did).await?.is_some() {
+            return Err(XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "AlreadyExists",
+                format!("an account already exists for {did}"),
+            ));
+        }
+        verify_did_control(&headers, did, &state.config).await?;
+        (did.clone(), None)
+    } else if did_web {
+        // (c) A single-user PDS can be its account's identity: the DID
+        //     document is served from our own well-known path, so there is
+        //     nothing to register.
+        (crate::did_doc::hostname_did_web(&state.config.hostname), None)
+    } else {
+        // (c) Create did:plc genesis operation. Rotation keys are in priority
+        //     order, so a user's recovery key can override our operations.
+        let rotation_keys = body
+            .recovery_key
+            .iter()
+            .cloned()
+            .chain([signing_key.did_key()])
+            .collect();
+        let pds_endpoint = state.config.public_url.clone();
+        let (did, signed_genesis_op) = dallaspds_crypto::create_did_plc_operation(
+            &signing_key,
+            rotation_keys,
+            &body.handle,
+            &pds_endpoint,
         )
-    })?;
+        .map_err(|e| {
+            XrpcError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "InternalServerError",
+                e.to_string(),
+            )
+        })?;
 
-    // (d) POST genesis op to PLC directory.
-    //     Wrap in a try — in dev mode the PLC directory may not be reachable.
-    let plc_url = format!("{}/{}", state.config.plc_url.trim_end_matches('/'), did);
-    let client = reqwest::Client::new();
-    match client.post(&plc_url).json(&signed_genesis_op).send().await {
-        Ok(resp) => {
-            if !resp.status().is_success() {
+        // (d) POST genesis op to PLC directory. The op is idempotent, so
+        //     transient failures are retried. Nothing has been stored yet,
+        //     so in `required` mode a failure leaves no trace of the account.
+        let plc_url = format!("{}/{}", state.config.plc_url.trim_end_matches('/'), did);
+        let request = reqwest::Client::new().post(&plc_url).json(&signed_genesis_op);
+        let failure = match dallaspds_identity::http_retry::send_with_retry(&state.config.http_retry, request).await {
+            Ok(resp) if resp.status().is_success() => None,
+            Ok(resp) => {
                 let status = resp.status();
                 let text = resp.text().await.unwrap_or_default();
-                tracing::warn!(
-                    "PLC directory returned non-success status {}: {}",
-                    status,
-                    text
-                );
+                Some(format!("PLC directory returned {status}: {text}"))
             }
+            Err(e) => Some(format!("failed to reach PLC directory at {plc_url}: {e}")),
+        };
+        if let Some(failure) = failure {
+            if state.config.plc_registration == PlcRegistration::Required {
+                return Err(XrpcError::new(
+                    StatusCode::BAD_GATEWAY,
+                    "PlcRegistrationFailed",
+                    failure,
+                ));
+            }
+            // In dev mode the PLC directory may not be reachable.
+            tracing::warn!("Creating {did} without registering it: {failure}");
         }
-        Err(e) => {
-            tracing::warn!("Failed to reach PLC directory at {}: {}", plc_url, e);
-        }
-    }
+        (did, Some(signed_genesis_op))
+    };
 
     // (e) Hash password.
-    let password_hash = dallaspds_crypto::hash_password(&body.password).map_err(|e| {
+    let password_hash = dallaspds_crypto::hash_password(&body.password, &state.config.password).map_err(|e| {
         XrpcError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
             "InternalServerError",
@@ -172,9 +246,18 @@ where
         email: body.email.clone(),
         password_hash,
         signing_key: signing_key.to_bytes(),
+        key_type: signing_key.key_type().to_string(),
     };
     state.account_store.create_account(&input).await?;
 
+    // Keep the signed genesis op so the DID's history can be rebuilt
+    // locally. Only now that the account exists, so a refused registration
+    // or a failed insert leaves no orphaned op behind.
+    if let Some(op) = &genesis_op {
+        let op_cid = dallaspds_crypto::plc_operation_cid(op)?;
+        state.account_store.append_plc_op(&did, &op_cid, op).await?;
+    }
+
     // Record invite code usage
     if state.config.invite_required {
         if let Some(ref code_str) = body.invite_code {
@@ -184,35 +267,45 @@ where
 
     // Send verification email if SMTP is configured
     if let (Some(email_sender), Some(email)) = (&state.email_sender, &body.email) {
-        let token = hex::encode(rand::random::<[u8; 16]>());
+        let token = generate_email_token(&state.config.email, "confirm_email");
         let _ = state.account_store.create_email_token("confirm_email", &did, &token).await;
-        if let Err(e) = email_sender.send_verification_email(email, &token, &state.config.public_url).await {
+        if let Err(e) = email_sender
+            .send_verification_email(email, &body.handle, &token, &state.config.public_url)
+            .await
+        {
             tracing::warn!("Failed to send verification email: {e}");
         }
     }
 
-    // (f2) Initialize the repository (empty MST + signed commit).
-    let (repo_root_cid, repo_rev) = dallaspds_repo::create_repo(
-        state.repo_store.clone(),
-        &did,
-        &signing_key,
-    )
-    .await
-    .map_err(|e| {
-        XrpcError::new(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "InternalServerError",
-            format!("failed to initialize repository: {e}"),
+    if migrating {
+        // (f2) Hold the account inactive until its repo is imported and the
+        //      DID points here; importRepo supplies the first commit.
+        state.account_store.deactivate_account(&did).await?;
+    } else {
+        // (f2) Initialize the repository (empty MST + signed commit).
+        let staged = std::sync::Arc::new(dallaspds_repo::StagedRepoStore::new(
+            state.repo_store.clone(),
+            &did,
+        ));
+        let (repo_root_cid, repo_rev) = dallaspds_repo::create_repo(
+            staged.clone(),
+            &did,
+            &signing_key,
         )
-    })?;
-    state
-        .account_store
-        .update_repo_root(&did, &repo_root_cid, &repo_rev)
-        .await?;
+        .await
+        .map_err(|e| {
+            XrpcError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "InternalServerError",
+                format!("failed to initialize repository: {e}"),
+            )
+        })?;
+        staged.commit(&[], &repo_root_cid, &repo_rev).await?;
+    }
 
     // (g) Create access + refresh JWTs.
     let access_jwt =
-        dallaspds_crypto::create_access_token(&did, &state.config.jwt.access_secret)?;
+        dallaspds_crypto::create_access_token(&did, &state.access_token_keys)?;
     let refresh_jti = uuid::Uuid::new_v4().to_string();
     let refresh_jwt =
         dallaspds_crypto::create_refresh_token(&did, &refresh_jti, &state.config.jwt.refresh_secret)?;
@@ -224,12 +317,18 @@ where
         expires_at: chrono::Utc::now() + chrono::Duration::days(90),
         next_id: None,
         app_password_name: None,
+        session_created_at: chrono::Utc::now(),
+        dpop_jkt: None,
     };
     state
         .account_store
         .create_refresh_token(&refresh_record)
         .await?;
 
+    if let Some(ref webhooks) = state.webhooks {
+        webhooks.notify(AccountEventKind::Create, &did);
+    }
+
     // (i) Return response.
     Ok(Json(json!({
         "did": did,
@@ -258,66 +357,136 @@ where
     R: RepoStore,
     B: BlobStore,
 {
-    // (a) Lookup account by handle or email (try both).
+    let account = verify_login(&state, &body.identifier, &body.password).await?;
+    let (access_jwt, refresh_jwt) = issue_session(&state, &account.did, None).await?;
+
+    Ok(Json(json!({
+        "did": account.did,
+        "handle": account.handle,
+        "email": account.email,
+        "accessJwt": access_jwt,
+        "refreshJwt": refresh_jwt,
+    })))
+}
+
+/// Look up an account by handle or email and check its password, upgrading
+/// the stored hash if it uses outdated parameters.
+pub(crate) async fn verify_login<A, R, B>(
+    state: &AppState<A, R, B>,
+    identifier: &str,
+    password: &str,
+) -> Result<ActorAccount, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    // Lookup account by handle or email (try both).
     let account = state
         .account_store
-        .get_account_by_handle(&body.identifier)
+        .get_account_by_handle(identifier)
         .await?;
     let account = match account {
         Some(a) => a,
         None => state
             .account_store
-            .get_account_by_email(&body.identifier)
+            .get_account_by_email(identifier)
             .await?
             .ok_or(PdsError::AccountNotFound)?,
     };
 
-    // (b) Verify password.
-    let valid =
-        dallaspds_crypto::verify_password(&body.password, &account.password_hash).map_err(
-            |e| {
-                XrpcError::new(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "InternalServerError",
-                    e.to_string(),
-                )
-            },
-        )?;
-    if !valid {
+    // Verify password.
+    let verification = dallaspds_crypto::verify_password(
+        password,
+        &account.password_hash,
+        &state.config.password,
+    )
+    .map_err(|e| {
+        XrpcError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "InternalServerError",
+            e.to_string(),
+        )
+    })?;
+    if !verification.is_valid() {
         return Err(PdsError::InvalidPassword.into());
     }
 
-    // (c) Create access + refresh JWTs.
-    let access_jwt =
-        dallaspds_crypto::create_access_token(&account.did, &state.config.jwt.access_secret)?;
+    // Upgrade hashes made with weaker argon2 parameters. Failure here must not
+    // block the login, the old hash is still valid.
+    if verification == PasswordVerification::ValidNeedsRehash {
+        match dallaspds_crypto::hash_password(password, &state.config.password) {
+            Ok(new_hash) => {
+                if let Err(e) = state
+                    .account_store
+                    .update_password(&account.did, &new_hash)
+                    .await
+                {
+                    tracing::warn!("Failed to store rehashed password: {e}");
+                }
+            }
+            Err(e) => tracing::warn!("Failed to rehash password: {e}"),
+        }
+    }
+
+    Ok(account)
+}
+
+/// An access token for `did`, bound to a DPoP key when `dpop_jkt` is set.
+fn create_access_jwt<A, R, B>(
+    state: &AppState<A, R, B>,
+    did: &str,
+    dpop_jkt: Option<&str>,
+) -> Result<String, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let keys = &state.access_token_keys;
+    Ok(match dpop_jkt {
+        Some(jkt) => dallaspds_crypto::create_dpop_access_token(did, jkt, keys)?,
+        None => dallaspds_crypto::create_access_token(did, keys)?,
+    })
+}
+
+/// Start a new session for `did`, returning its access and refresh tokens.
+/// The access token is bound to the DPoP key `dpop_jkt`, if given.
+pub(crate) async fn issue_session<A, R, B>(
+    state: &AppState<A, R, B>,
+    did: &str,
+    dpop_jkt: Option<&str>,
+) -> Result<(String, String), XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    // Create access + refresh JWTs.
+    let access_jwt = create_access_jwt(state, did, dpop_jkt)?;
     let refresh_jti = uuid::Uuid::new_v4().to_string();
     let refresh_jwt = dallaspds_crypto::create_refresh_token(
-        &account.did,
+        did,
         &refresh_jti,
         &state.config.jwt.refresh_secret,
     )?;
 
-    // (d) Store refresh token.
+    // Store refresh token.
     let refresh_record = RefreshTokenRecord {
         id: refresh_jti,
-        did: account.did.clone(),
+        did: did.to_string(),
         expires_at: chrono::Utc::now() + chrono::Duration::days(90),
         next_id: None,
         app_password_name: None,
+        session_created_at: chrono::Utc::now(),
+        dpop_jkt: dpop_jkt.map(str::to_string),
     };
     state
         .account_store
         .create_refresh_token(&refresh_record)
         .await?;
 
-    // (e) Return response.
-    Ok(Json(json!({
-        "did": account.did,
-        "handle": account.handle,
-        "email": account.email,
-        "accessJwt": access_jwt,
-        "refreshJwt": refresh_jwt,
-    })))
+    Ok((access_jwt, refresh_jwt))
 }
 
 // ---------------------------------------------------------------------------
@@ -361,7 +530,22 @@ where
     R: RepoStore,
     B: BlobStore,
 {
-    // Read Authorization header manually (refresh token, not access token).
+    let token = refresh_token_from_headers(&headers)?;
+    let (account, access_jwt, refresh_jwt) =
+        rotate_refresh_token(&state, token, &refresh_secret.0, None).await?;
+
+    Ok(Json(json!({
+        "did": account.did,
+        "handle": account.handle,
+        "accessJwt": access_jwt,
+        "refreshJwt": refresh_jwt,
+    })))
+}
+
+/// Read the bearer token from the Authorization header manually, since
+/// `refreshSession` and `deleteSession` take a refresh token, not an access
+/// token.
+fn refresh_token_from_headers(headers: &HeaderMap) -> Result<&str, XrpcError> {
     let auth_header = headers
         .get("authorization")
         .and_then(|v| v.to_str().ok())
@@ -373,40 +557,92 @@ where
             )
         })?;
 
-    let token = auth_header.strip_prefix("Bearer ").ok_or_else(|| {
+    auth_header.strip_prefix("Bearer ").ok_or_else(|| {
         XrpcError::new(
             StatusCode::UNAUTHORIZED,
             "AuthenticationRequired",
             "Invalid authorization format",
         )
-    })?;
+    })
+}
 
-    // Validate refresh token using the REFRESH secret.
-    let claims =
-        dallaspds_crypto::validate_refresh_token(token, &refresh_secret.0).map_err(|e| {
-            let err_msg = e.to_string();
-            if err_msg.contains("ExpiredSignature") {
-                XrpcError::new(
-                    StatusCode::UNAUTHORIZED,
-                    "ExpiredToken",
-                    "Refresh token has expired",
-                )
-            } else {
-                XrpcError::new(
-                    StatusCode::UNAUTHORIZED,
-                    "InvalidToken",
-                    "Invalid refresh token",
-                )
-            }
-        })?;
+/// Validate a refresh token's signature and expiry using the REFRESH secret.
+fn validate_refresh_jwt(
+    token: &str,
+    refresh_secret: &str,
+) -> Result<dallaspds_crypto::RefreshTokenClaims, XrpcError> {
+    dallaspds_crypto::validate_refresh_token(token, refresh_secret).map_err(|e| {
+        let err_msg = e.to_string();
+        if err_msg.contains("ExpiredSignature") {
+            XrpcError::new(
+                StatusCode::UNAUTHORIZED,
+                "ExpiredToken",
+                "Refresh token has expired",
+            )
+        } else {
+            XrpcError::new(
+                StatusCode::UNAUTHORIZED,
+                "InvalidToken",
+                "Invalid refresh token",
+            )
+        }
+    })
+}
+
+/// Validate a refresh token and rotate it, returning the account with a new
+/// access token and refresh token.
+///
+/// Shared by `refreshSession` and the OAuth `refresh_token` grant.
+pub(crate) async fn rotate_refresh_token<A, R, B>(
+    state: &AppState<A, R, B>,
+    token: &str,
+    refresh_secret: &str,
+    dpop_jkt: Option<&str>,
+) -> Result<(ActorAccount, String, String), XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let claims = validate_refresh_jwt(token, refresh_secret)?;
 
     // Lookup the stored refresh token record.
-    let _old_record = state
+    let old_record = state
         .account_store
         .get_refresh_token(&claims.jti)
         .await?
         .ok_or_else(|| PdsError::Auth("Refresh token not found".to_string()))?;
 
+    // A token that has already been rotated is being replayed; assume it was
+    // stolen and revoke every session for the account.
+    if old_record.next_id.is_some() {
+        return Err(revoke_reused_token(state, &old_record.did).await);
+    }
+
+    // An OAuth session may only be refreshed by the holder of its DPoP key,
+    // and a legacy session only through refreshSession.
+    if old_record.dpop_jkt.as_deref() != dpop_jkt {
+        return Err(XrpcError::new(
+            StatusCode::UNAUTHORIZED,
+            "InvalidToken",
+            "Refresh token is not bound to this DPoP key",
+        ));
+    }
+
+    // Rotation keeps a session alive indefinitely; enforce the absolute
+    // max age measured from when the chain began.
+    if let Some(max_age_days) = state.config.jwt.session_max_age_days
+        && chrono::Utc::now() - old_record.session_created_at
+            > chrono::Duration::days(max_age_days.into())
+    {
+        state.account_store.delete_refresh_token(&old_record.id).await?;
+        return Err(XrpcError::new(
+            StatusCode::UNAUTHORIZED,
+            "ExpiredToken",
+            "Session has exceeded its maximum age, please log in again",
+        ));
+    }
+
     // Lookup account.
     let account = state
         .account_store
@@ -414,16 +650,20 @@ where
         .await?
         .ok_or(PdsError::AccountNotFound)?;
 
-    // Delete old refresh token.
-    state
+    // Claim the old token by pointing it at its successor. This is a
+    // conditional update, so a concurrent replay loses the race and is
+    // treated as reuse.
+    let new_refresh_jti = uuid::Uuid::new_v4().to_string();
+    let claimed = state
         .account_store
-        .delete_refresh_token(&claims.jti)
+        .set_refresh_token_next_id(&claims.jti, &new_refresh_jti)
         .await?;
+    if !claimed {
+        return Err(revoke_reused_token(state, &old_record.did).await);
+    }
 
     // Create new tokens.
-    let access_jwt =
-        dallaspds_crypto::create_access_token(&account.did, &state.config.jwt.access_secret)?;
-    let new_refresh_jti = uuid::Uuid::new_v4().to_string();
+    let access_jwt = create_access_jwt(state, &account.did, dpop_jkt)?;
     let refresh_jwt = dallaspds_crypto::create_refresh_token(
         &account.did,
         &new_refresh_jti,
@@ -436,43 +676,87 @@ where
         did: account.did.clone(),
         expires_at: chrono::Utc::now() + chrono::Duration::days(90),
         next_id: None,
-        app_password_name: None,
+        app_password_name: old_record.app_password_name,
+        session_created_at: old_record.session_created_at,
+        dpop_jkt: old_record.dpop_jkt,
     };
     state
         .account_store
         .create_refresh_token(&refresh_record)
         .await?;
 
-    Ok(Json(json!({
-        "did": account.did,
-        "handle": account.handle,
-        "accessJwt": access_jwt,
-        "refreshJwt": refresh_jwt,
-    })))
+    Ok((account, access_jwt, refresh_jwt))
+}
+
+/// Revoke all refresh tokens for a DID after a rotated token was replayed.
+async fn revoke_reused_token<A, R, B>(state: &AppState<A, R, B>, did: &str) -> XrpcError
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    match state.account_store.delete_refresh_tokens_for_did(did).await {
+        Ok(revoked) => {
+            tracing::warn!("Refresh token reuse detected for {did}, revoked {revoked} tokens");
+            XrpcError::new(
+                StatusCode::UNAUTHORIZED,
+                "TokenReused",
+                "Refresh token has already been used",
+            )
+        }
+        Err(e) => e.into(),
+    }
 }
 
 // ---------------------------------------------------------------------------
 // 6. deleteSession
 // ---------------------------------------------------------------------------
 
+/// Log out the session the presented refresh token belongs to. The
+/// account's other sessions stay logged in.
 pub async fn delete_session<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    user: AuthenticatedUser,
+    headers: HeaderMap,
+    axum::Extension(refresh_secret): axum::Extension<JwtRefreshSecret>,
 ) -> Result<StatusCode, XrpcError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
-    // Delete all refresh tokens for this user.
+    let token = refresh_token_from_headers(&headers)?;
+    let claims = validate_refresh_jwt(token, &refresh_secret.0)?;
+
+    // Revoke the whole rotation chain, so an earlier token from this session
+    // can't be replayed to trip reuse detection on the others.
     state
         .account_store
-        .delete_refresh_tokens_for_did(&user.did)
+        .delete_refresh_token_chain(&claims.jti)
         .await?;
 
     Ok(StatusCode::OK)
 }
 
+/// Log out every session on the account, e.g. after losing a device.
+///
+/// Access tokens already handed out stay valid until they expire.
+pub async fn delete_all_sessions<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    user: AuthenticatedUser,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let revoked = state
+        .account_store
+        .delete_refresh_tokens_for_did(&user.did)
+        .await?;
+
+    Ok(Json(json!({ "revoked": revoked })))
+}
+
 // ---------------------------------------------------------------------------
 // 7. requestEmailConfirmation
 // ---------------------------------------------------------------------------
@@ -492,7 +776,7 @@ where
         .await?
         .ok_or(PdsError::AccountNotFound)?;
 
-    let token = hex::encode(rand::random::<[u8; 16]>());
+    let token = generate_email_token(&state.config.email, "confirm_email");
     state
         .account_store
         .create_email_token("confirm_email", &user.did, &token)
@@ -500,7 +784,12 @@ where
 
     if let (Some(email_sender), Some(email)) = (&state.email_sender, &account.email) {
         if let Err(e) = email_sender
-            .send_verification_email(email, &token, &state.config.public_url)
+            .send_verification_email(
+                email,
+                account.handle.as_deref().unwrap_or(&account.did),
+                &token,
+                &state.config.public_url,
+            )
             .await
         {
             tracing::warn!("Failed to send verification email: {e}");
@@ -531,6 +820,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     let stored = state
         .account_store
         .get_email_token("confirm_email", &user.did)
@@ -553,7 +844,7 @@ where
         ));
     }
 
-    if requested_at + chrono::Duration::hours(1) < chrono::Utc::now() {
+    if !is_email_token_valid(&state.config.email, requested_at, "confirm_email") {
         return Err(XrpcError::new(
             StatusCode::BAD_REQUEST,
             "ExpiredToken",
@@ -592,7 +883,7 @@ where
     // Always return 200 OK regardless of whether the email exists.
     // This prevents email enumeration attacks.
     if let Ok(Some(account)) = state.account_store.get_account_by_email(&body.email).await {
-        let token = hex::encode(rand::random::<[u8; 16]>());
+        let token = generate_email_token(&state.config.email, "reset_password");
         if let Err(e) = state
             .account_store
             .create_email_token("reset_password", &account.did, &token)
@@ -604,7 +895,12 @@ where
 
         if let Some(ref email_sender) = state.email_sender {
             if let Err(e) = email_sender
-                .send_password_reset_email(&body.email, &token, &state.config.public_url)
+                .send_password_reset_email(
+                    &body.email,
+                    account.handle.as_deref().unwrap_or(&account.did),
+                    &token,
+                    &state.config.public_url,
+                )
                 .await
             {
                 tracing::warn!("Failed to send password reset email: {e}");
@@ -635,6 +931,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     let (did, requested_at) = state
         .account_store
         .get_email_token_by_token("reset_password", &body.token)
@@ -647,7 +945,7 @@ where
             )
         })?;
 
-    if requested_at + chrono::Duration::hours(1) < chrono::Utc::now() {
+    if !is_email_token_valid(&state.config.email, requested_at, "reset_password") {
         return Err(XrpcError::new(
             StatusCode::BAD_REQUEST,
             "ExpiredToken",
@@ -655,7 +953,7 @@ where
         ));
     }
 
-    let password_hash = dallaspds_crypto::hash_password(&body.password).map_err(|e| {
+    let password_hash = dallaspds_crypto::hash_password(&body.password, &state.config.password).map_err(|e| {
         XrpcError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
             "InternalServerError",
@@ -692,7 +990,7 @@ where
     R: RepoStore,
     B: BlobStore,
 {
-    let token = hex::encode(rand::random::<[u8; 16]>());
+    let token = generate_email_token(&state.config.email, "update_email");
     state
         .account_store
         .create_email_token("update_email", &user.did, &token)
@@ -706,7 +1004,12 @@ where
 
     if let (Some(email_sender), Some(email)) = (&state.email_sender, &account.email) {
         if let Err(e) = email_sender
-            .send_email_update_email(email, &token, &state.config.public_url)
+            .send_email_update_email(
+                email,
+                account.handle.as_deref().unwrap_or(&account.did),
+                &token,
+                &state.config.public_url,
+            )
             .await
         {
             tracing::warn!("Failed to send email update email: {e}");
@@ -737,6 +1040,8 @@ where
     R: RepoStore,
     B: BlobStore,
 {
+    state.read_only.ensure_writable()?;
+
     if state.email_sender.is_some() {
         let provided_token = body.token.as_deref().ok_or_else(|| {
             XrpcError::new(
@@ -766,7 +1071,7 @@ where
             ));
         }
 
-        if requested_at + chrono::Duration::hours(1) < chrono::Utc::now() {
+        if !is_email_token_valid(&state.config.email, requested_at, "update_email") {
             return Err(XrpcError::new(
                 StatusCode::BAD_REQUEST,
                 "ExpiredToken",
@@ -787,3 +1092,205 @@ where
 
     Ok(Json(json!({})))
 }
+
+// ---------------------------------------------------------------------------
+// 13. checkAccountStatus
+// ---------------------------------------------------------------------------
+
+/// Report repo and blob counts for the caller, used by migration flows to
+/// tell whether an account has been fully moved to this PDS.
+pub async fn check_account_status<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    user: AuthenticatedUser,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let account = state
+        .account_store
+        .get_account_by_did(&user.did)
+        .await?
+        .ok_or(PdsError::AccountNotFound)?;
+
+    let repo_root = state
+        .account_store
+        .get_repo_root(&user.did)
+        .await?
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("repository not initialized for {}", user.did),
+            )
+        })?;
+    let repo_commit = dallaspds_repo::cid_from_bytes(&repo_root.cid)
+        .map_err(|e| XrpcError::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalServerError", e))?;
+
+    let (repo_blocks, _) = state.repo_store.get_storage_usage(&user.did).await?;
+    let indexed_records =
+        dallaspds_repo::count_records(state.repo_store.clone(), &user.did, &repo_root.cid).await?;
+
+    let mut imported_blobs = 0;
+    let mut cursor: Option<String> = None;
+    loop {
+        let page = state
+            .blob_store
+            .list_blobs(&user.did, cursor.as_deref(), 1000)
+            .await?;
+        imported_blobs += page.len();
+        match page.last() {
+            Some(last) if page.len() == 1000 => cursor = Some(last.clone()),
+            _ => break,
+        }
+    }
+
+    let valid_did = did_points_here(&user.did, &state.config).await;
+
+    Ok(Json(json!({
+        "activated": account.status == AccountStatus::Active,
+        "validDid": valid_did,
+        "repoCommit": repo_commit.to_string(),
+        "repoRev": repo_root.rev,
+        "repoBlocks": repo_blocks,
+        "indexedRecords": indexed_records,
+        // No private state is stored, and blob references in records are
+        // not indexed, so these are reported as zero.
+        "privateStateValues": 0,
+        "expectedBlobs": 0,
+        "importedBlobs": imported_blobs,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// 14. com.dallaspds.server.getConfigPublic
+// ---------------------------------------------------------------------------
+
+/// Operational limits and feature flags clients can use to adapt to this
+/// server. Unauthenticated, so it must never expose secrets, admin DIDs, or
+/// upstream service details; those stay in `com.dallaspds.admin.getConfig`.
+pub async fn get_config_public<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let config = &state.config;
+
+    Ok(Json(json!({
+        "availableUserDomains": config.available_user_domains,
+        "inviteCodeRequired": config.invite_required,
+        "limits": {
+            // Blobs and records are only bounded by the request body limit.
+            "maxUploadSize": super::MAX_REQUEST_BODY_BYTES,
+            "maxRecordSize": super::MAX_REQUEST_BODY_BYTES,
+            "maxConcurrentWrites": config.limits.max_concurrent_writes,
+            "writeQueueTimeoutMs": config.limits.write_queue_timeout_ms,
+            "maxWritesPerApply": config.limits.max_writes_per_apply,
+            "maxApplyWritesBytes": config.limits.max_apply_writes_bytes,
+            "maxRepoBytes": config.limits.max_repo_bytes,
+        },
+        "features": {
+            "oauth": true,
+            "firehose": config.firehose.enabled,
+            "recordValidation": config.validate_records,
+        },
+    })))
+}
+
+/// The DID document of `did`.
+async fn resolve_did_document(did: &str, config: &PdsConfig) -> Result<Value, XrpcError> {
+    dallaspds_identity::resolve_did_with_plc(did, &config.plc_url, &config.http_retry)
+        .await?
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidRequest",
+                format!("could not resolve the DID document of {did}"),
+            )
+        })
+}
+
+/// Check that a createAccount request for an existing `did` comes from
+/// whoever controls it: it must carry a service auth token for
+/// `com.atproto.server.createAccount`, addressed to this PDS and signed with
+/// the `#atproto` key in the DID's current document.
+async fn verify_did_control(headers: &HeaderMap, did: &str, config: &PdsConfig) -> Result<(), XrpcError> {
+    let token = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::UNAUTHORIZED,
+                "AuthenticationRequired",
+                "creating an account for an existing DID requires a service auth token from that DID",
+            )
+        })?;
+
+    let doc = resolve_did_document(did, config).await?;
+    let signing_key = dallaspds_identity::atproto_signing_key(&doc).ok_or_else(|| {
+        XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            format!("the DID document of {did} has no atproto signing key"),
+        )
+    })?;
+    let service_did = format!("did:web:{}", config.hostname);
+    crate::proxy::service_auth::verify_service_auth_token(
+        token,
+        did,
+        &signing_key,
+        &service_did,
+        "com.atproto.server.createAccount",
+    )
+    .map_err(|e| XrpcError::new(StatusCode::UNAUTHORIZED, "InvalidToken", e.to_string()))?;
+    Ok(())
+}
+
+/// Check that `account`'s DID document lists this PDS as its `#atproto_pds`
+/// service and the account's key as its `#atproto` signing key, as it must
+/// before the account goes live here.
+pub(crate) async fn check_did_document_points_here(
+    account: &ActorAccount,
+    config: &PdsConfig,
+) -> Result<(), XrpcError> {
+    let doc = resolve_did_document(&account.did, config).await?;
+    let signing_key = dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key)?;
+    let points_here = dallaspds_identity::pds_endpoint(&doc)
+        .is_some_and(|endpoint| endpoint == config.public_url.trim_end_matches('/'));
+    if !points_here {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            format!("the DID document of {} does not list this PDS as its service", account.did),
+        ));
+    }
+    if dallaspds_identity::atproto_signing_key(&doc) != Some(signing_key.did_key()) {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            format!("the DID document of {} does not list this PDS's signing key", account.did),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `did`'s DID document lists this PDS as its `#atproto_pds`
+/// service. Resolution failures count as "no".
+async fn did_points_here(did: &str, config: &PdsConfig) -> bool {
+    let resolved = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        dallaspds_identity::resolve_did_with_plc(did, &config.plc_url, &config.http_retry),
+    )
+    .await;
+    match resolved {
+        Ok(Ok(Some(doc))) => dallaspds_identity::pds_endpoint(&doc).is_some_and(|endpoint| {
+            endpoint.trim_end_matches('/') == config.public_url.trim_end_matches('/')
+        }),
+        _ => false,
+    }
+}
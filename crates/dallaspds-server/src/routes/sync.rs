@@ -1,12 +1,14 @@
 use axum::body::Body;
 use axum::extract::{Query, State};
-use axum::http::{StatusCode, header};
+use axum::http::{HeaderMap, StatusCode, header};
 use axum::response::Response;
 use axum::Json;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
+use crate::auth::OptionalAuth;
 use crate::error::XrpcError;
+use crate::etag::{IMMUTABLE_CACHE_CONTROL, etag_for_cid, if_none_match, not_modified};
 use crate::state::AppState;
 use dallaspds_core::traits::*;
 use dallaspds_repo::cid_from_bytes;
@@ -18,6 +20,18 @@ fn cid_bytes_to_string(cid_bytes: &[u8]) -> Result<String, XrpcError> {
     Ok(cid.to_string())
 }
 
+/// Helper: the sync API status string for an inactive account, or `None`
+/// if the account is active.
+fn inactive_status(status: &dallaspds_core::AccountStatus) -> Option<&'static str> {
+    match status {
+        dallaspds_core::AccountStatus::Active => None,
+        dallaspds_core::AccountStatus::Deactivated => Some("deactivated"),
+        dallaspds_core::AccountStatus::Takendown => Some("takendown"),
+        dallaspds_core::AccountStatus::Suspended => Some("suspended"),
+        dallaspds_core::AccountStatus::Deleted => Some("deleted"),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // 1. getRepo — returns the full repo as a CAR file
 // ---------------------------------------------------------------------------
@@ -32,12 +46,25 @@ pub struct GetRepoQuery {
 pub async fn get_repo<A, R, B>(
     State(state): State<AppState<A, R, B>>,
     Query(params): Query<GetRepoQuery>,
+    OptionalAuth(user): OptionalAuth,
 ) -> Result<Response, XrpcError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
+    // Inactive repos are hidden from relays and other consumers, each with
+    // its own error so they can update their view of the account. The owner
+    // and admins can still export, e.g. to migrate away.
+    if let Some(account) = state.account_store.get_account_by_did(&params.did).await? {
+        let privileged = user.is_some_and(|u| {
+            u.did == params.did || state.config.admin_dids.contains(&u.did)
+        });
+        if !privileged {
+            check_repo_available(&params.did, &account.status)?;
+        }
+    }
+
     let repo_root = state
         .account_store
         .get_repo_root(&params.did)
@@ -78,6 +105,26 @@ where
         .unwrap())
 }
 
+/// Helper: the sync error for a repo whose account is not active.
+fn check_repo_available(
+    did: &str,
+    status: &dallaspds_core::AccountStatus,
+) -> Result<(), XrpcError> {
+    let (error, reason) = match status {
+        dallaspds_core::AccountStatus::Takendown => ("RepoTakendown", "has been taken down"),
+        dallaspds_core::AccountStatus::Deactivated => ("RepoDeactivated", "is deactivated"),
+        dallaspds_core::AccountStatus::Suspended => ("RepoSuspended", "is suspended"),
+        dallaspds_core::AccountStatus::Active | dallaspds_core::AccountStatus::Deleted => {
+            return Ok(());
+        }
+    };
+    Err(XrpcError::new(
+        StatusCode::BAD_REQUEST,
+        error,
+        format!("repo {did} {reason}"),
+    ))
+}
+
 // ---------------------------------------------------------------------------
 // 2. getLatestCommit
 // ---------------------------------------------------------------------------
@@ -129,33 +176,150 @@ pub struct GetBlobQuery {
 pub async fn get_blob<A, R, B>(
     State(state): State<AppState<A, R, B>>,
     Query(params): Query<GetBlobQuery>,
+    headers: HeaderMap,
 ) -> Result<Response, XrpcError>
 where
     A: AccountStore,
     R: RepoStore,
     B: BlobStore,
 {
+    let blob_not_found = || {
+        XrpcError::new(
+            StatusCode::NOT_FOUND,
+            "BlobNotFound",
+            format!("blob not found: {}", params.cid),
+        )
+    };
+
+    // Blobs of repos hosted elsewhere are fetched from their own PDS, if
+    // the operator allows it.
+    if state.config.blobs.allow_blob_proxy
+        && params.did.starts_with("did:")
+        && state
+            .account_store
+            .get_account_by_did(&params.did)
+            .await?
+            .is_none()
+    {
+        return get_remote_blob(&state, &params, &headers).await;
+    }
+
+    // A taken-down blob is reported as missing, like one never uploaded.
+    if state
+        .account_store
+        .get_blob_takedown(&params.did, &params.cid)
+        .await?
+        .is_some()
+    {
+        return Err(blob_not_found());
+    }
+
+    let etag = etag_for_cid(&params.cid);
+
+    // Let the fronting web server send the file itself, if it can.
+    if let Some(prefix) = &state.config.blobs.x_accel_redirect_prefix
+        && let Some((path, mime_type)) = state
+            .blob_store
+            .get_blob_file(&params.did, &params.cid)
+            .await?
+    {
+        if if_none_match(&headers, &etag) {
+            return Ok(not_modified(&etag, Some(IMMUTABLE_CACHE_CONTROL)));
+        }
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, mime_type)
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, IMMUTABLE_CACHE_CONTROL)
+            .header("x-accel-redirect", format!("{}/{path}", prefix.trim_end_matches('/')))
+            .body(Body::empty())
+            .unwrap());
+    }
+
     let blob = state
         .blob_store
         .get_blob(&params.did, &params.cid)
         .await?
-        .ok_or_else(|| {
-            XrpcError::new(
-                StatusCode::NOT_FOUND,
-                "BlobNotFound",
-                format!("blob not found: {}", params.cid),
-            )
-        })?;
+        .ok_or_else(blob_not_found)?;
+
+    // Blob bytes never change for a CID, so a client holding the CID
+    // already has the content.
+    if if_none_match(&headers, &etag) {
+        return Ok(not_modified(&etag, Some(IMMUTABLE_CACHE_CONTROL)));
+    }
 
     let (data, mime_type) = blob;
 
     Ok(Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, mime_type)
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, IMMUTABLE_CACHE_CONTROL)
         .body(Body::from(data))
         .unwrap())
 }
 
+/// Serve a blob for a repo that is not hosted on this PDS by proxying it
+/// from the PDS listed in the repo's DID document.
+async fn get_remote_blob<A, R, B>(
+    state: &AppState<A, R, B>,
+    params: &GetBlobQuery,
+    headers: &HeaderMap,
+) -> Result<Response, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let etag = etag_for_cid(&params.cid);
+    if if_none_match(headers, &etag) {
+        return Ok(not_modified(&etag, Some(IMMUTABLE_CACHE_CONTROL)));
+    }
+
+    let blob = match state.remote_blob_cache.get(&params.did, &params.cid) {
+        Some(blob) => blob,
+        None => {
+            let endpoint = crate::proxy::remote_record::resolve_pds_endpoint(
+                &state.pds_endpoint_cache,
+                &state.config.plc_url,
+                &state.config.http_retry,
+                &params.did,
+            )
+            .await?
+            .ok_or_else(|| {
+                XrpcError::new(
+                    StatusCode::BAD_REQUEST,
+                    "RepoNotFound",
+                    format!("could not resolve PDS for {}", params.did),
+                )
+            })?;
+            let blob = crate::proxy::remote_blob::fetch_remote_blob(
+                &endpoint,
+                state.config.allow_private_endpoints,
+                &params.did,
+                &params.cid,
+            )
+            .await?;
+            state.remote_blob_cache.insert(&params.did, &params.cid, blob.clone());
+            blob
+        }
+    };
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_SECURITY_POLICY, crate::proxy::remote_blob::REMOTE_BLOB_CSP)
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, IMMUTABLE_CACHE_CONTROL);
+    if !crate::proxy::remote_blob::is_inline_media(&blob.mime_type) {
+        response = response.header(header::CONTENT_DISPOSITION, "attachment");
+    }
+    Ok(response
+        .header(header::CONTENT_TYPE, blob.mime_type)
+        .body(Body::from(blob.data))
+        .unwrap())
+}
+
 // ---------------------------------------------------------------------------
 // 4. listBlobs
 // ---------------------------------------------------------------------------
@@ -163,6 +327,8 @@ where
 #[derive(Debug, Deserialize)]
 pub struct ListBlobsQuery {
     pub did: String,
+    /// Only list blobs first referenced by a commit after this rev.
+    pub since: Option<String>,
     pub cursor: Option<String>,
     pub limit: Option<usize>,
 }
@@ -177,10 +343,20 @@ where
     B: BlobStore,
 {
     let limit = params.limit.unwrap_or(500).min(1000);
-    let cids = state
-        .blob_store
-        .list_blobs(&params.did, params.cursor.as_deref(), limit)
-        .await?;
+    let cids = match params.since.as_deref() {
+        Some(since) => {
+            state
+                .repo_store
+                .list_blobs_since(&params.did, since, params.cursor.as_deref(), limit)
+                .await?
+        }
+        None => {
+            state
+                .blob_store
+                .list_blobs(&params.did, params.cursor.as_deref(), limit)
+                .await?
+        }
+    };
 
     let cursor = if cids.len() >= limit {
         cids.last().cloned()
@@ -216,42 +392,29 @@ where
     B: BlobStore,
 {
     let limit = params.limit.unwrap_or(500).min(1000);
-    let accounts = state
+    let entries = state
         .account_store
-        .list_accounts(params.cursor.as_deref(), limit)
+        .list_repos(params.cursor.as_deref(), limit)
         .await?;
 
-    let mut repos = Vec::new();
-    for account in &accounts {
-        if let Some(root) = state.account_store.get_repo_root(&account.did).await? {
-            // Skip accounts with empty repo roots (newly created, no commits yet).
-            if root.cid.is_empty() {
-                continue;
-            }
-            let head = cid_bytes_to_string(&root.cid)?;
-            let active = account.status == dallaspds_core::AccountStatus::Active;
-            let mut repo = json!({
-                "did": account.did,
-                "head": head,
-                "rev": root.rev,
-                "active": active,
-            });
-            if !active {
-                let status = match account.status {
-                    dallaspds_core::AccountStatus::Deactivated => "deactivated",
-                    dallaspds_core::AccountStatus::Takendown => "takendown",
-                    dallaspds_core::AccountStatus::Suspended => "suspended",
-                    dallaspds_core::AccountStatus::Deleted => "deleted",
-                    dallaspds_core::AccountStatus::Active => unreachable!(),
-                };
-                repo["status"] = json!(status);
-            }
-            repos.push(repo);
+    let mut repos = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let head = cid_bytes_to_string(&entry.cid)?;
+        let status = inactive_status(&entry.status);
+        let mut repo = json!({
+            "did": entry.did,
+            "head": head,
+            "rev": entry.rev,
+            "active": status.is_none(),
+        });
+        if let Some(status) = status {
+            repo["status"] = json!(status);
         }
+        repos.push(repo);
     }
 
-    let cursor = if accounts.len() >= limit {
-        accounts.last().map(|a| json!(a.did))
+    let cursor = if entries.len() >= limit {
+        entries.last().map(|e| json!(e.did))
     } else {
         None
     };
@@ -263,3 +426,246 @@ where
 
     Ok(Json(response))
 }
+
+// ---------------------------------------------------------------------------
+// 6. getRepoStatus
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct GetRepoStatusQuery {
+    pub did: String,
+}
+
+pub async fn get_repo_status<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    Query(params): Query<GetRepoStatusQuery>,
+) -> Result<Json<Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let account = state
+        .account_store
+        .get_account_by_did(&params.did)
+        .await?
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("repository not found for {}", params.did),
+            )
+        })?;
+
+    let status = inactive_status(&account.status);
+    let mut response = json!({
+        "did": account.did,
+        "active": status.is_none(),
+    });
+    if let Some(status) = status {
+        response["status"] = json!(status);
+    }
+
+    if let Some(root) = state.account_store.get_repo_root(&params.did).await?
+        && !root.cid.is_empty()
+    {
+        response["rev"] = json!(root.rev);
+    }
+
+    Ok(Json(response))
+}
+
+// ---------------------------------------------------------------------------
+// 7. com.dallaspds.sync.getCommit — raw signed commit block
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct GetCommitQuery {
+    pub did: String,
+    /// Commit to fetch; defaults to the current head.
+    pub cid: Option<String>,
+}
+
+/// Return a single signed commit block as DAG-CBOR, for checking commit
+/// signatures without downloading the whole repo.
+pub async fn get_commit<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    Query(params): Query<GetCommitQuery>,
+) -> Result<Response, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let commit_cid = params
+        .cid
+        .as_deref()
+        .map(ipld_core::cid::Cid::try_from)
+        .transpose()
+        .map_err(|e| XrpcError::new(StatusCode::BAD_REQUEST, "InvalidRequest", format!("invalid cid: {e}")))?;
+
+    let repo_root = state
+        .account_store
+        .get_repo_root(&params.did)
+        .await?
+        .filter(|root| !root.cid.is_empty())
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("repository not found for {}", params.did),
+            )
+        })?;
+
+    let (_, block) = dallaspds_repo::get_commit_block(
+        state.repo_store.clone(),
+        &params.did,
+        &repo_root.cid,
+        commit_cid.as_ref(),
+    )
+    .await?
+    .ok_or_else(|| {
+        XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "CommitNotFound",
+            format!(
+                "commit {} is not in the history of {}",
+                params.cid.as_deref().unwrap_or("head"),
+                params.did
+            ),
+        )
+    })?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/vnd.ipld.dag-cbor")
+        .body(Body::from(block))
+        .unwrap())
+}
+
+// ---------------------------------------------------------------------------
+// 8. getBlocks — specific blocks as a CAR file
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct GetBlocksQuery {
+    pub did: String,
+    #[serde(default)]
+    pub cids: Vec<String>,
+}
+
+/// Return the requested blocks of a repo as a CAR file. Blocks the repo
+/// doesn't have are left out rather than failing the request.
+pub async fn get_blocks<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    axum_extra::extract::Query(params): axum_extra::extract::Query<GetBlocksQuery>,
+) -> Result<Response, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let cids = params
+        .cids
+        .iter()
+        .map(|cid| ipld_core::cid::Cid::try_from(cid.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| XrpcError::new(StatusCode::BAD_REQUEST, "InvalidRequest", format!("invalid cid: {e}")))?;
+
+    let account = state
+        .account_store
+        .get_account_by_did(&params.did)
+        .await?
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("repository not found for {}", params.did),
+            )
+        })?;
+    check_repo_available(&params.did, &account.status)?;
+
+    let mut blocks = Vec::with_capacity(cids.len());
+    for cid in &cids {
+        blocks.push(state.repo_store.get_block(&params.did, &cid.to_bytes()).await?);
+    }
+    let car_bytes = dallaspds_repo::blocks_to_car(&cids, &blocks)?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/vnd.ipld.car")
+        .body(Body::from(car_bytes))
+        .unwrap())
+}
+
+// ---------------------------------------------------------------------------
+// 9. getRecord — a record and its inclusion proof as a CAR file
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct GetRecordQuery {
+    pub did: String,
+    pub collection: String,
+    pub rkey: String,
+}
+
+/// Return the current commit, the MST nodes leading to the record and the
+/// record block as a CAR file. For a record that doesn't exist the CAR
+/// proves its absence instead.
+pub async fn get_record<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    Query(params): Query<GetRecordQuery>,
+) -> Result<Response, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    if !dallaspds_repo::is_valid_rkey(&params.rkey) {
+        return Err(XrpcError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            format!("invalid rkey: {}", params.rkey),
+        ));
+    }
+
+    let account = state
+        .account_store
+        .get_account_by_did(&params.did)
+        .await?
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("repository not found for {}", params.did),
+            )
+        })?;
+    check_repo_available(&params.did, &account.status)?;
+
+    let repo_root = state
+        .account_store
+        .get_repo_root(&params.did)
+        .await?
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::BAD_REQUEST,
+                "RepoNotFound",
+                format!("repository not found for {}", params.did),
+            )
+        })?;
+
+    let car_bytes = dallaspds_repo::generate_record_proof_car(
+        state.repo_store.clone(),
+        &params.did,
+        &repo_root.cid,
+        &params.collection,
+        &params.rkey,
+    )
+    .await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/vnd.ipld.car")
+        .body(Body::from(car_bytes))
+        .unwrap())
+}
@@ -1,21 +1,44 @@
-use axum::extract::State;
-use axum::http::{HeaderMap, StatusCode};
+use axum::extract::{FromRequestParts, Path, State};
+use axum::http::request::Parts;
+use axum::http::{StatusCode, header};
 use axum::response::IntoResponse;
+use axum::Json;
 
 use crate::error::XrpcError;
 use crate::state::AppState;
 use dallaspds_core::config::PdsMode;
 use dallaspds_core::traits::*;
 
+/// The host a request was sent to: its `Host` header, else the HTTP/2
+/// authority. `X-Forwarded-Host` and `Forwarded` are ignored, since any
+/// client can set them and they decide which account's DID is served.
+pub struct Host(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for Host {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let host = parts
+            .headers
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| parts.uri.authority().map(|authority| authority.as_str()))
+            .unwrap_or_default();
+        Ok(Host(host.to_string()))
+    }
+}
+
 /// GET /.well-known/atproto-did
 ///
 /// Returns the DID of the account as plain text.
 ///
 /// - In single-user mode: returns the DID of the sole account.
-/// - In multi-user mode: resolves from Host header to find the account.
+/// - In multi-user mode: resolves the requested host (the `Host` header, or
+///   the HTTP/2 authority) as a handle to find the account, so each hosted
+///   `{handle}.{domain}` subdomain verifies its own handle.
 pub async fn atproto_did<A, R, B>(
     State(state): State<AppState<A, R, B>>,
-    headers: HeaderMap,
+    host: Host,
 ) -> Result<impl IntoResponse, XrpcError>
 where
     A: AccountStore,
@@ -24,14 +47,9 @@ where
 {
     match state.config.mode {
         PdsMode::Multi => {
-            // Extract handle from Host header.
-            let host = headers
-                .get("host")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("");
-
-            // Strip port if present.
-            let hostname = host.split(':').next().unwrap_or(host);
+            // Strip the port and any trailing root dot.
+            let Host(host) = host;
+            let hostname = host.split(':').next().unwrap_or(&host).trim_end_matches('.');
 
             let account = state
                 .account_store
@@ -70,3 +88,81 @@ where
         }
     }
 }
+
+/// GET /.well-known/did.json
+///
+/// Returns the DID document for the `did:web` of the requested host (the
+/// `Host` header, else `hostname`): the account of a single-user PDS with
+/// `did_web` set, or a did:web account migrated here whose domain points
+/// at this PDS.
+pub async fn did_json<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    host: Host,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let did = crate::did_doc::hostname_did_web(&request_host(&state, host));
+    local_did_document(&state, &did).await.map(Json)
+}
+
+/// GET /user/{id}/did.json
+///
+/// Returns the DID document for the path-based `did:web:{host}:user:{id}`.
+pub async fn user_did_json<A, R, B>(
+    State(state): State<AppState<A, R, B>>,
+    host: Host,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let did = format!(
+        "{}:user:{id}",
+        crate::did_doc::hostname_did_web(&request_host(&state, host))
+    );
+    local_did_document(&state, &did).await.map(Json)
+}
+
+/// The requested host, without any trailing root dot, falling back to the
+/// configured hostname.
+fn request_host<A, R, B>(state: &AppState<A, R, B>, host: Host) -> String
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    match host.0.trim_end_matches('.') {
+        "" => state.config.hostname.clone(),
+        host => host.to_ascii_lowercase(),
+    }
+}
+
+/// The DID document of the local account `did`, or 404.
+async fn local_did_document<A, R, B>(
+    state: &AppState<A, R, B>,
+    did: &str,
+) -> Result<serde_json::Value, XrpcError>
+where
+    A: AccountStore,
+    R: RepoStore,
+    B: BlobStore,
+{
+    let account = state
+        .account_store
+        .get_account_by_did(did)
+        .await?
+        .ok_or_else(|| {
+            XrpcError::new(
+                StatusCode::NOT_FOUND,
+                "AccountNotFound",
+                format!("No account found for {did}"),
+            )
+        })?;
+
+    Ok(crate::did_doc::did_document(&account, &state.config.public_url))
+}
@@ -0,0 +1,86 @@
+//! Graceful shutdown on SIGTERM/SIGINT.
+//!
+//! On a signal the server stops accepting connections, firehose streams are
+//! told to reconnect elsewhere and closed, and in-flight requests get up to
+//! `server.shutdown_grace_secs` to finish before the process exits.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Process-wide shutdown flag watched by long-lived handlers such as
+/// firehose streams.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self {
+            sender: Arc::new(watch::Sender::new(false)),
+        }
+    }
+}
+
+impl Shutdown {
+    /// Begin shutting down.
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Resolve once shutdown has begun.
+    pub async fn wait(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|&triggered| triggered).await;
+    }
+}
+
+/// Wait for SIGTERM or SIGINT, then trigger `shutdown` and ask the server
+/// behind `handle` to stop, giving open connections `grace` to finish.
+pub async fn shutdown_on_signal<A>(
+    handle: axum_server::Handle<A>,
+    shutdown: Shutdown,
+    grace: Duration,
+) where
+    A: axum_server::Address,
+{
+    wait_for_signal().await;
+    tracing::info!("Shutdown signal received; draining connections for up to {grace:?}");
+    shutdown.trigger();
+    handle.graceful_shutdown(Some(grace));
+}
+
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for SIGINT: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
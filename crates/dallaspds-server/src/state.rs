@@ -3,9 +3,22 @@ use std::sync::Arc;
 use dallaspds_core::config::PdsConfig;
 use dallaspds_core::traits::*;
 
+use dallaspds_crypto::{AccessTokenKeys, TidGenerator};
+
 use crate::email::EmailSender;
 use crate::firehose::relay::RelayNotifier;
+use crate::firehose::retention::SubscriberCursors;
 use crate::firehose::sequencer::Sequencer;
+use crate::lexicon::LexiconSet;
+use crate::limits::{RepoQuota, WriteLimiter};
+use crate::oauth_client::ClientMetadataCache;
+use crate::proxy::remote_blob::RemoteBlobCache;
+use crate::proxy::remote_record::PdsEndpointCache;
+use crate::proxy::response_cache::AppViewCache;
+use crate::rate_limit::RateLimiter;
+use crate::read_only::ReadOnly;
+use crate::shutdown::Shutdown;
+use crate::webhooks::WebhookNotifier;
 
 #[derive(Clone)]
 pub struct AppState<A, R, B>
@@ -18,12 +31,43 @@ where
     pub repo_store: Arc<R>,
     pub blob_store: Arc<B>,
     pub config: Arc<PdsConfig>,
+    /// Keys for signing and verifying access tokens, per `jwt.algorithm`.
+    pub access_token_keys: Arc<AccessTokenKeys>,
+    /// Process-wide TID generator for record keys and commit revs.
+    ///
+    /// Shared so that TIDs stay strictly increasing across concurrent requests.
+    pub tid_gen: Arc<TidGenerator>,
     /// Firehose event sequencer (None if firehose is disabled).
-    pub sequencer: Option<Sequencer>,
+    pub sequencer: Option<Arc<dyn Sequencer>>,
     /// Relay notifier (None if no relay is configured).
     pub relay_notifier: Option<RelayNotifier>,
+    /// Account lifecycle webhook (None if not configured).
+    pub webhooks: Option<WebhookNotifier>,
     /// Event store for firehose persistence (None if not configured).
     pub event_store: Option<Arc<dyn EventStore>>,
+    /// Positions of connected firehose subscribers, which bound event pruning.
+    pub subscriber_cursors: Arc<SubscriberCursors>,
     /// Email sender (None if SMTP is not configured).
     pub email_sender: Option<Arc<EmailSender>>,
+    /// Resolved PDS endpoints for repos hosted elsewhere.
+    pub pds_endpoint_cache: Arc<PdsEndpointCache>,
+    /// Blobs recently proxied from other PDSes.
+    pub remote_blob_cache: Arc<RemoteBlobCache>,
+    /// Lexicon schemas for record validation and read-time normalization
+    /// (None if both are disabled).
+    pub lexicons: Option<Arc<LexiconSet>>,
+    /// Global cap on concurrent repo writes.
+    pub write_limiter: Arc<WriteLimiter>,
+    /// Per-account cap on stored repo bytes.
+    pub repo_quota: Arc<RepoQuota>,
+    /// Per-client request rate limits.
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Recently proxied AppView GET responses.
+    pub appview_cache: Arc<AppViewCache>,
+    /// Fetched OAuth client metadata documents.
+    pub oauth_clients: Arc<ClientMetadataCache>,
+    /// Set once the server starts shutting down.
+    pub shutdown: Shutdown,
+    /// Maintenance mode, in which mutating endpoints are refused.
+    pub read_only: ReadOnly,
 }
@@ -0,0 +1,163 @@
+//! Notifications of account lifecycle events to an operator's endpoint.
+//!
+//! Each event is POSTed as JSON with the hex-encoded HMAC-SHA256 of the body,
+//! keyed by the configured secret, in the `x-signature` header. Delivery is
+//! best-effort: it happens off the request path and is retried a few times
+//! before the event is dropped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use dallaspds_core::config::WebhooksConfig;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+
+/// Header carrying the body signature.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Attempts per event before it is dropped.
+const MAX_ATTEMPTS: u32 = 5;
+/// Delay before the first retry; doubled on each further failure.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Account lifecycle events reported to the webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AccountEventKind {
+    #[serde(rename = "account.create")]
+    Create,
+    #[serde(rename = "account.activate")]
+    Activate,
+    #[serde(rename = "account.deactivate")]
+    Deactivate,
+    #[serde(rename = "account.delete")]
+    Delete,
+    #[serde(rename = "account.takedown")]
+    Takedown,
+    #[serde(rename = "account.reverseTakedown")]
+    ReverseTakedown,
+}
+
+/// JSON body of a webhook request.
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    event: AccountEventKind,
+    did: &'a str,
+    time: String,
+}
+
+/// Queues account events for delivery to the configured webhook.
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    sender: mpsc::UnboundedSender<Vec<u8>>,
+}
+
+impl WebhookNotifier {
+    /// Returns the notifier handle and a worker that should be spawned to
+    /// deliver the events.
+    pub fn new(config: &WebhooksConfig) -> (Self, WebhookWorker) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let worker = WebhookWorker {
+            target: Arc::new(WebhookTarget {
+                url: config.url.clone(),
+                secret: config.secret.clone(),
+                client: reqwest::Client::new(),
+            }),
+            receiver,
+        };
+        (Self { sender }, worker)
+    }
+
+    /// Report `event` for `did`. Never blocks; the event is timestamped now.
+    pub fn notify(&self, event: AccountEventKind, did: &str) {
+        let payload = WebhookPayload {
+            event,
+            did,
+            time: chrono::Utc::now().to_rfc3339(),
+        };
+        match serde_json::to_vec(&payload) {
+            Ok(body) => {
+                let _ = self.sender.send(body);
+            }
+            Err(e) => tracing::warn!("Failed to encode webhook payload: {e}"),
+        }
+    }
+}
+
+pub struct WebhookWorker {
+    target: Arc<WebhookTarget>,
+    receiver: mpsc::UnboundedReceiver<Vec<u8>>,
+}
+
+impl WebhookWorker {
+    /// Deliver events until every [`WebhookNotifier`] is dropped. Should be
+    /// spawned as a tokio task.
+    ///
+    /// Each event is delivered by its own task, so one being retried doesn't
+    /// hold up the rest.
+    pub async fn run(mut self) {
+        while let Some(body) = self.receiver.recv().await {
+            tokio::spawn(self.target.clone().deliver_with_retry(body));
+        }
+    }
+}
+
+struct WebhookTarget {
+    url: String,
+    secret: String,
+    client: reqwest::Client,
+}
+
+impl WebhookTarget {
+    async fn deliver_with_retry(self: Arc<Self>, body: Vec<u8>) {
+        let signature = sign(&self.secret, &body);
+        let mut delay = INITIAL_RETRY_DELAY;
+        for attempt in 1..=MAX_ATTEMPTS {
+            if self.deliver(&body, &signature).await {
+                return;
+            }
+            if attempt < MAX_ATTEMPTS {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+        tracing::warn!(
+            "Dropping webhook event after {MAX_ATTEMPTS} failed attempts to {}",
+            self.url
+        );
+    }
+
+    /// Send one request and report whether the endpoint accepted it.
+    async fn deliver(&self, body: &[u8], signature: &str) -> bool {
+        let result = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .timeout(REQUEST_TIMEOUT)
+            .body(body.to_vec())
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => true,
+            Ok(resp) => {
+                tracing::warn!("Webhook at {} returned {}", self.url, resp.status());
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Failed to deliver webhook to {}: {e}", self.url);
+                false
+            }
+        }
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of `body` keyed by `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
@@ -24,7 +24,11 @@ async fn deactivate_account() {
 
 #[tokio::test]
 async fn activate_after_deactivation() {
-    let (router, stores) = create_test_router_and_stores().await;
+    let plc = spawn_fake_plc().await;
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = plc.url.clone();
+    let router = create_test_router_with_config(&stores, config);
     let (did, jwt, _) = create_account_via_api(&router, "react.test.pds.local").await;
 
     // Deactivate
@@ -95,6 +99,75 @@ async fn delete_wrong_password_fails() {
     assert_xrpc_error(status, &body, 401, "InvalidPassword");
 }
 
+#[tokio::test]
+async fn delete_with_grace_period_can_be_cancelled_or_purged() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    config.account_deletion_grace_secs = 3600;
+    let state = create_test_app_state_with_config(&stores, config);
+    let router = dallaspds_server::build_router(state.clone());
+    let (did, jwt, _) = create_account_via_api(&router, "graced.test.pds.local").await;
+
+    let delete = || {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.server.deleteAccount",
+            Some(&jwt),
+            Some(json!({ "did": did, "password": TEST_PASSWORD })),
+        )
+    };
+    let cancel = || {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.dallaspds.server.cancelAccountDeletion",
+            Some(&jwt),
+            None,
+        )
+    };
+
+    // Deleting only schedules the deletion and deactivates the account.
+    let (status, body) = delete().await;
+    assert_xrpc_ok(status, &body);
+    let account = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    assert_eq!(account.status, dallaspds_core::AccountStatus::Deactivated);
+    assert!(account.delete_after.unwrap() > chrono::Utc::now());
+
+    let (status, body) = cancel().await;
+    assert_xrpc_ok(status, &body);
+    let account = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    assert_eq!(account.status, dallaspds_core::AccountStatus::Active);
+    assert!(account.delete_after.is_none());
+    let (status, body) = cancel().await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+
+    // Not purged while in grace, then purged once it has passed.
+    let (status, body) = delete().await;
+    assert_xrpc_ok(status, &body);
+    // activateAccount can't be used to dodge the deletion.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.activateAccount",
+        Some(&jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+    assert_eq!(dallaspds_server::cleanup::purge_scheduled_deletions(&state).await.unwrap(), 0);
+    stores
+        .account_store
+        .set_delete_after(&did, Some(chrono::Utc::now() - chrono::Duration::seconds(1)))
+        .await
+        .unwrap();
+    assert_eq!(dallaspds_server::cleanup::purge_scheduled_deletions(&state).await.unwrap(), 1);
+    assert!(stores.account_store.get_account_by_did(&did).await.unwrap().is_none());
+}
+
 // ── Phase 1: Multi-user Admin Tests ────────────────────────────────────
 
 #[tokio::test]
@@ -241,6 +314,71 @@ async fn admin_can_search_accounts() {
     assert!(found_alice, "should find alice account");
 }
 
+#[tokio::test]
+async fn admin_can_filter_accounts_by_status_and_email() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (alice, _, _) = create_account_via_api(&temp_router, "alice.test.pds.local").await;
+    let (bob, _, _) = create_account_via_api(&temp_router, "bob.test.pds.local").await;
+    let (spam, _, _) = create_account_via_api(&temp_router, "spam.test.pds.local").await;
+    stores.account_store.confirm_email(&admin_did).await.unwrap();
+    stores.account_store.confirm_email(&alice).await.unwrap();
+    stores.account_store.set_takedown(&spam, Some("spam")).await.unwrap();
+
+    config.admin_dids = vec![admin_did.clone()];
+    let router = create_test_router_with_config(&stores, config);
+    let list = |query: &'static str| {
+        let (router, jwt) = (router.clone(), admin_jwt.clone());
+        async move {
+            let (status, body) = send_request(
+                &router,
+                "GET",
+                &format!("/xrpc/com.dallaspds.admin.listAccounts?{query}"),
+                Some(&jwt),
+                None,
+            )
+            .await;
+            assert_xrpc_ok(status, &body);
+            let mut dids: Vec<String> = body["accounts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|a| a["did"].as_str().unwrap().to_string())
+                .collect();
+            dids.sort();
+            dids
+        }
+    };
+    let sorted = |mut dids: Vec<&String>| {
+        dids.sort();
+        dids.into_iter().cloned().collect::<Vec<_>>()
+    };
+
+    assert_eq!(list("emailConfirmed=false").await, sorted(vec![&bob, &spam]));
+    assert_eq!(list("status=takendown").await, vec![spam.clone()]);
+    assert_eq!(list("status=active&emailConfirmed=false").await, vec![bob.clone()]);
+    // The text query composes with the filters.
+    assert_eq!(list("query=alice&emailConfirmed=true").await, vec![alice.clone()]);
+    assert!(list("query=alice&status=takendown").await.is_empty());
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.dallaspds.admin.listAccounts?status=bogus",
+        Some(&admin_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+}
+
 #[tokio::test]
 async fn admin_can_list_all_accounts() {
     let stores = create_test_stores().await;
@@ -603,3 +741,652 @@ async fn non_admin_cannot_search_accounts() {
     .await;
     assert_xrpc_error(status, &body, 403, "Forbidden");
 }
+
+#[tokio::test]
+async fn admin_revokes_all_sessions() {
+    let stores = create_test_stores().await;
+
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+
+    let (admin_did, admin_jwt, _) = create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (target_did, target_jwt, target_refresh) =
+        create_account_via_api(&temp_router, "target.test.pds.local").await;
+
+    config.admin_dids = vec![admin_did.clone()];
+    let router = create_test_router_with_config(&stores, config);
+
+    // The target cannot revoke sessions themselves through the admin route.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        &format!("/xrpc/com.dallaspds.admin.revokeAllSessions?did={}", target_did),
+        Some(&target_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 403, "Forbidden");
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        &format!("/xrpc/com.dallaspds.admin.revokeAllSessions?did={}", target_did),
+        Some(&admin_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["did"], target_did);
+    assert_eq!(body["revoked"], 1);
+
+    // The revoked refresh token no longer works.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&target_refresh),
+        None,
+    )
+    .await;
+    assert_eq!(status, 401, "refresh should fail after revocation: {body}");
+}
+
+#[tokio::test]
+async fn admin_corrects_blob_mime_type() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (did, jwt, _) = create_account_via_api(&temp_router, "uploader.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config);
+
+    // Upload a PNG mislabelled as plain text.
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.uploadBlob")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "text/plain")
+        .body(axum::body::Body::from(b"\x89PNG not really".to_vec()))
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let cid = body["blob"]["ref"]["$link"].as_str().unwrap().to_string();
+
+    let fix = json!({ "did": did, "cid": cid, "mimeType": "image/png" });
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.setBlobMime",
+        Some(&jwt),
+        Some(fix.clone()),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 403, "Forbidden");
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.setBlobMime",
+        Some(&admin_jwt),
+        Some(fix),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["mimeType"], "image/png");
+
+    let req = axum::http::Request::builder()
+        .uri(format!("/xrpc/com.atproto.sync.getBlob?did={did}&cid={cid}"))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "image/png");
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&bytes[..], b"\x89PNG not really");
+
+    // Invalid types and unknown blobs are rejected.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.setBlobMime",
+        Some(&admin_jwt),
+        Some(json!({ "did": did, "cid": cid, "mimeType": "png" })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.setBlobMime",
+        Some(&admin_jwt),
+        Some(json!({ "did": did, "cid": "bafkreimissing", "mimeType": "image/png" })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 404, "BlobNotFound");
+}
+
+#[tokio::test]
+async fn admin_verifies_repo_and_reports_missing_blocks() {
+    use dallaspds_core::RepoStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (did, jwt, _) = create_account_via_api(&temp_router, "verify.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config);
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "check me", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let record_cid = body["cid"].as_str().unwrap().to_string();
+
+    let uri = format!("/xrpc/com.dallaspds.admin.verifyRepo?did={did}");
+    let (status, body) = send_request(&router, "GET", &uri, Some(&jwt), None).await;
+    assert_xrpc_error(status, &body, 403, "Forbidden");
+
+    let (status, body) = send_request(&router, "GET", &uri, Some(&admin_jwt), None).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["ok"], true);
+    assert_eq!(body["signature"], "valid");
+    assert!(body["blocksChecked"].as_u64().unwrap() >= 3);
+
+    // Lose the record's block behind the repo's back.
+    let keep = stores
+        .repo_store
+        .get_all_blocks(&did)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(cid, _)| cid)
+        .filter(|cid| dallaspds_repo::cid_from_bytes(cid).unwrap().to_string() != record_cid)
+        .collect();
+    stores.repo_store.retain_blocks(&did, &keep).await.unwrap();
+
+    let (status, body) = send_request(&router, "GET", &uri, Some(&admin_jwt), None).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["ok"], false);
+    assert_eq!(body["missing"], json!([record_cid]));
+    assert_eq!(body["signature"], "valid");
+}
+
+#[tokio::test]
+async fn admin_reindexes_blob_refs() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (did, _, _) = create_account_via_api(&temp_router, "reindex.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config);
+
+    // A record written straight to the repo, as an old import would have,
+    // leaves its blob out of the index.
+    let blob_cid = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+    let account = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    let key =
+        dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key).unwrap();
+    let root = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+    let output = dallaspds_repo::create_record(
+        std::sync::Arc::new(stores.repo_store.clone()),
+        &did,
+        &key,
+        "app.bsky.feed.post",
+        None,
+        &json!({
+            "$type": "app.bsky.feed.post",
+            "text": "old import",
+            "embed": {
+                "$type": "app.bsky.embed.images",
+                "images": [{
+                    "image": {
+                        "$type": "blob",
+                        "ref": { "$link": blob_cid },
+                        "mimeType": "image/png",
+                        "size": 3,
+                    },
+                    "alt": "",
+                }],
+            },
+        }),
+        &dallaspds_crypto::TidGenerator::new(),
+        &root.cid,
+    )
+    .await
+    .unwrap();
+    stores
+        .account_store
+        .update_repo_root(&did, &output.new_root, &output.new_rev)
+        .await
+        .unwrap();
+    let list_blobs_uri = format!("/xrpc/com.atproto.sync.listBlobs?did={did}&since=2222222222222");
+    let list_blobs = || send_request(&router, "GET", &list_blobs_uri, None, None);
+    let (status, body) = list_blobs().await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["cids"], json!([]));
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.reindexBlobRefs",
+        Some(&admin_jwt),
+        Some(json!({ "did": did })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["blobs"], 1);
+    let (status, body) = list_blobs().await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["cids"], json!([blob_cid]));
+}
+
+#[tokio::test]
+async fn admin_rebuilds_lost_repo_root() {
+    use dallaspds_core::{AccountStore, RepoStore};
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (did, jwt, _) = create_account_via_api(&temp_router, "rebuild.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config);
+
+    for text in ["one", "two"] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": { "$type": "app.bsky.feed.post", "text": text, "createdAt": "2025-01-01T00:00:00Z" }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+    let good = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+
+    // The root row is clobbered, but the blocks survive.
+    stores.account_store.update_repo_root(&did, b"", "").await.unwrap();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.rebuildRepoRoot",
+        Some(&jwt),
+        Some(json!({ "did": did })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 403, "Forbidden");
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.rebuildRepoRoot",
+        Some(&admin_jwt),
+        Some(json!({ "did": did })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["rev"], good.rev);
+    let rebuilt = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+    assert_eq!((rebuilt.cid, rebuilt.rev), (good.cid, good.rev));
+
+    // The repo is usable again.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["records"].as_array().unwrap().len(), 2);
+
+    // With no blocks left there is nothing to recover from.
+    stores.repo_store.delete_blocks_for_did(&did).await.unwrap();
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.rebuildRepoRoot",
+        Some(&admin_jwt),
+        Some(json!({ "did": did })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 404, "CommitNotFound");
+}
+
+#[tokio::test]
+async fn admin_takes_down_a_single_blob() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (did, jwt, _) = create_account_via_api(&temp_router, "blobs.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config);
+
+    let mut cids = Vec::new();
+    for data in [&b"offending blob"[..], &b"innocent blob"[..]] {
+        let req = axum::http::Request::builder()
+            .method("POST")
+            .uri("/xrpc/com.atproto.repo.uploadBlob")
+            .header("authorization", format!("Bearer {jwt}"))
+            .header("content-type", "text/plain")
+            .body(axum::body::Body::from(data.to_vec()))
+            .unwrap();
+        let resp = router.clone().oneshot(req).await.unwrap();
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        cids.push(body["blob"]["ref"]["$link"].as_str().unwrap().to_string());
+    }
+    let (offending, innocent) = (&cids[0], &cids[1]);
+
+    let get_blob = |cid: &str| {
+        let req = axum::http::Request::builder()
+            .uri(format!("/xrpc/com.atproto.sync.getBlob?did={did}&cid={cid}"))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let router = router.clone();
+        async move { router.oneshot(req).await.unwrap().status() }
+    };
+    let set_takedown = |applied: bool| {
+        json!({
+            "subject": {
+                "$type": "com.atproto.admin.defs#repoBlobRef",
+                "did": did,
+                "cid": offending,
+            },
+            "takedown": { "applied": applied, "ref": "mod-123" },
+        })
+    };
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.admin.updateSubjectStatus",
+        Some(&admin_jwt),
+        Some(set_takedown(true)),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["subject"]["cid"], offending.as_str());
+    assert_eq!(body["takedown"], json!({ "applied": true, "ref": "mod-123" }));
+
+    assert_eq!(get_blob(offending).await, 404);
+    assert_eq!(get_blob(innocent).await, 200);
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.admin.getSubjectStatus?did={did}&blob={offending}"),
+        Some(&admin_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["takedown"]["applied"], true);
+
+    // The account itself is untouched.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.admin.getSubjectStatus?did={did}"),
+        Some(&admin_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["takedown"]["applied"], false);
+
+    // Reversing the takedown serves the blob again.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.admin.updateSubjectStatus",
+        Some(&admin_jwt),
+        Some(set_takedown(false)),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["takedown"]["applied"], false);
+    assert_eq!(get_blob(offending).await, 200);
+}
+
+#[tokio::test]
+async fn admin_reprovisions_account_without_repo() {
+    use dallaspds_core::{AccountStore, CreateAccountInput};
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    config.admin_dids = vec![admin_did.clone()];
+    let router = create_test_router_with_config(&stores, config);
+
+    // An account inserted straight into the store has an empty repo root.
+    let did = "did:plc:norepo000000000000000000";
+    let signing_key = dallaspds_crypto::SigningKey::generate_p256().unwrap();
+    stores
+        .account_store
+        .create_account(&CreateAccountInput {
+            did: did.to_string(),
+            handle: "norepo.test.pds.local".to_string(),
+            email: None,
+            password_hash: "unused".to_string(),
+            signing_key: signing_key.to_bytes(),
+            key_type: signing_key.key_type().to_string(),
+        })
+        .await
+        .unwrap();
+    let keys = dallaspds_crypto::AccessTokenKeys::hs256(TEST_ACCESS_SECRET);
+    let jwt = dallaspds_crypto::create_access_token(did, &keys).unwrap();
+
+    let create_record = || {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": { "$type": "app.bsky.feed.post", "text": "hi", "createdAt": "2025-01-01T00:00:00Z" }
+            })),
+        )
+    };
+    let (status, _) = create_record().await;
+    assert_ne!(status, 200);
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.reprovisionRepo",
+        Some(&admin_jwt),
+        Some(json!({ "did": did })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let root = stores.account_store.get_repo_root(did).await.unwrap().unwrap();
+    assert_eq!(body["rev"], root.rev);
+
+    let (status, body) = create_record().await;
+    assert_xrpc_ok(status, &body);
+    let (status, _) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getRepo?did={did}"),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    // An existing repo is never replaced.
+    for target in [did, admin_did.as_str()] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.dallaspds.admin.reprovisionRepo",
+            Some(&admin_jwt),
+            Some(json!({ "did": target })),
+        )
+        .await;
+        assert_xrpc_error(status, &body, 400, "RepoAlreadyExists");
+    }
+}
+
+#[tokio::test]
+async fn read_only_mode_blocks_writes_until_lifted() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (did, jwt, _) = create_account_via_api(&temp_router, "user.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config.clone());
+
+    let set_read_only = |jwt: String, read_only: bool| {
+        let router = router.clone();
+        async move {
+            send_request(
+                &router,
+                "POST",
+                "/xrpc/com.dallaspds.admin.setReadOnly",
+                Some(&jwt),
+                Some(json!({ "readOnly": read_only })),
+            )
+            .await
+        }
+    };
+    let create_record = || {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": { "$type": "app.bsky.feed.post", "text": "hi", "createdAt": "2025-01-01T00:00:00Z" }
+            })),
+        )
+    };
+
+    let (status, body) = set_read_only(jwt.clone(), true).await;
+    assert_xrpc_error(status, &body, 403, "Forbidden");
+    let (status, body) = set_read_only(admin_jwt.clone(), true).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["readOnly"], true);
+
+    let (status, body) = create_record().await;
+    assert_xrpc_error(status, &body, 503, "ServiceReadOnly");
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createAccount",
+        None,
+        Some(json!({ "handle": "late.test.pds.local", "password": "password123" })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 503, "ServiceReadOnly");
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.deactivateAccount",
+        Some(&jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 503, "ServiceReadOnly");
+    // So are the admin repairs that rewrite repos and blobs.
+    for method in ["rebuildRepoRoot", "reprovisionRepo"] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            &format!("/xrpc/com.dallaspds.admin.{method}"),
+            Some(&admin_jwt),
+            Some(json!({ "did": did })),
+        )
+        .await;
+        assert_xrpc_error(status, &body, 503, "ServiceReadOnly");
+    }
+
+    // Reads and sync keep working.
+    for path in [
+        format!("/xrpc/com.atproto.repo.describeRepo?repo={did}"),
+        format!("/xrpc/com.atproto.sync.getRepo?did={did}"),
+    ] {
+        let (status, _) = send_request(&router, "GET", &path, None, None).await;
+        assert_eq!(status, 200, "{path}");
+    }
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.dallaspds.admin.getConfig",
+        Some(&admin_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["readOnly"], true);
+
+    let (status, body) = set_read_only(admin_jwt.clone(), false).await;
+    assert_xrpc_ok(status, &body);
+    let (status, body) = create_record().await;
+    assert_xrpc_ok(status, &body);
+
+    // The config option starts the server in read-only mode.
+    config.read_only = true;
+    let router = create_test_router_with_config(&stores, config);
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.deleteRecord",
+        Some(&jwt),
+        Some(json!({ "repo": did, "collection": "app.bsky.feed.post", "rkey": "anything" })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 503, "ServiceReadOnly");
+}
@@ -46,12 +46,12 @@ async fn emit_broadcasts_to_sequencer() {
     // Emit an event via sequencer directly
     use dallaspds_server::firehose::events::{FirehoseEvent, IdentityEvent};
     let event = FirehoseEvent::Identity(IdentityEvent {
-        seq: sequencer.next_seq(),
+        seq: sequencer.next_seq().await.unwrap(),
         did: "did:plc:broadcast".to_string(),
         time: "2025-01-01T00:00:00Z".to_string(),
         handle: Some("broadcast.test".to_string()),
     });
-    sequencer.emit(event);
+    sequencer.emit(event).await;
 
     let received = rx.try_recv().unwrap();
     assert_eq!(received.seq(), 1);
@@ -69,7 +69,7 @@ async fn emit_without_event_store_still_broadcasts() {
 
     use dallaspds_server::firehose::events::{FirehoseEvent, IdentityEvent};
     let event = FirehoseEvent::Identity(IdentityEvent {
-        seq: sequencer.next_seq(),
+        seq: sequencer.next_seq().await.unwrap(),
         did: "did:plc:nostore".to_string(),
         time: "2025-01-01T00:00:00Z".to_string(),
         handle: None,
@@ -81,3 +81,684 @@ async fn emit_without_event_store_still_broadcasts() {
     let received = rx.try_recv().unwrap();
     assert_eq!(received.seq(), 1);
 }
+
+#[tokio::test]
+async fn firehose_disabled_writes_succeed_and_subscribe_reports_disabled() {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.firehose.enabled = false;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "nofirehose.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": {
+                "$type": "app.bsky.feed.post",
+                "text": "no firehose",
+                "createdAt": "2025-01-01T00:00:00Z"
+            }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    // Nothing was persisted.
+    use dallaspds_core::EventStore;
+    let events = stores.event_store.get_events_after(0, 100).await.unwrap();
+    assert!(events.is_empty());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{addr}/xrpc/com.atproto.sync.subscribeRepos"
+    ))
+    .await
+    .unwrap();
+    let frame = match ws.next().await.unwrap().unwrap() {
+        Message::Binary(bytes) => bytes,
+        other => panic!("expected binary error frame, got {other:?}"),
+    };
+    let needle = b"FirehoseDisabled";
+    assert!(
+        frame.windows(needle.len()).any(|w| w == needle),
+        "expected FirehoseDisabled error frame"
+    );
+}
+
+#[tokio::test]
+async fn create_record_op_path_uses_generated_rkey() {
+    use dallaspds_server::firehose::events::FirehoseEvent;
+
+    let stores = create_test_stores().await;
+    let state = create_test_app_state(&stores);
+    let router = dallaspds_server::build_router(state.clone());
+    let mut rx = state.sequencer.as_ref().unwrap().subscribe();
+    let (did, jwt, _) = create_account_via_api(&router, "autorkey.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "no rkey given" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let uri = body["uri"].as_str().unwrap();
+    let rkey = uri.rsplit('/').next().unwrap();
+    assert!(!rkey.is_empty());
+
+    let mut paths = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let FirehoseEvent::Commit(commit) = event.as_ref() {
+            paths.extend(commit.ops.iter().map(|op| op.path.clone()));
+        }
+    }
+    assert_eq!(paths, [format!("app.bsky.feed.post/{rkey}")]);
+}
+
+#[tokio::test]
+async fn oversized_commit_is_sent_too_big_without_blocks() {
+    use dallaspds_server::firehose::events::FirehoseEvent;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.firehose.max_commit_blocks_bytes = 4096;
+    let state = create_test_app_state_with_config(&stores, config);
+    let router = dallaspds_server::build_router(state.clone());
+    let mut rx = state.sequencer.as_ref().unwrap().subscribe();
+    let (did, jwt, _) = create_account_via_api(&router, "toobig.test.pds.local").await;
+
+    for text in ["small".to_string(), "x".repeat(8192)] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": { "$type": "app.bsky.feed.post", "text": text }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+
+    let mut commits = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let FirehoseEvent::Commit(commit) = event.as_ref() {
+            commits.push(commit.clone());
+        }
+    }
+    let [small, large] = commits.as_slice() else {
+        panic!("expected two commits, got {}", commits.len());
+    };
+    assert!(!small.too_big);
+    assert!(!small.blocks.is_empty());
+    assert!(large.too_big);
+    assert!(large.blocks.is_empty());
+    assert_eq!(large.ops.len(), 1);
+
+    // The persisted copy is trimmed the same way.
+    use dallaspds_core::EventStore;
+    let persisted = stores.event_store.get_events_after(0, 100).await.unwrap();
+    let last = dallaspds_server::firehose::wire::decode_event_frame(
+        &persisted.last().unwrap().payload,
+    )
+    .unwrap();
+    let FirehoseEvent::Commit(last) = last else {
+        panic!("expected the last persisted event to be a commit");
+    };
+    assert!(last.too_big);
+    assert!(last.blocks.is_empty());
+}
+
+#[tokio::test]
+async fn delete_record_op_carries_prior_cid() {
+    use dallaspds_server::firehose::events::FirehoseEvent;
+
+    let stores = create_test_stores().await;
+    let state = create_test_app_state(&stores);
+    let router = dallaspds_server::build_router(state.clone());
+    let mut rx = state.sequencer.as_ref().unwrap().subscribe();
+    let (did, jwt, _) = create_account_via_api(&router, "deleteop.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "rkey": "doomed",
+            "record": { "$type": "app.bsky.feed.post", "text": "short-lived" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let record_cid = body["cid"].as_str().unwrap().to_string();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.deleteRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "rkey": "doomed"
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    let mut delete_op = None;
+    while let Ok(event) = rx.try_recv() {
+        if let FirehoseEvent::Commit(commit) = event.as_ref() {
+            delete_op = commit.ops.iter().find(|op| op.action == "delete").cloned().or(delete_op);
+        }
+    }
+    let op = delete_op.expect("delete commit should be emitted");
+    assert_eq!(op.path, "app.bsky.feed.post/doomed");
+    assert!(op.cid.is_none());
+    assert_eq!(op.prev.unwrap().link, record_cid);
+}
+
+#[tokio::test]
+async fn delete_account_emits_final_sync_and_tombstone() {
+    let (router, stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "tombstone.test.pds.local").await;
+
+    send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": {
+                "$type": "app.bsky.feed.post",
+                "text": "about to be deleted",
+                "createdAt": "2025-01-01T00:00:00Z"
+            }
+        })),
+    )
+    .await;
+
+    use dallaspds_core::{AccountStore, EventStore, RepoStore};
+    let final_rev = stores.account_store.get_repo_root(&did).await.unwrap().unwrap().rev;
+
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.deleteAccount",
+        Some(&jwt),
+        Some(json!({ "did": did, "password": TEST_PASSWORD })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    // The last two events for the repo are #sync (final rev) then #account.
+    let events = stores.event_store.get_events_after(0, 100).await.unwrap();
+    let tail: Vec<_> = events.iter().filter(|e| e.did == did).rev().take(2).collect();
+    assert_eq!(tail[0].event_type, "account");
+    assert_eq!(tail[1].event_type, "sync");
+    assert!(tail[1].seq < tail[0].seq);
+    assert!(
+        tail[1].payload.windows(final_rev.len()).any(|w| w == final_rev.as_bytes()),
+        "#sync event should carry the final rev"
+    );
+    assert!(
+        tail[0].payload.windows(b"deleted".len()).any(|w| w == b"deleted"),
+        "#account event should report the deleted status"
+    );
+
+    // No blocks remain, and getRepo reports the repo as gone.
+    assert!(stores.repo_store.get_all_blocks(&did).await.unwrap().is_empty());
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getRepo?did={did}"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "RepoNotFound");
+}
+
+#[tokio::test]
+async fn lagging_subscriber_is_disconnected_with_consumer_too_slow() {
+    use dallaspds_server::firehose::events::{FirehoseEvent, IdentityEvent};
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.firehose.buffer_size = 4;
+    let state = create_test_app_state_with_config(&stores, config);
+    let sequencer = state.sequencer.clone().unwrap();
+    let router = dallaspds_server::build_router(state);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{addr}/xrpc/com.atproto.sync.subscribeRepos"
+    ))
+    .await
+    .unwrap();
+
+    let identity = |seq| {
+        FirehoseEvent::Identity(IdentityEvent {
+            seq,
+            did: "did:plc:laggy".to_string(),
+            time: "2025-01-01T00:00:00Z".to_string(),
+            handle: None,
+        })
+    };
+
+    // Wait until the subscription is live: the first event comes through.
+    let first = loop {
+        sequencer.emit(identity(sequencer.next_seq().await.unwrap())).await;
+        match tokio::time::timeout(std::time::Duration::from_millis(100), ws.next()).await {
+            Ok(frame) => break frame.unwrap().unwrap(),
+            Err(_) => continue,
+        }
+    };
+    assert!(matches!(first, Message::Binary(_)));
+
+    // Overflow the buffer without yielding, so the subscriber falls behind.
+    for _ in 0..10 {
+        sequencer.emit(identity(sequencer.next_seq().await.unwrap())).await;
+    }
+
+    let mut saw_too_slow = false;
+    while let Some(Ok(message)) = ws.next().await {
+        match message {
+            Message::Binary(frame) => {
+                let needle = b"ConsumerTooSlow";
+                saw_too_slow |= frame.windows(needle.len()).any(|w| w == needle);
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    assert!(saw_too_slow, "expected a ConsumerTooSlow error frame before close");
+}
+
+#[tokio::test]
+async fn backfill_interleaved_with_live_writes_has_no_gaps_or_duplicates() {
+    use dallaspds_server::firehose::wire::decode_event_frame;
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.firehose.backfill_batch_size = 3;
+    let state = create_test_app_state_with_config(&stores, config);
+    let sequencer = state.sequencer.clone().unwrap();
+    let router = dallaspds_server::build_router(state);
+    let (did, jwt, _) = create_account_via_api(&router, "backfill.test.pds.local").await;
+
+    let post = |text: String| {
+        let router = router.clone();
+        let did = did.clone();
+        let jwt = jwt.clone();
+        async move {
+            let (status, body) = send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.repo.createRecord",
+                Some(&jwt),
+                Some(json!({
+                    "repo": did,
+                    "collection": "app.bsky.feed.post",
+                    "record": {
+                        "$type": "app.bsky.feed.post",
+                        "text": text,
+                        "createdAt": "2025-01-01T00:00:00Z"
+                    }
+                })),
+            )
+            .await;
+            assert_xrpc_ok(status, &body);
+        }
+    };
+    for i in 0..10 {
+        post(format!("before {i}")).await;
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let serve_router = router.clone();
+    tokio::spawn(async move { axum::serve(listener, serve_router).await.unwrap() });
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{addr}/xrpc/com.atproto.sync.subscribeRepos?cursor=0"
+    ))
+    .await
+    .unwrap();
+
+    let mut next_event = async || loop {
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next())
+            .await
+            .expect("timed out waiting for firehose frame")
+            .unwrap()
+            .unwrap();
+        // Skip the OutdatedCursor info frame.
+        if let Message::Binary(frame) = message
+            && let Ok(event) = decode_event_frame(&frame)
+        {
+            return event.seq();
+        }
+    };
+
+    // Write more while the backfill is still paging through the store.
+    let mut seqs = vec![next_event().await, next_event().await];
+    for i in 0..5 {
+        post(format!("during {i}")).await;
+    }
+
+    let last = sequencer.current_seq().await.unwrap() - 1;
+    while *seqs.last().unwrap() < last {
+        seqs.push(next_event().await);
+    }
+    assert_eq!(seqs, (1..=last).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn pruning_respects_retention_and_subscriber_low_water_mark() {
+    use dallaspds_server::firehose::retention::{SubscriberCursors, prune_events};
+
+    let stores = create_test_stores().await;
+    let event_store = stores.event_store_arc();
+    for i in 1..=10 {
+        event_store
+            .append_event("commit", &format!("did:plc:prune{i}"), b"p")
+            .await
+            .unwrap();
+    }
+    let remaining = async || {
+        let events = event_store.get_events_after(0, 100).await.unwrap();
+        events.iter().map(|e| e.seq).collect::<Vec<_>>()
+    };
+
+    let mut config = create_test_config().firehose;
+    let cursors = std::sync::Arc::new(SubscriberCursors::default());
+
+    // No retention configured: nothing is pruned.
+    assert_eq!(prune_events(event_store.as_ref(), &config, &cursors).await.unwrap(), 0);
+
+    // A subscriber still at seq 3 holds back everything after it.
+    config.retention_events = Some(2);
+    let subscriber = cursors.track(3);
+    assert_eq!(prune_events(event_store.as_ref(), &config, &cursors).await.unwrap(), 3);
+    assert_eq!(remaining().await, (4..=10).collect::<Vec<_>>());
+
+    // Once it catches up, the count limit applies in full.
+    subscriber.advance(10);
+    prune_events(event_store.as_ref(), &config, &cursors).await.unwrap();
+    assert_eq!(remaining().await, vec![9, 10]);
+
+    // An age limit that excludes every event still keeps the newest one.
+    drop(subscriber);
+    config.retention_events = None;
+    config.retention_days = Some(0);
+    prune_events(event_store.as_ref(), &config, &cursors).await.unwrap();
+    assert_eq!(remaining().await, vec![10]);
+}
+
+/// Connect to `subscribeRepos` and collect frames until the server closes.
+async fn collect_frames_until_close(
+    router: axum::Router,
+    query: &str,
+) -> (Vec<Vec<u8>>, bool) {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{addr}/xrpc/com.atproto.sync.subscribeRepos{query}"
+    ))
+    .await
+    .unwrap();
+
+    let mut frames = Vec::new();
+    let mut closed = false;
+    while let Ok(Some(Ok(message))) =
+        tokio::time::timeout(std::time::Duration::from_secs(2), ws.next()).await
+    {
+        match message {
+            Message::Binary(frame) => frames.push(frame.to_vec()),
+            Message::Close(_) => {
+                closed = true;
+                break;
+            }
+            _ => {}
+        }
+    }
+    (frames, closed)
+}
+
+fn frame_contains(frame: &[u8], needle: &str) -> bool {
+    frame.windows(needle.len()).any(|w| w == needle.as_bytes())
+}
+
+#[tokio::test]
+async fn maintenance_mode_defers_new_subscribers() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.firehose.maintenance = true;
+    config.firehose.retry_after_secs = 120;
+    let router = create_test_router_with_config(&stores, config);
+
+    let (frames, closed) = collect_frames_until_close(router, "").await;
+    assert!(closed, "server should close the socket");
+    assert_eq!(frames.len(), 1);
+    assert!(frame_contains(&frames[0], "#info"));
+    assert!(frame_contains(&frames[0], "ReconnectLater"));
+    assert!(frame_contains(&frames[0], "120 seconds"));
+}
+
+#[tokio::test]
+async fn backfill_limit_defers_only_cursor_subscribers() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.firehose.max_concurrent_backfills = 1;
+    let state = create_test_app_state_with_config(&stores, config);
+    let router = dallaspds_server::build_router(state.clone());
+    create_account_via_api(&router, "busy.test.pds.local").await;
+
+    // Another client is already replaying history.
+    let _replaying = state.subscriber_cursors.track_backfill(0);
+
+    let (frames, closed) = collect_frames_until_close(router.clone(), "?cursor=0").await;
+    assert!(closed);
+    assert!(frames.iter().any(|f| frame_contains(f, "ReconnectLater")));
+
+    // Live-only subscribers don't replay anything and are still accepted.
+    let (frames, closed) = collect_frames_until_close(router, "").await;
+    assert!(!closed);
+    assert!(frames.is_empty());
+}
+
+#[tokio::test]
+async fn shutdown_tells_subscribers_to_reconnect_and_drains_the_server() {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let stores = create_test_stores().await;
+    let state = create_test_app_state(&stores);
+    let router = dallaspds_server::build_router(state.clone());
+    let handle = axum_server::Handle::new();
+    let addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = tokio::spawn(
+        axum_server::bind(addr)
+            .handle(handle.clone())
+            .serve(router.into_make_service()),
+    );
+    let addr = handle.listening().await.unwrap();
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{addr}/xrpc/com.atproto.sync.subscribeRepos"
+    ))
+    .await
+    .unwrap();
+    while state.subscriber_cursors.low_water_mark().is_none() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    state.shutdown.trigger();
+    handle.graceful_shutdown(Some(std::time::Duration::from_secs(5)));
+
+    let mut frames = Vec::new();
+    while let Ok(Some(Ok(message))) =
+        tokio::time::timeout(std::time::Duration::from_secs(2), ws.next()).await
+    {
+        match message {
+            Message::Binary(frame) => frames.push(frame.to_vec()),
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(frames.len(), 1);
+    assert!(frame_contains(&frames[0], "#info"));
+    assert!(frame_contains(&frames[0], "ServerShuttingDown"));
+    drop(ws);
+
+    // With the stream closed, the server finishes well within the grace period.
+    tokio::time::timeout(std::time::Duration::from_secs(3), server)
+        .await
+        .expect("server should stop once connections drain")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn subscribers_arriving_during_shutdown_are_deferred() {
+    let stores = create_test_stores().await;
+    let state = create_test_app_state(&stores);
+    state.shutdown.trigger();
+    let router = dallaspds_server::build_router(state);
+
+    let (frames, closed) = collect_frames_until_close(router, "").await;
+    assert!(closed);
+    assert_eq!(frames.len(), 1);
+    assert!(frame_contains(&frames[0], "ReconnectLater"));
+    assert!(frame_contains(&frames[0], "shutting down"));
+}
+
+#[tokio::test]
+async fn reconcile_re_emits_commit_lost_in_a_crash() {
+    use dallaspds_core::{AccountStore, EventStore};
+    use dallaspds_server::firehose::events::FirehoseEvent;
+    use dallaspds_server::firehose::reconcile::reconcile_repo_roots;
+    use dallaspds_server::firehose::wire::decode_event_frame;
+
+    let stores = create_test_stores().await;
+    let state = create_test_app_state(&stores);
+    let router = dallaspds_server::build_router(state.clone());
+    let (did, jwt, _) = create_account_via_api(&router, "crash.test.pds.local").await;
+    let post = |router: axum::Router, text: &'static str| {
+        let (did, jwt) = (did.clone(), jwt.clone());
+        async move {
+            let (status, body) = send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.repo.createRecord",
+                Some(&jwt),
+                Some(json!({
+                    "repo": did,
+                    "collection": "app.bsky.feed.post",
+                    "record": { "$type": "app.bsky.feed.post", "text": text }
+                })),
+            )
+            .await;
+            assert_xrpc_ok(status, &body);
+            body["uri"].as_str().unwrap().to_string()
+        }
+    };
+    post(router.clone(), "emitted").await;
+    assert_eq!(reconcile_repo_roots(&state).await.unwrap(), 0);
+
+    // The process dies between committing and emitting: the repo advances
+    // but no event is persisted.
+    let mut crashed = state.clone();
+    crashed.sequencer = None;
+    crashed.event_store = None;
+    let lost_uri = post(dallaspds_server::build_router(crashed), "lost").await;
+    let root = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+
+    // On restart the missing commit is re-emitted.
+    assert_eq!(reconcile_repo_roots(&state).await.unwrap(), 1);
+    let persisted = stores.event_store.get_last_event_for_did(&did, "commit").await.unwrap();
+    let FirehoseEvent::Commit(event) = decode_event_frame(&persisted.unwrap().payload).unwrap()
+    else {
+        panic!("expected a commit event");
+    };
+    assert_eq!(event.rev, root.rev);
+    assert_eq!(event.ops.len(), 1);
+    assert_eq!(event.ops[0].action, "create");
+    assert!(lost_uri.ends_with(&event.ops[0].path), "{lost_uri} vs {}", event.ops[0].path);
+    assert!(!event.blocks.is_empty());
+
+    // Once caught up, a further restart emits nothing.
+    assert_eq!(reconcile_repo_roots(&state).await.unwrap(), 0);
+}
+
+/// Needs a Redis server: `REDIS_URL=redis://127.0.0.1:6379 cargo test -- --ignored`.
+#[tokio::test]
+#[ignore = "needs a Redis server at REDIS_URL"]
+async fn redis_sequencers_share_seqs_and_events() {
+    use dallaspds_server::firehose::events::{FirehoseEvent, IdentityEvent};
+    use dallaspds_server::firehose::redis::RedisSequencer;
+    use dallaspds_server::firehose::sequencer::Sequencer;
+
+    let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+    let first = RedisSequencer::connect(&url, 1, 16).await.unwrap();
+    let second = RedisSequencer::connect(&url, 1, 16).await.unwrap();
+    let mut rx = second.subscribe();
+
+    // Both processes draw from the one counter.
+    let a = first.next_seq().await.unwrap();
+    let b = second.next_seq().await.unwrap();
+    assert!(b > a);
+    assert_eq!(first.current_seq().await.unwrap(), b + 1);
+
+    // An event emitted by one reaches the other's subscribers.
+    first
+        .emit(FirehoseEvent::Identity(IdentityEvent {
+            seq: a,
+            did: "did:plc:redis".to_string(),
+            time: "2025-01-01T00:00:00Z".to_string(),
+            handle: None,
+        }))
+        .await;
+    let received = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.seq(), a);
+}
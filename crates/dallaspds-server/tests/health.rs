@@ -6,4 +6,100 @@ async fn health_returns_200_with_version() {
     let (status, body) = send_request(&router, "GET", "/xrpc/_health", None, None).await;
     assert_xrpc_ok(status, &body);
     assert_eq!(body["version"], "0.1.0");
+    assert_eq!(body["checks"]["accountStore"], "ok");
+    assert_eq!(body["checks"]["repoStore"], "ok");
+    assert_eq!(body["checks"]["blobStore"], "ok");
+}
+
+#[tokio::test]
+async fn health_returns_503_when_a_store_is_unreachable() {
+    let (router, stores) = create_test_router_and_stores().await;
+    // Swap the blob directory for a file so every blob lookup fails.
+    let blobs = stores._tempdir.path().join("blobs");
+    std::fs::remove_dir_all(&blobs).unwrap();
+    std::fs::write(&blobs, b"").unwrap();
+
+    let (status, body) = send_request(&router, "GET", "/xrpc/_health", None, None).await;
+    assert_eq!(status, 503, "{body}");
+    assert_eq!(body["checks"]["accountStore"], "ok");
+    assert_eq!(body["checks"]["blobStore"], "unavailable");
+
+    // Liveness doesn't look at the stores.
+    let (status, body) = send_request(&router, "GET", "/xrpc/_health/live", None, None).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["version"], "0.1.0");
+}
+
+/// Fails with an `XrpcError` before touching any store.
+const UNAUTHENTICATED: &str = "/xrpc/com.atproto.server.getSession";
+
+async fn get_raw(router: &axum::Router, uri: &str) -> axum::response::Response {
+    use tower::ServiceExt;
+
+    let req = axum::http::Request::builder()
+        .uri(uri)
+        .body(axum::body::Body::empty())
+        .unwrap();
+    router.clone().oneshot(req).await.unwrap()
+}
+
+fn request_id(response: &axum::response::Response) -> String {
+    let id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    uuid::Uuid::parse_str(&id).expect("x-request-id should be a UUID");
+    id
+}
+
+#[tokio::test]
+async fn every_response_carries_a_unique_request_id() {
+    let (router, _stores) = create_test_router_and_stores().await;
+
+    let first = request_id(&get_raw(&router, "/xrpc/_health").await);
+    let second = request_id(&get_raw(&router, "/xrpc/_health").await);
+    assert_ne!(first, second);
+
+    let error = get_raw(&router, UNAUTHENTICATED).await;
+    assert_eq!(error.status(), 401);
+    request_id(&error);
+}
+
+#[tokio::test]
+async fn xrpc_errors_are_logged_with_the_request_id() {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer({
+            let captured = captured.clone();
+            move || captured.clone()
+        })
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let (router, _stores) = create_test_router_and_stores().await;
+    let response = get_raw(&router, UNAUTHENTICATED).await;
+    let id = request_id(&response);
+
+    let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let error_line = logs
+        .lines()
+        .find(|line| line.contains("error=") && !line.contains("request completed"))
+        .unwrap_or_else(|| panic!("no XrpcError log line in:\n{logs}"));
+    assert!(error_line.contains(&id), "{error_line}");
+    assert!(logs.lines().any(|line| line.contains("request completed") && line.contains(&id)));
 }
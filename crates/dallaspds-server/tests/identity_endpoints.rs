@@ -19,7 +19,7 @@ async fn resolve_handle_local() {
 }
 
 #[tokio::test]
-async fn resolve_unknown_404() {
+async fn resolve_unknown_local_handle_is_not_found() {
     let (router, _stores) = create_test_router_and_stores().await;
     let (status, body) = send_request(
         &router,
@@ -29,7 +29,132 @@ async fn resolve_unknown_404() {
         None,
     )
     .await;
-    assert_xrpc_error(status, &body, 404, "HandleNotFound");
+    assert_xrpc_error(status, &body, 400, "HandleNotFound");
+}
+
+#[tokio::test]
+async fn resolve_handle_is_case_insensitive() {
+    // Any handle sent to this resolver fails as an outage.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.handle_resolver_url = Some(closed);
+    let router = create_test_router_with_config(&stores, config);
+    let (did, _, _) = create_account_via_api(&router, "mixed.test.pds.local").await;
+
+    let resolve = |handle: &'static str| {
+        let router = router.clone();
+        async move {
+            let uri = format!("/xrpc/com.atproto.identity.resolveHandle?handle={handle}");
+            send_request(&router, "GET", &uri, None, None).await
+        }
+    };
+    let (status, body) = resolve("Mixed.TEST.pds.local").await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["did"], did);
+
+    // Our own domain in any case is never resolved externally.
+    let (status, body) = resolve("Unknown.TEST.PDS.local").await;
+    assert_xrpc_error(status, &body, 400, "HandleNotFound");
+}
+
+/// Start a fake handle resolver that answers every resolveHandle call with
+/// `status` and `body`.
+async fn mock_handle_resolver(status: u16, body: serde_json::Value) -> String {
+    let respond = move || {
+        let body = body.clone();
+        async move { (axum::http::StatusCode::from_u16(status).unwrap(), axum::Json(body)) }
+    };
+    let app = axum::Router::new().route(
+        "/xrpc/com.atproto.identity.resolveHandle",
+        axum::routing::get(respond),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}")
+}
+
+async fn resolve_external(resolver_url: String) -> (u16, serde_json::Value) {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.handle_resolver_url = Some(resolver_url);
+    let router = create_test_router_with_config(&stores, config);
+    send_request(
+        &router,
+        "GET",
+        "/xrpc/com.atproto.identity.resolveHandle?handle=someone.example.com",
+        None,
+        None,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn resolve_external_handle() {
+    let resolver = mock_handle_resolver(200, json!({ "did": "did:plc:someone" })).await;
+    let (status, body) = resolve_external(resolver).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["did"], "did:plc:someone");
+}
+
+#[tokio::test]
+async fn resolve_unresolvable_external_handle_is_not_found() {
+    let resolver = mock_handle_resolver(
+        400,
+        json!({ "error": "HandleNotFound", "message": "Unable to resolve handle" }),
+    )
+    .await;
+    let (status, body) = resolve_external(resolver).await;
+    assert_xrpc_error(status, &body, 400, "HandleNotFound");
+}
+
+#[tokio::test]
+async fn resolver_outage_is_upstream_failure() {
+    let resolver = mock_handle_resolver(
+        503,
+        json!({ "error": "ServiceUnavailable", "message": "down" }),
+    )
+    .await;
+    let (status, body) = resolve_external(resolver).await;
+    assert_xrpc_error(status, &body, 502, "UpstreamFailure");
+
+    // Nothing listening at all is an outage too.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let (status, body) = resolve_external(closed).await;
+    assert_xrpc_error(status, &body, 502, "UpstreamFailure");
+}
+
+#[tokio::test]
+async fn resolver_rejection_is_upstream_failure() {
+    let resolver = mock_handle_resolver(
+        400,
+        json!({ "error": "InvalidRequest", "message": "bad handle" }),
+    )
+    .await;
+    let (status, body) = resolve_external(resolver).await;
+    assert_xrpc_error(status, &body, 502, "UpstreamFailure");
+}
+
+#[tokio::test]
+async fn resolver_timeout_is_gateway_timeout() {
+    let app = axum::Router::new().route(
+        "/xrpc/com.atproto.identity.resolveHandle",
+        axum::routing::get(|| async {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            axum::Json(json!({ "did": "did:plc:tooslow" }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let (status, body) = resolve_external(format!("http://{addr}")).await;
+    assert_xrpc_error(status, &body, 504, "UpstreamTimeout");
 }
 
 #[tokio::test]
@@ -55,6 +180,161 @@ async fn update_handle_success() {
     assert_eq!(account.handle.as_deref(), Some("newh.test.pds.local"));
 }
 
+#[tokio::test]
+async fn update_handle_lowercases() {
+    let (router, stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "case.test.pds.local").await;
+
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.identity.updateHandle",
+        Some(&jwt),
+        Some(json!({
+            "handle": "MixedCase.test.pds.local",
+        })),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    use dallaspds_core::AccountStore;
+    let account = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    assert_eq!(account.handle.as_deref(), Some("mixedcase.test.pds.local"));
+}
+
+#[tokio::test]
+async fn update_handle_rejects_invalid_taken_and_reserved() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    config.reserved_handles = vec!["Admin.test.pds.local".to_string()];
+    config.reserved_handle_prefixes = vec!["staff".to_string()];
+    let router = create_test_router_with_config(&stores, config);
+    let (_, jwt, _) = create_account_via_api(&router, "mover.test.pds.local").await;
+    create_account_via_api(&router, "holder.test.pds.local").await;
+
+    let update = |handle: &'static str| {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.identity.updateHandle",
+            Some(&jwt),
+            Some(json!({ "handle": handle })),
+        )
+    };
+
+    for handle in ["mover.example.com", "a.b.test.pds.local", "-x.test.pds.local", ".test.pds.local"] {
+        let (status, body) = update(handle).await;
+        assert_xrpc_error(status, &body, 400, "InvalidHandle");
+    }
+    for handle in ["admin.test.pds.local", "Staff-Bob.test.pds.local"] {
+        let (status, body) = update(handle).await;
+        assert_xrpc_error(status, &body, 400, "HandleNotAvailable");
+    }
+    let (status, body) = update("holder.test.pds.local").await;
+    assert_xrpc_error(status, &body, 409, "HandleNotAvailable");
+
+    // Re-submitting your own handle is fine.
+    let (status, _) = update("mover.test.pds.local").await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn plc_op_log_holds_genesis_operation() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "oplog.test.pds.local").await;
+    let (_, other_jwt, _) = create_account_via_api(&router, "other.test.pds.local").await;
+    let uri = format!("/xrpc/com.dallaspds.identity.getPlcOpLog?did={did}");
+
+    let (status, body) = send_request(&router, "GET", &uri, Some(&jwt), None).await;
+    assert_xrpc_ok(status, &body);
+    let ops = body["operations"].as_array().unwrap();
+    assert_eq!(ops.len(), 1);
+    let genesis = &ops[0]["operation"];
+    assert_eq!(genesis["prev"], serde_json::Value::Null);
+    assert_eq!(genesis["alsoKnownAs"], json!(["at://oplog.test.pds.local"]));
+    assert_eq!(dallaspds_crypto::did_for_genesis_operation(genesis).unwrap(), did);
+    assert_eq!(ops[0]["cid"], dallaspds_crypto::plc_operation_cid(genesis).unwrap());
+
+    // Admins may read any account's log; other accounts may not.
+    let (status, admin_view) = send_request(&router, "GET", &uri, Some(&admin_jwt), None).await;
+    assert_xrpc_ok(status, &admin_view);
+    assert_eq!(admin_view, body);
+    let (status, body) = send_request(&router, "GET", &uri, Some(&other_jwt), None).await;
+    assert_xrpc_error(status, &body, 403, "Forbidden");
+}
+
+#[tokio::test]
+async fn update_plc_submits_and_logs_chained_operation() {
+    let plc = spawn_fake_plc().await;
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = plc.url.clone();
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "rotate.test.pds.local").await;
+    let genesis = plc.ops(&did)[0].clone();
+    let recovery = dallaspds_crypto::SigningKey::generate_k256().unwrap().did_key();
+    let pds_key = genesis["verificationMethods"]["atproto"].clone();
+    let update_plc = |body: serde_json::Value| {
+        send_request(&router, "POST", "/xrpc/com.dallaspds.identity.updatePlc", Some(&jwt), Some(body))
+    };
+
+    let (status, body) = update_plc(json!({ "password": "wrong", "rotationKeys": [recovery] })).await;
+    assert_xrpc_error(status, &body, 401, "InvalidPassword");
+
+    let (status, body) =
+        update_plc(json!({ "password": TEST_PASSWORD, "rotationKeys": [recovery, pds_key] })).await;
+    assert_xrpc_ok(status, &body);
+    let op = &body["operation"];
+    assert_eq!(op["prev"], dallaspds_crypto::plc_operation_cid(&genesis).unwrap());
+    assert_eq!(op["rotationKeys"], json!([recovery, pds_key]));
+    // Fields not given are carried over from the genesis operation.
+    assert_eq!(op["verificationMethods"], genesis["verificationMethods"]);
+    assert_eq!(op["services"], genesis["services"]);
+    assert_eq!(op["alsoKnownAs"], genesis["alsoKnownAs"]);
+    assert_eq!(plc.ops(&did).last(), Some(op));
+
+    let log_uri = format!("/xrpc/com.dallaspds.identity.getPlcOpLog?did={did}");
+    let (_, log) = send_request(&router, "GET", &log_uri, Some(&jwt), None).await;
+    assert_eq!(log["operations"].as_array().unwrap().len(), 2);
+    assert_eq!(log["operations"][1]["cid"], body["cid"]);
+
+    // The next operation builds on whatever the directory has last, even if
+    // it was signed elsewhere.
+    let mut external = op.clone();
+    external["prev"] = body["cid"].clone();
+    external["sig"] = json!("signed-with-the-recovery-key");
+    plc.push(&did, external.clone());
+    let (status, body) =
+        update_plc(json!({ "password": TEST_PASSWORD, "pdsEndpoint": "https://next.example.com" })).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["operation"]["prev"], dallaspds_crypto::plc_operation_cid(&external).unwrap());
+    assert_eq!(body["operation"]["rotationKeys"], json!([recovery, pds_key]));
+
+    // Dropping this PDS's key must be confirmed.
+    let (status, body) = update_plc(json!({ "password": TEST_PASSWORD, "rotationKeys": [recovery] })).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+
+    // A rejected operation is not logged.
+    plc.reject(true);
+    let (status, rejected) =
+        update_plc(json!({ "password": TEST_PASSWORD, "pdsEndpoint": "https://elsewhere.example.com" })).await;
+    assert_xrpc_error(status, &rejected, 502, "PlcOperationFailed");
+    let (_, log) = send_request(&router, "GET", &log_uri, Some(&jwt), None).await;
+    assert_eq!(log["operations"].as_array().unwrap().len(), 3);
+
+    let (status, body) =
+        update_plc(json!({ "password": TEST_PASSWORD, "rotationKeys": ["did:key:zNotAKey"] })).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+}
+
 #[tokio::test]
 async fn well_known_atproto_did() {
     let (router, _stores) = create_test_router_and_stores().await;
@@ -74,3 +354,161 @@ async fn well_known_atproto_did() {
     let text = String::from_utf8(bytes.to_vec()).unwrap();
     assert_eq!(text, did);
 }
+
+#[tokio::test]
+async fn well_known_atproto_did_per_host_in_multi_mode() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    let router = create_test_router_with_config(&stores, config);
+    let (alice, _, _) = create_account_via_api(&router, "alice.test.pds.local").await;
+    let (bob, _, _) = create_account_via_api(&router, "bob.test.pds.local").await;
+
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+    for (host, expected) in [
+        ("alice.test.pds.local", Some(&alice)),
+        ("Bob.test.pds.local:443", Some(&bob)),
+        ("carol.test.pds.local", None),
+    ] {
+        let req = axum::http::Request::builder()
+            .method("GET")
+            .uri("/.well-known/atproto-did")
+            .header("host", host)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = router.clone().oneshot(req).await.unwrap();
+        match expected {
+            Some(did) => {
+                assert_eq!(resp.status(), 200, "{host}");
+                let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+                assert_eq!(String::from_utf8(bytes.to_vec()).unwrap(), *did);
+            }
+            None => assert_eq!(resp.status(), 404, "{host}"),
+        }
+    }
+
+    // A client-supplied X-Forwarded-Host can't claim another account's host.
+    let req = axum::http::Request::builder()
+        .method("GET")
+        .uri("/.well-known/atproto-did")
+        .header("host", "alice.test.pds.local")
+        .header("x-forwarded-host", "bob.test.pds.local")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(String::from_utf8(bytes.to_vec()).unwrap(), alice);
+}
+
+#[tokio::test]
+async fn single_user_did_web_account() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.did_web = true;
+    let router = create_test_router_with_config(&stores, config);
+
+    // Before the account exists there is no document to serve.
+    let (status, _) = send_request(&router, "GET", "/.well-known/did.json", None, None).await;
+    assert_eq!(status, 404);
+
+    let (did, jwt, _) = create_account_via_api(&router, "me.test.pds.local").await;
+    assert_eq!(did, "did:web:test.pds.local");
+
+    let (status, doc) = send_request(&router, "GET", "/.well-known/did.json", None, None).await;
+    assert_xrpc_ok(status, &doc);
+    assert_eq!(doc["id"], did);
+    assert_eq!(doc["alsoKnownAs"], json!(["at://me.test.pds.local"]));
+    assert_eq!(doc["service"][0]["serviceEndpoint"], "https://test.pds.local");
+    let method = &doc["verificationMethod"][0];
+    assert_eq!(method["id"], format!("{did}#atproto"));
+    assert!(method["publicKeyMultibase"].as_str().unwrap().starts_with('z'));
+
+    // The other views of the identity agree.
+    let (status, atproto_did) =
+        send_request(&router, "GET", "/.well-known/atproto-did", None, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(atproto_did, did.as_str());
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.describeRepo?repo={did}"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["didDoc"], doc);
+
+    // The account is fully usable.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "hi", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn did_json_serves_migrated_did_web_accounts() {
+    use tower::ServiceExt;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    let router = create_test_router_with_config(&stores, config);
+
+    // Migrating in needs proof of control over the DID, which these can't
+    // give in a test; add the accounts as a finished migration would leave them.
+    use dallaspds_core::AccountStore;
+    for (did, handle) in [
+        ("did:web:test.pds.local:user:alice", "alice.test.pds.local"),
+        ("did:web:carol.test.pds.local", "carol.test.pds.local"),
+    ] {
+        let signing_key = dallaspds_crypto::SigningKey::generate_p256().unwrap();
+        stores
+            .account_store
+            .create_account(&dallaspds_core::CreateAccountInput {
+                did: did.to_string(),
+                handle: handle.to_string(),
+                email: None,
+                password_hash: "unused".to_string(),
+                signing_key: signing_key.to_bytes(),
+                key_type: signing_key.key_type().to_string(),
+            })
+            .await
+            .unwrap();
+    }
+
+    // Path-based did:web.
+    let (status, doc) = send_request(&router, "GET", "/user/alice/did.json", None, None).await;
+    assert_xrpc_ok(status, &doc);
+    assert_eq!(doc["id"], "did:web:test.pds.local:user:alice");
+    assert_eq!(doc["alsoKnownAs"], json!(["at://alice.test.pds.local"]));
+    assert_eq!(doc["verificationMethod"][0]["type"], "Multikey");
+    assert_eq!(doc["service"][0]["id"], "#atproto_pds");
+    let (status, _) = send_request(&router, "GET", "/user/bob/did.json", None, None).await;
+    assert_eq!(status, 404);
+
+    // Host-based did:web, chosen by the Host header.
+    let get_did_json = |host: &str| {
+        let req = axum::http::Request::builder()
+            .uri("/.well-known/did.json")
+            .header("host", host)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        router.clone().oneshot(req)
+    };
+    let resp = get_did_json("carol.test.pds.local").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = get_did_json("alice.test.pds.local").await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
@@ -0,0 +1,299 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dallaspds_test_utils::*;
+use serde_json::{Value, json};
+
+/// Start a fake AppView that echoes back the authorization header it received.
+async fn mock_appview() -> String {
+    counting_appview().await.0
+}
+
+/// Like [`mock_appview`], but also counts the requests it has served.
+async fn counting_appview() -> (String, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let echo = move |headers: axum::http::HeaderMap| {
+        let counted = counted.clone();
+        async move {
+            counted.fetch_add(1, Ordering::SeqCst);
+            let authorization = headers
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            axum::Json(json!({ "authorization": authorization }))
+        }
+    };
+    let app = axum::Router::new()
+        .route("/xrpc/app.bsky.actor.getProfile", axum::routing::get(echo.clone()))
+        .route("/xrpc/app.bsky.actor.putPreferences", axum::routing::post(echo));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{addr}"), calls)
+}
+
+fn decode_claims(token: &str) -> Value {
+    let header = jsonwebtoken::decode_header(token).unwrap();
+    let mut validation = jsonwebtoken::Validation::new(header.alg);
+    validation.insecure_disable_signature_validation();
+    validation.validate_aud = false;
+    jsonwebtoken::decode::<Value>(token, &jsonwebtoken::DecodingKey::from_secret(&[]), &validation)
+        .unwrap()
+        .claims
+}
+
+#[tokio::test]
+async fn authenticated_requests_carry_a_service_auth_token() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.appview_url = Some(mock_appview().await);
+    config.appview_did = Some("did:web:appview.test".to_string());
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "proxy.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/app.bsky.actor.getProfile?actor=proxy.test.pds.local",
+        Some(&jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    let token = body["authorization"]
+        .as_str()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .expect("AppView should receive a bearer token");
+    assert_ne!(token, jwt, "the user's PDS token must not be forwarded");
+    let claims = decode_claims(token);
+    assert_eq!(claims["iss"], did);
+    assert_eq!(claims["aud"], "did:web:appview.test");
+    assert_eq!(claims["lxm"], "app.bsky.actor.getProfile");
+    let lifetime = claims["exp"].as_i64().unwrap() - claims["iat"].as_i64().unwrap();
+    assert!(lifetime <= 60);
+}
+
+#[tokio::test]
+async fn audience_defaults_to_the_appview_host() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.appview_url = Some(mock_appview().await);
+    let router = create_test_router_with_config(&stores, config);
+    let (_, jwt, _) = create_account_via_api(&router, "proxy.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/app.bsky.actor.getProfile?actor=proxy.test.pds.local",
+        Some(&jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let token = body["authorization"].as_str().unwrap().strip_prefix("Bearer ").unwrap();
+    assert_eq!(decode_claims(token)["aud"], "did:web:127.0.0.1");
+}
+
+#[tokio::test]
+async fn unauthenticated_requests_are_proxied_without_authorization() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.appview_url = Some(mock_appview().await);
+    config.appview_did = Some("did:web:appview.test".to_string());
+    let router = create_test_router_with_config(&stores, config);
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/app.bsky.actor.getProfile?actor=someone.test",
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["authorization"], Value::Null);
+}
+
+#[tokio::test]
+async fn repeated_gets_are_served_from_the_cache() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    let (appview_url, calls) = counting_appview().await;
+    config.appview_url = Some(appview_url);
+    config.appview_cache.method_ttl_secs =
+        [("app.bsky.actor.getProfile".to_string(), 60)].into_iter().collect();
+    let router = create_test_router_with_config(&stores, config);
+    let (_, jwt, _) = create_account_via_api(&router, "cache.test.pds.local").await;
+    let uri = "/xrpc/app.bsky.actor.getProfile?actor=cache.test.pds.local";
+
+    let (status, first) = send_request(&router, "GET", uri, Some(&jwt), None).await;
+    assert_xrpc_ok(status, &first);
+
+    let req = axum::http::Request::builder()
+        .method("GET")
+        .uri(uri)
+        .header("authorization", format!("Bearer {jwt}"))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().contains_key("age"));
+    assert_eq!(resp.headers()["cache-control"], "private, max-age=60");
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let second: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(second, first);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Another caller doesn't share the authenticated response.
+    let (status, body) = send_request(&router, "GET", uri, None, None).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["authorization"], Value::Null);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    // Nor do requests asking for other labelers or languages.
+    for (name, value) in [("atproto-accept-labelers", "did:plc:labeler"), ("accept-language", "de")] {
+        let req = axum::http::Request::builder()
+            .method("GET")
+            .uri(uri)
+            .header("authorization", format!("Bearer {jwt}"))
+            .header(name, value)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert!(!resp.headers().contains_key("age"), "{name}");
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn posts_are_never_cached() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    let (appview_url, calls) = counting_appview().await;
+    config.appview_url = Some(appview_url);
+    let router = create_test_router_with_config(&stores, config);
+    let (_, jwt, _) = create_account_via_api(&router, "cache.test.pds.local").await;
+
+    for _ in 0..2 {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/app.bsky.actor.putPreferences",
+            Some(&jwt),
+            Some(json!({ "preferences": [] })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+/// A fake AppView that answers its first `failures` requests with `status`
+/// and, if given, a `Retry-After` header, counting every request it receives.
+async fn flaky_appview(
+    failures: usize,
+    status: u16,
+    retry_after: Option<&'static str>,
+) -> (String, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let handler = move || {
+        let counted = counted.clone();
+        async move {
+            let mut headers = axum::http::HeaderMap::new();
+            let status = if counted.fetch_add(1, Ordering::SeqCst) < failures {
+                if let Some(retry_after) = retry_after {
+                    headers.insert(axum::http::header::RETRY_AFTER, retry_after.parse().unwrap());
+                }
+                axum::http::StatusCode::from_u16(status).unwrap()
+            } else {
+                axum::http::StatusCode::OK
+            };
+            (status, headers, axum::Json(json!({})))
+        }
+    };
+    let app = axum::Router::new()
+        .route("/xrpc/app.bsky.actor.getProfile", axum::routing::get(handler.clone()))
+        .route("/xrpc/app.bsky.actor.putPreferences", axum::routing::post(handler));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{addr}"), calls)
+}
+
+fn retrying_router(stores: &TestStores, appview_url: String) -> axum::Router {
+    let mut config = create_test_config();
+    config.appview_url = Some(appview_url);
+    config.http_retry.max_attempts = 3;
+    config.http_retry.initial_backoff_ms = 1;
+    create_test_router_with_config(stores, config)
+}
+
+#[tokio::test]
+async fn gets_are_retried_after_server_errors() {
+    let stores = create_test_stores().await;
+    let (appview_url, calls) = flaky_appview(2, 503, None).await;
+    let router = retrying_router(&stores, appview_url);
+
+    let (status, body) =
+        send_request(&router, "GET", "/xrpc/app.bsky.actor.getProfile?actor=a.test", None, None)
+            .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn rate_limited_gets_wait_for_retry_after() {
+    let stores = create_test_stores().await;
+    let (appview_url, calls) = flaky_appview(1, 429, Some("1")).await;
+    let router = retrying_router(&stores, appview_url);
+    let started = std::time::Instant::now();
+    let (status, body) =
+        send_request(&router, "GET", "/xrpc/app.bsky.actor.getProfile?actor=a.test", None, None)
+            .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+
+    // A wait beyond the longest backoff is left to the client.
+    let stores = create_test_stores().await;
+    let (appview_url, calls) = flaky_appview(1, 429, Some("3600")).await;
+    let router = retrying_router(&stores, appview_url);
+    let (status, _) =
+        send_request(&router, "GET", "/xrpc/app.bsky.actor.getProfile?actor=a.test", None, None)
+            .await;
+    assert_eq!(status, 429);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn client_errors_and_posts_are_not_retried() {
+    let stores = create_test_stores().await;
+    let (appview_url, calls) = flaky_appview(1, 400, None).await;
+    let router = retrying_router(&stores, appview_url);
+    let (status, _) =
+        send_request(&router, "GET", "/xrpc/app.bsky.actor.getProfile?actor=a.test", None, None)
+            .await;
+    assert_eq!(status, 400);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let stores = create_test_stores().await;
+    let (appview_url, calls) = flaky_appview(1, 503, None).await;
+    let router = retrying_router(&stores, appview_url);
+    let (_, jwt, _) = create_account_via_api(&router, "noretry.test.pds.local").await;
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/app.bsky.actor.putPreferences",
+        Some(&jwt),
+        Some(json!({ "preferences": [] })),
+    )
+    .await;
+    assert_eq!(status, 503);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,140 @@
+use std::net::SocketAddr;
+
+use axum::extract::ConnectInfo;
+use dallaspds_test_utils::*;
+use http_body_util::BodyExt;
+use serde_json::json;
+use tower::ServiceExt;
+
+/// Send a request as if it arrived from `peer`.
+async fn request_from(
+    router: &axum::Router,
+    peer: &str,
+    uri: &str,
+    auth: Option<&str>,
+    headers: &[(&str, &str)],
+) -> axum::response::Response {
+    let mut builder = axum::http::Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(token) = auth {
+        builder = builder.header("authorization", format!("Bearer {token}"));
+    }
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    let mut req = builder
+        .body(axum::body::Body::from(
+            json!({ "identifier": "nobody.test", "password": "wrong" }).to_string(),
+        ))
+        .unwrap();
+    let peer: SocketAddr = format!("{peer}:4000").parse().unwrap();
+    req.extensions_mut().insert(ConnectInfo(peer));
+    router.clone().oneshot(req).await.unwrap()
+}
+
+const CREATE_SESSION: &str = "/xrpc/com.atproto.server.createSession";
+
+#[tokio::test]
+async fn login_attempts_are_limited_per_ip() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.rate_limit.auth_per_minute = 2;
+    let router = create_test_router_with_config(&stores, config);
+
+    for _ in 0..2 {
+        let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &[]).await;
+        assert_ne!(resp.status(), 429);
+    }
+    let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &[]).await;
+    assert_eq!(resp.status(), 429);
+    let retry_after: u64 = resp.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=30).contains(&retry_after), "Retry-After {retry_after}");
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "RateLimitExceeded");
+
+    // Other clients have their own bucket.
+    let resp = request_from(&router, "10.0.0.2", CREATE_SESSION, None, &[]).await;
+    assert_ne!(resp.status(), 429);
+}
+
+#[tokio::test]
+async fn authenticated_requests_are_limited_per_account() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    config.rate_limit.xrpc_per_minute = 3;
+    let router = create_test_router_with_config(&stores, config);
+    let (_, alice, _) = create_account_via_api(&router, "alice.test.pds.local").await;
+    let (_, bob, _) = create_account_via_api(&router, "bob.test.pds.local").await;
+
+    let get_session = "/xrpc/com.atproto.server.getSession";
+    let statuses = async |token: &str, peer: &str| {
+        let resp = request_from(&router, peer, get_session, Some(token), &[]).await;
+        resp.status().as_u16()
+    };
+
+    // Alice is limited wherever she connects from...
+    for peer in ["10.0.0.1", "10.0.0.2", "10.0.0.3"] {
+        assert_ne!(statuses(&alice, peer).await, 429);
+    }
+    assert_eq!(statuses(&alice, "10.0.0.4").await, 429);
+    // ...while Bob, from the same address, is not.
+    assert_ne!(statuses(&bob, "10.0.0.1").await, 429);
+}
+
+#[tokio::test]
+async fn forwarded_for_is_only_used_when_trusted() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.rate_limit.auth_per_minute = 1;
+    let router = create_test_router_with_config(&stores, config.clone());
+
+    // Untrusted: the spoofable header is ignored, so the proxy's own address
+    // is limited.
+    let first = [("x-forwarded-for", "203.0.113.1")];
+    let second = [("x-forwarded-for", "203.0.113.2")];
+    let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &first).await;
+    assert_ne!(resp.status(), 429);
+    let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &second).await;
+    assert_eq!(resp.status(), 429);
+
+    config.rate_limit.trust_forwarded_for = true;
+    let router = create_test_router_with_config(&stores, config);
+    let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &first).await;
+    assert_ne!(resp.status(), 429);
+    let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &second).await;
+    assert_ne!(resp.status(), 429);
+    let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &first).await;
+    assert_eq!(resp.status(), 429);
+
+    // Only the hop the proxy appended counts; a client can't dodge the limit
+    // by prepending addresses of its own.
+    let spoofed = [("x-forwarded-for", "198.51.100.7, 203.0.113.2")];
+    let resp = request_from(&router, "10.0.0.1", CREATE_SESSION, None, &spoofed).await;
+    assert_eq!(resp.status(), 429);
+}
+
+#[tokio::test]
+async fn only_the_liveness_probe_is_exempt() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.rate_limit.xrpc_per_minute = 1;
+    let router = create_test_router_with_config(&stores, config);
+    let get = |uri: &'static str| {
+        let mut req = axum::http::Request::builder()
+            .uri(uri)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        router.clone().oneshot(req)
+    };
+
+    for _ in 0..3 {
+        assert_eq!(get("/xrpc/_health/live").await.unwrap().status(), 200);
+    }
+    assert_ne!(get("/xrpc/_health").await.unwrap().status(), 429);
+    assert_eq!(get("/xrpc/_health").await.unwrap().status(), 429);
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use dallaspds_server::RelayNotifier;
+use dallaspds_test_utils::{RecordedCalls, spawn_recording_server, wait_for_calls};
+
+/// Start a fake relay that accepts every requestCrawl.
+async fn mock_relay() -> (String, RecordedCalls) {
+    flaky_relay(0).await
+}
+
+/// Like [`mock_relay`], but answers the first `failures` requests with a 500.
+async fn flaky_relay(failures: usize) -> (String, RecordedCalls) {
+    let seen = Arc::new(AtomicUsize::new(0));
+    let app = axum::Router::new().route(
+        "/xrpc/com.atproto.sync.requestCrawl",
+        axum::routing::post(move || {
+            let seen = seen.clone();
+            async move {
+                if seen.fetch_add(1, Ordering::SeqCst) < failures {
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR
+                } else {
+                    axum::http::StatusCode::OK
+                }
+            }
+        }),
+    );
+    spawn_recording_server(app).await
+}
+
+#[tokio::test]
+async fn requests_crawl_once_at_startup() {
+    let (relay_url, calls) = mock_relay().await;
+    let (_notifier, worker) = RelayNotifier::new(
+        vec![relay_url],
+        "test.pds.local".to_string(),
+        Duration::from_secs(60),
+    );
+    tokio::spawn(worker.run());
+
+    wait_for_calls(&calls, 1).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].json()["hostname"], "test.pds.local");
+}
+
+#[tokio::test]
+async fn debounces_repeated_writes_per_did() {
+    let (relay_url, calls) = mock_relay().await;
+    let (notifier, worker) = RelayNotifier::new(
+        vec![relay_url],
+        "test.pds.local".to_string(),
+        Duration::from_secs(60),
+    );
+    tokio::spawn(worker.run());
+    wait_for_calls(&calls, 1).await;
+
+    for _ in 0..5 {
+        notifier.notify("did:plc:alice");
+    }
+    notifier.notify("did:plc:bob");
+    wait_for_calls(&calls, 3).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Startup, then one each for alice and bob.
+    assert_eq!(calls.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn fans_out_to_every_relay_and_retries_failures() {
+    let (healthy_url, healthy) = mock_relay().await;
+    let (flaky_url, flaky) = flaky_relay(1).await;
+    let (notifier, worker) = RelayNotifier::new(
+        vec![flaky_url, "http://127.0.0.1:1".to_string(), healthy_url],
+        "test.pds.local".to_string(),
+        Duration::from_secs(60),
+    );
+    tokio::spawn(worker.run());
+
+    // The flaky relay's failed startup announcement is retried, and the
+    // unreachable relay does not hold up the others.
+    wait_for_calls(&healthy, 1).await;
+    wait_for_calls(&flaky, 2).await;
+
+    notifier.notify("did:plc:alice");
+    wait_for_calls(&healthy, 2).await;
+    wait_for_calls(&flaky, 3).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(healthy.lock().unwrap().len(), 2);
+    assert_eq!(flaky.lock().unwrap().len(), 3);
+}
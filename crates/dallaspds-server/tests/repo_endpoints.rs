@@ -1,3 +1,6 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use dallaspds_test_utils::*;
 use serde_json::json;
 
@@ -67,6 +70,128 @@ async fn create_record_wrong_did_forbidden() {
     assert_xrpc_error(status, &body, 403, "AuthorizationError");
 }
 
+#[tokio::test]
+async fn create_record_validates_against_lexicons() {
+    let lexicon_dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        lexicon_dir.path().join("post.json"),
+        json!({
+            "lexicon": 1,
+            "id": "app.bsky.feed.post",
+            "defs": { "main": { "type": "record", "record": {
+                "type": "object",
+                "required": ["text", "createdAt"],
+                "properties": {}
+            } } }
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.validate_records = true;
+    config.lexicon_dir = Some(lexicon_dir.path().to_string_lossy().into_owned());
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "lex.test.pds.local").await;
+
+    let create = |record: serde_json::Value| {
+        let router = router.clone();
+        let (did, jwt) = (did.clone(), jwt.clone());
+        async move {
+            send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.repo.createRecord",
+                Some(&jwt),
+                Some(json!({ "repo": did, "collection": "app.bsky.feed.post", "record": record })),
+            )
+            .await
+        }
+    };
+
+    // $type does not match the collection.
+    let (status, body) = create(json!({ "$type": "app.bsky.feed.like", "text": "x", "createdAt": "2025-01-01T00:00:00Z" })).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRecord");
+
+    // Missing a required field.
+    let (status, body) = create(json!({ "$type": "app.bsky.feed.post", "text": "x" })).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRecord");
+
+    let (status, body) = create(json!({ "$type": "app.bsky.feed.post", "text": "x", "createdAt": "2025-01-01T00:00:00Z" })).await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn create_record_validate_flag() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "createval.test.pds.local").await;
+
+    let create = |validate: bool| {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": { "$type": "app.bsky.feed.like" },
+                "validate": validate,
+            })),
+        )
+    };
+
+    let (status, body) = create(true).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRecord");
+
+    let (status, body) = create(false).await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn create_record_swap_commit() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "swapc.test.pds.local").await;
+
+    let (_, latest) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+        None,
+        None,
+    )
+    .await;
+    let head = latest["cid"].as_str().unwrap().to_string();
+
+    let create = |swap_commit: String| {
+        let router = router.clone();
+        let (did, jwt) = (did.clone(), jwt.clone());
+        async move {
+            send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.repo.createRecord",
+                Some(&jwt),
+                Some(json!({
+                    "repo": did,
+                    "collection": "app.bsky.feed.post",
+                    "record": { "$type": "app.bsky.feed.post", "text": "swap", "createdAt": "2025-01-01T00:00:00Z" },
+                    "swapCommit": swap_commit,
+                })),
+            )
+            .await
+        }
+    };
+
+    let (status, body) = create(head.clone()).await;
+    assert_xrpc_ok(status, &body);
+
+    // The head moved, so the old commit no longer matches.
+    let (status, body) = create(head).await;
+    assert_xrpc_error(status, &body, 400, "InvalidSwap");
+}
+
 // ── getRecord ───────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -159,6 +284,210 @@ async fn get_record_no_auth_required() {
     assert_eq!(status, 200);
 }
 
+/// Spawn a mock server acting as both a PLC directory and a remote PDS.
+/// Returns its base URL and a counter of DID document fetches.
+async fn spawn_mock_remote_pds(did: &'static str) -> (String, Arc<AtomicUsize>) {
+    use axum::extract::{Path, Query};
+    use axum::routing::get;
+    use std::collections::HashMap;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let did_fetches = Arc::new(AtomicUsize::new(0));
+
+    let endpoint = base_url.clone();
+    let fetches = did_fetches.clone();
+    let app = axum::Router::new()
+        .route(
+            "/xrpc/com.atproto.repo.getRecord",
+            get(|Query(q): Query<HashMap<String, String>>| async move {
+                axum::Json(json!({
+                    "uri": format!("at://{}/{}/{}", q["repo"], q["collection"], q["rkey"]),
+                    "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                    "value": { "$type": "app.bsky.feed.post", "text": "from afar" },
+                }))
+            }),
+        )
+        .route(
+            "/{did}",
+            get(move |Path(requested): Path<String>| async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(requested, did);
+                axum::Json(json!({
+                    "id": did,
+                    "service": [{
+                        "id": "#atproto_pds",
+                        "type": "AtprotoPersonalDataServer",
+                        "serviceEndpoint": endpoint,
+                    }],
+                }))
+            }),
+        );
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (base_url, did_fetches)
+}
+
+#[tokio::test]
+async fn get_record_proxies_foreign_repo() {
+    let foreign_did = "did:plc:remoteuser00000000000000";
+    let (mock_url, did_fetches) = spawn_mock_remote_pds(foreign_did).await;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = mock_url;
+    let router = create_test_router_with_config(&stores, config);
+
+    for _ in 0..2 {
+        let (status, body) = send_request(
+            &router,
+            "GET",
+            &format!("/xrpc/com.atproto.repo.getRecord?repo={foreign_did}&collection=app.bsky.feed.post&rkey=3abc"),
+            None,
+            None,
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+        assert_eq!(body["uri"], format!("at://{foreign_did}/app.bsky.feed.post/3abc"));
+        assert_eq!(body["value"]["text"], "from afar");
+    }
+
+    // The DID's PDS endpoint is resolved once and then served from cache.
+    assert_eq!(did_fetches.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn get_record_refuses_foreign_repo_on_a_private_address() {
+    let foreign_did = "did:plc:remoteuser00000000000000";
+    let (mock_url, _) = spawn_mock_remote_pds(foreign_did).await;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = mock_url;
+    config.allow_private_endpoints = false;
+    let router = create_test_router_with_config(&stores, config);
+
+    // The DID document points at 127.0.0.1, which is never fetched.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.getRecord?repo={foreign_did}&collection=app.bsky.feed.post&rkey=3abc"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 502, "UpstreamFailure");
+}
+
+#[tokio::test]
+async fn get_record_by_cid_returns_historical_version() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "hist.test.pds.local").await;
+
+    let mut cids = Vec::new();
+    for name in ["old name", "new name"] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.putRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.actor.profile",
+                "rkey": "self",
+                "record": { "$type": "app.bsky.actor.profile", "displayName": name },
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+        cids.push(body["cid"].as_str().unwrap().to_string());
+    }
+
+    let get_at = |rkey: &str, cid: &str| {
+        let router = router.clone();
+        let uri = format!(
+            "/xrpc/com.atproto.repo.getRecord?repo={did}&collection=app.bsky.actor.profile&rkey={rkey}&cid={cid}"
+        );
+        async move { send_request(&router, "GET", &uri, None, None).await }
+    };
+    let get = |cid: &str| get_at("self", cid);
+
+    let (status, body) = get(&cids[0]).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["cid"], cids[0]);
+    assert_eq!(body["value"]["displayName"], "old name");
+
+    let (status, body) = get(&cids[1]).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["value"]["displayName"], "new name");
+
+    // A stored version is only served at the path it was written to.
+    let (status, body) = get_at("other", &cids[0]).await;
+    assert_xrpc_error(status, &body, 400, "RecordNotFound");
+
+    // A well-formed CID that was never stored.
+    let (status, body) = get("bafyreifwqvs46vuze47wuimepm76irzgg5gl23b37xecsut7dwzkaucdie").await;
+    assert_xrpc_error(status, &body, 400, "RecordNotFound");
+
+    // A stored block that is not a record (the empty MST node).
+    let (status, body) = get("bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm").await;
+    assert_xrpc_error(status, &body, 400, "RecordNotFound");
+
+    let (status, body) = get("not-a-cid").await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+}
+
+#[tokio::test]
+async fn get_record_conditional_get() {
+    use tower::ServiceExt;
+
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "etagrecord.test.pds.local").await;
+
+    let put = |text: &'static str| {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.putRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.actor.profile",
+                "rkey": "self",
+                "record": { "$type": "app.bsky.actor.profile", "displayName": text }
+            })),
+        )
+    };
+    let (status, body) = put("first").await;
+    assert_xrpc_ok(status, &body);
+    let first_cid = body["cid"].as_str().unwrap().to_string();
+
+    let get_record = |if_none_match: Option<String>| {
+        let mut req = axum::http::Request::builder().method("GET").uri(format!(
+            "/xrpc/com.atproto.repo.getRecord?repo={did}&collection=app.bsky.actor.profile&rkey=self"
+        ));
+        if let Some(tag) = if_none_match {
+            req = req.header("if-none-match", tag);
+        }
+        router.clone().oneshot(req.body(axum::body::Body::empty()).unwrap())
+    };
+
+    let resp = get_record(None).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["etag"], format!("\"{first_cid}\"").as_str());
+
+    let resp = get_record(Some(format!("\"{first_cid}\""))).await.unwrap();
+    assert_eq!(resp.status(), 304);
+
+    // Once the record changes, the old tag no longer matches.
+    let (status, body) = put("second").await;
+    assert_xrpc_ok(status, &body);
+    let second_cid = body["cid"].as_str().unwrap().to_string();
+    let resp = get_record(Some(format!("\"{first_cid}\""))).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["etag"], format!("\"{second_cid}\"").as_str());
+}
+
 // ── listRecords ─────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -243,6 +572,342 @@ async fn list_records_limit() {
     assert_eq!(body["records"].as_array().unwrap().len(), 2);
 }
 
+#[tokio::test]
+async fn list_records_paginates_in_both_directions() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "paging.test.pds.local").await;
+
+    for rkey in ["a1", "a2", "a3", "a4", "a5"] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "rkey": rkey,
+                "record": { "$type": "app.bsky.feed.post", "text": rkey, "createdAt": "2025-01-01T00:00:00Z" }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+
+    for (reverse, want) in [
+        (false, ["a1", "a2", "a3", "a4", "a5"]),
+        (true, ["a5", "a4", "a3", "a2", "a1"]),
+    ] {
+        let mut listed = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut uri = format!(
+                "/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post&limit=2&reverse={reverse}"
+            );
+            if let Some(c) = &cursor {
+                uri.push_str(&format!("&cursor={c}"));
+            }
+            let (status, body) = send_request(&router, "GET", &uri, None, None).await;
+            assert_xrpc_ok(status, &body);
+            for record in body["records"].as_array().unwrap() {
+                let uri = record["uri"].as_str().unwrap();
+                listed.push(uri.rsplit('/').next().unwrap().to_string());
+            }
+            match body["cursor"].as_str() {
+                Some(c) => cursor = Some(c.to_string()),
+                None => break,
+            }
+        }
+        assert_eq!(listed, want, "reverse={reverse}");
+    }
+
+    // A cursor between existing keys is still a valid position.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post&cursor=a3x"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["records"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn list_records_filters_by_rkey_range() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "range.test.pds.local").await;
+
+    for rkey in ["a1", "a2", "a3", "a4", "a5", "a6"] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "rkey": rkey,
+                "record": { "$type": "app.bsky.feed.post", "text": rkey, "createdAt": "2025-01-01T00:00:00Z" }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+
+    let list = |query: String| {
+        let router = router.clone();
+        let did = did.clone();
+        async move {
+            let uri = format!(
+                "/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post&{query}"
+            );
+            let (status, body) = send_request(&router, "GET", &uri, None, None).await;
+            assert_xrpc_ok(status, &body);
+            body["records"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["uri"].as_str().unwrap().rsplit('/').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // Both bounds are inclusive, in either direction.
+    assert_eq!(list("rkeyStart=a2&rkeyEnd=a4".into()).await, ["a2", "a3", "a4"]);
+    assert_eq!(list("rkeyStart=a2&rkeyEnd=a4&reverse=true".into()).await, ["a4", "a3", "a2"]);
+    assert_eq!(list("rkeyStart=a5".into()).await, ["a5", "a6"]);
+    assert_eq!(list("rkeyEnd=a1x".into()).await, ["a1"]);
+
+    // A cursor narrows the bound the listing starts from, and a cursor
+    // outside the range leaves the range bound in place.
+    assert_eq!(list("rkeyStart=a2&rkeyEnd=a5&cursor=a3".into()).await, ["a4", "a5"]);
+    assert_eq!(list("rkeyStart=a2&rkeyEnd=a5&cursor=a1".into()).await, ["a2", "a3", "a4", "a5"]);
+    assert_eq!(
+        list("rkeyStart=a2&rkeyEnd=a5&cursor=a4&reverse=true".into()).await,
+        ["a3", "a2"]
+    );
+    assert_eq!(
+        list("rkeyStart=a2&rkeyEnd=a5&cursor=a6&reverse=true".into()).await,
+        ["a5", "a4", "a3", "a2"]
+    );
+}
+
+#[tokio::test]
+async fn list_records_rejects_malformed_cursor() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, _jwt, _) = create_account_via_api(&router, "badcursor.test.pds.local").await;
+
+    for cursor in ["..", "a%2Fb", "has%20space"] {
+        let (status, body) = send_request(
+            &router,
+            "GET",
+            &format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post&cursor={cursor}"),
+            None,
+            None,
+        )
+        .await;
+        assert_xrpc_error(status, &body, 400, "InvalidCursor");
+    }
+}
+
+#[tokio::test]
+async fn list_records_isolates_shared_prefix_collections() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "prefix.test.pds.local").await;
+
+    // `app.bsky.feed` is a string prefix of both other collections.
+    let collections = [
+        ("app.bsky.feed", 2),
+        ("app.bsky.feedgen", 3),
+        ("app.bsky.feed.post", 1),
+    ];
+    for (collection, count) in collections {
+        for i in 0..count {
+            let (status, body) = send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.repo.createRecord",
+                Some(&jwt),
+                Some(json!({
+                    "repo": did,
+                    "collection": collection,
+                    "record": { "$type": collection, "text": format!("{collection} {i}") }
+                })),
+            )
+            .await;
+            assert_xrpc_ok(status, &body);
+        }
+    }
+
+    for (collection, count) in collections {
+        let (status, body) = send_request(
+            &router,
+            "GET",
+            &format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection={collection}"),
+            None,
+            None,
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+        let records = body["records"].as_array().unwrap();
+        assert_eq!(records.len(), count, "wrong count for {collection}");
+        let prefix = format!("at://{did}/{collection}/");
+        for record in records {
+            let uri = record["uri"].as_str().unwrap();
+            let rkey = uri.strip_prefix(&prefix).unwrap_or_else(|| {
+                panic!("{uri} leaked into {collection} listing")
+            });
+            assert!(!rkey.contains('/'), "{uri} leaked into {collection} listing");
+        }
+    }
+}
+
+#[tokio::test]
+async fn normalize_on_read_repairs_legacy_records() {
+    let stores = create_test_stores().await;
+    let (did, jwt, _) =
+        create_account_via_api(&create_test_router(&stores), "legacy.test.pds.local").await;
+
+    // A record from before validation: no $type, and whole numbers stored
+    // as floats, in an integer field and in a number field.
+    let (status, body) = send_request(
+        &create_test_router(&stores),
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "validate": false,
+            "record": { "text": "legacy", "langs": ["en"], "likes": 3.0, "score": 2.0 }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let rkey = body["uri"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let get_uri = format!(
+        "/xrpc/com.atproto.repo.getRecord?repo={did}&collection=app.bsky.feed.post&rkey={rkey}"
+    );
+    let list_uri =
+        format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post");
+
+    // Off by default: the stored value is returned as-is.
+    let raw = create_test_router(&stores);
+    let (status, body) = send_request(&raw, "GET", &get_uri, None, None).await;
+    assert_xrpc_ok(status, &body);
+    assert!(body["value"].get("$type").is_none());
+
+    let lexicon_dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        lexicon_dir.path().join("post.json"),
+        json!({
+            "lexicon": 1,
+            "id": "app.bsky.feed.post",
+            "defs": { "main": { "type": "record", "record": {
+                "type": "object",
+                "properties": {
+                    "likes": { "type": "integer" },
+                    "score": { "type": "number" }
+                }
+            } } }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let mut config = create_test_config();
+    config.normalize_on_read = true;
+    config.lexicon_dir = Some(lexicon_dir.path().to_string_lossy().into_owned());
+    let router = create_test_router_with_config(&stores, config);
+    let (status, got) = send_request(&router, "GET", &get_uri, None, None).await;
+    assert_xrpc_ok(status, &got);
+    let (status, listed) = send_request(&router, "GET", &list_uri, None, None).await;
+    assert_xrpc_ok(status, &listed);
+    for (value, cid) in [
+        (&got["value"], &got["cid"]),
+        (&listed["records"][0]["value"], &listed["records"][0]["cid"]),
+    ] {
+        assert_eq!(value["$type"], "app.bsky.feed.post");
+        assert_eq!(value["text"], "legacy");
+        assert!(value["likes"].is_i64(), "{value}");
+        assert!(value["score"].is_f64(), "{value}");
+        // The stored block, and so the CID, is untouched.
+        assert_eq!(cid, &body["cid"]);
+    }
+}
+
+// ── listAllRecords ──────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn list_all_records_paginates_across_collections() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "listall.test.pds.local").await;
+
+    let writes = [
+        ("app.bsky.feed.post", "p2"),
+        ("app.bsky.actor.profile", "self"),
+        ("app.bsky.feed.like", "l1"),
+        ("app.bsky.feed.post", "p1"),
+        ("app.bsky.graph.follow", "f1"),
+    ];
+    for (collection, rkey) in writes {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": collection,
+                "rkey": rkey,
+                "record": { "$type": collection, "text": rkey }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+
+    let mut listed = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut uri = format!("/xrpc/com.dallaspds.repo.listAllRecords?repo={did}&limit=2");
+        if let Some(c) = &cursor {
+            uri.push_str(&format!("&cursor={c}"));
+        }
+        let (status, body) = send_request(&router, "GET", &uri, None, None).await;
+        assert_xrpc_ok(status, &body);
+        for record in body["records"].as_array().unwrap() {
+            let uri = record["uri"].as_str().unwrap();
+            listed.push(uri.strip_prefix(&format!("at://{did}/")).unwrap().to_string());
+        }
+        match body["cursor"].as_str() {
+            Some(c) => cursor = Some(c.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(
+        listed,
+        [
+            "app.bsky.actor.profile/self",
+            "app.bsky.feed.like/l1",
+            "app.bsky.feed.post/p1",
+            "app.bsky.feed.post/p2",
+            "app.bsky.graph.follow/f1",
+        ]
+    );
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.dallaspds.repo.listAllRecords?repo={did}&cursor=p1"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidCursor");
+}
+
 // ── putRecord ───────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -336,31 +1001,171 @@ async fn put_record_wrong_did_forbidden() {
     assert_xrpc_error(status, &body, 403, "AuthorizationError");
 }
 
-// ── deleteRecord ────────────────────────────────────────────────────────
-
 #[tokio::test]
-async fn delete_record_success() {
+async fn put_record_swap_record() {
     let (router, _stores) = create_test_router_and_stores().await;
-    let (did, jwt, _) = create_account_via_api(&router, "del.test.pds.local").await;
+    let (did, jwt, _) = create_account_via_api(&router, "swapp.test.pds.local").await;
 
-    // Create a record
-    let (_, create_body) = send_request(
-        &router,
-        "POST",
-        "/xrpc/com.atproto.repo.createRecord",
-        Some(&jwt),
-        Some(json!({
-            "repo": did,
-            "collection": "app.bsky.feed.post",
-            "record": { "$type": "app.bsky.feed.post", "text": "bye", "createdAt": "2025-01-01T00:00:00Z" }
-        })),
-    )
-    .await;
+    let put = |text: &'static str, swap_record: serde_json::Value| {
+        let router = router.clone();
+        let (did, jwt) = (did.clone(), jwt.clone());
+        async move {
+            send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.repo.putRecord",
+                Some(&jwt),
+                Some(json!({
+                    "repo": did,
+                    "collection": "app.bsky.actor.profile",
+                    "rkey": "self",
+                    "record": { "$type": "app.bsky.actor.profile", "displayName": text },
+                    "swapRecord": swap_record,
+                })),
+            )
+            .await
+        }
+    };
 
-    let uri = create_body["uri"].as_str().unwrap();
-    let rkey = uri.rsplit('/').next().unwrap();
+    // `null` requires that the record does not exist yet.
+    let (status, body) = put("first", serde_json::Value::Null).await;
+    assert_xrpc_ok(status, &body);
+    let first_cid = body["cid"].clone();
 
-    // Delete it
+    let (status, body) = put("again", serde_json::Value::Null).await;
+    assert_xrpc_error(status, &body, 400, "InvalidSwap");
+
+    let (status, body) = put("second", first_cid.clone()).await;
+    assert_xrpc_ok(status, &body);
+
+    // The record changed, so the first CID is stale.
+    let (status, body) = put("third", first_cid).await;
+    assert_xrpc_error(status, &body, 400, "InvalidSwap");
+}
+
+#[tokio::test]
+async fn put_record_create_vs_update_semantics() {
+    let (router, stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "putsem.test.pds.local").await;
+
+    let put = |body: serde_json::Value| {
+        let router = router.clone();
+        let jwt = jwt.clone();
+        async move {
+            send_request(&router, "POST", "/xrpc/com.atproto.repo.putRecord", Some(&jwt), Some(body)).await
+        }
+    };
+    let profile = |name: &str| json!({ "$type": "app.bsky.actor.profile", "displayName": name });
+
+    let (status, body) = put(json!({
+        "repo": did,
+        "collection": "app.bsky.actor.profile",
+        "rkey": "self",
+        "record": profile("v1"),
+    }))
+    .await;
+    assert_xrpc_ok(status, &body);
+    let v1_cid = body["cid"].as_str().unwrap().to_string();
+
+    // swapRecord = null means "must not exist", so this is rejected.
+    let (status, body) = put(json!({
+        "repo": did,
+        "collection": "app.bsky.actor.profile",
+        "rkey": "self",
+        "record": profile("clobber"),
+        "swapRecord": null,
+    }))
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidSwap");
+
+    // With the current CID the update goes through.
+    let (status, body) = put(json!({
+        "repo": did,
+        "collection": "app.bsky.actor.profile",
+        "rkey": "self",
+        "record": profile("v2"),
+        "swapRecord": v1_cid,
+    }))
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_ne!(body["cid"], v1_cid);
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.getRecord?repo={did}&collection=app.bsky.actor.profile&rkey=self"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["value"]["displayName"], "v2");
+
+    // The first put is reported to the firehose as a create, the second as an update.
+    use dallaspds_core::EventStore;
+    let events = stores.event_store.get_events_after(0, 100).await.unwrap();
+    let commits: Vec<_> = events.iter().filter(|e| e.event_type == "commit").collect();
+    let contains = |payload: &[u8], needle: &str| {
+        payload.windows(needle.len()).any(|w| w == needle.as_bytes())
+    };
+    let last = commits.len() - 1;
+    assert!(contains(&commits[last - 1].payload, "create"));
+    assert!(contains(&commits[last].payload, "update"));
+}
+
+#[tokio::test]
+async fn put_record_validate_flag() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "putval.test.pds.local").await;
+
+    let put = |validate: bool| {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.putRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.actor.profile",
+                "rkey": "self",
+                "record": { "$type": "app.bsky.feed.post" },
+                "validate": validate,
+            })),
+        )
+    };
+
+    let (status, body) = put(true).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRecord");
+
+    let (status, body) = put(false).await;
+    assert_xrpc_ok(status, &body);
+}
+
+// ── deleteRecord ────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn delete_record_success() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "del.test.pds.local").await;
+
+    // Create a record
+    let (_, create_body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "bye", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+
+    let uri = create_body["uri"].as_str().unwrap();
+    let rkey = uri.rsplit('/').next().unwrap();
+
+    // Delete it
     let (status, _) = send_request(
         &router,
         "POST",
@@ -443,6 +1248,68 @@ async fn delete_record_wrong_did_forbidden() {
     assert_xrpc_error(status, &body, 403, "AuthorizationError");
 }
 
+#[tokio::test]
+async fn delete_record_swap_record_mismatch() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "swapd.test.pds.local").await;
+
+    let (_, created) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "keep me", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+    let uri = created["uri"].as_str().unwrap();
+    let rkey = uri.rsplit('/').next().unwrap();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.deleteRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "rkey": rkey,
+            "swapRecord": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+        })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidSwap");
+
+    // The record is untouched.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.getRecord?repo={did}&collection=app.bsky.feed.post&rkey={rkey}"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.deleteRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "rkey": rkey,
+            "swapRecord": created["cid"],
+        })),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
 // ── describeRepo ────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -490,3 +1357,780 @@ async fn upload_blob_returns_blob_ref() {
     assert!(body["blob"]["ref"]["$link"].as_str().is_some());
     assert_eq!(body["blob"]["mimeType"], "image/png");
 }
+
+// ── importRepo ──────────────────────────────────────────────────────────
+
+async fn get_repo_car(router: &axum::Router, did: &str) -> Vec<u8> {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let req = axum::http::Request::builder()
+        .method("GET")
+        .uri(format!("/xrpc/com.atproto.sync.getRepo?did={did}"))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    resp.into_body().collect().await.unwrap().to_bytes().to_vec()
+}
+
+async fn import_repo_car(router: &axum::Router, jwt: &str, car: Vec<u8>) -> (u16, serde_json::Value) {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.importRepo")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "application/vnd.ipld.car")
+        .body(axum::body::Body::from(car))
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    let status = resp.status().as_u16();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null))
+}
+
+#[tokio::test]
+async fn import_repo_restores_backup() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "import.test.pds.local").await;
+
+    let create_post = |text: &'static str| {
+        let router = router.clone();
+        let (did, jwt) = (did.clone(), jwt.clone());
+        async move {
+            let (status, body) = send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.repo.createRecord",
+                Some(&jwt),
+                Some(json!({
+                    "repo": did,
+                    "collection": "app.bsky.feed.post",
+                    "record": { "$type": "app.bsky.feed.post", "text": text, "createdAt": "2025-01-01T00:00:00Z" }
+                })),
+            )
+            .await;
+            assert_xrpc_ok(status, &body);
+        }
+    };
+
+    create_post("before backup").await;
+    let backup = get_repo_car(&router, &did).await;
+    create_post("after backup").await;
+
+    let (status, body) = import_repo_car(&router, &jwt, backup).await;
+    assert_eq!(status, 200, "importRepo failed: {body}");
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let records = body["records"].as_array().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["value"]["text"], "before backup");
+
+    // Writes continue on top of the imported repo.
+    create_post("after restore").await;
+}
+
+#[tokio::test]
+async fn import_repo_reports_progress() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "progress.test.pds.local").await;
+
+    for i in 0..3 {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": {
+                    "$type": "app.bsky.feed.post",
+                    "text": format!("post {i}"),
+                    "createdAt": "2025-01-01T00:00:00Z"
+                }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+    let latest_commit = |router: axum::Router, did: String| async move {
+        let (status, body) = send_request(
+            &router,
+            "GET",
+            &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+            None,
+            None,
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+        body
+    };
+    let backup_commit = latest_commit(router.clone(), did.clone()).await;
+    let backup = get_repo_car(&router, &did).await;
+    let (_, blocks) = dallaspds_repo::read_car(&backup).unwrap();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "after backup" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_ne!(latest_commit(router.clone(), did.clone()).await, backup_commit);
+
+    let (status, body) = import_repo_car(&router, &jwt, backup).await;
+    assert_eq!(status, 200, "importRepo failed: {body}");
+    assert_eq!(body["blocks"], blocks.len());
+    let bytes: usize = blocks.iter().map(|(_, data)| data.len()).sum();
+    assert_eq!(body["bytes"], bytes);
+    assert_eq!(body["commit"], backup_commit["cid"]);
+    assert_eq!(body["rev"], backup_commit["rev"]);
+    assert_eq!(latest_commit(router.clone(), did.clone()).await, backup_commit);
+}
+
+#[tokio::test]
+async fn import_repo_rejects_bad_signature() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "forged.test.pds.local").await;
+
+    // Build a repo for the same DID, signed by a key the account doesn't hold.
+    let forge_stores = create_test_stores().await;
+    let forge_store = std::sync::Arc::new(forge_stores.repo_store.clone());
+    let forged_key = dallaspds_crypto::SigningKey::generate_p256().unwrap();
+    let (root, _) = dallaspds_repo::create_repo(forge_store.clone(), &did, &forged_key)
+        .await
+        .unwrap();
+    let car = dallaspds_repo::export_full_car(forge_store, &did, &root)
+        .await
+        .unwrap();
+
+    let (status, body) = import_repo_car(&router, &jwt, car).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+}
+
+#[tokio::test]
+async fn import_repo_rejects_other_accounts_repo() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    let router = create_test_router_with_config(&stores, config);
+    let (alice, _, _) = create_account_via_api(&router, "alice-imp.test.pds.local").await;
+    let (_, bob_jwt, _) = create_account_via_api(&router, "bob-imp.test.pds.local").await;
+
+    let car = get_repo_car(&router, &alice).await;
+    let (status, body) = import_repo_car(&router, &bob_jwt, car).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+}
+
+#[tokio::test]
+async fn import_repo_requires_auth() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.importRepo",
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, 401);
+}
+
+#[tokio::test]
+async fn import_repo_completes_account_migration() {
+    use dallaspds_core::{AccountStatus, AccountStore, EventStore};
+
+    // The account's current home.
+    let plc = spawn_fake_plc().await;
+    let mut config = create_test_config();
+    config.plc_url = plc.url.clone();
+    let old_stores = create_test_stores().await;
+    let old_pds = create_test_router_with_config(&old_stores, config.clone());
+    let (did, old_jwt, _) = create_account_via_api(&old_pds, "mover.test.pds.local").await;
+    let blob = {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let req = axum::http::Request::builder()
+            .method("POST")
+            .uri("/xrpc/com.atproto.repo.uploadBlob")
+            .header("authorization", format!("Bearer {old_jwt}"))
+            .header("content-type", "image/png")
+            .body(axum::body::Body::from("a moving van"))
+            .unwrap();
+        let resp = old_pds.clone().oneshot(req).await.unwrap();
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()["blob"].clone()
+    };
+    let (status, body) = send_request(
+        &old_pds,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&old_jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": {
+                "$type": "app.bsky.feed.post",
+                "text": "moving house",
+                "embed": {
+                    "$type": "app.bsky.embed.images",
+                    "images": [{ "image": blob, "alt": "" }],
+                },
+            }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let car = get_repo_car(&old_pds, &did).await;
+    let old_account = old_stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    let old_key =
+        dallaspds_crypto::SigningKey::from_bytes(&old_account.key_type, &old_account.signing_key).unwrap();
+
+    // 1. createAccount with the existing DID, proving control of it with a
+    //    service auth token from its current key: deactivated, no repo.
+    let (router, stores) = {
+        let stores = create_test_stores().await;
+        (create_test_router_with_config(&stores, config), stores)
+    };
+    let create_account = |token: Option<String>| {
+        let router = router.clone();
+        let did = did.clone();
+        async move {
+            send_request(
+                &router,
+                "POST",
+                "/xrpc/com.atproto.server.createAccount",
+                token.as_deref(),
+                Some(json!({
+                    "handle": "mover.test.pds.local",
+                    "password": TEST_PASSWORD,
+                    "did": did,
+                })),
+            )
+            .await
+        }
+    };
+    let service_auth = |key: &dallaspds_crypto::SigningKey| {
+        dallaspds_server::proxy::service_auth::create_service_auth_token(
+            key,
+            &did,
+            "did:web:test.pds.local",
+            "com.atproto.server.createAccount",
+        )
+        .unwrap()
+    };
+    let (status, body) = create_account(None).await;
+    assert_xrpc_error(status, &body, 401, "AuthenticationRequired");
+    let squatter = dallaspds_crypto::SigningKey::generate_p256().unwrap();
+    let (status, body) = create_account(Some(service_auth(&squatter))).await;
+    assert_xrpc_error(status, &body, 401, "InvalidToken");
+
+    let (status, body) = create_account(Some(service_auth(&old_key))).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["did"], did);
+    let jwt = body["accessJwt"].as_str().unwrap().to_string();
+    let account = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    assert_eq!(account.status, AccountStatus::Deactivated);
+    let root = stores.account_store.get_repo_root(&did).await.unwrap();
+    assert!(root.is_none_or(|root| root.cid.is_empty()));
+
+    // Activating before the repo arrives is refused.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.activateAccount",
+        Some(&jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+
+    // 2. importRepo installs the old repo, re-signed with this PDS's key.
+    let (status, body) = import_repo_car(&router, &jwt, car).await;
+    assert_eq!(status, 200, "importRepo failed: {body}");
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["records"][0]["value"]["text"], "moving house");
+    // The imported record's blob is indexed, so it shows up as expected.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.listBlobs?did={did}&since=2222222222222"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["cids"], json!([blob["ref"]["$link"]]));
+
+    // 3. activateAccount is refused while the DID document still names the
+    //    old PDS's key...
+    let activate = || send_request(&router, "POST", "/xrpc/com.atproto.server.activateAccount", Some(&jwt), None);
+    let (status, body) = activate().await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+
+    // 4. ...so the DID is pointed at this PDS's key, and 5. activateAccount
+    //    flips it live and announces it.
+    let new_key = dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key).unwrap();
+    let mut op = plc.ops(&did).pop().unwrap();
+    op["verificationMethods"]["atproto"] = json!(new_key.did_key());
+    plc.push(&did, op);
+    let (status, body) = activate().await;
+    assert_xrpc_ok(status, &body);
+    let events = stores.event_store.get_events_after(0, 100).await.unwrap();
+    assert!(events.iter().any(|e| e.event_type == "account" && e.did == did));
+
+    let (status, _) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+// ── applyWrites ─────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn apply_writes_rejects_oversized_batches_before_writing() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.limits.max_writes_per_apply = 3;
+    config.limits.max_apply_writes_bytes = 1000;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "batch.test.pds.local").await;
+    let root_before = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+
+    let create = |text: String| {
+        json!({
+            "$type": "com.atproto.repo.applyWrites#create",
+            "collection": "app.bsky.feed.post",
+            "value": { "$type": "app.bsky.feed.post", "text": text }
+        })
+    };
+    let too_many: Vec<_> = (0..4).map(|i| create(format!("post {i}"))).collect();
+    let too_large = vec![create("x".repeat(600)), create("y".repeat(600))];
+
+    for writes in [too_many, too_large] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.applyWrites",
+            Some(&jwt),
+            Some(json!({ "repo": did, "writes": writes })),
+        )
+        .await;
+        assert_xrpc_error(status, &body, 400, "InvalidRequest");
+    }
+
+    // Neither batch touched the repo.
+    let root_after = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+    assert_eq!(root_after.cid, root_before.cid);
+
+    // A batch within both limits goes through.
+    let writes: Vec<_> = (0..3).map(|i| create(format!("post {i}"))).collect();
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.applyWrites",
+        Some(&jwt),
+        Some(json!({ "repo": did, "writes": writes })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn apply_writes_validate_flag() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "applyval.test.pds.local").await;
+
+    let apply = |validate: bool| {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.applyWrites",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "validate": validate,
+                "writes": [{
+                    "$type": "com.atproto.repo.applyWrites#update",
+                    "collection": "app.bsky.actor.profile",
+                    "rkey": "self",
+                    "value": { "$type": "app.bsky.feed.post" }
+                }],
+            })),
+        )
+    };
+
+    let (status, body) = apply(true).await;
+    assert_xrpc_error(status, &body, 400, "InvalidRecord");
+
+    let (status, body) = apply(false).await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn apply_writes_rejects_conflicting_paths() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "conflict.test.pds.local").await;
+
+    let create = |rkey: &str| {
+        json!({
+            "$type": "com.atproto.repo.applyWrites#create",
+            "collection": "app.bsky.feed.post",
+            "rkey": rkey,
+            "value": { "$type": "app.bsky.feed.post", "text": "hi" }
+        })
+    };
+    let delete = |rkey: &str| {
+        json!({
+            "$type": "com.atproto.repo.applyWrites#delete",
+            "collection": "app.bsky.feed.post",
+            "rkey": rkey
+        })
+    };
+
+    for writes in [vec![create("dup"), create("dup")], vec![create("gone"), delete("gone")]] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.applyWrites",
+            Some(&jwt),
+            Some(json!({ "repo": did, "writes": writes })),
+        )
+        .await;
+        assert_xrpc_error(status, &body, 400, "InvalidRequest");
+        assert!(body["message"].as_str().unwrap().contains("app.bsky.feed.post/"));
+    }
+
+    // Nothing from either batch was written.
+    let uri = format!(
+        "/xrpc/com.atproto.repo.getRecord?repo={did}&collection=app.bsky.feed.post&rkey=dup"
+    );
+    let (status, body) = send_request(&router, "GET", &uri, None, None).await;
+    assert_xrpc_error(status, &body, 400, "RecordNotFound");
+
+    // Distinct rkeys in the same collection are fine.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.applyWrites",
+        Some(&jwt),
+        Some(json!({ "repo": did, "writes": [create("one"), create("two")] })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
+
+// ── write concurrency limit ─────────────────────────────────────────────
+
+async fn create_post_raw(router: &axum::Router, jwt: &str, did: &str) -> axum::response::Response {
+    use tower::ServiceExt;
+
+    let body = json!({
+        "repo": did,
+        "collection": "app.bsky.feed.post",
+        "record": {
+            "$type": "app.bsky.feed.post",
+            "text": "throttled?",
+            "createdAt": "2025-01-01T00:00:00Z"
+        }
+    });
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.createRecord")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "application/json")
+        .body(axum::body::Body::from(serde_json::to_vec(&body).unwrap()))
+        .unwrap();
+    router.clone().oneshot(req).await.unwrap()
+}
+
+#[tokio::test]
+async fn writes_throttled_when_write_limit_saturated() {
+    use http_body_util::BodyExt;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.limits.max_concurrent_writes = 2;
+    config.limits.write_queue_timeout_ms = 50;
+    let state = create_test_app_state_with_config(&stores, config);
+    let router = dallaspds_server::build_router(state.clone());
+    let (did, jwt, _) = create_account_via_api(&router, "limit.test.pds.local").await;
+
+    // Occupy every write slot with writes to other repos.
+    let first = state.write_limiter.acquire("did:plc:other1").await.unwrap();
+    let second = state.write_limiter.acquire("did:plc:other2").await.unwrap();
+
+    let resp = create_post_raw(&router, &jwt, &did).await;
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.headers()["retry-after"], "1");
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "ServiceUnavailable");
+
+    // A queued write proceeds as soon as a slot frees up.
+    let queued = tokio::spawn({
+        let router = router.clone();
+        let (jwt, did) = (jwt.clone(), did.clone());
+        async move { create_post_raw(&router, &jwt, &did).await.status() }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    drop(first);
+    assert_eq!(queued.await.unwrap(), 200);
+
+    drop(second);
+    let resp = create_post_raw(&router, &jwt, &did).await;
+    assert_eq!(resp.status(), 200);
+}
+
+// ── read-after-write ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn reads_always_see_the_callers_own_writes() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "raw.test.pds.local").await;
+
+    // Several clients of one account write concurrently; each reads back
+    // every record right after writing it.
+    let mut clients = tokio::task::JoinSet::new();
+    for client in 0..6 {
+        let (router, did, jwt) = (router.clone(), did.clone(), jwt.clone());
+        clients.spawn(async move {
+            for i in 0..8 {
+                let rkey = format!("c{client}r{i}");
+                for version in 0..2 {
+                    let text = format!("{rkey} v{version}");
+                    let (status, body) = send_request(
+                        &router,
+                        "POST",
+                        "/xrpc/com.atproto.repo.putRecord",
+                        Some(&jwt),
+                        Some(json!({
+                            "repo": did,
+                            "collection": "app.bsky.feed.post",
+                            "rkey": rkey,
+                            "record": { "$type": "app.bsky.feed.post", "text": text, "createdAt": "2025-01-01T00:00:00Z" }
+                        })),
+                    )
+                    .await;
+                    assert_xrpc_ok(status, &body);
+                    let written_cid = body["cid"].clone();
+
+                    let (status, body) = send_request(
+                        &router,
+                        "GET",
+                        &format!("/xrpc/com.atproto.repo.getRecord?repo={did}&collection=app.bsky.feed.post&rkey={rkey}"),
+                        None,
+                        None,
+                    )
+                    .await;
+                    assert_xrpc_ok(status, &body);
+                    assert_eq!(body["value"]["text"], text);
+                    assert_eq!(body["cid"], written_cid);
+                }
+            }
+        });
+    }
+    while let Some(result) = clients.join_next().await {
+        result.unwrap();
+    }
+
+    // No write was lost to a concurrent one.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.listRecords?repo={did}&collection=app.bsky.feed.post&limit=100"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let records = body["records"].as_array().unwrap();
+    assert_eq!(records.len(), 6 * 8);
+    assert!(records.iter().all(|r| r["value"]["text"].as_str().unwrap().ends_with(" v1")));
+}
+
+// ── repo storage quota ──────────────────────────────────────────────────
+
+#[tokio::test]
+async fn repo_quota_frees_headroom_once_the_repo_is_compacted() {
+    use dallaspds_core::RepoStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    let (admin_did, admin_jwt, _) = create_account_via_api(
+        &create_test_router_with_config(&stores, config.clone()),
+        "quotaadmin.test.pds.local",
+    )
+    .await;
+    config.admin_dids = vec![admin_did];
+    config.limits.max_repo_bytes = 20_000;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "quota.test.pds.local").await;
+
+    let create = |rkey: String| {
+        send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "rkey": rkey,
+                "record": { "$type": "app.bsky.feed.post", "text": rkey.repeat(1000) }
+            })),
+        )
+    };
+
+    // Large records fill the repo until the quota turns one away.
+    let mut created = Vec::new();
+    loop {
+        let rkey = format!("big{}", created.len());
+        let (status, body) = create(rkey.clone()).await;
+        if status != 200 {
+            assert_xrpc_error(status, &body, 400, "RepoQuotaExceeded");
+            break;
+        }
+        created.push(rkey);
+        assert!(created.len() < 10, "quota never enforced");
+    }
+    assert!(!created.is_empty());
+
+    // Deletes go through at the limit.
+    for rkey in &created {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.deleteRecord",
+            Some(&jwt),
+            Some(json!({ "repo": did, "collection": "app.bsky.feed.post", "rkey": rkey })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+
+    // Earlier versions of the repo still count until it is compacted.
+    let (status, body) = create("after".to_string()).await;
+    assert_xrpc_error(status, &body, 400, "RepoQuotaExceeded");
+
+    let (_, used_before) = stores.repo_store.get_storage_usage(&did).await.unwrap();
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.admin.compactRepo",
+        Some(&admin_jwt),
+        Some(json!({ "did": did })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert!(body["blocksRemoved"].as_u64().unwrap() > 0);
+    let (_, used_after) = stores.repo_store.get_storage_usage(&did).await.unwrap();
+    assert!(used_after < used_before);
+
+    let (status, body) = create("after".to_string()).await;
+    assert_xrpc_ok(status, &body);
+}
+
+// ── email confirmation for writes ───────────────────────────────────────
+
+#[tokio::test]
+async fn writes_require_confirmed_email_when_configured() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.require_email_confirmed_for_writes = true;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "unconfirmed.test.pds.local").await;
+
+    let post = json!({
+        "repo": did,
+        "collection": "app.bsky.feed.post",
+        "record": { "$type": "app.bsky.feed.post", "text": "hi", "createdAt": "2025-01-01T00:00:00Z" }
+    });
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(post.clone()),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 403, "EmailNotConfirmed");
+
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.uploadBlob")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "image/png")
+        .body(axum::body::Body::from(vec![1u8, 2, 3]))
+        .unwrap();
+    let resp = tower::ServiceExt::oneshot(router.clone(), req).await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.importRepo")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "application/vnd.ipld.car")
+        .body(axum::body::Body::from(vec![1u8, 2, 3]))
+        .unwrap();
+    let resp = tower::ServiceExt::oneshot(router.clone(), req).await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Reads are unaffected.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.repo.describeRepo?repo={did}"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    stores.account_store.confirm_email(&did).await.unwrap();
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(post),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
@@ -103,6 +103,201 @@ async fn create_account_initializes_repo() {
     assert!(!root.rev.is_empty(), "repo rev should be initialized");
 }
 
+#[tokio::test]
+async fn create_account_handles_are_case_insensitive() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createAccount",
+        None,
+        Some(json!({
+            "handle": "Alice.test.pds.local",
+            "email": "alice@test.com",
+            "password": TEST_PASSWORD,
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["handle"], "alice.test.pds.local");
+    let did = body["did"].as_str().unwrap().to_string();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createSession",
+        None,
+        Some(json!({
+            "identifier": "alice.test.pds.local",
+            "password": TEST_PASSWORD,
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["did"], did);
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.atproto.identity.resolveHandle?handle=ALICE.test.pds.local",
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["did"], did);
+}
+
+#[tokio::test]
+async fn create_account_rejects_case_only_duplicate_handle() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    let router = create_test_router_with_config(&stores, config);
+    create_account_via_api(&router, "Alice.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createAccount",
+        None,
+        Some(json!({
+            "handle": "alice.test.pds.local",
+            "email": "other@test.com",
+            "password": TEST_PASSWORD,
+        })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "HandleAlreadyTaken");
+}
+
+#[tokio::test]
+async fn create_account_rejects_reserved_handles_and_prefixes() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = dallaspds_core::config::PdsMode::Multi;
+    config.reserved_handles = vec!["Support".to_string()];
+    config.reserved_handle_prefixes = vec!["admin".to_string()];
+    let router = create_test_router_with_config(&stores, config);
+
+    for handle in ["support.test.pds.local", "SUPPORT.test.pds.local", "administrator.test.pds.local"] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.server.createAccount",
+            None,
+            Some(json!({
+                "handle": handle,
+                "email": "reserved@test.com",
+                "password": TEST_PASSWORD,
+            })),
+        )
+        .await;
+        assert_xrpc_error(status, &body, 400, "HandleNotAvailable");
+    }
+
+    // Only the name itself, or names starting with a prefix, are reserved.
+    create_account_via_api(&router, "supporter.test.pds.local").await;
+    create_account_via_api(&router, "sysadmin.test.pds.local").await;
+}
+
+#[tokio::test]
+async fn create_account_lists_recovery_key_before_pds_key() {
+    let plc = spawn_fake_plc().await;
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = plc.url.clone();
+    let router = create_test_router_with_config(&stores, config);
+    let recovery_key = dallaspds_crypto::SigningKey::generate_k256().unwrap().did_key();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createAccount",
+        None,
+        Some(json!({
+            "handle": "recover.test.pds.local",
+            "email": "recover@test.com",
+            "password": TEST_PASSWORD,
+            "recoveryKey": recovery_key,
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    let ops = plc.ops(body["did"].as_str().unwrap());
+    assert_eq!(ops.len(), 1, "the genesis op should be posted to PLC");
+    let pds_key = &ops[0]["verificationMethods"]["atproto"];
+    assert_eq!(ops[0]["rotationKeys"], json!([recovery_key, pds_key]));
+}
+
+#[tokio::test]
+async fn create_account_rejects_malformed_recovery_key() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createAccount",
+        None,
+        Some(json!({
+            "handle": "recover.test.pds.local",
+            "password": TEST_PASSWORD,
+            "recoveryKey": "did:key:not-a-key",
+        })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+}
+
+#[tokio::test]
+async fn required_plc_registration_fails_account_creation() {
+    // A PLC directory that rejects every operation.
+    let app = axum::Router::new().route(
+        "/{did}",
+        axum::routing::post(|| async { axum::http::StatusCode::BAD_REQUEST }),
+    );
+    let (plc_url, rejected) = spawn_recording_server(app).await;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = plc_url;
+    config.plc_registration = dallaspds_core::config::PlcRegistration::Required;
+    let router = create_test_router_with_config(&stores, config);
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createAccount",
+        None,
+        Some(json!({
+            "handle": "unregistered.test.pds.local",
+            "email": "unregistered@test.com",
+            "password": TEST_PASSWORD,
+        })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 502, "PlcRegistrationFailed");
+    use dallaspds_core::AccountStore;
+    let account = stores
+        .account_store
+        .get_account_by_handle("unregistered.test.pds.local")
+        .await
+        .unwrap();
+    assert!(account.is_none());
+    // Nor is the refused genesis op kept in the local PLC log.
+    let did = rejected.lock().unwrap()[0].uri.path().trim_start_matches('/').to_string();
+    assert!(stores.account_store.list_plc_ops(&did).await.unwrap().is_empty());
+
+    // With a working directory the same request succeeds.
+    let plc = spawn_fake_plc().await;
+    let mut config = create_test_config();
+    config.plc_url = plc.url.clone();
+    config.plc_registration = dallaspds_core::config::PlcRegistration::Required;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, _, _) = create_account_via_api(&router, "unregistered.test.pds.local").await;
+    assert_eq!(plc.ops(&did).len(), 1);
+}
+
 // ── createSession ───────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -181,6 +376,40 @@ async fn create_session_nonexistent_account() {
     assert_xrpc_error(status, &body, 400, "AccountNotFound");
 }
 
+#[tokio::test]
+async fn create_session_rehashes_outdated_password() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut weak = create_test_config();
+    weak.password.memory_kib = 1024;
+    weak.password.iterations = 1;
+    let router = create_test_router_with_config(&stores, weak);
+    let (did, _, _) = create_account_via_api(&router, "rehash.test.pds.local").await;
+
+    let before = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    assert!(before.password_hash.contains("m=1024,t=1"));
+
+    // Restart with stronger parameters and log in.
+    let router = create_test_router_with_config(&stores, create_test_config());
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createSession",
+        None,
+        Some(json!({
+            "identifier": "rehash.test.pds.local",
+            "password": TEST_PASSWORD,
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    let after = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    assert_ne!(before.password_hash, after.password_hash);
+    assert!(after.password_hash.contains("m=19456,t=2,p=1"));
+}
+
 // ── getSession ──────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -248,22 +477,270 @@ async fn refresh_session_invalid_token() {
     assert_xrpc_error(status, &body, 401, "InvalidToken");
 }
 
+#[tokio::test]
+async fn refresh_session_rotates_token() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (_, _, refresh_jwt) = create_account_via_api(&router, "rotate.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&refresh_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let new_refresh = body["refreshJwt"].as_str().unwrap().to_string();
+
+    // The rotated token keeps working.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&new_refresh),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn refresh_session_reuse_revokes_chain() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (_, _, refresh_jwt) = create_account_via_api(&router, "reuse.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&refresh_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let new_refresh = body["refreshJwt"].as_str().unwrap().to_string();
+
+    // Replaying the already-rotated token is detected.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&refresh_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 401, "TokenReused");
+
+    // The whole chain is revoked, including the legitimate successor.
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&new_refresh),
+        None,
+    )
+    .await;
+    assert_eq!(status, 401);
+}
+
+#[tokio::test]
+async fn refresh_session_rejected_past_max_age() {
+    use dallaspds_core::{AccountStore, RefreshTokenRecord};
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.jwt.session_max_age_days = Some(30);
+    let router = create_test_router_with_config(&stores, config);
+    let (did, _, refresh_jwt) = create_account_via_api(&router, "maxage.test.pds.local").await;
+
+    // A fresh session refreshes normally.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&refresh_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    // A token whose chain began 31 days ago is rejected, even though the
+    // token itself has not expired.
+    let jti = "aged-session-token";
+    stores
+        .account_store
+        .create_refresh_token(&RefreshTokenRecord {
+            id: jti.to_string(),
+            did: did.clone(),
+            expires_at: chrono::Utc::now() + chrono::Duration::days(60),
+            next_id: None,
+            app_password_name: None,
+            session_created_at: chrono::Utc::now() - chrono::Duration::days(31),
+            dpop_jkt: None,
+        })
+        .await
+        .unwrap();
+    let aged_jwt = dallaspds_crypto::create_refresh_token(&did, jti, TEST_REFRESH_SECRET).unwrap();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&aged_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 401, "ExpiredToken");
+    assert!(stores.account_store.get_refresh_token(jti).await.unwrap().is_none());
+}
+
 // ── deleteSession ───────────────────────────────────────────────────────
 
 #[tokio::test]
-async fn delete_session_clears_tokens() {
+async fn delete_session_revokes_only_that_session() {
     let (router, _stores) = create_test_router_and_stores().await;
-    let (_, access_jwt, _) = create_account_via_api(&router, "delsess.test.pds.local").await;
+    let (_, _, first_refresh) = create_account_via_api(&router, "delsess.test.pds.local").await;
+
+    // A second device logs in, then rotates its token once.
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createSession",
+        None,
+        Some(json!({ "identifier": "delsess.test.pds.local", "password": TEST_PASSWORD })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(body["refreshJwt"].as_str().unwrap()),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let second_refresh = body["refreshJwt"].as_str().unwrap().to_string();
 
+    // deleteSession takes the refresh token, not an access token.
     let (status, _) = send_request(
         &router,
         "POST",
         "/xrpc/com.atproto.server.deleteSession",
-        Some(&access_jwt),
+        Some(&first_refresh),
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&first_refresh),
+        None,
+    )
+    .await;
+    assert_eq!(status, 401);
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&second_refresh),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn delete_session_revokes_the_rotation_chain() {
+    use dallaspds_core::AccountStore;
+
+    let (router, stores) = create_test_router_and_stores().await;
+    let (did, _, original) = create_account_via_api(&router, "chain.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.refreshSession",
+        Some(&original),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let rotated = body["refreshJwt"].as_str().unwrap().to_string();
+
+    let (status, _) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.deleteSession",
+        Some(&rotated),
         None,
     )
     .await;
     assert_eq!(status, 200);
+
+    // Both the current token and the one it replaced are gone.
+    let remaining = stores.account_store.delete_refresh_tokens_for_did(&did).await.unwrap();
+    assert_eq!(remaining, 0);
+}
+
+#[tokio::test]
+async fn delete_session_rejects_access_token() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (_, access_jwt, _) = create_account_via_api(&router, "delaccess.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.deleteSession",
+        Some(&access_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 401, "InvalidToken");
+}
+
+#[tokio::test]
+async fn delete_all_sessions_revokes_every_session() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (_, access_jwt, first_refresh) =
+        create_account_via_api(&router, "delall.test.pds.local").await;
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.createSession",
+        None,
+        Some(json!({ "identifier": "delall.test.pds.local", "password": TEST_PASSWORD })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let second_refresh = body["refreshJwt"].as_str().unwrap().to_string();
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.dallaspds.server.deleteAllSessions",
+        Some(&access_jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["revoked"], 2);
+
+    for token in [first_refresh, second_refresh] {
+        let (status, _) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.server.refreshSession",
+            Some(&token),
+            None,
+        )
+        .await;
+        assert_eq!(status, 401);
+    }
 }
 
 // ── Email Confirmation ──────────────────────────────────────────────────
@@ -399,6 +876,76 @@ async fn reset_password_flow() {
     assert_xrpc_error(status, &body, 401, "InvalidPassword");
 }
 
+#[tokio::test]
+async fn reset_token_past_configured_expiry_is_rejected() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.email.reset.expiry_secs = 0;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, access_jwt, _) = create_account_via_api(&router, "expiry.test.pds.local").await;
+
+    stores
+        .account_store
+        .create_email_token("reset_password", &did, "reset-token-789")
+        .await
+        .unwrap();
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.resetPassword",
+        None,
+        Some(json!({ "token": "reset-token-789", "password": "new-password-123" })),
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "ExpiredToken");
+
+    // Other purposes keep the default lifetime.
+    stores
+        .account_store
+        .create_email_token("confirm_email", &did, "confirm-token-789")
+        .await
+        .unwrap();
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.confirmEmail",
+        Some(&access_jwt),
+        Some(json!({ "email": "expiry@test.com", "token": "confirm-token-789" })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
+
+#[tokio::test]
+async fn reset_token_length_is_configurable() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.email.reset.token_bytes = 32;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, _, _) = create_account_via_api(&router, "length.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.requestPasswordReset",
+        None,
+        Some(json!({ "email": "length@test.com" })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    let (token, _) = stores
+        .account_store
+        .get_email_token("reset_password", &did)
+        .await
+        .unwrap()
+        .expect("reset token should be stored");
+    assert_eq!(token.len(), 64);
+}
+
 #[tokio::test]
 async fn request_password_reset_unknown_email_200() {
     let (router, _stores) = create_test_router_and_stores().await;
@@ -417,6 +964,52 @@ async fn request_password_reset_unknown_email_200() {
     assert_xrpc_ok(status, &body);
 }
 
+#[tokio::test]
+async fn log_email_backend_delivers_reset_token() {
+    use dallaspds_core::config::EmailBackend;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.email.backend = EmailBackend::Log;
+    let state = create_test_app_state_with_config(&stores, config);
+    let email_sender = state.email_sender.clone().expect("log backend should be enabled");
+    let router = dallaspds_server::build_router(state);
+    create_account_via_api(&router, "logged.test.pds.local").await;
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.requestPasswordReset",
+        None,
+        Some(json!({ "email": "logged@test.com" })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    // The verification email from createAccount, then the reset email.
+    let outbox = email_sender.outbox();
+    assert_eq!(outbox.len(), 2);
+    let reset = &outbox[1];
+    assert_eq!(reset.to, "logged@test.com");
+    assert_eq!(reset.email.subject, "Password Reset Request");
+    let token = reset
+        .email
+        .text
+        .split("?token=")
+        .nth(1)
+        .expect("reset email should carry a token link");
+
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.resetPassword",
+        None,
+        Some(json!({ "token": token, "password": "logged-new-password" })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+}
+
 // ── Email Update ────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -467,3 +1060,169 @@ async fn request_email_update_returns_token_not_required() {
     assert_xrpc_ok(status, &body);
     assert_eq!(body["tokenRequired"], false);
 }
+
+// ── checkAccountStatus ──────────────────────────────────────────────────
+
+#[tokio::test]
+async fn check_account_status_reports_repo_and_blob_counts() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "status.test.pds.local").await;
+
+    for text in ["one", "two"] {
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": { "$type": "app.bsky.feed.post", "text": text }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+    }
+
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.uploadBlob")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "application/octet-stream")
+        .body(axum::body::Body::from(b"status blob".to_vec()))
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    resp.into_body().collect().await.unwrap();
+
+    let (status, latest) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &latest);
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.atproto.server.checkAccountStatus",
+        Some(&jwt),
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["activated"], true);
+    assert!(body["validDid"].is_boolean());
+    assert_eq!(body["repoCommit"], latest["cid"]);
+    assert_eq!(body["repoRev"], latest["rev"]);
+    assert!(body["repoBlocks"].as_u64().unwrap() > 2);
+    assert_eq!(body["indexedRecords"], 2);
+    assert_eq!(body["privateStateValues"], 0);
+    assert_eq!(body["expectedBlobs"], 0);
+    assert_eq!(body["importedBlobs"], 1);
+}
+
+#[tokio::test]
+async fn check_account_status_requires_auth() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.atproto.server.checkAccountStatus",
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 401, "AuthenticationRequired");
+}
+
+// ── getConfigPublic ─────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn get_config_public_exposes_limits_without_secrets() {
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.admin_dids = vec!["did:plc:secretadmin".to_string()];
+    config.appview_did = Some("did:web:appview.internal".to_string());
+    config.limits.max_concurrent_writes = 8;
+    let router = create_test_router_with_config(&stores, config);
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.dallaspds.server.getConfigPublic",
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["availableUserDomains"], json!([".test.pds.local"]));
+    assert_eq!(body["limits"]["maxUploadSize"], 10 * 1024 * 1024);
+    assert_eq!(body["limits"]["maxRecordSize"], 10 * 1024 * 1024);
+    assert_eq!(body["limits"]["maxConcurrentWrites"], 8);
+    assert_eq!(body["limits"]["maxWritesPerApply"], 200);
+    assert_eq!(body["features"]["oauth"], true);
+    assert_eq!(body["features"]["firehose"], true);
+
+    let text = body.to_string();
+    for sensitive in [
+        "did:plc:secretadmin",
+        "appview.internal",
+        TEST_ACCESS_SECRET,
+        TEST_REFRESH_SECRET,
+        "adminDids",
+    ] {
+        assert!(!text.contains(sensitive), "getConfigPublic leaked {sensitive}");
+    }
+}
+
+// ── CORS ────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn cors_allowlist_restricts_only_admin_and_oauth_routes() {
+    use tower::ServiceExt;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.cors.allowed_origins = vec!["https://admin.example.com".to_string()];
+    let router = create_test_router_with_config(&stores, config);
+
+    let allowed_origin = |method: &str, uri: &str, origin: &str| {
+        let mut req = axum::http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("origin", origin);
+        if method == "OPTIONS" {
+            req = req.header("access-control-request-method", "GET");
+        }
+        let req = req.body(axum::body::Body::empty()).unwrap();
+        let router = router.clone();
+        async move {
+            let resp = router.oneshot(req).await.unwrap();
+            resp.headers()
+                .get("access-control-allow-origin")
+                .map(|v| v.to_str().unwrap().to_string())
+        }
+    };
+
+    // Ordinary XRPC endpoints stay open to any origin.
+    let describe = "/xrpc/com.atproto.server.describeServer";
+    let got = allowed_origin("OPTIONS", describe, "https://client.example").await;
+    assert_eq!(got.as_deref(), Some("*"));
+
+    // Admin and OAuth endpoints only answer listed origins.
+    for uri in ["/xrpc/com.dallaspds.admin.listAccounts", "/oauth/jwks"] {
+        for method in ["OPTIONS", "GET"] {
+            let got = allowed_origin(method, uri, "https://client.example").await;
+            assert_eq!(got, None, "{method} {uri}");
+            let got = allowed_origin(method, uri, "https://admin.example.com").await;
+            assert_eq!(got.as_deref(), Some("https://admin.example.com"), "{method} {uri}");
+        }
+    }
+}
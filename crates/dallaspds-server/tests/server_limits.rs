@@ -0,0 +1,54 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use dallaspds_core::config::ServerConfig;
+use dallaspds_server::limits::configure_server;
+use dallaspds_test_utils::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Send a health check over `stream` and return what the server answers
+/// with, or an empty string if it closed the connection instead.
+async fn health_check(stream: &mut TcpStream) -> String {
+    let request = b"GET /xrpc/_health HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    if stream.write_all(request).await.is_err() {
+        return String::new();
+    }
+    let mut buf = [0u8; 1024];
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+        .await
+        .expect("server neither answered nor closed the connection");
+    String::from_utf8_lossy(&buf[..read.unwrap_or(0)]).into_owned()
+}
+
+#[tokio::test]
+async fn connections_over_the_limit_are_closed() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let config = ServerConfig {
+        max_connections: 1,
+        ..ServerConfig::default()
+    };
+    let handle = axum_server::Handle::new();
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = configure_server(axum_server::bind(addr).handle(handle.clone()), &config);
+    tokio::spawn(server.serve(router.into_make_service()));
+    let addr = handle.listening().await.unwrap();
+
+    let mut first = TcpStream::connect(addr).await.unwrap();
+    assert!(health_check(&mut first).await.starts_with("HTTP/1.1 200"));
+
+    // The only slot is held by the first (keep-alive) connection.
+    let mut second = TcpStream::connect(addr).await.unwrap();
+    assert_eq!(health_check(&mut second).await, "");
+
+    // Once it closes, new connections are served again.
+    drop(first);
+    for _ in 0..50 {
+        let mut next = TcpStream::connect(addr).await.unwrap();
+        if health_check(&mut next).await.starts_with("HTTP/1.1 200") {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("connection slot was not released");
+}
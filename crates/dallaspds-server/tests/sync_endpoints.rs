@@ -1,3 +1,4 @@
+use dallaspds_core::config::PdsMode;
 use dallaspds_test_utils::*;
 use serde_json::json;
 
@@ -23,6 +24,69 @@ async fn get_repo_returns_car_bytes() {
     assert!(!bytes.is_empty(), "CAR file should not be empty");
 }
 
+async fn get_repo_raw(router: &axum::Router, did: &str, jwt: Option<&str>) -> (u16, Vec<u8>) {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let mut req = axum::http::Request::builder()
+        .method("GET")
+        .uri(format!("/xrpc/com.atproto.sync.getRepo?did={did}"));
+    if let Some(jwt) = jwt {
+        req = req.header("authorization", format!("Bearer {jwt}"));
+    }
+    let resp = router
+        .clone()
+        .oneshot(req.body(axum::body::Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = resp.status().as_u16();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, bytes.to_vec())
+}
+
+#[tokio::test]
+async fn get_repo_takendown_and_deactivated_repos() {
+    use dallaspds_core::AccountStore;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let temp_router = create_test_router_with_config(&stores, config.clone());
+    let (admin_did, admin_jwt, _) =
+        create_account_via_api(&temp_router, "admin.test.pds.local").await;
+    let (taken_did, taken_jwt, _) =
+        create_account_via_api(&temp_router, "taken.test.pds.local").await;
+    let (inactive_did, inactive_jwt, _) =
+        create_account_via_api(&temp_router, "inactive.test.pds.local").await;
+    config.admin_dids = vec![admin_did];
+    let router = create_test_router_with_config(&stores, config);
+
+    stores.account_store.set_takedown(&taken_did, Some("tkd-1")).await.unwrap();
+    stores.account_store.deactivate_account(&inactive_did).await.unwrap();
+
+    for (did, error) in [(&taken_did, "RepoTakendown"), (&inactive_did, "RepoDeactivated")] {
+        let (status, bytes) = get_repo_raw(&router, did, None).await;
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_xrpc_error(status, &body, 400, error);
+    }
+
+    // The owner and admins can still export.
+    for (did, jwt) in [
+        (&taken_did, &taken_jwt),
+        (&inactive_did, &inactive_jwt),
+        (&taken_did, &admin_jwt),
+        (&inactive_did, &admin_jwt),
+    ] {
+        let (status, bytes) = get_repo_raw(&router, did, Some(jwt)).await;
+        assert_eq!(status, 200);
+        assert!(!bytes.is_empty());
+    }
+
+    // Another account's token is not enough.
+    let (status, _) = get_repo_raw(&router, &taken_did, Some(&inactive_jwt)).await;
+    assert_eq!(status, 400);
+}
+
 #[tokio::test]
 async fn get_repo_nonexistent_did_fails() {
     let (router, _stores) = create_test_router_and_stores().await;
@@ -133,6 +197,134 @@ async fn list_blobs_after_upload() {
     assert!(cids.iter().any(|c| c.as_str() == Some(cid)));
 }
 
+async fn upload_blob_ref(
+    router: &axum::Router,
+    jwt: &str,
+    data: &'static [u8],
+) -> serde_json::Value {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.uploadBlob")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "image/png")
+        .body(axum::body::Body::from(data))
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    body["blob"].clone()
+}
+
+async fn latest_rev(router: &axum::Router, did: &str) -> String {
+    let (_, body) = send_request(
+        router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+        None,
+        None,
+    )
+    .await;
+    body["rev"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn list_blobs_since_returns_only_later_references() {
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "blobsince.test.pds.local").await;
+    let genesis_rev = latest_rev(&router, &did).await;
+
+    let mut revs = Vec::new();
+    let mut cids = Vec::new();
+    for data in [&b"first image"[..], &b"second image"[..]] {
+        let blob = upload_blob_ref(&router, &jwt, data).await;
+        cids.push(blob["ref"]["$link"].as_str().unwrap().to_string());
+        let (status, body) = send_request(
+            &router,
+            "POST",
+            "/xrpc/com.atproto.repo.createRecord",
+            Some(&jwt),
+            Some(json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": {
+                    "$type": "app.bsky.feed.post",
+                    "text": "with image",
+                    "embed": {
+                        "$type": "app.bsky.embed.images",
+                        "images": [{ "alt": "", "image": blob }]
+                    }
+                }
+            })),
+        )
+        .await;
+        assert_xrpc_ok(status, &body);
+        revs.push(latest_rev(&router, &did).await);
+    }
+
+    let list_since = |since: String| {
+        let router = router.clone();
+        let did = did.clone();
+        async move {
+            let (status, body) = send_request(
+                &router,
+                "GET",
+                &format!("/xrpc/com.atproto.sync.listBlobs?did={did}&since={since}"),
+                None,
+                None,
+            )
+            .await;
+            assert_xrpc_ok(status, &body);
+            body["cids"].clone()
+        }
+    };
+
+    assert_eq!(list_since(revs[0].clone()).await, json!([cids[1]]));
+    assert_eq!(list_since(revs[1].clone()).await, json!([]));
+    let mut all = cids.clone();
+    all.sort();
+    assert_eq!(list_since(genesis_rev).await, json!(all));
+}
+
+#[tokio::test]
+async fn get_blob_offloads_to_x_accel_redirect_when_configured() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.blobs.x_accel_redirect_prefix = Some("/_blobs/".to_string());
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "accel.test.pds.local").await;
+    let blob = upload_blob_ref(&router, &jwt, b"served by nginx").await;
+    let cid = blob["ref"]["$link"].as_str().unwrap();
+
+    let req = axum::http::Request::builder()
+        .uri(format!("/xrpc/com.atproto.sync.getBlob?did={did}&cid={cid}"))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let expected = format!("/_blobs/{}/{cid}", did.replace(':', "_"));
+    assert_eq!(resp.headers()["x-accel-redirect"], expected.as_str());
+    assert_eq!(resp.headers()["content-type"], "image/png");
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    assert!(body.is_empty());
+
+    // Unknown blobs still 404 rather than redirecting.
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getBlob?did={did}&cid=bafkreimissing"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 404, "BlobNotFound");
+}
+
 #[tokio::test]
 async fn list_repos_includes_account() {
     let (router, _stores) = create_test_router_and_stores().await;
@@ -153,6 +345,130 @@ async fn list_repos_includes_account() {
     assert_eq!(repos[0]["active"], true);
 }
 
+#[tokio::test]
+async fn list_repos_pages_many_accounts() {
+    use dallaspds_core::{AccountStore, CreateAccountInput};
+
+    let (router, stores) = create_test_router_and_stores().await;
+    let (did, _, _) = create_account_via_api(&router, "bulk.test.pds.local").await;
+    let root = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+
+    // Seed the rest directly in the store; password hashing via the API is too slow.
+    for i in 1..500 {
+        let did = format!("did:plc:bulk{i:04}");
+        stores
+            .account_store
+            .create_account(&CreateAccountInput {
+                did: did.clone(),
+                handle: format!("bulk{i}.test.pds.local"),
+                email: None,
+                password_hash: "unused".to_string(),
+                signing_key: vec![],
+                key_type: "p256".to_string(),
+            })
+            .await
+            .unwrap();
+        stores
+            .account_store
+            .update_repo_root(&did, &root.cid, &root.rev)
+            .await
+            .unwrap();
+    }
+
+    let mut total = 0;
+    let mut pages = 0;
+    let mut cursor: Option<String> = None;
+    loop {
+        let uri = match &cursor {
+            Some(c) => format!("/xrpc/com.atproto.sync.listRepos?limit=200&cursor={c}"),
+            None => "/xrpc/com.atproto.sync.listRepos?limit=200".to_string(),
+        };
+        let (status, body) = send_request(&router, "GET", &uri, None, None).await;
+        assert_xrpc_ok(status, &body);
+        pages += 1;
+        total += body["repos"].as_array().unwrap().len();
+        match body["cursor"].as_str() {
+            Some(c) => cursor = Some(c.to_string()),
+            None => break,
+        }
+    }
+
+    assert_eq!(total, 500);
+    assert_eq!(pages, 3);
+}
+
+#[tokio::test]
+async fn get_repo_status_reports_active_and_rev() {
+    let (router, stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "status.test.pds.local").await;
+
+    use dallaspds_core::AccountStore;
+    let root = stores.account_store.get_repo_root(&did).await.unwrap().unwrap();
+
+    let uri = format!("/xrpc/com.atproto.sync.getRepoStatus?did={did}");
+    let (status, body) = send_request(&router, "GET", &uri, None, None).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["did"], did);
+    assert_eq!(body["active"], true);
+    assert_eq!(body["rev"], root.rev);
+    assert!(body.get("status").is_none());
+
+    // Deactivated accounts report their status.
+    send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.deactivateAccount",
+        Some(&jwt),
+        None,
+    )
+    .await;
+    let (status, body) = send_request(&router, "GET", &uri, None, None).await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["active"], false);
+    assert_eq!(body["status"], "deactivated");
+}
+
+#[tokio::test]
+async fn get_repo_status_omits_rev_for_empty_repo() {
+    use dallaspds_core::{AccountStore, CreateAccountInput};
+
+    let (router, stores) = create_test_router_and_stores().await;
+    stores
+        .account_store
+        .create_account(&CreateAccountInput {
+            did: "did:plc:emptyroot".to_string(),
+            handle: "empty.test.pds.local".to_string(),
+            email: None,
+            password_hash: "unused".to_string(),
+            signing_key: vec![],
+            key_type: "p256".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.atproto.sync.getRepoStatus?did=did:plc:emptyroot",
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    assert_eq!(body["active"], true);
+    assert!(body.get("rev").is_none());
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.atproto.sync.getRepoStatus?did=did:plc:nobody",
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "RepoNotFound");
+}
+
 #[tokio::test]
 async fn get_blob_after_upload() {
     let (router, _stores) = create_test_router_and_stores().await;
@@ -187,3 +503,463 @@ async fn get_blob_after_upload() {
     let body_bytes = resp.into_body().collect().await.unwrap().to_bytes();
     assert_eq!(&body_bytes[..], blob_data);
 }
+
+#[tokio::test]
+async fn get_blob_conditional_get() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let (router, _stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "etagblob.test.pds.local").await;
+
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/xrpc/com.atproto.repo.uploadBlob")
+        .header("authorization", format!("Bearer {jwt}"))
+        .header("content-type", "application/octet-stream")
+        .body(axum::body::Body::from(b"cacheable blob".to_vec()))
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let upload_body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let cid = upload_body["blob"]["ref"]["$link"].as_str().unwrap();
+
+    let get_blob = |if_none_match: Option<&str>| {
+        let mut req = axum::http::Request::builder()
+            .method("GET")
+            .uri(format!("/xrpc/com.atproto.sync.getBlob?did={did}&cid={cid}"));
+        if let Some(tag) = if_none_match {
+            req = req.header("if-none-match", tag);
+        }
+        router.clone().oneshot(req.body(axum::body::Body::empty()).unwrap())
+    };
+
+    let resp = get_blob(None).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let etag = resp.headers()["etag"].to_str().unwrap().to_string();
+    assert_eq!(etag, format!("\"{cid}\""));
+    assert_eq!(
+        resp.headers()["cache-control"],
+        "public, max-age=31536000, immutable"
+    );
+
+    let resp = get_blob(Some(&etag)).await.unwrap();
+    assert_eq!(resp.status(), 304);
+    assert_eq!(resp.headers()["etag"].to_str().unwrap(), etag);
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    assert!(body.is_empty());
+
+    let resp = get_blob(Some("\"bafkreisomethingelse\"")).await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+/// Spawn a mock server acting as both a PLC directory and a remote PDS that
+/// serves `data` as `content_type` for every getBlob. Returns its base URL
+/// and a counter of blob fetches.
+async fn spawn_mock_blob_pds(
+    did: &'static str,
+    data: &'static [u8],
+    content_type: &'static str,
+) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+    use axum::extract::Path;
+    use axum::routing::get;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let blob_fetches = std::sync::Arc::new(AtomicUsize::new(0));
+
+    let endpoint = base_url.clone();
+    let fetches = blob_fetches.clone();
+    let app = axum::Router::new()
+        .route(
+            "/xrpc/com.atproto.sync.getBlob",
+            get(move || async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                ([("content-type", content_type)], data)
+            }),
+        )
+        .route(
+            "/{did}",
+            get(move |Path(requested): Path<String>| async move {
+                assert_eq!(requested, did);
+                axum::Json(json!({
+                    "id": did,
+                    "service": [{
+                        "id": "#atproto_pds",
+                        "type": "AtprotoPersonalDataServer",
+                        "serviceEndpoint": endpoint,
+                    }],
+                }))
+            }),
+        );
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (base_url, blob_fetches)
+}
+
+#[tokio::test]
+async fn get_blob_proxies_foreign_repo_when_allowed() {
+    use http_body_util::BodyExt;
+    use std::sync::atomic::Ordering;
+    use tower::ServiceExt;
+
+    let foreign_did = "did:plc:remoteblobs000000000000";
+    let (mock_url, blob_fetches) =
+        spawn_mock_blob_pds(foreign_did, b"remote blob", "image/png").await;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = mock_url;
+    let router = create_test_router_with_config(&stores, config.clone());
+
+    // Upload the same bytes locally just to learn their CID.
+    let (_, jwt, _) = create_account_via_api(&router, "cids.test.pds.local").await;
+    let good = upload_blob_ref(&router, &jwt, b"remote blob").await;
+    let good = good["ref"]["$link"].as_str().unwrap().to_string();
+    let other = upload_blob_ref(&router, &jwt, b"something else").await;
+    let other = other["ref"]["$link"].as_str().unwrap().to_string();
+
+    let get_blob = |router: axum::Router, cid: String| {
+        let req = axum::http::Request::builder()
+            .uri(format!("/xrpc/com.atproto.sync.getBlob?did={foreign_did}&cid={cid}"))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        router.oneshot(req)
+    };
+
+    // Off by default.
+    let resp = get_blob(router, good.clone()).await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert_eq!(blob_fetches.load(Ordering::SeqCst), 0);
+
+    config.blobs.allow_blob_proxy = true;
+    config.blobs.proxy_cache_bytes = 1024;
+    let router = create_test_router_with_config(&stores, config);
+
+    for _ in 0..2 {
+        let resp = get_blob(router.clone(), good.clone()).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "image/png");
+        assert_eq!(resp.headers()["x-content-type-options"], "nosniff");
+        assert!(resp.headers().get("content-disposition").is_none());
+        assert_eq!(resp.headers()["etag"].to_str().unwrap(), format!("\"{good}\""));
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"remote blob");
+    }
+    // The second request is served from the cache.
+    assert_eq!(blob_fetches.load(Ordering::SeqCst), 1);
+
+    // Bytes that don't hash to the requested CID are refused.
+    let resp = get_blob(router, other).await.unwrap();
+    assert_eq!(resp.status(), 502);
+}
+
+#[tokio::test]
+async fn get_blob_proxy_serves_remote_html_as_a_download() {
+    use tower::ServiceExt;
+
+    let foreign_did = "did:plc:remotehtml00000000000000";
+    let page: &[u8] = b"<script>alert(document.cookie)</script>";
+    let (mock_url, _) = spawn_mock_blob_pds(foreign_did, page, "text/html").await;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.plc_url = mock_url;
+    config.blobs.allow_blob_proxy = true;
+    let router = create_test_router_with_config(&stores, config);
+    let (_, jwt, _) = create_account_via_api(&router, "html.test.pds.local").await;
+    let blob = upload_blob_ref(&router, &jwt, page).await;
+    let cid = blob["ref"]["$link"].as_str().unwrap();
+
+    let req = axum::http::Request::builder()
+        .uri(format!("/xrpc/com.atproto.sync.getBlob?did={foreign_did}&cid={cid}"))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "application/octet-stream");
+    assert_eq!(resp.headers()["content-disposition"], "attachment");
+    assert_eq!(resp.headers()["content-security-policy"], "default-src 'none'; sandbox");
+    assert_eq!(resp.headers()["x-content-type-options"], "nosniff");
+}
+
+// ── getBlocks ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn get_blocks_returns_requested_blocks_and_skips_missing() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let stores = create_test_stores().await;
+    let mut config = create_test_config();
+    config.mode = PdsMode::Multi;
+    let router = create_test_router_with_config(&stores, config);
+    let (did, jwt, _) = create_account_via_api(&router, "blocks.test.pds.local").await;
+    let (other_did, _, _) = create_account_via_api(&router, "noblocks.test.pds.local").await;
+
+    let (status, created) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "block me", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &created);
+    let commit_cid = |did: String| {
+        let router = router.clone();
+        async move {
+            let (_, body) = send_request(
+                &router,
+                "GET",
+                &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+                None,
+                None,
+            )
+            .await;
+            body["cid"].as_str().unwrap().to_string()
+        }
+    };
+    let commit = commit_cid(did.clone()).await;
+    let record = created["cid"].as_str().unwrap().to_string();
+    // A valid CID this repo doesn't hold.
+    let foreign = commit_cid(other_did).await;
+
+    let req = axum::http::Request::builder()
+        .method("GET")
+        .uri(format!(
+            "/xrpc/com.atproto.sync.getBlocks?did={did}&cids={record}&cids={foreign}&cids={commit}"
+        ))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "application/vnd.ipld.car");
+    let car = resp.into_body().collect().await.unwrap().to_bytes();
+
+    let (roots, blocks) = dallaspds_repo::read_car(&car).unwrap();
+    assert!(roots.is_empty());
+    let cids: Vec<String> = blocks.iter().map(|(cid, _)| cid.to_string()).collect();
+    assert_eq!(cids, [record, commit]);
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getBlocks?did={did}&cids=not-a-cid"),
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "InvalidRequest");
+}
+
+// ── getRecord ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn get_record_returns_proof_car() {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let stores = create_test_stores().await;
+    let router = create_test_router(&stores);
+    let (did, jwt, _) = create_account_via_api(&router, "proof.test.pds.local").await;
+    let (status, created) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "prove me", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &created);
+    let rkey = created["uri"].as_str().unwrap().rsplit('/').next().unwrap();
+    let (_, latest) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+        None,
+        None,
+    )
+    .await;
+
+    let req = axum::http::Request::builder()
+        .method("GET")
+        .uri(format!(
+            "/xrpc/com.atproto.sync.getRecord?did={did}&collection=app.bsky.feed.post&rkey={rkey}"
+        ))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "application/vnd.ipld.car");
+    let car = resp.into_body().collect().await.unwrap().to_bytes();
+
+    let (roots, blocks) = dallaspds_repo::read_car(&car).unwrap();
+    let commit = latest["cid"].as_str().unwrap();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0].to_string(), commit);
+    let cids: Vec<String> = blocks.iter().map(|(cid, _)| cid.to_string()).collect();
+    assert!(cids.iter().any(|cid| cid == commit));
+    assert!(cids.iter().any(|cid| cid == created["cid"].as_str().unwrap()));
+
+    let (status, body) = send_request(
+        &router,
+        "GET",
+        "/xrpc/com.atproto.sync.getRecord?did=did:plc:nobody&collection=app.bsky.feed.post&rkey=abc",
+        None,
+        None,
+    )
+    .await;
+    assert_xrpc_error(status, &body, 400, "RepoNotFound");
+}
+
+// ── com.dallaspds.sync.getCommit ────────────────────────────────────────
+
+async fn get_commit_raw(router: &axum::Router, query: &str) -> (u16, Vec<u8>) {
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    let req = axum::http::Request::builder()
+        .method("GET")
+        .uri(format!("/xrpc/com.dallaspds.sync.getCommit?{query}"))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let resp = router.clone().oneshot(req).await.unwrap();
+    let status = resp.status().as_u16();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, bytes.to_vec())
+}
+
+/// Check a commit block's signature: the signature covers the DAG-CBOR
+/// encoding of the commit without its `sig` field.
+fn commit_signature_valid(block: &[u8], key: &dallaspds_crypto::SigningKey) -> bool {
+    use ipld_core::ipld::Ipld;
+
+    let Ipld::Map(mut commit) = serde_ipld_dagcbor::from_slice::<Ipld>(block).unwrap() else {
+        panic!("commit is not a map");
+    };
+    let Some(Ipld::Bytes(sig)) = commit.remove("sig") else {
+        panic!("commit has no signature");
+    };
+    let unsigned = serde_ipld_dagcbor::to_vec(&Ipld::Map(commit)).unwrap();
+    key.verify(&unsigned, &sig)
+}
+
+#[tokio::test]
+async fn get_commit_returns_signed_commits_from_history() {
+    use dallaspds_core::AccountStore;
+
+    let (router, stores) = create_test_router_and_stores().await;
+    let (did, jwt, _) = create_account_via_api(&router, "commit.test.pds.local").await;
+    let account = stores.account_store.get_account_by_did(&did).await.unwrap().unwrap();
+    let key = dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key).unwrap();
+
+    let (_, first) = send_request(
+        &router,
+        "GET",
+        &format!("/xrpc/com.atproto.sync.getLatestCommit?did={did}"),
+        None,
+        None,
+    )
+    .await;
+    let first_cid = first["cid"].as_str().unwrap().to_string();
+
+    send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "sign me", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+
+    // Current head.
+    let (status, head) = get_commit_raw(&router, &format!("did={did}")).await;
+    assert_eq!(status, 200);
+    assert!(commit_signature_valid(&head, &key));
+    let other_key = dallaspds_crypto::SigningKey::generate_p256().unwrap();
+    assert!(!commit_signature_valid(&head, &other_key));
+
+    // An earlier commit, by CID.
+    let (status, earlier) = get_commit_raw(&router, &format!("did={did}&cid={first_cid}")).await;
+    assert_eq!(status, 200);
+    assert_ne!(earlier, head);
+    assert!(commit_signature_valid(&earlier, &key));
+
+    // A CID outside the repo's history.
+    let (status, body) = get_commit_raw(
+        &router,
+        &format!("did={did}&cid=bafyreifwqvs46vuze47wuimepm76irzgg5gl23b37xecsut7dwzkaucdie"),
+    )
+    .await;
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_xrpc_error(status, &body, 400, "CommitNotFound");
+
+    let (status, body) = get_commit_raw(&router, "did=did:plc:nobody").await;
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_xrpc_error(status, &body, 400, "RepoNotFound");
+}
+
+#[tokio::test]
+async fn secp256k1_account_signs_commits_with_its_own_curve() {
+    use dallaspds_core::{AccountStore, CreateAccountInput};
+
+    let (router, stores) = create_test_router_and_stores().await;
+    let did = "did:plc:k256signer";
+    let key = dallaspds_crypto::SigningKey::generate_k256().unwrap();
+    stores
+        .account_store
+        .create_account(&CreateAccountInput {
+            did: did.to_string(),
+            handle: "k256.test.pds.local".to_string(),
+            email: None,
+            password_hash: "unused".to_string(),
+            signing_key: key.to_bytes(),
+            key_type: key.key_type().to_string(),
+        })
+        .await
+        .unwrap();
+    let repo_store = std::sync::Arc::new(stores.repo_store.clone());
+    let (root, rev) = dallaspds_repo::create_repo(repo_store, did, &key).await.unwrap();
+    stores.account_store.update_repo_root(did, &root, &rev).await.unwrap();
+
+    let keys = dallaspds_crypto::AccessTokenKeys::hs256(TEST_ACCESS_SECRET);
+    let jwt = dallaspds_crypto::create_access_token(did, &keys).unwrap();
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.repo.createRecord",
+        Some(&jwt),
+        Some(json!({
+            "repo": did,
+            "collection": "app.bsky.feed.post",
+            "record": { "$type": "app.bsky.feed.post", "text": "k256", "createdAt": "2025-01-01T00:00:00Z" }
+        })),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+
+    // The stored curve reloads the same key, whose did:key verifies the new commit.
+    let account = stores.account_store.get_account_by_did(did).await.unwrap().unwrap();
+    assert_eq!(account.key_type, "k256");
+    let stored = dallaspds_crypto::SigningKey::from_bytes(&account.key_type, &account.signing_key)
+        .unwrap();
+    assert_eq!(stored.did_key(), key.did_key());
+
+    let (status, head) = get_commit_raw(&router, &format!("did={did}")).await;
+    assert_eq!(status, 200);
+    assert!(commit_signature_valid(&head, &stored));
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use axum::http::StatusCode;
+use dallaspds_core::config::WebhooksConfig;
+use dallaspds_server::webhooks::{SIGNATURE_HEADER, sign};
+use dallaspds_test_utils::*;
+use serde_json::{Value, json};
+
+const SECRET: &str = "webhook-test-secret";
+
+/// Start a fake webhook endpoint on `/hook`, answering the first `failures`
+/// requests with a 500.
+async fn mock_endpoint(failures: usize) -> (String, RecordedCalls) {
+    let seen = Arc::new(AtomicUsize::new(0));
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move || {
+            let seen = seen.clone();
+            async move {
+                if seen.fetch_add(1, Ordering::SeqCst) < failures {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                } else {
+                    StatusCode::OK
+                }
+            }
+        }),
+    );
+    let (url, calls) = spawn_recording_server(app).await;
+    (format!("{url}/hook"), calls)
+}
+
+fn router_with_webhook(stores: &TestStores, url: String) -> axum::Router {
+    let mut config = create_test_config();
+    config.webhooks = Some(WebhooksConfig {
+        url,
+        secret: SECRET.to_string(),
+    });
+    create_test_router_with_config(stores, config)
+}
+
+#[tokio::test]
+async fn signed_events_for_account_lifecycle() {
+    let (url, calls) = mock_endpoint(0).await;
+    let stores = create_test_stores().await;
+    let router = router_with_webhook(&stores, url);
+
+    let (did, jwt, _) = create_account_via_api(&router, "hooked.test.pds.local").await;
+    wait_for_calls(&calls, 1).await;
+    let (status, body) = send_request(
+        &router,
+        "POST",
+        "/xrpc/com.atproto.server.deactivateAccount",
+        Some(&jwt),
+        Some(json!({})),
+    )
+    .await;
+    assert_xrpc_ok(status, &body);
+    wait_for_calls(&calls, 2).await;
+
+    let calls = calls.lock().unwrap();
+    let events: Vec<Value> = calls
+        .iter()
+        .map(|call| {
+            let signature = call.headers[SIGNATURE_HEADER].to_str().unwrap();
+            assert_eq!(signature, sign(SECRET, &call.body));
+            call.json()
+        })
+        .collect();
+    assert_eq!(events[0]["event"], "account.create");
+    assert_eq!(events[1]["event"], "account.deactivate");
+    for event in &events {
+        assert_eq!(event["did"], did);
+        assert!(chrono::DateTime::parse_from_rfc3339(event["time"].as_str().unwrap()).is_ok());
+    }
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried() {
+    let (url, calls) = mock_endpoint(1).await;
+    let stores = create_test_stores().await;
+    let router = router_with_webhook(&stores, url);
+
+    // The endpoint is down for the first attempt; the request isn't held up.
+    create_account_via_api(&router, "retried.test.pds.local").await;
+    wait_for_calls(&calls, 2).await;
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(
+        calls[0].headers[SIGNATURE_HEADER],
+        calls[1].headers[SIGNATURE_HEADER]
+    );
+    assert_eq!(calls[0].body, calls[1].body);
+}
@@ -3,7 +3,18 @@ use std::sync::Arc;
 use dallaspds_blob_fs::FsBlobStore;
 use dallaspds_core::EventStore;
 use dallaspds_core::config::PdsConfig;
-use dallaspds_server::{AppState, build_router};
+use dallaspds_crypto::{AccessTokenKeys, TidGenerator};
+use dallaspds_server::firehose::retention::SubscriberCursors;
+use dallaspds_server::lexicon::LexiconSet;
+use dallaspds_server::limits::{RepoQuota, WriteLimiter, configure_server};
+use dallaspds_server::proxy::remote_blob::RemoteBlobCache;
+use dallaspds_server::proxy::remote_record::PdsEndpointCache;
+use dallaspds_server::proxy::response_cache::AppViewCache;
+use dallaspds_server::rate_limit::RateLimiter;
+use dallaspds_server::oauth_client::ClientMetadataCache;
+use dallaspds_server::read_only::ReadOnly;
+use dallaspds_server::shutdown::{Shutdown, shutdown_on_signal};
+use dallaspds_server::{AppState, RelayNotifier, WebhookNotifier, build_router};
 use dallaspds_storage_sqlite::{SqliteAccountStore, SqliteEventStore, SqliteRepoStore};
 
 #[tokio::main]
@@ -12,15 +23,14 @@ async fn main() -> anyhow::Result<()> {
 
     let config_path =
         std::env::var("CONFIG_PATH").unwrap_or_else(|_| "config/single.toml".to_string());
-    let config = PdsConfig::load(&config_path)?;
+    let config = PdsConfig::load(&config_path).await?;
 
     // Ensure the data directory exists
     std::fs::create_dir_all("data")?;
 
     // Connect real storage backends
-    let account_store = SqliteAccountStore::connect(&config.database.url).await?;
-    let repo_store = SqliteRepoStore::connect(&config.database.url).await?;
-    let event_store = SqliteEventStore::connect(&config.database.url).await?;
+    let account_store = SqliteAccountStore::connect_with(&config.database).await?;
+    let repo_store = SqliteRepoStore::connect_with(&config.database).await?;
 
     let blobs_path = config.blobs.path.as_deref().unwrap_or("data/blobs");
     let blob_store = FsBlobStore::new(blobs_path)?;
@@ -29,33 +39,104 @@ async fn main() -> anyhow::Result<()> {
 
     // Extract TLS config before moving config into Arc
     let tls_config = config.tls.clone();
+    let server_config = config.server.clone();
     let public_url = config.public_url.clone();
 
-    // Resume sequencer from the last persisted event sequence number.
-    let max_seq = event_store.get_max_seq().await?;
-    let sequencer = dallaspds_server::Sequencer::new(max_seq + 1, 1024);
-    let relay_notifier = None; // No relay configured by default
-
-    let event_store: Arc<dyn EventStore> = Arc::new(event_store);
+    let (sequencer, event_store) = if config.firehose.enabled {
+        let event_store = SqliteEventStore::connect_with(&config.database).await?;
+        // Resume sequencer from the last persisted event sequence number.
+        let max_seq = event_store.get_max_seq().await?;
+        let sequencer = dallaspds_server::connect_sequencer(&config.firehose, max_seq + 1).await?;
+        let event_store: Arc<dyn EventStore> = Arc::new(event_store);
+        (Some(sequencer), Some(event_store))
+    } else {
+        tracing::info!("Firehose disabled; skipping sequencer and event store");
+        (None, None)
+    };
+    let relay_notifier = if !config.relay_url.is_empty() && config.firehose.enabled {
+        let (notifier, worker) = RelayNotifier::new(
+            config.relay_url.clone(),
+            config.hostname.clone(),
+            std::time::Duration::from_secs(config.relay_debounce_secs),
+        );
+        tokio::spawn(worker.run());
+        Some(notifier)
+    } else {
+        None
+    };
 
-    let email_sender = config.smtp.as_ref().map(|smtp_config| {
-        Arc::new(
-            dallaspds_server::email::EmailSender::new(smtp_config)
-                .expect("Failed to init SMTP"),
-        )
+    let webhooks = config.webhooks.as_ref().map(|webhooks| {
+        let (notifier, worker) = WebhookNotifier::new(webhooks);
+        tokio::spawn(worker.run());
+        notifier
     });
 
+    let email_sender = dallaspds_server::email::EmailSender::from_config(&config)?.map(Arc::new);
+
+    let lexicons = LexiconSet::from_config(&config)?.map(Arc::new);
+    let access_token_keys = Arc::new(AccessTokenKeys::from_config(&config.jwt)?);
+    let write_limiter = Arc::new(WriteLimiter::new(&config.limits));
+    let repo_quota = Arc::new(RepoQuota::new(&config.limits));
+    let rate_limiter = Arc::new(RateLimiter::new(&config.rate_limit));
+    let appview_cache = Arc::new(AppViewCache::new(&config.appview_cache));
+    let remote_blob_cache = Arc::new(RemoteBlobCache::new(config.blobs.proxy_cache_bytes));
+    let read_only = ReadOnly::new(config.read_only);
+
     let state = AppState {
         account_store: Arc::new(account_store),
         repo_store: Arc::new(repo_store),
         blob_store: Arc::new(blob_store),
         config: Arc::new(config),
-        sequencer: Some(sequencer),
+        access_token_keys,
+        tid_gen: Arc::new(TidGenerator::new()),
+        sequencer,
         relay_notifier,
-        event_store: Some(event_store),
+        webhooks,
+        event_store,
+        subscriber_cursors: Arc::new(SubscriberCursors::default()),
         email_sender,
+        pds_endpoint_cache: Arc::new(PdsEndpointCache::default()),
+        remote_blob_cache,
+        lexicons,
+        write_limiter,
+        repo_quota,
+        rate_limiter,
+        appview_cache,
+        oauth_clients: Arc::new(ClientMetadataCache::default()),
+        shutdown: Shutdown::default(),
+        read_only,
     };
 
+    dallaspds_server::cleanup::spawn_refresh_token_cleanup(
+        state.account_store.clone(),
+        dallaspds_server::cleanup::REFRESH_TOKEN_CLEANUP_INTERVAL,
+    );
+    dallaspds_server::cleanup::spawn_email_token_cleanup(
+        state.account_store.clone(),
+        state.config.email.clone(),
+        dallaspds_server::cleanup::EMAIL_TOKEN_CLEANUP_INTERVAL,
+    );
+    dallaspds_server::cleanup::spawn_scheduled_account_deletion(
+        state.clone(),
+        dallaspds_server::cleanup::ACCOUNT_DELETION_INTERVAL,
+    );
+    // Catch the firehose up with commits made just before an unclean exit.
+    dallaspds_server::firehose::reconcile::spawn_reconcile(state.clone());
+    if let Some(event_store) = &state.event_store {
+        dallaspds_server::cleanup::spawn_event_pruning(
+            event_store.clone(),
+            state.config.firehose.clone(),
+            state.subscriber_cursors.clone(),
+        );
+    }
+
+    let handle = axum_server::Handle::new();
+    tokio::spawn(shutdown_on_signal(
+        handle.clone(),
+        state.shutdown.clone(),
+        std::time::Duration::from_secs(server_config.shutdown_grace_secs),
+    ));
+
     let router = build_router(state);
 
     if let Some(tls_config) = tls_config {
@@ -81,14 +162,17 @@ async fn main() -> anyhow::Result<()> {
 
         tracing::info!("dallaspds-single starting HTTPS on {}", addr);
         let sock_addr: std::net::SocketAddr = addr.parse()?;
-        axum_server::bind(sock_addr)
-            .acceptor(acceptor)
-            .serve(router.into_make_service())
+        let server = axum_server::bind(sock_addr).acceptor(acceptor).handle(handle);
+        configure_server(server, &server_config)
+            .serve(router.into_make_service_with_connect_info::<std::net::SocketAddr>())
             .await?;
     } else {
         tracing::info!("dallaspds-single starting on {}", addr);
-        let listener = tokio::net::TcpListener::bind(&addr).await?;
-        axum::serve(listener, router).await?;
+        let sock_addr: std::net::SocketAddr = addr.parse()?;
+        let server = axum_server::bind(sock_addr).handle(handle);
+        configure_server(server, &server_config)
+            .serve(router.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .await?;
     }
 
     Ok(())
@@ -0,0 +1,677 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use dallaspds_core::config::{EMAIL_TOKEN_PURPOSES, EmailConfig};
+use dallaspds_core::{
+    AccountSearchFilter, AccountStatus, AccountStore, ActorAccount, CreateAccountInput, InviteCode,
+    InviteCodeUse, OAuthRequest, PdsError, PdsResult, PlcOpLogEntry, RefreshTokenRecord, RepoListEntry,
+    RepoRoot,
+};
+
+use crate::repo::{MemRepoStore, RepoRoots};
+
+#[derive(Default)]
+struct Inner {
+    /// Accounts keyed by DID, so iteration is in DID order for keyset
+    /// pagination. `status` is recomputed on every read.
+    accounts: BTreeMap<String, ActorAccount>,
+    refresh_tokens: HashMap<String, RefreshTokenRecord>,
+    oauth_requests: HashMap<String, OAuthRequest>,
+    invite_codes: HashMap<String, InviteCode>,
+    /// (purpose, did) -> (token, requested_at)
+    email_tokens: HashMap<(String, String), (String, DateTime<Utc>)>,
+    /// (did, cid) -> takedown ref
+    blob_takedowns: HashMap<(String, String), String>,
+    /// PLC operations by DID, oldest first.
+    plc_ops: HashMap<String, Vec<PlcOpLogEntry>>,
+}
+
+impl Inner {
+    fn account(&self, did: &str) -> Option<ActorAccount> {
+        self.accounts.get(did).map(with_status)
+    }
+
+    fn account_mut(&mut self, did: &str) -> Option<&mut ActorAccount> {
+        self.accounts.get_mut(did)
+    }
+
+    fn handle_taken(&self, handle: &str, by_other_than: &str) -> bool {
+        self.accounts
+            .values()
+            .any(|a| a.did != by_other_than && a.handle.as_deref() == Some(handle))
+    }
+
+    fn email_taken(&self, email: &str, by_other_than: &str) -> bool {
+        self.accounts
+            .values()
+            .any(|a| a.did != by_other_than && a.email.as_deref() == Some(email))
+    }
+}
+
+/// Compute the account status from the deactivated_at and takedown_ref fields.
+fn compute_status(account: &ActorAccount) -> AccountStatus {
+    if account.takedown_ref.is_some() {
+        AccountStatus::Takendown
+    } else if account.deactivated_at.is_some() {
+        AccountStatus::Deactivated
+    } else {
+        AccountStatus::Active
+    }
+}
+
+fn with_status(account: &ActorAccount) -> ActorAccount {
+    ActorAccount {
+        status: compute_status(account),
+        ..account.clone()
+    }
+}
+
+/// How well `account` matches the lowercased search `query`, lower being
+/// better: an exact handle, a handle prefix, a handle substring, then an
+/// email substring. `None` if it doesn't match.
+fn search_rank(account: &ActorAccount, query: &str) -> Option<u8> {
+    let handle = account.handle.as_deref().unwrap_or_default().to_ascii_lowercase();
+    let email = account.email.as_deref().unwrap_or_default().to_ascii_lowercase();
+    if handle == query {
+        Some(0)
+    } else if handle.starts_with(query) {
+        Some(1)
+    } else if handle.contains(query) {
+        Some(2)
+    } else if email.contains(query) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// An `AccountStore` holding accounts, sessions and tokens in memory.
+/// Clones share the same data.
+#[derive(Clone, Default)]
+pub struct MemAccountStore {
+    inner: Arc<RwLock<Inner>>,
+    /// Shared with the repo stores from [`MemAccountStore::repo_store`].
+    repo_roots: RepoRoots,
+}
+
+impl MemAccountStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A repo store whose `commit_blocks` moves this store's repo roots, as
+    /// the SQL stores do by sharing one database.
+    pub fn repo_store(&self) -> MemRepoStore {
+        MemRepoStore::with_roots(self.repo_roots.clone())
+    }
+}
+
+#[async_trait]
+impl AccountStore for MemAccountStore {
+    async fn create_account(&self, input: &CreateAccountInput) -> PdsResult<ActorAccount> {
+        let mut inner = self.inner.write().unwrap();
+        let handle = input.handle.to_ascii_lowercase();
+        if inner.accounts.contains_key(&input.did) {
+            return Err(PdsError::Storage(format!("account {} already exists", input.did)));
+        }
+        if inner.handle_taken(&handle, &input.did) {
+            return Err(PdsError::HandleAlreadyTaken);
+        }
+        if let Some(email) = &input.email
+            && inner.email_taken(email, &input.did)
+        {
+            return Err(PdsError::Storage(format!("email {email} is already in use")));
+        }
+
+        let now = Utc::now();
+        let account = ActorAccount {
+            did: input.did.clone(),
+            handle: Some(handle),
+            email: input.email.clone(),
+            email_confirmed_at: None,
+            password_hash: input.password_hash.clone(),
+            signing_key: input.signing_key.clone(),
+            key_type: input.key_type.clone(),
+            created_at: now,
+            status: AccountStatus::Active,
+            deactivated_at: None,
+            takedown_ref: None,
+            delete_after: None,
+        };
+        inner.accounts.insert(input.did.clone(), account.clone());
+        self.repo_roots.write().unwrap().insert(
+            input.did.clone(),
+            RepoRoot {
+                did: input.did.clone(),
+                cid: Vec::new(),
+                rev: String::new(),
+                indexed_at: now,
+            },
+        );
+        Ok(account)
+    }
+
+    async fn get_account_by_did(&self, did: &str) -> PdsResult<Option<ActorAccount>> {
+        Ok(self.inner.read().unwrap().account(did))
+    }
+
+    async fn get_account_by_handle(&self, handle: &str) -> PdsResult<Option<ActorAccount>> {
+        let handle = handle.to_ascii_lowercase();
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .accounts
+            .values()
+            .find(|a| a.handle.as_deref() == Some(handle.as_str()))
+            .map(with_status))
+    }
+
+    async fn get_account_by_email(&self, email: &str) -> PdsResult<Option<ActorAccount>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .accounts
+            .values()
+            .find(|a| a.email.as_deref() == Some(email))
+            .map(with_status))
+    }
+
+    async fn update_handle(&self, did: &str, handle: &str) -> PdsResult<()> {
+        let handle = handle.to_ascii_lowercase();
+        let mut inner = self.inner.write().unwrap();
+        if inner.handle_taken(&handle, did) {
+            return Err(PdsError::HandleAlreadyTaken);
+        }
+        if let Some(account) = inner.account_mut(did) {
+            account.handle = Some(handle);
+        }
+        Ok(())
+    }
+
+    async fn update_password(&self, did: &str, password_hash: &str) -> PdsResult<()> {
+        if let Some(account) = self.inner.write().unwrap().account_mut(did) {
+            account.password_hash = password_hash.to_string();
+        }
+        Ok(())
+    }
+
+    async fn deactivate_account(&self, did: &str) -> PdsResult<()> {
+        if let Some(account) = self.inner.write().unwrap().account_mut(did) {
+            account.deactivated_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    async fn activate_account(&self, did: &str) -> PdsResult<()> {
+        if let Some(account) = self.inner.write().unwrap().account_mut(did) {
+            account.deactivated_at = None;
+        }
+        Ok(())
+    }
+
+    async fn set_delete_after(
+        &self,
+        did: &str,
+        delete_after: Option<chrono::DateTime<Utc>>,
+    ) -> PdsResult<()> {
+        if let Some(account) = self.inner.write().unwrap().account_mut(did) {
+            account.delete_after = delete_after;
+        }
+        Ok(())
+    }
+
+    async fn list_accounts_due_for_deletion(
+        &self,
+        now: chrono::DateTime<Utc>,
+    ) -> PdsResult<Vec<String>> {
+        let inner = self.inner.read().unwrap();
+        let mut due: Vec<String> = inner
+            .accounts
+            .values()
+            .filter(|a| a.delete_after.is_some_and(|t| t <= now))
+            .map(|a| a.did.clone())
+            .collect();
+        due.sort();
+        Ok(due)
+    }
+
+    async fn claim_scheduled_deletion(
+        &self,
+        did: &str,
+        now: chrono::DateTime<Utc>,
+        lease_until: chrono::DateTime<Utc>,
+    ) -> PdsResult<bool> {
+        let mut inner = self.inner.write().unwrap();
+        match inner.account_mut(did) {
+            Some(account) if account.delete_after.is_some_and(|t| t <= now) => {
+                account.delete_after = Some(lease_until);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete_account(&self, did: &str) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        inner.accounts.remove(did);
+        self.repo_roots.write().unwrap().remove(did);
+        inner.refresh_tokens.retain(|_, token| token.did != did);
+        inner.email_tokens.retain(|(_, token_did), _| token_did != did);
+        inner.blob_takedowns.retain(|(blob_did, _), _| blob_did != did);
+        Ok(())
+    }
+
+    async fn get_repo_root(&self, did: &str) -> PdsResult<Option<RepoRoot>> {
+        Ok(self.repo_roots.read().unwrap().get(did).cloned())
+    }
+
+    async fn update_repo_root(&self, did: &str, cid: &[u8], rev: &str) -> PdsResult<()> {
+        let root = RepoRoot {
+            did: did.to_string(),
+            cid: cid.to_vec(),
+            rev: rev.to_string(),
+            indexed_at: Utc::now(),
+        };
+        self.repo_roots.write().unwrap().insert(did.to_string(), root);
+        Ok(())
+    }
+
+    async fn create_refresh_token(&self, token: &RefreshTokenRecord) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        if inner.refresh_tokens.contains_key(&token.id) {
+            return Err(PdsError::Storage(format!("refresh token {} already exists", token.id)));
+        }
+        inner.refresh_tokens.insert(token.id.clone(), token.clone());
+        Ok(())
+    }
+
+    async fn get_refresh_token(&self, id: &str) -> PdsResult<Option<RefreshTokenRecord>> {
+        Ok(self.inner.read().unwrap().refresh_tokens.get(id).cloned())
+    }
+
+    async fn delete_refresh_token(&self, id: &str) -> PdsResult<()> {
+        self.inner.write().unwrap().refresh_tokens.remove(id);
+        Ok(())
+    }
+
+    async fn delete_refresh_token_chain(&self, id: &str) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        let mut pending = vec![id.to_string()];
+        let mut deleted = 0;
+        while let Some(id) = pending.pop() {
+            let Some(token) = inner.refresh_tokens.remove(&id) else {
+                continue;
+            };
+            deleted += 1;
+            pending.extend(token.next_id);
+            pending.extend(
+                inner
+                    .refresh_tokens
+                    .values()
+                    .filter(|t| t.next_id.as_deref() == Some(id.as_str()))
+                    .map(|t| t.id.clone()),
+            );
+        }
+        Ok(deleted)
+    }
+
+    async fn delete_refresh_tokens_for_did(&self, did: &str) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        let before = inner.refresh_tokens.len();
+        inner.refresh_tokens.retain(|_, token| token.did != did);
+        Ok((before - inner.refresh_tokens.len()) as u64)
+    }
+
+    async fn set_refresh_token_next_id(&self, id: &str, next_id: &str) -> PdsResult<bool> {
+        let mut inner = self.inner.write().unwrap();
+        match inner.refresh_tokens.get_mut(id) {
+            Some(token) if token.next_id.is_none() => {
+                token.next_id = Some(next_id.to_string());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete_expired_refresh_tokens(&self, now: DateTime<Utc>) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        let before = inner.refresh_tokens.len();
+        inner.refresh_tokens.retain(|_, token| token.expires_at >= now);
+        Ok((before - inner.refresh_tokens.len()) as u64)
+    }
+
+    async fn list_accounts(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<ActorAccount>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .accounts
+            .values()
+            .filter(|a| cursor.is_none_or(|cursor| a.did.as_str() > cursor))
+            .take(limit)
+            .map(with_status)
+            .collect())
+    }
+
+    async fn list_repos(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<RepoListEntry>> {
+        let inner = self.inner.read().unwrap();
+        let repo_roots = self.repo_roots.read().unwrap();
+        Ok(inner
+            .accounts
+            .values()
+            .filter(|a| cursor.is_none_or(|cursor| a.did.as_str() > cursor))
+            .filter_map(|a| {
+                let root = repo_roots.get(&a.did).filter(|r| !r.cid.is_empty())?;
+                Some(RepoListEntry {
+                    did: a.did.clone(),
+                    cid: root.cid.clone(),
+                    rev: root.rev.clone(),
+                    status: compute_status(a),
+                })
+            })
+            .take(limit)
+            .collect())
+    }
+
+    async fn create_oauth_request(&self, request: &OAuthRequest) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        if inner.oauth_requests.contains_key(&request.request_uri) {
+            return Err(PdsError::Storage(format!(
+                "oauth request {} already exists",
+                request.request_uri
+            )));
+        }
+        let request = OAuthRequest {
+            did: None,
+            code: None,
+            ..request.clone()
+        };
+        inner.oauth_requests.insert(request.request_uri.clone(), request);
+        Ok(())
+    }
+
+    async fn get_oauth_request(&self, request_uri: &str) -> PdsResult<Option<OAuthRequest>> {
+        Ok(self.inner.read().unwrap().oauth_requests.get(request_uri).cloned())
+    }
+
+    async fn authorize_oauth_request(
+        &self,
+        request_uri: &str,
+        did: &str,
+        code: &str,
+        expires_at: DateTime<Utc>,
+    ) -> PdsResult<bool> {
+        let mut inner = self.inner.write().unwrap();
+        match inner.oauth_requests.get_mut(request_uri) {
+            Some(request) if request.code.is_none() => {
+                request.did = Some(did.to_string());
+                request.code = Some(code.to_string());
+                request.expires_at = expires_at;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn consume_oauth_code(&self, code: &str) -> PdsResult<Option<OAuthRequest>> {
+        let mut inner = self.inner.write().unwrap();
+        let request_uri = inner
+            .oauth_requests
+            .values()
+            .find(|r| r.code.as_deref() == Some(code))
+            .map(|r| r.request_uri.clone());
+        Ok(request_uri.and_then(|uri| inner.oauth_requests.remove(&uri)))
+    }
+
+    async fn delete_expired_oauth_requests(&self, now: DateTime<Utc>) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        let before = inner.oauth_requests.len();
+        inner.oauth_requests.retain(|_, request| request.expires_at >= now);
+        Ok((before - inner.oauth_requests.len()) as u64)
+    }
+
+    async fn create_invite_code(
+        &self,
+        code: &str,
+        available_uses: i32,
+        for_account: &str,
+        created_by: &str,
+    ) -> PdsResult<InviteCode> {
+        let mut inner = self.inner.write().unwrap();
+        if inner.invite_codes.contains_key(code) {
+            return Err(PdsError::Storage(format!("invite code {code} already exists")));
+        }
+        let invite = InviteCode {
+            code: code.to_string(),
+            available_uses,
+            disabled: false,
+            for_account: for_account.to_string(),
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+            uses: Vec::new(),
+        };
+        inner.invite_codes.insert(code.to_string(), invite.clone());
+        Ok(invite)
+    }
+
+    async fn get_invite_code(&self, code: &str) -> PdsResult<Option<InviteCode>> {
+        Ok(self.inner.read().unwrap().invite_codes.get(code).cloned())
+    }
+
+    async fn use_invite_code(&self, code: &str, used_by: &str) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        let invite = inner
+            .invite_codes
+            .get_mut(code)
+            .ok_or_else(|| PdsError::Storage(format!("invite code {code} does not exist")))?;
+        if invite.uses.iter().any(|u| u.used_by == used_by) {
+            return Err(PdsError::Storage(format!("{used_by} already used invite code {code}")));
+        }
+        invite.uses.push(InviteCodeUse {
+            code: code.to_string(),
+            used_by: used_by.to_string(),
+            used_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    async fn list_invite_codes(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<InviteCode>> {
+        let inner = self.inner.read().unwrap();
+        let mut codes: Vec<&InviteCode> = inner
+            .invite_codes
+            .values()
+            .filter(|invite| cursor.is_none_or(|cursor| invite.code.as_str() < cursor))
+            .collect();
+        codes.sort_by_key(|invite| std::cmp::Reverse(invite.created_at));
+        Ok(codes.into_iter().take(limit).cloned().collect())
+    }
+
+    async fn list_invite_codes_for_account(&self, did: &str) -> PdsResult<Vec<InviteCode>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .invite_codes
+            .values()
+            .filter(|invite| invite.for_account == did || invite.created_by == did)
+            .cloned()
+            .collect())
+    }
+
+    async fn disable_invite_code(&self, code: &str) -> PdsResult<()> {
+        if let Some(invite) = self.inner.write().unwrap().invite_codes.get_mut(code) {
+            invite.disabled = true;
+        }
+        Ok(())
+    }
+
+    async fn search_accounts(
+        &self,
+        filter: &AccountSearchFilter,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<ActorAccount>> {
+        // Like SQLite's LIKE, the query matches ASCII case-insensitively.
+        let query = filter.query.as_deref().map(str::to_ascii_lowercase);
+        let inner = self.inner.read().unwrap();
+        // Without a query every account matches equally, leaving DID order.
+        let rank = |a: &ActorAccount| match query.as_deref() {
+            Some(q) => search_rank(a, q),
+            None => Some(0),
+        };
+        let key = |a: &ActorAccount| (rank(a), a.did.clone());
+        let after = cursor.map(|cursor| {
+            let rank = inner.accounts.get(cursor).and_then(rank).unwrap_or(0);
+            (Some(rank), cursor.to_string())
+        });
+        let mut matches: Vec<_> = inner
+            .accounts
+            .values()
+            .filter(|a| rank(a).is_some())
+            .filter(|a| after.as_ref().is_none_or(|after| key(a) > *after))
+            .filter(|a| filter.status.as_ref().is_none_or(|s| compute_status(a) == *s))
+            .filter(|a| {
+                filter.email_confirmed.is_none_or(|c| a.email_confirmed_at.is_some() == c)
+            })
+            .collect();
+        matches.sort_by_key(|a| key(a));
+        Ok(matches.into_iter().take(limit).map(with_status).collect())
+    }
+
+    async fn set_takedown(&self, did: &str, takedown_ref: Option<&str>) -> PdsResult<()> {
+        if let Some(account) = self.inner.write().unwrap().account_mut(did) {
+            account.takedown_ref = takedown_ref.map(str::to_string);
+        }
+        Ok(())
+    }
+
+    async fn set_blob_takedown(
+        &self,
+        did: &str,
+        cid: &str,
+        takedown_ref: Option<&str>,
+    ) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        let key = (did.to_string(), cid.to_string());
+        match takedown_ref {
+            Some(takedown_ref) => {
+                if !inner.accounts.contains_key(did) {
+                    return Err(PdsError::AccountNotFound);
+                }
+                inner.blob_takedowns.insert(key, takedown_ref.to_string());
+            }
+            None => {
+                inner.blob_takedowns.remove(&key);
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_blob_takedown(&self, did: &str, cid: &str) -> PdsResult<Option<String>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner.blob_takedowns.get(&(did.to_string(), cid.to_string())).cloned())
+    }
+
+    async fn append_plc_op(
+        &self,
+        did: &str,
+        cid: &str,
+        operation: &serde_json::Value,
+    ) -> PdsResult<()> {
+        let entry = PlcOpLogEntry {
+            did: did.to_string(),
+            cid: cid.to_string(),
+            operation: operation.clone(),
+            created_at: Utc::now(),
+        };
+        let mut inner = self.inner.write().unwrap();
+        inner.plc_ops.entry(did.to_string()).or_default().push(entry);
+        Ok(())
+    }
+
+    async fn list_plc_ops(&self, did: &str) -> PdsResult<Vec<PlcOpLogEntry>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner.plc_ops.get(did).cloned().unwrap_or_default())
+    }
+
+    async fn create_email_token(&self, purpose: &str, did: &str, token: &str) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        if !inner.accounts.contains_key(did) {
+            return Err(PdsError::AccountNotFound);
+        }
+        inner.email_tokens.insert(
+            (purpose.to_string(), did.to_string()),
+            (token.to_string(), Utc::now()),
+        );
+        Ok(())
+    }
+
+    async fn get_email_token(
+        &self,
+        purpose: &str,
+        did: &str,
+    ) -> PdsResult<Option<(String, DateTime<Utc>)>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner.email_tokens.get(&(purpose.to_string(), did.to_string())).cloned())
+    }
+
+    async fn get_email_token_by_token(
+        &self,
+        purpose: &str,
+        token: &str,
+    ) -> PdsResult<Option<(String, DateTime<Utc>)>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .email_tokens
+            .iter()
+            .find(|((p, _), (t, _))| p == purpose && t == token)
+            .map(|((_, did), (_, requested_at))| (did.clone(), *requested_at)))
+    }
+
+    async fn delete_email_token(&self, purpose: &str, did: &str) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        inner.email_tokens.remove(&(purpose.to_string(), did.to_string()));
+        Ok(())
+    }
+
+    async fn delete_expired_email_tokens(
+        &self,
+        now: DateTime<Utc>,
+        config: &EmailConfig,
+    ) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        let before = inner.email_tokens.len();
+        inner.email_tokens.retain(|(purpose, _), (_, requested_at)| {
+            !EMAIL_TOKEN_PURPOSES.contains(&purpose.as_str())
+                || config.token_expires_at(purpose, *requested_at) >= now
+        });
+        Ok((before - inner.email_tokens.len()) as u64)
+    }
+
+    async fn confirm_email(&self, did: &str) -> PdsResult<()> {
+        if let Some(account) = self.inner.write().unwrap().account_mut(did) {
+            account.email_confirmed_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    async fn update_email(&self, did: &str, email: &str) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        if inner.email_taken(email, did) {
+            return Err(PdsError::Storage(format!("email {email} is already in use")));
+        }
+        if let Some(account) = inner.account_mut(did) {
+            account.email = Some(email.to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use dallaspds_core::{EventStore, PdsResult, PersistedEvent};
+
+#[derive(Default)]
+struct Inner {
+    /// seq -> (event, when it was appended)
+    events: BTreeMap<i64, (PersistedEvent, DateTime<Utc>)>,
+    /// Highest seq ever assigned; like SQLite's AUTOINCREMENT it survives
+    /// pruning, so sequence numbers are never reused.
+    last_seq: i64,
+}
+
+/// An `EventStore` keeping the firehose backlog in memory. Clones share the
+/// same events.
+#[derive(Clone, Default)]
+pub struct MemEventStore {
+    inner: Arc<RwLock<Inner>>,
+}
+
+impl MemEventStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl EventStore for MemEventStore {
+    async fn append_event(&self, event_type: &str, did: &str, payload: &[u8]) -> PdsResult<i64> {
+        let mut inner = self.inner.write().unwrap();
+        inner.last_seq += 1;
+        let seq = inner.last_seq;
+        let event = PersistedEvent {
+            seq,
+            event_type: event_type.to_string(),
+            did: did.to_string(),
+            payload: payload.to_vec(),
+        };
+        inner.events.insert(seq, (event, Utc::now()));
+        Ok(seq)
+    }
+
+    async fn get_events_after(
+        &self,
+        after_seq: i64,
+        limit: usize,
+    ) -> PdsResult<Vec<PersistedEvent>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .events
+            .range(after_seq.saturating_add(1)..)
+            .take(limit)
+            .map(|(_, (event, _))| event.clone())
+            .collect())
+    }
+
+    async fn get_max_seq(&self) -> PdsResult<i64> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner.events.keys().next_back().copied().unwrap_or(0))
+    }
+
+    async fn get_last_event_for_did(
+        &self,
+        did: &str,
+        event_type: &str,
+    ) -> PdsResult<Option<PersistedEvent>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .events
+            .values()
+            .rev()
+            .map(|(event, _)| event)
+            .find(|event| event.did == did && event.event_type == event_type)
+            .cloned())
+    }
+
+    async fn get_first_seq_since(&self, since: DateTime<Utc>) -> PdsResult<Option<i64>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .events
+            .iter()
+            .find(|(_, (_, created_at))| *created_at >= since)
+            .map(|(seq, _)| *seq))
+    }
+
+    async fn prune_events_before(&self, before_seq: i64) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        let kept = inner.events.split_off(&before_seq);
+        let removed = inner.events.len() as u64;
+        inner.events = kept;
+        Ok(removed)
+    }
+}
@@ -0,0 +1,14 @@
+//! In-memory implementations of the storage traits.
+//!
+//! Nothing is persisted: every store starts empty and its contents are lost
+//! when it is dropped. They suit tests and throwaway demo instances, and
+//! mirror the SQLite stores' semantics (case-insensitive handles, keyset
+//! pagination by DID, never-reused event sequence numbers).
+
+pub mod account;
+pub mod event;
+pub mod repo;
+
+pub use account::MemAccountStore;
+pub use event::MemEventStore;
+pub use repo::MemRepoStore;
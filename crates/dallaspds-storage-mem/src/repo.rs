@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use dallaspds_core::{PdsError, PdsResult, RepoRoot, RepoStore};
+
+/// Repo roots by DID. They belong to the account store; a repo store only
+/// holds a handle to them so `commit_blocks` can move a root.
+pub(crate) type RepoRoots = Arc<RwLock<HashMap<String, RepoRoot>>>;
+
+#[derive(Default)]
+struct Inner {
+    /// did -> CID bytes -> block
+    blocks: HashMap<String, HashMap<Vec<u8>, Vec<u8>>>,
+    /// did -> blob CID -> rev that first referenced it
+    blob_refs: HashMap<String, BTreeMap<String, String>>,
+}
+
+/// A `RepoStore` holding every block in memory. Clones share the same data.
+///
+/// A store from [`MemRepoStore::new`] keeps the roots it commits to itself;
+/// use [`MemAccountStore::repo_store`](crate::MemAccountStore::repo_store)
+/// for one whose commits the account store can see.
+#[derive(Clone, Default)]
+pub struct MemRepoStore {
+    inner: Arc<RwLock<Inner>>,
+    repo_roots: RepoRoots,
+}
+
+impl MemRepoStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn with_roots(repo_roots: RepoRoots) -> Self {
+        Self {
+            inner: Arc::default(),
+            repo_roots,
+        }
+    }
+}
+
+#[async_trait]
+impl RepoStore for MemRepoStore {
+    async fn get_block(&self, did: &str, cid: &[u8]) -> PdsResult<Option<Vec<u8>>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner.blocks.get(did).and_then(|blocks| blocks.get(cid)).cloned())
+    }
+
+    async fn put_block(&self, did: &str, cid: &[u8], block: &[u8]) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        let blocks = inner.blocks.entry(did.to_string()).or_default();
+        blocks.entry(cid.to_vec()).or_insert_with(|| block.to_vec());
+        Ok(())
+    }
+
+    async fn put_blocks(&self, did: &str, batch: &[(Vec<u8>, Vec<u8>)]) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        let blocks = inner.blocks.entry(did.to_string()).or_default();
+        for (cid, block) in batch {
+            blocks.entry(cid.clone()).or_insert_with(|| block.clone());
+        }
+        Ok(())
+    }
+
+    async fn commit_blocks(
+        &self,
+        did: &str,
+        batch: &[(Vec<u8>, Vec<u8>)],
+        expected_root: &[u8],
+        root: &[u8],
+        rev: &str,
+    ) -> PdsResult<()> {
+        // Hold both locks so readers never see the root without its blocks.
+        let mut inner = self.inner.write().unwrap();
+        let mut repo_roots = self.repo_roots.write().unwrap();
+        let current: &[u8] = repo_roots.get(did).map_or(&[], |root| &root.cid);
+        if current != expected_root {
+            return Err(PdsError::RepoRootChanged);
+        }
+        let blocks = inner.blocks.entry(did.to_string()).or_default();
+        for (cid, block) in batch {
+            blocks.entry(cid.clone()).or_insert_with(|| block.clone());
+        }
+        let root = RepoRoot {
+            did: did.to_string(),
+            cid: root.to_vec(),
+            rev: rev.to_string(),
+            indexed_at: Utc::now(),
+        };
+        repo_roots.insert(did.to_string(), root);
+        Ok(())
+    }
+
+    async fn has_block(&self, did: &str, cid: &[u8]) -> PdsResult<bool> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner.blocks.get(did).is_some_and(|blocks| blocks.contains_key(cid)))
+    }
+
+    async fn get_all_blocks(&self, did: &str) -> PdsResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner
+            .blocks
+            .get(did)
+            .map(|blocks| blocks.iter().map(|(c, b)| (c.clone(), b.clone())).collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_storage_usage(&self, did: &str) -> PdsResult<(u64, u64)> {
+        let inner = self.inner.read().unwrap();
+        Ok(inner.blocks.get(did).map_or((0, 0), |blocks| {
+            let bytes = blocks.values().map(|block| block.len() as u64).sum();
+            (blocks.len() as u64, bytes)
+        }))
+    }
+
+    async fn delete_blocks_for_did(&self, did: &str) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        inner.blob_refs.remove(did);
+        Ok(inner.blocks.remove(did).map_or(0, |blocks| blocks.len() as u64))
+    }
+
+    async fn retain_blocks(&self, did: &str, keep: &HashSet<Vec<u8>>) -> PdsResult<u64> {
+        let mut inner = self.inner.write().unwrap();
+        let Some(blocks) = inner.blocks.get_mut(did) else {
+            return Ok(0);
+        };
+        let before = blocks.len();
+        blocks.retain(|cid, _| keep.contains(cid));
+        Ok((before - blocks.len()) as u64)
+    }
+
+    async fn add_blob_refs(&self, did: &str, cids: &[String], rev: &str) -> PdsResult<()> {
+        let mut inner = self.inner.write().unwrap();
+        let refs = inner.blob_refs.entry(did.to_string()).or_default();
+        for cid in cids {
+            refs.entry(cid.clone()).or_insert_with(|| rev.to_string());
+        }
+        Ok(())
+    }
+
+    async fn list_blobs_since(
+        &self,
+        did: &str,
+        since: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<String>> {
+        let inner = self.inner.read().unwrap();
+        let Some(refs) = inner.blob_refs.get(did) else {
+            return Ok(Vec::new());
+        };
+        Ok(refs
+            .iter()
+            .filter(|(cid, rev)| rev.as_str() > since && cid.as_str() > cursor.unwrap_or(""))
+            .take(limit)
+            .map(|(cid, _)| cid.clone())
+            .collect())
+    }
+}
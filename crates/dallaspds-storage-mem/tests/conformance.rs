@@ -0,0 +1,14 @@
+use dallaspds_storage_mem::{MemAccountStore, MemEventStore, MemRepoStore};
+use dallaspds_test_utils::ConformanceStores;
+
+async fn setup() -> ConformanceStores<MemAccountStore, MemRepoStore, MemEventStore> {
+    let account_store = MemAccountStore::new();
+    ConformanceStores {
+        repo_store: account_store.repo_store(),
+        account_store,
+        event_store: MemEventStore::new(),
+        guard: Box::new(()),
+    }
+}
+
+dallaspds_test_utils::store_conformance_tests!(setup);
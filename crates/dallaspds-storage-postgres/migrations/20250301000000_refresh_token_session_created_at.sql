@@ -0,0 +1,3 @@
+-- Track when each refresh-token chain began so sessions can have a max age.
+-- Existing sessions start their clock at upgrade time.
+ALTER TABLE refresh_token ADD COLUMN session_created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
@@ -0,0 +1,9 @@
+-- Blobs referenced by each repo, with the rev of the commit that first
+-- referenced them, so listBlobs can filter by `since`.
+CREATE TABLE IF NOT EXISTS blob_ref (
+    did TEXT NOT NULL,
+    cid TEXT NOT NULL,
+    rev TEXT NOT NULL,
+    PRIMARY KEY (did, cid)
+);
+CREATE INDEX IF NOT EXISTS idx_blob_ref_did_rev ON blob_ref(did, rev);
@@ -0,0 +1,2 @@
+-- Look up an account's latest event of a given type (startup reconciliation).
+CREATE INDEX IF NOT EXISTS idx_firehose_did_type ON firehose_event(did, event_type, seq);
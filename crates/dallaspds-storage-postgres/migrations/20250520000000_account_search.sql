@@ -0,0 +1,6 @@
+-- Trigram indexes so search_accounts' ILIKE '%q%' and similarity ranking
+-- over handles and emails don't scan the whole table.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_actor_handle_trgm ON actor USING GIN (handle gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_account_email_trgm ON account USING GIN (email gin_trgm_ops);
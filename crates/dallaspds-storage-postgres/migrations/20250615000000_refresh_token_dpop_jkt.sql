@@ -0,0 +1,2 @@
+-- Bind OAuth refresh tokens to the DPoP key they were issued to.
+ALTER TABLE refresh_token ADD COLUMN dpop_jkt TEXT;
@@ -0,0 +1,32 @@
+-- 20250415000000_lowercase_handles left handles that collide once lowercased
+-- as they were, where lookups (now lowercased) can't reach them. Resolve each
+-- collision deterministically: the account already holding the lowercase
+-- form keeps it, else the earliest created one. The others lose their handle
+-- until they pick a new one, and are recorded here; the server logs every
+-- row at startup until an operator deletes it.
+CREATE TABLE IF NOT EXISTS handle_collision (
+    did TEXT PRIMARY KEY NOT NULL,
+    handle TEXT NOT NULL,
+    kept_by TEXT NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO handle_collision (did, handle, kept_by)
+SELECT did, handle, kept_by FROM (
+    SELECT a.did, a.handle, (
+        SELECT w.did FROM actor w
+        WHERE lower(w.handle) = lower(a.handle)
+        ORDER BY w.handle <> lower(w.handle), w.created_at, w.did
+        LIMIT 1
+    ) AS kept_by
+    FROM actor a
+    WHERE a.handle IS NOT NULL
+      AND EXISTS (
+          SELECT 1 FROM actor other
+          WHERE other.did <> a.did AND lower(other.handle) = lower(a.handle)
+      )
+) collisions
+WHERE did <> kept_by;
+
+UPDATE actor SET handle = NULL WHERE did IN (SELECT did FROM handle_collision);
+UPDATE actor SET handle = lower(handle) WHERE handle <> lower(handle);
@@ -2,9 +2,10 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Row};
 
+use dallaspds_core::config::{DatabaseConfig, EMAIL_TOKEN_PURPOSES, EmailConfig};
 use dallaspds_core::{
-    AccountStatus, AccountStore, ActorAccount, CreateAccountInput, InviteCode, InviteCodeUse,
-    PdsError, PdsResult, RefreshTokenRecord, RepoRoot,
+    AccountSearchFilter, AccountStatus, AccountStore, ActorAccount, CreateAccountInput, InviteCode, InviteCodeUse,
+    OAuthRequest, PdsError, PdsResult, PlcOpLogEntry, RefreshTokenRecord, RepoListEntry, RepoRoot,
 };
 
 #[derive(Clone)]
@@ -26,6 +27,19 @@ fn compute_status(
     }
 }
 
+/// SQL condition matching accounts whose computed status is `status`.
+fn status_condition(status: &AccountStatus) -> &'static str {
+    match status {
+        AccountStatus::Takendown => " AND a.takedown_ref IS NOT NULL",
+        AccountStatus::Deactivated => {
+            " AND a.takedown_ref IS NULL AND a.deactivated_at IS NOT NULL"
+        }
+        AccountStatus::Active => " AND a.takedown_ref IS NULL AND a.deactivated_at IS NULL",
+        // Never produced by `compute_status`.
+        AccountStatus::Suspended | AccountStatus::Deleted => " AND 1 = 0",
+    }
+}
+
 /// Map a sqlx Row (from a joined actor + account query) to an ActorAccount.
 fn row_to_actor_account(row: &sqlx::postgres::PgRow) -> Result<ActorAccount, PdsError> {
     let did: String = row
@@ -46,6 +60,9 @@ fn row_to_actor_account(row: &sqlx::postgres::PgRow) -> Result<ActorAccount, Pds
     let signing_key: Vec<u8> = row
         .try_get("signing_key")
         .map_err(|e| PdsError::Storage(e.to_string()))?;
+    let key_type: String = row
+        .try_get("key_type")
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
     let created_at: DateTime<Utc> = row
         .try_get("created_at")
         .map_err(|e| PdsError::Storage(e.to_string()))?;
@@ -68,6 +85,7 @@ fn row_to_actor_account(row: &sqlx::postgres::PgRow) -> Result<ActorAccount, Pds
         email_confirmed_at,
         password_hash,
         signing_key,
+        key_type,
         created_at,
         status,
         deactivated_at,
@@ -88,23 +106,82 @@ const ACCOUNT_SELECT: &str = r#"
         ac.email,
         ac.email_confirmed_at,
         ac.password_hash,
-        ac.signing_key
+        ac.signing_key,
+        ac.key_type
     FROM actor a
     INNER JOIN account ac ON a.did = ac.did
 "#;
 
+/// SQL fragment joining actors to their repo roots, skipping empty roots.
+const OAUTH_REQUEST_COLUMNS: &str =
+    "request_uri, client_id, redirect_uri, scope, state, code_challenge, did, code, expires_at";
+
+/// Map an `oauth_request` row to an OAuthRequest.
+fn row_to_oauth_request(row: &sqlx::postgres::PgRow) -> Result<OAuthRequest, PdsError> {
+    let get = |column: &str| -> Result<Option<String>, PdsError> {
+        row.try_get(column).map_err(|e| PdsError::Storage(e.to_string()))
+    };
+    let required = |column: &str| -> Result<String, PdsError> {
+        get(column)?.ok_or_else(|| PdsError::Storage(format!("oauth_request.{column} is null")))
+    };
+    Ok(OAuthRequest {
+        request_uri: required("request_uri")?,
+        client_id: required("client_id")?,
+        redirect_uri: required("redirect_uri")?,
+        scope: required("scope")?,
+        state: get("state")?,
+        code_challenge: required("code_challenge")?,
+        did: get("did")?,
+        code: get("code")?,
+        expires_at: row
+            .try_get("expires_at")
+            .map_err(|e| PdsError::Storage(e.to_string()))?,
+    })
+}
+
+const REPO_LIST_SELECT: &str = r#"
+    SELECT a.did, a.takedown_ref, a.deactivated_at, r.cid, r.rev
+    FROM actor a
+    JOIN repo_root r ON r.did = a.did
+    WHERE octet_length(r.cid) > 0
+"#;
+
 impl PostgresAccountStore {
     pub async fn connect(url: &str) -> PdsResult<Self> {
-        let pool = PgPool::connect(url)
-            .await
-            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Self::connect_with(&DatabaseConfig::new(url)).await
+    }
+
+    /// Connect with the pool options from the `[database]` config section.
+    pub async fn connect_with(config: &DatabaseConfig) -> PdsResult<Self> {
+        let pool = crate::pool::connect_pool(config).await?;
 
         sqlx::migrate!("./migrations")
             .run(&pool)
             .await
             .map_err(|e| PdsError::Storage(e.to_string()))?;
 
-        Ok(Self { pool })
+        let store = Self { pool };
+        store.warn_handle_collisions().await?;
+        Ok(store)
+    }
+
+    /// Log the accounts that lost their handle to a case-insensitive
+    /// collision when handles were lowercased, until an operator clears them
+    /// from `handle_collision`.
+    async fn warn_handle_collisions(&self) -> PdsResult<()> {
+        let rows: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT did, handle, kept_by FROM handle_collision ORDER BY did")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+        for (did, handle, kept_by) in rows {
+            tracing::warn!(
+                %did, %handle, %kept_by,
+                "account has no handle: it collided with another account's once lowercased; \
+                 delete it from handle_collision once resolved"
+            );
+        }
+        Ok(())
     }
 
     /// Helper: fetch an ActorAccount with a WHERE clause appended to the base SELECT.
@@ -139,19 +216,21 @@ impl AccountStore for PostgresAccountStore {
         // Insert into actor table
         sqlx::query("INSERT INTO actor (did, handle) VALUES ($1, $2)")
             .bind(&input.did)
-            .bind(&input.handle)
+            .bind(input.handle.to_ascii_lowercase())
             .execute(&mut *tx)
             .await
             .map_err(|e| PdsError::Storage(e.to_string()))?;
 
         // Insert into account table
         sqlx::query(
-            "INSERT INTO account (did, email, password_hash, signing_key) VALUES ($1, $2, $3, $4)",
+            "INSERT INTO account (did, email, password_hash, signing_key, key_type) \
+             VALUES ($1, $2, $3, $4, $5)",
         )
         .bind(&input.did)
         .bind(&input.email)
         .bind(&input.password_hash)
         .bind(&input.signing_key)
+        .bind(&input.key_type)
         .execute(&mut *tx)
         .await
         .map_err(|e| PdsError::Storage(e.to_string()))?;
@@ -183,7 +262,7 @@ impl AccountStore for PostgresAccountStore {
     }
 
     async fn get_account_by_handle(&self, handle: &str) -> PdsResult<Option<ActorAccount>> {
-        self.get_account_where("a.handle = $1", handle).await
+        self.get_account_where("a.handle = $1", &handle.to_ascii_lowercase()).await
     }
 
     async fn get_account_by_email(&self, email: &str) -> PdsResult<Option<ActorAccount>> {
@@ -192,7 +271,7 @@ impl AccountStore for PostgresAccountStore {
 
     async fn update_handle(&self, did: &str, handle: &str) -> PdsResult<()> {
         sqlx::query("UPDATE actor SET handle = $1 WHERE did = $2")
-            .bind(handle)
+            .bind(handle.to_ascii_lowercase())
             .bind(did)
             .execute(&self.pool)
             .await
@@ -228,6 +307,46 @@ impl AccountStore for PostgresAccountStore {
         Ok(())
     }
 
+    async fn set_delete_after(
+        &self,
+        did: &str,
+        delete_after: Option<DateTime<Utc>>,
+    ) -> PdsResult<()> {
+        sqlx::query("UPDATE actor SET delete_after = $1 WHERE did = $2")
+            .bind(delete_after)
+            .bind(did)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_accounts_due_for_deletion(&self, now: DateTime<Utc>) -> PdsResult<Vec<String>> {
+        sqlx::query_scalar("SELECT did FROM actor WHERE delete_after <= $1 ORDER BY did")
+            .bind(now)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
+    async fn claim_scheduled_deletion(
+        &self,
+        did: &str,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> PdsResult<bool> {
+        let result = sqlx::query(
+            "UPDATE actor SET delete_after = $3 WHERE did = $1 AND delete_after <= $2",
+        )
+        .bind(did)
+        .bind(now)
+        .bind(lease_until)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected() == 1)
+    }
+
     async fn delete_account(&self, did: &str) -> PdsResult<()> {
         sqlx::query("DELETE FROM actor WHERE did = $1")
             .bind(did)
@@ -287,13 +406,15 @@ impl AccountStore for PostgresAccountStore {
 
     async fn create_refresh_token(&self, token: &RefreshTokenRecord) -> PdsResult<()> {
         sqlx::query(
-            "INSERT INTO refresh_token (id, did, expires_at, next_id, app_password_name) VALUES ($1, $2, $3, $4, $5)",
+            "INSERT INTO refresh_token (id, did, expires_at, next_id, app_password_name, session_created_at, dpop_jkt) VALUES ($1, $2, $3, $4, $5, $6, $7)",
         )
         .bind(&token.id)
         .bind(&token.did)
         .bind(token.expires_at)
         .bind(&token.next_id)
         .bind(&token.app_password_name)
+        .bind(token.session_created_at)
+        .bind(&token.dpop_jkt)
         .execute(&self.pool)
         .await
         .map_err(|e| PdsError::Storage(e.to_string()))?;
@@ -302,7 +423,7 @@ impl AccountStore for PostgresAccountStore {
 
     async fn get_refresh_token(&self, id: &str) -> PdsResult<Option<RefreshTokenRecord>> {
         let row = sqlx::query(
-            "SELECT id, did, expires_at, next_id, app_password_name FROM refresh_token WHERE id = $1",
+            "SELECT id, did, expires_at, next_id, app_password_name, session_created_at, dpop_jkt FROM refresh_token WHERE id = $1",
         )
         .bind(id)
         .fetch_optional(&self.pool)
@@ -326,6 +447,12 @@ impl AccountStore for PostgresAccountStore {
                 let app_password_name: Option<String> = r
                     .try_get("app_password_name")
                     .map_err(|e| PdsError::Storage(e.to_string()))?;
+                let session_created_at: DateTime<Utc> = r
+                    .try_get("session_created_at")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
+                let dpop_jkt: Option<String> = r
+                    .try_get("dpop_jkt")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
 
                 Ok(Some(RefreshTokenRecord {
                     id,
@@ -333,6 +460,8 @@ impl AccountStore for PostgresAccountStore {
                     expires_at,
                     next_id,
                     app_password_name,
+                    session_created_at,
+                    dpop_jkt,
                 }))
             }
             None => Ok(None),
@@ -348,6 +477,24 @@ impl AccountStore for PostgresAccountStore {
         Ok(())
     }
 
+    async fn delete_refresh_token_chain(&self, id: &str) -> PdsResult<u64> {
+        // Walk `next_id` links both ways from the presented token.
+        let result = sqlx::query(
+            "WITH RECURSIVE chain(id, next_id) AS ( \
+                 SELECT id, next_id FROM refresh_token WHERE id = $1 \
+                 UNION \
+                 SELECT r.id, r.next_id FROM refresh_token r \
+                 JOIN chain c ON r.next_id = c.id OR r.id = c.next_id \
+             ) \
+             DELETE FROM refresh_token WHERE id IN (SELECT id FROM chain)",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
     async fn delete_refresh_tokens_for_did(&self, did: &str) -> PdsResult<u64> {
         let result = sqlx::query("DELETE FROM refresh_token WHERE did = $1")
             .bind(did)
@@ -357,6 +504,30 @@ impl AccountStore for PostgresAccountStore {
         Ok(result.rows_affected())
     }
 
+    async fn set_refresh_token_next_id(&self, id: &str, next_id: &str) -> PdsResult<bool> {
+        let result = sqlx::query(
+            "UPDATE refresh_token SET next_id = $1 WHERE id = $2 AND next_id IS NULL",
+        )
+        .bind(next_id)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_expired_refresh_tokens(
+        &self,
+        now: DateTime<Utc>,
+    ) -> PdsResult<u64> {
+        let result = sqlx::query("DELETE FROM refresh_token WHERE expires_at < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
     async fn list_accounts(
         &self,
         cursor: Option<&str>,
@@ -383,7 +554,125 @@ impl AccountStore for PostgresAccountStore {
         rows.iter().map(row_to_actor_account).collect()
     }
 
+    async fn list_repos(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<RepoListEntry>> {
+        let rows = if let Some(cursor) = cursor {
+            let sql = format!("{REPO_LIST_SELECT} AND a.did > $1 ORDER BY a.did ASC LIMIT $2");
+            sqlx::query(&sql)
+                .bind(cursor)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?
+        } else {
+            let sql = format!("{REPO_LIST_SELECT} ORDER BY a.did ASC LIMIT $1");
+            sqlx::query(&sql)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?
+        };
+
+        rows.iter()
+            .map(|row| {
+                let deactivated_at: Option<DateTime<Utc>> = row
+                    .try_get("deactivated_at")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
+                let takedown_ref: Option<String> = row
+                    .try_get("takedown_ref")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
+                Ok(RepoListEntry {
+                    did: row
+                        .try_get("did")
+                        .map_err(|e| PdsError::Storage(e.to_string()))?,
+                    cid: row
+                        .try_get("cid")
+                        .map_err(|e| PdsError::Storage(e.to_string()))?,
+                    rev: row
+                        .try_get("rev")
+                        .map_err(|e| PdsError::Storage(e.to_string()))?,
+                    status: compute_status(&deactivated_at, &takedown_ref),
+                })
+            })
+            .collect()
+    }
+
     // Invite code management
+    async fn create_oauth_request(&self, request: &OAuthRequest) -> PdsResult<()> {
+        sqlx::query(
+            "INSERT INTO oauth_request \
+             (request_uri, client_id, redirect_uri, scope, state, code_challenge, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(&request.request_uri)
+        .bind(&request.client_id)
+        .bind(&request.redirect_uri)
+        .bind(&request.scope)
+        .bind(&request.state)
+        .bind(&request.code_challenge)
+        .bind(request.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get_oauth_request(&self, request_uri: &str) -> PdsResult<Option<OAuthRequest>> {
+        let sql =
+            format!("SELECT {OAUTH_REQUEST_COLUMNS} FROM oauth_request WHERE request_uri = $1");
+        let row = sqlx::query(&sql)
+            .bind(request_uri)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        row.as_ref().map(row_to_oauth_request).transpose()
+    }
+
+    async fn authorize_oauth_request(
+        &self,
+        request_uri: &str,
+        did: &str,
+        code: &str,
+        expires_at: DateTime<Utc>,
+    ) -> PdsResult<bool> {
+        let result = sqlx::query(
+            "UPDATE oauth_request SET did = $1, code = $2, expires_at = $3 \
+             WHERE request_uri = $4 AND code IS NULL",
+        )
+        .bind(did)
+        .bind(code)
+        .bind(expires_at)
+        .bind(request_uri)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn consume_oauth_code(&self, code: &str) -> PdsResult<Option<OAuthRequest>> {
+        let sql = format!(
+            "DELETE FROM oauth_request WHERE code = $1 RETURNING {OAUTH_REQUEST_COLUMNS}"
+        );
+        let row = sqlx::query(&sql)
+            .bind(code)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        row.as_ref().map(row_to_oauth_request).transpose()
+    }
+
+    async fn delete_expired_oauth_requests(&self, now: DateTime<Utc>) -> PdsResult<u64> {
+        let result = sqlx::query("DELETE FROM oauth_request WHERE expires_at < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
     async fn create_invite_code(&self, code: &str, available_uses: i32, for_account: &str, created_by: &str) -> PdsResult<InviteCode> {
         sqlx::query("INSERT INTO invite_code (code, available_uses, for_account, created_by) VALUES ($1, $2, $3, $4)")
             .bind(code)
@@ -518,50 +807,71 @@ impl AccountStore for PostgresAccountStore {
     }
 
     // Account search and moderation
-    async fn search_accounts(&self, query: Option<&str>, cursor: Option<&str>, limit: usize) -> PdsResult<Vec<ActorAccount>> {
-        let rows = match (query, cursor) {
-            (Some(q), Some(cursor)) => {
-                let pattern = format!("%{}%", q);
-                let sql = format!("{ACCOUNT_SELECT} WHERE (a.handle ILIKE $1 OR ac.email ILIKE $2) AND a.did > $3 ORDER BY a.did ASC LIMIT $4");
-                sqlx::query(&sql)
-                    .bind(&pattern)
-                    .bind(&pattern)
-                    .bind(cursor)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
-            }
-            (Some(q), None) => {
-                let pattern = format!("%{}%", q);
-                let sql = format!("{ACCOUNT_SELECT} WHERE (a.handle ILIKE $1 OR ac.email ILIKE $2) ORDER BY a.did ASC LIMIT $3");
-                sqlx::query(&sql)
-                    .bind(&pattern)
-                    .bind(&pattern)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
+    async fn search_accounts(
+        &self,
+        filter: &AccountSearchFilter,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<ActorAccount>> {
+        let mut sql = sqlx::QueryBuilder::new("");
+        if let Some(q) = &filter.query {
+            // ILIKE narrows to substring matches via the trigram indexes.
+            // Handle hits rank above email-only hits, each ordered by
+            // trigram similarity (at most 1).
+            let pattern = format!("%{q}%");
+            sql.push("WITH hits AS (SELECT a.did, CASE WHEN a.handle ILIKE ")
+                .push_bind(pattern.clone())
+                .push(" THEN 1 + similarity(a.handle, ")
+                .push_bind(q.clone())
+                .push(") ELSE similarity(COALESCE(ac.email, ''), ")
+                .push_bind(q.clone())
+                .push(
+                    ") END AS rank FROM actor a INNER JOIN account ac ON a.did = ac.did \
+                     WHERE a.handle ILIKE ",
+                )
+                .push_bind(pattern.clone())
+            .push(" OR ac.email ILIKE ")
+            .push_bind(pattern)
+            .push(") ")
+            .push(ACCOUNT_SELECT)
+            .push(" INNER JOIN hits h ON h.did = a.did WHERE 1 = 1");
+        } else {
+            sql.push(ACCOUNT_SELECT).push(" WHERE 1 = 1");
+        }
+        if let Some(status) = &filter.status {
+            sql.push(status_condition(status));
+        }
+        if let Some(confirmed) = filter.email_confirmed {
+            sql.push(if confirmed {
+                " AND ac.email_confirmed_at IS NOT NULL"
+            } else {
+                " AND ac.email_confirmed_at IS NULL"
+            });
+        }
+        match (&filter.query, cursor) {
+            // Resume after the cursor account's position in the ranking.
+            (Some(_), Some(cursor)) => {
+                sql.push(" AND (-h.rank, a.did) > (SELECT -rank, did FROM hits WHERE did = ")
+                    .push_bind(cursor)
+                    .push(")");
             }
             (None, Some(cursor)) => {
-                let sql = format!("{ACCOUNT_SELECT} WHERE a.did > $1 ORDER BY a.did ASC LIMIT $2");
-                sqlx::query(&sql)
-                    .bind(cursor)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
+                sql.push(" AND a.did > ").push_bind(cursor);
             }
-            (None, None) => {
-                let sql = format!("{ACCOUNT_SELECT} ORDER BY a.did ASC LIMIT $1");
-                sqlx::query(&sql)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
-            }
-        };
+            (_, None) => {}
+        }
+        if filter.query.is_some() {
+            sql.push(" ORDER BY h.rank DESC, a.did ASC LIMIT ");
+        } else {
+            sql.push(" ORDER BY a.did ASC LIMIT ");
+        }
+        sql.push_bind(limit as i64);
 
+        let rows = sql
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
         rows.iter().map(row_to_actor_account).collect()
     }
 
@@ -575,6 +885,81 @@ impl AccountStore for PostgresAccountStore {
         Ok(())
     }
 
+    async fn set_blob_takedown(
+        &self,
+        did: &str,
+        cid: &str,
+        takedown_ref: Option<&str>,
+    ) -> PdsResult<()> {
+        let query = match takedown_ref {
+            Some(takedown_ref) => sqlx::query(
+                "INSERT INTO blob_takedown (did, cid, takedown_ref) VALUES ($1, $2, $3) \
+                 ON CONFLICT (did, cid) DO UPDATE SET takedown_ref = EXCLUDED.takedown_ref",
+            )
+            .bind(did)
+            .bind(cid)
+            .bind(takedown_ref),
+            None => sqlx::query("DELETE FROM blob_takedown WHERE did = $1 AND cid = $2")
+                .bind(did)
+                .bind(cid),
+        };
+        query
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get_blob_takedown(&self, did: &str, cid: &str) -> PdsResult<Option<String>> {
+        sqlx::query_scalar("SELECT takedown_ref FROM blob_takedown WHERE did = $1 AND cid = $2")
+            .bind(did)
+            .bind(cid)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
+    // PLC operation log
+    async fn append_plc_op(
+        &self,
+        did: &str,
+        cid: &str,
+        operation: &serde_json::Value,
+    ) -> PdsResult<()> {
+        sqlx::query("INSERT INTO plc_op_log (did, cid, operation) VALUES ($1, $2, $3)")
+            .bind(did)
+            .bind(cid)
+            .bind(operation.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_plc_ops(&self, did: &str) -> PdsResult<Vec<PlcOpLogEntry>> {
+        let rows = sqlx::query(
+            "SELECT did, cid, operation, created_at FROM plc_op_log WHERE did = $1 ORDER BY id",
+        )
+        .bind(did)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                let operation: String =
+                    row.try_get("operation").map_err(|e| PdsError::Storage(e.to_string()))?;
+                Ok(PlcOpLogEntry {
+                    did: row.try_get("did").map_err(|e| PdsError::Storage(e.to_string()))?,
+                    cid: row.try_get("cid").map_err(|e| PdsError::Storage(e.to_string()))?,
+                    operation: serde_json::from_str(&operation)
+                        .map_err(|e| PdsError::Storage(format!("invalid PLC operation: {e}")))?,
+                    created_at: row.try_get("created_at").map_err(|e| PdsError::Storage(e.to_string()))?,
+                })
+            })
+            .collect()
+    }
+
     // Email token management
     async fn create_email_token(&self, purpose: &str, did: &str, token: &str) -> PdsResult<()> {
         sqlx::query(
@@ -644,6 +1029,25 @@ impl AccountStore for PostgresAccountStore {
         Ok(())
     }
 
+    async fn delete_expired_email_tokens(
+        &self,
+        now: DateTime<Utc>,
+        config: &EmailConfig,
+    ) -> PdsResult<u64> {
+        let mut deleted = 0;
+        for purpose in EMAIL_TOKEN_PURPOSES {
+            deleted +=
+                sqlx::query("DELETE FROM email_token WHERE purpose = $1 AND requested_at < $2")
+                    .bind(purpose)
+                    .bind(now - config.token_lifetime(purpose))
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| PdsError::Storage(e.to_string()))?
+                    .rows_affected();
+        }
+        Ok(deleted)
+    }
+
     async fn confirm_email(&self, did: &str) -> PdsResult<()> {
         sqlx::query("UPDATE account SET email_confirmed_at = NOW() WHERE did = $1")
             .bind(did)
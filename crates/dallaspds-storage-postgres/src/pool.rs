@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
+
+use dallaspds_core::config::DatabaseConfig;
+use dallaspds_core::{PdsError, PdsResult};
+
+/// Open a connection pool for `config.url` with the configured limits.
+pub async fn connect_pool(config: &DatabaseConfig) -> PdsResult<PgPool> {
+    PgPoolOptions::new()
+        .max_connections(config.max_connections.max(1))
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .idle_timeout((config.idle_timeout_secs > 0).then(|| {
+            Duration::from_secs(config.idle_timeout_secs)
+        }))
+        .connect(&config.url)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))
+}
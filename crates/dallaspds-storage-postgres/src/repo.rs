@@ -1,6 +1,9 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use sqlx::{PgPool, Row};
 
+use dallaspds_core::config::DatabaseConfig;
 use dallaspds_core::{PdsError, PdsResult, RepoStore};
 
 #[derive(Clone)]
@@ -10,9 +13,12 @@ pub struct PostgresRepoStore {
 
 impl PostgresRepoStore {
     pub async fn connect(url: &str) -> PdsResult<Self> {
-        let pool = PgPool::connect(url)
-            .await
-            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Self::connect_with(&DatabaseConfig::new(url)).await
+    }
+
+    /// Connect with the pool options from the `[database]` config section.
+    pub async fn connect_with(config: &DatabaseConfig) -> PdsResult<Self> {
+        let pool = crate::pool::connect_pool(config).await?;
 
         sqlx::migrate!("./migrations")
             .run(&pool)
@@ -57,6 +63,76 @@ impl RepoStore for PostgresRepoStore {
         Ok(())
     }
 
+    async fn put_blocks(&self, did: &str, blocks: &[(Vec<u8>, Vec<u8>)]) -> PdsResult<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        for (cid, block) in blocks {
+            sqlx::query(
+                "INSERT INTO repo_block (did, cid, block) VALUES ($1, $2, $3) \
+                 ON CONFLICT (did, cid) DO NOTHING",
+            )
+            .bind(did)
+            .bind(cid)
+            .bind(block)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
+    async fn commit_blocks(
+        &self,
+        did: &str,
+        blocks: &[(Vec<u8>, Vec<u8>)],
+        expected_root: &[u8],
+        root: &[u8],
+        rev: &str,
+    ) -> PdsResult<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        // The conditional update locks the root row, so a concurrent commit
+        // waits and then sees the moved root.
+        let moved = sqlx::query(
+            "INSERT INTO repo_root (did, cid, rev, indexed_at) VALUES ($1, $2, $3, NOW()) \
+             ON CONFLICT (did) DO UPDATE SET cid = $2, rev = $3, indexed_at = NOW() \
+             WHERE repo_root.cid = $4",
+        )
+        .bind(did)
+        .bind(root)
+        .bind(rev)
+        .bind(expected_root)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        if moved.rows_affected() == 0 {
+            return Err(PdsError::RepoRootChanged);
+        }
+        for (cid, block) in blocks {
+            sqlx::query(
+                "INSERT INTO repo_block (did, cid, block) VALUES ($1, $2, $3) \
+                 ON CONFLICT (did, cid) DO NOTHING",
+            )
+            .bind(did)
+            .bind(cid)
+            .bind(block)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
     async fn has_block(&self, did: &str, cid: &[u8]) -> PdsResult<bool> {
         let row = sqlx::query("SELECT 1 FROM repo_block WHERE did = $1 AND cid = $2")
             .bind(did)
@@ -88,12 +164,112 @@ impl RepoStore for PostgresRepoStore {
         Ok(blocks)
     }
 
+    async fn get_storage_usage(&self, did: &str) -> PdsResult<(u64, u64)> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS blocks, COALESCE(SUM(LENGTH(block)), 0)::BIGINT AS bytes \
+             FROM repo_block WHERE did = $1",
+        )
+        .bind(did)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        let blocks: i64 = row
+            .try_get("blocks")
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        let bytes: i64 = row
+            .try_get("bytes")
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok((blocks as u64, bytes as u64))
+    }
+
     async fn delete_blocks_for_did(&self, did: &str) -> PdsResult<u64> {
         let result = sqlx::query("DELETE FROM repo_block WHERE did = $1")
             .bind(did)
             .execute(&self.pool)
             .await
             .map_err(|e| PdsError::Storage(e.to_string()))?;
+        sqlx::query("DELETE FROM blob_ref WHERE did = $1")
+            .bind(did)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
         Ok(result.rows_affected())
     }
+
+    async fn retain_blocks(&self, did: &str, keep: &HashSet<Vec<u8>>) -> PdsResult<u64> {
+        let rows = sqlx::query("SELECT cid FROM repo_block WHERE did = $1")
+            .bind(did)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        let mut removed = 0;
+        for row in &rows {
+            let cid: Vec<u8> = row
+                .try_get("cid")
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+            if keep.contains(&cid) {
+                continue;
+            }
+            removed += sqlx::query("DELETE FROM repo_block WHERE did = $1 AND cid = $2")
+                .bind(did)
+                .bind(&cid)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?
+                .rows_affected();
+        }
+        tx.commit()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(removed)
+    }
+
+    async fn add_blob_refs(&self, did: &str, cids: &[String], rev: &str) -> PdsResult<()> {
+        for cid in cids {
+            sqlx::query(
+                "INSERT INTO blob_ref (did, cid, rev) VALUES ($1, $2, $3) \
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(did)
+            .bind(cid)
+            .bind(rev)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn list_blobs_since(
+        &self,
+        did: &str,
+        since: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT cid FROM blob_ref WHERE did = $1 AND rev > $2 AND cid > $3 \
+             ORDER BY cid LIMIT $4",
+        )
+        .bind(did)
+        .bind(since)
+        .bind(cursor.unwrap_or(""))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                row.try_get("cid")
+                    .map_err(|e| PdsError::Storage(e.to_string()))
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,55 @@
+//! Needs a PostgreSQL server: `DATABASE_URL=postgres://... cargo test -p
+//! dallaspds-storage-postgres -- --ignored`. Each test migrates a schema of
+//! its own, which is left behind.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dallaspds_storage_postgres::account::PostgresAccountStore;
+use dallaspds_storage_postgres::event::PostgresEventStore;
+use dallaspds_storage_postgres::repo::PostgresRepoStore;
+use dallaspds_test_utils::ConformanceStores;
+
+async fn setup() -> ConformanceStores<PostgresAccountStore, PostgresRepoStore, PostgresEventStore> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let base = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let schema = format!(
+        "conformance_{}_{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    );
+    let pool = sqlx::PgPool::connect(&base).await.unwrap();
+    // pg_trgm is per database, so install it once where every test schema
+    // can see it; the lock keeps parallel tests from racing to create it.
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("SELECT pg_advisory_xact_lock(7253)")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    sqlx::query("CREATE EXTENSION IF NOT EXISTS pg_trgm SCHEMA public")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    sqlx::query(&format!("DROP SCHEMA IF EXISTS {schema} CASCADE"))
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {schema}"))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let separator = if base.contains('?') { '&' } else { '?' };
+    let db_url = format!("{base}{separator}options=-c%20search_path%3D{schema}%2Cpublic");
+    ConformanceStores {
+        account_store: PostgresAccountStore::connect(&db_url).await.unwrap(),
+        repo_store: PostgresRepoStore::connect(&db_url).await.unwrap(),
+        event_store: PostgresEventStore::connect(&db_url).await.unwrap(),
+        guard: Box::new(()),
+    }
+}
+
+dallaspds_test_utils::store_conformance_tests!(
+    #[ignore = "needs a PostgreSQL server at DATABASE_URL"]
+    setup
+);
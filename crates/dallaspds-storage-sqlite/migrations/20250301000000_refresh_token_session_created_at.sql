@@ -0,0 +1,5 @@
+-- Track when each refresh-token chain began so sessions can have a max age.
+ALTER TABLE refresh_token ADD COLUMN session_created_at TEXT;
+
+-- Existing sessions start their clock at upgrade time.
+UPDATE refresh_token SET session_created_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
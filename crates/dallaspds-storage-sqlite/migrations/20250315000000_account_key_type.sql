@@ -0,0 +1,3 @@
+-- Record each account's signing-key curve so keys are reloaded correctly.
+-- Every key created before this column existed is P-256.
+ALTER TABLE account ADD COLUMN key_type TEXT NOT NULL DEFAULT 'p256';
@@ -0,0 +1,9 @@
+-- Handles are case-insensitive, so they are now stored lowercased.
+-- Handles whose lowercased form collides with another account's are left
+-- as-is for an operator to resolve by hand.
+UPDATE actor SET handle = lower(handle)
+WHERE handle <> lower(handle)
+  AND NOT EXISTS (
+      SELECT 1 FROM actor other
+      WHERE other.did <> actor.did AND lower(other.handle) = lower(actor.handle)
+  );
@@ -0,0 +1,14 @@
+-- Pushed OAuth authorization requests (PAR). Once the user approves, the
+-- row holds the issued authorization code until it is exchanged.
+CREATE TABLE IF NOT EXISTS oauth_request (
+    request_uri TEXT PRIMARY KEY,
+    client_id TEXT NOT NULL,
+    redirect_uri TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    state TEXT,
+    code_challenge TEXT NOT NULL,
+    did TEXT REFERENCES actor(did) ON DELETE CASCADE,
+    code TEXT UNIQUE,
+    expires_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_oauth_request_expires_at ON oauth_request(expires_at);
@@ -0,0 +1,31 @@
+-- Full-text index over account handles and emails for search_accounts.
+-- The trigram tokenizer matches any substring of three or more characters,
+-- case-insensitively; triggers keep it in step with actor and account.
+CREATE VIRTUAL TABLE IF NOT EXISTS account_search USING fts5(
+    did UNINDEXED,
+    handle,
+    email,
+    tokenize = 'trigram'
+);
+
+INSERT INTO account_search (did, handle, email)
+SELECT a.did, a.handle, ac.email
+FROM actor a
+INNER JOIN account ac ON a.did = ac.did;
+
+CREATE TRIGGER IF NOT EXISTS account_search_insert AFTER INSERT ON account BEGIN
+    INSERT INTO account_search (did, handle, email)
+    SELECT NEW.did, handle, NEW.email FROM actor WHERE did = NEW.did;
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_search_email AFTER UPDATE OF email ON account BEGIN
+    UPDATE account_search SET email = NEW.email WHERE did = NEW.did;
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_search_handle AFTER UPDATE OF handle ON actor BEGIN
+    UPDATE account_search SET handle = NEW.handle WHERE did = NEW.did;
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_search_delete AFTER DELETE ON actor BEGIN
+    DELETE FROM account_search WHERE did = OLD.did;
+END;
@@ -0,0 +1,8 @@
+-- Moderation takedowns of individual blobs, applied independently of the
+-- account's own takedown status.
+CREATE TABLE IF NOT EXISTS blob_takedown (
+    did TEXT NOT NULL REFERENCES actor(did) ON DELETE CASCADE,
+    cid TEXT NOT NULL,
+    takedown_ref TEXT NOT NULL,
+    PRIMARY KEY (did, cid)
+);
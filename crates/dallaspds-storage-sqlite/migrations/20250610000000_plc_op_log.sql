@@ -0,0 +1,12 @@
+-- PLC operations this PDS signed for each DID, in submission order, so the
+-- DID's history can be reconstructed locally. Deliberately not tied to the
+-- actor row: the genesis operation is logged before the account exists.
+CREATE TABLE IF NOT EXISTS plc_op_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    did TEXT NOT NULL,
+    cid TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_plc_op_log_did ON plc_op_log(did, id);
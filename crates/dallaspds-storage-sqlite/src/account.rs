@@ -2,9 +2,10 @@ use async_trait::async_trait;
 use chrono::{NaiveDateTime, TimeZone, Utc};
 use sqlx::{Row, SqlitePool};
 
+use dallaspds_core::config::{DatabaseConfig, EMAIL_TOKEN_PURPOSES, EmailConfig};
 use dallaspds_core::{
-    AccountStatus, AccountStore, ActorAccount, CreateAccountInput, InviteCode, InviteCodeUse,
-    PdsError, PdsResult, RefreshTokenRecord, RepoRoot,
+    AccountSearchFilter, AccountStatus, AccountStore, ActorAccount, CreateAccountInput, InviteCode, InviteCodeUse,
+    OAuthRequest, PdsError, PdsResult, PlcOpLogEntry, RefreshTokenRecord, RepoListEntry, RepoRoot,
 };
 
 #[derive(Clone)]
@@ -54,6 +55,19 @@ fn compute_status(deactivated_at: &Option<String>, takedown_ref: &Option<String>
     }
 }
 
+/// SQL condition matching accounts whose computed status is `status`.
+fn status_condition(status: &AccountStatus) -> &'static str {
+    match status {
+        AccountStatus::Takendown => " AND a.takedown_ref IS NOT NULL",
+        AccountStatus::Deactivated => {
+            " AND a.takedown_ref IS NULL AND a.deactivated_at IS NOT NULL"
+        }
+        AccountStatus::Active => " AND a.takedown_ref IS NULL AND a.deactivated_at IS NULL",
+        // Never produced by `compute_status`.
+        AccountStatus::Suspended | AccountStatus::Deleted => " AND 1 = 0",
+    }
+}
+
 /// Map a sqlx Row (from a joined actor + account query) to an ActorAccount.
 fn row_to_actor_account(row: &sqlx::sqlite::SqliteRow) -> Result<ActorAccount, PdsError> {
     let did: String = row
@@ -74,6 +88,9 @@ fn row_to_actor_account(row: &sqlx::sqlite::SqliteRow) -> Result<ActorAccount, P
     let signing_key: Vec<u8> = row
         .try_get("signing_key")
         .map_err(|e| PdsError::Storage(e.to_string()))?;
+    let key_type: String = row
+        .try_get("key_type")
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
     let created_at: String = row
         .try_get("created_at")
         .map_err(|e| PdsError::Storage(e.to_string()))?;
@@ -96,6 +113,7 @@ fn row_to_actor_account(row: &sqlx::sqlite::SqliteRow) -> Result<ActorAccount, P
         email_confirmed_at: parse_datetime_opt(email_confirmed_at.as_deref())?,
         password_hash,
         signing_key,
+        key_type,
         created_at: parse_datetime(&created_at)?,
         status,
         deactivated_at: parse_datetime_opt(deactivated_at.as_deref())?,
@@ -116,23 +134,80 @@ const ACCOUNT_SELECT: &str = r#"
         ac.email,
         ac.email_confirmed_at,
         ac.password_hash,
-        ac.signing_key
+        ac.signing_key,
+        ac.key_type
     FROM actor a
     INNER JOIN account ac ON a.did = ac.did
 "#;
 
+/// SQL fragment joining actors to their repo roots, skipping empty roots.
+const OAUTH_REQUEST_COLUMNS: &str =
+    "request_uri, client_id, redirect_uri, scope, state, code_challenge, did, code, expires_at";
+
+/// Map an `oauth_request` row to an OAuthRequest.
+fn row_to_oauth_request(row: &sqlx::sqlite::SqliteRow) -> Result<OAuthRequest, PdsError> {
+    let get = |column: &str| -> Result<Option<String>, PdsError> {
+        row.try_get(column).map_err(|e| PdsError::Storage(e.to_string()))
+    };
+    let required = |column: &str| -> Result<String, PdsError> {
+        get(column)?.ok_or_else(|| PdsError::Storage(format!("oauth_request.{column} is null")))
+    };
+    Ok(OAuthRequest {
+        request_uri: required("request_uri")?,
+        client_id: required("client_id")?,
+        redirect_uri: required("redirect_uri")?,
+        scope: required("scope")?,
+        state: get("state")?,
+        code_challenge: required("code_challenge")?,
+        did: get("did")?,
+        code: get("code")?,
+        expires_at: parse_datetime(&required("expires_at")?)?,
+    })
+}
+
+const REPO_LIST_SELECT: &str = r#"
+    SELECT a.did, a.takedown_ref, a.deactivated_at, r.cid, r.rev
+    FROM actor a
+    JOIN repo_root r ON r.did = a.did
+    WHERE length(r.cid) > 0
+"#;
+
 impl SqliteAccountStore {
     pub async fn connect(url: &str) -> PdsResult<Self> {
-        let pool = SqlitePool::connect(url)
-            .await
-            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Self::connect_with(&DatabaseConfig::new(url)).await
+    }
+
+    /// Connect with the pool options from the `[database]` config section.
+    pub async fn connect_with(config: &DatabaseConfig) -> PdsResult<Self> {
+        let pool = crate::pool::connect_pool(config).await?;
 
         sqlx::migrate!("./migrations")
             .run(&pool)
             .await
             .map_err(|e| PdsError::Storage(e.to_string()))?;
 
-        Ok(Self { pool })
+        let store = Self { pool };
+        store.warn_handle_collisions().await?;
+        Ok(store)
+    }
+
+    /// Log the accounts that lost their handle to a case-insensitive
+    /// collision when handles were lowercased, until an operator clears them
+    /// from `handle_collision`.
+    async fn warn_handle_collisions(&self) -> PdsResult<()> {
+        let rows: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT did, handle, kept_by FROM handle_collision ORDER BY did")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+        for (did, handle, kept_by) in rows {
+            tracing::warn!(
+                %did, %handle, %kept_by,
+                "account has no handle: it collided with another account's once lowercased; \
+                 delete it from handle_collision once resolved"
+            );
+        }
+        Ok(())
     }
 
     /// Helper: fetch an ActorAccount with a WHERE clause appended to the base SELECT.
@@ -167,19 +242,21 @@ impl AccountStore for SqliteAccountStore {
         // Insert into actor table
         sqlx::query("INSERT INTO actor (did, handle) VALUES (?, ?)")
             .bind(&input.did)
-            .bind(&input.handle)
+            .bind(input.handle.to_ascii_lowercase())
             .execute(&mut *tx)
             .await
             .map_err(|e| PdsError::Storage(e.to_string()))?;
 
         // Insert into account table
         sqlx::query(
-            "INSERT INTO account (did, email, password_hash, signing_key) VALUES (?, ?, ?, ?)",
+            "INSERT INTO account (did, email, password_hash, signing_key, key_type) \
+             VALUES (?, ?, ?, ?, ?)",
         )
         .bind(&input.did)
         .bind(&input.email)
         .bind(&input.password_hash)
         .bind(&input.signing_key)
+        .bind(&input.key_type)
         .execute(&mut *tx)
         .await
         .map_err(|e| PdsError::Storage(e.to_string()))?;
@@ -211,7 +288,7 @@ impl AccountStore for SqliteAccountStore {
     }
 
     async fn get_account_by_handle(&self, handle: &str) -> PdsResult<Option<ActorAccount>> {
-        self.get_account_where("a.handle = ?", handle).await
+        self.get_account_where("a.handle = ?", &handle.to_ascii_lowercase()).await
     }
 
     async fn get_account_by_email(&self, email: &str) -> PdsResult<Option<ActorAccount>> {
@@ -220,7 +297,7 @@ impl AccountStore for SqliteAccountStore {
 
     async fn update_handle(&self, did: &str, handle: &str) -> PdsResult<()> {
         sqlx::query("UPDATE actor SET handle = ? WHERE did = ?")
-            .bind(handle)
+            .bind(handle.to_ascii_lowercase())
             .bind(did)
             .execute(&self.pool)
             .await
@@ -258,6 +335,49 @@ impl AccountStore for SqliteAccountStore {
         Ok(())
     }
 
+    async fn set_delete_after(
+        &self,
+        did: &str,
+        delete_after: Option<chrono::DateTime<Utc>>,
+    ) -> PdsResult<()> {
+        sqlx::query("UPDATE actor SET delete_after = ? WHERE did = ?")
+            .bind(delete_after.map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()))
+            .bind(did)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_accounts_due_for_deletion(
+        &self,
+        now: chrono::DateTime<Utc>,
+    ) -> PdsResult<Vec<String>> {
+        sqlx::query_scalar("SELECT did FROM actor WHERE delete_after <= ? ORDER BY did")
+            .bind(now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
+    async fn claim_scheduled_deletion(
+        &self,
+        did: &str,
+        now: chrono::DateTime<Utc>,
+        lease_until: chrono::DateTime<Utc>,
+    ) -> PdsResult<bool> {
+        let result = sqlx::query(
+            "UPDATE actor SET delete_after = ? WHERE did = ? AND delete_after <= ?",
+        )
+        .bind(lease_until.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        .bind(did)
+        .bind(now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected() == 1)
+    }
+
     async fn delete_account(&self, did: &str) -> PdsResult<()> {
         sqlx::query("DELETE FROM actor WHERE did = ?")
             .bind(did)
@@ -315,13 +435,15 @@ impl AccountStore for SqliteAccountStore {
 
     async fn create_refresh_token(&self, token: &RefreshTokenRecord) -> PdsResult<()> {
         sqlx::query(
-            "INSERT INTO refresh_token (id, did, expires_at, next_id, app_password_name) VALUES (?, ?, ?, ?, ?)",
+            "INSERT INTO refresh_token (id, did, expires_at, next_id, app_password_name, session_created_at, dpop_jkt) VALUES (?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&token.id)
         .bind(&token.did)
         .bind(token.expires_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
         .bind(&token.next_id)
         .bind(&token.app_password_name)
+        .bind(token.session_created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        .bind(&token.dpop_jkt)
         .execute(&self.pool)
         .await
         .map_err(|e| PdsError::Storage(e.to_string()))?;
@@ -330,7 +452,7 @@ impl AccountStore for SqliteAccountStore {
 
     async fn get_refresh_token(&self, id: &str) -> PdsResult<Option<RefreshTokenRecord>> {
         let row = sqlx::query(
-            "SELECT id, did, expires_at, next_id, app_password_name FROM refresh_token WHERE id = ?",
+            "SELECT id, did, expires_at, next_id, app_password_name, session_created_at, dpop_jkt FROM refresh_token WHERE id = ?",
         )
         .bind(id)
         .fetch_optional(&self.pool)
@@ -354,6 +476,12 @@ impl AccountStore for SqliteAccountStore {
                 let app_password_name: Option<String> = r
                     .try_get("app_password_name")
                     .map_err(|e| PdsError::Storage(e.to_string()))?;
+                let session_created_at: String = r
+                    .try_get("session_created_at")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
+                let dpop_jkt: Option<String> = r
+                    .try_get("dpop_jkt")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
 
                 Ok(Some(RefreshTokenRecord {
                     id,
@@ -361,6 +489,8 @@ impl AccountStore for SqliteAccountStore {
                     expires_at: parse_datetime(&expires_at)?,
                     next_id,
                     app_password_name,
+                    session_created_at: parse_datetime(&session_created_at)?,
+                    dpop_jkt,
                 }))
             }
             None => Ok(None),
@@ -376,6 +506,24 @@ impl AccountStore for SqliteAccountStore {
         Ok(())
     }
 
+    async fn delete_refresh_token_chain(&self, id: &str) -> PdsResult<u64> {
+        // Walk `next_id` links both ways from the presented token.
+        let result = sqlx::query(
+            "WITH RECURSIVE chain(id, next_id) AS ( \
+                 SELECT id, next_id FROM refresh_token WHERE id = ? \
+                 UNION \
+                 SELECT r.id, r.next_id FROM refresh_token r \
+                 JOIN chain c ON r.next_id = c.id OR r.id = c.next_id \
+             ) \
+             DELETE FROM refresh_token WHERE id IN (SELECT id FROM chain)",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
     async fn delete_refresh_tokens_for_did(&self, did: &str) -> PdsResult<u64> {
         let result = sqlx::query("DELETE FROM refresh_token WHERE did = ?")
             .bind(did)
@@ -385,6 +533,29 @@ impl AccountStore for SqliteAccountStore {
         Ok(result.rows_affected())
     }
 
+    async fn set_refresh_token_next_id(&self, id: &str, next_id: &str) -> PdsResult<bool> {
+        let result =
+            sqlx::query("UPDATE refresh_token SET next_id = ? WHERE id = ? AND next_id IS NULL")
+                .bind(next_id)
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_expired_refresh_tokens(
+        &self,
+        now: chrono::DateTime<Utc>,
+    ) -> PdsResult<u64> {
+        let result = sqlx::query("DELETE FROM refresh_token WHERE expires_at < ?")
+            .bind(now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
     async fn list_accounts(
         &self,
         cursor: Option<&str>,
@@ -410,7 +581,125 @@ impl AccountStore for SqliteAccountStore {
         rows.iter().map(row_to_actor_account).collect()
     }
 
+    async fn list_repos(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<RepoListEntry>> {
+        let rows = if let Some(cursor) = cursor {
+            let sql = format!("{REPO_LIST_SELECT} AND a.did > ? ORDER BY a.did ASC LIMIT ?");
+            sqlx::query(&sql)
+                .bind(cursor)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?
+        } else {
+            let sql = format!("{REPO_LIST_SELECT} ORDER BY a.did ASC LIMIT ?");
+            sqlx::query(&sql)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?
+        };
+
+        rows.iter()
+            .map(|row| {
+                let deactivated_at: Option<String> = row
+                    .try_get("deactivated_at")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
+                let takedown_ref: Option<String> = row
+                    .try_get("takedown_ref")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?;
+                Ok(RepoListEntry {
+                    did: row
+                        .try_get("did")
+                        .map_err(|e| PdsError::Storage(e.to_string()))?,
+                    cid: row
+                        .try_get("cid")
+                        .map_err(|e| PdsError::Storage(e.to_string()))?,
+                    rev: row
+                        .try_get("rev")
+                        .map_err(|e| PdsError::Storage(e.to_string()))?,
+                    status: compute_status(&deactivated_at, &takedown_ref),
+                })
+            })
+            .collect()
+    }
+
     // Invite code management (stubs for Phase 2 compatibility)
+    async fn create_oauth_request(&self, request: &OAuthRequest) -> PdsResult<()> {
+        sqlx::query(
+            "INSERT INTO oauth_request \
+             (request_uri, client_id, redirect_uri, scope, state, code_challenge, expires_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&request.request_uri)
+        .bind(&request.client_id)
+        .bind(&request.redirect_uri)
+        .bind(&request.scope)
+        .bind(&request.state)
+        .bind(&request.code_challenge)
+        .bind(request.expires_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get_oauth_request(&self, request_uri: &str) -> PdsResult<Option<OAuthRequest>> {
+        let sql =
+            format!("SELECT {OAUTH_REQUEST_COLUMNS} FROM oauth_request WHERE request_uri = ?");
+        let row = sqlx::query(&sql)
+            .bind(request_uri)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        row.as_ref().map(row_to_oauth_request).transpose()
+    }
+
+    async fn authorize_oauth_request(
+        &self,
+        request_uri: &str,
+        did: &str,
+        code: &str,
+        expires_at: chrono::DateTime<Utc>,
+    ) -> PdsResult<bool> {
+        let result = sqlx::query(
+            "UPDATE oauth_request SET did = ?, code = ?, expires_at = ? \
+             WHERE request_uri = ? AND code IS NULL",
+        )
+        .bind(did)
+        .bind(code)
+        .bind(expires_at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        .bind(request_uri)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn consume_oauth_code(&self, code: &str) -> PdsResult<Option<OAuthRequest>> {
+        let sql = format!(
+            "DELETE FROM oauth_request WHERE code = ? RETURNING {OAUTH_REQUEST_COLUMNS}"
+        );
+        let row = sqlx::query(&sql)
+            .bind(code)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        row.as_ref().map(row_to_oauth_request).transpose()
+    }
+
+    async fn delete_expired_oauth_requests(&self, now: chrono::DateTime<Utc>) -> PdsResult<u64> {
+        let result = sqlx::query("DELETE FROM oauth_request WHERE expires_at < ?")
+            .bind(now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
     async fn create_invite_code(&self, code: &str, available_uses: i32, for_account: &str, created_by: &str) -> PdsResult<InviteCode> {
         sqlx::query("INSERT INTO invite_code (code, available_uses, for_account, created_by) VALUES (?, ?, ?, ?)")
             .bind(code)
@@ -546,50 +835,75 @@ impl AccountStore for SqliteAccountStore {
     }
 
     // Account search and moderation (stubs for Phase 2 compatibility)
-    async fn search_accounts(&self, query: Option<&str>, cursor: Option<&str>, limit: usize) -> PdsResult<Vec<ActorAccount>> {
-        let rows = match (query, cursor) {
-            (Some(q), Some(cursor)) => {
-                let pattern = format!("%{}%", q);
-                let sql = format!("{ACCOUNT_SELECT} WHERE (a.handle LIKE ? OR ac.email LIKE ?) AND a.did > ? ORDER BY a.did ASC LIMIT ?");
-                sqlx::query(&sql)
-                    .bind(&pattern)
-                    .bind(&pattern)
-                    .bind(cursor)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
+    async fn search_accounts(
+        &self,
+        filter: &AccountSearchFilter,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<ActorAccount>> {
+        let mut sql = sqlx::QueryBuilder::new("");
+        if let Some(q) = &filter.query {
+            // Rank matches from the account_search FTS table by bm25, handle
+            // hits weighing more than email hits (lower is better). Trigrams
+            // need three characters, so shorter queries scan with LIKE.
+            if q.chars().count() >= 3 {
+                let phrase = format!("{{handle email}} : \"{}\"", q.replace('"', "\"\""));
+                sql.push(
+                    "WITH hits AS (SELECT did, bm25(account_search, 0.0, 2.0, 1.0) AS rank \
+                     FROM account_search WHERE account_search MATCH ",
+                )
+                .push_bind(phrase)
+                .push(") ");
+            } else {
+                let pattern = format!("%{q}%");
+                sql.push(
+                    "WITH hits AS (SELECT did, 0.0 AS rank FROM account_search \
+                     WHERE handle LIKE ",
+                )
+                .push_bind(pattern.clone())
+                .push(" OR email LIKE ")
+                .push_bind(pattern)
+                .push(") ");
             }
-            (Some(q), None) => {
-                let pattern = format!("%{}%", q);
-                let sql = format!("{ACCOUNT_SELECT} WHERE (a.handle LIKE ? OR ac.email LIKE ?) ORDER BY a.did ASC LIMIT ?");
-                sqlx::query(&sql)
-                    .bind(&pattern)
-                    .bind(&pattern)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
+            sql.push(ACCOUNT_SELECT)
+                .push(" INNER JOIN hits h ON h.did = a.did WHERE 1 = 1");
+        } else {
+            sql.push(ACCOUNT_SELECT).push(" WHERE 1 = 1");
+        }
+        if let Some(status) = &filter.status {
+            sql.push(status_condition(status));
+        }
+        if let Some(confirmed) = filter.email_confirmed {
+            sql.push(if confirmed {
+                " AND ac.email_confirmed_at IS NOT NULL"
+            } else {
+                " AND ac.email_confirmed_at IS NULL"
+            });
+        }
+        match (&filter.query, cursor) {
+            // Resume after the cursor account's position in the ranking.
+            (Some(_), Some(cursor)) => {
+                sql.push(" AND (h.rank, a.did) > (SELECT rank, did FROM hits WHERE did = ")
+                    .push_bind(cursor)
+                    .push(")");
             }
             (None, Some(cursor)) => {
-                let sql = format!("{ACCOUNT_SELECT} WHERE a.did > ? ORDER BY a.did ASC LIMIT ?");
-                sqlx::query(&sql)
-                    .bind(cursor)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
-            }
-            (None, None) => {
-                let sql = format!("{ACCOUNT_SELECT} ORDER BY a.did ASC LIMIT ?");
-                sqlx::query(&sql)
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| PdsError::Storage(e.to_string()))?
+                sql.push(" AND a.did > ").push_bind(cursor);
             }
-        };
+            (_, None) => {}
+        }
+        if filter.query.is_some() {
+            sql.push(" ORDER BY h.rank ASC, a.did ASC LIMIT ");
+        } else {
+            sql.push(" ORDER BY a.did ASC LIMIT ");
+        }
+        sql.push_bind(limit as i64);
 
+        let rows = sql
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
         rows.iter().map(row_to_actor_account).collect()
     }
 
@@ -603,6 +917,83 @@ impl AccountStore for SqliteAccountStore {
         Ok(())
     }
 
+    async fn set_blob_takedown(
+        &self,
+        did: &str,
+        cid: &str,
+        takedown_ref: Option<&str>,
+    ) -> PdsResult<()> {
+        let query = match takedown_ref {
+            Some(takedown_ref) => sqlx::query(
+                "INSERT INTO blob_takedown (did, cid, takedown_ref) VALUES (?, ?, ?) \
+                 ON CONFLICT (did, cid) DO UPDATE SET takedown_ref = excluded.takedown_ref",
+            )
+            .bind(did)
+            .bind(cid)
+            .bind(takedown_ref),
+            None => sqlx::query("DELETE FROM blob_takedown WHERE did = ? AND cid = ?")
+                .bind(did)
+                .bind(cid),
+        };
+        query
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get_blob_takedown(&self, did: &str, cid: &str) -> PdsResult<Option<String>> {
+        sqlx::query_scalar("SELECT takedown_ref FROM blob_takedown WHERE did = ? AND cid = ?")
+            .bind(did)
+            .bind(cid)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
+    // PLC operation log
+    async fn append_plc_op(
+        &self,
+        did: &str,
+        cid: &str,
+        operation: &serde_json::Value,
+    ) -> PdsResult<()> {
+        sqlx::query("INSERT INTO plc_op_log (did, cid, operation) VALUES (?, ?, ?)")
+            .bind(did)
+            .bind(cid)
+            .bind(operation.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn list_plc_ops(&self, did: &str) -> PdsResult<Vec<PlcOpLogEntry>> {
+        let rows = sqlx::query(
+            "SELECT did, cid, operation, created_at FROM plc_op_log WHERE did = ? ORDER BY id",
+        )
+        .bind(did)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                let operation: String =
+                    row.try_get("operation").map_err(|e| PdsError::Storage(e.to_string()))?;
+                let created_at: String =
+                    row.try_get("created_at").map_err(|e| PdsError::Storage(e.to_string()))?;
+                Ok(PlcOpLogEntry {
+                    did: row.try_get("did").map_err(|e| PdsError::Storage(e.to_string()))?,
+                    cid: row.try_get("cid").map_err(|e| PdsError::Storage(e.to_string()))?,
+                    operation: serde_json::from_str(&operation)
+                        .map_err(|e| PdsError::Storage(format!("invalid PLC operation: {e}")))?,
+                    created_at: parse_datetime(&created_at)?,
+                })
+            })
+            .collect()
+    }
+
     // Email token management (Phase 2)
     async fn create_email_token(&self, purpose: &str, did: &str, token: &str) -> PdsResult<()> {
         sqlx::query("INSERT OR REPLACE INTO email_token (purpose, did, token) VALUES (?, ?, ?)")
@@ -671,6 +1062,25 @@ impl AccountStore for SqliteAccountStore {
         Ok(())
     }
 
+    async fn delete_expired_email_tokens(
+        &self,
+        now: chrono::DateTime<Utc>,
+        config: &EmailConfig,
+    ) -> PdsResult<u64> {
+        let mut deleted = 0;
+        for purpose in EMAIL_TOKEN_PURPOSES {
+            let cutoff = now - config.token_lifetime(purpose);
+            deleted += sqlx::query("DELETE FROM email_token WHERE purpose = ? AND requested_at < ?")
+                .bind(purpose)
+                .bind(cutoff.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+                .execute(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?
+                .rows_affected();
+        }
+        Ok(deleted)
+    }
+
     async fn confirm_email(&self, did: &str) -> PdsResult<()> {
         sqlx::query("UPDATE account SET email_confirmed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE did = ?")
             .bind(did)
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use sqlx::{Row, SqlitePool};
 
+use dallaspds_core::config::DatabaseConfig;
 use dallaspds_core::{EventStore, PdsError, PdsResult, PersistedEvent};
 
 #[derive(Clone)]
@@ -10,9 +11,12 @@ pub struct SqliteEventStore {
 
 impl SqliteEventStore {
     pub async fn connect(url: &str) -> PdsResult<Self> {
-        let pool = SqlitePool::connect(url)
-            .await
-            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Self::connect_with(&DatabaseConfig::new(url)).await
+    }
+
+    /// Connect with the pool options from the `[database]` config section.
+    pub async fn connect_with(config: &DatabaseConfig) -> PdsResult<Self> {
+        let pool = crate::pool::connect_pool(config).await?;
         Ok(Self { pool })
     }
 }
@@ -72,4 +76,59 @@ impl EventStore for SqliteEventStore {
         row.try_get("max_seq")
             .map_err(|e| PdsError::Storage(e.to_string()))
     }
+
+    async fn get_last_event_for_did(
+        &self,
+        did: &str,
+        event_type: &str,
+    ) -> PdsResult<Option<PersistedEvent>> {
+        let row = sqlx::query(
+            "SELECT seq, event_type, did, payload FROM firehose_event \
+             WHERE did = ? AND event_type = ? ORDER BY seq DESC LIMIT 1",
+        )
+        .bind(did)
+        .bind(event_type)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        row.map(|r| {
+            Ok(PersistedEvent {
+                seq: r.try_get("seq").map_err(|e| PdsError::Storage(e.to_string()))?,
+                event_type: r
+                    .try_get("event_type")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?,
+                did: r.try_get("did").map_err(|e| PdsError::Storage(e.to_string()))?,
+                payload: r
+                    .try_get("payload")
+                    .map_err(|e| PdsError::Storage(e.to_string()))?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn get_first_seq_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> PdsResult<Option<i64>> {
+        let row = sqlx::query(
+            "SELECT MIN(seq) as min_seq FROM firehose_event WHERE created_at >= ?",
+        )
+        .bind(since.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        row.try_get("min_seq")
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
+    async fn prune_events_before(&self, before_seq: i64) -> PdsResult<u64> {
+        let result = sqlx::query("DELETE FROM firehose_event WHERE seq < ?")
+            .bind(before_seq)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
 }
@@ -1,5 +1,6 @@
 pub mod account;
 pub mod event;
+pub mod pool;
 pub mod repo;
 
 pub use account::SqliteAccountStore;
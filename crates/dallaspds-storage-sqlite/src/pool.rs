@@ -0,0 +1,31 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+
+use dallaspds_core::config::DatabaseConfig;
+use dallaspds_core::{PdsError, PdsResult};
+
+/// Open a connection pool for `config.url` with the configured limits.
+///
+/// Each connection sets `busy_timeout`, and with `sqlite_wal` switches the
+/// database to write-ahead logging, so concurrent writers wait for the lock
+/// instead of failing with `database is locked`.
+pub async fn connect_pool(config: &DatabaseConfig) -> PdsResult<SqlitePool> {
+    let mut options = SqliteConnectOptions::from_str(&config.url)
+        .map_err(|e| PdsError::Storage(e.to_string()))?
+        .busy_timeout(Duration::from_millis(config.sqlite_busy_timeout_ms));
+    if config.sqlite_wal {
+        options = options.journal_mode(SqliteJournalMode::Wal);
+    }
+    SqlitePoolOptions::new()
+        .max_connections(config.max_connections.max(1))
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .idle_timeout((config.idle_timeout_secs > 0).then(|| {
+            Duration::from_secs(config.idle_timeout_secs)
+        }))
+        .connect_with(options)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))
+}
@@ -1,6 +1,9 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use sqlx::{Row, SqlitePool};
 
+use dallaspds_core::config::DatabaseConfig;
 use dallaspds_core::{PdsError, PdsResult, RepoStore};
 
 #[derive(Clone)]
@@ -10,9 +13,12 @@ pub struct SqliteRepoStore {
 
 impl SqliteRepoStore {
     pub async fn connect(url: &str) -> PdsResult<Self> {
-        let pool = SqlitePool::connect(url)
-            .await
-            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Self::connect_with(&DatabaseConfig::new(url)).await
+    }
+
+    /// Connect with the pool options from the `[database]` config section.
+    pub async fn connect_with(config: &DatabaseConfig) -> PdsResult<Self> {
+        let pool = crate::pool::connect_pool(config).await?;
 
         sqlx::migrate!("./migrations")
             .run(&pool)
@@ -55,6 +61,70 @@ impl RepoStore for SqliteRepoStore {
         Ok(())
     }
 
+    async fn put_blocks(&self, did: &str, blocks: &[(Vec<u8>, Vec<u8>)]) -> PdsResult<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        for (cid, block) in blocks {
+            sqlx::query("INSERT OR IGNORE INTO repo_block (did, cid, block) VALUES (?, ?, ?)")
+                .bind(did)
+                .bind(cid)
+                .bind(block)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
+    async fn commit_blocks(
+        &self,
+        did: &str,
+        blocks: &[(Vec<u8>, Vec<u8>)],
+        expected_root: &[u8],
+        root: &[u8],
+        rev: &str,
+    ) -> PdsResult<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        // Move the root first: it takes the write lock, so the comparison
+        // can't interleave with another commit.
+        let moved = sqlx::query(
+            "INSERT INTO repo_root (did, cid, rev, indexed_at) VALUES (?, ?, ?, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) \
+             ON CONFLICT (did) DO UPDATE SET cid = excluded.cid, rev = excluded.rev, indexed_at = excluded.indexed_at \
+             WHERE repo_root.cid = ?",
+        )
+        .bind(did)
+        .bind(root)
+        .bind(rev)
+        .bind(expected_root)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        if moved.rows_affected() == 0 {
+            return Err(PdsError::RepoRootChanged);
+        }
+        for (cid, block) in blocks {
+            sqlx::query("INSERT OR IGNORE INTO repo_block (did, cid, block) VALUES (?, ?, ?)")
+                .bind(did)
+                .bind(cid)
+                .bind(block)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))
+    }
+
     async fn has_block(&self, did: &str, cid: &[u8]) -> PdsResult<bool> {
         let row = sqlx::query("SELECT 1 FROM repo_block WHERE did = ? AND cid = ?")
             .bind(did)
@@ -86,12 +156,109 @@ impl RepoStore for SqliteRepoStore {
         Ok(blocks)
     }
 
+    async fn get_storage_usage(&self, did: &str) -> PdsResult<(u64, u64)> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS blocks, COALESCE(SUM(LENGTH(block)), 0) AS bytes \
+             FROM repo_block WHERE did = ?",
+        )
+        .bind(did)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+        let blocks: i64 = row
+            .try_get("blocks")
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        let bytes: i64 = row
+            .try_get("bytes")
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok((blocks as u64, bytes as u64))
+    }
+
     async fn delete_blocks_for_did(&self, did: &str) -> PdsResult<u64> {
         let result = sqlx::query("DELETE FROM repo_block WHERE did = ?")
             .bind(did)
             .execute(&self.pool)
             .await
             .map_err(|e| PdsError::Storage(e.to_string()))?;
+        sqlx::query("DELETE FROM blob_ref WHERE did = ?")
+            .bind(did)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
         Ok(result.rows_affected())
     }
+
+    async fn retain_blocks(&self, did: &str, keep: &HashSet<Vec<u8>>) -> PdsResult<u64> {
+        let rows = sqlx::query("SELECT cid FROM repo_block WHERE did = ?")
+            .bind(did)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        let mut removed = 0;
+        for row in &rows {
+            let cid: Vec<u8> = row
+                .try_get("cid")
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+            if keep.contains(&cid) {
+                continue;
+            }
+            removed += sqlx::query("DELETE FROM repo_block WHERE did = ? AND cid = ?")
+                .bind(did)
+                .bind(&cid)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?
+                .rows_affected();
+        }
+        tx.commit()
+            .await
+            .map_err(|e| PdsError::Storage(e.to_string()))?;
+        Ok(removed)
+    }
+
+    async fn add_blob_refs(&self, did: &str, cids: &[String], rev: &str) -> PdsResult<()> {
+        for cid in cids {
+            sqlx::query("INSERT OR IGNORE INTO blob_ref (did, cid, rev) VALUES (?, ?, ?)")
+                .bind(did)
+                .bind(cid)
+                .bind(rev)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| PdsError::Storage(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn list_blobs_since(
+        &self,
+        did: &str,
+        since: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> PdsResult<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT cid FROM blob_ref WHERE did = ? AND rev > ? AND cid > ? \
+             ORDER BY cid LIMIT ?",
+        )
+        .bind(did)
+        .bind(since)
+        .bind(cursor.unwrap_or(""))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| PdsError::Storage(e.to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                row.try_get("cid")
+                    .map_err(|e| PdsError::Storage(e.to_string()))
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,19 @@
+use dallaspds_storage_sqlite::{SqliteAccountStore, SqliteEventStore, SqliteRepoStore};
+use dallaspds_test_utils::ConformanceStores;
+use tempfile::TempDir;
+
+async fn setup() -> ConformanceStores<SqliteAccountStore, SqliteRepoStore, SqliteEventStore> {
+    let tempdir = TempDir::new().unwrap();
+    let db_url = format!(
+        "sqlite://{}?mode=rwc",
+        tempdir.path().join("test.db").display()
+    );
+    ConformanceStores {
+        account_store: SqliteAccountStore::connect(&db_url).await.unwrap(),
+        repo_store: SqliteRepoStore::connect(&db_url).await.unwrap(),
+        event_store: SqliteEventStore::connect(&db_url).await.unwrap(),
+        guard: Box::new(tempdir),
+    }
+}
+
+dallaspds_test_utils::store_conformance_tests!(setup);
@@ -0,0 +1,70 @@
+use std::borrow::Cow;
+
+use dallaspds_core::AccountStore;
+use dallaspds_storage_sqlite::SqliteAccountStore;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn handle_collisions_are_resolved_and_recorded() {
+    let tempdir = TempDir::new().unwrap();
+    let db_url = format!("sqlite://{}?mode=rwc", tempdir.path().join("test.db").display());
+
+    // A database migrated up to the collision fix, holding handles that only
+    // differ in case.
+    let pool = sqlx::SqlitePool::connect(&db_url).await.unwrap();
+    let mut migrator = sqlx::migrate!("./migrations");
+    migrator.migrations = Cow::Owned(
+        migrator
+            .migrations
+            .iter()
+            .filter(|migration| migration.version < 20250620000000)
+            .cloned()
+            .collect(),
+    );
+    migrator.run(&pool).await.unwrap();
+    for (did, handle, created_at) in [
+        ("did:plc:a", "Alice.test", "2025-01-01T00:00:00.000Z"),
+        ("did:plc:b", "alice.test", "2025-02-01T00:00:00.000Z"),
+        ("did:plc:c", "Bob.test", "2025-01-01T00:00:00.000Z"),
+        ("did:plc:d", "BOB.test", "2025-02-01T00:00:00.000Z"),
+        ("did:plc:e", "Carol.test", "2025-01-01T00:00:00.000Z"),
+    ] {
+        sqlx::query("INSERT INTO actor (did, handle, created_at) VALUES (?, ?, ?)")
+            .bind(did)
+            .bind(handle)
+            .bind(created_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO account (did, password_hash, signing_key) VALUES (?, 'hash', X'01')")
+            .bind(did)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let store = SqliteAccountStore::connect(&db_url).await.unwrap();
+    let handle = |did: &'static str| {
+        let store = store.clone();
+        async move { store.get_account_by_did(did).await.unwrap().unwrap().handle }
+    };
+    // The holder of the lowercase form keeps it; otherwise the oldest account.
+    assert_eq!(handle("did:plc:a").await, None);
+    assert_eq!(handle("did:plc:b").await.as_deref(), Some("alice.test"));
+    assert_eq!(handle("did:plc:c").await.as_deref(), Some("bob.test"));
+    assert_eq!(handle("did:plc:d").await, None);
+    assert_eq!(handle("did:plc:e").await.as_deref(), Some("carol.test"));
+
+    let recorded: Vec<(String, String, String)> =
+        sqlx::query_as("SELECT did, handle, kept_by FROM handle_collision ORDER BY did")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        recorded,
+        [
+            ("did:plc:a".into(), "Alice.test".into(), "did:plc:b".into()),
+            ("did:plc:d".into(), "BOB.test".into(), "did:plc:c".into()),
+        ]
+    );
+}
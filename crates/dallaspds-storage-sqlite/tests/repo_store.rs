@@ -1,89 +1,96 @@
-use dallaspds_core::RepoStore;
-use dallaspds_storage_sqlite::SqliteRepoStore;
-use tempfile::TempDir;
-
-async fn setup() -> (SqliteRepoStore, TempDir) {
-    let tempdir = TempDir::new().unwrap();
-    let db_path = tempdir.path().join("test.db");
-    let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
-    let store = SqliteRepoStore::connect(&db_url).await.unwrap();
-    (store, tempdir)
-}
-
-#[tokio::test]
-async fn put_and_get_block() {
-    let (store, _dir) = setup().await;
-    let cid = vec![0x01, 0x71, 0x12, 0x20, 0xAA];
-    let block = b"block data here".to_vec();
-
-    store.put_block("did:plc:test", &cid, &block).await.unwrap();
-    let result = store.get_block("did:plc:test", &cid).await.unwrap();
-    assert_eq!(result, Some(block));
-}
-
-#[tokio::test]
-async fn get_nonexistent() {
-    let (store, _dir) = setup().await;
-    let result = store.get_block("did:plc:test", &[0xFF]).await.unwrap();
-    assert!(result.is_none());
-}
-
-#[tokio::test]
-async fn has_block() {
-    let (store, _dir) = setup().await;
-    let cid = vec![1, 2, 3];
-    assert!(!store.has_block("did:plc:test", &cid).await.unwrap());
-
-    store.put_block("did:plc:test", &cid, b"data").await.unwrap();
-    assert!(store.has_block("did:plc:test", &cid).await.unwrap());
-}
-
-#[tokio::test]
-async fn put_idempotent() {
-    let (store, _dir) = setup().await;
-    let cid = vec![1, 2, 3];
-    store.put_block("did:plc:test", &cid, b"data").await.unwrap();
-    // INSERT OR IGNORE should not error on duplicate
-    store.put_block("did:plc:test", &cid, b"data").await.unwrap();
-    let result = store.get_block("did:plc:test", &cid).await.unwrap();
-    assert_eq!(result, Some(b"data".to_vec()));
-}
+//! SQLite-specific repo store behaviour; the shared suite is in
+//! `conformance.rs`.
 
-#[tokio::test]
-async fn get_all_blocks() {
-    let (store, _dir) = setup().await;
-    store.put_block("did:plc:test", &[1], b"block1").await.unwrap();
-    store.put_block("did:plc:test", &[2], b"block2").await.unwrap();
-    store.put_block("did:plc:test", &[3], b"block3").await.unwrap();
-
-    let blocks = store.get_all_blocks("did:plc:test").await.unwrap();
-    assert_eq!(blocks.len(), 3);
-}
+use dallaspds_core::config::DatabaseConfig;
+use dallaspds_core::{AccountStore, CreateAccountInput, RepoStore};
+use dallaspds_storage_sqlite::{SqliteAccountStore, SqliteRepoStore};
+use tempfile::TempDir;
 
 #[tokio::test]
-async fn scoped_to_did() {
-    let (store, _dir) = setup().await;
-    let cid = vec![1, 2, 3];
-    store.put_block("did:plc:a", &cid, b"block-a").await.unwrap();
-    store.put_block("did:plc:b", &cid, b"block-b").await.unwrap();
+async fn parallel_writes_from_separate_pools_do_not_lock() {
+    let tempdir = TempDir::new().unwrap();
+    let db_url = format!(
+        "sqlite://{}?mode=rwc",
+        tempdir.path().join("test.db").display()
+    );
+    let mut config = DatabaseConfig::new(&db_url);
+    config.max_connections = 8;
+    let stores = [
+        SqliteRepoStore::connect_with(&config).await.unwrap(),
+        SqliteRepoStore::connect_with(&config).await.unwrap(),
+    ];
 
-    let result_a = store.get_block("did:plc:a", &cid).await.unwrap();
-    assert_eq!(result_a, Some(b"block-a".to_vec()));
+    let mut tasks = tokio::task::JoinSet::new();
+    for i in 0..64u8 {
+        let store = stores[usize::from(i % 2)].clone();
+        tasks.spawn(async move {
+            let did = format!("did:plc:writer{}", i % 4);
+            let blocks: Vec<(Vec<u8>, Vec<u8>)> =
+                (0..20u8).map(|j| (vec![i, j], vec![j; 256])).collect();
+            store.put_blocks(&did, &blocks).await
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        result.unwrap().expect("parallel write failed");
+    }
 
-    let result_b = store.get_block("did:plc:b", &cid).await.unwrap();
-    assert_eq!(result_b, Some(b"block-b".to_vec()));
+    let mut total = 0;
+    for writer in 0..4 {
+        total += stores[0]
+            .get_all_blocks(&format!("did:plc:writer{writer}"))
+            .await
+            .unwrap()
+            .len();
+    }
+    assert_eq!(total, 64 * 20);
 }
 
 #[tokio::test]
-async fn delete_blocks_for_did() {
-    let (store, _dir) = setup().await;
-    store.put_block("did:plc:del", &[1], b"a").await.unwrap();
-    store.put_block("did:plc:del", &[2], b"b").await.unwrap();
-    store.put_block("did:plc:keep", &[1], b"c").await.unwrap();
+async fn commit_blocks_rolls_back_root_when_a_block_fails() {
+    let tempdir = TempDir::new().unwrap();
+    let db_url = format!(
+        "sqlite://{}?mode=rwc",
+        tempdir.path().join("test.db").display()
+    );
+    let repo_store = SqliteRepoStore::connect(&db_url).await.unwrap();
+    let account_store = SqliteAccountStore::connect(&db_url).await.unwrap();
+    let input = CreateAccountInput {
+        did: "did:plc:bad".to_string(),
+        handle: "bad.test".to_string(),
+        email: None,
+        password_hash: "hash".to_string(),
+        signing_key: vec![1, 2, 3, 4],
+        key_type: "p256".to_string(),
+    };
+    account_store.create_account(&input).await.unwrap();
 
-    let deleted = store.delete_blocks_for_did("did:plc:del").await.unwrap();
-    assert_eq!(deleted, 2);
+    let pool = sqlx::SqlitePool::connect(&db_url).await.unwrap();
+    sqlx::query(
+        "CREATE TRIGGER reject_block BEFORE INSERT ON repo_block WHEN NEW.did = 'did:plc:bad' \
+         BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
 
-    assert!(store.get_block("did:plc:del", &[1]).await.unwrap().is_none());
-    assert!(store.get_block("did:plc:keep", &[1]).await.unwrap().is_some());
+    let blocks = vec![(vec![1], b"commit".to_vec()), (vec![2], b"node".to_vec())];
+    assert!(
+        repo_store
+            .commit_blocks("did:plc:bad", &blocks, &[], &[1], "rev1")
+            .await
+            .is_err()
+    );
+    assert!(
+        repo_store
+            .get_all_blocks("did:plc:bad")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let root = account_store
+        .get_repo_root("did:plc:bad")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(root.cid.is_empty());
 }
@@ -0,0 +1,578 @@
+//! [`AccountStore`] conformance tests.
+
+use dallaspds_core::config::EmailConfig;
+use dallaspds_core::{
+    AccountSearchFilter, AccountStatus, AccountStore, CreateAccountInput, OAuthRequest, RefreshTokenRecord,
+};
+
+use super::ConformanceStores;
+
+fn test_input(did: &str, handle: &str) -> CreateAccountInput {
+    CreateAccountInput {
+        did: did.to_string(),
+        handle: handle.to_string(),
+        email: Some(format!("{handle}@test.com")),
+        password_hash: "$argon2id$v=19$m=65536,t=3,p=4$fakesalt$fakehash".to_string(),
+        signing_key: vec![1, 2, 3, 4],
+        key_type: "p256".to_string(),
+    }
+}
+
+// ── Account CRUD ────────────────────────────────────────────────────────
+
+pub async fn create_and_get_by_did<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    let input = test_input("did:plc:test1", "alice.test");
+    let account = store.create_account(&input).await.unwrap();
+    assert_eq!(account.did, "did:plc:test1");
+    assert_eq!(account.handle.as_deref(), Some("alice.test"));
+
+    let fetched = store.get_account_by_did("did:plc:test1").await.unwrap();
+    assert!(fetched.is_some());
+    assert_eq!(fetched.unwrap().did, "did:plc:test1");
+}
+
+pub async fn get_by_handle<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:h1", "bob.test")).await.unwrap();
+    let account = store.get_account_by_handle("bob.test").await.unwrap();
+    assert!(account.is_some());
+    assert_eq!(account.unwrap().did, "did:plc:h1");
+}
+
+pub async fn get_by_email<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:e1", "carol.test")).await.unwrap();
+    let account = store.get_account_by_email("carol.test@test.com").await.unwrap();
+    assert!(account.is_some());
+    assert_eq!(account.unwrap().did, "did:plc:e1");
+}
+
+pub async fn get_nonexistent_returns_none<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    assert!(store.get_account_by_did("did:plc:nope").await.unwrap().is_none());
+    assert!(store.get_account_by_handle("nope.test").await.unwrap().is_none());
+    assert!(store.get_account_by_email("nope@test.com").await.unwrap().is_none());
+}
+
+// ── Updates ─────────────────────────────────────────────────────────────
+
+pub async fn update_handle<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:uh1", "old.test")).await.unwrap();
+    store.update_handle("did:plc:uh1", "new.test").await.unwrap();
+    let account = store.get_account_by_did("did:plc:uh1").await.unwrap().unwrap();
+    assert_eq!(account.handle.as_deref(), Some("new.test"));
+}
+
+pub async fn update_password<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:up1", "pass.test")).await.unwrap();
+    store.update_password("did:plc:up1", "new-hash").await.unwrap();
+    let account = store.get_account_by_did("did:plc:up1").await.unwrap().unwrap();
+    assert_eq!(account.password_hash, "new-hash");
+}
+
+// ── Lifecycle ───────────────────────────────────────────────────────────
+
+pub async fn deactivate_and_activate<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:da1", "active.test")).await.unwrap();
+
+    store.deactivate_account("did:plc:da1").await.unwrap();
+    let account = store.get_account_by_did("did:plc:da1").await.unwrap().unwrap();
+    assert_eq!(account.status, AccountStatus::Deactivated);
+    assert!(account.deactivated_at.is_some());
+
+    store.activate_account("did:plc:da1").await.unwrap();
+    let account = store.get_account_by_did("did:plc:da1").await.unwrap().unwrap();
+    assert_eq!(account.status, AccountStatus::Active);
+    assert!(account.deactivated_at.is_none());
+}
+
+pub async fn delete_account_cascades<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:del1", "delete.test")).await.unwrap();
+    store.delete_account("did:plc:del1").await.unwrap();
+    assert!(store.get_account_by_did("did:plc:del1").await.unwrap().is_none());
+}
+
+// ── Repo root ───────────────────────────────────────────────────────────
+
+pub async fn repo_root_initially_empty<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rr1", "repo.test")).await.unwrap();
+    let root = store.get_repo_root("did:plc:rr1").await.unwrap().unwrap();
+    assert!(root.cid.is_empty(), "initial repo root CID should be empty");
+}
+
+pub async fn repo_root_update_and_get<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rr2", "root.test")).await.unwrap();
+    let cid_bytes = vec![0x01, 0x71, 0x12, 0x20, 0xAA];
+    store.update_repo_root("did:plc:rr2", &cid_bytes, "rev1").await.unwrap();
+
+    let root = store.get_repo_root("did:plc:rr2").await.unwrap().unwrap();
+    assert_eq!(root.cid, cid_bytes);
+    assert_eq!(root.rev, "rev1");
+}
+
+pub async fn repo_root_overwrite<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rr3", "over.test")).await.unwrap();
+    store.update_repo_root("did:plc:rr3", &[1], "rev1").await.unwrap();
+    store.update_repo_root("did:plc:rr3", &[2], "rev2").await.unwrap();
+
+    let root = store.get_repo_root("did:plc:rr3").await.unwrap().unwrap();
+    assert_eq!(root.cid, vec![2]);
+    assert_eq!(root.rev, "rev2");
+}
+
+// ── Refresh tokens ──────────────────────────────────────────────────────
+
+pub async fn refresh_token_crud<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rt1", "token.test")).await.unwrap();
+
+    let token = RefreshTokenRecord {
+        id: "tok-1".to_string(),
+        did: "did:plc:rt1".to_string(),
+        expires_at: chrono::Utc::now() + chrono::Duration::days(90),
+        next_id: None,
+        app_password_name: None,
+        session_created_at: "2025-01-02T03:04:05.678Z".parse().unwrap(),
+        dpop_jkt: Some("jkt-1".to_string()),
+    };
+    store.create_refresh_token(&token).await.unwrap();
+
+    let fetched = store.get_refresh_token("tok-1").await.unwrap();
+    assert!(fetched.is_some());
+    let fetched = fetched.unwrap();
+    assert_eq!(fetched.did, "did:plc:rt1");
+    assert_eq!(fetched.session_created_at, token.session_created_at);
+    assert_eq!(fetched.dpop_jkt.as_deref(), Some("jkt-1"));
+
+    store.delete_refresh_token("tok-1").await.unwrap();
+    assert!(store.get_refresh_token("tok-1").await.unwrap().is_none());
+}
+
+pub async fn refresh_token_delete_all_for_did<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rt2", "tokens.test")).await.unwrap();
+
+    for i in 0..3 {
+        let token = RefreshTokenRecord {
+            id: format!("tok-{i}"),
+            did: "did:plc:rt2".to_string(),
+            expires_at: chrono::Utc::now() + chrono::Duration::days(90),
+            next_id: None,
+            app_password_name: None,
+            session_created_at: chrono::Utc::now(),
+            dpop_jkt: None,
+        };
+        store.create_refresh_token(&token).await.unwrap();
+    }
+
+    let deleted = store.delete_refresh_tokens_for_did("did:plc:rt2").await.unwrap();
+    assert_eq!(deleted, 3);
+    assert!(store.get_refresh_token("tok-0").await.unwrap().is_none());
+}
+
+pub async fn refresh_token_next_id_set_once<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rt3", "rotate.test")).await.unwrap();
+
+    let token = RefreshTokenRecord {
+        id: "tok-a".to_string(),
+        did: "did:plc:rt3".to_string(),
+        expires_at: chrono::Utc::now() + chrono::Duration::days(90),
+        next_id: None,
+        app_password_name: None,
+        session_created_at: chrono::Utc::now(),
+        dpop_jkt: None,
+    };
+    store.create_refresh_token(&token).await.unwrap();
+
+    assert!(store.set_refresh_token_next_id("tok-a", "tok-b").await.unwrap());
+    // A second rotation of the same token must not succeed.
+    assert!(!store.set_refresh_token_next_id("tok-a", "tok-c").await.unwrap());
+
+    let fetched = store.get_refresh_token("tok-a").await.unwrap().unwrap();
+    assert_eq!(fetched.next_id.as_deref(), Some("tok-b"));
+}
+
+pub async fn refresh_token_delete_chain<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rt5", "chain.test")).await.unwrap();
+
+    // tok-1 -> tok-2 -> tok-3 is one session; tok-x is another.
+    let tokens = [
+        ("tok-1", Some("tok-2")),
+        ("tok-2", Some("tok-3")),
+        ("tok-3", None),
+        ("tok-x", None),
+    ];
+    for (id, next_id) in tokens {
+        let token = RefreshTokenRecord {
+            id: id.to_string(),
+            did: "did:plc:rt5".to_string(),
+            expires_at: chrono::Utc::now() + chrono::Duration::days(90),
+            next_id: next_id.map(str::to_string),
+            app_password_name: None,
+            session_created_at: chrono::Utc::now(),
+            dpop_jkt: None,
+        };
+        store.create_refresh_token(&token).await.unwrap();
+    }
+
+    assert_eq!(store.delete_refresh_token_chain("tok-2").await.unwrap(), 3);
+    for id in ["tok-1", "tok-2", "tok-3"] {
+        assert!(store.get_refresh_token(id).await.unwrap().is_none());
+    }
+    assert!(store.get_refresh_token("tok-x").await.unwrap().is_some());
+}
+
+pub async fn refresh_token_delete_expired<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:rt4", "expiry.test")).await.unwrap();
+
+    let expired = RefreshTokenRecord {
+        id: "tok-old".to_string(),
+        did: "did:plc:rt4".to_string(),
+        expires_at: chrono::Utc::now() - chrono::Duration::days(1),
+        next_id: Some("tok-new".to_string()),
+        app_password_name: None,
+        session_created_at: chrono::Utc::now(),
+        dpop_jkt: None,
+    };
+    let live = RefreshTokenRecord {
+        id: "tok-new".to_string(),
+        did: "did:plc:rt4".to_string(),
+        expires_at: chrono::Utc::now() + chrono::Duration::days(90),
+        next_id: None,
+        app_password_name: None,
+        session_created_at: chrono::Utc::now(),
+        dpop_jkt: None,
+    };
+    store.create_refresh_token(&expired).await.unwrap();
+    store.create_refresh_token(&live).await.unwrap();
+
+    let deleted = store
+        .delete_expired_refresh_tokens(chrono::Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(deleted, 1);
+    assert!(store.get_refresh_token("tok-old").await.unwrap().is_none());
+    assert!(store.get_refresh_token("tok-new").await.unwrap().is_some());
+}
+
+pub async fn refresh_token_get_nonexistent<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    assert!(store.get_refresh_token("does-not-exist").await.unwrap().is_none());
+}
+
+// ── OAuth requests ──────────────────────────────────────────────────────
+
+pub async fn oauth_request_code_is_approved_once_and_consumed_once<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:oa1", "oauth.test")).await.unwrap();
+
+    let request = OAuthRequest {
+        request_uri: "urn:req-1".to_string(),
+        client_id: "https://app.example/client.json".to_string(),
+        redirect_uri: "https://app.example/cb".to_string(),
+        scope: "atproto".to_string(),
+        state: Some("xyz".to_string()),
+        code_challenge: "challenge".to_string(),
+        did: None,
+        code: None,
+        expires_at: chrono::Utc::now() + chrono::Duration::minutes(5),
+    };
+    store.create_oauth_request(&request).await.unwrap();
+    let fetched = store.get_oauth_request("urn:req-1").await.unwrap().unwrap();
+    assert_eq!(fetched.state.as_deref(), Some("xyz"));
+    assert!(fetched.code.is_none());
+
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(1);
+    let approve = |code: &'static str| {
+        store.authorize_oauth_request("urn:req-1", "did:plc:oa1", code, expires_at)
+    };
+    assert!(approve("code-1").await.unwrap());
+    assert!(!approve("code-2").await.unwrap());
+
+    let consumed = store.consume_oauth_code("code-1").await.unwrap().unwrap();
+    assert_eq!(consumed.did.as_deref(), Some("did:plc:oa1"));
+    assert_eq!(consumed.code_challenge, "challenge");
+    assert!(store.consume_oauth_code("code-1").await.unwrap().is_none());
+    assert!(store.get_oauth_request("urn:req-1").await.unwrap().is_none());
+}
+
+pub async fn oauth_request_delete_expired<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    for (uri, offset) in [("urn:old", -1), ("urn:new", 5)] {
+        let request = OAuthRequest {
+            request_uri: uri.to_string(),
+            client_id: "client".to_string(),
+            redirect_uri: "https://app.example/cb".to_string(),
+            scope: "atproto".to_string(),
+            state: None,
+            code_challenge: "challenge".to_string(),
+            did: None,
+            code: None,
+            expires_at: chrono::Utc::now() + chrono::Duration::minutes(offset),
+        };
+        store.create_oauth_request(&request).await.unwrap();
+    }
+
+    let deleted = store.delete_expired_oauth_requests(chrono::Utc::now()).await.unwrap();
+    assert_eq!(deleted, 1);
+    assert!(store.get_oauth_request("urn:old").await.unwrap().is_none());
+    assert!(store.get_oauth_request("urn:new").await.unwrap().is_some());
+}
+
+// ── Email tokens ────────────────────────────────────────────────────────
+
+pub async fn email_tokens_past_their_lifetime_are_swept<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:e1", "e1.test")).await.unwrap();
+    store.create_email_token("reset_password", "did:plc:e1", "reset-token").await.unwrap();
+    store.create_email_token("confirm_email", "did:plc:e1", "confirm-token").await.unwrap();
+
+    // Ten minutes on, a reset token with a one-minute lifetime is stale
+    // while a confirmation token with the default hour is not.
+    let mut config = EmailConfig::default();
+    config.reset.expiry_secs = 60;
+    let later = chrono::Utc::now() + chrono::Duration::minutes(10);
+    let deleted = store.delete_expired_email_tokens(later, &config).await.unwrap();
+    assert_eq!(deleted, 1);
+    assert!(store.get_email_token("reset_password", "did:plc:e1").await.unwrap().is_none());
+    assert!(store.get_email_token("confirm_email", "did:plc:e1").await.unwrap().is_some());
+}
+
+// ── Search ──────────────────────────────────────────────────────────────
+
+fn search(query: &str) -> AccountSearchFilter {
+    AccountSearchFilter {
+        query: Some(query.to_string()),
+        status: None,
+        email_confirmed: None,
+    }
+}
+
+async fn search_dids(store: &impl AccountStore, query: &str) -> Vec<String> {
+    let accounts = store.search_accounts(&search(query), None, 100).await.unwrap();
+    accounts.into_iter().map(|a| a.did).collect()
+}
+
+pub async fn search_accounts_ranks_by_relevance_and_pages<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:a", "xbobx.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:b", "bob.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:c", "carol.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:d", "dave.test")).await.unwrap();
+    store.update_email("did:plc:c", "Bob@mail.test").await.unwrap();
+
+    let ranked = search_dids(store, "BOB").await;
+    assert_eq!(ranked, ["did:plc:b", "did:plc:a", "did:plc:c"]);
+
+    // Paging one at a time follows the same ranking.
+    let mut paged = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let page = store
+            .search_accounts(&search("bob"), cursor.as_deref(), 1)
+            .await
+            .unwrap();
+        let Some(last) = page.last() else { break };
+        cursor = Some(last.did.clone());
+        paged.push(last.did.clone());
+    }
+    assert_eq!(paged, ranked);
+
+    // Short queries, handle changes and deletions are all reflected.
+    assert_eq!(search_dids(store, "da").await, ["did:plc:d"]);
+    store.update_handle("did:plc:d", "bobby.test").await.unwrap();
+    assert!(search_dids(store, "bob").await.contains(&"did:plc:d".to_string()));
+    store.delete_account("did:plc:a").await.unwrap();
+    assert!(!search_dids(store, "bob").await.contains(&"did:plc:a".to_string()));
+}
+
+// ── Pagination ──────────────────────────────────────────────────────────
+
+pub async fn list_accounts_empty<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    let accounts = store.list_accounts(None, 10).await.unwrap();
+    assert!(accounts.is_empty());
+}
+
+pub async fn list_accounts_populated<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:la1", "a.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:la2", "b.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:la3", "c.test")).await.unwrap();
+
+    let accounts = store.list_accounts(None, 10).await.unwrap();
+    assert_eq!(accounts.len(), 3);
+}
+
+pub async fn list_accounts_cursor_and_limit<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:p1", "p1.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:p2", "p2.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:p3", "p3.test")).await.unwrap();
+
+    // Limit to 2
+    let page1 = store.list_accounts(None, 2).await.unwrap();
+    assert_eq!(page1.len(), 2);
+
+    // Use cursor from last DID
+    let cursor = &page1.last().unwrap().did;
+    let page2 = store.list_accounts(Some(cursor), 10).await.unwrap();
+    assert_eq!(page2.len(), 1);
+}
+
+pub async fn list_repos_skips_empty_roots_and_reports_status<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:r1", "r1.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:r2", "r2.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:r3", "r3.test")).await.unwrap();
+    store.update_repo_root("did:plc:r1", &[1, 2, 3], "rev1").await.unwrap();
+    store.update_repo_root("did:plc:r3", &[4, 5, 6], "rev3").await.unwrap();
+    store.set_takedown("did:plc:r3", Some("mod-1")).await.unwrap();
+
+    let repos = store.list_repos(None, 10).await.unwrap();
+    assert_eq!(repos.len(), 2);
+    assert_eq!(repos[0].did, "did:plc:r1");
+    assert_eq!(repos[0].cid, vec![1, 2, 3]);
+    assert_eq!(repos[0].rev, "rev1");
+    assert_eq!(repos[0].status, AccountStatus::Active);
+    assert_eq!(repos[1].did, "did:plc:r3");
+    assert_eq!(repos[1].status, AccountStatus::Takendown);
+}
+
+pub async fn scheduled_deletion_listed_once_due<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:d1", "d1.test")).await.unwrap();
+    store.create_account(&test_input("did:plc:d2", "d2.test")).await.unwrap();
+    let now = chrono::Utc::now();
+    store
+        .set_delete_after("did:plc:d1", Some(now - chrono::Duration::minutes(1)))
+        .await
+        .unwrap();
+    store
+        .set_delete_after("did:plc:d2", Some(now + chrono::Duration::hours(1)))
+        .await
+        .unwrap();
+
+    let due = store.list_accounts_due_for_deletion(now).await.unwrap();
+    assert_eq!(due, ["did:plc:d1"]);
+    let account = store.get_account_by_did("did:plc:d2").await.unwrap().unwrap();
+    assert!(account.delete_after.is_some());
+
+    store.set_delete_after("did:plc:d1", None).await.unwrap();
+    assert!(store.list_accounts_due_for_deletion(now).await.unwrap().is_empty());
+}
+
+pub async fn scheduled_deletion_claimed_once<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:c1", "c1.test")).await.unwrap();
+    let now = chrono::Utc::now();
+    let lease = now + chrono::Duration::minutes(10);
+    assert!(!store.claim_scheduled_deletion("did:plc:c1", now, lease).await.unwrap());
+
+    store
+        .set_delete_after("did:plc:c1", Some(now + chrono::Duration::hours(1)))
+        .await
+        .unwrap();
+    assert!(!store.claim_scheduled_deletion("did:plc:c1", now, lease).await.unwrap());
+
+    store
+        .set_delete_after("did:plc:c1", Some(now - chrono::Duration::minutes(1)))
+        .await
+        .unwrap();
+    assert!(store.claim_scheduled_deletion("did:plc:c1", now, lease).await.unwrap());
+    assert!(!store.claim_scheduled_deletion("did:plc:c1", now, lease).await.unwrap());
+
+    // A claim that was never followed by a purge comes due again once its
+    // lease runs out.
+    let account = store.get_account_by_did("did:plc:c1").await.unwrap().unwrap();
+    assert!(account.delete_after.is_some_and(|t| t > now));
+    assert_eq!(store.list_accounts_due_for_deletion(lease).await.unwrap(), ["did:plc:c1"]);
+    assert!(store.claim_scheduled_deletion("did:plc:c1", lease, lease).await.unwrap());
+}
+
+pub async fn blob_takedown_set_and_cleared<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    store.create_account(&test_input("did:plc:bt1", "bt1.test")).await.unwrap();
+
+    store.set_blob_takedown("did:plc:bt1", "bafyblob", Some("mod-1")).await.unwrap();
+    let takedown = store.get_blob_takedown("did:plc:bt1", "bafyblob").await.unwrap();
+    assert_eq!(takedown.as_deref(), Some("mod-1"));
+    assert!(store.get_blob_takedown("did:plc:bt1", "bafyother").await.unwrap().is_none());
+
+    store.set_blob_takedown("did:plc:bt1", "bafyblob", None).await.unwrap();
+    assert!(store.get_blob_takedown("did:plc:bt1", "bafyblob").await.unwrap().is_none());
+
+    // Takedowns go with the account.
+    store.set_blob_takedown("did:plc:bt1", "bafyblob", Some("mod-2")).await.unwrap();
+    store.delete_account("did:plc:bt1").await.unwrap();
+    assert!(store.get_blob_takedown("did:plc:bt1", "bafyblob").await.unwrap().is_none());
+}
+
+pub async fn plc_ops_are_listed_in_order_and_outlive_the_account<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    // The genesis operation is logged before the account is created.
+    let genesis = serde_json::json!({ "type": "plc_operation", "prev": null });
+    store.append_plc_op("did:plc:op1", "bafygenesis", &genesis).await.unwrap();
+    store.create_account(&test_input("did:plc:op1", "op1.test")).await.unwrap();
+    let update = serde_json::json!({ "type": "plc_operation", "prev": "bafygenesis" });
+    store.append_plc_op("did:plc:op1", "bafyupdate", &update).await.unwrap();
+    store.append_plc_op("did:plc:op2", "bafyother", &genesis).await.unwrap();
+
+    let ops = store.list_plc_ops("did:plc:op1").await.unwrap();
+    let cids: Vec<&str> = ops.iter().map(|op| op.cid.as_str()).collect();
+    assert_eq!(cids, ["bafygenesis", "bafyupdate"]);
+    assert_eq!(ops[0].operation, genesis);
+    assert_eq!(ops[1].operation, update);
+    assert!(ops.iter().all(|op| op.did == "did:plc:op1"));
+
+    store.delete_account("did:plc:op1").await.unwrap();
+    assert_eq!(store.list_plc_ops("did:plc:op1").await.unwrap().len(), 2);
+    assert!(store.list_plc_ops("did:plc:none").await.unwrap().is_empty());
+}
+
+pub async fn list_repos_keyset_pages_large_instance<A: AccountStore, R, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.account_store;
+    for i in 0..500 {
+        let did = format!("did:plc:bulk{i:04}");
+        store
+            .create_account(&test_input(&did, &format!("bulk{i}.test")))
+            .await
+            .unwrap();
+        // Every 50th account has no commits yet.
+        if i % 50 != 0 {
+            store.update_repo_root(&did, &[i as u8, 1], "rev").await.unwrap();
+        }
+    }
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    loop {
+        let page = store.list_repos(cursor.as_deref(), 100).await.unwrap();
+        pages += 1;
+        seen.extend(page.iter().map(|r| r.did.clone()));
+        if page.len() < 100 {
+            break;
+        }
+        cursor = page.last().map(|r| r.did.clone());
+    }
+
+    // One query per page: 490 repos in 100-row pages.
+    assert_eq!(pages, 5);
+    assert_eq!(seen.len(), 490);
+    let mut sorted = seen.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted, seen, "pages must be ordered by DID without overlap");
+}
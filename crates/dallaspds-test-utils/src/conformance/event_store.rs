@@ -0,0 +1,92 @@
+//! [`EventStore`] conformance tests.
+
+use dallaspds_core::EventStore;
+
+use super::ConformanceStores;
+
+pub async fn append_returns_seq<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let seq = store.append_event("commit", "did:plc:test", b"payload1").await.unwrap();
+    assert!(seq > 0, "first seq should be > 0");
+}
+
+pub async fn sequential_seq<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let seq1 = store.append_event("commit", "did:plc:test", b"p1").await.unwrap();
+    let seq2 = store.append_event("commit", "did:plc:test", b"p2").await.unwrap();
+    let seq3 = store.append_event("identity", "did:plc:test", b"p3").await.unwrap();
+    assert!(seq2 > seq1);
+    assert!(seq3 > seq2);
+}
+
+pub async fn get_events_after<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let seq1 = store.append_event("commit", "did:plc:a", b"p1").await.unwrap();
+    let _seq2 = store.append_event("commit", "did:plc:b", b"p2").await.unwrap();
+    let _seq3 = store.append_event("identity", "did:plc:c", b"p3").await.unwrap();
+
+    let events = store.get_events_after(seq1, 100).await.unwrap();
+    assert_eq!(events.len(), 2, "should get 2 events after seq1");
+    assert_eq!(events[0].did, "did:plc:b");
+    assert_eq!(events[1].did, "did:plc:c");
+}
+
+pub async fn get_events_limit<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    for i in 0..5 {
+        store.append_event("commit", &format!("did:plc:{i}"), b"p").await.unwrap();
+    }
+
+    let events = store.get_events_after(0, 2).await.unwrap();
+    assert_eq!(events.len(), 2);
+}
+
+pub async fn get_events_empty<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let events = store.get_events_after(0, 100).await.unwrap();
+    assert!(events.is_empty());
+}
+
+pub async fn max_seq_initial<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let max = store.get_max_seq().await.unwrap();
+    assert_eq!(max, 0);
+}
+
+pub async fn max_seq_after_inserts<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let seq1 = store.append_event("commit", "did:plc:a", b"p1").await.unwrap();
+    let seq2 = store.append_event("commit", "did:plc:b", b"p2").await.unwrap();
+    let max = store.get_max_seq().await.unwrap();
+    assert_eq!(max, seq2);
+    assert!(max > seq1);
+}
+
+pub async fn prune_events_before_keeps_later_events<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let mut seqs = Vec::new();
+    for i in 0..5 {
+        seqs.push(store.append_event("commit", &format!("did:plc:{i}"), b"p").await.unwrap());
+    }
+
+    let pruned = store.prune_events_before(seqs[3]).await.unwrap();
+    assert_eq!(pruned, 3);
+    let events = store.get_events_after(0, 100).await.unwrap();
+    let remaining: Vec<i64> = events.iter().map(|e| e.seq).collect();
+    assert_eq!(remaining, &seqs[3..]);
+    // Pruning never rewinds the sequence.
+    assert_eq!(store.get_max_seq().await.unwrap(), seqs[4]);
+}
+
+pub async fn first_seq_since<A, R, E: EventStore>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.event_store;
+    let hour_ago = chrono::Utc::now() - chrono::Duration::hours(1);
+    assert_eq!(store.get_first_seq_since(hour_ago).await.unwrap(), None);
+
+    let seq1 = store.append_event("commit", "did:plc:a", b"p1").await.unwrap();
+    store.append_event("commit", "did:plc:b", b"p2").await.unwrap();
+    assert_eq!(store.get_first_seq_since(hour_ago).await.unwrap(), Some(seq1));
+
+    let in_an_hour = chrono::Utc::now() + chrono::Duration::hours(1);
+    assert_eq!(store.get_first_seq_since(in_an_hour).await.unwrap(), None);
+}
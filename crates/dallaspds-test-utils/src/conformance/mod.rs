@@ -0,0 +1,125 @@
+//! Behaviour every [`AccountStore`], [`RepoStore`] and [`EventStore`]
+//! implementation must share.
+//!
+//! Each storage crate runs the whole suite against its own stores with
+//! [`store_conformance_tests!`](crate::store_conformance_tests), giving every
+//! test a fresh set from a setup function it provides:
+//!
+//! ```ignore
+//! async fn setup() -> ConformanceStores<MemAccountStore, MemRepoStore, MemEventStore> { ... }
+//!
+//! dallaspds_test_utils::store_conformance_tests!(setup);
+//! ```
+//!
+//! Attributes given before the setup function, such as `#[ignore]` for
+//! stores that need an external database, are applied to every test.
+//!
+//! [`AccountStore`]: dallaspds_core::AccountStore
+//! [`RepoStore`]: dallaspds_core::RepoStore
+//! [`EventStore`]: dallaspds_core::EventStore
+
+pub mod account_store;
+pub mod event_store;
+pub mod repo_store;
+
+/// Fresh stores for one conformance test. The repo store must move the
+/// account store's repo roots, as stores sharing a database do.
+pub struct ConformanceStores<A, R, E> {
+    pub account_store: A,
+    pub repo_store: R,
+    pub event_store: E,
+    /// Whatever keeps the backing database alive for the test, e.g. its
+    /// temporary directory.
+    pub guard: Box<dyn std::any::Any + Send>,
+}
+
+/// Generate a `#[tokio::test]` for every conformance test, each calling the
+/// given setup function for its stores.
+#[macro_export]
+macro_rules! store_conformance_tests {
+    ($(#[$attr:meta])* $setup:ident) => {
+        $crate::__store_conformance_tests! {
+            [$(#[$attr])*] $setup;
+            account_store:
+            create_and_get_by_did,
+            get_by_handle,
+            get_by_email,
+            get_nonexistent_returns_none,
+            update_handle,
+            update_password,
+            deactivate_and_activate,
+            delete_account_cascades,
+            repo_root_initially_empty,
+            repo_root_update_and_get,
+            repo_root_overwrite,
+            refresh_token_crud,
+            refresh_token_delete_all_for_did,
+            refresh_token_next_id_set_once,
+            refresh_token_delete_chain,
+            refresh_token_delete_expired,
+            refresh_token_get_nonexistent,
+            oauth_request_code_is_approved_once_and_consumed_once,
+            oauth_request_delete_expired,
+            email_tokens_past_their_lifetime_are_swept,
+            search_accounts_ranks_by_relevance_and_pages,
+            list_accounts_empty,
+            list_accounts_populated,
+            list_accounts_cursor_and_limit,
+            list_repos_skips_empty_roots_and_reports_status,
+            scheduled_deletion_listed_once_due,
+            scheduled_deletion_claimed_once,
+            blob_takedown_set_and_cleared,
+            plc_ops_are_listed_in_order_and_outlive_the_account,
+            list_repos_keyset_pages_large_instance,
+            ;
+            repo_store:
+            put_and_get_block,
+            get_nonexistent,
+            has_block,
+            put_idempotent,
+            get_all_blocks,
+            storage_usage,
+            scoped_to_did,
+            delete_blocks_for_did,
+            blob_refs_keep_first_rev,
+            commit_blocks_moves_the_account_stores_root,
+            ;
+            event_store:
+            append_returns_seq,
+            sequential_seq,
+            get_events_after,
+            get_events_limit,
+            get_events_empty,
+            max_seq_initial,
+            max_seq_after_inserts,
+            prune_events_before_keeps_later_events,
+            first_seq_since,
+            ;
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __store_conformance_tests {
+    ($attrs:tt $setup:ident; $($suite:ident: $($name:ident,)*;)*) => {
+        $(
+            mod $suite {
+                $( $crate::__store_conformance_test!($attrs $setup $suite $name); )*
+            }
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __store_conformance_test {
+    ([$(#[$attr:meta])*] $setup:ident $suite:ident $name:ident) => {
+        #[tokio::test]
+        $(#[$attr])*
+        async fn $name() {
+            let stores = super::$setup().await;
+            $crate::conformance::$suite::$name(&stores).await;
+        }
+    };
+}
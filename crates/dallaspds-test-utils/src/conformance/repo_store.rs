@@ -0,0 +1,139 @@
+//! [`RepoStore`] conformance tests.
+
+use dallaspds_core::{AccountStore, CreateAccountInput, PdsError, RepoStore};
+
+use super::ConformanceStores;
+
+pub async fn put_and_get_block<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    let cid = vec![0x01, 0x71, 0x12, 0x20, 0xAA];
+    let block = b"block data here".to_vec();
+
+    store.put_block("did:plc:test", &cid, &block).await.unwrap();
+    let result = store.get_block("did:plc:test", &cid).await.unwrap();
+    assert_eq!(result, Some(block));
+}
+
+pub async fn get_nonexistent<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    let result = store.get_block("did:plc:test", &[0xFF]).await.unwrap();
+    assert!(result.is_none());
+}
+
+pub async fn has_block<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    let cid = vec![1, 2, 3];
+    assert!(!store.has_block("did:plc:test", &cid).await.unwrap());
+
+    store.put_block("did:plc:test", &cid, b"data").await.unwrap();
+    assert!(store.has_block("did:plc:test", &cid).await.unwrap());
+}
+
+pub async fn put_idempotent<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    let cid = vec![1, 2, 3];
+    store.put_block("did:plc:test", &cid, b"data").await.unwrap();
+    // A duplicate put is ignored, not an error
+    store.put_block("did:plc:test", &cid, b"data").await.unwrap();
+    let result = store.get_block("did:plc:test", &cid).await.unwrap();
+    assert_eq!(result, Some(b"data".to_vec()));
+}
+
+pub async fn get_all_blocks<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    store.put_block("did:plc:test", &[1], b"block1").await.unwrap();
+    store.put_block("did:plc:test", &[2], b"block2").await.unwrap();
+    store.put_block("did:plc:test", &[3], b"block3").await.unwrap();
+
+    let blocks = store.get_all_blocks("did:plc:test").await.unwrap();
+    assert_eq!(blocks.len(), 3);
+}
+
+pub async fn storage_usage<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    assert_eq!(store.get_storage_usage("did:plc:test").await.unwrap(), (0, 0));
+
+    store.put_block("did:plc:test", &[1], b"block1").await.unwrap();
+    store.put_block("did:plc:test", &[2], b"longer block").await.unwrap();
+    store.put_block("did:plc:other", &[1], b"not counted").await.unwrap();
+    assert_eq!(store.get_storage_usage("did:plc:test").await.unwrap(), (2, 18));
+}
+
+pub async fn scoped_to_did<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    let cid = vec![1, 2, 3];
+    store.put_block("did:plc:a", &cid, b"block-a").await.unwrap();
+    store.put_block("did:plc:b", &cid, b"block-b").await.unwrap();
+
+    let result_a = store.get_block("did:plc:a", &cid).await.unwrap();
+    assert_eq!(result_a, Some(b"block-a".to_vec()));
+
+    let result_b = store.get_block("did:plc:b", &cid).await.unwrap();
+    assert_eq!(result_b, Some(b"block-b".to_vec()));
+}
+
+pub async fn delete_blocks_for_did<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    store.put_block("did:plc:del", &[1], b"a").await.unwrap();
+    store.put_block("did:plc:del", &[2], b"b").await.unwrap();
+    store.put_block("did:plc:keep", &[1], b"c").await.unwrap();
+
+    let deleted = store.delete_blocks_for_did("did:plc:del").await.unwrap();
+    assert_eq!(deleted, 2);
+
+    assert!(store.get_block("did:plc:del", &[1]).await.unwrap().is_none());
+    assert!(store.get_block("did:plc:keep", &[1]).await.unwrap().is_some());
+}
+
+pub async fn blob_refs_keep_first_rev<A, R: RepoStore, E>(stores: &ConformanceStores<A, R, E>) {
+    let store = &stores.repo_store;
+    let did = "did:plc:test";
+
+    store.add_blob_refs(did, &["bafyblobb".to_string()], "rev1").await.unwrap();
+    store
+        .add_blob_refs(did, &["bafyblobb".to_string(), "bafybloba".to_string()], "rev2")
+        .await
+        .unwrap();
+    store.add_blob_refs("did:plc:other", &["bafyblobc".to_string()], "rev3").await.unwrap();
+
+    let since = |rev: &'static str| store.list_blobs_since(did, rev, None, 10);
+    assert_eq!(since("rev0").await.unwrap(), ["bafybloba", "bafyblobb"]);
+    assert_eq!(since("rev1").await.unwrap(), ["bafybloba"]);
+    assert!(since("rev2").await.unwrap().is_empty());
+
+    let page = store.list_blobs_since(did, "rev0", Some("bafybloba"), 10).await.unwrap();
+    assert_eq!(page, ["bafyblobb"]);
+
+    store.delete_blocks_for_did(did).await.unwrap();
+    assert!(since("rev0").await.unwrap().is_empty());
+}
+
+pub async fn commit_blocks_moves_the_account_stores_root<A: AccountStore, R: RepoStore, E>(
+    stores: &ConformanceStores<A, R, E>,
+) {
+    let (account_store, repo_store) = (&stores.account_store, &stores.repo_store);
+    let input = CreateAccountInput {
+        did: "did:plc:ok".to_string(),
+        handle: "ok.test".to_string(),
+        email: None,
+        password_hash: "hash".to_string(),
+        signing_key: vec![1, 2, 3, 4],
+        key_type: "p256".to_string(),
+    };
+    account_store.create_account(&input).await.unwrap();
+
+    let blocks = vec![(vec![1], b"commit".to_vec()), (vec![2], b"node".to_vec())];
+    repo_store.commit_blocks("did:plc:ok", &blocks, &[], &[1], "rev1").await.unwrap();
+    let root = account_store.get_repo_root("did:plc:ok").await.unwrap().unwrap();
+    assert_eq!((root.cid, root.rev.as_str()), (vec![1], "rev1"));
+    assert_eq!(repo_store.get_all_blocks("did:plc:ok").await.unwrap().len(), 2);
+
+    // A commit built on a root that has since moved is refused.
+    let next = vec![(vec![3], b"commit2".to_vec())];
+    let stale = repo_store.commit_blocks("did:plc:ok", &next, &[], &[3], "rev2").await;
+    assert!(matches!(stale, Err(PdsError::RepoRootChanged)));
+    assert!(!repo_store.has_block("did:plc:ok", &[3]).await.unwrap());
+    repo_store.commit_blocks("did:plc:ok", &next, &[1], &[3], "rev2").await.unwrap();
+    let root = account_store.get_repo_root("did:plc:ok").await.unwrap().unwrap();
+    assert_eq!((root.cid, root.rev.as_str()), (vec![3], "rev2"));
+}
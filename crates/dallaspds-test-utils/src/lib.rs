@@ -1,15 +1,23 @@
 pub mod assertions;
+pub mod conformance;
+pub mod mock_server;
+pub mod plc;
 pub mod server;
 pub mod stores;
 
 pub use assertions::{assert_xrpc_error, assert_xrpc_ok};
+pub use conformance::ConformanceStores;
+pub use mock_server::{RecordedCalls, RecordedRequest, spawn_recording_server, wait_for_calls};
+pub use plc::{FakePlc, spawn_fake_plc};
 pub use server::{
     TEST_ACCESS_SECRET, TEST_PASSWORD, TEST_REFRESH_SECRET,
     create_account_via_api, create_test_app_state, create_test_router,
     create_test_router_and_stores, create_test_config, create_test_app_state_with_config,
     create_test_router_with_config, send_request,
 };
-pub use stores::{TestStores, create_test_stores};
+pub use stores::{
+    TestAccountStore, TestEventStore, TestRepoStore, TestStores, create_test_stores,
+};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,71 @@
+//! Stand-in upstream services (relays, webhook endpoints, ...) that record
+//! what the server sends them.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{HeaderMap, Method, Uri};
+use axum::middleware::Next;
+use serde_json::Value;
+
+/// A request received by a [`spawn_recording_server`].
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// The body parsed as JSON.
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).expect("recorded body is not JSON")
+    }
+}
+
+/// Requests received by a [`spawn_recording_server`], oldest first.
+pub type RecordedCalls = Arc<Mutex<Vec<RecordedRequest>>>;
+
+/// Serve `router` on a local port, recording every request before it is
+/// handled. Returns the server's base URL.
+pub async fn spawn_recording_server(router: axum::Router) -> (String, RecordedCalls) {
+    let calls = RecordedCalls::default();
+    let recorded = calls.clone();
+    let app = router.layer(axum::middleware::from_fn(
+        move |request: Request, next: Next| {
+            let recorded = recorded.clone();
+            async move {
+                let (parts, body) = request.into_parts();
+                let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+                recorded.lock().unwrap().push(RecordedRequest {
+                    method: parts.method.clone(),
+                    uri: parts.uri.clone(),
+                    headers: parts.headers.clone(),
+                    body: body.to_vec(),
+                });
+                next.run(Request::from_parts(parts, Body::from(body))).await
+            }
+        },
+    ));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{addr}"), calls)
+}
+
+/// Wait up to five seconds for `calls` to hold at least `count` requests.
+pub async fn wait_for_calls(calls: &Mutex<Vec<RecordedRequest>>, count: usize) {
+    for _ in 0..250 {
+        if calls.lock().unwrap().len() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!(
+        "server saw {} calls, expected {count}",
+        calls.lock().unwrap().len()
+    );
+}
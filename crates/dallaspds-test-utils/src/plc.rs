@@ -0,0 +1,96 @@
+//! A stand-in PLC directory for tests that need DIDs to resolve.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::{Value, json};
+
+/// Operations submitted to a [`spawn_fake_plc`] directory, by DID. Nothing
+/// is verified: the latest operation of each DID is its document.
+#[derive(Clone, Default)]
+pub struct FakePlc {
+    pub url: String,
+    ops: Arc<Mutex<HashMap<String, Vec<Value>>>>,
+    rejecting: Arc<AtomicBool>,
+}
+
+impl FakePlc {
+    /// Record `op` as `did`'s latest operation, as if submitted from elsewhere.
+    pub fn push(&self, did: &str, op: Value) {
+        self.ops.lock().unwrap().entry(did.to_string()).or_default().push(op);
+    }
+
+    /// Every operation submitted for `did`, oldest first.
+    pub fn ops(&self, did: &str) -> Vec<Value> {
+        self.ops.lock().unwrap().get(did).cloned().unwrap_or_default()
+    }
+
+    /// While set, submitted operations are refused with a 400 and not recorded.
+    pub fn reject(&self, rejecting: bool) {
+        self.rejecting.store(rejecting, Ordering::SeqCst);
+    }
+
+    fn last(&self, did: &str) -> Option<Value> {
+        self.ops(did).pop()
+    }
+}
+
+/// Serve a PLC directory on a local port: `POST /{did}` records an operation,
+/// `GET /{did}` returns the document of the latest one and
+/// `GET /{did}/log/last` the operation itself.
+pub async fn spawn_fake_plc() -> FakePlc {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let plc = FakePlc {
+        url: format!("http://{}", listener.local_addr().unwrap()),
+        ..FakePlc::default()
+    };
+    let app = axum::Router::new()
+        .route("/{did}", axum::routing::post(submit).get(document))
+        .route("/{did}/log/last", axum::routing::get(last_op))
+        .with_state(plc.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    plc
+}
+
+async fn submit(State(plc): State<FakePlc>, Path(did): Path<String>, Json(op): Json<Value>) -> StatusCode {
+    if plc.rejecting.load(Ordering::SeqCst) {
+        return StatusCode::BAD_REQUEST;
+    }
+    plc.push(&did, op);
+    StatusCode::OK
+}
+
+async fn last_op(State(plc): State<FakePlc>, Path(did): Path<String>) -> Response {
+    match plc.last(&did) {
+        Some(op) => Json(op).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn document(State(plc): State<FakePlc>, Path(did): Path<String>) -> Response {
+    let Some(op) = plc.last(&did) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let key = op["verificationMethods"]["atproto"].as_str().unwrap_or_default();
+    Json(json!({
+        "id": did,
+        "alsoKnownAs": op["alsoKnownAs"],
+        "verificationMethod": [{
+            "id": format!("{did}#atproto"),
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": key.strip_prefix("did:key:").unwrap_or(key),
+        }],
+        "service": [{
+            "id": "#atproto_pds",
+            "type": "AtprotoPersonalDataServer",
+            "serviceEndpoint": op["services"]["atproto_pds"]["endpoint"],
+        }],
+    }))
+    .into_response()
+}
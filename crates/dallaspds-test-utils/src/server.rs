@@ -7,11 +7,25 @@ use serde_json::Value;
 use tower::ServiceExt;
 
 use dallaspds_blob_fs::FsBlobStore;
-use dallaspds_core::config::{BlobsConfig, DatabaseConfig, JwtConfig, PdsConfig, PdsMode};
-use dallaspds_server::{AppState, Sequencer, build_router};
-use dallaspds_storage_sqlite::{SqliteAccountStore, SqliteRepoStore};
-
-use crate::stores::{TestStores, create_test_stores};
+use dallaspds_core::config::{
+    AppViewCacheConfig, BlobsConfig, CorsConfig, DatabaseConfig, EmailConfig, FirehoseConfig,
+    HttpRetryConfig, JwtAlgorithm, JwtConfig, LimitsConfig, PasswordConfig, PdsConfig, PdsMode,
+    PlcRegistration, RateLimitConfig, SecretsConfig, ServerConfig,
+};
+use dallaspds_crypto::{AccessTokenKeys, TidGenerator};
+use dallaspds_server::email::EmailSender;
+use dallaspds_server::firehose::retention::SubscriberCursors;
+use dallaspds_server::lexicon::LexiconSet;
+use dallaspds_server::limits::{RepoQuota, WriteLimiter};
+use dallaspds_server::proxy::remote_blob::RemoteBlobCache;
+use dallaspds_server::proxy::remote_record::PdsEndpointCache;
+use dallaspds_server::proxy::response_cache::AppViewCache;
+use dallaspds_server::rate_limit::RateLimiter;
+use dallaspds_server::oauth_client::ClientMetadataCache;
+use dallaspds_server::read_only::ReadOnly;
+use dallaspds_server::shutdown::Shutdown;
+use dallaspds_server::{AppState, MemorySequencer, Sequencer, WebhookNotifier, build_router};
+use crate::stores::{TestAccountStore, TestRepoStore, TestStores, create_test_stores};
 
 pub const TEST_ACCESS_SECRET: &str = "test-access-secret-at-least-32-chars-long";
 pub const TEST_REFRESH_SECRET: &str = "test-refresh-secret-at-least-32-chars-long";
@@ -24,44 +38,92 @@ pub fn create_test_config() -> PdsConfig {
         public_url: "https://test.pds.local".to_string(),
         plc_url: "https://plc.directory".to_string(),
         available_user_domains: vec![".test.pds.local".to_string()],
+        reserved_handles: Vec::new(),
+        reserved_handle_prefixes: Vec::new(),
         invite_required: false,
+        require_email_confirmed_for_writes: false,
+        account_deletion_grace_secs: 0,
+        plc_registration: PlcRegistration::BestEffort,
+        read_only: false,
         jwt: JwtConfig {
             access_secret: TEST_ACCESS_SECRET.to_string(),
             refresh_secret: TEST_REFRESH_SECRET.to_string(),
+            session_max_age_days: None,
+            algorithm: JwtAlgorithm::Hs256,
+            es256_private_key_path: None,
+            es256_public_key_path: None,
         },
-        database: DatabaseConfig {
-            url: String::new(), // not used; stores are pre-connected
-        },
+        database: DatabaseConfig::new(""), // not used; stores are pre-connected
         blobs: BlobsConfig {
             path: None,
             bucket: None,
             region: None,
             endpoint: None,
+            x_accel_redirect_prefix: None,
+            allow_blob_proxy: false,
+            proxy_cache_bytes: 0,
         },
         mode: PdsMode::Single,
+        did_web: false,
         appview_url: None,
         appview_did: None,
-        relay_url: None,
+        handle_resolver_url: None,
+        // Mock PDSes listen on 127.0.0.1.
+        allow_private_endpoints: true,
+        relay_url: Vec::new(),
+        relay_debounce_secs: 60,
         admin_dids: vec![],
         tls: None,
         smtp: None,
+        webhooks: None,
+        email: EmailConfig::default(),
+        password: PasswordConfig::default(),
+        validate_records: false,
+        lexicon_dir: None,
+        normalize_on_read: false,
+        firehose: FirehoseConfig::default(),
+        limits: LimitsConfig::default(),
+        server: ServerConfig::default(),
+        rate_limit: RateLimitConfig::default(),
+        appview_cache: AppViewCacheConfig::default(),
+        // Tests never reach a real upstream, so fail on the first attempt.
+        http_retry: HttpRetryConfig {
+            max_attempts: 1,
+            ..HttpRetryConfig::default()
+        },
+        cors: CorsConfig::default(),
+        secrets: SecretsConfig::default(),
     }
 }
 
 pub fn create_test_app_state(
     stores: &TestStores,
-) -> AppState<SqliteAccountStore, SqliteRepoStore, FsBlobStore> {
-    let sequencer = Sequencer::new(1, 256);
+) -> AppState<TestAccountStore, TestRepoStore, FsBlobStore> {
+    let sequencer: Arc<dyn Sequencer> = Arc::new(MemorySequencer::new(1, 256));
 
     AppState {
         account_store: Arc::new(stores.account_store.clone()),
         repo_store: Arc::new(stores.repo_store.clone()),
         blob_store: Arc::new(stores.blob_store.clone()),
         config: Arc::new(create_test_config()),
+        access_token_keys: Arc::new(AccessTokenKeys::hs256(TEST_ACCESS_SECRET)),
+        tid_gen: Arc::new(TidGenerator::new()),
         sequencer: Some(sequencer),
         relay_notifier: None,
+        webhooks: None,
         event_store: Some(stores.event_store_arc()),
+        subscriber_cursors: Arc::new(SubscriberCursors::default()),
         email_sender: None,
+        pds_endpoint_cache: Arc::new(PdsEndpointCache::default()),
+        remote_blob_cache: Arc::new(RemoteBlobCache::default()),
+        lexicons: None,
+        write_limiter: Arc::new(WriteLimiter::default()),
+        repo_quota: Arc::new(RepoQuota::default()),
+        rate_limiter: Arc::new(RateLimiter::default()),
+        appview_cache: Arc::new(AppViewCache::default()),
+        oauth_clients: Arc::new(ClientMetadataCache::default()),
+        shutdown: Shutdown::default(),
+        read_only: ReadOnly::default(),
     }
 }
 
@@ -156,18 +218,54 @@ pub async fn send_request(
 pub fn create_test_app_state_with_config(
     stores: &TestStores,
     config: PdsConfig,
-) -> AppState<SqliteAccountStore, SqliteRepoStore, FsBlobStore> {
-    let sequencer = Sequencer::new(1, 256);
+) -> AppState<TestAccountStore, TestRepoStore, FsBlobStore> {
+    let firehose_enabled = config.firehose.enabled;
+    let buffer_size = config.firehose.buffer_size;
+    let lexicons = LexiconSet::from_config(&config)
+        .expect("failed to load lexicons")
+        .map(Arc::new);
+    let write_limiter = Arc::new(WriteLimiter::new(&config.limits));
+    let repo_quota = Arc::new(RepoQuota::new(&config.limits));
+    let rate_limiter = Arc::new(RateLimiter::new(&config.rate_limit));
+    let appview_cache = Arc::new(AppViewCache::new(&config.appview_cache));
+    let remote_blob_cache = Arc::new(RemoteBlobCache::new(config.blobs.proxy_cache_bytes));
+    let read_only = ReadOnly::new(config.read_only);
+    let access_token_keys = Arc::new(
+        AccessTokenKeys::from_config(&config.jwt).expect("failed to load access token keys"),
+    );
+    let webhooks = config.webhooks.as_ref().map(|webhooks| {
+        let (notifier, worker) = WebhookNotifier::new(webhooks);
+        tokio::spawn(worker.run());
+        notifier
+    });
+    let email_sender = EmailSender::from_config(&config)
+        .expect("failed to set up email")
+        .map(Arc::new);
 
     AppState {
         account_store: Arc::new(stores.account_store.clone()),
         repo_store: Arc::new(stores.repo_store.clone()),
         blob_store: Arc::new(stores.blob_store.clone()),
         config: Arc::new(config),
-        sequencer: Some(sequencer),
+        access_token_keys,
+        tid_gen: Arc::new(TidGenerator::new()),
+        sequencer: firehose_enabled
+            .then(|| Arc::new(MemorySequencer::new(1, buffer_size)) as Arc<dyn Sequencer>),
         relay_notifier: None,
-        event_store: Some(stores.event_store_arc()),
-        email_sender: None,
+        webhooks,
+        event_store: firehose_enabled.then(|| stores.event_store_arc()),
+        subscriber_cursors: Arc::new(SubscriberCursors::default()),
+        email_sender,
+        pds_endpoint_cache: Arc::new(PdsEndpointCache::default()),
+        remote_blob_cache,
+        lexicons,
+        write_limiter,
+        repo_quota,
+        rate_limiter,
+        appview_cache,
+        oauth_clients: Arc::new(ClientMetadataCache::default()),
+        shutdown: Shutdown::default(),
+        read_only,
     }
 }
 
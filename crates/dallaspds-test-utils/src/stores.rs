@@ -3,12 +3,25 @@ use tempfile::TempDir;
 
 use dallaspds_blob_fs::FsBlobStore;
 use dallaspds_core::EventStore;
-use dallaspds_storage_sqlite::{SqliteAccountStore, SqliteEventStore, SqliteRepoStore};
+
+/// Store types behind [`TestStores`]: SQLite by default, or the in-memory
+/// stores with the `mem-stores` feature, e.g.
+/// `cargo test --workspace --features dallaspds-test-utils/mem-stores`.
+#[cfg(not(feature = "mem-stores"))]
+pub use dallaspds_storage_sqlite::{
+    SqliteAccountStore as TestAccountStore, SqliteEventStore as TestEventStore,
+    SqliteRepoStore as TestRepoStore,
+};
+#[cfg(feature = "mem-stores")]
+pub use dallaspds_storage_mem::{
+    MemAccountStore as TestAccountStore, MemEventStore as TestEventStore,
+    MemRepoStore as TestRepoStore,
+};
 
 pub struct TestStores {
-    pub account_store: SqliteAccountStore,
-    pub repo_store: SqliteRepoStore,
-    pub event_store: SqliteEventStore,
+    pub account_store: TestAccountStore,
+    pub repo_store: TestRepoStore,
+    pub event_store: TestEventStore,
     pub blob_store: FsBlobStore,
     /// Hold the TempDir to keep it alive for the test's duration.
     pub _tempdir: TempDir,
@@ -20,16 +33,42 @@ pub struct TestStores {
 /// writes to a `blobs/` subdirectory inside the same tempdir.
 pub async fn create_test_stores() -> TestStores {
     let tempdir = TempDir::new().expect("failed to create tempdir");
+    let (account_store, repo_store, event_store) = connect_stores(&tempdir).await;
+
+    let blobs_path = tempdir.path().join("blobs");
+    let blob_store =
+        FsBlobStore::new(blobs_path.to_str().unwrap()).expect("failed to create blob store");
+
+    TestStores {
+        account_store,
+        repo_store,
+        event_store,
+        blob_store,
+        _tempdir: tempdir,
+    }
+}
+
+#[cfg(feature = "mem-stores")]
+async fn connect_stores(_tempdir: &TempDir) -> (TestAccountStore, TestRepoStore, TestEventStore) {
+    // The repo store shares the account store's repo roots, as the SQLite
+    // stores share a database.
+    let account_store = TestAccountStore::new();
+    let repo_store = account_store.repo_store();
+    (account_store, repo_store, TestEventStore::new())
+}
+
+#[cfg(not(feature = "mem-stores"))]
+async fn connect_stores(tempdir: &TempDir) -> (TestAccountStore, TestRepoStore, TestEventStore) {
     let db_path = tempdir.path().join("test.db");
     let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
 
-    let account_store = SqliteAccountStore::connect(&db_url)
+    let account_store = TestAccountStore::connect(&db_url)
         .await
         .expect("failed to connect account store");
-    let repo_store = SqliteRepoStore::connect(&db_url)
+    let repo_store = TestRepoStore::connect(&db_url)
         .await
         .expect("failed to connect repo store");
-    let event_store = SqliteEventStore::connect(&db_url)
+    let event_store = TestEventStore::connect(&db_url)
         .await
         .expect("failed to connect event store");
 
@@ -50,17 +89,7 @@ pub async fn create_test_stores() -> TestStores {
     .await
     .expect("create firehose_event table");
 
-    let blobs_path = tempdir.path().join("blobs");
-    let blob_store =
-        FsBlobStore::new(blobs_path.to_str().unwrap()).expect("failed to create blob store");
-
-    TestStores {
-        account_store,
-        repo_store,
-        event_store,
-        blob_store,
-        _tempdir: tempdir,
-    }
+    (account_store, repo_store, event_store)
 }
 
 impl TestStores {